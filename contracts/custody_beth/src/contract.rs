@@ -1,3 +1,4 @@
+use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{
     from_binary, log, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
     InitResponse, InitResult, MigrateResponse, MigrateResult, Querier, StdError, StdResult,
@@ -5,14 +6,21 @@ use cosmwasm_std::{
 };
 
 use crate::collateral::{
-    deposit_collateral, liquidate_collateral, lock_collateral, query_borrower, query_borrowers,
-    unlock_collateral, withdraw_collateral,
+    compound_collateral, deposit_collateral, liquidate_collateral, lock_collateral, query_borrower,
+    query_borrowers, query_compounded_collateral, unlock_collateral, withdraw_collateral,
+};
+use crate::distribution::{
+    distribute_hook, distribute_rewards, query_swap_route, swap_to_stable_denom, update_swap_route,
+};
+use crate::state::{
+    read_borrowers, read_config, read_pause_info, store_config, store_pause_info, Config,
 };
-use crate::distribution::{distribute_hook, distribute_rewards, swap_to_stable_denom};
-use crate::state::{read_config, store_config, Config};
 
 use cw20::Cw20ReceiveMsg;
 use moneymarket::custody::{ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, MigrateMsg, QueryMsg};
+use moneymarket::pause::PauseInfo;
+use moneymarket::sweep::{sweep_msg, SweepAsset};
+use moneymarket::tax::{read_tax_policy, store_tax_policy, TaxPolicy};
 use terra_cosmwasm::TerraMsgWrapper;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -29,6 +37,11 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
         stable_denom: msg.stable_denom,
         basset_info: msg.basset_info,
+        guardian: None,
+        exchange_rate_contract: None,
+        compound_contract: None,
+        compound_ratio: Decimal256::zero(),
+        swap_router: None,
     };
 
     store_config(&mut deps.storage, &config)?;
@@ -46,7 +59,33 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
         HandleMsg::UpdateConfig {
             owner,
             liquidation_contract,
-        } => update_config(deps, env, owner, liquidation_contract),
+            guardian,
+            exchange_rate_contract,
+            compound_contract,
+            compound_ratio,
+            swap_router,
+        } => update_config(
+            deps,
+            env,
+            owner,
+            liquidation_contract,
+            guardian,
+            exchange_rate_contract,
+            compound_contract,
+            compound_ratio,
+            swap_router,
+        ),
+        HandleMsg::UpdateSwapRoute { denom, route } => update_swap_route(deps, env, denom, route),
+        HandleMsg::SetPause {
+            deposits,
+            borrows,
+            liquidations,
+            withdrawals,
+        } => set_pause(deps, env, deposits, borrows, liquidations, withdrawals),
+        HandleMsg::UpdateTaxPolicy { tax_policy } => update_tax_policy(deps, env, tax_policy),
+        HandleMsg::SweepUnrelatedFunds { asset, recipient } => {
+            sweep_unrelated_funds(deps, env, asset, recipient)
+        }
         HandleMsg::LockCollateral { borrower, amount } => {
             lock_collateral(deps, env, borrower, amount)
         }
@@ -70,7 +109,7 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
     env: Env,
     cw20_msg: Cw20ReceiveMsg,
 ) -> HandleResult<TerraMsgWrapper> {
-    let contract_addr = env.message.sender;
+    let contract_addr = env.message.sender.clone();
     if let Some(msg) = cw20_msg.msg {
         match from_binary(&msg)? {
             Cw20HookMsg::DepositCollateral {} => {
@@ -82,6 +121,17 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
 
                 deposit_collateral(deps, cw20_msg.sender, cw20_msg.amount.into())
             }
+            Cw20HookMsg::CompoundCollateral {} => {
+                let config: Config = read_config(&deps.storage)?;
+                if deps.api.canonical_address(&contract_addr)? != config.collateral_token
+                    || Some(deps.api.canonical_address(&cw20_msg.sender)?)
+                        != config.compound_contract
+                {
+                    return Err(StdError::unauthorized());
+                }
+
+                compound_collateral(deps, env, cw20_msg.amount.into())
+            }
         }
     } else {
         Err(StdError::generic_err(
@@ -90,11 +140,17 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     owner: Option<HumanAddr>,
     liquidation_contract: Option<HumanAddr>,
+    guardian: Option<HumanAddr>,
+    exchange_rate_contract: Option<HumanAddr>,
+    compound_contract: Option<HumanAddr>,
+    compound_ratio: Option<Decimal256>,
+    swap_router: Option<HumanAddr>,
 ) -> HandleResult<TerraMsgWrapper> {
     let mut config: Config = read_config(&deps.storage)?;
 
@@ -110,6 +166,46 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.liquidation_contract = deps.api.canonical_address(&liquidation_contract)?;
     }
 
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
+    // the stored balances are only backed by shares
+    // when they were deposited with the exchange rate set
+    if let Some(exchange_rate_contract) = exchange_rate_contract {
+        if !read_borrowers(deps, None, Some(1), None)?.is_empty() {
+            return Err(StdError::generic_err(
+                "Exchange rate contract cannot be set while the custody has borrowers",
+            ));
+        }
+
+        config.exchange_rate_contract = Some(deps.api.canonical_address(&exchange_rate_contract)?);
+    }
+
+    if let Some(compound_contract) = compound_contract {
+        config.compound_contract = Some(deps.api.canonical_address(&compound_contract)?);
+    }
+
+    if let Some(compound_ratio) = compound_ratio {
+        if compound_ratio > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Compound ratio cannot be bigger than one",
+            ));
+        }
+
+        config.compound_ratio = compound_ratio;
+    }
+
+    if let Some(swap_router) = swap_router {
+        config.swap_router = Some(deps.api.canonical_address(&swap_router)?);
+    }
+
+    if config.exchange_rate_contract.is_some() && config.compound_contract.is_some() {
+        return Err(StdError::generic_err(
+            "Rewards cannot be compounded into rebasing collateral",
+        ));
+    }
+
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse {
         messages: vec![],
@@ -118,6 +214,99 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits: Option<bool>,
+    borrows: Option<bool>,
+    liquidations: Option<bool>,
+    withdrawals: Option<bool>,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only pause
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner;
+    if !is_owner && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(&deps.storage)?;
+    pause_info.update(deposits, borrows, liquidations, withdrawals, is_owner)?;
+    store_pause_info(&mut deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposits", pause_info.deposits),
+            log("borrows", pause_info.borrows),
+            log("liquidations", pause_info.liquidations),
+            log("withdrawals", pause_info.withdrawals),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: owner
+pub fn update_tax_policy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    tax_policy: TaxPolicy,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    store_tax_policy(&mut deps.storage, &tax_policy)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_tax_policy")],
+        data: None,
+    })
+}
+
+pub fn sweep_unrelated_funds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: SweepAsset,
+    recipient: HumanAddr,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    // native balances are swapped into rewards and
+    // the collateral balance is owned by the borrowers
+    let is_tracked = match &asset {
+        SweepAsset::Native { .. } => true,
+        SweepAsset::Token { contract_addr } => {
+            deps.api.canonical_address(contract_addr)? == config.collateral_token
+        }
+    };
+    if is_tracked {
+        return Err(StdError::generic_err(format!(
+            "Cannot sweep tracked funds: {}",
+            asset
+        )));
+    }
+
+    let (amount, msg) = sweep_msg(deps, &env, &asset, recipient.clone())?;
+    Ok(HandleResponse {
+        messages: vec![msg],
+        log: vec![
+            log("action", "sweep_unrelated_funds"),
+            log("asset", asset),
+            log("amount", amount),
+            log("recipient", recipient),
+        ],
+        data: None,
+    })
+}
+
 pub fn query<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
@@ -128,6 +317,12 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
         QueryMsg::Borrowers { start_after, limit } => {
             to_binary(&query_borrowers(deps, start_after, limit)?)
         }
+        QueryMsg::PauseInfo {} => to_binary(&read_pause_info(&deps.storage)?),
+        QueryMsg::TaxPolicy {} => to_binary(&read_tax_policy(&deps.storage)?),
+        QueryMsg::CompoundedCollateral { borrower } => {
+            to_binary(&query_compounded_collateral(deps, borrower)?)
+        }
+        QueryMsg::SwapRoute { denom } => to_binary(&query_swap_route(deps, denom)?),
     }
 }
 
@@ -144,6 +339,23 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
         liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
         stable_denom: config.stable_denom,
         basset_info: config.basset_info,
+        guardian: match config.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?),
+            None => None,
+        },
+        exchange_rate_contract: match config.exchange_rate_contract {
+            Some(exchange_rate_contract) => Some(deps.api.human_address(&exchange_rate_contract)?),
+            None => None,
+        },
+        compound_contract: match config.compound_contract {
+            Some(compound_contract) => Some(deps.api.human_address(&compound_contract)?),
+            None => None,
+        },
+        compound_ratio: config.compound_ratio,
+        swap_router: match config.swap_router {
+            Some(swap_router) => Some(deps.api.human_address(&swap_router)?),
+            None => None,
+        },
     })
 }
 
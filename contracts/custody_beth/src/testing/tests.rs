@@ -53,6 +53,7 @@ fn proper_initialization() {
         config_res.liquidation_contract
     );
     assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(None, config_res.guardian);
 }
 
 #[test]
@@ -82,6 +83,11 @@ fn update_config() {
     let msg = HandleMsg::UpdateConfig {
         owner: Some(HumanAddr::from("owner2")),
         liquidation_contract: Some(HumanAddr::from("liquidation2")),
+        guardian: Some(HumanAddr::from("guardian")),
+        exchange_rate_contract: None,
+        compound_contract: None,
+        compound_ratio: None,
+        swap_router: None,
     };
     let env = mock_env("owner", &[]);
     handle(&mut deps, env, msg.clone()).unwrap();
@@ -98,6 +104,7 @@ fn update_config() {
         config_res.liquidation_contract
     );
     assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(Some(HumanAddr::from("guardian")), config_res.guardian);
 
     let env = mock_env("addr0000", &[]);
     let res = handle(&mut deps, env, msg.clone());
@@ -415,9 +422,9 @@ fn lock_collateral() {
 
     assert_eq!(
         res2,
-        StdError::generic_err(format!(
-            "Lock amount cannot excceed the user's spendable amount: 100"
-        ))
+        StdError::generic_err(
+            "Lock amount cannot excceed the user's spendable amount: 100".to_string()
+        )
     );
 
     let env = mock_env("overseer", &[]);
@@ -917,10 +924,8 @@ fn liquidate_collateral() {
                 contract: HumanAddr::from("liquidation"),
                 amount: Uint128::from(10u128),
                 msg: Some(
-                    to_binary(&LiquidationCw20HookMsg::ExecuteBid {
-                        liquidator: HumanAddr::from("liquidator"),
-                        fee_address: Some(HumanAddr::from("overseer")),
-                        repay_address: Some(HumanAddr::from("market")),
+                    to_binary(&LiquidationCw20HookMsg::StageCollateral {
+                        operator: Some(HumanAddr::from("overseer")),
                     })
                     .unwrap()
                 ),
@@ -1,16 +1,18 @@
 use crate::state::{
-    read_borrower_info, read_borrowers, read_config, remove_borrower_info, store_borrower_info,
-    BorrowerInfo, Config,
+    read_borrower_info, read_borrowers, read_compound_index, read_config, read_pause_info,
+    remove_borrower_info, store_borrower_info, store_compound_index, BorrowerInfo, Config,
 };
 
-use cosmwasm_bignumber::Uint256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
 };
-use cw20::Cw20HandleMsg;
-use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
-use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
+use moneymarket::custody::{BorrowerResponse, BorrowersResponse, CompoundedCollateralResponse};
+use moneymarket::liquidation::{execute_bid_msg, Cw20HookMsg as LiquidationCw20HookMsg};
+use moneymarket::payout::token_payout_msg;
+use moneymarket::querier::{query_exchange_rate, query_token_balance};
+use moneymarket::rounding::{self, Rounding};
 use terra_cosmwasm::TerraMsgWrapper;
 
 /// Deposit new collateral
@@ -20,12 +22,21 @@ pub fn deposit_collateral<S: Storage, A: Api, Q: Querier>(
     borrower: HumanAddr,
     amount: Uint256,
 ) -> HandleResult<TerraMsgWrapper> {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(StdError::generic_err("Deposits are paused"));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (mut borrower_info, exchange_rate) =
+        read_rebased_borrower_info(deps, &config, &borrower_raw)?;
 
     // withdraw rewards to pending rewards
     borrower_info.balance += amount;
     borrower_info.spendable += amount;
+    if let Some(exchange_rate) = exchange_rate {
+        borrower_info.shares += rounding::div(amount, exchange_rate, Rounding::Down);
+    }
 
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
 
@@ -47,11 +58,16 @@ pub fn withdraw_collateral<S: Storage, A: Api, Q: Querier>(
     env: Env,
     amount: Option<Uint256>,
 ) -> HandleResult<TerraMsgWrapper> {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(StdError::generic_err("Withdrawals are paused"));
+    }
+
     let config: Config = read_config(&deps.storage)?;
 
     let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (mut borrower_info, exchange_rate) =
+        read_rebased_borrower_info(deps, &config, &borrower_raw)?;
 
     // Check spendable balance
     let amount = amount.unwrap_or(borrower_info.spendable);
@@ -65,6 +81,9 @@ pub fn withdraw_collateral<S: Storage, A: Api, Q: Querier>(
     // withdraw rewards to pending rewards
     borrower_info.balance = borrower_info.balance - amount;
     borrower_info.spendable = borrower_info.spendable - amount;
+    if let Some(exchange_rate) = exchange_rate {
+        remove_shares(&mut borrower_info, amount, exchange_rate);
+    }
 
     if borrower_info.balance == Uint256::zero() {
         remove_borrower_info(&mut deps.storage, &borrower_raw);
@@ -73,14 +92,11 @@ pub fn withdraw_collateral<S: Storage, A: Api, Q: Querier>(
     }
 
     Ok(HandleResponse {
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.collateral_token)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: borrower.clone(),
-                amount: amount.into(),
-            })?,
-        })],
+        messages: vec![token_payout_msg(
+            deps.api.human_address(&config.collateral_token)?,
+            borrower.clone(),
+            amount,
+        )?],
         log: vec![
             log("action", "withdraw_collateral"),
             log("borrower", borrower.as_str()),
@@ -105,7 +121,7 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     }
 
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
-    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (mut borrower_info, _) = read_rebased_borrower_info(deps, &config, &borrower_raw)?;
     if amount > borrower_info.spendable {
         return Err(StdError::generic_err(format!(
             "Lock amount cannot excceed the user's spendable amount: {}",
@@ -141,7 +157,7 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     }
 
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
-    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (mut borrower_info, _) = read_rebased_borrower_info(deps, &config, &borrower_raw)?;
     let borrowed_amt = borrower_info.balance - borrower_info.spendable;
     if amount > borrowed_amt {
         return Err(StdError::generic_err(format!(
@@ -176,8 +192,13 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(StdError::generic_err("Liquidations are paused"));
+    }
+
     let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
-    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (mut borrower_info, exchange_rate) =
+        read_rebased_borrower_info(deps, &config, &borrower_raw)?;
     let borrowed_amt = borrower_info.balance - borrower_info.spendable;
     if amount > borrowed_amt {
         return Err(StdError::generic_err(format!(
@@ -187,22 +208,22 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
     }
 
     borrower_info.balance = borrower_info.balance - amount;
+    if let Some(exchange_rate) = exchange_rate {
+        remove_shares(&mut borrower_info, amount, exchange_rate);
+    }
+
     store_borrower_info(&mut deps.storage, &borrower_raw, &borrower_info)?;
 
     Ok(HandleResponse {
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.collateral_token)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Send {
-                contract: deps.api.human_address(&config.liquidation_contract)?,
-                amount: amount.into(),
-                msg: Some(to_binary(&LiquidationCw20HookMsg::ExecuteBid {
-                    liquidator: liquidator.clone(),
-                    fee_address: Some(deps.api.human_address(&config.overseer_contract)?),
-                    repay_address: Some(deps.api.human_address(&config.market_contract)?),
-                })?),
-            })?,
-        })],
+        messages: vec![execute_bid_msg(
+            deps.api.human_address(&config.collateral_token)?,
+            deps.api.human_address(&config.liquidation_contract)?,
+            amount,
+            // The overseer sells all liquidated collaterals in one ExecuteBids
+            LiquidationCw20HookMsg::StageCollateral {
+                operator: Some(deps.api.human_address(&config.overseer_contract)?),
+            },
+        )?],
         log: vec![
             log("action", "liquidate_collateral"),
             log("liquidator", liquidator),
@@ -213,12 +234,61 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Spread the collateral bought with the compounded rewards
+/// over the deposited collateral
+/// Executor: compound contract, through the bAsset token contract
+pub fn compound_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+
+    // the received amount is already in the balance
+    let total_balance = query_token_balance(
+        deps,
+        &deps.api.human_address(&config.collateral_token)?,
+        &env.contract.address,
+    )?;
+    if total_balance <= amount {
+        return Err(StdError::generic_err("No collateral to compound into"));
+    }
+
+    let compound_index = read_compound_index(&deps.storage)?
+        + Decimal256::from_uint256(amount) / Decimal256::from_uint256(total_balance - amount);
+    store_compound_index(&mut deps.storage, &compound_index)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "compound_collateral"),
+            log("amount", amount),
+            log("compound_index", compound_index),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_compounded_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<CompoundedCollateralResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let (borrower_info, _) = read_rebased_borrower_info(deps, &config, &borrower_raw)?;
+    Ok(CompoundedCollateralResponse {
+        borrower,
+        compounded: borrower_info.compounded,
+    })
+}
+
 pub fn query_borrower<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     borrower: HumanAddr,
 ) -> StdResult<BorrowerResponse> {
+    let config: Config = read_config(&deps.storage)?;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let (borrower_info, _) = read_rebased_borrower_info(deps, &config, &borrower_raw)?;
     Ok(BorrowerResponse {
         borrower,
         balance: borrower_info.balance,
@@ -237,6 +307,43 @@ pub fn query_borrowers<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let borrowers = read_borrowers(deps, start_after, limit)?;
+    let config: Config = read_config(&deps.storage)?;
+    let exchange_rate = match config.exchange_rate_contract {
+        Some(exchange_rate_contract) => Some(query_exchange_rate(
+            deps,
+            &deps.api.human_address(&exchange_rate_contract)?,
+        )?),
+        None => None,
+    };
+
+    let borrowers = read_borrowers(deps, start_after, limit, exchange_rate)?;
     Ok(BorrowersResponse { borrowers })
 }
+
+/// Borrower info with the compounded collateral credited and the balance
+/// moved to the current value of its shares, along with the exchange
+/// rate when the collateral rebases
+pub(crate) fn read_rebased_borrower_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    borrower: &CanonicalAddr,
+) -> StdResult<(BorrowerInfo, Option<Decimal256>)> {
+    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, borrower);
+    borrower_info.accrue_compounded(read_compound_index(&deps.storage)?);
+    let exchange_rate = match &config.exchange_rate_contract {
+        Some(exchange_rate_contract) => {
+            query_exchange_rate(deps, &deps.api.human_address(exchange_rate_contract)?)?
+        }
+        None => return Ok((borrower_info, None)),
+    };
+
+    borrower_info.rebase(exchange_rate);
+    Ok((borrower_info, Some(exchange_rate)))
+}
+
+/// Shares leaving the custody are rounded up, so the
+/// remaining balance is always backed
+fn remove_shares(borrower_info: &mut BorrowerInfo, amount: Uint256, exchange_rate: Decimal256) {
+    let shares = rounding::div(amount, exchange_rate, Rounding::Up);
+    borrower_info.shares = borrower_info.shares - std::cmp::min(borrower_info.shares, shares);
+}
@@ -1,14 +1,16 @@
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    Querier, StdError, Storage, WasmMsg,
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, Querier,
+    StdError, StdResult, Storage, WasmMsg,
 };
 
 use crate::external::handle::RewardContractHandleMsg;
-use crate::state::{read_config, Config};
+use crate::state::{read_config, read_swap_route, remove_swap_route, store_swap_route, Config};
 
-use moneymarket::custody::HandleMsg;
+use moneymarket::custody::{CompoundContractHandleMsg, HandleMsg, SwapRouteResponse};
+use moneymarket::payout::native_payout_msg;
 use moneymarket::querier::{deduct_tax, query_all_balances, query_balance};
+use moneymarket::swap::{AssetInfo, RouterHandleMsg, SwapRoute};
 use terra_cosmwasm::{create_swap_msg, TerraMsgWrapper};
 
 /// Request withdraw reward operation to
@@ -67,28 +69,53 @@ pub fn distribute_hook<S: Storage, A: Api, Q: Querier>(
     // reward_amount = (prev_balance + reward_amount) - prev_balance
     // = (0 + reward_amount) - 0 = reward_amount = balance
     let reward_amount: Uint256 =
-        query_balance(&deps, &contract_addr, config.stable_denom.to_string())?;
+        query_balance(deps, &contract_addr, config.stable_denom.to_string())?;
+
+    // the compounded share buys collateral for the depositors
+    let mut compound_amount = Uint256::zero();
     let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
-    if !reward_amount.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: contract_addr,
-            to_address: overseer_contract,
-            amount: vec![deduct_tax(
-                deps,
-                Coin {
-                    denom: config.stable_denom,
-                    amount: reward_amount.into(),
-                },
-            )?],
-        }));
+    if let Some(compound_contract) = &config.compound_contract {
+        compound_amount = reward_amount * config.compound_ratio;
+        if !compound_amount.is_zero() {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.human_address(compound_contract)?,
+                send: vec![deduct_tax(
+                    deps,
+                    Coin {
+                        denom: config.stable_denom.clone(),
+                        amount: compound_amount.into(),
+                    },
+                )?],
+                msg: to_binary(&CompoundContractHandleMsg::SwapToCollateral {
+                    collateral_token: deps.api.human_address(&config.collateral_token)?,
+                    recipient: contract_addr.clone(),
+                })?,
+            }));
+        }
+    }
+
+    let buffer_amount = reward_amount - compound_amount;
+    if !buffer_amount.is_zero() {
+        messages.push(native_payout_msg(
+            deps,
+            contract_addr,
+            overseer_contract,
+            config.stable_denom,
+            buffer_amount,
+        )?);
+    }
+
+    let mut logs = vec![
+        log("action", "distribute_rewards"),
+        log("buffer_rewards", buffer_amount),
+    ];
+    if !compound_amount.is_zero() {
+        logs.push(log("compound_rewards", compound_amount));
     }
 
     Ok(HandleResponse {
         messages,
-        log: vec![
-            log("action", "distribute_rewards"),
-            log("buffer_rewards", reward_amount),
-        ],
+        log: logs,
         data: None,
     })
 }
@@ -106,18 +133,31 @@ pub fn swap_to_stable_denom<S: Storage, A: Api, Q: Querier>(
     }
 
     let contract_addr = env.contract.address;
-    let balances: Vec<Coin> = query_all_balances(&deps, &contract_addr)?;
-    let messages: Vec<CosmosMsg<TerraMsgWrapper>> = balances
-        .iter()
-        .filter(|x| x.denom != config.stable_denom)
-        .map(|coin: &Coin| {
-            create_swap_msg(
+    let balances: Vec<Coin> = query_all_balances(deps, &contract_addr)?;
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    for coin in balances.iter().filter(|x| x.denom != config.stable_denom) {
+        // denoms with a configured route go through the router,
+        // the rest through the market swap
+        let route = read_swap_route(&deps.storage, &coin.denom)?;
+        match (&config.swap_router, route) {
+            (Some(swap_router), Some(route)) => {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: deps.api.human_address(swap_router)?,
+                    send: vec![deduct_tax(deps, coin.clone())?],
+                    msg: to_binary(&RouterHandleMsg::ExecuteSwapOperations {
+                        operations: route.operations,
+                        minimum_receive: None,
+                        to: None,
+                    })?,
+                }));
+            }
+            _ => messages.push(create_swap_msg(
                 contract_addr.clone(),
                 coin.clone(),
                 config.stable_denom.clone(),
-            )
-        })
-        .collect();
+            )),
+        }
+    }
 
     Ok(HandleResponse {
         messages,
@@ -125,3 +165,53 @@ pub fn swap_to_stable_denom<S: Storage, A: Api, Q: Querier>(
         data: None,
     })
 }
+
+/// Set or clear the swap route of a reward denom
+/// Executor: owner
+pub fn update_swap_route<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    denom: String,
+    route: Option<SwapRoute>,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if denom == config.stable_denom {
+        return Err(StdError::generic_err(
+            "Cannot set a swap route for the stable denom",
+        ));
+    }
+
+    match route {
+        Some(route) => {
+            route.assert_valid(
+                &AssetInfo::NativeToken {
+                    denom: denom.clone(),
+                },
+                &AssetInfo::NativeToken {
+                    denom: config.stable_denom,
+                },
+            )?;
+
+            store_swap_route(&mut deps.storage, &denom, &route)?;
+        }
+        None => remove_swap_route(&mut deps.storage, &denom),
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_swap_route"), log("denom", denom)],
+        data: None,
+    })
+}
+
+pub fn query_swap_route<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    denom: String,
+) -> StdResult<SwapRouteResponse> {
+    let route = read_swap_route(&deps.storage, &denom)?;
+    Ok(SwapRouteResponse { denom, route })
+}
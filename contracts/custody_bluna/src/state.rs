@@ -1,13 +1,18 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_bignumber::Uint256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{Api, CanonicalAddr, Extern, Order, Querier, StdResult, Storage};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 use moneymarket::custody::{BAssetInfo, BorrowerResponse};
+use moneymarket::pause::PauseInfo;
+use moneymarket::swap::SwapRoute;
 
 const KEY_CONFIG: &[u8] = b"config";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const KEY_COMPOUND_INDEX: &[u8] = b"compound_index";
 const PREFIX_BORROWER: &[u8] = b"borrower";
+const PREFIX_SWAP_ROUTE: &[u8] = b"swap_route";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -19,12 +24,55 @@ pub struct Config {
     pub liquidation_contract: CanonicalAddr,
     pub stable_denom: String,
     pub basset_info: BAssetInfo,
+    pub guardian: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub exchange_rate_contract: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub compound_contract: Option<CanonicalAddr>,
+    #[serde(default = "Decimal256::zero")]
+    pub compound_ratio: Decimal256,
+    #[serde(default)]
+    pub swap_router: Option<CanonicalAddr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfo {
     pub balance: Uint256,
     pub spendable: Uint256,
+    /// Shares of a rebasing collateral backing the balance
+    #[serde(default)]
+    pub shares: Uint256,
+    /// Compound index at the last update
+    #[serde(default = "Decimal256::zero")]
+    pub compound_index: Decimal256,
+    /// Total collateral credited by reward compounding
+    #[serde(default)]
+    pub compounded: Uint256,
+}
+
+impl BorrowerInfo {
+    /// Move the balance to the value of the shares; gains are
+    /// spendable and losses come out of the spendable amount first
+    pub fn rebase(&mut self, exchange_rate: Decimal256) {
+        let balance = self.shares * exchange_rate;
+        if balance > self.balance {
+            self.spendable += balance - self.balance;
+        } else {
+            let loss = self.balance - balance;
+            self.spendable = self.spendable - std::cmp::min(self.spendable, loss);
+        }
+
+        self.balance = balance;
+    }
+
+    /// Credit the collateral compounded since the last update
+    pub fn accrue_compounded(&mut self, compound_index: Decimal256) {
+        let compounded = self.balance * (compound_index - self.compound_index);
+        self.balance += compounded;
+        self.spendable += compounded;
+        self.compounded += compounded;
+        self.compound_index = compound_index;
+    }
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
@@ -35,13 +83,54 @@ pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn store_pause_info<S: Storage>(storage: &mut S, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info<S: Storage>(storage: &S) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// Compounded collateral per unit of collateral deposited
+pub fn store_compound_index<S: Storage>(storage: &mut S, data: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_COMPOUND_INDEX).save(data)
+}
+
+pub fn read_compound_index<S: Storage>(storage: &S) -> StdResult<Decimal256> {
+    Ok(ReadonlySingleton::new(storage, KEY_COMPOUND_INDEX)
+        .may_load()?
+        .unwrap_or_else(Decimal256::zero))
+}
+
+pub fn store_swap_route<S: Storage>(
+    storage: &mut S,
+    denom: &str,
+    route: &SwapRoute,
+) -> StdResult<()> {
+    let mut route_bucket: Bucket<S, SwapRoute> = Bucket::new(PREFIX_SWAP_ROUTE, storage);
+    route_bucket.save(denom.as_bytes(), route)
+}
+
+pub fn remove_swap_route<S: Storage>(storage: &mut S, denom: &str) {
+    let mut route_bucket: Bucket<S, SwapRoute> = Bucket::new(PREFIX_SWAP_ROUTE, storage);
+    route_bucket.remove(denom.as_bytes());
+}
+
+pub fn read_swap_route<S: Storage>(storage: &S, denom: &str) -> StdResult<Option<SwapRoute>> {
+    let route_bucket: ReadonlyBucket<S, SwapRoute> =
+        ReadonlyBucket::new(PREFIX_SWAP_ROUTE, storage);
+    route_bucket.may_load(denom.as_bytes())
+}
+
 pub fn store_borrower_info<S: Storage>(
     storage: &mut S,
     borrower: &CanonicalAddr,
     borrower_info: &BorrowerInfo,
 ) -> StdResult<()> {
     let mut borrower_bucket: Bucket<S, BorrowerInfo> = Bucket::new(PREFIX_BORROWER, storage);
-    borrower_bucket.save(borrower.as_slice(), &borrower_info)?;
+    borrower_bucket.save(borrower.as_slice(), borrower_info)?;
 
     Ok(())
 }
@@ -54,11 +143,14 @@ pub fn remove_borrower_info<S: Storage>(storage: &mut S, borrower: &CanonicalAdd
 pub fn read_borrower_info<S: Storage>(storage: &S, borrower: &CanonicalAddr) -> BorrowerInfo {
     let borrower_bucket: ReadonlyBucket<S, BorrowerInfo> =
         ReadonlyBucket::new(PREFIX_BORROWER, storage);
-    match borrower_bucket.load(&borrower.as_slice()) {
+    match borrower_bucket.load(borrower.as_slice()) {
         Ok(v) => v,
         _ => BorrowerInfo {
             balance: Uint256::zero(),
             spendable: Uint256::zero(),
+            shares: Uint256::zero(),
+            compound_index: Decimal256::zero(),
+            compounded: Uint256::zero(),
         },
     }
 }
@@ -70,18 +162,25 @@ pub fn read_borrowers<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     start_after: Option<CanonicalAddr>,
     limit: Option<u32>,
+    exchange_rate: Option<Decimal256>,
 ) -> StdResult<Vec<BorrowerResponse>> {
     let position_bucket: ReadonlyBucket<S, BorrowerInfo> =
         ReadonlyBucket::new(PREFIX_BORROWER, &deps.storage);
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(start_after);
+    let compound_index = read_compound_index(&deps.storage)?;
 
     position_bucket
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|item| {
-            let (k, v) = item?;
+            let (k, mut v) = item?;
+            v.accrue_compounded(compound_index);
+            if let Some(exchange_rate) = exchange_rate {
+                v.rebase(exchange_rate);
+            }
+
             let borrower: CanonicalAddr = CanonicalAddr::from(k);
             Ok(BorrowerResponse {
                 borrower: deps.api.human_address(&borrower)?,
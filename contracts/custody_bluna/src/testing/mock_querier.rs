@@ -1,10 +1,12 @@
+use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_slice, to_binary, Api, CanonicalAddr, Coin, Decimal, Extern, HumanAddr, Querier,
-    QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Api, CanonicalAddr, Coin, Decimal, Extern, HumanAddr,
+    Querier, QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
 };
 use cosmwasm_storage::to_length_prefixed;
 use cw20::TokenInfoResponse;
+use moneymarket::custody::{ExchangeRateQueryMsg, ExchangeRateResponse};
 use std::collections::HashMap;
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
@@ -32,6 +34,7 @@ pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
     token_querier: TokenQuerier,
     tax_querier: TaxQuerier,
+    exchange_rate: Decimal256,
     canonical_length: usize,
 }
 
@@ -164,7 +167,7 @@ impl WasmMockQuerier {
                             name: "mAPPL".to_string(),
                             symbol: "mAPPL".to_string(),
                             decimals: 6,
-                            total_supply: total_supply,
+                            total_supply,
                         })
                         .unwrap(),
                     ))
@@ -195,6 +198,11 @@ impl WasmMockQuerier {
                     panic!("DO NOT ENTER HERE")
                 }
             }
+            QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => match from_binary(msg).unwrap() {
+                ExchangeRateQueryMsg::ExchangeRate {} => Ok(to_binary(&ExchangeRateResponse {
+                    rate: self.exchange_rate,
+                })),
+            },
             _ => self.base.handle_query(request),
         }
     }
@@ -210,6 +218,7 @@ impl WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
             tax_querier: TaxQuerier::default(),
+            exchange_rate: Decimal256::one(),
             canonical_length,
         }
     }
@@ -223,4 +232,8 @@ impl WasmMockQuerier {
     pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
         self.tax_querier = TaxQuerier::new(rate, caps);
     }
+
+    pub fn with_exchange_rate(&mut self, exchange_rate: Decimal256) {
+        self.exchange_rate = exchange_rate;
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_bignumber::Uint256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     from_binary, log, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError,
     Uint128, WasmMsg,
@@ -12,9 +12,11 @@ use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
 use moneymarket::custody::{
-    BAssetInfo, BorrowerResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg,
+    BAssetInfo, BorrowerResponse, CompoundContractHandleMsg, CompoundedCollateralResponse,
+    ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg, SwapRouteResponse,
 };
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
+use moneymarket::swap::{AssetInfo, RouterHandleMsg, SwapOperation, SwapRoute};
 use terra_cosmwasm::create_swap_msg;
 
 #[test]
@@ -53,6 +55,7 @@ fn proper_initialization() {
         config_res.liquidation_contract
     );
     assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(None, config_res.guardian);
 }
 
 #[test]
@@ -82,6 +85,11 @@ fn update_config() {
     let msg = HandleMsg::UpdateConfig {
         owner: Some(HumanAddr::from("owner2")),
         liquidation_contract: Some(HumanAddr::from("liquidation2")),
+        guardian: Some(HumanAddr::from("guardian")),
+        exchange_rate_contract: None,
+        compound_contract: None,
+        compound_ratio: None,
+        swap_router: None,
     };
     let env = mock_env("owner", &[]);
     handle(&mut deps, env, msg.clone()).unwrap();
@@ -98,6 +106,7 @@ fn update_config() {
         config_res.liquidation_contract
     );
     assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(Some(HumanAddr::from("guardian")), config_res.guardian);
 
     let env = mock_env("addr0000", &[]);
     let res = handle(&mut deps, env, msg.clone());
@@ -415,9 +424,9 @@ fn lock_collateral() {
 
     assert_eq!(
         res2,
-        StdError::generic_err(format!(
-            "Lock amount cannot excceed the user's spendable amount: 100"
-        ))
+        StdError::generic_err(
+            "Lock amount cannot excceed the user's spendable amount: 100".to_string()
+        )
     );
 
     let env = mock_env("overseer", &[]);
@@ -910,10 +919,8 @@ fn liquidate_collateral() {
                 contract: HumanAddr::from("liquidation"),
                 amount: Uint128::from(10u128),
                 msg: Some(
-                    to_binary(&LiquidationCw20HookMsg::ExecuteBid {
-                        liquidator: HumanAddr::from("liquidator"),
-                        fee_address: Some(HumanAddr::from("overseer")),
-                        repay_address: Some(HumanAddr::from("market")),
+                    to_binary(&LiquidationCw20HookMsg::StageCollateral {
+                        operator: Some(HumanAddr::from("overseer")),
                     })
                     .unwrap()
                 ),
@@ -922,3 +929,366 @@ fn liquidate_collateral() {
         })]
     );
 }
+
+#[test]
+fn rebasing_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("bluna"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            decimals: 6,
+        },
+    };
+    let _res = init(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        owner: None,
+        liquidation_contract: None,
+        guardian: None,
+        exchange_rate_contract: Some(HumanAddr::from("exchange_rate")),
+        compound_contract: None,
+        compound_ratio: None,
+        swap_router: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg.clone()).unwrap();
+
+    // 100 tokens at 2 tokens per share are 50 shares
+    deps.querier
+        .with_exchange_rate(Decimal256::from_uint256(2u64));
+    let deposit_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("bluna", &[]), deposit_msg).unwrap();
+
+    match handle(&mut deps, mock_env("owner", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Exchange rate contract cannot be set while the custody has borrowers"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::LockCollateral {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(60u64),
+    };
+    let _res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+
+    // the rebase gain becomes spendable
+    deps.querier
+        .with_exchange_rate(Decimal256::from_uint256(3u64));
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(150u64),
+            spendable: Uint256::from(90u64),
+        }
+    );
+
+    // 90 tokens take 30 shares
+    let msg = HandleMsg::WithdrawCollateral { amount: None };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("bluna"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(90u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let borrower_info = read_borrower_info(
+        &deps.storage,
+        &deps
+            .api
+            .canonical_address(&HumanAddr::from("addr0000"))
+            .unwrap(),
+    );
+    assert_eq!(borrower_info.shares, Uint256::from(20u64));
+    assert_eq!(borrower_info.balance, Uint256::from(60u64));
+    assert_eq!(borrower_info.spendable, Uint256::zero());
+}
+
+#[test]
+fn compound_rewards() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(1000000u128),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("bluna"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            decimals: 6,
+        },
+    };
+    let _res = init(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        owner: None,
+        liquidation_contract: None,
+        guardian: None,
+        exchange_rate_contract: None,
+        compound_contract: Some(HumanAddr::from("compound")),
+        compound_ratio: Some(Decimal256::percent(50)),
+        swap_router: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    for (borrower, amount) in [("addr0000", 100u128), ("addr0001", 300u128)].iter() {
+        let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from(*borrower),
+            amount: Uint128::from(*amount),
+            msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+        });
+        let _res = handle(&mut deps, mock_env("bluna", &[]), msg).unwrap();
+    }
+
+    let msg = HandleMsg::DistributeHook {};
+    let res = handle(&mut deps, mock_env(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("compound"),
+                send: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128(500000u128),
+                }],
+                msg: to_binary(&CompoundContractHandleMsg::SwapToCollateral {
+                    collateral_token: HumanAddr::from("bluna"),
+                    recipient: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("overseer"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128(500000u128),
+                }],
+            }),
+        ]
+    );
+
+    // the swap route sends back 40 collateral tokens
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("bluna"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(440u128),
+        )],
+    )]);
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(40u128),
+        msg: Some(to_binary(&Cw20HookMsg::CompoundCollateral {}).unwrap()),
+    });
+    match handle(&mut deps, mock_env("bluna", &[]), msg) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("compound"),
+        amount: Uint128::from(40u128),
+        msg: Some(to_binary(&Cw20HookMsg::CompoundCollateral {}).unwrap()),
+    });
+    let _res = handle(&mut deps, mock_env("bluna", &[]), msg).unwrap();
+
+    let query_res = query(
+        &deps,
+        QueryMsg::Borrower {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(110u64),
+            spendable: Uint256::from(110u64),
+        }
+    );
+
+    let query_res = query(
+        &deps,
+        QueryMsg::CompoundedCollateral {
+            borrower: HumanAddr::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let compounded_res: CompoundedCollateralResponse = from_binary(&query_res).unwrap();
+    assert_eq!(compounded_res.compounded, Uint256::from(30u64));
+}
+
+#[test]
+fn swap_route() {
+    let mut deps = mock_dependencies(
+        20,
+        &[
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128(20000000000u128),
+            },
+            Coin {
+                denom: "usdr".to_string(),
+                amount: Uint128(2000000u128),
+            },
+        ],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        collateral_token: HumanAddr::from("bluna"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        reward_contract: HumanAddr::from("reward"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+        basset_info: BAssetInfo {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            decimals: 6,
+        },
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        owner: None,
+        liquidation_contract: None,
+        guardian: None,
+        exchange_rate_contract: None,
+        compound_contract: None,
+        compound_ratio: None,
+        swap_router: Some(HumanAddr::from("router")),
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let operations = vec![
+        SwapOperation::NativeSwap {
+            offer_denom: "ukrw".to_string(),
+            ask_denom: "uluna".to_string(),
+        },
+        SwapOperation::PairSwap {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+    ];
+
+    let msg = HandleMsg::UpdateSwapRoute {
+        denom: "ukrw".to_string(),
+        route: Some(SwapRoute {
+            operations: operations[..1].to_vec(),
+        }),
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Swap route must end with: uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateSwapRoute {
+        denom: "ukrw".to_string(),
+        route: Some(SwapRoute {
+            operations: operations.clone(),
+        }),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let query_res = query(
+        &deps,
+        QueryMsg::SwapRoute {
+            denom: "ukrw".to_string(),
+        },
+    )
+    .unwrap();
+    let route_res: SwapRouteResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        route_res.route,
+        Some(SwapRoute {
+            operations: operations.clone(),
+        })
+    );
+
+    // routed denoms go through the router, the rest through the market
+    let msg = HandleMsg::SwapToStableDenom {};
+    let res = handle(&mut deps, mock_env(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("router"),
+                send: vec![Coin {
+                    denom: "ukrw".to_string(),
+                    amount: Uint128::from(20000000000u128),
+                }],
+                msg: to_binary(&RouterHandleMsg::ExecuteSwapOperations {
+                    operations,
+                    minimum_receive: None,
+                    to: None,
+                })
+                .unwrap(),
+            }),
+            create_swap_msg(
+                HumanAddr::from(MOCK_CONTRACT_ADDR),
+                Coin {
+                    denom: "usdr".to_string(),
+                    amount: Uint128::from(2000000u128),
+                },
+                "uusd".to_string(),
+            ),
+        ]
+    );
+}
@@ -0,0 +1,25 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
+use moneymarket::custody_multi::{
+    CollateralResponse, CollateralsResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+};
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InitMsg), &out_dir);
+    export_schema(&schema_for!(HandleMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(BorrowerResponse), &out_dir);
+    export_schema(&schema_for!(BorrowersResponse), &out_dir);
+    export_schema(&schema_for!(CollateralResponse), &out_dir);
+    export_schema(&schema_for!(CollateralsResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+}
@@ -0,0 +1,299 @@
+use crate::state::{
+    read_borrower_info, read_borrowers, read_collateral_info, read_config, read_pause_info,
+    remove_borrower_info, store_borrower_info, BorrowerInfo, Config,
+};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
+use moneymarket::liquidation::{execute_bid_msg, Cw20HookMsg as LiquidationCw20HookMsg};
+use moneymarket::payout::token_payout_msg;
+use terra_cosmwasm::TerraMsgWrapper;
+
+/// Deposit new collateral
+/// Executor: registered bAsset token contract
+pub fn deposit_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(StdError::generic_err("Deposits are paused"));
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+
+    borrower_info.balance += amount;
+    borrower_info.spendable += amount;
+
+    store_borrower_info(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &borrower_raw,
+        &borrower_info,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deposit_collateral"),
+            log("collateral_token", collateral_token),
+            log("borrower", borrower.as_str()),
+            log("amount", amount.to_string()),
+        ],
+        data: None,
+    })
+}
+
+/// Withdraw spendable collateral or a specified amount of collateral
+/// Executor: borrower
+pub fn withdraw_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    amount: Option<Uint256>,
+) -> HandleResult<TerraMsgWrapper> {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(StdError::generic_err("Withdrawals are paused"));
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let borrower = env.message.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+
+    // Check spendable balance
+    let amount = amount.unwrap_or(borrower_info.spendable);
+    if borrower_info.spendable < amount {
+        return Err(StdError::generic_err(format!(
+            "Withdraw amount cannot exceed the user's spendable amount: {}",
+            borrower_info.spendable
+        )));
+    }
+
+    borrower_info.balance = borrower_info.balance - amount;
+    borrower_info.spendable = borrower_info.spendable - amount;
+
+    if borrower_info.balance == Uint256::zero() {
+        remove_borrower_info(&mut deps.storage, &collateral_token_raw, &borrower_raw);
+    } else {
+        store_borrower_info(
+            &mut deps.storage,
+            &collateral_token_raw,
+            &borrower_raw,
+            &borrower_info,
+        )?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![token_payout_msg(
+            collateral_token.clone(),
+            borrower.clone(),
+            amount,
+        )?],
+        log: vec![
+            log("action", "withdraw_collateral"),
+            log("collateral_token", collateral_token),
+            log("borrower", borrower.as_str()),
+            log("amount", amount.to_string()),
+        ],
+        data: None,
+    })
+}
+
+/// Decrease spendable collateral to lock
+/// specified amount of collateral token
+/// Executor: overseer
+pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+    if amount > borrower_info.spendable {
+        return Err(StdError::generic_err(format!(
+            "Lock amount cannot excceed the user's spendable amount: {}",
+            borrower_info.spendable
+        )));
+    }
+
+    borrower_info.spendable = borrower_info.spendable - amount;
+    store_borrower_info(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &borrower_raw,
+        &borrower_info,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "lock_collateral"),
+            log("collateral_token", collateral_token),
+            log("borrower", borrower),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+/// Increase spendable collateral to unlock
+/// specified amount of collateral token
+/// Executor: overseer
+pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    if amount > borrowed_amt {
+        return Err(StdError::generic_err(format!(
+            "Unlock amount cannot exceed locked amount: {}",
+            borrowed_amt
+        )));
+    }
+
+    borrower_info.spendable += amount;
+    store_borrower_info(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &borrower_raw,
+        &borrower_info,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "unlock_collateral"),
+            log("collateral_token", collateral_token),
+            log("borrower", borrower),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: overseer
+pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    liquidator: HumanAddr,
+    borrower: HumanAddr,
+    amount: Uint256,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.overseer_contract {
+        return Err(StdError::unauthorized());
+    }
+
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(StdError::generic_err("Liquidations are paused"));
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    if amount > borrowed_amt {
+        return Err(StdError::generic_err(format!(
+            "Liquidation amount cannot exceed locked amount: {}",
+            borrowed_amt
+        )));
+    }
+
+    borrower_info.balance = borrower_info.balance - amount;
+    store_borrower_info(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &borrower_raw,
+        &borrower_info,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![execute_bid_msg(
+            collateral_token.clone(),
+            deps.api.human_address(&config.liquidation_contract)?,
+            amount,
+            // The overseer sells all liquidated collaterals in one ExecuteBids
+            LiquidationCw20HookMsg::StageCollateral {
+                operator: Some(deps.api.human_address(&config.overseer_contract)?),
+            },
+        )?],
+        log: vec![
+            log("action", "liquidate_collateral"),
+            log("collateral_token", collateral_token),
+            log("liquidator", liquidator),
+            log("borrower", borrower),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_borrower<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    borrower: HumanAddr,
+) -> StdResult<BorrowerResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    read_collateral_info(&deps.storage, &collateral_token_raw)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let borrower_info: BorrowerInfo =
+        read_borrower_info(&deps.storage, &collateral_token_raw, &borrower_raw);
+    Ok(BorrowerResponse {
+        borrower,
+        balance: borrower_info.balance,
+        spendable: borrower_info.spendable,
+    })
+}
+
+pub fn query_borrowers<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<BorrowersResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    read_collateral_info(&deps.storage, &collateral_token_raw)?;
+
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let borrowers = read_borrowers(deps, &collateral_token_raw, start_after, limit)?;
+    Ok(BorrowersResponse { borrowers })
+}
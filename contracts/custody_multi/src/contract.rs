@@ -0,0 +1,346 @@
+use cosmwasm_std::{
+    from_binary, log, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, InitResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::collateral::{
+    deposit_collateral, liquidate_collateral, lock_collateral, query_borrower, query_borrowers,
+    unlock_collateral, withdraw_collateral,
+};
+use crate::distribution::{distribute_hook, distribute_rewards, swap_to_stable_denom};
+use crate::state::{
+    read_collateral_info, read_collaterals, read_config, read_pause_info, store_collateral_info,
+    store_config, store_pause_info, CollateralInfo, Config,
+};
+
+use cw20::Cw20ReceiveMsg;
+use moneymarket::custody::{BAssetInfo, Cw20HookMsg, HandleMsg as CustodyHandleMsg};
+use moneymarket::custody_multi::{
+    CollateralResponse, CollateralsResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+};
+use moneymarket::pause::PauseInfo;
+use moneymarket::tax::{read_tax_policy, store_tax_policy, TaxPolicy};
+use terra_cosmwasm::TerraMsgWrapper;
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: InitMsg,
+) -> InitResult {
+    let config = Config {
+        owner: deps.api.canonical_address(&msg.owner)?,
+        overseer_contract: deps.api.canonical_address(&msg.overseer_contract)?,
+        market_contract: deps.api.canonical_address(&msg.market_contract)?,
+        liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
+        stable_denom: msg.stable_denom,
+        guardian: None,
+    };
+
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult<TerraMsgWrapper> {
+    match msg {
+        HandleMsg::Receive(msg) => receive_cw20(deps, env, msg),
+        HandleMsg::UpdateConfig {
+            owner,
+            liquidation_contract,
+            guardian,
+        } => update_config(deps, env, owner, liquidation_contract, guardian),
+        HandleMsg::RegisterCollateral {
+            collateral_token,
+            reward_contract,
+            basset_info,
+        } => register_collateral(deps, env, collateral_token, reward_contract, basset_info),
+        HandleMsg::SetPause {
+            deposits,
+            borrows,
+            liquidations,
+            withdrawals,
+        } => set_pause(deps, env, deposits, borrows, liquidations, withdrawals),
+        HandleMsg::UpdateTaxPolicy { tax_policy } => update_tax_policy(deps, env, tax_policy),
+        HandleMsg::Collateral {
+            collateral_token,
+            msg,
+        } => handle_collateral(deps, env, collateral_token, msg),
+        HandleMsg::DistributeHook {} => distribute_hook(deps, env),
+        HandleMsg::SwapToStableDenom {} => swap_to_stable_denom(deps, env),
+    }
+}
+
+/// Dispatch a single-asset custody message to the
+/// balances of a registered collateral token
+pub fn handle_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    msg: CustodyHandleMsg,
+) -> HandleResult<TerraMsgWrapper> {
+    read_collateral_info(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    match msg {
+        CustodyHandleMsg::LockCollateral { borrower, amount } => {
+            lock_collateral(deps, env, collateral_token, borrower, amount)
+        }
+        CustodyHandleMsg::UnlockCollateral { borrower, amount } => {
+            unlock_collateral(deps, env, collateral_token, borrower, amount)
+        }
+        CustodyHandleMsg::LiquidateCollateral {
+            liquidator,
+            borrower,
+            amount,
+        } => liquidate_collateral(deps, env, collateral_token, liquidator, borrower, amount),
+        CustodyHandleMsg::DistributeRewards {} => distribute_rewards(deps, env, collateral_token),
+        CustodyHandleMsg::WithdrawCollateral { amount } => {
+            withdraw_collateral(deps, env, collateral_token, amount)
+        }
+        _ => Err(StdError::generic_err(
+            "Message is not supported per collateral",
+        )),
+    }
+}
+
+pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    cw20_msg: Cw20ReceiveMsg,
+) -> HandleResult<TerraMsgWrapper> {
+    let contract_addr = env.message.sender;
+    if let Some(msg) = cw20_msg.msg {
+        match from_binary(&msg)? {
+            Cw20HookMsg::DepositCollateral {} => {
+                // only registered asset contracts can execute this message
+                let contract_addr_raw = deps.api.canonical_address(&contract_addr)?;
+                if read_collateral_info(&deps.storage, &contract_addr_raw).is_err() {
+                    return Err(StdError::unauthorized());
+                }
+
+                deposit_collateral(deps, contract_addr, cw20_msg.sender, cw20_msg.amount.into())
+            }
+            Cw20HookMsg::CompoundCollateral {} => {
+                Err(StdError::generic_err("Reward compounding is not supported"))
+            }
+        }
+    } else {
+        Err(StdError::generic_err(
+            "Invalid request: \"deposit collateral\" message not included in request",
+        ))
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: Option<HumanAddr>,
+    liquidation_contract: Option<HumanAddr>,
+    guardian: Option<HumanAddr>,
+) -> HandleResult<TerraMsgWrapper> {
+    let mut config: Config = read_config(&deps.storage)?;
+
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+
+    if let Some(liquidation_contract) = liquidation_contract {
+        config.liquidation_contract = deps.api.canonical_address(&liquidation_contract)?;
+    }
+
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_config")],
+        data: None,
+    })
+}
+
+/// Executor: owner
+pub fn register_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    reward_contract: HumanAddr,
+    basset_info: BAssetInfo,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    if read_collateral_info(&deps.storage, &collateral_token_raw).is_ok() {
+        return Err(StdError::generic_err(
+            "Collateral token is already registered",
+        ));
+    }
+
+    store_collateral_info(
+        &mut deps.storage,
+        &collateral_token_raw,
+        &CollateralInfo {
+            reward_contract: deps.api.canonical_address(&reward_contract)?,
+            basset_info,
+            last_distributed: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_collateral"),
+            log("collateral_token", collateral_token),
+            log("reward_contract", reward_contract),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits: Option<bool>,
+    borrows: Option<bool>,
+    liquidations: Option<bool>,
+    withdrawals: Option<bool>,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only pause
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner;
+    if !is_owner && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(&deps.storage)?;
+    pause_info.update(deposits, borrows, liquidations, withdrawals, is_owner)?;
+    store_pause_info(&mut deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposits", pause_info.deposits),
+            log("borrows", pause_info.borrows),
+            log("liquidations", pause_info.liquidations),
+            log("withdrawals", pause_info.withdrawals),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: owner
+pub fn update_tax_policy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    tax_policy: TaxPolicy,
+) -> HandleResult<TerraMsgWrapper> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    store_tax_policy(&mut deps.storage, &tax_policy)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_tax_policy")],
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Collateral { collateral_token } => {
+            to_binary(&query_collateral(deps, collateral_token)?)
+        }
+        QueryMsg::Collaterals { start_after, limit } => {
+            to_binary(&query_collaterals(deps, start_after, limit)?)
+        }
+        QueryMsg::Borrower {
+            collateral_token,
+            address,
+        } => to_binary(&query_borrower(deps, collateral_token, address)?),
+        QueryMsg::Borrowers {
+            collateral_token,
+            start_after,
+            limit,
+        } => to_binary(&query_borrowers(
+            deps,
+            collateral_token,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::PauseInfo {} => to_binary(&read_pause_info(&deps.storage)?),
+        QueryMsg::TaxPolicy {} => to_binary(&read_tax_policy(&deps.storage)?),
+    }
+}
+
+pub fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?,
+        overseer_contract: deps.api.human_address(&config.overseer_contract)?,
+        market_contract: deps.api.human_address(&config.market_contract)?,
+        liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
+        stable_denom: config.stable_denom,
+        guardian: match config.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?),
+            None => None,
+        },
+    })
+}
+
+pub fn query_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<CollateralResponse> {
+    let collateral_info: CollateralInfo = read_collateral_info(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(CollateralResponse {
+        collateral_token,
+        reward_contract: deps.api.human_address(&collateral_info.reward_contract)?,
+        basset_info: collateral_info.basset_info,
+        last_distributed: collateral_info.last_distributed,
+    })
+}
+
+pub fn query_collaterals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<CollateralsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let collaterals = read_collaterals(deps, start_after, limit)?;
+    Ok(CollateralsResponse { collaterals })
+}
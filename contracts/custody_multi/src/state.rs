@@ -0,0 +1,181 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{Api, CanonicalAddr, Extern, Order, Querier, StdError, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use moneymarket::custody::{BAssetInfo, BorrowerResponse};
+use moneymarket::custody_multi::CollateralResponse;
+use moneymarket::pause::PauseInfo;
+
+const KEY_CONFIG: &[u8] = b"config";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const PREFIX_COLLATERAL: &[u8] = b"collateral";
+const PREFIX_BORROWER: &[u8] = b"borrower";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub market_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub stable_denom: String,
+    pub guardian: Option<CanonicalAddr>,
+}
+
+/// Registered collateral token and its reward distribution state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralInfo {
+    pub reward_contract: CanonicalAddr,
+    pub basset_info: BAssetInfo,
+    pub last_distributed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerInfo {
+    pub balance: Uint256,
+    pub spendable: Uint256,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONFIG).save(data)
+}
+
+pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+pub fn store_pause_info<S: Storage>(storage: &mut S, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info<S: Storage>(storage: &S) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_collateral_info<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    collateral_info: &CollateralInfo,
+) -> StdResult<()> {
+    let mut collateral_bucket: Bucket<S, CollateralInfo> = Bucket::new(PREFIX_COLLATERAL, storage);
+    collateral_bucket.save(collateral_token.as_slice(), collateral_info)
+}
+
+pub fn read_collateral_info<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<CollateralInfo> {
+    let collateral_bucket: ReadonlyBucket<S, CollateralInfo> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL, storage);
+    match collateral_bucket.load(collateral_token.as_slice()) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("Collateral token is not registered")),
+    }
+}
+
+pub fn store_borrower_info<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    borrower: &CanonicalAddr,
+    borrower_info: &BorrowerInfo,
+) -> StdResult<()> {
+    let mut borrower_bucket: Bucket<S, BorrowerInfo> =
+        Bucket::multilevel(&[PREFIX_BORROWER, collateral_token.as_slice()], storage);
+    borrower_bucket.save(borrower.as_slice(), borrower_info)
+}
+
+pub fn remove_borrower_info<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    borrower: &CanonicalAddr,
+) {
+    let mut borrower_bucket: Bucket<S, BorrowerInfo> =
+        Bucket::multilevel(&[PREFIX_BORROWER, collateral_token.as_slice()], storage);
+    borrower_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_borrower_info<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+    borrower: &CanonicalAddr,
+) -> BorrowerInfo {
+    let borrower_bucket: ReadonlyBucket<S, BorrowerInfo> =
+        ReadonlyBucket::multilevel(&[PREFIX_BORROWER, collateral_token.as_slice()], storage);
+    match borrower_bucket.load(borrower.as_slice()) {
+        Ok(v) => v,
+        _ => BorrowerInfo {
+            balance: Uint256::zero(),
+            spendable: Uint256::zero(),
+        },
+    }
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+pub fn read_collaterals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CollateralResponse>> {
+    let collateral_bucket: ReadonlyBucket<S, CollateralInfo> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL, &deps.storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    collateral_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok(CollateralResponse {
+                collateral_token: deps.api.human_address(&CanonicalAddr::from(k))?,
+                reward_contract: deps.api.human_address(&v.reward_contract)?,
+                basset_info: v.basset_info,
+                last_distributed: v.last_distributed,
+            })
+        })
+        .collect()
+}
+
+pub fn read_borrowers<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: &CanonicalAddr,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BorrowerResponse>> {
+    let position_bucket: ReadonlyBucket<S, BorrowerInfo> = ReadonlyBucket::multilevel(
+        &[PREFIX_BORROWER, collateral_token.as_slice()],
+        &deps.storage,
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    position_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            let borrower: CanonicalAddr = CanonicalAddr::from(k);
+            Ok(BorrowerResponse {
+                borrower: deps.api.human_address(&borrower)?,
+                balance: v.balance,
+                spendable: v.spendable,
+            })
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| {
+        let mut v = addr.as_slice().to_vec();
+        v.push(1);
+        v
+    })
+}
@@ -0,0 +1,289 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{from_binary, log, to_binary, CosmosMsg, Extern, HumanAddr, StdError, WasmMsg};
+
+use crate::contract::{handle, init, query};
+use crate::external::handle::RewardContractHandleMsg;
+
+use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
+use moneymarket::custody::{
+    BAssetInfo, BorrowerResponse, BorrowersResponse, Cw20HookMsg, HandleMsg as CustodyHandleMsg,
+};
+use moneymarket::custody_multi::{
+    CollateralResponse, CollateralsResponse, HandleMsg, InitMsg, QueryMsg,
+};
+
+fn init_with_collaterals() -> Extern<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner"),
+        overseer_contract: HumanAddr::from("overseer"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        stable_denom: "uusd".to_string(),
+    };
+
+    let env = mock_env("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    for token in ["bluna", "beth"].iter() {
+        let msg = HandleMsg::RegisterCollateral {
+            collateral_token: HumanAddr::from(*token),
+            reward_contract: HumanAddr::from(format!("reward_{}", token)),
+            basset_info: BAssetInfo {
+                name: token.to_string(),
+                symbol: token.to_string(),
+                decimals: 6,
+            },
+        };
+        let env = mock_env("owner", &[]);
+        let _res = handle(&mut deps, env, msg).unwrap();
+    }
+
+    deps
+}
+
+fn deposit_msg(amount: u128) -> HandleMsg {
+    HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: amount.into(),
+        msg: Some(to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap()),
+    })
+}
+
+#[test]
+fn register_collateral() {
+    let mut deps = init_with_collaterals();
+
+    let msg = HandleMsg::RegisterCollateral {
+        collateral_token: HumanAddr::from("batom"),
+        reward_contract: HumanAddr::from("reward_batom"),
+        basset_info: BAssetInfo {
+            name: "batom".to_string(),
+            symbol: "batom".to_string(),
+            decimals: 6,
+        },
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::RegisterCollateral {
+        collateral_token: HumanAddr::from("bluna"),
+        reward_contract: HumanAddr::from("reward_bluna"),
+        basset_info: BAssetInfo {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            decimals: 6,
+        },
+    };
+    let env = mock_env("owner", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Collateral token is already registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(collaterals_res.collaterals.len(), 2);
+
+    let res = query(
+        &deps,
+        QueryMsg::Collateral {
+            collateral_token: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let collateral_res: CollateralResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collateral_res.reward_contract,
+        HumanAddr::from("reward_bluna")
+    );
+}
+
+#[test]
+fn deposit_and_lock_per_collateral() {
+    let mut deps = init_with_collaterals();
+
+    // unregistered tokens cannot deposit
+    let env = mock_env("batom", &[]);
+    match handle(&mut deps, env, deposit_msg(100)) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("bluna", &[]);
+    let res = handle(&mut deps, env, deposit_msg(100)).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit_collateral"),
+            log("collateral_token", "bluna"),
+            log("borrower", "addr0000"),
+            log("amount", "100"),
+        ]
+    );
+
+    let env = mock_env("beth", &[]);
+    let _res = handle(&mut deps, env, deposit_msg(50)).unwrap();
+
+    // only the overseer can lock
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("bluna"),
+        msg: CustodyHandleMsg::LockCollateral {
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(60u64),
+        },
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("overseer", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // the beth balance is untouched by the bluna lock
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("beth"),
+        msg: CustodyHandleMsg::LockCollateral {
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(60u64),
+        },
+    };
+    let env = mock_env("overseer", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Lock amount cannot excceed the user's spendable amount: 50"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::Borrower {
+            collateral_token: HumanAddr::from("bluna"),
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(100u64),
+            spendable: Uint256::from(40u64),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Borrowers {
+            collateral_token: HumanAddr::from("beth"),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let borrowers_res: BorrowersResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrowers_res.borrowers,
+        vec![BorrowerResponse {
+            borrower: HumanAddr::from("addr0000"),
+            balance: Uint256::from(50u64),
+            spendable: Uint256::from(50u64),
+        }]
+    );
+
+    // withdraw pays out the token of the dispatched collateral
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("bluna"),
+        msg: CustodyHandleMsg::WithdrawCollateral { amount: None },
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("bluna"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: 40u128.into(),
+            })
+            .unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn dispatch_collateral() {
+    let mut deps = init_with_collaterals();
+
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("batom"),
+        msg: CustodyHandleMsg::DistributeRewards {},
+    };
+    let env = mock_env("overseer", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Collateral token is not registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("bluna"),
+        msg: CustodyHandleMsg::DistributeHook {},
+    };
+    let env = mock_env("overseer", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Message is not supported per collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // rewards are claimed from the reward contract of the collateral
+    let msg = HandleMsg::Collateral {
+        collateral_token: HumanAddr::from("beth"),
+        msg: CustodyHandleMsg::DistributeRewards {},
+    };
+    let env = mock_env("overseer", &[]);
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("reward_beth"),
+            send: vec![],
+            msg: to_binary(&RewardContractHandleMsg::ClaimRewards { recipient: None }).unwrap(),
+        })
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collateral {
+            collateral_token: HumanAddr::from("beth"),
+        },
+    )
+    .unwrap();
+    let collateral_res: CollateralResponse = from_binary(&res).unwrap();
+    assert_eq!(collateral_res.last_distributed, env.block.height);
+}
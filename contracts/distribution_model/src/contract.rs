@@ -1,4 +1,6 @@
-use crate::state::{read_config, store_config, Config};
+use crate::state::{
+    read_config, read_emission_schedule, store_config, store_emission_schedule, Config,
+};
 
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{
@@ -6,7 +8,8 @@ use cosmwasm_std::{
     MigrateResponse, MigrateResult, Querier, StdError, StdResult, Storage,
 };
 use moneymarket::distribution_model::{
-    AncEmissionRateResponse, ConfigResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg,
+    ActiveEmissionSegmentResponse, AncEmissionRateResponse, ConfigResponse, EmissionSegment,
+    HandleMsg, InitMsg, MigrateMsg, QueryMsg,
 };
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -49,6 +52,9 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             increment_multiplier,
             decrement_multiplier,
         ),
+        HandleMsg::UpdateEmissionSchedule { schedule } => {
+            update_emission_schedule(deps, env, schedule)
+        }
     }
 }
 
@@ -90,6 +96,47 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse::default())
 }
 
+pub fn update_emission_schedule<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    schedule: Vec<EmissionSegment>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut prev_end_height = 0u64;
+    for segment in schedule.iter() {
+        if segment.start_height >= segment.end_height {
+            return Err(StdError::generic_err(
+                "Segment must end after its start height",
+            ));
+        }
+
+        if segment.start_height < prev_end_height {
+            return Err(StdError::generic_err(
+                "Segments must be sorted and must not overlap",
+            ));
+        }
+
+        if segment.emission_floor > segment.emission_cap {
+            return Err(StdError::generic_err(
+                "Emission floor cannot be bigger than the emission cap",
+            ));
+        }
+
+        if segment.decay > Decimal256::one() {
+            return Err(StdError::generic_err("Decay cannot be bigger than one"));
+        }
+
+        prev_end_height = segment.end_height;
+    }
+
+    store_emission_schedule(&mut deps.storage, &schedule)?;
+    Ok(HandleResponse::default())
+}
+
 pub fn query<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
@@ -101,13 +148,18 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             target_deposit_rate,
             threshold_deposit_rate,
             current_emission_rate,
+            block_height,
         } => to_binary(&query_anc_emission_rate(
             deps,
             deposit_rate,
             target_deposit_rate,
             threshold_deposit_rate,
             current_emission_rate,
+            block_height,
         )?),
+        QueryMsg::ActiveEmissionSegment { block_height } => {
+            to_binary(&query_active_emission_segment(deps, block_height)?)
+        }
     }
 }
 
@@ -132,8 +184,16 @@ fn query_anc_emission_rate<S: Storage, A: Api, Q: Querier>(
     target_deposit_rate: Decimal256,
     threshold_deposit_rate: Decimal256,
     current_emission_rate: Decimal256,
+    block_height: Option<u64>,
 ) -> StdResult<AncEmissionRateResponse> {
     let config: Config = read_config(&deps.storage)?;
+    let (emission_cap, emission_floor) = match block_height {
+        Some(block_height) => {
+            let res = query_active_emission_segment(deps, block_height)?;
+            (res.emission_cap, res.emission_floor)
+        }
+        None => (config.emission_cap, config.emission_floor),
+    };
 
     let half_dec = Decimal256::one() + Decimal256::one();
     let mid_rate = (threshold_deposit_rate + target_deposit_rate) / half_dec;
@@ -148,10 +208,10 @@ fn query_anc_emission_rate<S: Storage, A: Api, Q: Querier>(
         current_emission_rate
     };
 
-    let emission_rate = if emission_rate > config.emission_cap {
-        config.emission_cap
-    } else if emission_rate < config.emission_floor {
-        config.emission_floor
+    let emission_rate = if emission_rate > emission_cap {
+        emission_cap
+    } else if emission_rate < emission_floor {
+        emission_floor
     } else {
         emission_rate
     };
@@ -159,6 +219,40 @@ fn query_anc_emission_rate<S: Storage, A: Api, Q: Querier>(
     Ok(AncEmissionRateResponse { emission_rate })
 }
 
+fn query_active_emission_segment<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<ActiveEmissionSegmentResponse> {
+    let segment = read_emission_schedule(&deps.storage)?
+        .into_iter()
+        .find(|s| s.start_height <= block_height && block_height < s.end_height);
+
+    let (emission_cap, emission_floor) = match &segment {
+        Some(segment) => {
+            // decayed = value * (1 - decay * elapsed / duration)
+            let progress = Decimal256::from_ratio(
+                block_height - segment.start_height,
+                segment.end_height - segment.start_height,
+            );
+            let factor = Decimal256::one() - segment.decay * progress;
+            (
+                segment.emission_cap * factor,
+                segment.emission_floor * factor,
+            )
+        }
+        None => {
+            let config: Config = read_config(&deps.storage)?;
+            (config.emission_cap, config.emission_floor)
+        }
+    };
+
+    Ok(ActiveEmissionSegmentResponse {
+        segment,
+        emission_cap,
+        emission_floor,
+    })
+}
+
 pub fn migrate<S: Storage, A: Api, Q: Querier>(
     _deps: &mut Extern<S, A, Q>,
     _env: Env,
@@ -277,6 +371,7 @@ mod tests {
             Decimal256::percent(10),
             Decimal256::percent(5),
             Decimal256::from_uint256(99u128),
+            None,
         )
         .unwrap();
         assert_eq!("99", &value.emission_rate.to_string());
@@ -288,6 +383,7 @@ mod tests {
             Decimal256::percent(10),
             Decimal256::percent(5),
             Decimal256::from_uint256(80u128),
+            None,
         )
         .unwrap();
         assert_eq!("88", &value.emission_rate.to_string());
@@ -299,6 +395,7 @@ mod tests {
             Decimal256::percent(10),
             Decimal256::percent(5),
             Decimal256::from_uint256(99u128),
+            None,
         )
         .unwrap();
         assert_eq!("100", &value.emission_rate.to_string());
@@ -309,6 +406,7 @@ mod tests {
             Decimal256::percent(10),
             Decimal256::percent(5),
             Decimal256::from_uint256(99u128),
+            None,
         )
         .unwrap();
         assert_eq!("89.1", &value.emission_rate.to_string());
@@ -320,8 +418,82 @@ mod tests {
             Decimal256::percent(10),
             Decimal256::percent(5),
             Decimal256::from_uint256(11u128),
+            None,
         )
         .unwrap();
         assert_eq!("10", &value.emission_rate.to_string());
     }
+
+    #[test]
+    fn emission_schedule() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            emission_cap: Decimal256::from_uint256(100u64),
+            emission_floor: Decimal256::from_uint256(10u64),
+            increment_multiplier: Decimal256::percent(110),
+            decrement_multiplier: Decimal256::percent(90),
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let segment = EmissionSegment {
+            start_height: 100,
+            end_height: 200,
+            emission_cap: Decimal256::from_uint256(80u64),
+            emission_floor: Decimal256::from_uint256(20u64),
+            decay: Decimal256::percent(50),
+        };
+
+        let msg = HandleMsg::UpdateEmissionSchedule {
+            schedule: vec![segment.clone()],
+        };
+        match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let overlapping_msg = HandleMsg::UpdateEmissionSchedule {
+            schedule: vec![
+                segment.clone(),
+                EmissionSegment {
+                    start_height: 150,
+                    end_height: 300,
+                    ..segment.clone()
+                },
+            ],
+        };
+        match handle(&mut deps, mock_env("owner0000", &[]), overlapping_msg) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Segments must be sorted and must not overlap")
+            }
+            _ => panic!("Must return overlap error"),
+        }
+
+        handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+        // halfway through the segment, decayed by 25%
+        let value = query_active_emission_segment(&deps, 150).unwrap();
+        assert_eq!(value.segment, Some(segment));
+        assert_eq!("60", &value.emission_cap.to_string());
+        assert_eq!("15", &value.emission_floor.to_string());
+
+        let value = query_anc_emission_rate(
+            &deps,
+            Decimal256::percent(7),
+            Decimal256::percent(10),
+            Decimal256::percent(5),
+            Decimal256::from_uint256(99u128),
+            Some(150),
+        )
+        .unwrap();
+        assert_eq!("60", &value.emission_rate.to_string());
+
+        // outside of the schedule the config applies
+        let value = query_active_emission_segment(&deps, 200).unwrap();
+        assert_eq!(value.segment, None);
+        assert_eq!("100", &value.emission_cap.to_string());
+    }
 }
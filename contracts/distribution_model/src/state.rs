@@ -4,8 +4,10 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::distribution_model::EmissionSegment;
 
 static KEY_CONFIG: &[u8] = b"config";
+static KEY_EMISSION_SCHEDULE: &[u8] = b"emission_schedule";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -23,3 +25,16 @@ pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<(
 pub fn read_config<S: Storage>(storage: &S) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
+
+pub fn store_emission_schedule<S: Storage>(
+    storage: &mut S,
+    schedule: &[EmissionSegment],
+) -> StdResult<()> {
+    singleton(storage, KEY_EMISSION_SCHEDULE).save(&schedule.to_vec())
+}
+
+pub fn read_emission_schedule<S: Storage>(storage: &S) -> StdResult<Vec<EmissionSegment>> {
+    Ok(singleton_read(storage, KEY_EMISSION_SCHEDULE)
+        .may_load()?
+        .unwrap_or_default())
+}
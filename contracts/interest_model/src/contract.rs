@@ -7,20 +7,32 @@ use cosmwasm_std::{
     Querier, StdError, StdResult, Storage,
 };
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+    BacktestRatesResponse, BorrowRateResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg,
+    RateModel,
 };
 
+/// Maximum number of utilization points a backtest can evaluate
+const MAX_BACKTEST_POINTS: usize = 100;
+
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     _env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    let rate_model = msg.rate_model.unwrap_or_default();
+    assert_rate_model(&rate_model)?;
+    assert_borrow_rate_bounds(msg.min_borrow_rate, msg.max_borrow_rate)?;
+
     store_config(
         &mut deps.storage,
         &Config {
             owner: deps.api.canonical_address(&msg.owner)?,
             base_rate: msg.base_rate,
             interest_multiplier: msg.interest_multiplier,
+            rate_model,
+            min_borrow_rate: msg.min_borrow_rate,
+            max_borrow_rate: msg.max_borrow_rate,
+            use_smoothed_utilization: msg.use_smoothed_utilization.unwrap_or(false),
         },
     )?;
 
@@ -37,16 +49,38 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             owner,
             base_rate,
             interest_multiplier,
-        } => update_config(deps, env, owner, base_rate, interest_multiplier),
+            rate_model,
+            min_borrow_rate,
+            max_borrow_rate,
+            clear_borrow_rate_bounds,
+            use_smoothed_utilization,
+        } => update_config(
+            deps,
+            env,
+            owner,
+            base_rate,
+            interest_multiplier,
+            rate_model,
+            min_borrow_rate,
+            max_borrow_rate,
+            clear_borrow_rate_bounds,
+            use_smoothed_utilization,
+        ),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     owner: Option<HumanAddr>,
     base_rate: Option<Decimal256>,
     interest_multiplier: Option<Decimal256>,
+    rate_model: Option<RateModel>,
+    min_borrow_rate: Option<Decimal256>,
+    max_borrow_rate: Option<Decimal256>,
+    clear_borrow_rate_bounds: Option<bool>,
+    use_smoothed_utilization: Option<bool>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
     if deps.api.canonical_address(&env.message.sender)? != config.owner {
@@ -65,6 +99,30 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.interest_multiplier = interest_multiplier;
     }
 
+    if let Some(rate_model) = rate_model {
+        assert_rate_model(&rate_model)?;
+        config.rate_model = rate_model;
+    }
+
+    if clear_borrow_rate_bounds.unwrap_or(false) {
+        config.min_borrow_rate = None;
+        config.max_borrow_rate = None;
+    }
+
+    if min_borrow_rate.is_some() {
+        config.min_borrow_rate = min_borrow_rate;
+    }
+
+    if max_borrow_rate.is_some() {
+        config.max_borrow_rate = max_borrow_rate;
+    }
+
+    assert_borrow_rate_bounds(config.min_borrow_rate, config.max_borrow_rate)?;
+
+    if let Some(use_smoothed_utilization) = use_smoothed_utilization {
+        config.use_smoothed_utilization = use_smoothed_utilization;
+    }
+
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse::default())
 }
@@ -79,12 +137,17 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             market_balance,
             total_liabilities,
             total_reserves,
+            smoothed_utilization,
         } => to_binary(&query_borrow_rate(
             deps,
             market_balance,
             total_liabilities,
             total_reserves,
+            smoothed_utilization,
         )?),
+        QueryMsg::BacktestRates { utilizations } => {
+            to_binary(&query_backtest_rates(deps, utilizations)?)
+        }
     }
 }
 
@@ -96,6 +159,10 @@ fn query_config<S: Storage, A: Api, Q: Querier>(
         owner: deps.api.human_address(&state.owner)?,
         base_rate: state.base_rate,
         interest_multiplier: state.interest_multiplier,
+        rate_model: state.rate_model,
+        min_borrow_rate: state.min_borrow_rate,
+        max_borrow_rate: state.max_borrow_rate,
+        use_smoothed_utilization: state.use_smoothed_utilization,
     };
 
     Ok(resp)
@@ -106,9 +173,18 @@ fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
     market_balance: Uint256,
     total_liabilities: Decimal256,
     total_reserves: Decimal256,
+    smoothed_utilization: Option<Decimal256>,
 ) -> StdResult<BorrowRateResponse> {
     let config: Config = read_config(&deps.storage)?;
 
+    if config.use_smoothed_utilization {
+        if let Some(smoothed_utilization) = smoothed_utilization {
+            return Ok(BorrowRateResponse {
+                rate: compute_borrow_rate(&config, smoothed_utilization),
+            });
+        }
+    }
+
     // ignore decimal parts
     let total_value_in_market =
         Decimal256::from_uint256(market_balance) + total_liabilities - total_reserves;
@@ -120,15 +196,99 @@ fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
     };
 
     Ok(BorrowRateResponse {
-        rate: utilization_ratio * config.interest_multiplier + config.base_rate,
+        rate: compute_borrow_rate(&config, utilization_ratio),
+    })
+}
+
+fn query_backtest_rates<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    utilizations: Vec<Decimal256>,
+) -> StdResult<BacktestRatesResponse> {
+    if utilizations.len() > MAX_BACKTEST_POINTS {
+        return Err(StdError::generic_err(format!(
+            "Too many utilization points; max: {}",
+            MAX_BACKTEST_POINTS
+        )));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    Ok(BacktestRatesResponse {
+        rates: utilizations
+            .into_iter()
+            .map(|utilization_ratio| compute_borrow_rate(&config, utilization_ratio))
+            .collect(),
     })
 }
 
+fn compute_borrow_rate(config: &Config, utilization_ratio: Decimal256) -> Decimal256 {
+    let rate = match &config.rate_model {
+        RateModel::Linear {} => utilization_ratio * config.interest_multiplier + config.base_rate,
+        RateModel::KinkedRate {
+            base,
+            slope_1,
+            slope_2,
+            optimal_utilization,
+        } => {
+            if utilization_ratio <= *optimal_utilization {
+                *base + utilization_ratio * *slope_1
+            } else {
+                *base
+                    + *optimal_utilization * *slope_1
+                    + (utilization_ratio - *optimal_utilization) * *slope_2
+            }
+        }
+    };
+
+    // Keep the rate within the governance bounds
+    let rate = match config.max_borrow_rate {
+        Some(max_borrow_rate) => std::cmp::min(rate, max_borrow_rate),
+        None => rate,
+    };
+
+    match config.min_borrow_rate {
+        Some(min_borrow_rate) => std::cmp::max(rate, min_borrow_rate),
+        None => rate,
+    }
+}
+
+fn assert_borrow_rate_bounds(
+    min_borrow_rate: Option<Decimal256>,
+    max_borrow_rate: Option<Decimal256>,
+) -> StdResult<()> {
+    if let (Some(min_borrow_rate), Some(max_borrow_rate)) = (min_borrow_rate, max_borrow_rate) {
+        if min_borrow_rate > max_borrow_rate {
+            return Err(StdError::generic_err(
+                "Min borrow rate cannot exceed max borrow rate",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn assert_rate_model(rate_model: &RateModel) -> StdResult<()> {
+    if let RateModel::KinkedRate {
+        optimal_utilization,
+        ..
+    } = rate_model
+    {
+        if *optimal_utilization == Decimal256::zero() || *optimal_utilization > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Optimal utilization must be bigger than zero and not exceed one",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::StdError;
+    use cosmwasm_std::{CanonicalAddr, StdError};
+    use cosmwasm_storage::singleton;
+    use serde::{Deserialize, Serialize};
 
     #[test]
     fn proper_initialization() {
@@ -138,6 +298,10 @@ mod tests {
             owner: HumanAddr("owner0000".to_string()),
             base_rate: Decimal256::percent(10),
             interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            use_smoothed_utilization: None,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -157,6 +321,7 @@ mod tests {
             Uint256::from(1000000u128),
             Decimal256::from_uint256(500000u128),
             Decimal256::from_uint256(100000u128),
+            None,
         )
         .unwrap();
         // utilization_ratio = 0.35714285714285714
@@ -168,11 +333,312 @@ mod tests {
             Uint256::zero(),
             Decimal256::zero(),
             Decimal256::zero(),
+            None,
         )
         .unwrap();
         assert_eq!("0.1", &value.rate.to_string());
     }
 
+    #[test]
+    fn backtest_rates() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            use_smoothed_utilization: None,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let value = query_backtest_rates(
+            &deps,
+            vec![
+                Decimal256::zero(),
+                Decimal256::percent(50),
+                Decimal256::one(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            value.rates,
+            vec![
+                Decimal256::percent(10),
+                Decimal256::percent(15),
+                Decimal256::percent(20),
+            ]
+        );
+
+        let res = query_backtest_rates(&deps, vec![Decimal256::zero(); MAX_BACKTEST_POINTS + 1]);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Too many utilization points; max: 100")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    #[test]
+    fn smoothed_utilization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            use_smoothed_utilization: None,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        // spot utilization drives the rate until enabled
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(500000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::zero(),
+            Some(Decimal256::percent(10)),
+        )
+        .unwrap();
+        assert_eq!(value.rate, Decimal256::percent(15));
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: Some(true),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+        assert!(query_config(&deps).unwrap().use_smoothed_utilization);
+
+        // utilization_ratio = 0.1
+        // borrow_rate = 0.01 + 0.1
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(500000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::zero(),
+            Some(Decimal256::percent(10)),
+        )
+        .unwrap();
+        assert_eq!(value.rate, Decimal256::percent(11));
+
+        // markets which do not report it keep the spot utilization
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(500000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::zero(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(value.rate, Decimal256::percent(15));
+    }
+
+    #[test]
+    fn kinked_rate() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            use_smoothed_utilization: None,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: Some(RateModel::KinkedRate {
+                base: Decimal256::percent(2),
+                slope_1: Decimal256::percent(10),
+                slope_2: Decimal256::one(),
+                optimal_utilization: Decimal256::zero(),
+            }),
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: None,
+        };
+        let res = handle(&mut deps, env.clone(), msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                "Optimal utilization must be bigger than zero and not exceed one"
+            ),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: Some(RateModel::KinkedRate {
+                base: Decimal256::percent(2),
+                slope_1: Decimal256::percent(10),
+                slope_2: Decimal256::one(),
+                optimal_utilization: Decimal256::percent(80),
+            }),
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        // below the kink: 0.02 + 0.5 * 0.1
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(500000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::zero(),
+            None,
+        )
+        .unwrap();
+        assert_eq!("0.07", &value.rate.to_string());
+
+        // past the kink: 0.02 + 0.8 * 0.1 + 0.1 * 1
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(100000u128),
+            Decimal256::from_uint256(900000u128),
+            Decimal256::zero(),
+            None,
+        )
+        .unwrap();
+        assert_eq!("0.2", &value.rate.to_string());
+    }
+
+    #[test]
+    fn borrow_rate_bounds() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_rate: Decimal256::percent(10),
+            interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: Some(Decimal256::percent(12)),
+            max_borrow_rate: Some(Decimal256::percent(15)),
+            use_smoothed_utilization: None,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let value = query_backtest_rates(
+            &deps,
+            vec![
+                Decimal256::zero(),
+                Decimal256::percent(30),
+                Decimal256::one(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            value.rates,
+            vec![
+                Decimal256::percent(12),
+                Decimal256::percent(13),
+                Decimal256::percent(15),
+            ]
+        );
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: None,
+            min_borrow_rate: Some(Decimal256::percent(20)),
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Min borrow rate cannot exceed max borrow rate")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        // clear both bounds
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: Some(true),
+            use_smoothed_utilization: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let value = query_config(&deps).unwrap();
+        assert_eq!(None, value.min_borrow_rate);
+        assert_eq!(None, value.max_borrow_rate);
+
+        let value = query_backtest_rates(
+            &deps,
+            vec![
+                Decimal256::zero(),
+                Decimal256::percent(30),
+                Decimal256::one(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            value.rates,
+            vec![
+                Decimal256::percent(10),
+                Decimal256::percent(13),
+                Decimal256::percent(20),
+            ]
+        );
+
+        // a bound given with the clear flag is kept
+        let msg = HandleMsg::UpdateConfig {
+            owner: None,
+            base_rate: None,
+            interest_multiplier: None,
+            rate_model: None,
+            min_borrow_rate: Some(Decimal256::percent(20)),
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: Some(true),
+            use_smoothed_utilization: None,
+        };
+        handle(&mut deps, env, msg).unwrap();
+
+        let value = query_config(&deps).unwrap();
+        assert_eq!(Some(Decimal256::percent(20)), value.min_borrow_rate);
+        assert_eq!(None, value.max_borrow_rate);
+    }
+
     #[test]
     fn update_config() {
         let mut deps = mock_dependencies(20, &[]);
@@ -181,6 +647,10 @@ mod tests {
             owner: HumanAddr("owner0000".to_string()),
             base_rate: Decimal256::percent(10),
             interest_multiplier: Decimal256::percent(10),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            use_smoothed_utilization: None,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -192,6 +662,11 @@ mod tests {
             owner: Some(HumanAddr("owner0001".to_string())),
             base_rate: None,
             interest_multiplier: None,
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: None,
         };
 
         let res = handle(&mut deps, env, msg).unwrap();
@@ -209,6 +684,11 @@ mod tests {
             owner: None,
             base_rate: Some(Decimal256::percent(1)),
             interest_multiplier: Some(Decimal256::percent(1)),
+            rate_model: None,
+            min_borrow_rate: None,
+            max_borrow_rate: None,
+            clear_borrow_rate_bounds: None,
+            use_smoothed_utilization: None,
         };
 
         let res = handle(&mut deps, env, msg);
@@ -217,4 +697,46 @@ mod tests {
             _ => panic!("Must return unauthorized error"),
         }
     }
+
+    #[test]
+    fn load_legacy_config() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        // config stored before the rate models were added
+        #[derive(Serialize, Deserialize)]
+        struct LegacyConfig {
+            owner: CanonicalAddr,
+            base_rate: Decimal256,
+            interest_multiplier: Decimal256,
+        }
+
+        let owner = deps
+            .api
+            .canonical_address(&HumanAddr::from("owner0000"))
+            .unwrap();
+        singleton(&mut deps.storage, b"config")
+            .save(&LegacyConfig {
+                owner,
+                base_rate: Decimal256::percent(10),
+                interest_multiplier: Decimal256::percent(10),
+            })
+            .unwrap();
+
+        let value = query_config(&deps).unwrap();
+        assert_eq!("owner0000", value.owner.as_str());
+        assert_eq!(RateModel::Linear {}, value.rate_model);
+        assert_eq!(None, value.min_borrow_rate);
+        assert_eq!(None, value.max_borrow_rate);
+        assert!(!value.use_smoothed_utilization);
+
+        let value = query_borrow_rate(
+            &deps,
+            Uint256::from(1000000u128),
+            Decimal256::from_uint256(500000u128),
+            Decimal256::from_uint256(100000u128),
+            None,
+        )
+        .unwrap();
+        assert_eq!("0.135714285714285714", &value.rate.to_string());
+    }
 }
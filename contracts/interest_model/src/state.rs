@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::interest_model::RateModel;
 
 static KEY_CONFIG: &[u8] = b"config";
 
@@ -12,6 +13,15 @@ pub struct Config {
     pub owner: CanonicalAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    // configs stored before the rate models load as linear and unbounded
+    #[serde(default)]
+    pub rate_model: RateModel,
+    #[serde(default)]
+    pub min_borrow_rate: Option<Decimal256>,
+    #[serde(default)]
+    pub max_borrow_rate: Option<Decimal256>,
+    #[serde(default)]
+    pub use_smoothed_utilization: bool,
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
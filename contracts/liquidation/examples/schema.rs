@@ -4,8 +4,14 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::liquidation::{
-    BidResponse, BidsResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg,
-    LiquidationAmountResponse, QueryMsg,
+    AccruedFeesResponse, AllowlistResponse, BidMigrationResponse, BidResponse, BidderStatsResponse,
+    BidsResponse, BlacklistResponse, ClaimAsResponse, CollateralInfoResponse,
+    CollateralStatusResponse, ConfigResponse, ConverterHookMsg, ConverterResponse,
+    CrossBidResponse, Cw20HookMsg, DeprecatedCollateralsResponse, ExportBidsResponse,
+    ExportCrossBidsResponse, HandleMsg, InitMsg, InterfaceVersionResponse, InvariantsResponse,
+    LiquidationAmountResponse, LiquidationHistoryResponse, MigrateMsg, PendingConfigResponse,
+    PendingRewardsResponse, QueryMsg, ReferralFeesResponse, SimulateLiquidationResponse,
+    SweptDustResponse,
 };
 
 fn main() {
@@ -17,9 +23,32 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(ConverterHookMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(PendingConfigResponse), &out_dir);
+    export_schema(&schema_for!(InterfaceVersionResponse), &out_dir);
     export_schema(&schema_for!(BidResponse), &out_dir);
     export_schema(&schema_for!(BidsResponse), &out_dir);
+    export_schema(&schema_for!(CrossBidResponse), &out_dir);
+    export_schema(&schema_for!(ExportBidsResponse), &out_dir);
+    export_schema(&schema_for!(ExportCrossBidsResponse), &out_dir);
     export_schema(&schema_for!(LiquidationAmountResponse), &out_dir);
+    export_schema(&schema_for!(SimulateLiquidationResponse), &out_dir);
+    export_schema(&schema_for!(InvariantsResponse), &out_dir);
+    export_schema(&schema_for!(SweptDustResponse), &out_dir);
+    export_schema(&schema_for!(ReferralFeesResponse), &out_dir);
+    export_schema(&schema_for!(AccruedFeesResponse), &out_dir);
+    export_schema(&schema_for!(BidderStatsResponse), &out_dir);
+    export_schema(&schema_for!(BlacklistResponse), &out_dir);
+    export_schema(&schema_for!(AllowlistResponse), &out_dir);
+    export_schema(&schema_for!(DeprecatedCollateralsResponse), &out_dir);
+    export_schema(&schema_for!(LiquidationHistoryResponse), &out_dir);
+    export_schema(&schema_for!(CollateralStatusResponse), &out_dir);
+    export_schema(&schema_for!(CollateralInfoResponse), &out_dir);
+    export_schema(&schema_for!(ConverterResponse), &out_dir);
+    export_schema(&schema_for!(ClaimAsResponse), &out_dir);
+    export_schema(&schema_for!(PendingRewardsResponse), &out_dir);
+    export_schema(&schema_for!(BidMigrationResponse), &out_dir);
 }
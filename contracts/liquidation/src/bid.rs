@@ -1,52 +1,126 @@
+use crate::converter::collateral_payout_msg;
+use crate::error::ContractError;
+use crate::incentive::update_bid_rewards;
 use crate::state::{
-    read_bid, read_bids_by_collateral, read_bids_by_user, read_config, remove_bid, store_bid, Bid,
-    Config,
+    is_allowed, is_blacklisted, is_collateral_deprecated, is_collateral_paused, page_limit,
+    read_accrued_fees, read_all_bidder_stats, read_bid, read_bid_count, read_bid_migration,
+    read_bidder_stats, read_bids, read_bids_by_collateral, read_bids_by_user,
+    read_collateral_decimals, read_config, read_cross_bid, read_cross_bids, read_dust_bids,
+    read_expired_bids, read_fill_window, read_liquidation_records, read_pause_info,
+    read_price_bounds, read_price_source, read_referral_fees, read_staged_collateral,
+    read_swept_dust, remove_bid, remove_cross_bid, store_accrued_fees, store_bid,
+    store_bid_migration, store_bidder_stats, store_cross_bid, store_fill_window,
+    store_liquidation_record, store_referral_fees, store_staged_collateral, store_swept_dust,
+    AccruedFees, Bid, BidderStats, Config, CrossBid, FillWindow, LiquidationRecord, ReferralFees,
 };
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse, HumanAddr,
+    LogAttribute, Querier, StdResult, Storage, Uint128, WasmMsg,
+};
+use moneymarket::decimals::normalize_amount;
+use moneymarket::liquidation::{
+    BidMigrationResponse, BidResponse, BidderCollateralStats, BidderStatsResponse, BidsResponse,
+    CrossBidResponse, ExportBidsResponse, ExportCrossBidsResponse, ExportedBid, InvariantsResponse,
+    LiquidationHistoryResponse, LiquidationQueueHandleMsg, LiquidationRecordResponse,
+    SimulateLiquidationResponse, SweptDustResponse, LIQUIDATION_SCHEMA_VERSION,
 };
-use cw20::Cw20HandleMsg;
-use moneymarket::liquidation::{BidResponse, BidsResponse};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::querier::{deduct_tax, query_price, TimeConstraints};
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::{deduct_tax, query_price_with_mode};
+use moneymarket::rounding::{self, Rounding};
+use std::convert::TryFrom;
 
+#[allow(clippy::too_many_arguments)]
 pub fn submit_bid<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     collateral_token: HumanAddr,
     premium_rate: Decimal256,
-) -> HandleResult {
+    expires_at: Option<u64>,
+    referrer: Option<HumanAddr>,
+    bid_for: Option<HumanAddr>,
+    unlock_receiver: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(ContractError::DepositsPaused {});
+    }
+
+    if read_bid_migration(&deps.storage)?.is_some() {
+        return Err(ContractError::BidsMigrating {});
+    }
+
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time {
+            return Err(ContractError::InvalidBidExpiry {});
+        }
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let bidder = match bid_for {
+        Some(bid_for) if bid_for != env.message.sender => {
+            if !config.delegated_bids_enabled {
+                return Err(ContractError::DelegatedBidsDisabled {});
+            }
+
+            let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+            if is_blacklisted(&deps.storage, &sender_raw)? {
+                return Err(ContractError::Blacklisted {
+                    address: env.message.sender.clone(),
+                });
+            }
+
+            bid_for
+        }
+        _ => env.message.sender.clone(),
+    };
+
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
-    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let bidder_raw = deps.api.canonical_address(&bidder)?;
+    if is_blacklisted(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::Blacklisted {
+            address: bidder.clone(),
+        });
+    }
+
+    if !is_allowed(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::NotAllowlisted {
+            address: bidder.clone(),
+        });
+    }
+
+    if is_collateral_deprecated(&deps.storage, &collateral_token_raw)? {
+        return Err(ContractError::CollateralDeprecated {
+            collateral_token: collateral_token.clone(),
+        });
+    }
+
     if read_bid(&deps.storage, &bidder_raw, &collateral_token_raw).is_ok() {
-        return Err(StdError::generic_err(format!(
-            "User already has bid for specified collateral: {}",
-            collateral_token
-        )));
+        return Err(ContractError::BidAlreadyExists {
+            collateral_token: collateral_token.clone(),
+        });
     }
 
-    let config: Config = read_config(&deps.storage)?;
     if config.max_premium_rate < premium_rate {
-        return Err(StdError::generic_err(format!(
-            "Premium rate cannot exceed the max premium rate: {}",
-            config.max_premium_rate
-        )));
+        return Err(ContractError::PremiumRateTooHigh {
+            max_premium_rate: config.max_premium_rate,
+        });
     }
 
+    let unlock_receiver_raw = match &unlock_receiver {
+        Some(unlock_receiver) => Some(assert_unlock_receiver(deps, unlock_receiver)?),
+        None => None,
+    };
+
     let amount: Uint256 = Uint256::from(
         env.message
             .sent_funds
             .iter()
             .find(|c| c.denom == config.stable_denom)
             .map(|c| c.amount)
-            .ok_or_else(|| {
-                StdError::generic_err(format!(
-                    "No {} assets have been provided",
-                    config.stable_denom
-                ))
+            .ok_or_else(|| ContractError::NoStableProvided {
+                denom: config.stable_denom.clone(),
             })?,
     );
 
@@ -57,16 +131,45 @@ pub fn submit_bid<S: Storage, A: Api, Q: Querier>(
         Bid {
             amount,
             premium_rate,
+            expires_at,
+            referrer: match referrer {
+                Some(referrer) => Some(deps.api.canonical_address(&referrer)?),
+                None => None,
+            },
+            unlock_receiver: unlock_receiver_raw,
         },
     )?;
 
+    let mut stats: BidderStats =
+        read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    let active_before = stats.active_bid_amount;
+    stats.active_bid_amount += amount;
+    store_bidder_stats(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        &stats,
+    )?;
+    update_bid_rewards(
+        &mut deps.storage,
+        env.block.time,
+        &bidder_raw,
+        &collateral_token_raw,
+        active_before,
+        stats.active_bid_amount,
+    )?;
+
     Ok(HandleResponse {
         messages: vec![],
-        log: vec![
-            log("action", "submit_bid"),
-            log("collateral_token", collateral_token),
-            log("amount", amount),
-        ],
+        log: [
+            vec![
+                log("action", "submit_bid"),
+                log("collateral_token", collateral_token),
+                log("amount", amount),
+            ],
+            bid_logs(&bidder, premium_rate, Uint256::zero(), amount),
+        ]
+        .concat(),
         data: None,
     })
 }
@@ -76,22 +179,32 @@ pub fn retract_bid<S: Storage, A: Api, Q: Querier>(
     env: Env,
     collateral_token: HumanAddr,
     amount: Option<Uint256>,
-) -> HandleResult {
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(ContractError::WithdrawalsPaused {});
+    }
+
     let config: Config = read_config(&deps.storage)?;
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
     let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
-    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)
+        .map_err(|_| ContractError::BidNotFound {})?;
 
     let amount = amount.unwrap_or(bid.amount);
     if amount > bid.amount {
-        return Err(StdError::generic_err(format!(
-            "Retract amount cannot exceed bid balance: {}",
-            bid.amount
-        )));
+        return Err(ContractError::RetractExceedsBid {
+            bid_amount: bid.amount,
+        });
     }
 
+    let logs = bid_logs(
+        &env.message.sender,
+        bid.premium_rate,
+        bid.amount,
+        bid.amount - amount,
+    );
     if amount == bid.amount {
-        remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw);
+        remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw)?;
     } else {
         store_bid(
             &mut deps.storage,
@@ -104,171 +217,1361 @@ pub fn retract_bid<S: Storage, A: Api, Q: Querier>(
         )?;
     }
 
+    let mut stats: BidderStats =
+        read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    let active_before = stats.active_bid_amount;
+    stats.active_bid_amount = saturating_sub(stats.active_bid_amount, amount);
+    store_bidder_stats(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        &stats,
+    )?;
+    update_bid_rewards(
+        &mut deps.storage,
+        env.block.time,
+        &bidder_raw,
+        &collateral_token_raw,
+        active_before,
+        stats.active_bid_amount,
+    )?;
+
     Ok(HandleResponse {
-        messages: vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: env.message.sender.clone(),
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom,
-                    amount: amount.into(),
-                },
-            )?],
-        })],
-        log: vec![
-            log("action", "retract_bid"),
-            log("collateral_token", collateral_token),
-            log("bidder", env.message.sender),
-            log("amount", amount),
-        ],
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            env.message.sender.clone(),
+            config.stable_denom,
+            amount,
+        )?],
+        log: [
+            vec![
+                log("action", "retract_bid"),
+                log("collateral_token", collateral_token),
+                log("amount", amount),
+            ],
+            logs,
+        ]
+        .concat(),
         data: None,
     })
 }
 
-pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
+pub fn submit_cross_bid<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    liquidator: HumanAddr,
-    repay_address: HumanAddr,
-    fee_address: HumanAddr,
-    collateral_token: HumanAddr,
-    amount: Uint256,
-) -> HandleResult {
+    collateral_tokens: Vec<HumanAddr>,
+    premium_rate: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(ContractError::DepositsPaused {});
+    }
+
+    if read_bid_migration(&deps.storage)?.is_some() {
+        return Err(ContractError::BidsMigrating {});
+    }
+
+    if collateral_tokens.is_empty() {
+        return Err(ContractError::NoCollateralsToBid {});
+    }
+
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    if is_blacklisted(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::Blacklisted {
+            address: env.message.sender.clone(),
+        });
+    }
+
+    if !is_allowed(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::NotAllowlisted {
+            address: env.message.sender.clone(),
+        });
+    }
+
+    if read_cross_bid(&deps.storage, &bidder_raw)?.is_some() {
+        return Err(ContractError::CrossBidAlreadyExists {});
+    }
+
+    let mut collateral_tokens_raw: Vec<CanonicalAddr> = vec![];
+    for collateral_token in collateral_tokens.iter() {
+        let collateral_token_raw = deps.api.canonical_address(collateral_token)?;
+        if collateral_tokens_raw.contains(&collateral_token_raw) {
+            return Err(ContractError::DuplicateCollateral {
+                collateral_token: collateral_token.clone(),
+            });
+        }
+
+        if is_collateral_deprecated(&deps.storage, &collateral_token_raw)? {
+            return Err(ContractError::CollateralDeprecated {
+                collateral_token: collateral_token.clone(),
+            });
+        }
+
+        collateral_tokens_raw.push(collateral_token_raw);
+    }
+
     let config: Config = read_config(&deps.storage)?;
-    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
-    let bidder_raw = deps.api.canonical_address(&liquidator)?;
-    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    if config.max_premium_rate < premium_rate {
+        return Err(ContractError::PremiumRateTooHigh {
+            max_premium_rate: config.max_premium_rate,
+        });
+    }
 
-    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
-    let price: PriceResponse = query_price(
-        &deps,
-        &oracle_contract,
-        collateral_token.to_string(),
-        config.stable_denom.clone(),
-        Some(TimeConstraints {
-            block_time: env.block.time,
-            valid_timeframe: config.price_timeframe,
-        }),
+    let amount: Uint256 = Uint256::from(
+        env.message
+            .sent_funds
+            .iter()
+            .find(|c| c.denom == config.stable_denom)
+            .map(|c| c.amount)
+            .ok_or_else(|| ContractError::NoStableProvided {
+                denom: config.stable_denom.clone(),
+            })?,
+    );
+
+    store_cross_bid(
+        &mut deps.storage,
+        &bidder_raw,
+        &CrossBid {
+            collateral_tokens: collateral_tokens_raw,
+            amount,
+            premium_rate,
+        },
     )?;
 
-    let collateral_value = amount * price.rate;
-    let required_stable = collateral_value
-        * (Decimal256::one() - std::cmp::min(bid.premium_rate, config.max_premium_rate));
-    if required_stable > bid.amount {
-        return Err(StdError::generic_err(format!(
-            "Insufficient bid balance; Required balance: {}",
-            required_stable
-        )));
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "submit_cross_bid"),
+                log("collateral_count", collateral_tokens.len()),
+                log("amount", amount),
+            ],
+            bid_logs(&env.message.sender, premium_rate, Uint256::zero(), amount),
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn retract_cross_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Option<Uint256>,
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(ContractError::WithdrawalsPaused {});
     }
 
-    // Update bid
-    if bid.amount == required_stable {
-        remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw);
+    let config: Config = read_config(&deps.storage)?;
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let cross_bid: CrossBid = match read_cross_bid(&deps.storage, &bidder_raw)? {
+        Some(v) => v,
+        None => return Err(ContractError::CrossBidNotFound {}),
+    };
+
+    let amount = amount.unwrap_or(cross_bid.amount);
+    if amount > cross_bid.amount {
+        return Err(ContractError::RetractExceedsBid {
+            bid_amount: cross_bid.amount,
+        });
+    }
+
+    let logs = bid_logs(
+        &env.message.sender,
+        cross_bid.premium_rate,
+        cross_bid.amount,
+        cross_bid.amount - amount,
+    );
+    if amount == cross_bid.amount {
+        remove_cross_bid(&mut deps.storage, &bidder_raw);
     } else {
-        store_bid(
+        store_cross_bid(
             &mut deps.storage,
             &bidder_raw,
-            &collateral_token_raw,
-            Bid {
-                amount: bid.amount - required_stable,
-                ..bid
+            &CrossBid {
+                amount: cross_bid.amount - amount,
+                ..cross_bid
             },
         )?;
     }
 
-    let bid_fee = required_stable * config.bid_fee;
-    let repay_amount = required_stable - bid_fee;
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            env.message.sender.clone(),
+            config.stable_denom,
+            amount,
+        )?],
+        log: [
+            vec![log("action", "retract_cross_bid"), log("amount", amount)],
+            logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn transfer_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    new_bidder: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let new_bidder_raw = deps.api.canonical_address(&new_bidder)?;
+    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)
+        .map_err(|_| ContractError::BidNotFound {})?;
+
+    if is_blacklisted(&deps.storage, &new_bidder_raw)? {
+        return Err(ContractError::Blacklisted {
+            address: new_bidder.clone(),
+        });
+    }
+
+    if !is_allowed(&deps.storage, &new_bidder_raw)? {
+        return Err(ContractError::NotAllowlisted {
+            address: new_bidder.clone(),
+        });
+    }
 
-    let mut messages: Vec<CosmosMsg> = vec![
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: collateral_token.clone(),
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: liquidator,
-                amount: amount.into(),
-            })?,
-        }),
-        CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address.clone(),
-            to_address: repay_address,
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: repay_amount.into(),
-                },
-            )?],
-        }),
-    ];
+    if read_bid(&deps.storage, &new_bidder_raw, &collateral_token_raw).is_ok() {
+        return Err(ContractError::BidAlreadyExists {
+            collateral_token: collateral_token.clone(),
+        });
+    }
+
+    // the new bidder receives the collateral unless it routes it elsewhere
+    remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw)?;
+    store_bid(
+        &mut deps.storage,
+        &new_bidder_raw,
+        &collateral_token_raw,
+        Bid {
+            unlock_receiver: None,
+            ..bid.clone()
+        },
+    )?;
+
+    let mut stats: BidderStats =
+        read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    let active_before = stats.active_bid_amount;
+    stats.active_bid_amount = saturating_sub(stats.active_bid_amount, bid.amount);
+    store_bidder_stats(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        &stats,
+    )?;
+    update_bid_rewards(
+        &mut deps.storage,
+        env.block.time,
+        &bidder_raw,
+        &collateral_token_raw,
+        active_before,
+        stats.active_bid_amount,
+    )?;
+
+    let mut new_stats: BidderStats =
+        read_bidder_stats(&deps.storage, &new_bidder_raw, &collateral_token_raw)?;
+    let active_before = new_stats.active_bid_amount;
+    new_stats.active_bid_amount += bid.amount;
+    store_bidder_stats(
+        &mut deps.storage,
+        &new_bidder_raw,
+        &collateral_token_raw,
+        &new_stats,
+    )?;
+    update_bid_rewards(
+        &mut deps.storage,
+        env.block.time,
+        &new_bidder_raw,
+        &collateral_token_raw,
+        active_before,
+        new_stats.active_bid_amount,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "transfer_bid"),
+            log("collateral_token", collateral_token),
+            log("bidder", env.message.sender),
+            log("new_bidder", new_bidder),
+            log("amount", bid.amount),
+        ],
+        data: None,
+    })
+}
+
+/// Submit the outstanding bids to the liquidation queue on behalf
+/// of their bidders. Queue premium slots are whole percents up to
+/// 255, so the premium rates are rounded down and the bids rounded
+/// this way are counted in the logs
+/// Executor: owner
+pub fn migrate_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    new_liquidation_queue: HumanAddr,
+    limit: Option<u32>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_liquidation_queue_raw = deps.api.canonical_address(&new_liquidation_queue)?;
+    match read_bid_migration(&deps.storage)? {
+        Some(v) if v != new_liquidation_queue_raw => {
+            return Err(ContractError::BidsMigratingTo {
+                new_liquidation_queue: deps.api.human_address(&v)?,
+            })
+        }
+        Some(_) => {}
+        None => store_bid_migration(&mut deps.storage, &new_liquidation_queue_raw)?,
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut rounded_premium_rates: u32 = 0;
+    let bids = read_bids(&deps.storage, None, limit)?;
+    for (bidder_raw, collateral_token_raw, bid) in bids.iter() {
+        let premium_slot: Uint128 = (Uint256::from(100u64) * bid.premium_rate).into();
+        let premium_slot =
+            u8::try_from(premium_slot.u128()).map_err(|_| ContractError::PremiumSlotOverflow {
+                premium_rate: bid.premium_rate,
+            })?;
+        if Decimal256::percent(premium_slot as u64) != bid.premium_rate {
+            rounded_premium_rates += 1;
+        }
+
+        remove_bid(&mut deps.storage, bidder_raw, collateral_token_raw)?;
+
+        let mut stats: BidderStats =
+            read_bidder_stats(&deps.storage, bidder_raw, collateral_token_raw)?;
+        let active_before = stats.active_bid_amount;
+        stats.active_bid_amount = saturating_sub(stats.active_bid_amount, bid.amount);
+        store_bidder_stats(&mut deps.storage, bidder_raw, collateral_token_raw, &stats)?;
+        update_bid_rewards(
+            &mut deps.storage,
+            env.block.time,
+            bidder_raw,
+            collateral_token_raw,
+            active_before,
+            stats.active_bid_amount,
+        )?;
 
-    if !bid_fee.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: fee_address,
-            amount: vec![deduct_tax(
-                &deps,
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: new_liquidation_queue.clone(),
+            send: vec![deduct_tax(
+                deps,
                 Coin {
                     denom: config.stable_denom.clone(),
-                    amount: bid_fee.into(),
+                    amount: bid.amount.into(),
                 },
             )?],
+            msg: to_binary(&LiquidationQueueHandleMsg::SubmitBid {
+                collateral_token: deps.api.human_address(collateral_token_raw)?,
+                premium_slot,
+                bidder: Some(deps.api.human_address(bidder_raw)?),
+            })?,
         }));
     }
 
+    // the queue has no cross collateral bids; they are
+    // refunded once all the other bids are migrated
+    let cross_bids = if bids.is_empty() {
+        read_cross_bids(&deps.storage, None, limit)?
+    } else {
+        vec![]
+    };
+    for (bidder_raw, cross_bid) in cross_bids.iter() {
+        remove_cross_bid(&mut deps.storage, bidder_raw);
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            deps.api.human_address(bidder_raw)?,
+            config.stable_denom.clone(),
+            cross_bid.amount,
+        )?);
+    }
+
     Ok(HandleResponse {
         messages,
         log: vec![
-            log("action", "execute_bid"),
-            log("stable_denom", config.stable_denom),
-            log("repay_amount", repay_amount),
-            log("bid_fee", bid_fee),
-            log("collateral_token", collateral_token),
-            log("collateral_amount", amount),
+            log("action", "migrate_bids"),
+            log("new_liquidation_queue", new_liquidation_queue),
+            log("migrated_bids", bids.len()),
+            log("rounded_premium_rates", rounded_premium_rates),
+            log("refunded_cross_bids", cross_bids.len()),
         ],
         data: None,
     })
 }
 
-pub fn query_bid<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
+#[allow(clippy::too_many_arguments)]
+pub fn execute_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    liquidator: HumanAddr,
+    repay_address: HumanAddr,
+    fee_address: HumanAddr,
+    liquidator_fee_address: HumanAddr,
     collateral_token: HumanAddr,
-    bidder: HumanAddr,
-) -> StdResult<BidResponse> {
-    let bid: Bid = read_bid(
-        &deps.storage,
-        &deps.api.canonical_address(&bidder)?,
-        &deps.api.canonical_address(&collateral_token)?,
+    amount: Uint256,
+    borrower: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(ContractError::LiquidationsPaused {});
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let fill = fill_bid(
+        deps,
+        &env,
+        &config,
+        &liquidator,
+        &collateral_token,
+        amount,
+        &borrower,
     )?;
 
-    Ok(BidResponse {
-        collateral_token,
-        bidder,
-        amount: bid.amount,
-        premium_rate: bid.premium_rate,
+    let messages = payout_messages(
+        deps,
+        &env,
+        &config,
+        vec![(
+            fill.collateral_receiver.clone(),
+            collateral_token.clone(),
+            amount,
+        )],
+        repay_address,
+        fee_address,
+        liquidator_fee_address,
+        &fill,
+    )?;
+
+    Ok(HandleResponse {
+        messages,
+        log: [
+            vec![
+                log("action", "execute_bid"),
+                log("stable_denom", config.stable_denom),
+                log("repay_amount", fill.repay_amount),
+                log("bid_fee", fill.bid_fee),
+                log("liquidator_fee", fill.liquidator_fee),
+                log("collateral_token", collateral_token),
+                log("collateral_amount", amount),
+            ],
+            fill.logs,
+        ]
+        .concat(),
+        data: None,
     })
 }
 
-pub fn query_bids_by_user<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    bidder: HumanAddr,
-    start_after: Option<HumanAddr>,
-    limit: Option<u32>,
-) -> StdResult<BidsResponse> {
-    let start_after = if let Some(start_after) = start_after {
-        Some(deps.api.canonical_address(&start_after)?)
-    } else {
-        None
-    };
-
-    let bids: Vec<BidResponse> = read_bids_by_user(
-        &deps,
-        &deps.api.canonical_address(&bidder)?,
-        start_after,
-        limit,
+/// Hold the sent collateral until the operator liquidates it with ExecuteBids
+pub fn stage_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    operator: HumanAddr,
+    collateral_token: HumanAddr,
+    amount: Uint256,
+) -> Result<HandleResponse, ContractError> {
+    let operator_raw = deps.api.canonical_address(&operator)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let staged_amount =
+        read_staged_collateral(&deps.storage, &operator_raw, &collateral_token_raw)?;
+    store_staged_collateral(
+        &mut deps.storage,
+        &operator_raw,
+        &collateral_token_raw,
+        staged_amount + amount,
     )?;
 
-    Ok(BidsResponse { bids })
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "stage_collateral"),
+            log("operator", operator),
+            log("collateral_token", collateral_token),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+/// Sell the collaterals staged by the sender to the bids of the liquidator,
+/// paying out a single aggregated repay amount and fees
+#[allow(clippy::too_many_arguments)]
+pub fn execute_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    liquidator: HumanAddr,
+    collaterals: Vec<(HumanAddr, Uint256)>,
+    repay_address: HumanAddr,
+    fee_address: HumanAddr,
+    liquidator_fee_address: HumanAddr,
+    borrower: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(ContractError::LiquidationsPaused {});
+    }
+
+    if collaterals.is_empty() {
+        return Err(ContractError::NoCollateralsToLiquidate {});
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let operator_raw = deps.api.canonical_address(&env.message.sender)?;
+    let mut total = BidFill::default();
+    let mut collateral_payouts: Vec<(HumanAddr, HumanAddr, Uint256)> = vec![];
+    let mut logs = vec![log("action", "execute_bids")];
+    for (collateral_token, amount) in collaterals.iter() {
+        let collateral_token_raw = deps.api.canonical_address(collateral_token)?;
+        let staged_amount =
+            read_staged_collateral(&deps.storage, &operator_raw, &collateral_token_raw)?;
+        if *amount > staged_amount {
+            return Err(ContractError::InsufficientStagedCollateral {
+                collateral_token: collateral_token.clone(),
+                staged_amount,
+            });
+        }
+
+        store_staged_collateral(
+            &mut deps.storage,
+            &operator_raw,
+            &collateral_token_raw,
+            staged_amount - *amount,
+        )?;
+
+        let fill = fill_bid(
+            deps,
+            &env,
+            &config,
+            &liquidator,
+            collateral_token,
+            *amount,
+            &borrower,
+        )?;
+        total.repay_amount += fill.repay_amount;
+        total.bid_fee += fill.bid_fee;
+        total.referral_fee += fill.referral_fee;
+        total.liquidator_fee += fill.liquidator_fee;
+        collateral_payouts.push((fill.collateral_receiver, collateral_token.clone(), *amount));
+
+        logs.push(log("collateral_token", collateral_token));
+        logs.push(log("collateral_amount", amount));
+        logs.extend(fill.logs);
+    }
+
+    let messages = payout_messages(
+        deps,
+        &env,
+        &config,
+        collateral_payouts,
+        repay_address,
+        fee_address,
+        liquidator_fee_address,
+        &total,
+    )?;
+
+    logs.push(log("stable_denom", config.stable_denom));
+    logs.push(log("repay_amount", total.repay_amount));
+    logs.push(log("bid_fee", total.bid_fee));
+    logs.push(log("liquidator_fee", total.liquidator_fee));
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Stable amounts produced by executed bids
+#[derive(Default)]
+struct BidFill {
+    repay_amount: Uint256,
+    bid_fee: Uint256,
+    liquidator_fee: Uint256,
+    /// Part of the bid fee credited to the referrer of the bid
+    referral_fee: Uint256,
+    /// Bidder, or the receiver the bid routes the collateral to
+    collateral_receiver: HumanAddr,
+    /// Bid state change of a single fill
+    logs: Vec<LogAttribute>,
+}
+
+/// Attributes logged by every bid state transition, so fills and
+/// retractions can be followed without replaying the handlers
+fn bid_logs(
+    bidder: &HumanAddr,
+    premium_rate: Decimal256,
+    bid_amount_before: Uint256,
+    bid_amount_after: Uint256,
+) -> Vec<LogAttribute> {
+    vec![
+        log("bidder", bidder),
+        log("premium_rate", premium_rate),
+        log("bid_amount_before", bid_amount_before),
+        log("bid_amount_after", bid_amount_after),
+    ]
+}
+
+pub fn move_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    new_premium_rate: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    if read_bid_migration(&deps.storage)?.is_some() {
+        return Err(ContractError::BidsMigrating {});
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    if config.max_premium_rate < new_premium_rate {
+        return Err(ContractError::PremiumRateTooHigh {
+            max_premium_rate: config.max_premium_rate,
+        });
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)
+        .map_err(|_| ContractError::BidNotFound {})?;
+    if bid.is_expired(env.block.time) {
+        return Err(ContractError::BidExpired {});
+    }
+
+    if bid.premium_rate == new_premium_rate {
+        return Err(ContractError::SamePremiumRate {
+            premium_rate: new_premium_rate,
+        });
+    }
+
+    let prev_premium_rate = bid.premium_rate;
+    store_bid(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        Bid {
+            premium_rate: new_premium_rate,
+            ..bid
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "move_bid"),
+            log("collateral_token", collateral_token),
+            log("bidder", env.message.sender),
+            log("prev_premium_rate", prev_premium_rate),
+            log("new_premium_rate", new_premium_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_bid_receiver<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    unlock_receiver: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let bid: Bid = read_bid(&deps.storage, &bidder_raw, &collateral_token_raw)
+        .map_err(|_| ContractError::BidNotFound {})?;
+
+    let unlock_receiver_raw = match &unlock_receiver {
+        Some(unlock_receiver) => Some(assert_unlock_receiver(deps, unlock_receiver)?),
+        None => None,
+    };
+
+    store_bid(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        Bid {
+            unlock_receiver: unlock_receiver_raw,
+            ..bid
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_bid_receiver"),
+            log("collateral_token", collateral_token),
+            log("bidder", &env.message.sender),
+            log(
+                "unlock_receiver",
+                unlock_receiver.unwrap_or(env.message.sender),
+            ),
+        ],
+        data: None,
+    })
+}
+
+/// Blacklisted addresses cannot receive liquidated collaterals
+fn assert_unlock_receiver<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    unlock_receiver: &HumanAddr,
+) -> Result<CanonicalAddr, ContractError> {
+    let unlock_receiver_raw = deps.api.canonical_address(unlock_receiver)?;
+    if is_blacklisted(&deps.storage, &unlock_receiver_raw)? {
+        return Err(ContractError::Blacklisted {
+            address: unlock_receiver.clone(),
+        });
+    }
+
+    Ok(unlock_receiver_raw)
+}
+
+/// Expired bids can no longer be filled, so anyone can
+/// send them back to their bidders
+pub fn prune_expired_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    limit: Option<u32>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let expired_bids =
+        read_expired_bids(&deps.storage, &collateral_token_raw, env.block.time, limit)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![
+        log("action", "prune_expired_bids"),
+        log("collateral_token", collateral_token),
+        log("pruned_count", expired_bids.len()),
+    ];
+    for (bidder_raw, bid) in expired_bids.iter() {
+        remove_bid(&mut deps.storage, bidder_raw, &collateral_token_raw)?;
+
+        let mut stats: BidderStats =
+            read_bidder_stats(&deps.storage, bidder_raw, &collateral_token_raw)?;
+        let active_before = stats.active_bid_amount;
+        stats.active_bid_amount = saturating_sub(stats.active_bid_amount, bid.amount);
+        store_bidder_stats(&mut deps.storage, bidder_raw, &collateral_token_raw, &stats)?;
+        update_bid_rewards(
+            &mut deps.storage,
+            env.block.time,
+            bidder_raw,
+            &collateral_token_raw,
+            active_before,
+            stats.active_bid_amount,
+        )?;
+
+        let bidder = deps.api.human_address(bidder_raw)?;
+        logs.extend(bid_logs(
+            &bidder,
+            bid.premium_rate,
+            bid.amount,
+            Uint256::zero(),
+        ));
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            bidder,
+            config.stable_denom.clone(),
+            bid.amount,
+        )?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Bids left too small to be worth filling or retracting
+/// are closed and their balance sent to the reserve contract
+pub fn sweep_dust<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    limit: Option<u32>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reserve_contract = match config.reserve_contract {
+        Some(v) => deps.api.human_address(&v)?,
+        None => return Err(ContractError::ReserveContractNotSet {}),
+    };
+
+    if config.dust_threshold.is_zero() {
+        return Err(ContractError::DustSweepingDisabled {});
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let dust_bids = read_dust_bids(
+        &deps.storage,
+        &collateral_token_raw,
+        config.dust_threshold,
+        limit,
+    )?;
+    if dust_bids.is_empty() {
+        return Err(ContractError::NoDust {});
+    }
+
+    let swept_amount = dust_bids
+        .iter()
+        .fold(Uint256::zero(), |total, (_, bid)| total + bid.amount);
+    let mut logs = vec![
+        log("action", "sweep_dust"),
+        log("collateral_token", collateral_token),
+        log("swept_count", dust_bids.len()),
+        log("swept_amount", swept_amount),
+    ];
+    for (bidder_raw, bid) in dust_bids.iter() {
+        remove_bid(&mut deps.storage, bidder_raw, &collateral_token_raw)?;
+
+        let mut stats: BidderStats =
+            read_bidder_stats(&deps.storage, bidder_raw, &collateral_token_raw)?;
+        let active_before = stats.active_bid_amount;
+        stats.active_bid_amount = saturating_sub(stats.active_bid_amount, bid.amount);
+        store_bidder_stats(&mut deps.storage, bidder_raw, &collateral_token_raw, &stats)?;
+        update_bid_rewards(
+            &mut deps.storage,
+            env.block.time,
+            bidder_raw,
+            &collateral_token_raw,
+            active_before,
+            stats.active_bid_amount,
+        )?;
+
+        logs.extend(bid_logs(
+            &deps.api.human_address(bidder_raw)?,
+            bid.premium_rate,
+            bid.amount,
+            Uint256::zero(),
+        ));
+    }
+
+    let total_swept = read_swept_dust(&deps.storage, &collateral_token_raw)? + swept_amount;
+    store_swept_dust(&mut deps.storage, &collateral_token_raw, total_swept)?;
+
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            reserve_contract,
+            config.stable_denom,
+            swept_amount,
+        )?],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Sell `amount` of the collateral to the bid of the liquidator,
+/// updating the bid, the bidder stats and the liquidation history
+fn fill_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    liquidator: &HumanAddr,
+    collateral_token: &HumanAddr,
+    amount: Uint256,
+    borrower: &Option<HumanAddr>,
+) -> Result<BidFill, ContractError> {
+    let collateral_token_raw = deps.api.canonical_address(collateral_token)?;
+    let bidder_raw = deps.api.canonical_address(liquidator)?;
+    if is_blacklisted(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::Blacklisted {
+            address: liquidator.clone(),
+        });
+    }
+
+    if !is_allowed(&deps.storage, &bidder_raw)? {
+        return Err(ContractError::NotAllowlisted {
+            address: liquidator.clone(),
+        });
+    }
+
+    // deprecated collaterals can only be sold to the outstanding bids
+    let (bid, cross_bid) = match read_fillable_bid(
+        &deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        Some(env.block.time),
+    ) {
+        Ok(v) => v,
+        Err(_) if is_collateral_deprecated(&deps.storage, &collateral_token_raw)? => {
+            return Err(ContractError::CollateralDeprecated {
+                collateral_token: collateral_token.clone(),
+            })
+        }
+        Err(err) => return Err(err),
+    };
+    let referrer = bid.referrer.clone();
+    let bid_amount = bid.amount;
+    let collateral_receiver = match &bid.unlock_receiver {
+        Some(unlock_receiver) => {
+            let collateral_receiver = deps.api.human_address(unlock_receiver)?;
+            if is_blacklisted(&deps.storage, unlock_receiver)? {
+                return Err(ContractError::Blacklisted {
+                    address: collateral_receiver,
+                });
+            }
+
+            collateral_receiver
+        }
+        None => liquidator.clone(),
+    };
+
+    if is_collateral_paused(&deps.storage, &collateral_token_raw)? {
+        return Err(ContractError::CollateralPaused {
+            collateral_token: collateral_token.clone(),
+        });
+    }
+
+    let oracle_contract = collateral_oracle(deps, config, &collateral_token_raw)?;
+    let price: PriceResponse = query_price_with_mode(
+        deps,
+        &oracle_contract,
+        collateral_token.to_string(),
+        config.stable_denom.clone(),
+        Some(config.price_mode.clone()),
+        None,
+    )?;
+
+    if is_price_stale(&price, env.block.time, config.price_timeframe) {
+        return Err(ContractError::PriceExpired {
+            collateral_token: collateral_token.clone(),
+            last_updated: std::cmp::min(price.last_updated_base, price.last_updated_quote),
+        });
+    }
+
+    // guards against a compromised feeder draining the bids at once
+    if !read_price_bounds(&deps.storage, &collateral_token_raw)?.contains(price.rate) {
+        return Err(ContractError::PriceOutOfBounds {
+            collateral_token: collateral_token.clone(),
+            price: price.rate,
+        });
+    }
+
+    let premium_rate = std::cmp::min(bid.premium_rate, config.max_premium_rate);
+    let decimals = read_collateral_decimals(&deps.storage, &collateral_token_raw)?;
+    let required_stable = compute_required_stable(amount, decimals, price.rate, premium_rate);
+    if required_stable > bid.amount {
+        return Err(ContractError::InsufficientBidAmount {
+            required_amount: required_stable,
+        });
+    }
+
+    record_fill(
+        &mut deps.storage,
+        env,
+        config,
+        &collateral_token_raw,
+        collateral_token,
+        required_stable,
+    )?;
+
+    // Update bid
+    let mut stats: BidderStats =
+        read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    match cross_bid {
+        Some(_) if bid.amount == required_stable => {
+            remove_cross_bid(&mut deps.storage, &bidder_raw);
+        }
+        Some(cross_bid) => {
+            store_cross_bid(
+                &mut deps.storage,
+                &bidder_raw,
+                &CrossBid {
+                    amount: bid.amount - required_stable,
+                    ..cross_bid
+                },
+            )?;
+        }
+        None => {
+            if bid.amount == required_stable {
+                remove_bid(&mut deps.storage, &bidder_raw, &collateral_token_raw)?;
+            } else {
+                store_bid(
+                    &mut deps.storage,
+                    &bidder_raw,
+                    &collateral_token_raw,
+                    Bid {
+                        amount: bid.amount - required_stable,
+                        ..bid
+                    },
+                )?;
+            }
+
+            let active_before = stats.active_bid_amount;
+            stats.active_bid_amount = saturating_sub(stats.active_bid_amount, required_stable);
+            update_bid_rewards(
+                &mut deps.storage,
+                env.block.time,
+                &bidder_raw,
+                &collateral_token_raw,
+                active_before,
+                stats.active_bid_amount,
+            )?;
+        }
+    }
+
+    stats.filled_amount += required_stable;
+    stats.collateral_received += amount;
+    store_bidder_stats(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        &stats,
+    )?;
+
+    let bid_fee = rounding::mul(required_stable, config.bid_fee, Rounding::Up);
+    let liquidator_fee = rounding::mul(required_stable, config.liquidator_fee, Rounding::Down);
+    if bid_fee + liquidator_fee > required_stable {
+        return Err(ContractError::InvalidFees {});
+    }
+
+    let repay_amount = required_stable - bid_fee - liquidator_fee;
+
+    // the referral fee stays in the contract until the referrer claims it
+    let referral_fee = match referrer {
+        Some(referrer) => {
+            let referral_fee = rounding::mul(bid_fee, config.referral_fee_share, Rounding::Down);
+            let mut referral_fees: ReferralFees = read_referral_fees(&deps.storage, &referrer)?;
+            referral_fees.pending += referral_fee;
+            store_referral_fees(&mut deps.storage, &referrer, &referral_fees)?;
+            referral_fee
+        }
+        None => Uint256::zero(),
+    };
+
+    let borrower_raw = match borrower {
+        Some(borrower) => Some(deps.api.canonical_address(borrower)?),
+        None => None,
+    };
+    store_liquidation_record(
+        &mut deps.storage,
+        &LiquidationRecord {
+            collateral_token: collateral_token_raw,
+            borrower: borrower_raw,
+            repay_amount,
+            collateral_amount: amount,
+            avg_premium: premium_rate,
+            timestamp: env.block.time,
+        },
+    )?;
+
+    Ok(BidFill {
+        repay_amount,
+        bid_fee,
+        liquidator_fee,
+        referral_fee,
+        collateral_receiver,
+        logs: bid_logs(
+            liquidator,
+            premium_rate,
+            bid_amount,
+            bid_amount - required_stable,
+        ),
+    })
+}
+
+/// Add the filled stable value to the fill window of the collateral,
+/// rejecting the fill once the window limit is exceeded so the caller
+/// can retry in a later window
+fn record_fill<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    config: &Config,
+    collateral_token_raw: &CanonicalAddr,
+    collateral_token: &HumanAddr,
+    amount: Uint256,
+) -> Result<(), ContractError> {
+    if config.max_fill_per_window.is_zero() {
+        return Ok(());
+    }
+
+    let mut fill_window: FillWindow = read_fill_window(storage, collateral_token_raw)?;
+    let is_current = if config.fill_window == 0 {
+        fill_window.started_at == env.block.height
+    } else {
+        fill_window.started_at <= env.block.time
+            && env.block.time < fill_window.started_at + config.fill_window
+    };
+
+    if !is_current {
+        fill_window = FillWindow {
+            started_at: if config.fill_window == 0 {
+                env.block.height
+            } else {
+                env.block.time
+            },
+            filled_amount: Uint256::zero(),
+        };
+    }
+
+    fill_window.filled_amount += amount;
+    if fill_window.filled_amount > config.max_fill_per_window {
+        return Err(ContractError::FillLimitExceeded {
+            collateral_token: collateral_token.clone(),
+            remaining_limit: saturating_sub(
+                config.max_fill_per_window,
+                fill_window.filled_amount - amount,
+            ),
+        });
+    }
+
+    store_fill_window(storage, collateral_token_raw, &fill_window)?;
+
+    Ok(())
+}
+
+/// Bid of the bidder which can buy the collateral, along with the cross
+/// bid it was taken from; the bid on the collateral takes precedence
+/// unless it has expired
+fn read_fillable_bid<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    block_time: Option<u64>,
+) -> Result<(Bid, Option<CrossBid>), ContractError> {
+    let bid = read_bid(storage, bidder, collateral_token)
+        .map_err(|_| ContractError::BidNotFound {})
+        .and_then(|bid| match block_time {
+            Some(block_time) if bid.is_expired(block_time) => Err(ContractError::BidExpired {}),
+            _ => Ok(bid),
+        });
+
+    match bid {
+        Ok(bid) => Ok((bid, None)),
+        Err(err) => match read_cross_bid(storage, bidder)? {
+            Some(cross_bid) if cross_bid.collateral_tokens.contains(collateral_token) => Ok((
+                Bid {
+                    amount: cross_bid.amount,
+                    premium_rate: cross_bid.premium_rate,
+                    expires_at: None,
+                    referrer: None,
+                    unlock_receiver: None,
+                },
+                Some(cross_bid),
+            )),
+            _ => Err(err),
+        },
+    }
+}
+
+/// Collateral payouts to the receivers of the bids followed
+/// by the stable payouts of the executed bids
+#[allow(clippy::too_many_arguments)]
+fn payout_messages<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    config: &Config,
+    collateral_payouts: Vec<(HumanAddr, HumanAddr, Uint256)>,
+    repay_address: HumanAddr,
+    fee_address: HumanAddr,
+    liquidator_fee_address: HumanAddr,
+    fill: &BidFill,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages: Vec<CosmosMsg> = collateral_payouts
+        .into_iter()
+        .map(|(receiver, collateral_token, amount)| {
+            collateral_payout_msg(deps, &receiver, collateral_token, amount)
+        })
+        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+    messages.push(native_payout_msg(
+        deps,
+        env.contract.address.clone(),
+        repay_address,
+        config.stable_denom.clone(),
+        fill.repay_amount,
+    )?);
+
+    // the reserve share of the bid fee is rounded down
+    // in favor of the fee address
+    let reserve_fee = match &config.reserve_contract {
+        Some(reserve_contract) => {
+            let reserve_fee = rounding::mul(fill.bid_fee, config.reserve_fee_share, Rounding::Down);
+            if !reserve_fee.is_zero() {
+                messages.push(native_payout_msg(
+                    deps,
+                    env.contract.address.clone(),
+                    deps.api.human_address(reserve_contract)?,
+                    config.stable_denom.clone(),
+                    reserve_fee,
+                )?);
+            }
+
+            reserve_fee
+        }
+        None => Uint256::zero(),
+    };
+
+    let bid_fee = fill.bid_fee - fill.referral_fee - reserve_fee;
+    if config.fee_admin.is_some() {
+        // held in the contract until the fee admin withdraws it
+        let mut accrued_fees: AccruedFees = read_accrued_fees(&deps.storage)?;
+        accrued_fees.pending += bid_fee;
+        store_accrued_fees(&mut deps.storage, &accrued_fees)?;
+    } else if !bid_fee.is_zero() {
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            fee_address,
+            config.stable_denom.clone(),
+            bid_fee,
+        )?);
+    }
+
+    if !fill.liquidator_fee.is_zero() {
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            liquidator_fee_address,
+            config.stable_denom.clone(),
+            fill.liquidator_fee,
+        )?);
+    }
+
+    Ok(messages)
+}
+
+/// Stable paid by a bid for the collateral at the discounted price;
+/// the bidder pays for the collateral, so round up
+pub(crate) fn compute_required_stable(
+    amount: Uint256,
+    decimals: u8,
+    price: Decimal256,
+    premium_rate: Decimal256,
+) -> Uint256 {
+    rounding::mul(
+        normalize_amount(amount, decimals, Rounding::Up),
+        price * (Decimal256::one() - premium_rate),
+        Rounding::Up,
+    )
+}
+
+/// Bids placed before the stats were tracked are not counted
+/// in the active amount, so the counter must not underflow
+fn saturating_sub(a: Uint256, b: Uint256) -> Uint256 {
+    if a > b {
+        a - b
+    } else {
+        Uint256::zero()
+    }
+}
+
+/// Oracle the collateral is priced by; the configured
+/// one unless the collateral has its own price source
+pub(crate) fn collateral_oracle<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<HumanAddr> {
+    let oracle_contract = read_price_source(&deps.storage, collateral_token)?
+        .unwrap_or_else(|| config.oracle_contract.clone());
+    deps.api.human_address(&oracle_contract)
+}
+
+/// Both base and quote prices must be updated within the price_timeframe
+pub(crate) fn is_price_stale(price: &PriceResponse, block_time: u64, price_timeframe: u64) -> bool {
+    let valid_update_time = block_time.saturating_sub(price_timeframe);
+    price.last_updated_base < valid_update_time || price.last_updated_quote < valid_update_time
+}
+
+pub fn query_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    bidder: HumanAddr,
+) -> StdResult<BidResponse> {
+    let bid: Bid = read_bid(
+        &deps.storage,
+        &deps.api.canonical_address(&bidder)?,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(BidResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        bidder,
+        amount: bid.amount,
+        premium_rate: bid.premium_rate,
+        expires_at: bid.expires_at,
+        unlock_receiver: match bid.unlock_receiver {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+    })
+}
+
+pub fn query_cross_bid<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    bidder: HumanAddr,
+) -> StdResult<CrossBidResponse> {
+    let cross_bid: CrossBid =
+        match read_cross_bid(&deps.storage, &deps.api.canonical_address(&bidder)?)? {
+            Some(v) => v,
+            None => return Err(ContractError::CrossBidNotFound {}.into()),
+        };
+
+    Ok(CrossBidResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bidder,
+        collateral_tokens: cross_bid
+            .collateral_tokens
+            .iter()
+            .map(|collateral_token| deps.api.human_address(collateral_token))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+        amount: cross_bid.amount,
+        premium_rate: cross_bid.premium_rate,
+    })
+}
+
+pub fn query_bid_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<BidMigrationResponse> {
+    Ok(BidMigrationResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        new_liquidation_queue: match read_bid_migration(&deps.storage)? {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+    })
+}
+
+pub fn query_bids_by_user<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    bidder: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<BidsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let bidder_raw = deps.api.canonical_address(&bidder)?;
+    let bids: Vec<BidResponse> = read_bids_by_user(deps, &bidder_raw, start_after, limit)?;
+
+    // a full page may have more bids behind it
+    let next_start_after = if bids.len() == page_limit(limit) {
+        bids.last().map(|bid| bid.collateral_token.clone())
+    } else {
+        None
+    };
+
+    Ok(BidsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bids,
+        total_count: Some(read_bid_count(&deps.storage, &bidder_raw)?),
+        next_start_after,
+    })
 }
 
 pub fn query_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
@@ -284,11 +1587,313 @@ pub fn query_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
     };
 
     let bids: Vec<BidResponse> = read_bids_by_collateral(
-        &deps,
+        deps,
         &deps.api.canonical_address(&collateral_token)?,
         start_after,
         limit,
     )?;
 
-    Ok(BidsResponse { bids })
+    let next_start_after = if bids.len() == page_limit(limit) {
+        bids.last().map(|bid| bid.bidder.clone())
+    } else {
+        None
+    };
+
+    Ok(BidsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bids,
+        total_count: None,
+        next_start_after,
+    })
+}
+
+pub fn query_export_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<(HumanAddr, HumanAddr)>,
+    limit: Option<u32>,
+) -> StdResult<ExportBidsResponse> {
+    let start_after = if let Some((bidder, collateral_token)) = start_after {
+        Some((
+            deps.api.canonical_address(&bidder)?,
+            deps.api.canonical_address(&collateral_token)?,
+        ))
+    } else {
+        None
+    };
+
+    let bids: Vec<ExportedBid> = read_bids(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(bidder, collateral_token, bid)| {
+            Ok(ExportedBid {
+                bidder: deps.api.human_address(&bidder)?,
+                collateral_token: deps.api.human_address(&collateral_token)?,
+                amount: bid.amount,
+                premium_rate: bid.premium_rate,
+                expires_at: bid.expires_at,
+                referrer: match bid.referrer {
+                    Some(v) => Some(deps.api.human_address(&v)?),
+                    None => None,
+                },
+                unlock_receiver: match bid.unlock_receiver {
+                    Some(v) => Some(deps.api.human_address(&v)?),
+                    None => None,
+                },
+            })
+        })
+        .collect::<StdResult<Vec<ExportedBid>>>()?;
+
+    Ok(ExportBidsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bids,
+    })
+}
+
+pub fn query_export_cross_bids<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<ExportCrossBidsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let cross_bids: Vec<CrossBidResponse> = read_cross_bids(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(bidder, cross_bid)| {
+            Ok(CrossBidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
+                bidder: deps.api.human_address(&bidder)?,
+                collateral_tokens: cross_bid
+                    .collateral_tokens
+                    .iter()
+                    .map(|collateral_token| deps.api.human_address(collateral_token))
+                    .collect::<StdResult<Vec<HumanAddr>>>()?,
+                amount: cross_bid.amount,
+                premium_rate: cross_bid.premium_rate,
+            })
+        })
+        .collect::<StdResult<Vec<CrossBidResponse>>>()?;
+
+    Ok(ExportCrossBidsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        cross_bids,
+    })
+}
+
+/// Bids and bidder stats are updated together, so any drift
+/// between them points to an accounting bug
+pub fn query_invariants<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<InvariantsResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let bids: Vec<BidResponse> =
+        read_bids_by_collateral(deps, &collateral_token_raw, start_after, limit)?;
+
+    let mut total_bid_amount = Uint256::zero();
+    let mut total_active_bid_amount = Uint256::zero();
+    let mut mismatched_bidders: Vec<HumanAddr> = vec![];
+    for bid in bids.iter() {
+        let stats: BidderStats = read_bidder_stats(
+            &deps.storage,
+            &deps.api.canonical_address(&bid.bidder)?,
+            &collateral_token_raw,
+        )?;
+
+        total_bid_amount += bid.amount;
+        total_active_bid_amount += stats.active_bid_amount;
+        if bid.amount != stats.active_bid_amount {
+            mismatched_bidders.push(bid.bidder.clone());
+        }
+    }
+
+    let drift = if total_bid_amount > total_active_bid_amount {
+        total_bid_amount - total_active_bid_amount
+    } else {
+        total_active_bid_amount - total_bid_amount
+    };
+
+    Ok(InvariantsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        sampled_bids: bids.len() as u32,
+        total_bid_amount,
+        total_active_bid_amount,
+        drift,
+        mismatched_bidders,
+        last_bidder: bids.last().map(|bid| bid.bidder.clone()),
+    })
+}
+
+pub fn query_swept_dust<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<SweptDustResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    Ok(SweptDustResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        total_swept: read_swept_dust(&deps.storage, &collateral_token_raw)?,
+    })
+}
+
+pub fn query_liquidation_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    borrower: Option<HumanAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LiquidationHistoryResponse> {
+    let borrower_raw = match borrower {
+        Some(borrower) => Some(deps.api.canonical_address(&borrower)?),
+        None => None,
+    };
+
+    let records: Vec<LiquidationRecordResponse> = read_liquidation_records(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        borrower_raw,
+        start_after,
+        limit,
+    )?
+    .into_iter()
+    .map(|(idx, record)| {
+        Ok(LiquidationRecordResponse {
+            idx,
+            collateral_token: deps.api.human_address(&record.collateral_token)?,
+            borrower: match record.borrower {
+                Some(borrower) => Some(deps.api.human_address(&borrower)?),
+                None => None,
+            },
+            repay_amount: record.repay_amount,
+            collateral_amount: record.collateral_amount,
+            avg_premium: record.avg_premium,
+            timestamp: record.timestamp,
+        })
+    })
+    .collect::<StdResult<Vec<LiquidationRecordResponse>>>()?;
+
+    Ok(LiquidationHistoryResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        records,
+    })
+}
+
+pub fn query_bidder_stats<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    bidder: HumanAddr,
+) -> StdResult<BidderStatsResponse> {
+    let bidder_raw = deps.api.canonical_address(&bidder)?;
+
+    let mut total_active_bid_amount = Uint256::zero();
+    let mut total_filled_amount = Uint256::zero();
+    let collaterals: Vec<BidderCollateralStats> =
+        read_all_bidder_stats(&deps.storage, &bidder_raw)?
+            .into_iter()
+            .map(|(collateral_token, stats)| {
+                total_active_bid_amount += stats.active_bid_amount;
+                total_filled_amount += stats.filled_amount;
+                Ok(BidderCollateralStats {
+                    collateral_token: deps.api.human_address(&collateral_token)?,
+                    active_bid_amount: stats.active_bid_amount,
+                    filled_amount: stats.filled_amount,
+                    collateral_received: stats.collateral_received,
+                })
+            })
+            .collect::<StdResult<Vec<BidderCollateralStats>>>()?;
+
+    Ok(BidderStatsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bidder,
+        total_active_bid_amount,
+        total_filled_amount,
+        collaterals,
+    })
+}
+
+/// Dry run of selling collaterals to the bid of `bidder`, optionally
+/// at a hypothetical price. Transfers are reported net of tax.
+pub fn query_simulate_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    collateral_amount: Uint256,
+    bidder: HumanAddr,
+    price_override: Option<Decimal256>,
+) -> StdResult<SimulateLiquidationResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let (bid, _) = read_fillable_bid(
+        &deps.storage,
+        &deps.api.canonical_address(&bidder)?,
+        &collateral_token_raw,
+        None,
+    )?;
+
+    let price = match price_override {
+        Some(price) => price,
+        None => {
+            let oracle_contract = collateral_oracle(deps, &config, &collateral_token_raw)?;
+            let price: PriceResponse = query_price_with_mode(
+                deps,
+                &oracle_contract,
+                collateral_token.to_string(),
+                config.stable_denom.clone(),
+                Some(config.price_mode.clone()),
+                None,
+            )?;
+            price.rate
+        }
+    };
+
+    let premium_rate = std::cmp::min(bid.premium_rate, config.max_premium_rate);
+    let decimals = read_collateral_decimals(&deps.storage, &collateral_token_raw)?;
+    let required_stable = compute_required_stable(collateral_amount, decimals, price, premium_rate);
+    if required_stable > bid.amount {
+        return Err(ContractError::InsufficientBidAmount {
+            required_amount: required_stable,
+        }
+        .into());
+    }
+
+    let bid_fee = rounding::mul(required_stable, config.bid_fee, Rounding::Up);
+    let liquidator_fee = rounding::mul(required_stable, config.liquidator_fee, Rounding::Down);
+    let repay_amount = required_stable - bid_fee - liquidator_fee;
+
+    let net_of_tax = |amount: Uint256| -> StdResult<Uint256> {
+        if amount.is_zero() {
+            return Ok(amount);
+        }
+
+        let coin = deduct_tax(
+            deps,
+            Coin {
+                denom: config.stable_denom.clone(),
+                amount: amount.into(),
+            },
+        )?;
+        Ok(coin.amount.into())
+    };
+
+    Ok(SimulateLiquidationResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        collateral_amount,
+        price,
+        premium_rate,
+        required_stable,
+        repay_amount: net_of_tax(repay_amount)?,
+        bid_fee: net_of_tax(bid_fee)?,
+        liquidator_fee: net_of_tax(liquidator_fee)?,
+        remaining_bid_amount: bid.amount - required_stable,
+    })
 }
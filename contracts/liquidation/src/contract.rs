@@ -1,25 +1,63 @@
 use crate::bid::{
-    execute_bid, query_bid, query_bids_by_collateral, query_bids_by_user, retract_bid, submit_bid,
+    collateral_oracle, execute_bid, execute_bids, is_price_stale, migrate_bids, move_bid,
+    prune_expired_bids, query_bid, query_bid_migration, query_bidder_stats,
+    query_bids_by_collateral, query_bids_by_user, query_cross_bid, query_export_bids,
+    query_export_cross_bids, query_invariants, query_liquidation_history,
+    query_simulate_liquidation, query_swept_dust, retract_bid, retract_cross_bid, stage_collateral,
+    submit_bid, submit_cross_bid, sweep_dust, transfer_bid, update_bid_receiver,
+};
+use crate::converter::{query_claim_as, query_converter, register_converter, set_claim_as};
+use crate::error::ContractError;
+use crate::fee::{query_accrued_fees, withdraw_fees};
+use crate::incentive::{claim_rewards, query_pending_rewards, set_incentive_rate};
+use crate::migration::migrate_storage;
+use crate::referral::{claim_referral_fees, query_referral_fees};
+use crate::state::{
+    exportable_namespaces, is_collateral_paused, read_accrued_fees, read_allowlist,
+    read_allowlist_enabled, read_blacklist, read_collateral_custody, read_collateral_decimals,
+    read_config, read_contract_version, read_deprecated_collaterals, read_pause_info,
+    read_pending_config, read_price_bounds, read_price_source, remove_allowlisted,
+    remove_blacklisted, remove_pending_config, store_accrued_fees, store_allowlist_enabled,
+    store_allowlisted, store_blacklisted, store_collateral_custody, store_collateral_decimals,
+    store_collateral_deprecated, store_collateral_paused, store_config, store_contract_version,
+    store_pause_info, store_pending_config, store_price_bounds, store_price_source, AccruedFees,
+    Config, PendingConfig, PriceBounds, CONFIG_TIMELOCK_PERIOD, CONTRACT_VERSION,
 };
-use crate::state::{read_config, store_config, Config};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    from_binary, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
-    InitResponse, Querier, StdError, StdResult, Storage,
+    from_binary, log, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse,
+    MigrateResponse, Querier, StdResult, Storage,
 };
 use cw20::Cw20ReceiveMsg;
+use moneymarket::decimals::{normalize_amount, MAX_DECIMALS};
+use moneymarket::denom_migration::{
+    claim_denom_migration, propose_denom_migration, query_pending_denom_migration,
+};
+use moneymarket::halt_recovery::{read_halt_recovery, record_halt_recovery};
 use moneymarket::liquidation::{
-    ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, LiquidationAmountResponse, QueryMsg,
+    AllowlistResponse, BlacklistResponse, CollateralInfoResponse, CollateralStatusResponse,
+    ConfigResponse, Cw20HookMsg, DeprecatedCollateralsResponse, HandleMsg, InitMsg,
+    InterfaceVersionResponse, LiquidationAmountResponse, MigrateMsg, PendingConfigResponse,
+    PriceStatus, QueryMsg, LIQUIDATION_INTERFACE_VERSION, LIQUIDATION_SCHEMA_VERSION,
 };
-use moneymarket::querier::query_tax_rate;
+use moneymarket::oracle::{PriceMode, PriceResponse};
+use moneymarket::ownership::{claim_ownership, propose_owner, query_pending_owner};
+use moneymarket::pause::PauseInfo;
+use moneymarket::querier::{query_price_with_mode, query_tax_rate};
+use moneymarket::raw_state::query_raw_state;
+use moneymarket::rounding::{self, Rounding};
+use moneymarket::sweep::{sweep_msg, SweepAsset};
+use moneymarket::tax::{read_tax_policy, store_tax_policy, TaxPolicy};
 use moneymarket::tokens::TokensHuman;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     _env: Env,
     msg: InitMsg,
-) -> StdResult<InitResponse> {
+) -> Result<InitResponse, ContractError> {
+    assert_max_close_factor(msg.max_close_factor)?;
+    assert_fees(msg.bid_fee, msg.liquidator_fee)?;
     store_config(
         &mut deps.storage,
         &Config {
@@ -28,11 +66,27 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             stable_denom: msg.stable_denom,
             safe_ratio: msg.safe_ratio,
             bid_fee: msg.bid_fee,
+            liquidator_fee: msg.liquidator_fee,
             max_premium_rate: msg.max_premium_rate,
             liquidation_threshold: msg.liquidation_threshold,
+            max_close_factor: msg.max_close_factor,
             price_timeframe: msg.price_timeframe,
+            price_mode: msg.price_mode,
+            guardian: None,
+            min_liquidation: Uint256::zero(),
+            reserve_contract: None,
+            reserve_fee_share: Decimal256::zero(),
+            referral_fee_share: Decimal256::zero(),
+            dust_threshold: Uint256::zero(),
+            delegated_bids_enabled: false,
+            reward_token: None,
+            max_fill_per_window: Uint256::zero(),
+            fill_window: 0,
+            fee_admin: None,
+            overseer_contract: None,
         },
     )?;
+    store_contract_version(&mut deps.storage, CONTRACT_VERSION)?;
 
     Ok(InitResponse::default())
 }
@@ -41,38 +95,220 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: HandleMsg,
-) -> HandleResult {
+) -> Result<HandleResponse, ContractError> {
     match msg {
         HandleMsg::Receive(msg) => receive_cw20(deps, env, msg),
+        HandleMsg::ProposeOwner {
+            new_owner,
+            expires_in,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            Ok(propose_owner(
+                deps,
+                env,
+                &config.owner,
+                new_owner,
+                expires_in,
+            )?)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env),
+        HandleMsg::ProposeDenomMigration {
+            new_denom,
+            conversion_contract,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            Ok(propose_denom_migration(
+                deps,
+                env,
+                &config.owner,
+                new_denom,
+                conversion_contract,
+            )?)
+        }
+        HandleMsg::ExecuteDenomMigration {} => execute_denom_migration(deps, env),
         HandleMsg::UpdateConfig {
-            owner,
             oracle_contract,
-            stable_denom,
             safe_ratio,
             bid_fee,
+            liquidator_fee,
             max_premium_rate,
             liquidation_threshold,
+            max_close_factor,
             price_timeframe,
+            price_mode,
+            guardian,
+            min_liquidation,
+            reserve_contract,
+            reserve_fee_share,
+            referral_fee_share,
+            dust_threshold,
+            delegated_bids_enabled,
+            reward_token,
+            max_fill_per_window,
+            fill_window,
+            fee_admin,
+            overseer_contract,
         } => update_config(
             deps,
             env,
-            owner,
             oracle_contract,
-            stable_denom,
             safe_ratio,
             bid_fee,
+            liquidator_fee,
             max_premium_rate,
             liquidation_threshold,
+            max_close_factor,
             price_timeframe,
+            price_mode,
+            guardian,
+            min_liquidation,
+            reserve_contract,
+            reserve_fee_share,
+            referral_fee_share,
+            dust_threshold,
+            delegated_bids_enabled,
+            reward_token,
+            max_fill_per_window,
+            fill_window,
+            fee_admin,
+            overseer_contract,
         ),
+        HandleMsg::ApplyConfig {} => apply_config(deps, env),
         HandleMsg::SubmitBid {
             collateral_token,
             premium_rate,
-        } => submit_bid(deps, env, collateral_token, premium_rate),
+            expires_at,
+            referrer,
+            bid_for,
+            unlock_receiver,
+        } => submit_bid(
+            deps,
+            env,
+            collateral_token,
+            premium_rate,
+            expires_at,
+            referrer,
+            bid_for,
+            unlock_receiver,
+        ),
         HandleMsg::RetractBid {
             collateral_token,
             amount,
         } => retract_bid(deps, env, collateral_token, amount),
+        HandleMsg::SubmitCrossBid {
+            collateral_tokens,
+            premium_rate,
+        } => submit_cross_bid(deps, env, collateral_tokens, premium_rate),
+        HandleMsg::RetractCrossBid { amount } => retract_cross_bid(deps, env, amount),
+        HandleMsg::ExecuteBids {
+            liquidator,
+            collaterals,
+            fee_address,
+            repay_address,
+            liquidator_fee_address,
+            borrower,
+        } => {
+            let repay_address = repay_address.unwrap_or_else(|| env.message.sender.clone());
+            let fee_address = fee_address.unwrap_or_else(|| env.message.sender.clone());
+            let liquidator_fee_address =
+                liquidator_fee_address.unwrap_or_else(|| liquidator.clone());
+
+            execute_bids(
+                deps,
+                env,
+                liquidator,
+                collaterals,
+                repay_address,
+                fee_address,
+                liquidator_fee_address,
+                borrower,
+            )
+        }
+        HandleMsg::TransferBid {
+            collateral_token,
+            new_bidder,
+        } => transfer_bid(deps, env, collateral_token, new_bidder),
+        HandleMsg::MoveBid {
+            collateral_token,
+            new_premium_rate,
+        } => move_bid(deps, env, collateral_token, new_premium_rate),
+        HandleMsg::UpdateBidReceiver {
+            collateral_token,
+            unlock_receiver,
+        } => update_bid_receiver(deps, env, collateral_token, unlock_receiver),
+        HandleMsg::UpdateBlacklist { add, remove } => update_blacklist(deps, env, add, remove),
+        HandleMsg::UpdateAllowlist {
+            enabled,
+            add,
+            remove,
+        } => update_allowlist(deps, env, enabled, add, remove),
+        HandleMsg::PauseCollateral { collateral_token } => {
+            update_collateral_paused(deps, env, collateral_token, true)
+        }
+        HandleMsg::ResumeCollateral { collateral_token } => {
+            update_collateral_paused(deps, env, collateral_token, false)
+        }
+        HandleMsg::DeprecateCollateral { collateral_token } => {
+            deprecate_collateral(deps, env, collateral_token)
+        }
+        HandleMsg::RegisterCollateral {
+            collateral_token,
+            decimals,
+        } => register_collateral(deps, env, collateral_token, decimals),
+        HandleMsg::SetPriceBounds {
+            collateral_token,
+            min_price,
+            max_price,
+        } => set_price_bounds(deps, env, collateral_token, min_price, max_price),
+        HandleMsg::SetPriceSource {
+            collateral_token,
+            oracle_contract,
+        } => set_price_source(deps, env, collateral_token, oracle_contract),
+        HandleMsg::SetCollateralCustody {
+            collateral_token,
+            custody_contract,
+        } => set_collateral_custody(deps, env, collateral_token, custody_contract),
+        HandleMsg::SetPause {
+            deposits,
+            borrows,
+            liquidations,
+            withdrawals,
+        } => set_pause(deps, env, deposits, borrows, liquidations, withdrawals),
+        HandleMsg::UpdateTaxPolicy { tax_policy } => update_tax_policy(deps, env, tax_policy),
+        HandleMsg::RecoverFromHalt { halted_at } => recover_from_halt(deps, env, halted_at),
+        HandleMsg::MigrateBids {
+            new_liquidation_queue,
+            limit,
+        } => migrate_bids(deps, env, new_liquidation_queue, limit),
+        HandleMsg::PruneExpiredBids {
+            collateral_token,
+            limit,
+        } => prune_expired_bids(deps, env, collateral_token, limit),
+        HandleMsg::ClaimReferralFees {} => claim_referral_fees(deps, env),
+        HandleMsg::WithdrawFees { recipient, amount } => {
+            withdraw_fees(deps, env, recipient, amount)
+        }
+        HandleMsg::SweepDust {
+            collateral_token,
+            limit,
+        } => sweep_dust(deps, env, collateral_token, limit),
+        HandleMsg::RegisterConverter {
+            collateral_token,
+            claim_as,
+            converter,
+        } => register_converter(deps, env, collateral_token, claim_as, converter),
+        HandleMsg::SetClaimAs {
+            collateral_token,
+            claim_as,
+        } => set_claim_as(deps, env, collateral_token, claim_as),
+        HandleMsg::SetIncentiveRate {
+            collateral_token,
+            emission_rate,
+        } => set_incentive_rate(deps, env, collateral_token, emission_rate),
+        HandleMsg::ClaimRewards { collateral_token } => claim_rewards(deps, env, collateral_token),
+        HandleMsg::SweepUnrelatedFunds { asset, recipient } => {
+            sweep_unrelated_funds(deps, env, asset, recipient)
+        }
     }
 }
 
@@ -80,7 +316,7 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     cw20_msg: Cw20ReceiveMsg,
-) -> HandleResult {
+) -> Result<HandleResponse, ContractError> {
     let contract_addr = env.message.sender.clone();
     if let Some(msg) = cw20_msg.msg.clone() {
         match from_binary(&msg)? {
@@ -88,10 +324,16 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
                 liquidator,
                 repay_address,
                 fee_address,
+                liquidator_fee_address,
+                borrower,
             } => {
                 let collateral_token = contract_addr;
                 let repay_address = repay_address.unwrap_or_else(|| cw20_msg.sender.clone());
                 let fee_address = fee_address.unwrap_or_else(|| cw20_msg.sender.clone());
+                // the cw20 sender is the custody contract,
+                // so the fee goes to the liquidator by default
+                let liquidator_fee_address =
+                    liquidator_fee_address.unwrap_or_else(|| liquidator.clone());
 
                 execute_bid(
                     deps,
@@ -99,15 +341,20 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
                     liquidator,
                     repay_address,
                     fee_address,
+                    liquidator_fee_address,
                     collateral_token,
                     cw20_msg.amount.into(),
+                    borrower,
                 )
             }
+            Cw20HookMsg::StageCollateral { operator } => {
+                let operator = operator.unwrap_or_else(|| cw20_msg.sender.clone());
+                assert_staging_allowed(deps, &contract_addr, &cw20_msg.sender, &operator)?;
+                stage_collateral(deps, operator, contract_addr, cw20_msg.amount.into())
+            }
         }
     } else {
-        Err(StdError::generic_err(
-            "Invalid request: \"execute bid\" message not included in request",
-        ))
+        Err(ContractError::InvalidRequest {})
     }
 }
 
@@ -115,54 +362,682 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner: Option<HumanAddr>,
     oracle_contract: Option<HumanAddr>,
-    stable_denom: Option<String>,
     safe_ratio: Option<Decimal256>,
     bid_fee: Option<Decimal256>,
+    liquidator_fee: Option<Decimal256>,
     max_premium_rate: Option<Decimal256>,
     liquidation_threshold: Option<Uint256>,
+    max_close_factor: Option<Decimal256>,
     price_timeframe: Option<u64>,
-) -> HandleResult {
+    price_mode: Option<PriceMode>,
+    guardian: Option<HumanAddr>,
+    min_liquidation: Option<Uint256>,
+    reserve_contract: Option<HumanAddr>,
+    reserve_fee_share: Option<Decimal256>,
+    referral_fee_share: Option<Decimal256>,
+    dust_threshold: Option<Uint256>,
+    delegated_bids_enabled: Option<bool>,
+    reward_token: Option<HumanAddr>,
+    max_fill_per_window: Option<Uint256>,
+    fill_window: Option<u64>,
+    fee_admin: Option<HumanAddr>,
+    overseer_contract: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
     let mut config: Config = read_config(&deps.storage)?;
     if deps.api.canonical_address(&env.message.sender)? != config.owner {
-        return Err(StdError::unauthorized());
-    }
-
-    if let Some(owner) = owner {
-        config.owner = deps.api.canonical_address(&owner)?;
+        return Err(ContractError::Unauthorized {});
     }
 
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
     }
 
-    if let Some(stable_denom) = stable_denom {
-        config.stable_denom = stable_denom;
+    if let Some(price_timeframe) = price_timeframe {
+        config.price_timeframe = price_timeframe;
+    }
+
+    if let Some(price_mode) = price_mode {
+        config.price_mode = price_mode;
+    }
+
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
+    if let Some(min_liquidation) = min_liquidation {
+        config.min_liquidation = min_liquidation;
+    }
+
+    if let Some(reserve_contract) = reserve_contract {
+        config.reserve_contract = Some(deps.api.canonical_address(&reserve_contract)?);
+    }
+
+    if let Some(reserve_fee_share) = reserve_fee_share {
+        if reserve_fee_share > Decimal256::one() {
+            return Err(ContractError::InvalidReserveFeeShare {});
+        }
+
+        config.reserve_fee_share = reserve_fee_share;
     }
 
-    if let Some(safe_ratio) = safe_ratio {
+    if let Some(referral_fee_share) = referral_fee_share {
+        config.referral_fee_share = referral_fee_share;
+    }
+
+    if config.reserve_fee_share + config.referral_fee_share > Decimal256::one() {
+        return Err(ContractError::InvalidFeeShares {});
+    }
+
+    if let Some(dust_threshold) = dust_threshold {
+        config.dust_threshold = dust_threshold;
+    }
+
+    if let Some(delegated_bids_enabled) = delegated_bids_enabled {
+        config.delegated_bids_enabled = delegated_bids_enabled;
+    }
+
+    if let Some(reward_token) = reward_token {
+        config.reward_token = Some(deps.api.canonical_address(&reward_token)?);
+    }
+
+    if let Some(max_fill_per_window) = max_fill_per_window {
+        config.max_fill_per_window = max_fill_per_window;
+    }
+
+    if let Some(fill_window) = fill_window {
+        config.fill_window = fill_window;
+    }
+
+    if let Some(fee_admin) = fee_admin {
+        config.fee_admin = Some(deps.api.canonical_address(&fee_admin)?);
+    }
+
+    if let Some(overseer_contract) = overseer_contract {
+        config.overseer_contract = Some(deps.api.canonical_address(&overseer_contract)?);
+    }
+
+    store_config(&mut deps.storage, &config)?;
+
+    // risk parameters are applied only after the timelock,
+    // so bidders can react before they change
+    if safe_ratio.is_none()
+        && bid_fee.is_none()
+        && liquidator_fee.is_none()
+        && max_premium_rate.is_none()
+        && liquidation_threshold.is_none()
+        && max_close_factor.is_none()
+    {
+        return Ok(HandleResponse::default());
+    }
+
+    let mut pending_config: PendingConfig = read_pending_config(&deps.storage)?.unwrap_or_default();
+    if safe_ratio.is_some() {
+        pending_config.safe_ratio = safe_ratio;
+    }
+
+    if bid_fee.is_some() {
+        pending_config.bid_fee = bid_fee;
+    }
+
+    if liquidator_fee.is_some() {
+        pending_config.liquidator_fee = liquidator_fee;
+    }
+
+    assert_fees(
+        pending_config.bid_fee.unwrap_or(config.bid_fee),
+        pending_config
+            .liquidator_fee
+            .unwrap_or(config.liquidator_fee),
+    )?;
+
+    if max_premium_rate.is_some() {
+        pending_config.max_premium_rate = max_premium_rate;
+    }
+
+    if liquidation_threshold.is_some() {
+        pending_config.liquidation_threshold = liquidation_threshold;
+    }
+
+    if let Some(max_close_factor) = max_close_factor {
+        assert_max_close_factor(max_close_factor)?;
+        pending_config.max_close_factor = Some(max_close_factor);
+    }
+
+    pending_config.effective_at = env.block.time + CONFIG_TIMELOCK_PERIOD;
+    store_pending_config(&mut deps.storage, &pending_config)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "schedule_config"),
+            log("effective_at", pending_config.effective_at),
+        ],
+        data: None,
+    })
+}
+
+pub fn apply_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> Result<HandleResponse, ContractError> {
+    let pending_config: PendingConfig = match read_pending_config(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoPendingConfig {}),
+    };
+
+    if env.block.time < pending_config.effective_at {
+        return Err(ContractError::PendingConfigNotEffective {
+            effective_at: pending_config.effective_at,
+        });
+    }
+
+    let mut config: Config = read_config(&deps.storage)?;
+    if let Some(safe_ratio) = pending_config.safe_ratio {
         config.safe_ratio = safe_ratio;
     }
 
-    if let Some(bid_fee) = bid_fee {
+    if let Some(bid_fee) = pending_config.bid_fee {
         config.bid_fee = bid_fee;
     }
 
-    if let Some(max_premium_rate) = max_premium_rate {
+    if let Some(liquidator_fee) = pending_config.liquidator_fee {
+        config.liquidator_fee = liquidator_fee;
+    }
+
+    if let Some(max_premium_rate) = pending_config.max_premium_rate {
         config.max_premium_rate = max_premium_rate;
     }
 
-    if let Some(liquidation_threshold) = liquidation_threshold {
+    if let Some(liquidation_threshold) = pending_config.liquidation_threshold {
         config.liquidation_threshold = liquidation_threshold;
     }
 
-    if let Some(price_timeframe) = price_timeframe {
-        config.price_timeframe = price_timeframe;
+    if let Some(max_close_factor) = pending_config.max_close_factor {
+        config.max_close_factor = max_close_factor;
     }
 
+    assert_fees(config.bid_fee, config.liquidator_fee)?;
+    store_config(&mut deps.storage, &config)?;
+    remove_pending_config(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "apply_config")],
+        data: None,
+    })
+}
+
+pub fn accept_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> Result<HandleResponse, ContractError> {
+    let mut config: Config = read_config(&deps.storage)?;
+    config.owner = claim_ownership(deps, &env)?;
     store_config(&mut deps.storage, &config)?;
-    Ok(HandleResponse::default())
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_ownership"),
+            log("owner", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn execute_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> Result<HandleResponse, ContractError> {
+    let mut config: Config = read_config(&deps.storage)?;
+    let (migration, messages, conversion_tax) =
+        claim_denom_migration(deps, &env, &config.owner, &config.stable_denom)?;
+
+    // the bids are owed in full, so the accrued protocol fees
+    // cover the conversion tax first
+    let mut accrued_fees: AccruedFees = read_accrued_fees(&deps.storage)?;
+    let covered_tax = std::cmp::min(accrued_fees.pending, conversion_tax);
+    accrued_fees.pending = accrued_fees.pending - covered_tax;
+    store_accrued_fees(&mut deps.storage, &accrued_fees)?;
+
+    let old_denom = std::mem::replace(&mut config.stable_denom, migration.new_denom);
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "execute_denom_migration"),
+            log("old_denom", old_denom),
+            log("new_denom", config.stable_denom),
+            log("conversion_tax", conversion_tax),
+            log("covered_by_fees", covered_tax),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_blacklist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    add: Vec<HumanAddr>,
+    remove: Vec<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in add.iter() {
+        store_blacklisted(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+    }
+
+    for address in remove.iter() {
+        remove_blacklisted(&mut deps.storage, &deps.api.canonical_address(address)?);
+    }
+
+    let add_logs: Vec<String> = add.iter().map(|a| a.to_string()).collect();
+    let remove_logs: Vec<String> = remove.iter().map(|a| a.to_string()).collect();
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_blacklist"),
+            log("add", add_logs.join(",")),
+            log("remove", remove_logs.join(",")),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    enabled: Option<bool>,
+    add: Vec<HumanAddr>,
+    remove: Vec<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(enabled) = enabled {
+        store_allowlist_enabled(&mut deps.storage, enabled)?;
+    }
+
+    for address in add.iter() {
+        store_allowlisted(&mut deps.storage, &deps.api.canonical_address(address)?)?;
+    }
+
+    for address in remove.iter() {
+        remove_allowlisted(&mut deps.storage, &deps.api.canonical_address(address)?);
+    }
+
+    let add_logs: Vec<String> = add.iter().map(|a| a.to_string()).collect();
+    let remove_logs: Vec<String> = remove.iter().map(|a| a.to_string()).collect();
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_allowlist"),
+            log("enabled", read_allowlist_enabled(&deps.storage)?),
+            log("add", add_logs.join(",")),
+            log("remove", remove_logs.join(",")),
+        ],
+        data: None,
+    })
+}
+
+pub fn deprecate_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    store_collateral_deprecated(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deprecate_collateral"),
+            log("collateral_token", collateral_token),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_collateral_paused<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    paused: bool,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    store_collateral_paused(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        paused,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log(
+                "action",
+                if paused {
+                    "pause_collateral"
+                } else {
+                    "resume_collateral"
+                },
+            ),
+            log("collateral_token", collateral_token),
+        ],
+        data: None,
+    })
+}
+
+pub fn register_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    decimals: u8,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if decimals > MAX_DECIMALS {
+        return Err(ContractError::InvalidCollateralDecimals {
+            max_decimals: MAX_DECIMALS,
+        });
+    }
+
+    store_collateral_decimals(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        decimals,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_collateral"),
+            log("collateral_token", collateral_token),
+            log("decimals", decimals),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_price_bounds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    min_price: Option<Decimal256>,
+    max_price: Option<Decimal256>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let (Some(min_price), Some(max_price)) = (min_price, max_price) {
+        if min_price > max_price {
+            return Err(ContractError::InvalidPriceBounds {});
+        }
+    }
+
+    store_price_bounds(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        &PriceBounds {
+            min_price,
+            max_price,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_price_bounds"),
+            log("collateral_token", collateral_token),
+            log(
+                "min_price",
+                min_price.map_or_else(String::new, |v| v.to_string()),
+            ),
+            log(
+                "max_price",
+                max_price.map_or_else(String::new, |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_price_source<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    oracle_contract: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let oracle_contract_raw = match &oracle_contract {
+        Some(oracle_contract) => Some(deps.api.canonical_address(oracle_contract)?),
+        None => None,
+    };
+    store_price_source(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        oracle_contract_raw.as_ref(),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_price_source"),
+            log("collateral_token", collateral_token),
+            log(
+                "oracle_contract",
+                oracle_contract.map_or_else(String::new, |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_collateral_custody<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    custody_contract: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let custody_contract_raw = match &custody_contract {
+        Some(custody_contract) => Some(deps.api.canonical_address(custody_contract)?),
+        None => None,
+    };
+    store_collateral_custody(
+        &mut deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        custody_contract_raw.as_ref(),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_collateral_custody"),
+            log("collateral_token", collateral_token),
+            log(
+                "custody_contract",
+                custody_contract.map_or_else(String::new, |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+/// Staged collaterals are consumed by the ExecuteBids of the operator,
+/// so only the registered custody can stage, for itself or the overseer
+fn assert_staging_allowed<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: &HumanAddr,
+    sender: &HumanAddr,
+    operator: &HumanAddr,
+) -> Result<(), ContractError> {
+    let sender_raw = deps.api.canonical_address(sender)?;
+    let custody_contract = read_collateral_custody(
+        &deps.storage,
+        &deps.api.canonical_address(collateral_token)?,
+    )?;
+    if custody_contract != Some(sender_raw.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let operator_raw = deps.api.canonical_address(operator)?;
+    let config: Config = read_config(&deps.storage)?;
+    if operator_raw != sender_raw && Some(operator_raw) != config.overseer_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits: Option<bool>,
+    borrows: Option<bool>,
+    liquidations: Option<bool>,
+    withdrawals: Option<bool>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only pause
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner;
+    if !is_owner && Some(sender_raw) != config.guardian {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(&deps.storage)?;
+    pause_info.update(deposits, borrows, liquidations, withdrawals, is_owner)?;
+    store_pause_info(&mut deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposits", pause_info.deposits),
+            log("borrows", pause_info.borrows),
+            log("liquidations", pause_info.liquidations),
+            log("withdrawals", pause_info.withdrawals),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: owner
+pub fn update_tax_policy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    tax_policy: TaxPolicy,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    store_tax_policy(&mut deps.storage, &tax_policy)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_tax_policy")],
+        data: None,
+    })
+}
+
+pub fn recover_from_halt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    halted_at: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if sender_raw != config.owner && Some(sender_raw) != config.guardian {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let duration = record_halt_recovery(&mut deps.storage, halted_at, env.block.time)?;
+
+    // risk parameter changes keep their full timelock
+    if let Some(mut pending_config) = read_pending_config(&deps.storage)? {
+        pending_config.effective_at += duration;
+        store_pending_config(&mut deps.storage, &pending_config)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "recover_from_halt"),
+            log("halted_at", halted_at),
+            log("duration", duration),
+        ],
+        data: None,
+    })
+}
+
+pub fn sweep_unrelated_funds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: SweepAsset,
+    recipient: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the stable balance is owned by the bidders
+    let is_tracked = match &asset {
+        SweepAsset::Native { denom } => *denom == config.stable_denom,
+        SweepAsset::Token { .. } => false,
+    };
+    if is_tracked {
+        return Err(ContractError::TrackedFunds {
+            asset: asset.to_string(),
+        });
+    }
+
+    let (amount, msg) = sweep_msg(deps, &env, &asset, recipient.clone())?;
+    Ok(HandleResponse {
+        messages: vec![msg],
+        log: vec![
+            log("action", "sweep_unrelated_funds"),
+            log("asset", asset),
+            log("amount", amount),
+            log("recipient", recipient),
+        ],
+        data: None,
+    })
 }
 
 pub fn query<S: Storage, A: Api, Q: Querier>(
@@ -171,6 +1046,9 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingConfig {} => to_binary(&query_pending_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::PendingDenomMigration {} => to_binary(&query_pending_denom_migration(deps)?),
         QueryMsg::LiquidationAmount {
             borrow_amount,
             borrow_limit,
@@ -183,6 +1061,30 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             collaterals,
             collateral_prices,
         )?),
+        QueryMsg::LiquidationAmountAtOraclePrices {
+            borrow_amount,
+            borrow_limit,
+            collaterals,
+            block_time,
+        } => to_binary(&query_liquidation_amount_at_oracle_prices(
+            deps,
+            borrow_amount,
+            borrow_limit,
+            collaterals,
+            block_time,
+        )?),
+        QueryMsg::SimulateLiquidation {
+            collateral_token,
+            collateral_amount,
+            bidder,
+            price_override,
+        } => to_binary(&query_simulate_liquidation(
+            deps,
+            collateral_token,
+            collateral_amount,
+            bidder,
+            price_override,
+        )?),
         QueryMsg::Bid {
             collateral_token,
             bidder,
@@ -192,6 +1094,8 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             start_after,
             limit,
         } => to_binary(&query_bids_by_user(deps, bidder, start_after, limit)?),
+        QueryMsg::CrossBid { bidder } => to_binary(&query_cross_bid(deps, bidder)?),
+        QueryMsg::BidderStats { bidder } => to_binary(&query_bidder_stats(deps, bidder)?),
         QueryMsg::BidsByCollateral {
             collateral_token,
             start_after,
@@ -202,6 +1106,96 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             start_after,
             limit,
         )?),
+        QueryMsg::Blacklist { start_after, limit } => {
+            to_binary(&query_blacklist(deps, start_after, limit)?)
+        }
+        QueryMsg::Allowlist { start_after, limit } => {
+            to_binary(&query_allowlist(deps, start_after, limit)?)
+        }
+        QueryMsg::DeprecatedCollaterals { start_after, limit } => {
+            to_binary(&query_deprecated_collaterals(deps, start_after, limit)?)
+        }
+        QueryMsg::LiquidationHistory {
+            collateral_token,
+            borrower,
+            start_after,
+            limit,
+        } => to_binary(&query_liquidation_history(
+            deps,
+            collateral_token,
+            borrower,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::CollateralStatus {
+            collateral_token,
+            block_time,
+        } => to_binary(&query_collateral_status(
+            deps,
+            collateral_token,
+            block_time,
+        )?),
+        QueryMsg::CollateralInfo { collateral_token } => {
+            to_binary(&query_collateral_info(deps, collateral_token)?)
+        }
+        QueryMsg::PauseInfo {} => to_binary(&read_pause_info(&deps.storage)?),
+        QueryMsg::TaxPolicy {} => to_binary(&read_tax_policy(&deps.storage)?),
+        QueryMsg::HaltRecovery {} => to_binary(&read_halt_recovery(&deps.storage)?),
+        QueryMsg::BidMigration {} => to_binary(&query_bid_migration(deps)?),
+        QueryMsg::ReferralFees { referrer } => to_binary(&query_referral_fees(deps, referrer)?),
+        QueryMsg::AccruedFees {} => to_binary(&query_accrued_fees(deps)?),
+        QueryMsg::SweptDust { collateral_token } => {
+            to_binary(&query_swept_dust(deps, collateral_token)?)
+        }
+        QueryMsg::Converter {
+            collateral_token,
+            claim_as,
+        } => to_binary(&query_converter(deps, collateral_token, claim_as)?),
+        QueryMsg::ClaimAs {
+            collateral_token,
+            bidder,
+        } => to_binary(&query_claim_as(deps, collateral_token, bidder)?),
+        QueryMsg::Invariants {
+            collateral_token,
+            start_after,
+            limit,
+        } => to_binary(&query_invariants(
+            deps,
+            collateral_token,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::PendingRewards {
+            bidder,
+            collateral_token,
+            block_time,
+        } => to_binary(&query_pending_rewards(
+            deps,
+            bidder,
+            collateral_token,
+            block_time,
+        )?),
+        QueryMsg::InterfaceVersion {} => to_binary(&InterfaceVersionResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            version: LIQUIDATION_INTERFACE_VERSION,
+        }),
+        QueryMsg::RawState {
+            namespace,
+            start_after,
+            limit,
+        } => to_binary(&query_raw_state(
+            &deps.storage,
+            &exportable_namespaces(),
+            namespace,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ExportBids { start_after, limit } => {
+            to_binary(&query_export_bids(deps, start_after, limit)?)
+        }
+        QueryMsg::ExportCrossBids { start_after, limit } => {
+            to_binary(&query_export_cross_bids(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -210,19 +1204,194 @@ fn query_config<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<ConfigResponse> {
     let config = read_config(&deps.storage)?;
     let resp = ConfigResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
         owner: deps.api.human_address(&config.owner)?,
         oracle_contract: deps.api.human_address(&config.oracle_contract)?,
         stable_denom: config.stable_denom,
         safe_ratio: config.safe_ratio,
         bid_fee: config.bid_fee,
+        liquidator_fee: config.liquidator_fee,
         max_premium_rate: config.max_premium_rate,
         liquidation_threshold: config.liquidation_threshold,
+        max_close_factor: config.max_close_factor,
         price_timeframe: config.price_timeframe,
+        price_mode: config.price_mode,
+        guardian: match config.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?),
+            None => None,
+        },
+        min_liquidation: config.min_liquidation,
+        reserve_contract: match config.reserve_contract {
+            Some(reserve_contract) => Some(deps.api.human_address(&reserve_contract)?),
+            None => None,
+        },
+        reserve_fee_share: config.reserve_fee_share,
+        referral_fee_share: config.referral_fee_share,
+        dust_threshold: config.dust_threshold,
+        delegated_bids_enabled: config.delegated_bids_enabled,
+        reward_token: match config.reward_token {
+            Some(reward_token) => Some(deps.api.human_address(&reward_token)?),
+            None => None,
+        },
+        max_fill_per_window: config.max_fill_per_window,
+        fill_window: config.fill_window,
+        fee_admin: match config.fee_admin {
+            Some(fee_admin) => Some(deps.api.human_address(&fee_admin)?),
+            None => None,
+        },
+        overseer_contract: match config.overseer_contract {
+            Some(overseer_contract) => Some(deps.api.human_address(&overseer_contract)?),
+            None => None,
+        },
     };
 
     Ok(resp)
 }
 
+fn assert_max_close_factor(max_close_factor: Decimal256) -> Result<(), ContractError> {
+    if max_close_factor == Decimal256::zero() || max_close_factor > Decimal256::one() {
+        return Err(ContractError::InvalidCloseFactor {});
+    }
+
+    Ok(())
+}
+
+fn assert_fees(bid_fee: Decimal256, liquidator_fee: Decimal256) -> Result<(), ContractError> {
+    if bid_fee + liquidator_fee >= Decimal256::one() {
+        return Err(ContractError::InvalidFees {});
+    }
+
+    Ok(())
+}
+
+fn query_pending_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<PendingConfigResponse> {
+    let pending_config: PendingConfig = match read_pending_config(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(ContractError::NoPendingConfig {}.into()),
+    };
+
+    Ok(PendingConfigResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        safe_ratio: pending_config.safe_ratio,
+        bid_fee: pending_config.bid_fee,
+        liquidator_fee: pending_config.liquidator_fee,
+        max_premium_rate: pending_config.max_premium_rate,
+        liquidation_threshold: pending_config.liquidation_threshold,
+        max_close_factor: pending_config.max_close_factor,
+        effective_at: pending_config.effective_at,
+    })
+}
+
+fn query_blacklist<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<BlacklistResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let addresses: Vec<HumanAddr> = read_blacklist(deps, start_after, limit)?;
+    Ok(BlacklistResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        addresses,
+    })
+}
+
+fn query_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<AllowlistResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    Ok(AllowlistResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        enabled: read_allowlist_enabled(&deps.storage)?,
+        addresses: read_allowlist(deps, start_after, limit)?,
+    })
+}
+
+fn query_deprecated_collaterals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<DeprecatedCollateralsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let collaterals: Vec<HumanAddr> = read_deprecated_collaterals(deps, start_after, limit)?;
+    Ok(DeprecatedCollateralsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collaterals,
+    })
+}
+
+fn query_collateral_status<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    block_time: u64,
+) -> StdResult<CollateralStatusResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let price: PriceResponse = query_price_with_mode(
+        deps,
+        &collateral_oracle(deps, &config, &collateral_token_raw)?,
+        collateral_token.to_string(),
+        config.stable_denom.clone(),
+        Some(config.price_mode.clone()),
+        None,
+    )?;
+
+    let price_status = if is_price_stale(&price, block_time, config.price_timeframe) {
+        PriceStatus::Stale
+    } else {
+        PriceStatus::Fresh
+    };
+
+    Ok(CollateralStatusResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        paused: is_collateral_paused(&deps.storage, &collateral_token_raw)?,
+        collateral_token,
+        price_status,
+        last_updated_base: price.last_updated_base,
+        last_updated_quote: price.last_updated_quote,
+    })
+}
+
+fn query_collateral_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<CollateralInfoResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let decimals = read_collateral_decimals(&deps.storage, &collateral_token_raw)?;
+    let price_bounds: PriceBounds = read_price_bounds(&deps.storage, &collateral_token_raw)?;
+    let price_source = match read_price_source(&deps.storage, &collateral_token_raw)? {
+        Some(oracle_contract) => Some(deps.api.human_address(&oracle_contract)?),
+        None => None,
+    };
+
+    Ok(CollateralInfoResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        decimals,
+        min_price: price_bounds.min_price,
+        max_price: price_bounds.max_price,
+        price_source,
+    })
+}
+
 fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     borrow_amount: Uint256,
@@ -235,54 +1404,134 @@ fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
     // Safely collateralized check
     if borrow_amount <= borrow_limit {
         return Ok(LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
             collaterals: vec![],
         });
     }
 
     let mut collaterals_value = Uint256::zero();
+    let mut collateral_values: Vec<Uint256> = vec![];
     for c in collaterals.iter().zip(collateral_prices.iter()) {
         let (collateral, price) = c;
-        let collateral_value = collateral.1 * *price;
+        let decimals =
+            read_collateral_decimals(&deps.storage, &deps.api.canonical_address(&collateral.0)?)?;
+        let collateral_value = normalize_amount(collateral.1, decimals, Rounding::Down) * *price;
         collaterals_value += collateral_value;
+        collateral_values.push(collateral_value);
     }
 
-    let tax_rate = query_tax_rate(&deps)?;
+    let tax_rate = query_tax_rate(deps)?;
 
     let fee_deductor = (Decimal256::one() - config.max_premium_rate)
-        * (Decimal256::one() - config.bid_fee)
+        * (Decimal256::one() - config.bid_fee - config.liquidator_fee)
         * (Decimal256::one() - tax_rate);
 
     // expected_repay_amount must be bigger than borrow_amount
     // else force liquidate all collaterals
     let expected_repay_amount = collaterals_value * fee_deductor;
-    if expected_repay_amount <= borrow_amount {
-        return Ok(LiquidationAmountResponse { collaterals });
-    }
-
-    // When collaterals_value is smaller than liquidation_threshold,
-    // liquidate all collaterals
-    let safe_borrow_amount = borrow_limit * config.safe_ratio;
-    let liquidation_ratio = if collaterals_value < config.liquidation_threshold {
+    let is_small_position = collaterals_value < config.liquidation_threshold;
+    let liquidation_ratio = if expected_repay_amount <= borrow_amount {
+        Decimal256::one()
+    } else if is_small_position {
+        // When collaterals_value is smaller than liquidation_threshold,
+        // liquidate all collaterals
         Decimal256::from_uint256(borrow_amount) / Decimal256::from_uint256(expected_repay_amount)
     } else {
+        let safe_borrow_amount = borrow_limit * config.safe_ratio;
         Decimal256::from_uint256(borrow_amount - safe_borrow_amount)
             / Decimal256::from_uint256(expected_repay_amount - safe_borrow_amount)
     };
 
-    // Cap the liquidation_ratio to 1
-    let liquidation_ratio = std::cmp::min(Decimal256::one(), liquidation_ratio);
+    // Cap the liquidation_ratio to 1, and to the close factor unless the
+    // position is small enough to be liquidated at once; the remaining
+    // risk is handled by subsequent liquidations
+    let max_ratio = if is_small_position {
+        Decimal256::one()
+    } else {
+        config.max_close_factor
+    };
+    let liquidation_ratio = std::cmp::min(max_ratio, liquidation_ratio);
     Ok(LiquidationAmountResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
         collaterals: collaterals
             .iter()
-            .zip(collateral_prices.iter())
+            .zip(collateral_values.iter())
+            // dust liquidations cost more gas than they recover
+            .filter(|(_, value)| **value * liquidation_ratio >= config.min_liquidation)
             .map(|c| {
                 let (collateral, _) = c;
                 let mut collateral = collateral.clone();
 
-                collateral.1 = collateral.1 * liquidation_ratio;
+                // never liquidate beyond the computed ratio
+                collateral.1 = rounding::mul(collateral.1, liquidation_ratio, Rounding::Down);
                 collateral
             })
             .filter(|c| c.1 > Uint256::zero())
             .collect::<TokensHuman>(),
     })
 }
+
+fn query_liquidation_amount_at_oracle_prices<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrow_amount: Uint256,
+    borrow_limit: Uint256,
+    collaterals: TokensHuman,
+    block_time: u64,
+) -> StdResult<LiquidationAmountResponse> {
+    let config: Config = read_config(&deps.storage)?;
+
+    let mut collateral_prices: Vec<Decimal256> = vec![];
+    for (collateral_token, _) in collaterals.iter() {
+        let oracle_contract = collateral_oracle(
+            deps,
+            &config,
+            &deps.api.canonical_address(collateral_token)?,
+        )?;
+        let price: PriceResponse = query_price_with_mode(
+            deps,
+            &oracle_contract,
+            collateral_token.to_string(),
+            config.stable_denom.clone(),
+            Some(config.price_mode.clone()),
+            None,
+        )?;
+
+        if is_price_stale(&price, block_time, config.price_timeframe) {
+            return Err(ContractError::PriceExpired {
+                collateral_token: collateral_token.clone(),
+                last_updated: std::cmp::min(price.last_updated_base, price.last_updated_quote),
+            }
+            .into());
+        }
+
+        collateral_prices.push(price.rate);
+    }
+
+    query_liquidation_amount(
+        deps,
+        borrow_amount,
+        borrow_limit,
+        collaterals,
+        collateral_prices,
+    )
+}
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<MigrateResponse, ContractError> {
+    let from_version = read_contract_version(&deps.storage)?;
+    migrate_storage(&mut deps.storage, from_version)?;
+    store_contract_version(&mut deps.storage, CONTRACT_VERSION)?;
+
+    Ok(MigrateResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "migrate"),
+            log("from_version", from_version),
+            log("to_version", CONTRACT_VERSION),
+        ],
+        data: None,
+    })
+}
@@ -0,0 +1,168 @@
+use crate::error::ContractError;
+use crate::state::{
+    read_claim_as, read_config, read_converter, remove_claim_as, remove_converter, store_claim_as,
+    store_converter, Config,
+};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HumanAddr, Querier, StdResult,
+    Storage, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use moneymarket::liquidation::{
+    ClaimAsResponse, ClaimAsset, ConverterHookMsg, ConverterResponse, LIQUIDATION_SCHEMA_VERSION,
+};
+use moneymarket::payout::token_payout_msg;
+
+pub fn register_converter<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    claim_as: ClaimAsset,
+    converter: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    match &converter {
+        Some(converter) => store_converter(
+            &mut deps.storage,
+            &collateral_token_raw,
+            &claim_as,
+            &deps.api.canonical_address(converter)?,
+        )?,
+        None => remove_converter(&mut deps.storage, &collateral_token_raw, &claim_as),
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_converter"),
+            log("collateral_token", collateral_token),
+            log("claim_as", claim_as),
+            log(
+                "converter",
+                converter.map_or_else(String::new, |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_claim_as<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    claim_as: Option<ClaimAsset>,
+) -> Result<HandleResponse, ContractError> {
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    match &claim_as {
+        Some(claim_as) => {
+            if read_converter(&deps.storage, &collateral_token_raw, claim_as)?.is_none() {
+                return Err(ContractError::ConverterNotFound { collateral_token });
+            }
+
+            store_claim_as(
+                &mut deps.storage,
+                &bidder_raw,
+                &collateral_token_raw,
+                claim_as,
+            )?
+        }
+        None => remove_claim_as(&mut deps.storage, &bidder_raw, &collateral_token_raw),
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_claim_as"),
+            log("bidder", env.message.sender),
+            log("collateral_token", collateral_token),
+            log(
+                "claim_as",
+                claim_as.map_or_else(String::new, |v| v.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+/// Send the bought collateral to the bidder, through the converter
+/// of its claim preference if one is registered. A missing converter
+/// falls back to a plain transfer so it cannot block liquidations
+pub fn collateral_payout_msg<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    bidder: &HumanAddr,
+    collateral_token: HumanAddr,
+    amount: Uint256,
+) -> StdResult<CosmosMsg> {
+    let bidder_raw = deps.api.canonical_address(bidder)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let converter = match read_claim_as(&deps.storage, &bidder_raw, &collateral_token_raw)? {
+        Some(claim_as) => read_converter(&deps.storage, &collateral_token_raw, &claim_as)?
+            .map(|converter| (claim_as, converter)),
+        None => None,
+    };
+
+    match converter {
+        Some((claim_as, converter)) => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: deps.api.human_address(&converter)?,
+                amount: amount.into(),
+                msg: Some(to_binary(&ConverterHookMsg::Convert {
+                    claim_as,
+                    recipient: bidder.clone(),
+                })?),
+            })?,
+        })),
+        None => token_payout_msg(collateral_token, bidder.clone(), amount),
+    }
+}
+
+pub fn query_converter<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    claim_as: ClaimAsset,
+) -> StdResult<ConverterResponse> {
+    let converter = read_converter(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+        &claim_as,
+    )?;
+
+    Ok(ConverterResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        claim_as,
+        converter: match converter {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+    })
+}
+
+pub fn query_claim_as<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    bidder: HumanAddr,
+) -> StdResult<ClaimAsResponse> {
+    let claim_as = read_claim_as(
+        &deps.storage,
+        &deps.api.canonical_address(&bidder)?,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(ClaimAsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        collateral_token,
+        bidder,
+        claim_as,
+    })
+}
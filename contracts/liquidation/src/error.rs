@@ -0,0 +1,298 @@
+use std::fmt;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{HumanAddr, StdError};
+
+/// Errors returned by the handlers. The messages are part of the
+/// interface, so callers can match either the variant or its message
+#[derive(Debug)]
+pub enum ContractError {
+    Std(StdError),
+    Unauthorized {},
+    InvalidRequest {},
+    DepositsPaused {},
+    WithdrawalsPaused {},
+    LiquidationsPaused {},
+    CollateralPaused {
+        collateral_token: HumanAddr,
+    },
+    CollateralDeprecated {
+        collateral_token: HumanAddr,
+    },
+    DuplicateCollateral {
+        collateral_token: HumanAddr,
+    },
+    NoCollateralsToBid {},
+    NoCollateralsToLiquidate {},
+    InvalidCollateralDecimals {
+        max_decimals: u8,
+    },
+    Blacklisted {
+        address: HumanAddr,
+    },
+    NotAllowlisted {
+        address: HumanAddr,
+    },
+    DelegatedBidsDisabled {},
+    BidsMigrating {},
+    BidsMigratingTo {
+        new_liquidation_queue: HumanAddr,
+    },
+    BidNotFound {},
+    BidAlreadyExists {
+        collateral_token: HumanAddr,
+    },
+    CrossBidNotFound {},
+    CrossBidAlreadyExists {},
+    BidExpired {},
+    InvalidBidExpiry {},
+    SamePremiumRate {
+        premium_rate: Decimal256,
+    },
+    PremiumRateTooHigh {
+        max_premium_rate: Decimal256,
+    },
+    PremiumSlotOverflow {
+        premium_rate: Decimal256,
+    },
+    NoStableProvided {
+        denom: String,
+    },
+    RetractExceedsBid {
+        bid_amount: Uint256,
+    },
+    InsufficientBidAmount {
+        required_amount: Uint256,
+    },
+    InsufficientStagedCollateral {
+        collateral_token: HumanAddr,
+        staged_amount: Uint256,
+    },
+    PriceExpired {
+        collateral_token: HumanAddr,
+        last_updated: u64,
+    },
+    FillLimitExceeded {
+        collateral_token: HumanAddr,
+        remaining_limit: Uint256,
+    },
+    PriceOutOfBounds {
+        collateral_token: HumanAddr,
+        price: Decimal256,
+    },
+    InvalidPriceBounds {},
+    InvalidCloseFactor {},
+    InvalidReserveFeeShare {},
+    InvalidFeeShares {},
+    InvalidFees {},
+    NoPendingConfig {},
+    PendingConfigNotEffective {
+        effective_at: u64,
+    },
+    TrackedFunds {
+        asset: String,
+    },
+    ReserveContractNotSet {},
+    DustSweepingDisabled {},
+    NoDust {},
+    ConverterNotFound {
+        collateral_token: HumanAddr,
+    },
+    RewardTokenNotSet {},
+    NoRewards {},
+    NoReferralFees {},
+    NoFees {},
+    WithdrawExceedsFees {
+        pending_fees: Uint256,
+    },
+    NewerContractVersion {
+        version: u64,
+    },
+    MigrationNotFound {
+        version: u64,
+    },
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContractError::Std(err) => write!(f, "{}", err),
+            ContractError::Unauthorized {} => write!(f, "Unauthorized"),
+            ContractError::InvalidRequest {} => write!(
+                f,
+                "Invalid request: \"execute bid\" message not included in request"
+            ),
+            ContractError::DepositsPaused {} => write!(f, "Deposits are paused"),
+            ContractError::WithdrawalsPaused {} => write!(f, "Withdrawals are paused"),
+            ContractError::LiquidationsPaused {} => write!(f, "Liquidations are paused"),
+            ContractError::CollateralPaused { collateral_token } => write!(
+                f,
+                "Liquidations are paused for collateral: {}",
+                collateral_token
+            ),
+            ContractError::CollateralDeprecated { collateral_token } => {
+                write!(f, "Collateral is deprecated: {}", collateral_token)
+            }
+            ContractError::DuplicateCollateral { collateral_token } => {
+                write!(f, "Duplicate collateral: {}", collateral_token)
+            }
+            ContractError::NoCollateralsToBid {} => write!(f, "No collaterals to bid on"),
+            ContractError::NoCollateralsToLiquidate {} => {
+                write!(f, "No collaterals to liquidate")
+            }
+            ContractError::InvalidCollateralDecimals { max_decimals } => {
+                write!(f, "Collateral decimals cannot exceed {}", max_decimals)
+            }
+            ContractError::Blacklisted { address } => {
+                write!(f, "Address is blacklisted: {}", address)
+            }
+            ContractError::NotAllowlisted { address } => {
+                write!(f, "Address is not allowlisted: {}", address)
+            }
+            ContractError::DelegatedBidsDisabled {} => write!(f, "Delegated bids are disabled"),
+            ContractError::BidsMigrating {} => write!(f, "Bids are being migrated"),
+            ContractError::BidsMigratingTo {
+                new_liquidation_queue,
+            } => write!(f, "Bids are being migrated to: {}", new_liquidation_queue),
+            ContractError::BidNotFound {} => {
+                write!(f, "No bids with the specified information exist")
+            }
+            ContractError::BidAlreadyExists { collateral_token } => write!(
+                f,
+                "User already has bid for specified collateral: {}",
+                collateral_token
+            ),
+            ContractError::CrossBidNotFound {} => write!(f, "No cross bid exists"),
+            ContractError::CrossBidAlreadyExists {} => write!(f, "User already has a cross bid"),
+            ContractError::BidExpired {} => write!(f, "Bid has expired"),
+            ContractError::InvalidBidExpiry {} => write!(f, "Bid expiry must be in the future"),
+            ContractError::SamePremiumRate { premium_rate } => {
+                write!(f, "Bid already has the premium rate: {}", premium_rate)
+            }
+            ContractError::PremiumRateTooHigh { max_premium_rate } => write!(
+                f,
+                "Premium rate cannot exceed the max premium rate: {}",
+                max_premium_rate
+            ),
+            ContractError::PremiumSlotOverflow { premium_rate } => write!(
+                f,
+                "Premium rate does not fit a liquidation queue premium slot: {}",
+                premium_rate
+            ),
+            ContractError::NoStableProvided { denom } => {
+                write!(f, "No {} assets have been provided", denom)
+            }
+            ContractError::RetractExceedsBid { bid_amount } => {
+                write!(
+                    f,
+                    "Retract amount cannot exceed bid balance: {}",
+                    bid_amount
+                )
+            }
+            ContractError::InsufficientBidAmount { required_amount } => write!(
+                f,
+                "Insufficient bid balance; Required balance: {}",
+                required_amount
+            ),
+            ContractError::InsufficientStagedCollateral {
+                collateral_token,
+                staged_amount,
+            } => write!(
+                f,
+                "Insufficient staged collateral: {}; staged amount: {}",
+                collateral_token, staged_amount
+            ),
+            ContractError::PriceExpired {
+                collateral_token,
+                last_updated,
+            } => write!(
+                f,
+                "Oracle price is stale for collateral: {}; last updated: {}",
+                collateral_token, last_updated
+            ),
+            ContractError::FillLimitExceeded {
+                collateral_token,
+                remaining_limit,
+            } => write!(
+                f,
+                "Fill limit exceeded for collateral: {}; remaining limit: {}",
+                collateral_token, remaining_limit
+            ),
+            ContractError::PriceOutOfBounds {
+                collateral_token,
+                price,
+            } => write!(
+                f,
+                "Oracle price is out of bounds for collateral: {}; price: {}",
+                collateral_token, price
+            ),
+            ContractError::InvalidPriceBounds {} => {
+                write!(f, "Min price cannot be bigger than max price")
+            }
+            ContractError::InvalidCloseFactor {} => write!(
+                f,
+                "Max close factor must be bigger than zero and not exceed one"
+            ),
+            ContractError::InvalidReserveFeeShare {} => {
+                write!(f, "Reserve fee share cannot be bigger than one")
+            }
+            ContractError::InvalidFeeShares {} => {
+                write!(f, "Reserve and referral fee shares cannot exceed one")
+            }
+            ContractError::InvalidFees {} => write!(
+                f,
+                "Bid fee and liquidator fee together must be less than one"
+            ),
+            ContractError::NoPendingConfig {} => write!(f, "No pending config change"),
+            ContractError::PendingConfigNotEffective { effective_at } => {
+                write!(f, "Pending config is not effective until {}", effective_at)
+            }
+            ContractError::TrackedFunds { asset } => {
+                write!(f, "Cannot sweep tracked funds: {}", asset)
+            }
+            ContractError::ReserveContractNotSet {} => write!(f, "Reserve contract is not set"),
+            ContractError::DustSweepingDisabled {} => write!(f, "Dust sweeping is disabled"),
+            ContractError::NoDust {} => write!(f, "No dust to sweep"),
+            ContractError::ConverterNotFound { collateral_token } => write!(
+                f,
+                "No converter registered for collateral: {}",
+                collateral_token
+            ),
+            ContractError::RewardTokenNotSet {} => write!(f, "Reward token is not set"),
+            ContractError::NoRewards {} => write!(f, "No rewards to claim"),
+            ContractError::NoReferralFees {} => write!(f, "No referral fees to claim"),
+            ContractError::NoFees {} => write!(f, "No fees to withdraw"),
+            ContractError::WithdrawExceedsFees { pending_fees } => write!(
+                f,
+                "Withdraw amount cannot exceed the accrued fees: {}",
+                pending_fees
+            ),
+            ContractError::NewerContractVersion { version } => write!(
+                f,
+                "Cannot migrate from a newer contract version: {}",
+                version
+            ),
+            ContractError::MigrationNotFound { version } => {
+                write!(f, "No migration from contract version: {}", version)
+            }
+        }
+    }
+}
+
+impl From<StdError> for ContractError {
+    fn from(err: StdError) -> Self {
+        ContractError::Std(err)
+    }
+}
+
+/// Queries keep returning StdError, carrying the message of the
+/// contract error they share with the handlers
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            ContractError::Unauthorized {} => StdError::unauthorized(),
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
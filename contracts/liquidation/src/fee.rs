@@ -0,0 +1,69 @@
+use crate::error::ContractError;
+use crate::state::{read_accrued_fees, read_config, store_accrued_fees, AccruedFees, Config};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{log, Api, Env, Extern, HandleResponse, HumanAddr, Querier, StdResult, Storage};
+use moneymarket::liquidation::{AccruedFeesResponse, LIQUIDATION_SCHEMA_VERSION};
+use moneymarket::payout::native_payout_msg;
+
+pub fn withdraw_fees<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    recipient: HumanAddr,
+    amount: Option<Uint256>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if config.fee_admin != Some(deps.api.canonical_address(&env.message.sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut accrued_fees: AccruedFees = read_accrued_fees(&deps.storage)?;
+    let amount = amount.unwrap_or(accrued_fees.pending);
+    if amount.is_zero() {
+        return Err(ContractError::NoFees {});
+    }
+
+    if amount > accrued_fees.pending {
+        return Err(ContractError::WithdrawExceedsFees {
+            pending_fees: accrued_fees.pending,
+        });
+    }
+
+    accrued_fees.pending = accrued_fees.pending - amount;
+    accrued_fees.withdrawn += amount;
+    store_accrued_fees(&mut deps.storage, &accrued_fees)?;
+
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            recipient.clone(),
+            config.stable_denom,
+            amount,
+        )?],
+        log: vec![
+            log("action", "withdraw_fees"),
+            log("recipient", recipient),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_accrued_fees<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<AccruedFeesResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let accrued_fees: AccruedFees = read_accrued_fees(&deps.storage)?;
+
+    Ok(AccruedFeesResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        fee_admin: match config.fee_admin {
+            Some(fee_admin) => Some(deps.api.human_address(&fee_admin)?),
+            None => None,
+        },
+        denom: config.stable_denom,
+        pending_fees: accrued_fees.pending,
+        withdrawn_fees: accrued_fees.withdrawn,
+    })
+}
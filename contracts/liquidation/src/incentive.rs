@@ -0,0 +1,190 @@
+use crate::error::ContractError;
+use crate::state::{
+    read_bid_reward, read_bidder_stats, read_config, read_incentive_pool, store_bid_reward,
+    store_incentive_pool, BidReward, Config, IncentivePool,
+};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HumanAddr, Querier, StdResult, Storage,
+};
+use moneymarket::liquidation::{PendingRewardsResponse, LIQUIDATION_SCHEMA_VERSION};
+use moneymarket::payout::token_payout_msg;
+use moneymarket::rounding::{self, Rounding};
+
+/// Accrue the rewards of the collateral pool up to the block time
+fn accrue_pool_rewards(pool: &mut IncentivePool, block_time: u64) {
+    if block_time <= pool.last_updated {
+        return;
+    }
+
+    if !pool.total_bid_amount.is_zero() {
+        let elapsed = Uint256::from(block_time - pool.last_updated);
+        pool.reward_index += Decimal256::from_uint256(elapsed) * pool.emission_rate
+            / Decimal256::from_uint256(pool.total_bid_amount);
+    }
+
+    pool.last_updated = block_time;
+}
+
+/// Settle the bidder rewards earned on `active_before` and update
+/// the pool share to `active_after`. Bids placed before the bidder
+/// first touched the incentives only start accruing from then on
+pub fn update_bid_rewards<S: Storage>(
+    storage: &mut S,
+    block_time: u64,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    active_before: Uint256,
+    active_after: Uint256,
+) -> StdResult<BidReward> {
+    let mut pool: IncentivePool = read_incentive_pool(storage, collateral_token)?;
+    accrue_pool_rewards(&mut pool, block_time);
+
+    // a bidder without reward info is not counted in the pool yet
+    let (reward, counted_before) = match read_bid_reward(storage, bidder, collateral_token)? {
+        Some(v) => (
+            BidReward {
+                reward_index: pool.reward_index,
+                pending_rewards: v.pending_rewards
+                    + Decimal256::from_uint256(active_before)
+                        * (pool.reward_index - v.reward_index),
+            },
+            active_before,
+        ),
+        None => (
+            BidReward {
+                reward_index: pool.reward_index,
+                pending_rewards: Decimal256::zero(),
+            },
+            Uint256::zero(),
+        ),
+    };
+    store_bid_reward(storage, bidder, collateral_token, &reward)?;
+
+    pool.total_bid_amount = if pool.total_bid_amount + active_after > counted_before {
+        pool.total_bid_amount + active_after - counted_before
+    } else {
+        Uint256::zero()
+    };
+    store_incentive_pool(storage, collateral_token, &pool)?;
+
+    Ok(reward)
+}
+
+/// Executor: owner
+pub fn set_incentive_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    emission_rate: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // rewards accrued so far are paid at the previous rate
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut pool: IncentivePool = read_incentive_pool(&deps.storage, &collateral_token_raw)?;
+    accrue_pool_rewards(&mut pool, env.block.time);
+    pool.emission_rate = emission_rate;
+    store_incentive_pool(&mut deps.storage, &collateral_token_raw, &pool)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_incentive_rate"),
+            log("collateral_token", collateral_token),
+            log("emission_rate", emission_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn claim_rewards<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    let reward_token = match config.reward_token {
+        Some(v) => deps.api.human_address(&v)?,
+        None => return Err(ContractError::RewardTokenNotSet {}),
+    };
+
+    let bidder_raw = deps.api.canonical_address(&env.message.sender)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let stats = read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+    let mut reward: BidReward = update_bid_rewards(
+        &mut deps.storage,
+        env.block.time,
+        &bidder_raw,
+        &collateral_token_raw,
+        stats.active_bid_amount,
+        stats.active_bid_amount,
+    )?;
+
+    // the fraction below one token unit stays pending
+    let claim_amount = rounding::to_uint(reward.pending_rewards, Rounding::Down);
+    if claim_amount.is_zero() {
+        return Err(ContractError::NoRewards {});
+    }
+
+    reward.pending_rewards = reward.pending_rewards - Decimal256::from_uint256(claim_amount);
+    store_bid_reward(
+        &mut deps.storage,
+        &bidder_raw,
+        &collateral_token_raw,
+        &reward,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![token_payout_msg(
+            reward_token,
+            env.message.sender.clone(),
+            claim_amount,
+        )?],
+        log: vec![
+            log("action", "claim_rewards"),
+            log("collateral_token", collateral_token),
+            log("bidder", env.message.sender),
+            log("amount", claim_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_pending_rewards<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    bidder: HumanAddr,
+    collateral_token: HumanAddr,
+    block_time: Option<u64>,
+) -> StdResult<PendingRewardsResponse> {
+    let bidder_raw = deps.api.canonical_address(&bidder)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+
+    let mut pool: IncentivePool = read_incentive_pool(&deps.storage, &collateral_token_raw)?;
+    if let Some(block_time) = block_time {
+        accrue_pool_rewards(&mut pool, block_time);
+    }
+
+    let pending_rewards = match read_bid_reward(&deps.storage, &bidder_raw, &collateral_token_raw)?
+    {
+        Some(v) => {
+            let stats = read_bidder_stats(&deps.storage, &bidder_raw, &collateral_token_raw)?;
+            v.pending_rewards
+                + Decimal256::from_uint256(stats.active_bid_amount)
+                    * (pool.reward_index - v.reward_index)
+        }
+        None => Decimal256::zero(),
+    };
+
+    Ok(PendingRewardsResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        bidder,
+        collateral_token,
+        emission_rate: pool.emission_rate,
+        pending_rewards: rounding::to_uint(pending_rewards, Rounding::Down),
+    })
+}
@@ -1,9 +1,15 @@
 mod bid;
 pub mod contract;
+mod converter;
+mod error;
+mod fee;
+mod incentive;
+mod migration;
+mod referral;
 pub mod state;
 
 #[cfg(test)]
 mod testing;
 
 #[cfg(all(target_arch = "wasm32", not(feature = "library")))]
-cosmwasm_std::create_entry_points!(contract);
+cosmwasm_std::create_entry_points_with_migration!(contract);
@@ -0,0 +1,45 @@
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::state::{count_bids, read_config, store_bid_count, store_config, CONTRACT_VERSION};
+
+/// Run every migration step from `from_version` up to the current
+/// contract version. Each step moves the storage exactly one version
+/// forward, so a later release only needs to add its own step here.
+pub fn migrate_storage<S: Storage>(
+    storage: &mut S,
+    from_version: u64,
+) -> Result<(), ContractError> {
+    if from_version > CONTRACT_VERSION {
+        return Err(ContractError::NewerContractVersion {
+            version: from_version,
+        });
+    }
+
+    for version in from_version..CONTRACT_VERSION {
+        match version {
+            0 => migrate_v0_to_v1(storage)?,
+            1 => migrate_v1_to_v2(storage)?,
+            _ => return Err(ContractError::MigrationNotFound { version }),
+        }
+    }
+
+    Ok(())
+}
+
+/// Contracts deployed before versioning keep config fields added
+/// later only as serde defaults; store them explicitly
+fn migrate_v0_to_v1<S: Storage>(storage: &mut S) -> StdResult<()> {
+    let config = read_config(storage)?;
+    store_config(storage, &config)
+}
+
+/// Bid counts are kept per bidder from v2 on; count the bids
+/// stored before that once
+fn migrate_v1_to_v2<S: Storage>(storage: &mut S) -> StdResult<()> {
+    for (bidder, bid_count) in count_bids(storage)? {
+        store_bid_count(storage, &bidder, bid_count)?;
+    }
+
+    Ok(())
+}
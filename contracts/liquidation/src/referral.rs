@@ -0,0 +1,56 @@
+use crate::error::ContractError;
+use crate::state::{read_config, read_referral_fees, store_referral_fees, Config, ReferralFees};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{log, Api, Env, Extern, HandleResponse, HumanAddr, Querier, StdResult, Storage};
+use moneymarket::liquidation::{ReferralFeesResponse, LIQUIDATION_SCHEMA_VERSION};
+use moneymarket::payout::native_payout_msg;
+
+pub fn claim_referral_fees<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(&deps.storage)?;
+    let referrer_raw = deps.api.canonical_address(&env.message.sender)?;
+    let mut referral_fees: ReferralFees = read_referral_fees(&deps.storage, &referrer_raw)?;
+
+    let amount = referral_fees.pending;
+    if amount.is_zero() {
+        return Err(ContractError::NoReferralFees {});
+    }
+
+    referral_fees.pending = Uint256::zero();
+    referral_fees.claimed += amount;
+    store_referral_fees(&mut deps.storage, &referrer_raw, &referral_fees)?;
+
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            env.message.sender.clone(),
+            config.stable_denom,
+            amount,
+        )?],
+        log: vec![
+            log("action", "claim_referral_fees"),
+            log("referrer", env.message.sender),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_referral_fees<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    referrer: HumanAddr,
+) -> StdResult<ReferralFeesResponse> {
+    let referral_fees: ReferralFees =
+        read_referral_fees(&deps.storage, &deps.api.canonical_address(&referrer)?)?;
+
+    Ok(ReferralFeesResponse {
+        schema_version: LIQUIDATION_SCHEMA_VERSION,
+        referrer,
+        pending_fees: referral_fees.pending,
+        claimed_fees: referral_fees.claimed,
+    })
+}
@@ -7,13 +7,87 @@ use cosmwasm_std::{
     Storage,
 };
 use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
-use moneymarket::liquidation::BidResponse;
+use moneymarket::decimals::DEFAULT_DECIMALS;
+use moneymarket::liquidation::{BidResponse, ClaimAsset, LIQUIDATION_SCHEMA_VERSION};
+use moneymarket::oracle::PriceMode;
+use moneymarket::pause::PauseInfo;
+use moneymarket::raw_state::Namespace;
 
 static KEY_CONFIG: &[u8] = b"config";
+static KEY_PAUSE_INFO: &[u8] = b"pause_info";
+static KEY_PENDING_CONFIG: &[u8] = b"pending_config";
+static KEY_LIQUIDATION_RECORD_IDX: &[u8] = b"liquidation_record_idx";
+static KEY_ALLOWLIST_ENABLED: &[u8] = b"allowlist_enabled";
+static KEY_BID_MIGRATION: &[u8] = b"bid_migration";
+static KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
+static KEY_ACCRUED_FEES: &[u8] = b"accrued_fees";
+
+/// Version of the storage layout; bumped together with a new
+/// migration step whenever the layout changes
+pub const CONTRACT_VERSION: u64 = 2;
+
+/// Delay in seconds before a risk parameter change takes effect
+pub const CONFIG_TIMELOCK_PERIOD: u64 = 86400;
 
 static PREFIX_BID: &[u8] = b"bid";
 static PREFIX_BID_BY_USER: &[u8] = b"bid_by_user";
 static PREFIX_BID_BY_COLLATERAL: &[u8] = b"bid_by_collateral";
+static PREFIX_BID_COUNT: &[u8] = b"bid_count";
+static PREFIX_CROSS_BID: &[u8] = b"cross_bid";
+static PREFIX_BLACKLIST: &[u8] = b"blacklist";
+static PREFIX_ALLOWLIST: &[u8] = b"allowlist";
+static PREFIX_PAUSED_COLLATERAL: &[u8] = b"paused_collateral";
+static PREFIX_DEPRECATED_COLLATERAL: &[u8] = b"deprecated_collateral";
+static PREFIX_COLLATERAL_DECIMALS: &[u8] = b"collateral_decimals";
+static PREFIX_PRICE_BOUNDS: &[u8] = b"price_bounds";
+static PREFIX_PRICE_SOURCE: &[u8] = b"price_source";
+static PREFIX_COLLATERAL_CUSTODY: &[u8] = b"collateral_custody";
+static PREFIX_LIQUIDATION_RECORD: &[u8] = b"liquidation_record";
+static PREFIX_BIDDER_STATS: &[u8] = b"bidder_stats";
+static PREFIX_STAGED_COLLATERAL: &[u8] = b"staged_collateral";
+static PREFIX_REFERRAL_FEES: &[u8] = b"referral_fees";
+static PREFIX_SWEPT_DUST: &[u8] = b"swept_dust";
+static PREFIX_CONVERTER: &[u8] = b"converter";
+static PREFIX_CLAIM_AS: &[u8] = b"claim_as";
+static PREFIX_INCENTIVE_POOL: &[u8] = b"incentive_pool";
+static PREFIX_BID_REWARD: &[u8] = b"bid_reward";
+static PREFIX_FILL_WINDOW: &[u8] = b"fill_window";
+
+/// Protocol state which can be exported with the RawState query
+pub fn exportable_namespaces() -> Vec<Namespace> {
+    vec![
+        Namespace::Singleton(KEY_CONFIG),
+        Namespace::Singleton(KEY_PAUSE_INFO),
+        Namespace::Singleton(KEY_LIQUIDATION_RECORD_IDX),
+        Namespace::Singleton(KEY_BID_MIGRATION),
+        Namespace::Singleton(KEY_CONTRACT_VERSION),
+        Namespace::Singleton(KEY_ACCRUED_FEES),
+        Namespace::Bucket(PREFIX_BID),
+        Namespace::Bucket(PREFIX_BID_BY_USER),
+        Namespace::Bucket(PREFIX_BID_BY_COLLATERAL),
+        Namespace::Bucket(PREFIX_BID_COUNT),
+        Namespace::Bucket(PREFIX_CROSS_BID),
+        Namespace::Bucket(PREFIX_BLACKLIST),
+        Namespace::Singleton(KEY_ALLOWLIST_ENABLED),
+        Namespace::Bucket(PREFIX_ALLOWLIST),
+        Namespace::Bucket(PREFIX_PAUSED_COLLATERAL),
+        Namespace::Bucket(PREFIX_DEPRECATED_COLLATERAL),
+        Namespace::Bucket(PREFIX_COLLATERAL_DECIMALS),
+        Namespace::Bucket(PREFIX_PRICE_BOUNDS),
+        Namespace::Bucket(PREFIX_PRICE_SOURCE),
+        Namespace::Bucket(PREFIX_COLLATERAL_CUSTODY),
+        Namespace::Bucket(PREFIX_LIQUIDATION_RECORD),
+        Namespace::Bucket(PREFIX_BIDDER_STATS),
+        Namespace::Bucket(PREFIX_STAGED_COLLATERAL),
+        Namespace::Bucket(PREFIX_REFERRAL_FEES),
+        Namespace::Bucket(PREFIX_SWEPT_DUST),
+        Namespace::Bucket(PREFIX_CONVERTER),
+        Namespace::Bucket(PREFIX_CLAIM_AS),
+        Namespace::Bucket(PREFIX_INCENTIVE_POOL),
+        Namespace::Bucket(PREFIX_BID_REWARD),
+        Namespace::Bucket(PREFIX_FILL_WINDOW),
+    ]
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -22,9 +96,35 @@ pub struct Config {
     pub stable_denom: String,
     pub safe_ratio: Decimal256,
     pub bid_fee: Decimal256,
+    pub liquidator_fee: Decimal256,
     pub max_premium_rate: Decimal256,
     pub liquidation_threshold: Uint256,
+    pub max_close_factor: Decimal256,
     pub price_timeframe: u64,
+    pub price_mode: PriceMode,
+    pub guardian: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub min_liquidation: Uint256,
+    #[serde(default)]
+    pub reserve_contract: Option<CanonicalAddr>,
+    #[serde(default = "Decimal256::zero")]
+    pub reserve_fee_share: Decimal256,
+    #[serde(default = "Decimal256::zero")]
+    pub referral_fee_share: Decimal256,
+    #[serde(default)]
+    pub dust_threshold: Uint256,
+    #[serde(default)]
+    pub delegated_bids_enabled: bool,
+    #[serde(default)]
+    pub reward_token: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub max_fill_per_window: Uint256,
+    #[serde(default)]
+    pub fill_window: u64,
+    #[serde(default)]
+    pub fee_admin: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub overseer_contract: Option<CanonicalAddr>,
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
@@ -35,10 +135,73 @@ pub fn read_config<S: ReadonlyStorage>(storage: &S) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
+pub fn store_contract_version<S: Storage>(storage: &mut S, version: u64) -> StdResult<()> {
+    singleton(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+/// Contracts deployed before versioning have no stored version
+/// and are treated as version zero
+pub fn read_contract_version<S: ReadonlyStorage>(storage: &S) -> StdResult<u64> {
+    Ok(singleton_read(storage, KEY_CONTRACT_VERSION)
+        .may_load()?
+        .unwrap_or(0))
+}
+
+/// Risk parameter changes waiting for their timelock to pass
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct PendingConfig {
+    pub safe_ratio: Option<Decimal256>,
+    pub bid_fee: Option<Decimal256>,
+    pub liquidator_fee: Option<Decimal256>,
+    pub max_premium_rate: Option<Decimal256>,
+    pub liquidation_threshold: Option<Uint256>,
+    pub max_close_factor: Option<Decimal256>,
+    pub effective_at: u64,
+}
+
+pub fn store_pending_config<S: Storage>(
+    storage: &mut S,
+    pending_config: &PendingConfig,
+) -> StdResult<()> {
+    singleton(storage, KEY_PENDING_CONFIG).save(pending_config)
+}
+
+pub fn remove_pending_config<S: Storage>(storage: &mut S) {
+    singleton::<S, PendingConfig>(storage, KEY_PENDING_CONFIG).remove()
+}
+
+pub fn read_pending_config<S: ReadonlyStorage>(storage: &S) -> StdResult<Option<PendingConfig>> {
+    singleton_read(storage, KEY_PENDING_CONFIG).may_load()
+}
+
+pub fn store_pause_info<S: Storage>(storage: &mut S, pause_info: &PauseInfo) -> StdResult<()> {
+    singleton(storage, KEY_PAUSE_INFO).save(pause_info)
+}
+
+pub fn read_pause_info<S: ReadonlyStorage>(storage: &S) -> StdResult<PauseInfo> {
+    Ok(singleton_read(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Bid {
     pub amount: Uint256,
     pub premium_rate: Decimal256,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub referrer: Option<CanonicalAddr>,
+    /// Receives the collateral bought by the bid instead of the bidder
+    #[serde(default)]
+    pub unlock_receiver: Option<CanonicalAddr>,
+}
+
+impl Bid {
+    pub fn is_expired(&self, block_time: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= block_time)
+    }
 }
 
 pub fn store_bid<S: Storage>(
@@ -55,8 +218,16 @@ pub fn store_bid<S: Storage>(
 
     let mut bid_user_index: Bucket<S, bool> =
         Bucket::multilevel(&[PREFIX_BID_BY_USER, bidder.as_slice()], storage);
+    let is_new_bid = bid_user_index
+        .may_load(collateral_token.as_slice())?
+        .is_none();
     bid_user_index.save(collateral_token.as_slice(), &true)?;
 
+    if is_new_bid {
+        let bid_count = read_bid_count(storage, bidder)?;
+        store_bid_count(storage, bidder, bid_count + 1)?;
+    }
+
     let mut bid_collateral_index: Bucket<S, bool> = Bucket::multilevel(
         &[PREFIX_BID_BY_COLLATERAL, collateral_token.as_slice()],
         storage,
@@ -70,12 +241,15 @@ pub fn remove_bid<S: Storage>(
     storage: &mut S,
     bidder: &CanonicalAddr,
     collateral_token: &CanonicalAddr,
-) {
+) -> StdResult<()> {
     let mut bid_bucket: Bucket<S, Bid> = Bucket::new(PREFIX_BID, storage);
     bid_bucket.remove(&[bidder.as_slice(), collateral_token.as_slice()].concat());
 
     let mut bid_user_index: Bucket<S, bool> =
         Bucket::multilevel(&[PREFIX_BID_BY_USER, bidder.as_slice()], storage);
+    let had_bid = bid_user_index
+        .may_load(collateral_token.as_slice())?
+        .is_some();
     bid_user_index.remove(collateral_token.as_slice());
 
     let mut bid_collateral_index: Bucket<S, bool> = Bucket::multilevel(
@@ -83,6 +257,53 @@ pub fn remove_bid<S: Storage>(
         storage,
     );
     bid_collateral_index.remove(bidder.as_slice());
+
+    if had_bid {
+        let bid_count = read_bid_count(storage, bidder)?;
+        store_bid_count(storage, bidder, bid_count.saturating_sub(1))?;
+    }
+
+    Ok(())
+}
+
+/// Number of bids of the bidder, kept with the bid
+/// index so it never needs a scan
+pub fn read_bid_count<S: Storage>(storage: &S, bidder: &CanonicalAddr) -> StdResult<u64> {
+    let count_bucket: ReadonlyBucket<S, u64> = ReadonlyBucket::new(PREFIX_BID_COUNT, storage);
+    Ok(count_bucket.may_load(bidder.as_slice())?.unwrap_or(0))
+}
+
+pub fn store_bid_count<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    bid_count: u64,
+) -> StdResult<()> {
+    let mut count_bucket: Bucket<S, u64> = Bucket::new(PREFIX_BID_COUNT, storage);
+    if bid_count == 0 {
+        count_bucket.remove(bidder.as_slice());
+        Ok(())
+    } else {
+        count_bucket.save(bidder.as_slice(), &bid_count)
+    }
+}
+
+/// Bid count of every bidder, counted from the stored bids.
+/// Scans the whole bid bucket, so only for migrations
+pub fn count_bids<S: Storage>(storage: &S) -> StdResult<Vec<(CanonicalAddr, u64)>> {
+    let bid_bucket: ReadonlyBucket<S, Bid> = ReadonlyBucket::new(PREFIX_BID, storage);
+
+    // bids are keyed by bidder first, so each bidder is a contiguous run
+    let mut bid_counts: Vec<(CanonicalAddr, u64)> = vec![];
+    for elem in bid_bucket.range(None, None, Order::Ascending) {
+        let (k, _) = elem?;
+        let bidder = CanonicalAddr::from(&k[..k.len() / 2]);
+        match bid_counts.last_mut() {
+            Some((last_bidder, count)) if *last_bidder == bidder => *count += 1,
+            _ => bid_counts.push((bidder, 1)),
+        }
+    }
+
+    Ok(bid_counts)
 }
 
 pub fn read_bid<'a, S: Storage>(
@@ -97,9 +318,350 @@ pub fn read_bid<'a, S: Storage>(
         .map_err(|_| StdError::generic_err("No bids with the specified information exist"))
 }
 
+/// First `limit` bids on the collateral which expired at `block_time`
+pub fn read_expired_bids<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+    block_time: u64,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Bid)>> {
+    let bid_bucket: ReadonlyBucket<S, bool> = ReadonlyBucket::multilevel(
+        &[PREFIX_BID_BY_COLLATERAL, collateral_token.as_slice()],
+        storage,
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    bid_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            let bidder = CanonicalAddr::from(k);
+            let bid = read_bid(storage, &bidder, collateral_token)?;
+            Ok((bidder, bid))
+        })
+        .filter(|elem: &StdResult<(CanonicalAddr, Bid)>| match elem {
+            Ok((_, bid)) => bid.is_expired(block_time),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Bids on the collateral with a non-zero amount below `threshold`
+pub fn read_dust_bids<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+    threshold: Uint256,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Bid)>> {
+    let bid_bucket: ReadonlyBucket<S, bool> = ReadonlyBucket::multilevel(
+        &[PREFIX_BID_BY_COLLATERAL, collateral_token.as_slice()],
+        storage,
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    bid_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            let bidder = CanonicalAddr::from(k);
+            let bid = read_bid(storage, &bidder, collateral_token)?;
+            Ok((bidder, bid))
+        })
+        .filter(|elem: &StdResult<(CanonicalAddr, Bid)>| match elem {
+            Ok((_, bid)) => !bid.amount.is_zero() && bid.amount < threshold,
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Liquidation queue the outstanding bids are moved into
+pub fn store_bid_migration<S: Storage>(
+    storage: &mut S,
+    new_liquidation_queue: &CanonicalAddr,
+) -> StdResult<()> {
+    singleton(storage, KEY_BID_MIGRATION).save(new_liquidation_queue)
+}
+
+pub fn read_bid_migration<S: ReadonlyStorage>(storage: &S) -> StdResult<Option<CanonicalAddr>> {
+    singleton_read(storage, KEY_BID_MIGRATION).may_load()
+}
+
+/// First `limit` bids in key order as (bidder, collateral_token, bid)
+pub fn read_bids<S: Storage>(
+    storage: &S,
+    start_after: Option<(CanonicalAddr, CanonicalAddr)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, CanonicalAddr, Bid)>> {
+    let bid_bucket: ReadonlyBucket<S, Bid> = ReadonlyBucket::new(PREFIX_BID, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|(bidder, collateral_token)| {
+        let mut v = [bidder.as_slice(), collateral_token.as_slice()].concat();
+        v.push(1);
+        v
+    });
+
+    bid_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+
+            // the key is the concatenation of two canonical
+            // addresses, which share the same length
+            let (bidder, collateral_token) = k.split_at(k.len() / 2);
+            Ok((
+                CanonicalAddr::from(bidder),
+                CanonicalAddr::from(collateral_token),
+                v,
+            ))
+        })
+        .collect()
+}
+
+/// Stable committed by a bidder to any collateral of the set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CrossBid {
+    pub collateral_tokens: Vec<CanonicalAddr>,
+    pub amount: Uint256,
+    pub premium_rate: Decimal256,
+}
+
+pub fn store_cross_bid<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    cross_bid: &CrossBid,
+) -> StdResult<()> {
+    let mut cross_bid_bucket: Bucket<S, CrossBid> = Bucket::new(PREFIX_CROSS_BID, storage);
+    cross_bid_bucket.save(bidder.as_slice(), cross_bid)
+}
+
+pub fn remove_cross_bid<S: Storage>(storage: &mut S, bidder: &CanonicalAddr) {
+    let mut cross_bid_bucket: Bucket<S, CrossBid> = Bucket::new(PREFIX_CROSS_BID, storage);
+    cross_bid_bucket.remove(bidder.as_slice());
+}
+
+pub fn read_cross_bid<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+) -> StdResult<Option<CrossBid>> {
+    let cross_bid_bucket: ReadonlyBucket<S, CrossBid> =
+        ReadonlyBucket::new(PREFIX_CROSS_BID, storage);
+    cross_bid_bucket.may_load(bidder.as_slice())
+}
+
+pub fn read_cross_bids<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, CrossBid)>> {
+    let cross_bid_bucket: ReadonlyBucket<S, CrossBid> =
+        ReadonlyBucket::new(PREFIX_CROSS_BID, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+    cross_bid_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_blacklisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) -> StdResult<()> {
+    let mut blacklist_bucket: Bucket<S, bool> = Bucket::new(PREFIX_BLACKLIST, storage);
+    blacklist_bucket.save(address.as_slice(), &true)
+}
+
+pub fn remove_blacklisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) {
+    let mut blacklist_bucket: Bucket<S, bool> = Bucket::new(PREFIX_BLACKLIST, storage);
+    blacklist_bucket.remove(address.as_slice());
+}
+
+pub fn is_blacklisted<S: Storage>(storage: &S, address: &CanonicalAddr) -> StdResult<bool> {
+    let blacklist_bucket: ReadonlyBucket<S, bool> = ReadonlyBucket::new(PREFIX_BLACKLIST, storage);
+    Ok(blacklist_bucket
+        .may_load(address.as_slice())?
+        .unwrap_or(false))
+}
+
+pub fn store_allowlist_enabled<S: Storage>(storage: &mut S, enabled: bool) -> StdResult<()> {
+    singleton(storage, KEY_ALLOWLIST_ENABLED).save(&enabled)
+}
+
+pub fn read_allowlist_enabled<S: ReadonlyStorage>(storage: &S) -> StdResult<bool> {
+    Ok(singleton_read(storage, KEY_ALLOWLIST_ENABLED)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+pub fn store_allowlisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) -> StdResult<()> {
+    let mut allowlist_bucket: Bucket<S, bool> = Bucket::new(PREFIX_ALLOWLIST, storage);
+    allowlist_bucket.save(address.as_slice(), &true)
+}
+
+pub fn remove_allowlisted<S: Storage>(storage: &mut S, address: &CanonicalAddr) {
+    let mut allowlist_bucket: Bucket<S, bool> = Bucket::new(PREFIX_ALLOWLIST, storage);
+    allowlist_bucket.remove(address.as_slice());
+}
+
+/// Every address is allowed unless the allowlist mode is enabled
+pub fn is_allowed<S: Storage>(storage: &S, address: &CanonicalAddr) -> StdResult<bool> {
+    if !read_allowlist_enabled(storage)? {
+        return Ok(true);
+    }
+
+    let allowlist_bucket: ReadonlyBucket<S, bool> = ReadonlyBucket::new(PREFIX_ALLOWLIST, storage);
+    Ok(allowlist_bucket
+        .may_load(address.as_slice())?
+        .unwrap_or(false))
+}
+
+pub fn store_collateral_paused<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    paused: bool,
+) -> StdResult<()> {
+    let mut paused_bucket: Bucket<S, bool> = Bucket::new(PREFIX_PAUSED_COLLATERAL, storage);
+    if paused {
+        paused_bucket.save(collateral_token.as_slice(), &true)
+    } else {
+        paused_bucket.remove(collateral_token.as_slice());
+        Ok(())
+    }
+}
+
+pub fn is_collateral_paused<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<bool> {
+    let paused_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_PAUSED_COLLATERAL, storage);
+    Ok(paused_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or(false))
+}
+
+pub fn store_collateral_decimals<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    decimals: u8,
+) -> StdResult<()> {
+    let mut decimals_bucket: Bucket<S, u8> = Bucket::new(PREFIX_COLLATERAL_DECIMALS, storage);
+    decimals_bucket.save(collateral_token.as_slice(), &decimals)
+}
+
+pub fn read_collateral_decimals<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<u8> {
+    let decimals_bucket: ReadonlyBucket<S, u8> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_DECIMALS, storage);
+    Ok(decimals_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or(DEFAULT_DECIMALS))
+}
+
+/// Oracle prices of a collateral accepted when executing bids
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct PriceBounds {
+    pub min_price: Option<Decimal256>,
+    pub max_price: Option<Decimal256>,
+}
+
+impl PriceBounds {
+    pub fn contains(&self, price: Decimal256) -> bool {
+        self.min_price.is_none_or(|min_price| price >= min_price)
+            && self.max_price.is_none_or(|max_price| price <= max_price)
+    }
+}
+
+pub fn store_price_bounds<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    price_bounds: &PriceBounds,
+) -> StdResult<()> {
+    let mut bounds_bucket: Bucket<S, PriceBounds> = Bucket::new(PREFIX_PRICE_BOUNDS, storage);
+    bounds_bucket.save(collateral_token.as_slice(), price_bounds)
+}
+
+pub fn read_price_bounds<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<PriceBounds> {
+    let bounds_bucket: ReadonlyBucket<S, PriceBounds> =
+        ReadonlyBucket::new(PREFIX_PRICE_BOUNDS, storage);
+    Ok(bounds_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn store_price_source<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    oracle_contract: Option<&CanonicalAddr>,
+) -> StdResult<()> {
+    let mut source_bucket: Bucket<S, CanonicalAddr> = Bucket::new(PREFIX_PRICE_SOURCE, storage);
+    match oracle_contract {
+        Some(oracle_contract) => source_bucket.save(collateral_token.as_slice(), oracle_contract),
+        None => {
+            source_bucket.remove(collateral_token.as_slice());
+            Ok(())
+        }
+    }
+}
+
+/// Oracle overriding the configured one for the collateral
+pub fn read_price_source<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    let source_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_PRICE_SOURCE, storage);
+    source_bucket.may_load(collateral_token.as_slice())
+}
+
+pub fn store_collateral_custody<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    custody_contract: Option<&CanonicalAddr>,
+) -> StdResult<()> {
+    let mut custody_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::new(PREFIX_COLLATERAL_CUSTODY, storage);
+    match custody_contract {
+        Some(custody_contract) => {
+            custody_bucket.save(collateral_token.as_slice(), custody_contract)
+        }
+        None => {
+            custody_bucket.remove(collateral_token.as_slice());
+            Ok(())
+        }
+    }
+}
+
+/// Custody allowed to stage the collateral
+pub fn read_collateral_custody<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<CanonicalAddr>> {
+    let custody_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_CUSTODY, storage);
+    custody_bucket.may_load(collateral_token.as_slice())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
+
+/// Number of entries a page holds for the requested limit
+pub fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
 pub fn read_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     collateral_token: &CanonicalAddr,
@@ -111,7 +673,7 @@ pub fn read_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
         &deps.storage,
     );
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = page_limit(limit);
     let start = calc_range_start(start_after);
 
     bid_bucket
@@ -120,18 +682,26 @@ pub fn read_bids_by_collateral<S: Storage, A: Api, Q: Querier>(
         .map(|elem| {
             let (k, _) = elem?;
             let bidder = CanonicalAddr::from(k);
-            let bid = read_bid(&deps.storage, &bidder, &collateral_token)?;
+            let bid = read_bid(&deps.storage, &bidder, collateral_token)?;
 
             let bidder: HumanAddr = deps.api.human_address(&bidder)?;
-            let collateral_token: HumanAddr = deps.api.human_address(&collateral_token)?;
+            let collateral_token: HumanAddr = deps.api.human_address(collateral_token)?;
             let amount = bid.amount;
             let premium_rate = bid.premium_rate;
+            let expires_at = bid.expires_at;
+            let unlock_receiver = match bid.unlock_receiver {
+                Some(unlock_receiver) => Some(deps.api.human_address(&unlock_receiver)?),
+                None => None,
+            };
 
             Ok(BidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
                 collateral_token,
                 bidder,
                 amount,
                 premium_rate,
+                expires_at,
+                unlock_receiver,
             })
         })
         .collect()
@@ -146,7 +716,7 @@ pub fn read_bids_by_user<S: Storage, A: Api, Q: Querier>(
     let bid_bucket: ReadonlyBucket<S, bool> =
         ReadonlyBucket::multilevel(&[PREFIX_BID_BY_USER, bidder.as_slice()], &deps.storage);
 
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = page_limit(limit);
     let start = calc_range_start(start_after);
 
     bid_bucket
@@ -155,23 +725,113 @@ pub fn read_bids_by_user<S: Storage, A: Api, Q: Querier>(
         .map(|elem| {
             let (k, _) = elem?;
             let collateral_token = CanonicalAddr::from(k);
-            let bid = read_bid(&deps.storage, &bidder, &collateral_token)?;
+            let bid = read_bid(&deps.storage, bidder, &collateral_token)?;
 
             let collateral_token: HumanAddr = deps.api.human_address(&collateral_token)?;
-            let bidder: HumanAddr = deps.api.human_address(&bidder)?;
+            let bidder: HumanAddr = deps.api.human_address(bidder)?;
             let amount = bid.amount;
             let premium_rate = bid.premium_rate;
+            let expires_at = bid.expires_at;
+            let unlock_receiver = match bid.unlock_receiver {
+                Some(unlock_receiver) => Some(deps.api.human_address(&unlock_receiver)?),
+                None => None,
+            };
 
             Ok(BidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
                 collateral_token,
                 bidder,
                 amount,
                 premium_rate,
+                expires_at,
+                unlock_receiver,
             })
         })
         .collect()
 }
 
+pub fn store_collateral_deprecated<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut deprecated_bucket: Bucket<S, bool> = Bucket::new(PREFIX_DEPRECATED_COLLATERAL, storage);
+    deprecated_bucket.save(collateral_token.as_slice(), &true)
+}
+
+pub fn is_collateral_deprecated<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<bool> {
+    let deprecated_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_DEPRECATED_COLLATERAL, storage);
+    Ok(deprecated_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or(false))
+}
+
+pub fn read_deprecated_collaterals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HumanAddr>> {
+    let deprecated_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_DEPRECATED_COLLATERAL, &deps.storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    deprecated_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            deps.api.human_address(&CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+pub fn read_blacklist<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HumanAddr>> {
+    let blacklist_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_BLACKLIST, &deps.storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    blacklist_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            deps.api.human_address(&CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+pub fn read_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HumanAddr>> {
+    let allowlist_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_ALLOWLIST, &deps.storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    allowlist_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            deps.api.human_address(&CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
@@ -180,3 +840,369 @@ fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
         v
     })
 }
+
+/// Compact record of an executed bid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationRecord {
+    pub collateral_token: CanonicalAddr,
+    pub borrower: Option<CanonicalAddr>,
+    pub repay_amount: Uint256,
+    pub collateral_amount: Uint256,
+    pub avg_premium: Decimal256,
+    pub timestamp: u64,
+}
+
+/// Store the record under the next index and return the index
+pub fn store_liquidation_record<S: Storage>(
+    storage: &mut S,
+    record: &LiquidationRecord,
+) -> StdResult<u64> {
+    let idx: u64 = singleton_read(storage, KEY_LIQUIDATION_RECORD_IDX)
+        .may_load()?
+        .unwrap_or(0u64)
+        + 1;
+    singleton(storage, KEY_LIQUIDATION_RECORD_IDX).save(&idx)?;
+
+    let mut record_bucket: Bucket<S, LiquidationRecord> = Bucket::multilevel(
+        &[
+            PREFIX_LIQUIDATION_RECORD,
+            record.collateral_token.as_slice(),
+        ],
+        storage,
+    );
+    record_bucket.save(&idx.to_be_bytes(), record)?;
+
+    Ok(idx)
+}
+
+pub fn read_liquidation_records<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+    borrower: Option<CanonicalAddr>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, LiquidationRecord)>> {
+    let record_bucket: ReadonlyBucket<S, LiquidationRecord> = ReadonlyBucket::multilevel(
+        &[PREFIX_LIQUIDATION_RECORD, collateral_token.as_slice()],
+        storage,
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    record_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .filter(|elem| match (elem, &borrower) {
+            (Ok((_, record)), Some(borrower)) => record.borrower.as_ref() == Some(borrower),
+            _ => true,
+        })
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut idx_bytes = [0u8; 8];
+            idx_bytes.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(idx_bytes), v))
+        })
+        .collect()
+}
+
+/// Running totals of a bidder on a collateral, kept
+/// up to date on every bid change
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct BidderStats {
+    pub active_bid_amount: Uint256,
+    pub filled_amount: Uint256,
+    pub collateral_received: Uint256,
+}
+
+pub fn store_bidder_stats<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    stats: &BidderStats,
+) -> StdResult<()> {
+    let mut stats_bucket: Bucket<S, BidderStats> =
+        Bucket::multilevel(&[PREFIX_BIDDER_STATS, bidder.as_slice()], storage);
+    stats_bucket.save(collateral_token.as_slice(), stats)
+}
+
+pub fn read_bidder_stats<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<BidderStats> {
+    let stats_bucket: ReadonlyBucket<S, BidderStats> =
+        ReadonlyBucket::multilevel(&[PREFIX_BIDDER_STATS, bidder.as_slice()], storage);
+    Ok(stats_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_default())
+}
+
+pub fn read_all_bidder_stats<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+) -> StdResult<Vec<(CanonicalAddr, BidderStats)>> {
+    let stats_bucket: ReadonlyBucket<S, BidderStats> =
+        ReadonlyBucket::multilevel(&[PREFIX_BIDDER_STATS, bidder.as_slice()], storage);
+    stats_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+/// Collateral held for an ExecuteBids call of the operator
+pub fn store_staged_collateral<S: Storage>(
+    storage: &mut S,
+    operator: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    amount: Uint256,
+) -> StdResult<()> {
+    let mut staged_bucket: Bucket<S, Uint256> =
+        Bucket::multilevel(&[PREFIX_STAGED_COLLATERAL, operator.as_slice()], storage);
+    if amount.is_zero() {
+        staged_bucket.remove(collateral_token.as_slice());
+        Ok(())
+    } else {
+        staged_bucket.save(collateral_token.as_slice(), &amount)
+    }
+}
+
+pub fn read_staged_collateral<S: Storage>(
+    storage: &S,
+    operator: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Uint256> {
+    let staged_bucket: ReadonlyBucket<S, Uint256> =
+        ReadonlyBucket::multilevel(&[PREFIX_STAGED_COLLATERAL, operator.as_slice()], storage);
+    Ok(staged_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_else(Uint256::zero))
+}
+
+/// Bid fees credited to a referrer
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct ReferralFees {
+    pub pending: Uint256,
+    pub claimed: Uint256,
+}
+
+pub fn store_referral_fees<S: Storage>(
+    storage: &mut S,
+    referrer: &CanonicalAddr,
+    fees: &ReferralFees,
+) -> StdResult<()> {
+    let mut fees_bucket: Bucket<S, ReferralFees> = Bucket::new(PREFIX_REFERRAL_FEES, storage);
+    fees_bucket.save(referrer.as_slice(), fees)
+}
+
+pub fn read_referral_fees<S: Storage>(
+    storage: &S,
+    referrer: &CanonicalAddr,
+) -> StdResult<ReferralFees> {
+    let fees_bucket: ReadonlyBucket<S, ReferralFees> =
+        ReadonlyBucket::new(PREFIX_REFERRAL_FEES, storage);
+    Ok(fees_bucket
+        .may_load(referrer.as_slice())?
+        .unwrap_or_default())
+}
+
+/// Bid fees held for the fee admin
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct AccruedFees {
+    pub pending: Uint256,
+    pub withdrawn: Uint256,
+}
+
+pub fn store_accrued_fees<S: Storage>(storage: &mut S, fees: &AccruedFees) -> StdResult<()> {
+    singleton(storage, KEY_ACCRUED_FEES).save(fees)
+}
+
+pub fn read_accrued_fees<S: ReadonlyStorage>(storage: &S) -> StdResult<AccruedFees> {
+    Ok(singleton_read(storage, KEY_ACCRUED_FEES)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// Cumulative dust swept from the bids on a collateral
+pub fn store_swept_dust<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    amount: Uint256,
+) -> StdResult<()> {
+    let mut dust_bucket: Bucket<S, Uint256> = Bucket::new(PREFIX_SWEPT_DUST, storage);
+    dust_bucket.save(collateral_token.as_slice(), &amount)
+}
+
+pub fn read_swept_dust<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Uint256> {
+    let dust_bucket: ReadonlyBucket<S, Uint256> = ReadonlyBucket::new(PREFIX_SWEPT_DUST, storage);
+    Ok(dust_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_else(Uint256::zero))
+}
+
+pub fn store_converter<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    claim_as: &ClaimAsset,
+    converter: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut converter_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::multilevel(&[PREFIX_CONVERTER, collateral_token.as_slice()], storage);
+    converter_bucket.save(claim_as.as_str().as_bytes(), converter)
+}
+
+pub fn remove_converter<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    claim_as: &ClaimAsset,
+) {
+    let mut converter_bucket: Bucket<S, CanonicalAddr> =
+        Bucket::multilevel(&[PREFIX_CONVERTER, collateral_token.as_slice()], storage);
+    converter_bucket.remove(claim_as.as_str().as_bytes())
+}
+
+pub fn read_converter<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+    claim_as: &ClaimAsset,
+) -> StdResult<Option<CanonicalAddr>> {
+    let converter_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::multilevel(&[PREFIX_CONVERTER, collateral_token.as_slice()], storage);
+    converter_bucket.may_load(claim_as.as_str().as_bytes())
+}
+
+/// Kept apart from the bid so the preference outlives
+/// a bid that is fully filled and submitted again
+pub fn store_claim_as<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    claim_as: &ClaimAsset,
+) -> StdResult<()> {
+    let mut claim_as_bucket: Bucket<S, ClaimAsset> =
+        Bucket::multilevel(&[PREFIX_CLAIM_AS, bidder.as_slice()], storage);
+    claim_as_bucket.save(collateral_token.as_slice(), claim_as)
+}
+
+pub fn remove_claim_as<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) {
+    let mut claim_as_bucket: Bucket<S, ClaimAsset> =
+        Bucket::multilevel(&[PREFIX_CLAIM_AS, bidder.as_slice()], storage);
+    claim_as_bucket.remove(collateral_token.as_slice())
+}
+
+pub fn read_claim_as<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<ClaimAsset>> {
+    let claim_as_bucket: ReadonlyBucket<S, ClaimAsset> =
+        ReadonlyBucket::multilevel(&[PREFIX_CLAIM_AS, bidder.as_slice()], storage);
+    claim_as_bucket.may_load(collateral_token.as_slice())
+}
+
+/// Reward accounting of the bids on a collateral; `reward_index` is
+/// the reward accrued per unit of active bid amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IncentivePool {
+    pub emission_rate: Decimal256,
+    pub reward_index: Decimal256,
+    pub total_bid_amount: Uint256,
+    pub last_updated: u64,
+}
+
+impl Default for IncentivePool {
+    fn default() -> Self {
+        IncentivePool {
+            emission_rate: Decimal256::zero(),
+            reward_index: Decimal256::zero(),
+            total_bid_amount: Uint256::zero(),
+            last_updated: 0,
+        }
+    }
+}
+
+pub fn store_incentive_pool<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    pool: &IncentivePool,
+) -> StdResult<()> {
+    let mut pool_bucket: Bucket<S, IncentivePool> = Bucket::new(PREFIX_INCENTIVE_POOL, storage);
+    pool_bucket.save(collateral_token.as_slice(), pool)
+}
+
+pub fn read_incentive_pool<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<IncentivePool> {
+    let pool_bucket: ReadonlyBucket<S, IncentivePool> =
+        ReadonlyBucket::new(PREFIX_INCENTIVE_POOL, storage);
+    Ok(pool_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_default())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidReward {
+    pub reward_index: Decimal256,
+    pub pending_rewards: Decimal256,
+}
+
+pub fn store_bid_reward<S: Storage>(
+    storage: &mut S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+    reward: &BidReward,
+) -> StdResult<()> {
+    let mut reward_bucket: Bucket<S, BidReward> =
+        Bucket::multilevel(&[PREFIX_BID_REWARD, bidder.as_slice()], storage);
+    reward_bucket.save(collateral_token.as_slice(), reward)
+}
+
+pub fn read_bid_reward<S: Storage>(
+    storage: &S,
+    bidder: &CanonicalAddr,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<BidReward>> {
+    let reward_bucket: ReadonlyBucket<S, BidReward> =
+        ReadonlyBucket::multilevel(&[PREFIX_BID_REWARD, bidder.as_slice()], storage);
+    reward_bucket.may_load(collateral_token.as_slice())
+}
+
+/// Stable value filled on a collateral since `started_at`, which is
+/// the block height when fills are limited per block and the block
+/// time otherwise
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct FillWindow {
+    pub started_at: u64,
+    pub filled_amount: Uint256,
+}
+
+pub fn store_fill_window<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    fill_window: &FillWindow,
+) -> StdResult<()> {
+    let mut window_bucket: Bucket<S, FillWindow> = Bucket::new(PREFIX_FILL_WINDOW, storage);
+    window_bucket.save(collateral_token.as_slice(), fill_window)
+}
+
+pub fn read_fill_window<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<FillWindow> {
+    let window_bucket: ReadonlyBucket<S, FillWindow> =
+        ReadonlyBucket::new(PREFIX_FILL_WINDOW, storage);
+    Ok(window_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or_default())
+}
@@ -1,3 +1,6 @@
+// the mocks are fed with nested tuples of the queried values
+#![allow(clippy::type_complexity)]
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +12,7 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use moneymarket::oracle::PriceResponse;
+use moneymarket::oracle::{PriceResponse, ORACLE_SCHEMA_VERSION};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -140,10 +143,11 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
-            }) => match from_binary(&msg).unwrap() {
+            }) => match from_binary(msg).unwrap() {
                 QueryMsg::Price { base, quote } => {
                     match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                         Some(v) => Ok(to_binary(&PriceResponse {
+                            schema_version: ORACLE_SCHEMA_VERSION,
                             rate: v.0,
                             last_updated_base: v.1,
                             last_updated_quote: v.2,
@@ -1,17 +1,32 @@
-use crate::contract::{handle, init, query};
+use crate::contract::{handle, init, migrate, query};
+use crate::error::ContractError;
+use crate::state::{
+    read_contract_version, store_contract_version, CONFIG_TIMELOCK_PERIOD, CONTRACT_VERSION,
+};
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128,
+    from_binary, log, to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128,
     WasmMsg,
 };
 use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
+use moneymarket::halt_recovery::HaltRecovery;
 use moneymarket::liquidation::{
-    BidResponse, BidsResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg,
-    LiquidationAmountResponse, QueryMsg,
+    AccruedFeesResponse, AllowlistResponse, BidMigrationResponse, BidResponse,
+    BidderCollateralStats, BidderStatsResponse, BidsResponse, BlacklistResponse, ClaimAsResponse,
+    ClaimAsset, CollateralInfoResponse, CollateralStatusResponse, ConfigResponse, ConverterHookMsg,
+    CrossBidResponse, Cw20HookMsg, DeprecatedCollateralsResponse, ExportBidsResponse,
+    ExportCrossBidsResponse, ExportedBid, HandleMsg, InitMsg, InterfaceVersionResponse,
+    InvariantsResponse, LiquidationAmountResponse, LiquidationHistoryResponse,
+    LiquidationQueueHandleMsg, LiquidationRecordResponse, MigrateMsg, PendingConfigResponse,
+    PendingRewardsResponse, PriceStatus, QueryMsg, ReferralFeesResponse,
+    SimulateLiquidationResponse, SweptDustResponse, LIQUIDATION_INTERFACE_VERSION,
+    LIQUIDATION_SCHEMA_VERSION,
 };
+use moneymarket::oracle::PriceMode;
+use moneymarket::tax::TaxPolicy;
 
 #[test]
 fn proper_initialization() {
@@ -23,9 +38,12 @@ fn proper_initialization() {
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -39,16 +57,36 @@ fn proper_initialization() {
     assert_eq!(
         value,
         ConfigResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
             owner: HumanAddr::from("owner0000"),
             oracle_contract: HumanAddr::from("oracle0000"),
             stable_denom: "uusd".to_string(),
             safe_ratio: Decimal256::percent(10),
             bid_fee: Decimal256::percent(1),
+            liquidator_fee: Decimal256::zero(),
             max_premium_rate: Decimal256::percent(5),
             liquidation_threshold: Uint256::from(100000000u64),
+            max_close_factor: Decimal256::one(),
             price_timeframe: 60u64,
+            price_mode: PriceMode::Spot,
+            guardian: None,
+            min_liquidation: Uint256::zero(),
+            reserve_contract: None,
+            reserve_fee_share: Decimal256::zero(),
+            referral_fee_share: Decimal256::zero(),
+            dust_threshold: Uint256::zero(),
+            delegated_bids_enabled: false,
+            reward_token: None,
+            max_fill_per_window: Uint256::zero(),
+            fill_window: 0,
+            fee_admin: None,
+            overseer_contract: None,
         }
     );
+
+    let value: InterfaceVersionResponse =
+        from_binary(&query(&deps, QueryMsg::InterfaceVersion {}).unwrap()).unwrap();
+    assert_eq!(value.version, LIQUIDATION_INTERFACE_VERSION);
 }
 
 #[test]
@@ -61,94 +99,228 @@ fn update_config() {
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
     let _res = init(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // transfer ownership
     let env = mock_env("owner0000", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner: Some(HumanAddr("owner0001".to_string())),
-        oracle_contract: None,
-        stable_denom: None,
-        safe_ratio: None,
-        bid_fee: None,
-        max_premium_rate: None,
-        liquidation_threshold: None,
-        price_timeframe: None,
+    let msg = HandleMsg::ProposeOwner {
+        new_owner: HumanAddr("owner0001".to_string()),
+        expires_in: 100u64,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
+    let env = mock_env("owner0001", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(0, res.messages.len());
+
     // it worked, let's query the state
     let value: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
     assert_eq!(
         value,
         ConfigResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
             owner: HumanAddr::from("owner0001"),
             oracle_contract: HumanAddr::from("oracle0000"),
             stable_denom: "uusd".to_string(),
             safe_ratio: Decimal256::percent(10),
             bid_fee: Decimal256::percent(1),
+            liquidator_fee: Decimal256::zero(),
             max_premium_rate: Decimal256::percent(5),
             liquidation_threshold: Uint256::from(100000000u64),
+            max_close_factor: Decimal256::one(),
             price_timeframe: 60u64,
+            price_mode: PriceMode::Spot,
+            guardian: None,
+            min_liquidation: Uint256::zero(),
+            reserve_contract: None,
+            reserve_fee_share: Decimal256::zero(),
+            referral_fee_share: Decimal256::zero(),
+            dust_threshold: Uint256::zero(),
+            delegated_bids_enabled: false,
+            reward_token: None,
+            max_fill_per_window: Uint256::zero(),
+            fill_window: 0,
+            fee_admin: None,
+            overseer_contract: None,
         }
     );
 
     // Update left items
     let env = mock_env("owner0001", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some(HumanAddr::from("oracle0001")),
-        stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(15)),
         bid_fee: Some(Decimal256::percent(2)),
+        liquidator_fee: None,
         max_premium_rate: Some(Decimal256::percent(7)),
         liquidation_threshold: Some(Uint256::from(150000000u64)),
+        max_close_factor: Some(Decimal256::percent(50)),
         price_timeframe: Some(120u64),
+        price_mode: Some(PriceMode::Twap { window: 600 }),
+        guardian: Some(HumanAddr::from("guardian0000")),
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
     };
 
-    let res = handle(&mut deps, env, msg).unwrap();
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
     assert_eq!(0, res.messages.len());
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "schedule_config"),
+            log("effective_at", env.block.time + CONFIG_TIMELOCK_PERIOD),
+        ]
+    );
+
+    // risk parameters are not changed until the timelock passes
+    let value: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        value,
+        ConfigResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            owner: HumanAddr::from("owner0001"),
+            oracle_contract: HumanAddr::from("oracle0001"),
+            stable_denom: "uusd".to_string(),
+            safe_ratio: Decimal256::percent(10),
+            bid_fee: Decimal256::percent(1),
+            liquidator_fee: Decimal256::zero(),
+            max_premium_rate: Decimal256::percent(5),
+            liquidation_threshold: Uint256::from(100000000u64),
+            max_close_factor: Decimal256::one(),
+            price_timeframe: 120u64,
+            price_mode: PriceMode::Twap { window: 600 },
+            guardian: Some(HumanAddr::from("guardian0000")),
+            min_liquidation: Uint256::zero(),
+            reserve_contract: None,
+            reserve_fee_share: Decimal256::zero(),
+            referral_fee_share: Decimal256::zero(),
+            dust_threshold: Uint256::zero(),
+            delegated_bids_enabled: false,
+            reward_token: None,
+            max_fill_per_window: Uint256::zero(),
+            fill_window: 0,
+            fee_admin: None,
+            overseer_contract: None,
+        }
+    );
+
+    let value: PendingConfigResponse =
+        from_binary(&query(&deps, QueryMsg::PendingConfig {}).unwrap()).unwrap();
+    assert_eq!(
+        value,
+        PendingConfigResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            safe_ratio: Some(Decimal256::percent(15)),
+            bid_fee: Some(Decimal256::percent(2)),
+            liquidator_fee: None,
+            max_premium_rate: Some(Decimal256::percent(7)),
+            liquidation_threshold: Some(Uint256::from(150000000u64)),
+            max_close_factor: Some(Decimal256::percent(50)),
+            effective_at: env.block.time + CONFIG_TIMELOCK_PERIOD,
+        }
+    );
+
+    // anyone can apply, but only after the timelock
+    let mut env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env.clone(), HandleMsg::ApplyConfig {});
+    match res {
+        Err(ContractError::PendingConfigNotEffective { effective_at }) => {
+            assert_eq!(effective_at, env.block.time + CONFIG_TIMELOCK_PERIOD)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time += CONFIG_TIMELOCK_PERIOD;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ApplyConfig {}).unwrap();
+    assert_eq!(res.log, vec![log("action", "apply_config")]);
+
+    let res = handle(&mut deps, env, HandleMsg::ApplyConfig {});
+    match res {
+        Err(ContractError::NoPendingConfig {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
     // it worked, let's query the state
     let value: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
     assert_eq!(
         value,
         ConfigResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
             owner: HumanAddr::from("owner0001"),
             oracle_contract: HumanAddr::from("oracle0001"),
-            stable_denom: "ukrw".to_string(),
+            stable_denom: "uusd".to_string(),
             safe_ratio: Decimal256::percent(15),
             bid_fee: Decimal256::percent(2),
+            liquidator_fee: Decimal256::zero(),
             max_premium_rate: Decimal256::percent(7),
             liquidation_threshold: Uint256::from(150000000u64),
+            max_close_factor: Decimal256::percent(50),
             price_timeframe: 120u64,
+            price_mode: PriceMode::Twap { window: 600 },
+            guardian: Some(HumanAddr::from("guardian0000")),
+            min_liquidation: Uint256::zero(),
+            reserve_contract: None,
+            reserve_fee_share: Decimal256::zero(),
+            referral_fee_share: Decimal256::zero(),
+            dust_threshold: Uint256::zero(),
+            delegated_bids_enabled: false,
+            reward_token: None,
+            max_fill_per_window: Uint256::zero(),
+            fill_window: 0,
+            fee_admin: None,
+            overseer_contract: None,
         }
     );
 
     // Unauthorized err
     let env = mock_env("owner0000", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some(HumanAddr::from("oracle0001")),
-        stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(1)),
         bid_fee: Some(Decimal256::percent(2)),
+        liquidator_fee: None,
         max_premium_rate: Some(Decimal256::percent(7)),
         liquidation_threshold: Some(Uint256::from(150000000u64)),
+        max_close_factor: None,
         price_timeframe: Some(100u64),
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
     };
 
     let res = handle(&mut deps, env, msg);
     match res {
-        Err(StdError::Unauthorized { .. }) => {}
+        Err(ContractError::Unauthorized {}) => {}
         _ => panic!("Must return unauthorized error"),
     }
 }
@@ -163,9 +335,12 @@ fn submit_bid() {
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -174,11 +349,15 @@ fn submit_bid() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(20),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let res = handle(&mut deps, env.clone(), msg);
     match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Premium rate cannot exceed the max premium rate: 0.05")
+        Err(ContractError::PremiumRateTooHigh { max_premium_rate }) => {
+            assert_eq!(max_premium_rate, Decimal256::percent(5))
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -186,11 +365,15 @@ fn submit_bid() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "No uusd assets have been provided")
+        Err(ContractError::NoStableProvided { denom }) => {
+            assert_eq!(denom, "uusd")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -218,10 +401,13 @@ fn submit_bid() {
     assert_eq!(
         bid_response,
         BidResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
             collateral_token: HumanAddr::from("asset0000"),
             bidder: HumanAddr::from("addr0000"),
             amount: Uint256::from(1000000u128),
             premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            unlock_receiver: None,
         }
     );
 }
@@ -236,9 +422,12 @@ fn retract_bid() {
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -247,6 +436,10 @@ fn retract_bid() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let env = mock_env(
         "addr0000",
@@ -264,8 +457,8 @@ fn retract_bid() {
     let env = mock_env("addr0000", &[]);
     let res = handle(&mut deps, env.clone(), msg);
     match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Retract amount cannot exceed bid balance: 1000000")
+        Err(ContractError::RetractExceedsBid { bid_amount }) => {
+            assert_eq!(bid_amount, Uint256::from(1000000u64))
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -305,6 +498,107 @@ fn retract_bid() {
     );
 }
 
+#[test]
+fn transfer_bid() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    for bidder in ["addr0000", "addr0001"].iter() {
+        let env = mock_env(
+            *bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        handle(&mut deps, env, msg.clone()).unwrap();
+    }
+
+    // receiver already has a bid
+    let msg = HandleMsg::TransferBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        new_bidder: HumanAddr::from("addr0001"),
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(ContractError::BidAlreadyExists { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::TransferBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        new_bidder: HumanAddr::from("addr0002"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let bids_response: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(0, bids_response.bids.len());
+
+    let bids_response: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0002"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids_response.bids,
+        vec![BidResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0002"),
+            amount: Uint256::from(1000000u64),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            unlock_receiver: None,
+        }]
+    );
+}
+
 #[test]
 fn execute_bid() {
     let mut deps = mock_dependencies(20, &[]);
@@ -318,9 +612,12 @@ fn execute_bid() {
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -334,6 +631,10 @@ fn execute_bid() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let env = mock_env(
         "addr0000",
@@ -352,6 +653,8 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: Some(HumanAddr::from("fee0000")),
                 repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -359,8 +662,8 @@ fn execute_bid() {
     let env = mock_env("asset0000", &[]);
     let res = handle(&mut deps, env.clone(), msg);
     match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Insufficient bid balance; Required balance: 1000001")
+        Err(ContractError::InsufficientBidAmount { required_amount }) => {
+            assert_eq!(required_amount, Uint256::from(1000002u64))
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
@@ -375,6 +678,8 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: Some(HumanAddr::from("fee0000")),
                 repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -419,6 +724,8 @@ fn execute_bid() {
                 liquidator: HumanAddr::from("addr0000"),
                 fee_address: None,
                 repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
             })
             .unwrap(),
         ),
@@ -457,109 +764,150 @@ fn execute_bid() {
 }
 
 #[test]
-fn query_liquidation_amount() {
+fn simulate_liquidation() {
     let mut deps = mock_dependencies(20, &[]);
     deps.querier.with_tax(
         Decimal::percent(1),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-
     let msg = InitMsg {
         owner: HumanAddr::from("owner0000"),
         oracle_contract: HumanAddr::from("oracle0000"),
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
     let _res = init(&mut deps, env, msg).unwrap();
 
-    // fee_deductor = 0.931095
-    // expected_repay_amount = 931,095
-    let msg = QueryMsg::LiquidationAmount {
-        borrow_amount: Uint256::from(931095u64),
-        borrow_limit: Uint256::from(900000u64),
-        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
-        collateral_prices: vec![Decimal256::percent(10)],
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
-
-    let res = query(&mut deps, msg).unwrap();
-    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
-    assert_eq!(
-        res,
-        LiquidationAmountResponse {
-            collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
-        }
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
     );
+    handle(&mut deps, env, msg).unwrap();
 
-    let msg = QueryMsg::LiquidationAmount {
-        borrow_amount: Uint256::from(100000u64),
-        borrow_limit: Uint256::from(1000000u64),
-        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
-        collateral_prices: vec![Decimal256::one()],
-    };
-
-    let res = query(&mut deps, msg).unwrap();
-    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    // required_stable 495,000
+    // bid_fee         4,950
+    // repay_amount    490,050
+    let simulate_res: SimulateLiquidationResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::SimulateLiquidation {
+                collateral_token: HumanAddr::from("asset0000"),
+                collateral_amount: Uint256::from(1000000u64),
+                bidder: HumanAddr::from("addr0000"),
+                price_override: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
     assert_eq!(
-        res,
-        LiquidationAmountResponse {
-            collaterals: vec![],
+        simulate_res,
+        SimulateLiquidationResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collateral_token: HumanAddr::from("asset0000"),
+            collateral_amount: Uint256::from(1000000u64),
+            price: Decimal256::percent(50),
+            premium_rate: Decimal256::percent(1),
+            required_stable: Uint256::from(495000u64),
+            repay_amount: Uint256::from(485198u64), // 490050 / (1 + tax_rate)
+            bid_fee: Uint256::from(4900u64),        // 4950 / (1 + tax_rate)
+            liquidator_fee: Uint256::zero(),
+            remaining_bid_amount: Uint256::from(505000u64),
         }
     );
 
-    let query_msg = QueryMsg::LiquidationAmount {
-        borrow_amount: Uint256::from(1000000u64),
-        borrow_limit: Uint256::from(99999u64),
-        collaterals: vec![
-            (HumanAddr::from("token0000"), Uint256::from(1000000u64)),
-            (HumanAddr::from("token0001"), Uint256::from(2000000u64)),
-            (HumanAddr::from("token0002"), Uint256::from(3000000u64)),
-        ],
-        collateral_prices: vec![
-            Decimal256::percent(50),
-            Decimal256::percent(50),
-            Decimal256::percent(50),
-        ],
-    };
+    // required_stable = 1,000,000 * 0.4 * 0.99
+    let simulate_res: SimulateLiquidationResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::SimulateLiquidation {
+                collateral_token: HumanAddr::from("asset0000"),
+                collateral_amount: Uint256::from(1000000u64),
+                bidder: HumanAddr::from("addr0000"),
+                price_override: Some(Decimal256::percent(40)),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulate_res.required_stable, Uint256::from(396000u64));
+    assert_eq!(simulate_res.remaining_bid_amount, Uint256::from(604000u64));
 
-    // fee_deductor = 0.931095
-    // liquidation_ratio = 0.3580014213
-    let res = query(&mut deps, query_msg.clone()).unwrap();
-    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
-    assert_eq!(
-        res,
-        LiquidationAmountResponse {
-            collaterals: vec![
-                (HumanAddr::from("token0000"), Uint256::from(358001u64)),
-                (HumanAddr::from("token0001"), Uint256::from(716002u64)),
-                (HumanAddr::from("token0002"), Uint256::from(1074004u64)),
-            ],
-        }
+    let res = query(
+        &deps,
+        QueryMsg::SimulateLiquidation {
+            collateral_token: HumanAddr::from("asset0000"),
+            collateral_amount: Uint256::from(2020206u64),
+            bidder: HumanAddr::from("addr0000"),
+            price_override: None,
+        },
     );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Insufficient bid balance; Required balance: 1000002")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the bid is left untouched
+    let bid_res: BidResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Bid {
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(bid_res.amount, Uint256::from(1000000u64));
 }
 
 #[test]
-fn query_bids_by_user() {
+fn execute_bid_with_liquidator_fee() {
     let mut deps = mock_dependencies(20, &[]);
     deps.querier.with_tax(
         Decimal::percent(1),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-
     let msg = InitMsg {
         owner: HumanAddr::from("owner0000"),
         oracle_contract: HumanAddr::from("oracle0000"),
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(1),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -568,11 +916,23 @@ fn query_bids_by_user() {
         &(Decimal256::percent(50), env.block.time, env.block.time),
     )]);
 
+    // the fees cannot take the whole repay amount
+    let mut invalid_msg = msg.clone();
+    invalid_msg.liquidator_fee = Decimal256::percent(99);
+    match init(&mut deps, env.clone(), invalid_msg) {
+        Err(ContractError::InvalidFees {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     let _res = init(&mut deps, env, msg).unwrap();
 
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let env = mock_env(
         "addr0000",
@@ -583,147 +943,4245 @@ fn query_bids_by_user() {
     );
     handle(&mut deps, env, msg).unwrap();
 
-    let msg = HandleMsg::SubmitBid {
-        collateral_token: HumanAddr::from("asset0001"),
-        premium_rate: Decimal256::percent(2),
-    };
-    let env = mock_env(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(2000000u128),
-        }],
-    );
-    handle(&mut deps, env, msg).unwrap();
-
-    let msg = HandleMsg::SubmitBid {
-        collateral_token: HumanAddr::from("asset0002"),
-        premium_rate: Decimal256::percent(3),
-    };
-    let env = mock_env(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(3000000u128),
-        }],
-    );
+    // required_stable 495,000
+    // bid_fee         4,950
+    // liquidator_fee  4,950
+    // repay_amount    485,100
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("fee0000")),
+                repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: Some(HumanAddr::from("keeper0000")),
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("repay0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(480297u128), // 485100 / (1 + tax_rate)
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("fee0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("keeper0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                }]
+            }),
+        ]
+    );
+
+    // the liquidator fee goes to the liquidator when no address is given,
+    // not to the custody contract sending the collateral
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(100000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages[3],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(490u128), // 495 / (1 + tax_rate)
+            }]
+        })
+    );
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: Some(Decimal256::percent(99)),
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    let env = mock_env("owner0000", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(ContractError::InvalidFees {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn execute_bids() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("asset0000".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+        (
+            &("asset0001".to_string(), "uusd".to_string()),
+            &(Decimal256::one(), env.block.time, env.block.time),
+        ),
+    ]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    for collateral_token in &["asset0000", "asset0001"] {
+        let msg = HandleMsg::SubmitBid {
+            collateral_token: HumanAddr::from(*collateral_token),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            referrer: None,
+            bid_for: None,
+            unlock_receiver: None,
+        };
+        let env = mock_env(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    let stage_msg = |amount: u128, sender: &str, operator: &str| {
+        HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from(sender),
+            amount: Uint128::from(amount),
+            msg: Some(
+                to_binary(&Cw20HookMsg::StageCollateral {
+                    operator: Some(HumanAddr::from(operator)),
+                })
+                .unwrap(),
+            ),
+        })
+    };
+
+    // only registered custodies can stage
+    let res = handle(
+        &mut deps,
+        mock_env("asset0000", &[]),
+        stage_msg(1000000, "custody0000", "overseer0000"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::SetCollateralCustody {
+        collateral_token: HumanAddr::from("asset0000"),
+        custody_contract: Some(HumanAddr::from("custody0000")),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    for collateral_token in &["asset0000", "asset0001"] {
+        let msg = HandleMsg::SetCollateralCustody {
+            collateral_token: HumanAddr::from(*collateral_token),
+            custody_contract: Some(HumanAddr::from("custody0000")),
+        };
+        handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    }
+
+    // other senders and operators are still rejected
+    let res = handle(
+        &mut deps,
+        mock_env("asset0000", &[]),
+        stage_msg(1000000, "addr0001", "addr0001"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("asset0000", &[]),
+        stage_msg(1000000, "custody0000", "overseer0000"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: Some(HumanAddr::from("overseer0000")),
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    // custodies stage the collaterals for the overseer
+    for (collateral_token, amount) in &[("asset0000", 1000000u128), ("asset0001", 500000u128)] {
+        let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("custody0000"),
+            amount: Uint128::from(*amount),
+            msg: Some(
+                to_binary(&Cw20HookMsg::StageCollateral {
+                    operator: Some(HumanAddr::from("overseer0000")),
+                })
+                .unwrap(),
+            ),
+        });
+        handle(&mut deps, mock_env(*collateral_token, &[]), msg).unwrap();
+    }
+
+    let msg = HandleMsg::ExecuteBids {
+        liquidator: HumanAddr::from("addr0000"),
+        collaterals: vec![
+            (HumanAddr::from("asset0000"), Uint256::from(1000000u64)),
+            (HumanAddr::from("asset0001"), Uint256::from(500000u64)),
+        ],
+        fee_address: None,
+        repay_address: Some(HumanAddr::from("market0000")),
+        liquidator_fee_address: None,
+        borrower: Some(HumanAddr::from("borrower0000")),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone());
+    match res {
+        Err(ContractError::InsufficientStagedCollateral {
+            collateral_token,
+            staged_amount,
+        }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"));
+            assert_eq!(staged_amount, Uint256::zero());
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // required_stable 495,000 + 495,000
+    // bid_fee         4,950 + 4,950
+    // repay_amount    490,050 + 490,050
+    let res = handle(&mut deps, mock_env("overseer0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0001"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("market0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(980100u128),
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("overseer0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(9900u128),
+                }]
+            }),
+        ]
+    );
+
+    // staged collaterals are consumed
+    let res = handle(&mut deps, mock_env("overseer0000", &[]), msg);
+    match res {
+        Err(ContractError::InsufficientStagedCollateral {
+            collateral_token,
+            staged_amount,
+        }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"));
+            assert_eq!(staged_amount, Uint256::zero());
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn liquidation_history() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // required_stable 495,000; repay_amount 490,050
+    let env = mock_env("asset0000", &[]);
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: Some(HumanAddr::from("borrower0000")),
+            })
+            .unwrap(),
+        ),
+    });
+    handle(&mut deps, env.clone(), msg).unwrap();
+
+    // required_stable 247,500; repay_amount 245,025
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(500000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: Some(HumanAddr::from("borrower0001")),
+            })
+            .unwrap(),
+        ),
+    });
+    handle(&mut deps, env.clone(), msg).unwrap();
+
+    let history_res: LiquidationHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationHistory {
+                collateral_token: HumanAddr::from("asset0000"),
+                borrower: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        history_res.records,
+        vec![
+            LiquidationRecordResponse {
+                idx: 1u64,
+                collateral_token: HumanAddr::from("asset0000"),
+                borrower: Some(HumanAddr::from("borrower0000")),
+                repay_amount: Uint256::from(490050u64),
+                collateral_amount: Uint256::from(1000000u64),
+                avg_premium: Decimal256::percent(1),
+                timestamp: env.block.time,
+            },
+            LiquidationRecordResponse {
+                idx: 2u64,
+                collateral_token: HumanAddr::from("asset0000"),
+                borrower: Some(HumanAddr::from("borrower0001")),
+                repay_amount: Uint256::from(245025u64),
+                collateral_amount: Uint256::from(500000u64),
+                avg_premium: Decimal256::percent(1),
+                timestamp: env.block.time,
+            },
+        ]
+    );
+
+    // filter by borrower
+    let history_res: LiquidationHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationHistory {
+                collateral_token: HumanAddr::from("asset0000"),
+                borrower: Some(HumanAddr::from("borrower0000")),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history_res.records.len(), 1);
+    assert_eq!(history_res.records[0].idx, 1u64);
+
+    // paginate
+    let history_res: LiquidationHistoryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationHistory {
+                collateral_token: HumanAddr::from("asset0000"),
+                borrower: None,
+                start_after: Some(1u64),
+                limit: Some(1u32),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history_res.records.len(), 1);
+    assert_eq!(history_res.records[0].idx, 2u64);
+}
+
+#[test]
+fn bidder_stats() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    for collateral_token in &["asset0000", "asset0001"] {
+        let msg = HandleMsg::SubmitBid {
+            collateral_token: HumanAddr::from(*collateral_token),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            referrer: None,
+            bid_for: None,
+            unlock_receiver: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    // required_stable 495,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: Some(Uint256::from(105000u64)),
+    };
+    handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let stats_res: BidderStatsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidderStats {
+                bidder: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        stats_res,
+        BidderStatsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bidder: HumanAddr::from("addr0000"),
+            total_active_bid_amount: Uint256::from(1400000u64),
+            total_filled_amount: Uint256::from(495000u64),
+            collaterals: vec![
+                BidderCollateralStats {
+                    collateral_token: HumanAddr::from("asset0000"),
+                    active_bid_amount: Uint256::from(400000u64),
+                    filled_amount: Uint256::from(495000u64),
+                    collateral_received: Uint256::from(1000000u64),
+                },
+                BidderCollateralStats {
+                    collateral_token: HumanAddr::from("asset0001"),
+                    active_bid_amount: Uint256::from(1000000u64),
+                    filled_amount: Uint256::zero(),
+                    collateral_received: Uint256::zero(),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn query_liquidation_amount() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    // fee_deductor = 0.931095
+    // expected_repay_amount = 931,095
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(931095u64),
+        borrow_limit: Uint256::from(900000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        collateral_prices: vec![Decimal256::percent(10)],
+    };
+
+    let res = query(&deps, msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        }
+    );
+
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(100000u64),
+        borrow_limit: Uint256::from(1000000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        collateral_prices: vec![Decimal256::one()],
+    };
+
+    let res = query(&deps, msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![],
+        }
+    );
+
+    let query_msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(1000000u64),
+        borrow_limit: Uint256::from(99999u64),
+        collaterals: vec![
+            (HumanAddr::from("token0000"), Uint256::from(1000000u64)),
+            (HumanAddr::from("token0001"), Uint256::from(2000000u64)),
+            (HumanAddr::from("token0002"), Uint256::from(3000000u64)),
+        ],
+        collateral_prices: vec![
+            Decimal256::percent(50),
+            Decimal256::percent(50),
+            Decimal256::percent(50),
+        ],
+    };
+
+    // fee_deductor = 0.931095
+    // liquidation_ratio = 0.3580014213
+    let res = query(&deps, query_msg.clone()).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![
+                (HumanAddr::from("token0000"), Uint256::from(358001u64)),
+                (HumanAddr::from("token0001"), Uint256::from(716002u64)),
+                (HumanAddr::from("token0002"), Uint256::from(1074004u64)),
+            ],
+        }
+    );
+
+    // token0000 would only liquidate 179,000 worth of collateral
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: Some(Uint256::from(200000u64)),
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let res = query(&deps, query_msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![
+                (HumanAddr::from("token0001"), Uint256::from(716002u64)),
+                (HumanAddr::from("token0002"), Uint256::from(1074004u64)),
+            ],
+        }
+    );
+}
+
+#[test]
+fn liquidation_amount_close_factor() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let mut msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::zero(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let res = init(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(ContractError::InvalidCloseFactor {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    msg.max_close_factor = Decimal256::percent(20);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    // expected_repay_amount is smaller than the loan, which
+    // would liquidate all collaterals without the close factor
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(1000000000u64),
+        borrow_limit: Uint256::from(600000000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000000u64))],
+        collateral_prices: vec![Decimal256::one()],
+    };
+
+    let res = query(&deps, msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(200000000u64))],
+        }
+    );
+
+    // positions under the liquidation threshold are not capped
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(1000000u64),
+        borrow_limit: Uint256::from(600000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        collateral_prices: vec![Decimal256::one()],
+    };
+
+    let res = query(&deps, msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        }
+    );
+
+    // the same position priced by the oracle
+    deps.querier.with_oracle_price(&[(
+        &("token0000".to_string(), "uusd".to_string()),
+        &(Decimal256::one(), 123456u64, 123456u64),
+    )]);
+    let msg = QueryMsg::LiquidationAmountAtOraclePrices {
+        borrow_amount: Uint256::from(1000000u64),
+        borrow_limit: Uint256::from(600000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        block_time: 123456u64 + 60u64,
+    };
+
+    let res = query(&deps, msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        }
+    );
+
+    let msg = QueryMsg::LiquidationAmountAtOraclePrices {
+        borrow_amount: Uint256::from(1000000u64),
+        borrow_limit: Uint256::from(600000u64),
+        collaterals: vec![(HumanAddr::from("token0000"), Uint256::from(1000000u64))],
+        block_time: 123456u64 + 61u64,
+    };
+    match query(&deps, msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Oracle price is stale for collateral: token0000; last updated: 123456"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_bids_by_user() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        premium_rate: Decimal256::percent(2),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0002"),
+        premium_rate: Decimal256::percent(3),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(3000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0000"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1000000u128),
+                    premium_rate: Decimal256::percent(1),
+                    expires_at: None,
+                    unlock_receiver: None,
+                },
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0001"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(2000000u128),
+                    premium_rate: Decimal256::percent(2),
+                    expires_at: None,
+                    unlock_receiver: None,
+                },
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0002"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(3000000u128),
+                    premium_rate: Decimal256::percent(3),
+                    expires_at: None,
+                    unlock_receiver: None,
+                }
+            ],
+            total_count: Some(3),
+            next_start_after: None
+        }
+    );
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0000"),
+                start_after: Some(HumanAddr::from("asset0000")),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0001"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(2000000u128),
+                    premium_rate: Decimal256::percent(2),
+                    expires_at: None,
+                    unlock_receiver: None,
+                },
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0002"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(3000000u128),
+                    premium_rate: Decimal256::percent(3),
+                    expires_at: None,
+                    unlock_receiver: None,
+                }
+            ],
+            total_count: Some(3),
+            next_start_after: None
+        }
+    );
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0000"),
+                start_after: None,
+                limit: Some(1u32),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![BidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u128),
+                premium_rate: Decimal256::percent(1),
+                expires_at: None,
+                unlock_receiver: None,
+            }],
+            total_count: Some(3),
+            next_start_after: Some(HumanAddr::from("asset0000"))
+        }
+    );
+
+    // retracting the whole bid removes it from the count
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        amount: None,
+    };
+    handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByUser {
+                bidder: HumanAddr::from("addr0000"),
+                start_after: Some(HumanAddr::from("asset0000")),
+                limit: Some(1u32),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(bids.bids.len(), 1);
+    assert_eq!(bids.bids[0].collateral_token, HumanAddr::from("asset0002"));
+    assert_eq!(bids.total_count, Some(2));
+    assert_eq!(bids.next_start_after, Some(HumanAddr::from("asset0002")));
+}
+
+#[test]
+fn query_bids_by_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), 123456u64, 123456u64),
+    )]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(2),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        premium_rate: Decimal256::percent(3),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(3000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByCollateral {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0000"),
+                    bidder: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1000000u128),
+                    premium_rate: Decimal256::percent(1),
+                    expires_at: None,
+                    unlock_receiver: None,
+                },
+                BidResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    collateral_token: HumanAddr::from("asset0000"),
+                    bidder: HumanAddr::from("addr0001"),
+                    amount: Uint256::from(2000000u128),
+                    premium_rate: Decimal256::percent(2),
+                    expires_at: None,
+                    unlock_receiver: None,
+                }
+            ],
+            total_count: None,
+            next_start_after: None
+        }
+    );
+
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByCollateral {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: Some(HumanAddr::from("addr0000")),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![BidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0001"),
+                amount: Uint256::from(2000000u128),
+                premium_rate: Decimal256::percent(2),
+                expires_at: None,
+                unlock_receiver: None,
+            }],
+            total_count: None,
+            next_start_after: None
+        }
+    );
+    let bids: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByCollateral {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: None,
+                limit: Some(1u32),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bids,
+        BidsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bids: vec![BidResponse {
+                schema_version: LIQUIDATION_SCHEMA_VERSION,
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0000"),
+                amount: Uint256::from(1000000u128),
+                premium_rate: Decimal256::percent(1),
+                expires_at: None,
+                unlock_receiver: None,
+            }],
+            total_count: None,
+            next_start_after: Some(HumanAddr::from("addr0000"))
+        }
+    );
+}
+
+#[test]
+fn blacklist() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let bid_msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let bidder_env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, bidder_env.clone(), bid_msg.clone()).unwrap();
+
+    let msg = HandleMsg::UpdateBlacklist {
+        add: vec![HumanAddr::from("addr0000"), HumanAddr::from("addr0001")],
+        remove: vec![],
+    };
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let blacklist_res: BlacklistResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Blacklist {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        blacklist_res,
+        BlacklistResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            addresses: vec![HumanAddr::from("addr0000"), HumanAddr::from("addr0001")],
+        }
+    );
+
+    // blacklisted bidder cannot receive collaterals
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg);
+    match res {
+        Err(ContractError::Blacklisted { address }) => {
+            assert_eq!(address, HumanAddr::from("addr0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // blacklisted address cannot submit bids
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let res = handle(&mut deps, bidder_env.clone(), msg);
+    match res {
+        Err(ContractError::Blacklisted { address }) => {
+            assert_eq!(address, HumanAddr::from("addr0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // funds remain retractable
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(990099u128), // 1000000 / (1 + tax_rate)
+            }]
+        })]
+    );
+
+    let msg = HandleMsg::UpdateBlacklist {
+        add: vec![],
+        remove: vec![HumanAddr::from("addr0000")],
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    handle(&mut deps, bidder_env, bid_msg).unwrap();
+}
+
+#[test]
+fn allowlist() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let bid_msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let bidder_env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, bidder_env.clone(), bid_msg.clone()).unwrap();
+
+    let msg = HandleMsg::UpdateAllowlist {
+        enabled: Some(true),
+        add: vec![HumanAddr::from("addr0001")],
+        remove: vec![],
+    };
+    match handle(&mut deps, env, msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let allowlist_res: AllowlistResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Allowlist {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        allowlist_res,
+        AllowlistResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            enabled: true,
+            addresses: vec![HumanAddr::from("addr0001")],
+        }
+    );
+
+    // bids of addresses out of the list cannot be filled
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    match handle(&mut deps, mock_env("asset0000", &[]), msg) {
+        Err(ContractError::NotAllowlisted { address }) => {
+            assert_eq!(address, HumanAddr::from("addr0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    match handle(&mut deps, bidder_env, msg.clone()) {
+        Err(ContractError::NotAllowlisted { address }) => {
+            assert_eq!(address, HumanAddr::from("addr0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+}
+
+#[test]
+fn collateral_status() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let bidder_env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, bidder_env, msg).unwrap();
+
+    let status_res: CollateralStatusResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CollateralStatus {
+                collateral_token: HumanAddr::from("asset0000"),
+                block_time: env.block.time + 60u64,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        status_res,
+        CollateralStatusResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collateral_token: HumanAddr::from("asset0000"),
+            paused: false,
+            price_status: PriceStatus::Fresh,
+            last_updated_base: env.block.time,
+            last_updated_quote: env.block.time,
+        }
+    );
+
+    let status_res: CollateralStatusResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CollateralStatus {
+                collateral_token: HumanAddr::from("asset0000"),
+                block_time: env.block.time + 61u64,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(status_res.price_status, PriceStatus::Stale);
+
+    let execute_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+
+    let mut custody_env = mock_env("asset0000", &[]);
+    custody_env.block.time += 61u64;
+    let res = handle(&mut deps, custody_env, execute_msg.clone());
+    match res {
+        Err(ContractError::PriceExpired {
+            collateral_token,
+            last_updated,
+        }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"));
+            assert_eq!(last_updated, env.block.time);
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::PauseCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let res = handle(&mut deps, mock_env("asset0000", &[]), execute_msg.clone());
+    match res {
+        Err(ContractError::CollateralPaused { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::ResumeCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    handle(&mut deps, mock_env("asset0000", &[]), execute_msg).unwrap();
+}
+
+#[test]
+fn deprecate_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg.clone()).unwrap();
+
+    let deprecate_msg = HandleMsg::DeprecateCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, deprecate_msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    let res = handle(&mut deps, env, deprecate_msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deprecate_collateral"),
+            log("collateral_token", "asset0000"),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::DeprecatedCollaterals {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let deprecated_res: DeprecatedCollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        deprecated_res,
+        DeprecatedCollateralsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collaterals: vec![HumanAddr::from("asset0000")],
+        }
+    );
+
+    // new bids are rejected
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = handle(&mut deps, env, msg);
+    match res {
+        Err(ContractError::CollateralDeprecated { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // executions without an outstanding bid are rejected
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("custody0000"),
+        amount: Uint128::from(1000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0001"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env, msg);
+    match res {
+        Err(ContractError::CollateralDeprecated { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // outstanding bids can still be retracted
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: None,
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }]
+        })]
+    );
+}
+
+#[test]
+fn collateral_decimals() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // unregistered collaterals have 6 decimals
+    let res: CollateralInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CollateralInfo {
+                collateral_token: HumanAddr::from("asset0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(6u8, res.decimals);
+
+    let msg = HandleMsg::RegisterCollateral {
+        collateral_token: HumanAddr::from("asset0000"),
+        decimals: 18u8,
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let res: CollateralInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::CollateralInfo {
+                collateral_token: HumanAddr::from("asset0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(18u8, res.decimals);
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // 10^18 raw units are 10^6 units in the 6-decimal basis
+    // required_stable 495,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000000000000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("fee0000")),
+                repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let res: BidResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Bid {
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(Uint256::from(505000u64), res.amount);
+
+    // collateral value 1,000,000 * 0.5 = 500,000
+    let res: LiquidationAmountResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationAmount {
+                borrow_amount: Uint256::from(1000000u64),
+                borrow_limit: Uint256::from(250000u64),
+                collaterals: vec![(
+                    HumanAddr::from("asset0000"),
+                    Uint256::from(1000000000000000000u128),
+                )],
+                collateral_prices: vec![Decimal256::percent(50)],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.collaterals,
+        vec![(
+            HumanAddr::from("asset0000"),
+            Uint256::from(1000000000000000000u128),
+        )]
+    );
+}
+
+#[test]
+fn cross_bid() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("asset0000".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+        (
+            &("asset0001".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+        (
+            &("asset0002".to_string(), "uusd".to_string()),
+            &(Decimal256::percent(50), env.block.time, env.block.time),
+        ),
+    ]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitCrossBid {
+        collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0000")],
+        premium_rate: Decimal256::percent(1),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    match handle(&mut deps, env.clone(), msg) {
+        Err(ContractError::DuplicateCollateral { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::SubmitCrossBid {
+        collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0001")],
+        premium_rate: Decimal256::percent(1),
+    };
+    handle(&mut deps, env, msg).unwrap();
+
+    // required_stable 495,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    handle(&mut deps, mock_env("asset0001", &[]), msg.clone()).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::CrossBid {
+            bidder: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let cross_bid_res: CrossBidResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        cross_bid_res,
+        CrossBidResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bidder: HumanAddr::from("addr0000"),
+            collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0001"),],
+            amount: Uint256::from(505000u64),
+            premium_rate: Decimal256::percent(1),
+        }
+    );
+
+    // collaterals outside the set cannot fill the cross bid
+    match handle(&mut deps, mock_env("asset0002", &[]), msg) {
+        Err(ContractError::BidNotFound {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::RetractCrossBid { amount: None };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500000u128), // 505000 / (1 + tax_rate)
+            }]
+        })]
+    );
+
+    match query(
+        &deps,
+        QueryMsg::CrossBid {
+            bidder: HumanAddr::from("addr0000"),
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No cross bid exists"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn recover_from_halt() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: Some(Decimal256::percent(2)),
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: Some(HumanAddr::from("guardian0000")),
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // the chain was halted for 600 seconds
+    let mut recover_env = mock_env("addr0000", &[]);
+    recover_env.block.time = env.block.time + 1000u64;
+    let msg = HandleMsg::RecoverFromHalt {
+        halted_at: env.block.time + 400u64,
+    };
+    match handle(&mut deps, recover_env.clone(), msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    recover_env.message.sender = HumanAddr::from("guardian0000");
+    let res = handle(&mut deps, recover_env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "recover_from_halt"),
+            log("halted_at", env.block.time + 400u64),
+            log("duration", 600u64),
+        ]
+    );
+
+    let value: PendingConfigResponse =
+        from_binary(&query(&deps, QueryMsg::PendingConfig {}).unwrap()).unwrap();
+    assert_eq!(
+        value.effective_at,
+        env.block.time + CONFIG_TIMELOCK_PERIOD + 600u64
+    );
+
+    let value: Option<HaltRecovery> =
+        from_binary(&query(&deps, QueryMsg::HaltRecovery {}).unwrap()).unwrap();
+    assert_eq!(
+        value,
+        Some(HaltRecovery {
+            halted_at: env.block.time + 400u64,
+            recovered_at: env.block.time + 1000u64,
+        })
+    );
+
+    // the same halt cannot be recovered twice
+    match handle(&mut deps, recover_env, msg) {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => assert_eq!(
+            msg,
+            format!(
+                "Halt has already been recovered: {}",
+                env.block.time + 1000u64
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn migrate_bids() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::permille(25),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    handle(&mut deps, env.clone(), msg.clone()).unwrap();
+
+    let msg = HandleMsg::SubmitCrossBid {
+        collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0001")],
+        premium_rate: Decimal256::percent(1),
+    };
+    handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::MigrateBids {
+        new_liquidation_queue: HumanAddr::from("queue0000"),
+        limit: None,
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // regular bids are migrated first with the premium rounded down
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg.clone()).unwrap();
+    assert!(res.log.contains(&log("rounded_premium_rates", 1)));
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("queue0000"),
+            send: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(990099u128),
+            }],
+            msg: to_binary(&LiquidationQueueHandleMsg::SubmitBid {
+                collateral_token: HumanAddr::from("asset0000"),
+                premium_slot: 2u8,
+                bidder: Some(HumanAddr::from("addr0000")),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let msg2 = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0001"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    match handle(&mut deps, env, msg2) {
+        Err(ContractError::BidsMigrating {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg2 = HandleMsg::MigrateBids {
+        new_liquidation_queue: HumanAddr::from("queue0001"),
+        limit: None,
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg2) {
+        Err(ContractError::BidsMigratingTo {
+            new_liquidation_queue,
+        }) => {
+            assert_eq!(new_liquidation_queue, HumanAddr::from("queue0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // cross bids are refunded afterwards
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(990099u128),
+            }],
+        })]
+    );
+
+    let res = query(&deps, QueryMsg::BidMigration {}).unwrap();
+    let migration: BidMigrationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        migration.new_liquidation_queue,
+        Some(HumanAddr::from("queue0000"))
+    );
+
+    // premium rates past the last queue slot are not migrated
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(300),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+    let _res = init(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(256),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::MigrateBids {
+        new_liquidation_queue: HumanAddr::from("queue0000"),
+        limit: None,
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg) {
+        Err(ContractError::PremiumSlotOverflow { premium_rate }) => {
+            assert_eq!(premium_rate, Decimal256::percent(256))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn reserve_fee_share() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let mut msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: Some(HumanAddr::from("reserve0000")),
+        reserve_fee_share: Some(Decimal256::percent(101)),
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg.clone()) {
+        Err(ContractError::InvalidReserveFeeShare {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateConfig {
+        reserve_fee_share, ..
+    } = &mut msg
+    {
+        *reserve_fee_share = Some(Decimal256::percent(40));
+    }
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let res = query(&deps, QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config.reserve_contract,
+        Some(HumanAddr::from("reserve0000"))
+    );
+    assert_eq!(config.reserve_fee_share, Decimal256::percent(40));
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // bid_fee         4,950
+    // reserve_fee     1,980
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("fee0000")),
+                repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[2..].to_vec(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("reserve0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1960u128), // 1980 / (1 + tax_rate)
+                }]
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("fee0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(2940u128), // 2970 / (1 + tax_rate)
+                }]
+            }),
+        ]
+    );
+}
+
+#[test]
+fn bid_expiry() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let mut env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: Some(env.block.time),
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(ContractError::InvalidBidExpiry {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: Some(env.block.time + 100),
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    handle(&mut deps, env.clone(), msg).unwrap();
+
+    env.block.time += 100;
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let mut asset_env = env.clone();
+    asset_env.message.sender = HumanAddr::from("asset0000");
+    match handle(&mut deps, asset_env, msg) {
+        Err(ContractError::BidExpired {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // anyone can send the expired bid back to its bidder
+    let msg = HandleMsg::PruneExpiredBids {
+        collateral_token: HumanAddr::from("asset0000"),
+        limit: None,
+    };
+    let mut prune_env = env.clone();
+    prune_env.message.sender = HumanAddr::from("addr0001");
+    let res = handle(&mut deps, prune_env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(990099u128),
+            }],
+        })]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Bid {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No bids with the specified information exist")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn referral_fees() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let mut msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: Some(Decimal256::percent(90)),
+        referral_fee_share: Some(Decimal256::percent(20)),
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg.clone()) {
+        Err(ContractError::InvalidFeeShares {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateConfig {
+        reserve_fee_share, ..
+    } = &mut msg
+    {
+        *reserve_fee_share = None;
+    }
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: Some(HumanAddr::from("referrer0000")),
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // bid_fee         4,950
+    // referral_fee    990
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("fee0000")),
+                repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[2],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("fee0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(3920u128), // 3960 / (1 + tax_rate)
+            }]
+        })
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::ReferralFees {
+            referrer: HumanAddr::from("referrer0000"),
+        },
+    )
+    .unwrap();
+    let referral_fees: ReferralFeesResponse = from_binary(&res).unwrap();
+    assert_eq!(referral_fees.pending_fees, Uint256::from(990u64));
+
+    let res = handle(
+        &mut deps,
+        mock_env("referrer0000", &[]),
+        HandleMsg::ClaimReferralFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("referrer0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(980u128), // 990 / (1 + tax_rate)
+            }],
+        })]
+    );
+
+    match handle(
+        &mut deps,
+        mock_env("referrer0000", &[]),
+        HandleMsg::ClaimReferralFees {},
+    ) {
+        Err(ContractError::NoReferralFees {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn move_bid() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::MoveBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        new_premium_rate: Decimal256::percent(6),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(ContractError::PremiumRateTooHigh { max_premium_rate }) => {
+            assert_eq!(max_premium_rate, Decimal256::percent(5))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::MoveBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        new_premium_rate: Decimal256::percent(3),
+    };
+    match handle(&mut deps, mock_env("addr0001", &[]), msg.clone()) {
+        Err(ContractError::BidNotFound {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::Bid {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let bid: BidResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        bid,
+        BidResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+            amount: Uint256::from(1000000u64),
+            premium_rate: Decimal256::percent(3),
+            expires_at: None,
+            unlock_receiver: None,
+        }
+    );
+}
+
+#[test]
+fn bid_unlock_receiver() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: Some(HumanAddr::from("exchange0000")),
+    };
+    handle(&mut deps, env, msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::Bid {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let bid: BidResponse = from_binary(&res).unwrap();
+    assert_eq!(bid.unlock_receiver, Some(HumanAddr::from("exchange0000")));
+
+    // the collateral goes to the receiver of the bid
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(100000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("exchange0000"),
+                amount: Uint128::from(100000u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    let msg_update = HandleMsg::UpdateBidReceiver {
+        collateral_token: HumanAddr::from("asset0000"),
+        unlock_receiver: None,
+    };
+    match handle(&mut deps, mock_env("addr0001", &[]), msg_update.clone()) {
+        Err(ContractError::BidNotFound {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg_update).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "update_bid_receiver"),
+            log("collateral_token", "asset0000"),
+            log("bidder", "addr0000"),
+            log("unlock_receiver", "addr0000"),
+        ]
+    );
+
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(100000u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    // blacklisted addresses cannot receive the collateral
+    let msg = HandleMsg::UpdateBlacklist {
+        add: vec![HumanAddr::from("exchange0001")],
+        remove: vec![],
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    let msg = HandleMsg::UpdateBidReceiver {
+        collateral_token: HumanAddr::from("asset0000"),
+        unlock_receiver: Some(HumanAddr::from("exchange0001")),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(ContractError::Blacklisted { address }) => {
+            assert_eq!(address, HumanAddr::from("exchange0001"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn bid_logs() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log[3..].to_vec(),
+        vec![
+            log("bidder", "addr0000"),
+            log("premium_rate", "0.01"),
+            log("bid_amount_before", 0),
+            log("bid_amount_after", 1000000),
+        ]
+    );
+
+    // required_stable 495,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log[7..].to_vec(),
+        vec![
+            log("bidder", "addr0000"),
+            log("premium_rate", "0.01"),
+            log("bid_amount_before", 1000000),
+            log("bid_amount_after", 505000),
+        ]
+    );
+
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "retract_bid"),
+            log("collateral_token", "asset0000"),
+            log("amount", 505000),
+            log("bidder", "addr0000"),
+            log("premium_rate", "0.01"),
+            log("bid_amount_before", 505000),
+            log("bid_amount_after", 0),
+        ]
+    );
+}
+
+#[test]
+fn invariants() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    for bidder in ["addr0000", "addr0001"].iter() {
+        let msg = HandleMsg::SubmitBid {
+            collateral_token: HumanAddr::from("asset0000"),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            referrer: None,
+            bid_for: None,
+            unlock_receiver: None,
+        };
+        let env = mock_env(
+            *bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: Some(Uint256::from(400000u64)),
+    };
+    handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    let res: InvariantsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Invariants {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        InvariantsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            collateral_token: HumanAddr::from("asset0000"),
+            sampled_bids: 2,
+            total_bid_amount: Uint256::from(1600000u64),
+            total_active_bid_amount: Uint256::from(1600000u64),
+            drift: Uint256::zero(),
+            mismatched_bidders: vec![],
+            last_bidder: Some(HumanAddr::from("addr0001")),
+        }
+    );
+
+    let res: InvariantsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Invariants {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.sampled_bids, 1);
+    assert_eq!(res.total_bid_amount, Uint256::from(1000000u64));
+    assert_eq!(res.last_bidder, Some(HumanAddr::from("addr0000")));
+}
+
+#[test]
+fn sweep_dust() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    for (bidder, amount) in [("addr0000", 500u128), ("addr0001", 1000000u128)].iter() {
+        let msg = HandleMsg::SubmitBid {
+            collateral_token: HumanAddr::from("asset0000"),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            referrer: None,
+            bid_for: None,
+            unlock_receiver: None,
+        };
+        let env = mock_env(
+            *bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(*amount),
+            }],
+        );
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    let msg = HandleMsg::SweepDust {
+        collateral_token: HumanAddr::from("asset0000"),
+        limit: None,
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match handle(&mut deps, mock_env("owner0000", &[]), msg.clone()) {
+        Err(ContractError::ReserveContractNotSet {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: Some(HumanAddr::from("reserve0000")),
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: Some(Uint256::from(1000u64)),
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), update_msg).unwrap();
+
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("reserve0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(495u128),
+            }],
+        })]
+    );
+    assert_eq!(
+        res.log[..4].to_vec(),
+        vec![
+            log("action", "sweep_dust"),
+            log("collateral_token", "asset0000"),
+            log("swept_count", 1),
+            log("swept_amount", 500),
+        ]
+    );
+
+    // the remaining bid is untouched
+    let res: BidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BidsByCollateral {
+                collateral_token: HumanAddr::from("asset0000"),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.bids.len(), 1);
+    assert_eq!(res.bids[0].bidder, HumanAddr::from("addr0001"));
+
+    let res: SweptDustResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::SweptDust {
+                collateral_token: HumanAddr::from("asset0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_swept, Uint256::from(500u64));
+
+    match handle(&mut deps, mock_env("owner0000", &[]), msg) {
+        Err(ContractError::NoDust {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn migrate_contract_version() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        read_contract_version(&deps.storage).unwrap(),
+        CONTRACT_VERSION
+    );
+
+    // contract deployed before versioning
+    store_contract_version(&mut deps.storage, 0).unwrap();
+    let res = migrate(&mut deps, env.clone(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "migrate"),
+            log("from_version", 0),
+            log("to_version", CONTRACT_VERSION),
+        ]
+    );
+    assert_eq!(
+        read_contract_version(&deps.storage).unwrap(),
+        CONTRACT_VERSION
+    );
+
+    store_contract_version(&mut deps.storage, CONTRACT_VERSION + 1).unwrap();
+    match migrate(&mut deps, env, MigrateMsg {}) {
+        Err(ContractError::NewerContractVersion { version }) => {
+            assert_eq!(version, CONTRACT_VERSION + 1)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn delegated_bid() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: Some(HumanAddr::from("treasury0000")),
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(ContractError::DelegatedBidsDisabled {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: Some(true),
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), update_msg).unwrap();
+
+    handle(&mut deps, env, msg).unwrap();
+    let bid: BidResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::Bid {
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("treasury0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(bid.amount, Uint256::from(1000000u64));
+
+    // only the recorded bidder can retract
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: None,
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(ContractError::BidNotFound {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("treasury0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("treasury0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn claim_as_converter() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::SetClaimAs {
+        collateral_token: HumanAddr::from("asset0000"),
+        claim_as: Some(ClaimAsset::Unbond),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(ContractError::ConverterNotFound { collateral_token }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let register_msg = HandleMsg::RegisterConverter {
+        collateral_token: HumanAddr::from("asset0000"),
+        claim_as: ClaimAsset::Unbond,
+        converter: Some(HumanAddr::from("unbonder0000")),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), register_msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    handle(&mut deps, mock_env("owner0000", &[]), register_msg).unwrap();
+
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_claim_as"),
+            log("bidder", "addr0000"),
+            log("collateral_token", "asset0000"),
+            log("claim_as", "unbond"),
+        ]
+    );
+
+    let res: ClaimAsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ClaimAs {
+                collateral_token: HumanAddr::from("asset0000"),
+                bidder: HumanAddr::from("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.claim_as, Some(ClaimAsset::Unbond));
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let res = handle(&mut deps, mock_env("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("asset0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: HumanAddr::from("unbonder0000"),
+                amount: Uint128::from(1000000u128),
+                msg: Some(
+                    to_binary(&ConverterHookMsg::Convert {
+                        claim_as: ClaimAsset::Unbond,
+                        recipient: HumanAddr::from("addr0000"),
+                    })
+                    .unwrap()
+                ),
+            })
+            .unwrap(),
+        })
+    );
+}
+
+#[test]
+fn bid_incentives() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    let start_time = env.block.time;
+
+    let msg = HandleMsg::SetIncentiveRate {
+        collateral_token: HumanAddr::from("asset0000"),
+        emission_rate: Decimal256::from_uint256(10u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    handle(&mut deps, env, msg).unwrap();
+
+    for (bidder, amount) in [("addr0000", 100u128), ("addr0001", 300u128)].iter() {
+        let msg = HandleMsg::SubmitBid {
+            collateral_token: HumanAddr::from("asset0000"),
+            premium_rate: Decimal256::percent(1),
+            expires_at: None,
+            referrer: None,
+            bid_for: None,
+            unlock_receiver: None,
+        };
+        let env = mock_env(
+            *bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(*amount),
+            }],
+        );
+        handle(&mut deps, env, msg).unwrap();
+    }
+
+    let rewards: PendingRewardsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::PendingRewards {
+                bidder: HumanAddr::from("addr0001"),
+                collateral_token: HumanAddr::from("asset0000"),
+                block_time: Some(start_time + 100),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        rewards,
+        PendingRewardsResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bidder: HumanAddr::from("addr0001"),
+            collateral_token: HumanAddr::from("asset0000"),
+            emission_rate: Decimal256::from_uint256(10u64),
+            pending_rewards: Uint256::from(750u64),
+        }
+    );
+
+    let mut env = mock_env("addr0000", &[]);
+    env.block.time = start_time + 100;
+    let msg = HandleMsg::ClaimRewards {
+        collateral_token: HumanAddr::from("asset0000"),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(ContractError::RewardTokenNotSet {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: Some(HumanAddr::from("reward0000")),
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), update_msg).unwrap();
+
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("reward0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(250u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res = handle(&mut deps, env, msg);
+    match res {
+        Err(ContractError::NoRewards {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn tax_policy() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("owner0000", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateTaxPolicy {
+        tax_policy: TaxPolicy::None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    handle(&mut deps, env, msg).unwrap();
+
+    let tax_policy: TaxPolicy =
+        from_binary(&query(&deps, QueryMsg::TaxPolicy {}).unwrap()).unwrap();
+    assert_eq!(tax_policy, TaxPolicy::None);
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RetractBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: None,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn fill_limit() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: Some(Uint256::from(600000u64)),
+        fill_window: Some(0u64),
+        fee_admin: None,
+        overseer_contract: None,
+    };
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    // required_stable 495,000
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let mut env = mock_env("asset0000", &[]);
+    handle(&mut deps, env.clone(), msg.clone()).unwrap();
+
+    // the second fill in the same block exceeds the limit
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(ContractError::FillLimitExceeded {
+            collateral_token,
+            remaining_limit,
+        }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"));
+            assert_eq!(remaining_limit, Uint256::from(105000u64));
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // and succeeds in the next block
+    env.block.height += 1;
+    handle(&mut deps, env, msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::Bid {
+            collateral_token: HumanAddr::from("asset0000"),
+            bidder: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let bid_response: BidResponse = from_binary(&res).unwrap();
+    assert_eq!(bid_response.amount, Uint256::from(10000u64));
+}
+
+#[test]
+fn accrued_fees() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        safe_ratio: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        max_premium_rate: None,
+        liquidation_threshold: None,
+        max_close_factor: None,
+        price_timeframe: None,
+        price_mode: None,
+        guardian: None,
+        min_liquidation: None,
+        reserve_contract: None,
+        reserve_fee_share: None,
+        referral_fee_share: None,
+        dust_threshold: None,
+        delegated_bids_enabled: None,
+        reward_token: None,
+        max_fill_per_window: None,
+        fill_window: None,
+        fee_admin: Some(HumanAddr::from("admin0000")),
+        overseer_contract: None,
+    };
+    let env = mock_env("owner0000", &[]);
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
     handle(&mut deps, env, msg).unwrap();
 
-    let bids: BidsResponse = from_binary(
+    // required_stable 495,000
+    // bid_fee         4,950
+    // repay_amount    490,050
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: Some(HumanAddr::from("fee0000")),
+                repay_address: Some(HumanAddr::from("repay0000")),
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("repay0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(490050u128),
+                }]
+            }),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::AccruedFees {}).unwrap();
+    let fees_response: AccruedFeesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        fees_response,
+        AccruedFeesResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            fee_admin: Some(HumanAddr::from("admin0000")),
+            denom: "uusd".to_string(),
+            pending_fees: Uint256::from(4950u64),
+            withdrawn_fees: Uint256::zero(),
+        }
+    );
+
+    let msg = HandleMsg::WithdrawFees {
+        recipient: HumanAddr::from("splitter0000"),
+        amount: None,
+    };
+    let env = mock_env("owner0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("admin0000", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::WithdrawFees {
+            recipient: HumanAddr::from("splitter0000"),
+            amount: Some(Uint256::from(5000u64)),
+        },
+    );
+    match res {
+        Err(ContractError::WithdrawExceedsFees { pending_fees }) => {
+            assert_eq!(pending_fees, Uint256::from(4950u64))
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("splitter0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(4950u128),
+            }]
+        })]
+    );
+
+    let res = query(&deps, QueryMsg::AccruedFees {}).unwrap();
+    let fees_response: AccruedFeesResponse = from_binary(&res).unwrap();
+    assert_eq!(fees_response.pending_fees, Uint256::zero());
+    assert_eq!(fees_response.withdrawn_fees, Uint256::from(4950u64));
+}
+
+#[test]
+fn price_bounds() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(Decimal256::percent(50), env.block.time, env.block.time),
+    )]);
+
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SetPriceBounds {
+        collateral_token: HumanAddr::from("asset0000"),
+        min_price: Some(Decimal256::percent(60)),
+        max_price: Some(Decimal256::percent(200)),
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let invalid_msg = HandleMsg::SetPriceBounds {
+        collateral_token: HumanAddr::from("asset0000"),
+        min_price: Some(Decimal256::percent(200)),
+        max_price: Some(Decimal256::percent(60)),
+    };
+    let env = mock_env("owner0000", &[]);
+    let res = handle(&mut deps, env.clone(), invalid_msg);
+    match res {
+        Err(ContractError::InvalidPriceBounds {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_price_bounds"),
+            log("collateral_token", "asset0000"),
+            log("min_price", "0.6"),
+            log("max_price", "2"),
+        ]
+    );
+
+    let res: CollateralInfoResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByUser {
-                bidder: HumanAddr::from("addr0000"),
-                start_after: None,
-                limit: None,
+            QueryMsg::CollateralInfo {
+                collateral_token: HumanAddr::from("asset0000"),
             },
         )
         .unwrap(),
     )
     .unwrap();
-    assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0000"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(1000000u128),
-                    premium_rate: Decimal256::percent(1),
-                },
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0001"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(2000000u128),
-                    premium_rate: Decimal256::percent(2),
-                },
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0002"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(3000000u128),
-                    premium_rate: Decimal256::percent(3),
-                }
-            ]
+    assert_eq!(res.min_price, Some(Decimal256::percent(60)));
+    assert_eq!(res.max_price, Some(Decimal256::percent(200)));
+
+    let msg = HandleMsg::SubmitBid {
+        collateral_token: HumanAddr::from("asset0000"),
+        premium_rate: Decimal256::percent(1),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0001"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: HumanAddr::from("addr0000"),
+                fee_address: None,
+                repay_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })
+            .unwrap(),
+        ),
+    });
+    let env = mock_env("asset0000", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(ContractError::PriceOutOfBounds {
+            collateral_token,
+            price,
+        }) => {
+            assert_eq!(collateral_token, HumanAddr::from("asset0000"));
+            assert_eq!(price, Decimal256::percent(50));
         }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // removing the lower bound lets the bid execute
+    let bounds_msg = HandleMsg::SetPriceBounds {
+        collateral_token: HumanAddr::from("asset0000"),
+        min_price: None,
+        max_price: Some(Decimal256::percent(200)),
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), bounds_msg).unwrap();
+    handle(&mut deps, env, msg).unwrap();
+}
+
+#[test]
+fn price_source() {
+    let mut deps = mock_dependencies(20, &[]);
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
+        max_premium_rate: Decimal256::percent(5),
+        liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
+        price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
+    };
+
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::SetPriceSource {
+        collateral_token: HumanAddr::from("asset0000"),
+        oracle_contract: Some(HumanAddr::from("oracle0001")),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_price_source"),
+            log("collateral_token", "asset0000"),
+            log("oracle_contract", "oracle0001"),
+        ]
     );
 
-    let bids: BidsResponse = from_binary(
+    let res: CollateralInfoResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByUser {
-                bidder: HumanAddr::from("addr0000"),
-                start_after: Some(HumanAddr::from("asset0000")),
-                limit: None,
+            QueryMsg::CollateralInfo {
+                collateral_token: HumanAddr::from("asset0000"),
             },
         )
         .unwrap(),
     )
     .unwrap();
-    assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0001"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(2000000u128),
-                    premium_rate: Decimal256::percent(2),
-                },
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0002"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(3000000u128),
-                    premium_rate: Decimal256::percent(3),
-                }
-            ]
-        }
-    );
-    let bids: BidsResponse = from_binary(
+    assert_eq!(res.price_source, Some(HumanAddr::from("oracle0001")));
+
+    // removing the override falls back to the configured oracle
+    let msg = HandleMsg::SetPriceSource {
+        collateral_token: HumanAddr::from("asset0000"),
+        oracle_contract: None,
+    };
+    let res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+    assert_eq!(res.log[2], log("oracle_contract", ""));
+
+    let res: CollateralInfoResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByUser {
-                bidder: HumanAddr::from("addr0000"),
-                start_after: None,
-                limit: Some(1u32),
+            QueryMsg::CollateralInfo {
+                collateral_token: HumanAddr::from("asset0000"),
             },
         )
         .unwrap(),
     )
     .unwrap();
-    assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![BidResponse {
-                collateral_token: HumanAddr::from("asset0000"),
-                bidder: HumanAddr::from("addr0000"),
-                amount: Uint256::from(1000000u128),
-                premium_rate: Decimal256::percent(1),
-            }]
-        }
-    );
+    assert_eq!(res.price_source, None);
 }
 
 #[test]
-fn query_bids_by_collateral() {
+fn export_bids() {
     let mut deps = mock_dependencies(20, &[]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-    deps.querier.with_oracle_price(&[(
-        &("asset0000".to_string(), "uusd".to_string()),
-        &(Decimal256::percent(50), 123456u64, 123456u64),
-    )]);
-
     let msg = InitMsg {
         owner: HumanAddr::from("owner0000"),
         oracle_contract: HumanAddr::from("oracle0000"),
         stable_denom: "uusd".to_string(),
         safe_ratio: Decimal256::percent(10),
         bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::zero(),
         max_premium_rate: Decimal256::percent(5),
         liquidation_threshold: Uint256::from(100000000u64),
+        max_close_factor: Decimal256::one(),
         price_timeframe: 60u64,
+        price_mode: PriceMode::Spot,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -732,6 +5190,10 @@ fn query_bids_by_collateral() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(1),
+        expires_at: Some(1000000000000u64),
+        referrer: Some(HumanAddr::from("referrer0000")),
+        bid_for: None,
+        unlock_receiver: None,
     };
     let env = mock_env(
         "addr0000",
@@ -745,6 +5207,10 @@ fn query_bids_by_collateral() {
     let msg = HandleMsg::SubmitBid {
         collateral_token: HumanAddr::from("asset0000"),
         premium_rate: Decimal256::percent(2),
+        expires_at: None,
+        referrer: None,
+        bid_for: None,
+        unlock_receiver: None,
     };
     let env = mock_env(
         "addr0001",
@@ -755,12 +5221,12 @@ fn query_bids_by_collateral() {
     );
     handle(&mut deps, env, msg).unwrap();
 
-    let msg = HandleMsg::SubmitBid {
-        collateral_token: HumanAddr::from("asset0001"),
+    let msg = HandleMsg::SubmitCrossBid {
+        collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0001")],
         premium_rate: Decimal256::percent(3),
     };
     let env = mock_env(
-        "addr0000",
+        "addr0002",
         &[Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(3000000u128),
@@ -768,11 +5234,10 @@ fn query_bids_by_collateral() {
     );
     handle(&mut deps, env, msg).unwrap();
 
-    let bids: BidsResponse = from_binary(
+    let res: ExportBidsResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByCollateral {
-                collateral_token: HumanAddr::from("asset0000"),
+            QueryMsg::ExportBids {
                 start_after: None,
                 limit: None,
             },
@@ -780,70 +5245,74 @@ fn query_bids_by_collateral() {
         .unwrap(),
     )
     .unwrap();
-    assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0000"),
-                    bidder: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(1000000u128),
-                    premium_rate: Decimal256::percent(1),
-                },
-                BidResponse {
-                    collateral_token: HumanAddr::from("asset0000"),
-                    bidder: HumanAddr::from("addr0001"),
-                    amount: Uint256::from(2000000u128),
-                    premium_rate: Decimal256::percent(2),
-                }
-            ]
-        }
-    );
+    assert_eq!(res.bids.len(), 2);
+    let first_bid = res.bids[0].clone();
+    let second_bid = res.bids[1].clone();
+    assert!(res.bids.contains(&ExportedBid {
+        bidder: HumanAddr::from("addr0000"),
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: Uint256::from(1000000u64),
+        premium_rate: Decimal256::percent(1),
+        expires_at: Some(1000000000000u64),
+        referrer: Some(HumanAddr::from("referrer0000")),
+        unlock_receiver: None,
+    }));
+    assert!(res.bids.contains(&ExportedBid {
+        bidder: HumanAddr::from("addr0001"),
+        collateral_token: HumanAddr::from("asset0000"),
+        amount: Uint256::from(2000000u64),
+        premium_rate: Decimal256::percent(2),
+        expires_at: None,
+        referrer: None,
+        unlock_receiver: None,
+    }));
 
-    let bids: BidsResponse = from_binary(
+    // pages continue after the given bid
+    let res: ExportBidsResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByCollateral {
-                collateral_token: HumanAddr::from("asset0000"),
-                start_after: Some(HumanAddr::from("addr0000")),
-                limit: None,
+            QueryMsg::ExportBids {
+                start_after: Some((first_bid.bidder, first_bid.collateral_token)),
+                limit: Some(1u32),
             },
         )
         .unwrap(),
     )
     .unwrap();
-    assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![BidResponse {
-                collateral_token: HumanAddr::from("asset0000"),
-                bidder: HumanAddr::from("addr0001"),
-                amount: Uint256::from(2000000u128),
-                premium_rate: Decimal256::percent(2),
-            }]
-        }
-    );
-    let bids: BidsResponse = from_binary(
+    assert_eq!(res.bids, vec![second_bid]);
+
+    let res: ExportCrossBidsResponse = from_binary(
         &query(
             &deps,
-            QueryMsg::BidsByCollateral {
-                collateral_token: HumanAddr::from("asset0000"),
+            QueryMsg::ExportCrossBids {
                 start_after: None,
-                limit: Some(1u32),
+                limit: None,
             },
         )
         .unwrap(),
     )
     .unwrap();
     assert_eq!(
-        bids,
-        BidsResponse {
-            bids: vec![BidResponse {
-                collateral_token: HumanAddr::from("asset0000"),
-                bidder: HumanAddr::from("addr0000"),
-                amount: Uint256::from(1000000u128),
-                premium_rate: Decimal256::percent(1),
-            }]
-        }
+        res.cross_bids,
+        vec![CrossBidResponse {
+            schema_version: LIQUIDATION_SCHEMA_VERSION,
+            bidder: HumanAddr::from("addr0002"),
+            collateral_tokens: vec![HumanAddr::from("asset0000"), HumanAddr::from("asset0001")],
+            amount: Uint256::from(3000000u64),
+            premium_rate: Decimal256::percent(3),
+        }]
     );
+
+    let res: ExportCrossBidsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ExportCrossBids {
+                start_after: Some(HumanAddr::from("addr0002")),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.cross_bids, vec![]);
 }
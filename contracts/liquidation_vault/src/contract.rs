@@ -0,0 +1,372 @@
+use crate::state::{
+    read_config, read_shares, read_state, read_strategy, store_collateral, store_config,
+    store_shares, store_state, store_strategy, Allocation, Config, State,
+};
+use crate::strategy::{
+    compute_collateral_values, compute_total_value, load_holdings, rebalance, retracted_amount,
+};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
+use moneymarket::liquidation_vault::{
+    ConfigResponse, HandleMsg, InitMsg, QueryMsg, SharesResponse, StateResponse,
+    StrategyAllocation, StrategyResponse,
+};
+use moneymarket::overseer::HandleMsg as OverseerHandleMsg;
+use moneymarket::payout::{native_payout_msg, token_payout_msg};
+use moneymarket::rounding::{self, Rounding};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        &mut deps.storage,
+        &Config {
+            contract_addr: deps.api.canonical_address(&env.contract.address)?,
+            owner: deps.api.canonical_address(&msg.owner)?,
+            stable_denom: msg.stable_denom,
+            liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
+            overseer_contract: deps.api.canonical_address(&msg.overseer_contract)?,
+            oracle_contract: deps.api.canonical_address(&msg.oracle_contract)?,
+        },
+    )?;
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_shares: Uint256::zero(),
+        },
+    )?;
+    store_strategy(&mut deps.storage, &[])?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig { owner } => update_config(deps, env, owner),
+        HandleMsg::UpdateStrategy { allocations } => update_strategy(deps, env, allocations),
+        HandleMsg::DepositStable {} => deposit_stable(deps, env),
+        HandleMsg::Withdraw { shares } => withdraw(deps, env, shares),
+        HandleMsg::Rebalance {} => rebalance(deps, env),
+        HandleMsg::Liquidate { borrower } => liquidate(deps, borrower),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: Option<HumanAddr>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn update_strategy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    allocations: Vec<StrategyAllocation>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut total_weight = Decimal256::zero();
+    let mut strategy: Vec<Allocation> = vec![];
+    for allocation in allocations.iter() {
+        let collateral_token = deps.api.canonical_address(&allocation.collateral_token)?;
+        if strategy
+            .iter()
+            .any(|elem| elem.collateral_token == collateral_token)
+        {
+            return Err(StdError::generic_err(format!(
+                "Duplicate collateral in strategy: {}",
+                allocation.collateral_token
+            )));
+        }
+
+        total_weight += allocation.weight;
+        strategy.push(Allocation {
+            collateral_token,
+            premium_rate: allocation.premium_rate,
+            weight: allocation.weight,
+        });
+    }
+
+    if total_weight > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "Sum of the strategy weights cannot exceed one",
+        ));
+    }
+
+    for allocation in strategy.iter() {
+        store_collateral(&mut deps.storage, &allocation.collateral_token)?;
+    }
+    store_strategy(&mut deps.storage, &strategy)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "update_strategy"),
+            log("allocations", strategy.len()),
+        ],
+        data: None,
+    })
+}
+
+pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    // Check base denom deposit
+    let deposit_amount: Uint256 = env
+        .message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    // Cannot deposit zero amount
+    if deposit_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Deposit amount must be greater than 0 {}",
+            config.stable_denom,
+        )));
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    let mint_amount = if state.total_shares.is_zero() {
+        deposit_amount
+    } else {
+        // the sent funds are already part of the balance
+        let holdings = load_holdings(deps, &env.contract.address)?;
+        let total_value = compute_total_value(deps, &holdings)? - deposit_amount;
+        if total_value.is_zero() {
+            return Err(StdError::generic_err(
+                "Vault has no value backing its shares",
+            ));
+        }
+
+        let share_price =
+            Decimal256::from_uint256(total_value) / Decimal256::from_uint256(state.total_shares);
+        rounding::div(deposit_amount, share_price, Rounding::Down)
+    };
+
+    let depositor_raw = deps.api.canonical_address(&env.message.sender)?;
+    let shares = read_shares(&deps.storage, &depositor_raw)?;
+    store_shares(&mut deps.storage, &depositor_raw, shares + mint_amount)?;
+
+    state.total_shares += mint_amount;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deposit_stable"),
+            log("depositor", env.message.sender),
+            log("mint_amount", mint_amount),
+            log("deposit_amount", deposit_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    shares: Uint256,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let sender_shares = read_shares(&deps.storage, &sender_raw)?;
+    if shares.is_zero() || shares > sender_shares {
+        return Err(StdError::generic_err(format!(
+            "Invalid withdraw amount; available shares: {}",
+            sender_shares
+        )));
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    let ratio = Decimal256::from_uint256(shares) / Decimal256::from_uint256(state.total_shares);
+    let holdings = load_holdings(deps, &env.contract.address)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut stable_amount = rounding::mul(holdings.stable_balance, ratio, Rounding::Down);
+    for (collateral_token, amount) in holdings.bids.into_iter() {
+        let retract_amount = rounding::mul(amount, ratio, Rounding::Down);
+        if retract_amount.is_zero() {
+            continue;
+        }
+
+        stable_amount += retracted_amount(deps, &config, retract_amount)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract.clone(),
+            send: vec![],
+            msg: to_binary(&LiquidationHandleMsg::RetractBid {
+                collateral_token,
+                amount: Some(retract_amount),
+            })?,
+        }));
+    }
+
+    for (collateral_token, amount) in holdings.collaterals.into_iter() {
+        let collateral_amount = rounding::mul(amount, ratio, Rounding::Down);
+        if collateral_amount.is_zero() {
+            continue;
+        }
+
+        messages.push(token_payout_msg(
+            collateral_token,
+            env.message.sender.clone(),
+            collateral_amount,
+        )?);
+    }
+
+    if !stable_amount.is_zero() {
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address,
+            env.message.sender.clone(),
+            config.stable_denom,
+            stable_amount,
+        )?);
+    }
+
+    store_shares(&mut deps.storage, &sender_raw, sender_shares - shares)?;
+    state.total_shares = state.total_shares - shares;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "withdraw"),
+            log("withdrawer", env.message.sender),
+            log("burn_amount", shares),
+            log("stable_amount", stable_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn liquidate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.overseer_contract)?,
+            send: vec![],
+            msg: to_binary(&OverseerHandleMsg::LiquidateCollateral {
+                borrower: borrower.clone(),
+            })?,
+        })],
+        log: vec![log("action", "liquidate"), log("borrower", borrower)],
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Strategy {} => to_binary(&query_strategy(deps)?),
+        QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::Shares { address } => to_binary(&query_shares(deps, address)?),
+    }
+}
+
+pub fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?,
+        stable_denom: config.stable_denom,
+        liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
+        overseer_contract: deps.api.human_address(&config.overseer_contract)?,
+        oracle_contract: deps.api.human_address(&config.oracle_contract)?,
+    })
+}
+
+pub fn query_strategy<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<StrategyResponse> {
+    let allocations = read_strategy(&deps.storage)?
+        .into_iter()
+        .map(|allocation| {
+            Ok(StrategyAllocation {
+                collateral_token: deps.api.human_address(&allocation.collateral_token)?,
+                premium_rate: allocation.premium_rate,
+                weight: allocation.weight,
+            })
+        })
+        .collect::<StdResult<Vec<StrategyAllocation>>>()?;
+
+    Ok(StrategyResponse { allocations })
+}
+
+pub fn query_state<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<StateResponse> {
+    let state: State = read_state(&deps.storage)?;
+    let config: Config = read_config(&deps.storage)?;
+
+    // queries have no env, so the vault address is kept in the config
+    let holdings = load_holdings(deps, &deps.api.human_address(&config.contract_addr)?)?;
+    let values = compute_collateral_values(deps, &holdings)?;
+    let bid_amount = holdings.bid_amount();
+    let total_value = values
+        .iter()
+        .fold(holdings.stable_balance + bid_amount, |total, value| {
+            total + *value
+        });
+
+    Ok(StateResponse {
+        total_shares: state.total_shares,
+        stable_balance: holdings.stable_balance,
+        bid_amount,
+        collaterals: holdings
+            .collaterals
+            .into_iter()
+            .zip(values)
+            .map(|((collateral_token, amount), value)| (collateral_token, amount, value))
+            .collect(),
+        total_value,
+    })
+}
+
+pub fn query_shares<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+) -> StdResult<SharesResponse> {
+    let shares = read_shares(&deps.storage, &deps.api.canonical_address(&address)?)?;
+    Ok(SharesResponse { address, shares })
+}
@@ -0,0 +1,102 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, Order, ReadonlyStorage, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+
+static KEY_CONFIG: &[u8] = b"config";
+static KEY_STATE: &[u8] = b"state";
+static KEY_STRATEGY: &[u8] = b"strategy";
+
+static PREFIX_SHARES: &[u8] = b"shares";
+static PREFIX_COLLATERAL: &[u8] = b"collateral";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub contract_addr: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub stable_denom: String,
+    pub liquidation_contract: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub oracle_contract: CanonicalAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub total_shares: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Allocation {
+    pub collateral_token: CanonicalAddr,
+    pub premium_rate: Decimal256,
+    pub weight: Decimal256,
+}
+
+pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config<S: ReadonlyStorage>(storage: &S) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_state<S: Storage>(storage: &mut S, state: &State) -> StdResult<()> {
+    singleton(storage, KEY_STATE).save(state)
+}
+
+pub fn read_state<S: ReadonlyStorage>(storage: &S) -> StdResult<State> {
+    singleton_read(storage, KEY_STATE).load()
+}
+
+pub fn store_strategy<S: Storage>(storage: &mut S, strategy: &[Allocation]) -> StdResult<()> {
+    singleton(storage, KEY_STRATEGY).save(&strategy.to_vec())
+}
+
+pub fn read_strategy<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<Allocation>> {
+    singleton_read(storage, KEY_STRATEGY).load()
+}
+
+pub fn store_shares<S: Storage>(
+    storage: &mut S,
+    address: &CanonicalAddr,
+    shares: Uint256,
+) -> StdResult<()> {
+    let mut shares_bucket: Bucket<S, Uint256> = Bucket::new(PREFIX_SHARES, storage);
+    if shares.is_zero() {
+        shares_bucket.remove(address.as_slice());
+        Ok(())
+    } else {
+        shares_bucket.save(address.as_slice(), &shares)
+    }
+}
+
+pub fn read_shares<S: ReadonlyStorage>(storage: &S, address: &CanonicalAddr) -> StdResult<Uint256> {
+    let shares_bucket: ReadonlyBucket<S, Uint256> = ReadonlyBucket::new(PREFIX_SHARES, storage);
+    Ok(shares_bucket
+        .may_load(address.as_slice())?
+        .unwrap_or_else(Uint256::zero))
+}
+
+/// Remember every collateral the vault ever bid on,
+/// so received collaterals stay part of the vault value
+pub fn store_collateral<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<()> {
+    let mut collateral_bucket: Bucket<S, bool> = Bucket::new(PREFIX_COLLATERAL, storage);
+    collateral_bucket.save(collateral_token.as_slice(), &true)
+}
+
+pub fn read_collaterals<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<CanonicalAddr>> {
+    let collateral_bucket: ReadonlyBucket<S, bool> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL, storage);
+    collateral_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
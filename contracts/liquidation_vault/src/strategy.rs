@@ -0,0 +1,215 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, QueryRequest, StdResult, Storage, WasmMsg, WasmQuery,
+};
+
+use crate::state::{read_collaterals, read_config, read_strategy, Allocation, Config};
+
+use moneymarket::decimals::normalize_amount;
+use moneymarket::liquidation::{
+    BidsResponse, CollateralInfoResponse, HandleMsg as LiquidationHandleMsg,
+    QueryMsg as LiquidationQueryMsg,
+};
+use moneymarket::oracle::PriceResponse;
+use moneymarket::querier::{deduct_tax, query_balance, query_price, query_token_balance};
+use moneymarket::rounding::{self, Rounding};
+
+/// Page size used to load the vault bids
+const BIDS_PAGE_LIMIT: u32 = 30;
+
+/// Everything the vault shares are backed by
+pub struct Holdings {
+    pub stable_balance: Uint256,
+    /// Active bids as (collateral_token, amount)
+    pub bids: Vec<(HumanAddr, Uint256)>,
+    /// Held collaterals as (collateral_token, amount)
+    pub collaterals: Vec<(HumanAddr, Uint256)>,
+}
+
+impl Holdings {
+    pub fn bid_amount(&self) -> Uint256 {
+        self.bids
+            .iter()
+            .fold(Uint256::zero(), |total, bid| total + bid.1)
+    }
+}
+
+pub fn load_holdings<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    contract_addr: &HumanAddr,
+) -> StdResult<Holdings> {
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+
+    let stable_balance = query_balance(deps, contract_addr, config.stable_denom)?;
+
+    let mut bids: Vec<(HumanAddr, Uint256)> = vec![];
+    let mut start_after: Option<HumanAddr> = None;
+    loop {
+        let res: BidsResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: liquidation_contract.clone(),
+            msg: to_binary(&LiquidationQueryMsg::BidsByUser {
+                bidder: contract_addr.clone(),
+                start_after,
+                limit: Some(BIDS_PAGE_LIMIT),
+            })?,
+        }))?;
+
+        let page_len = res.bids.len();
+        start_after = res.bids.last().map(|bid| bid.collateral_token.clone());
+        bids.extend(
+            res.bids
+                .into_iter()
+                .map(|bid| (bid.collateral_token, bid.amount)),
+        );
+        if page_len < BIDS_PAGE_LIMIT as usize {
+            break;
+        }
+    }
+
+    let mut collaterals: Vec<(HumanAddr, Uint256)> = vec![];
+    for collateral_token in read_collaterals(&deps.storage)? {
+        let collateral_token = deps.api.human_address(&collateral_token)?;
+        let amount = query_token_balance(deps, &collateral_token, contract_addr)?;
+        if !amount.is_zero() {
+            collaterals.push((collateral_token, amount));
+        }
+    }
+
+    Ok(Holdings {
+        stable_balance,
+        bids,
+        collaterals,
+    })
+}
+
+/// Value of the held collaterals in stable denom, at oracle prices
+pub fn compute_collateral_values<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    holdings: &Holdings,
+) -> StdResult<Vec<Uint256>> {
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+
+    holdings
+        .collaterals
+        .iter()
+        .map(|(collateral_token, amount)| {
+            let collateral_info: CollateralInfoResponse =
+                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: liquidation_contract.clone(),
+                    msg: to_binary(&LiquidationQueryMsg::CollateralInfo {
+                        collateral_token: collateral_token.clone(),
+                    })?,
+                }))?;
+            let price: PriceResponse = query_price(
+                deps,
+                &oracle_contract,
+                collateral_token.to_string(),
+                config.stable_denom.clone(),
+                None,
+            )?;
+
+            Ok(rounding::mul(
+                normalize_amount(*amount, collateral_info.decimals, Rounding::Down),
+                price.rate,
+                Rounding::Down,
+            ))
+        })
+        .collect()
+}
+
+/// Total value backing the vault shares
+pub fn compute_total_value<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    holdings: &Holdings,
+) -> StdResult<Uint256> {
+    Ok(compute_collateral_values(deps, holdings)?.into_iter().fold(
+        holdings.stable_balance + holdings.bid_amount(),
+        |total, value| total + value,
+    ))
+}
+
+/// Retract every bid of the vault and bid the
+/// whole stable funds again following the strategy
+pub fn rebalance<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let strategy: Vec<Allocation> = read_strategy(&deps.storage)?;
+    let holdings = load_holdings(deps, &env.contract.address)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut available = holdings.stable_balance;
+    for (collateral_token, amount) in holdings.bids.into_iter() {
+        // retracted bids are sent back after tax
+        available += retracted_amount(deps, &config, amount)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract.clone(),
+            send: vec![],
+            msg: to_binary(&LiquidationHandleMsg::RetractBid {
+                collateral_token,
+                amount: None,
+            })?,
+        }));
+    }
+
+    let mut bid_amount = Uint256::zero();
+    for allocation in strategy.iter() {
+        let amount = rounding::mul(available, allocation.weight, Rounding::Down);
+        if amount.is_zero() {
+            continue;
+        }
+
+        let coin = deduct_tax(
+            deps,
+            Coin {
+                denom: config.stable_denom.clone(),
+                amount: amount.into(),
+            },
+        )?;
+        bid_amount += Uint256::from(coin.amount);
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: liquidation_contract.clone(),
+            send: vec![coin],
+            msg: to_binary(&LiquidationHandleMsg::SubmitBid {
+                collateral_token: deps.api.human_address(&allocation.collateral_token)?,
+                premium_rate: allocation.premium_rate,
+                expires_at: None,
+                referrer: None,
+                bid_for: None,
+                unlock_receiver: None,
+            })?,
+        }));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "rebalance"),
+            log("available_amount", available),
+            log("bid_amount", bid_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Stable the vault receives back when retracting `amount` of a bid
+pub fn retracted_amount<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    amount: Uint256,
+) -> StdResult<Uint256> {
+    let coin = deduct_tax(
+        deps,
+        Coin {
+            denom: config.stable_denom.clone(),
+            amount: amount.into(),
+        },
+    )?;
+    Ok(coin.amount.into())
+}
@@ -0,0 +1,221 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Api, CanonicalAddr, Coin, Decimal, Extern, HumanAddr,
+    Querier, QuerierResult, QueryRequest, SystemError, Uint128, WasmQuery,
+};
+use cosmwasm_storage::to_length_prefixed;
+use std::collections::HashMap;
+
+use moneymarket::liquidation::{
+    BidResponse, BidsResponse, CollateralInfoResponse, LIQUIDATION_SCHEMA_VERSION,
+};
+use moneymarket::oracle::{PriceResponse, ORACLE_SCHEMA_VERSION};
+use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Query oracle price to oracle contract
+    Price { base: String, quote: String },
+    /// Query bids to liquidation contract
+    BidsByUser { bidder: HumanAddr },
+    /// Query collateral decimals to liquidation contract
+    CollateralInfo { collateral_token: HumanAddr },
+}
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    canonical_length: usize,
+    contract_balance: &[Coin],
+) -> Extern<MockStorage, MockApi, WasmMockQuerier> {
+    let contract_addr = HumanAddr::from(MOCK_CONTRACT_ADDR);
+    let custom_querier: WasmMockQuerier = WasmMockQuerier::new(
+        MockQuerier::new(&[(&contract_addr, contract_balance)]),
+        canonical_length,
+    );
+
+    Extern {
+        storage: MockStorage::default(),
+        api: MockApi::new(canonical_length),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<TerraQueryWrapper>,
+    tax_querier: TaxQuerier,
+    token_balances: HashMap<HumanAddr, HashMap<HumanAddr, Uint128>>,
+    oracle_prices: HashMap<(String, String), Decimal256>,
+    bids: Vec<(HumanAddr, Uint256)>,
+    canonical_length: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct TaxQuerier {
+    rate: Decimal,
+    // this lets us iterate over all pairs that match the first string
+    caps: HashMap<String, Uint128>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+        match &request {
+            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
+                if &TerraRoute::Treasury == route {
+                    match query_data {
+                        TerraQuery::TaxRate {} => {
+                            let res = TaxRateResponse {
+                                rate: self.tax_querier.rate,
+                            };
+                            Ok(to_binary(&res))
+                        }
+                        TerraQuery::TaxCap { denom } => {
+                            let cap = self
+                                .tax_querier
+                                .caps
+                                .get(denom)
+                                .copied()
+                                .unwrap_or_default();
+                            let res = TaxCapResponse { cap };
+                            Ok(to_binary(&res))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
+                } else {
+                    panic!("DO NOT ENTER HERE")
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _,
+                msg,
+            }) => match from_binary(msg).unwrap() {
+                QueryMsg::Price { base, quote } => match self.oracle_prices.get(&(base, quote)) {
+                    Some(v) => Ok(to_binary(&PriceResponse {
+                        schema_version: ORACLE_SCHEMA_VERSION,
+                        rate: *v,
+                        last_updated_base: 0u64,
+                        last_updated_quote: 0u64,
+                    })),
+                    None => Err(SystemError::InvalidRequest {
+                        error: "No oracle price exists".to_string(),
+                        request: msg.as_slice().into(),
+                    }),
+                },
+                QueryMsg::BidsByUser { bidder } => Ok(to_binary(&BidsResponse {
+                    schema_version: LIQUIDATION_SCHEMA_VERSION,
+                    bids: self
+                        .bids
+                        .iter()
+                        .map(|bid| BidResponse {
+                            schema_version: LIQUIDATION_SCHEMA_VERSION,
+                            collateral_token: bid.0.clone(),
+                            bidder: bidder.clone(),
+                            amount: bid.1,
+                            premium_rate: Decimal256::percent(2),
+                            expires_at: None,
+                            unlock_receiver: None,
+                        })
+                        .collect(),
+                    total_count: Some(self.bids.len() as u64),
+                    next_start_after: None,
+                })),
+                QueryMsg::CollateralInfo { collateral_token } => {
+                    Ok(to_binary(&CollateralInfoResponse {
+                        schema_version: LIQUIDATION_SCHEMA_VERSION,
+                        collateral_token,
+                        decimals: 6u8,
+                        min_price: None,
+                        max_price: None,
+                        price_source: None,
+                    }))
+                }
+            },
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                let key: &[u8] = key.as_slice();
+                let prefix_balance = to_length_prefixed(b"balance").to_vec();
+                if key[..prefix_balance.len()].to_vec() != prefix_balance {
+                    panic!("DO NOT ENTER HERE")
+                }
+
+                let address_raw = CanonicalAddr::from(&key[prefix_balance.len()..]);
+                let address = MockApi::new(self.canonical_length)
+                    .human_address(&address_raw)
+                    .unwrap();
+                match self
+                    .token_balances
+                    .get(contract_addr)
+                    .and_then(|balances| balances.get(&address))
+                {
+                    Some(balance) => Ok(to_binary(&to_binary(balance).unwrap())),
+                    None => Err(SystemError::InvalidRequest {
+                        error: "Balance not found".to_string(),
+                        request: key.into(),
+                    }),
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<TerraQueryWrapper>, canonical_length: usize) -> Self {
+        WasmMockQuerier {
+            base,
+            tax_querier: TaxQuerier::default(),
+            token_balances: HashMap::new(),
+            oracle_prices: HashMap::new(),
+            bids: vec![],
+            canonical_length,
+        }
+    }
+
+    pub fn with_token_balances(&mut self, balances: &[(&HumanAddr, &[(&HumanAddr, &Uint128)])]) {
+        self.token_balances = HashMap::new();
+        for (contract_addr, balances) in balances.iter() {
+            let mut contract_balances: HashMap<HumanAddr, Uint128> = HashMap::new();
+            for (addr, balance) in balances.iter() {
+                contract_balances.insert(HumanAddr::from(addr), **balance);
+            }
+
+            self.token_balances
+                .insert(HumanAddr::from(contract_addr), contract_balances);
+        }
+    }
+
+    pub fn with_oracle_prices(&mut self, prices: &[(&(String, String), &Decimal256)]) {
+        self.oracle_prices = prices
+            .iter()
+            .map(|(base_quote, price)| ((*base_quote).clone(), **price))
+            .collect();
+    }
+
+    // configure the bids of the vault in the liquidation contract
+    pub fn with_bids(&mut self, bids: &[(&HumanAddr, &Uint256)]) {
+        self.bids = bids
+            .iter()
+            .map(|(collateral_token, amount)| (HumanAddr::from(*collateral_token), **amount))
+            .collect();
+    }
+}
@@ -0,0 +1,232 @@
+use crate::contract::{handle, init, query};
+use crate::testing::mock_querier::mock_dependencies;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, to_binary, BankMsg, Coin, CosmosMsg, HumanAddr, StdError, Uint128, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
+use moneymarket::liquidation_vault::{
+    HandleMsg, InitMsg, QueryMsg, SharesResponse, StateResponse, StrategyAllocation,
+};
+use moneymarket::overseer::HandleMsg as OverseerHandleMsg;
+
+fn init_msg() -> InitMsg {
+    InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        stable_denom: "uusd".to_string(),
+        liquidation_contract: HumanAddr::from("liquidation0000"),
+        overseer_contract: HumanAddr::from("overseer0000"),
+        oracle_contract: HumanAddr::from("oracle0000"),
+    }
+}
+
+#[test]
+fn update_strategy() {
+    let mut deps = mock_dependencies(20, &[]);
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+
+    let msg = HandleMsg::UpdateStrategy {
+        allocations: vec![StrategyAllocation {
+            collateral_token: HumanAddr::from("asset0000"),
+            premium_rate: Decimal256::percent(2),
+            weight: Decimal256::percent(60),
+        }],
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let msg = HandleMsg::UpdateStrategy {
+        allocations: vec![
+            StrategyAllocation {
+                collateral_token: HumanAddr::from("asset0000"),
+                premium_rate: Decimal256::percent(2),
+                weight: Decimal256::percent(60),
+            },
+            StrategyAllocation {
+                collateral_token: HumanAddr::from("asset0001"),
+                premium_rate: Decimal256::percent(3),
+                weight: Decimal256::percent(50),
+            },
+        ],
+    };
+    match handle(&mut deps, mock_env("owner0000", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Sum of the strategy weights cannot exceed one")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn deposit_rebalance_and_withdraw() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(400000u128),
+        }],
+    );
+    let _res = init(&mut deps, mock_env("addr0000", &[]), init_msg()).unwrap();
+
+    let msg = HandleMsg::UpdateStrategy {
+        allocations: vec![StrategyAllocation {
+            collateral_token: HumanAddr::from("asset0000"),
+            premium_rate: Decimal256::percent(2),
+            weight: Decimal256::percent(60),
+        }],
+    };
+    handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+
+    // first deposit mints shares one to one
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    handle(&mut deps, env, HandleMsg::DepositStable {}).unwrap();
+    let res = query(
+        &deps,
+        QueryMsg::Shares {
+            address: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let shares_res: SharesResponse = from_binary(&res).unwrap();
+    assert_eq!(shares_res.shares, Uint256::from(1000000u64));
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("liquidation0000"),
+            send: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(240000u128),
+            }],
+            msg: to_binary(&LiquidationHandleMsg::SubmitBid {
+                collateral_token: HumanAddr::from("asset0000"),
+                premium_rate: Decimal256::percent(2),
+                expires_at: None,
+                referrer: None,
+                bid_for: None,
+                unlock_receiver: None,
+            })
+            .unwrap(),
+        })]
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::Liquidate {
+            borrower: HumanAddr::from("borrower0000"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("overseer0000"),
+            send: vec![],
+            msg: to_binary(&OverseerHandleMsg::LiquidateCollateral {
+                borrower: HumanAddr::from("borrower0000"),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // part of the bid was filled with collaterals
+    deps.querier
+        .with_bids(&[(&HumanAddr::from("asset0000"), &Uint256::from(300000u64))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("asset0000"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(1000000u128),
+        )],
+    )]);
+    deps.querier.with_oracle_prices(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &Decimal256::percent(30),
+    )]);
+
+    let res = query(&deps, QueryMsg::State {}).unwrap();
+    let state_res: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state_res,
+        StateResponse {
+            total_shares: Uint256::from(1000000u64),
+            stable_balance: Uint256::from(400000u64),
+            bid_amount: Uint256::from(300000u64),
+            collaterals: vec![(
+                HumanAddr::from("asset0000"),
+                Uint256::from(1000000u64),
+                Uint256::from(300000u64),
+            )],
+            total_value: Uint256::from(1000000u64),
+        }
+    );
+
+    let msg = HandleMsg::Withdraw {
+        shares: Uint256::from(1000001u64),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Invalid withdraw amount; available shares: 1000000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // half of the shares take half of every holding
+    let msg = HandleMsg::Withdraw {
+        shares: Uint256::from(500000u64),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation0000"),
+                send: vec![],
+                msg: to_binary(&LiquidationHandleMsg::RetractBid {
+                    collateral_token: HumanAddr::from("asset0000"),
+                    amount: Some(Uint256::from(150000u64)),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("asset0000"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Transfer {
+                    recipient: HumanAddr::from("addr0000"),
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(350000u128),
+                }],
+            }),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::State {}).unwrap();
+    let state_res: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_res.total_shares, Uint256::from(500000u64));
+}
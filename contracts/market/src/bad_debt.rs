@@ -0,0 +1,149 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::borrow::{
+    apply_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+};
+use crate::deposit::compute_exchange_rate_raw;
+use crate::querier::query_collaterals;
+use crate::state::{
+    read_accrual_freeze, read_bad_debt_records, read_borrower_info, read_config, read_state,
+    store_accrual_freeze, store_bad_debt_record, store_borrower_checkpoint, store_borrower_info,
+    store_state, AccrualFreeze, BadDebtRecord, BorrowerCheckpoint, BorrowerInfo, Config, State,
+};
+
+use moneymarket::market::{BadDebtHistoryResponse, BadDebtRecordResponse};
+use moneymarket::querier::{query_balance, query_supply};
+use moneymarket::rounding::{self, Rounding};
+
+/// Executor: overseer
+/// Drop the loan a liquidation could not repay. The reserves cover
+/// what they can and the rest lowers the aterra exchange rate
+pub fn write_off_bad_debt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    // the loan is only bad once nothing is left to liquidate
+    let overseer = deps.api.human_address(&config.overseer_contract)?;
+    let collaterals = query_collaterals(deps, &overseer, &borrower)?.collaterals;
+    if collaterals.iter().any(|c| !c.1.is_zero()) {
+        return Err(StdError::generic_err(
+            "Borrower still has collaterals to liquidate",
+        ));
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let mut freeze: Option<AccrualFreeze> = read_accrual_freeze(&deps.storage, &borrower_raw)?;
+
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
+    apply_borrower_interest(&mut state, &mut liability, freeze.as_mut());
+
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    // the overseer sends the write-off after every liquidation
+    // taking the last collaterals, also when they repaid the loan
+    let amount = liability.loan_amount;
+    if amount.is_zero() {
+        return Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "write_off_bad_debt"),
+                log("borrower", borrower),
+                log("amount", amount),
+            ],
+            data: None,
+        });
+    }
+
+    liability.loan_amount = Uint256::zero();
+    state.total_liabilities = if Decimal256::from_uint256(amount) < state.total_liabilities {
+        state.total_liabilities - Decimal256::from_uint256(amount)
+    } else {
+        Decimal256::zero()
+    };
+
+    let total_reserves = rounding::to_uint(state.total_reserves, Rounding::Down);
+    let covered_by_reserves = if amount < total_reserves {
+        amount
+    } else {
+        total_reserves
+    };
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(covered_by_reserves);
+
+    // the next epoch measures the deposit rate from the lowered rate
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom)?;
+    state.prev_aterra_supply = aterra_supply;
+    state.prev_exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance)?;
+
+    if let Some(freeze) = freeze {
+        store_accrual_freeze(&mut deps.storage, &borrower_raw, &freeze)?;
+    }
+    store_state(&mut deps.storage, &state)?;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_borrower_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &BorrowerCheckpoint {
+            loan_amount: liability.loan_amount,
+            interest_index: liability.interest_index,
+            global_interest_index: state.global_interest_index,
+        },
+    )?;
+    let idx = store_bad_debt_record(
+        &mut deps.storage,
+        &BadDebtRecord {
+            borrower: borrower_raw,
+            amount,
+            covered_by_reserves,
+            block_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "write_off_bad_debt"),
+            log("record_idx", idx),
+            log("borrower", borrower),
+            log("amount", amount),
+            log("covered_by_reserves", covered_by_reserves),
+            log("exchange_rate", state.prev_exchange_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_bad_debt_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BadDebtHistoryResponse> {
+    let records = read_bad_debt_records(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(idx, record)| {
+            Ok(BadDebtRecordResponse {
+                idx,
+                borrower: deps.api.human_address(&record.borrower)?,
+                amount: record.amount,
+                covered_by_reserves: record.covered_by_reserves,
+                block_height: record.block_height,
+            })
+        })
+        .collect::<StdResult<Vec<BadDebtRecordResponse>>>()?;
+
+    Ok(BadDebtHistoryResponse { records })
+}
@@ -1,37 +1,62 @@
 use anchor_token::distributor::HandleMsg as FaucetHandleMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage, WasmMsg,
 };
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
+use moneymarket::market::{
+    BorrowerInfoProjectedResponse, BorrowerInfoResponse, BorrowerInfosResponse,
+    BorrowerSnapshotResponse,
+};
 use moneymarket::overseer::BorrowLimitResponse;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::{query_balance, query_supply};
+use moneymarket::rounding::{self, Rounding};
 
 use crate::deposit::compute_exchange_rate_raw;
-use crate::querier::{query_borrow_limit, query_borrow_rate, query_target_deposit_rate};
+use crate::flash::assert_no_flash_loan;
+use crate::querier::{
+    query_borrow_limit, query_borrow_rate, query_debt_ceiling, query_target_deposit_rate,
+};
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_accrual_freeze, read_borrower_checkpoint, read_borrower_info, read_borrower_infos,
+    read_config, read_pause_info, read_state, store_accrual_freeze, store_borrower_checkpoint,
+    store_borrower_info, store_state, AccrualFreeze, BorrowerCheckpoint, BorrowerInfo, Config,
+    State,
 };
 
+/// Half lives after which the previous utilization average is dropped
+const MAX_UTILIZATION_HALVINGS: u64 = 64;
+
 pub fn borrow_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     borrow_amount: Uint256,
     to: Option<HumanAddr>,
 ) -> HandleResult {
+    if read_pause_info(&deps.storage)?.borrows {
+        return Err(StdError::generic_err("Borrows are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
     let config: Config = read_config(&deps.storage)?;
 
     let mut state: State = read_state(&deps.storage)?;
 
     let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if read_accrual_freeze(&deps.storage, &borrower_raw)?.is_some() {
+        return Err(StdError::generic_err(
+            "Interest accrual is frozen for the borrower",
+        ));
+    }
+
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
     compute_borrower_interest(&state, &mut liability);
 
     // Compute ANC reward
@@ -49,11 +74,19 @@ pub fn borrow_stable<S: Storage, A: Api, Q: Querier>(
         )));
     }
 
-    let current_balance = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    if let Some(debt_ceiling) = query_debt_ceiling(deps, &overseer)? {
+        if Decimal256::from_uint256(debt_ceiling)
+            < state.total_liabilities + Decimal256::from_uint256(borrow_amount)
+        {
+            return Err(StdError::generic_err(format!(
+                "Borrow amount too high; Total liabilities become greater than debt ceiling: {}",
+                debt_ceiling,
+            )));
+        }
+    }
+
+    let current_balance =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
 
     // Assert borrow amount
     assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
@@ -62,19 +95,25 @@ pub fn borrow_stable<S: Storage, A: Api, Q: Querier>(
     state.total_liabilities += Decimal256::from_uint256(borrow_amount);
     store_state(&mut deps.storage, &state)?;
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_borrower_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &BorrowerCheckpoint {
+            loan_amount: liability.loan_amount,
+            interest_index: liability.interest_index,
+            global_interest_index: state.global_interest_index,
+        },
+    )?;
 
     Ok(HandleResponse {
-        messages: vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: to.unwrap_or_else(|| borrower.clone()),
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom,
-                    amount: borrow_amount.into(),
-                },
-            )?],
-        })],
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            to.unwrap_or_else(|| borrower.clone()),
+            config.stable_denom,
+            borrow_amount,
+        )?],
         log: vec![
             log("action", "borrow_stable"),
             log("borrower", borrower),
@@ -95,11 +134,10 @@ pub fn repay_stable_from_liquidation<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    let cur_balance: Uint256 = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    assert_no_flash_loan(&deps.storage)?;
+
+    let cur_balance: Uint256 =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
 
     // override env
     let mut env = env;
@@ -110,13 +148,20 @@ pub fn repay_stable_from_liquidation<S: Storage, A: Api, Q: Querier>(
         amount: (cur_balance - prev_balance).into(),
     }];
 
-    repay_stable(deps, env)
+    repay_stable(deps, env, None)
 }
 
+/// Repay the loan of `borrower`, or of the sender when not given;
+/// the amount exceeding the loan is returned to the sender
 pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    borrower: Option<HumanAddr>,
 ) -> HandleResult {
+    // flash borrowed funds would otherwise be counted
+    // both as the repayment and as the flash loan return
+    assert_no_flash_loan(&deps.storage)?;
+
     let config: Config = read_config(&deps.storage)?;
 
     // Check stable denom deposit
@@ -138,13 +183,16 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
 
     let mut state: State = read_state(&deps.storage)?;
 
-    let borrower = env.message.sender;
+    let payer = env.message.sender;
+    let repay_for = borrower.is_some();
+    let borrower = borrower.unwrap_or_else(|| payer.clone());
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let mut freeze: Option<AccrualFreeze> = read_accrual_freeze(&deps.storage, &borrower_raw)?;
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, Some(amount))?;
-    compute_borrower_interest(&state, &mut liability);
+    compute_interest(deps, &config, &mut state, env.block.height, Some(amount))?;
+    apply_borrower_interest(&mut state, &mut liability, freeze.as_mut());
 
     // Compute ANC reward
     compute_reward(&mut state, env.block.height);
@@ -157,17 +205,13 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
         liability.loan_amount = Uint256::zero();
 
         // Payback left repay amount to sender
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: borrower.clone(),
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom,
-                    amount: (amount - repay_amount).into(),
-                },
-            )?],
-        }));
+        messages.push(native_payout_msg(
+            deps,
+            env.contract.address,
+            payer.clone(),
+            config.stable_denom,
+            amount - repay_amount,
+        )?);
     } else {
         repay_amount = amount;
         liability.loan_amount = liability.loan_amount - repay_amount;
@@ -175,16 +219,40 @@ pub fn repay_stable<S: Storage, A: Api, Q: Querier>(
 
     state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(repay_amount);
 
+    if let Some(freeze) = freeze {
+        store_accrual_freeze(&mut deps.storage, &borrower_raw, &freeze)?;
+    }
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
     store_state(&mut deps.storage, &state)?;
+    store_borrower_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &BorrowerCheckpoint {
+            loan_amount: liability.loan_amount,
+            interest_index: liability.interest_index,
+            global_interest_index: state.global_interest_index,
+        },
+    )?;
 
-    Ok(HandleResponse {
-        messages,
-        log: vec![
+    let logs = if repay_for {
+        vec![
+            log("action", "repay_stable_for"),
+            log("payer", payer),
+            log("borrower", borrower),
+            log("repay_amount", repay_amount),
+        ]
+    } else {
+        vec![
             log("action", "repay_stable"),
             log("borrower", borrower),
             log("repay_amount", repay_amount),
-        ],
+        ]
+    };
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
         data: None,
     })
 }
@@ -200,20 +268,34 @@ pub fn claim_rewards<S: Storage, A: Api, Q: Querier>(
     let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let mut freeze: Option<AccrualFreeze> = read_accrual_freeze(&deps.storage, &borrower_raw)?;
 
     // Compute interest
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
-    compute_borrower_interest(&state, &mut liability);
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
+    apply_borrower_interest(&mut state, &mut liability, freeze.as_mut());
 
     // Compute ANC reward
     compute_reward(&mut state, env.block.height);
     compute_borrower_reward(&state, &mut liability);
 
-    let claim_amount = liability.pending_rewards * Uint256::one();
+    let claim_amount = rounding::to_uint(liability.pending_rewards, Rounding::Down);
     liability.pending_rewards = liability.pending_rewards - Decimal256::from_uint256(claim_amount);
 
+    if let Some(freeze) = freeze {
+        store_accrual_freeze(&mut deps.storage, &borrower_raw, &freeze)?;
+    }
     store_state(&mut deps.storage, &state)?;
     store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_borrower_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &BorrowerCheckpoint {
+            loan_amount: liability.loan_amount,
+            interest_index: liability.interest_index,
+            global_interest_index: state.global_interest_index,
+        },
+    )?;
 
     let messages: Vec<CosmosMsg> = if !claim_amount.is_zero() {
         vec![CosmosMsg::Wasm(WasmMsg::Execute {
@@ -251,23 +333,26 @@ pub fn compute_interest<S: Storage, A: Api, Q: Querier>(
         return Ok(());
     }
 
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
     let balance: Uint256 = query_balance(
-        &deps,
+        deps,
         &deps.api.human_address(&config.contract_addr)?,
         config.stable_denom.to_string(),
     )? - deposit_amount.unwrap_or_else(Uint256::zero);
 
+    compute_utilization_ema(config, state, block_height, balance);
+
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        &deps,
+        deps,
         &deps.api.human_address(&config.interest_model)?,
         balance,
         state.total_liabilities,
         state.total_reserves,
+        smoothed_utilization(config, state),
     )?;
 
     let target_deposit_rate: Decimal256 =
-        query_target_deposit_rate(&deps, &deps.api.human_address(&config.overseer_contract)?)?;
+        query_target_deposit_rate(deps, &deps.api.human_address(&config.overseer_contract)?)?;
 
     compute_interest_raw(
         state,
@@ -276,9 +361,7 @@ pub fn compute_interest<S: Storage, A: Api, Q: Querier>(
         aterra_supply,
         borrow_rate_res.rate,
         target_deposit_rate,
-    );
-
-    Ok(())
+    )
 }
 
 // CONTRACT: to use this function as state update purpose,
@@ -293,9 +376,9 @@ pub fn compute_interest_raw(
     aterra_supply: Uint256,
     borrow_rate: Decimal256,
     target_deposit_rate: Decimal256,
-) {
+) -> StdResult<()> {
     if state.last_interest_updated >= block_height {
-        return;
+        return Ok(());
     }
 
     let passed_blocks = Decimal256::from_uint256(block_height - state.last_interest_updated);
@@ -307,7 +390,7 @@ pub fn compute_interest_raw(
         state.global_interest_index * (Decimal256::one() + interest_factor);
     state.total_liabilities += interest_accrued;
 
-    let mut exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+    let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance)?;
     let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
     let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
 
@@ -319,14 +402,71 @@ pub fn compute_interest_raw(
 
         // excess_yield = prev_deposits * excess_deposit_rate(_per_block) * blocks
         let excess_yield = prev_deposits * passed_blocks * excess_deposit_rate;
-        
+
         state.total_reserves += excess_yield;
-        exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+        exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance)?;
     }
 
     state.prev_aterra_supply = aterra_supply;
     state.prev_exchange_rate = exchange_rate;
     state.last_interest_updated = block_height;
+
+    Ok(())
+}
+
+pub(crate) fn compute_utilization_ratio(state: &State, balance: Uint256) -> Decimal256 {
+    let total_value_in_market =
+        Decimal256::from_uint256(balance) + state.total_liabilities - state.total_reserves;
+    if total_value_in_market.is_zero() {
+        Decimal256::zero()
+    } else {
+        state.total_liabilities / total_value_in_market
+    }
+}
+
+// CONTRACT: must run before compute_interest_raw,
+// which moves state.last_interest_updated
+/// Move the utilization average toward the spot utilization;
+/// the previous average weighs half after every half life,
+/// interpolated linearly within a half life
+pub(crate) fn compute_utilization_ema(
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    balance: Uint256,
+) {
+    let half_life = config.utilization_half_life;
+    if half_life == 0 || state.last_interest_updated >= block_height {
+        return;
+    }
+
+    let passed_blocks = block_height - state.last_interest_updated;
+    let halvings = passed_blocks / half_life;
+    let weight = if halvings >= MAX_UTILIZATION_HALVINGS {
+        Decimal256::zero()
+    } else {
+        let mut weight =
+            Decimal256::one() - Decimal256::from_ratio(passed_blocks % half_life, 2 * half_life);
+        for _ in 0..halvings {
+            weight = weight * Decimal256::percent(50);
+        }
+
+        weight
+    };
+
+    let utilization_ratio = compute_utilization_ratio(state, balance);
+    state.utilization_ema =
+        state.utilization_ema * weight + utilization_ratio * (Decimal256::one() - weight);
+}
+
+/// Utilization average to report to the interest model,
+/// none while smoothing is disabled
+pub(crate) fn smoothed_utilization(config: &Config, state: &State) -> Option<Decimal256> {
+    if config.utilization_half_life == 0 {
+        None
+    } else {
+        Some(state.utilization_ema)
+    }
 }
 
 /// Compute new interest and apply to liability
@@ -336,6 +476,35 @@ pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerI
     liability.interest_index = state.global_interest_index;
 }
 
+/// Compute new interest and apply to liability, unless the borrower
+/// accrual is frozen; the interest a frozen liability skips is dropped
+/// from the total liabilities, which already accrued it
+pub(crate) fn apply_borrower_interest(
+    state: &mut State,
+    liability: &mut BorrowerInfo,
+    freeze: Option<&mut AccrualFreeze>,
+) {
+    let freeze = match freeze {
+        Some(v) => v,
+        None => {
+            compute_borrower_interest(state, liability);
+            return;
+        }
+    };
+
+    let loan_amount = Decimal256::from_uint256(liability.loan_amount);
+    let skipped_interest =
+        loan_amount * state.global_interest_index / liability.interest_index - loan_amount;
+    state.total_liabilities = if state.total_liabilities > skipped_interest {
+        state.total_liabilities - skipped_interest
+    } else {
+        Decimal256::zero()
+    };
+
+    freeze.forgiven_interest += skipped_interest;
+    liability.interest_index = state.global_interest_index;
+}
+
 /// Compute distributed reward and update global index
 pub fn compute_reward(state: &mut State, block_height: u64) {
     if state.last_reward_updated >= block_height {
@@ -366,15 +535,16 @@ pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
     borrower: HumanAddr,
     block_height: Option<u64>,
 ) -> StdResult<BorrowerInfoResponse> {
-    let mut borrower_info: BorrowerInfo =
-        read_borrower_info(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
 
     if let Some(block_height) = block_height {
         let config: Config = read_config(&deps.storage)?;
         let mut state: State = read_state(&deps.storage)?;
+        let mut freeze = read_accrual_freeze(&deps.storage, &borrower_raw)?;
 
-        compute_interest(&deps, &config, &mut state, block_height, None)?;
-        compute_borrower_interest(&state, &mut borrower_info);
+        compute_interest(deps, &config, &mut state, block_height, None)?;
+        apply_borrower_interest(&mut state, &mut borrower_info, freeze.as_mut());
 
         compute_reward(&mut state, block_height);
         compute_borrower_reward(&state, &mut borrower_info);
@@ -389,6 +559,68 @@ pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+pub fn query_borrower_info_projected<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<BorrowerInfoProjectedResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    if block_height < state.last_interest_updated {
+        return Err(StdError::generic_err(
+            "block_height must bigger than last_interest_updated",
+        ));
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+    let prev_loan_amount = borrower_info.loan_amount;
+    let mut freeze = read_accrual_freeze(&deps.storage, &borrower_raw)?;
+
+    compute_interest(deps, &config, &mut state, block_height, None)?;
+    apply_borrower_interest(&mut state, &mut borrower_info, freeze.as_mut());
+
+    compute_reward(&mut state, block_height);
+    compute_borrower_reward(&state, &mut borrower_info);
+
+    Ok(BorrowerInfoProjectedResponse {
+        borrower,
+        block_height,
+        loan_amount: borrower_info.loan_amount,
+        accrued_interest: borrower_info.loan_amount - prev_loan_amount,
+        pending_rewards: borrower_info.pending_rewards,
+    })
+}
+
+/// Borrower liability at the nearest checkpoint at or before the given height
+pub fn query_borrower_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<BorrowerSnapshotResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    Ok(
+        match read_borrower_checkpoint(&deps.storage, &borrower_raw, block_height)? {
+            Some((checkpoint_height, checkpoint)) => BorrowerSnapshotResponse {
+                borrower,
+                block_height,
+                checkpoint_height: Some(checkpoint_height),
+                loan_amount: checkpoint.loan_amount,
+                interest_index: checkpoint.interest_index,
+                global_interest_index: checkpoint.global_interest_index,
+            },
+            None => BorrowerSnapshotResponse {
+                borrower,
+                block_height,
+                checkpoint_height: None,
+                loan_amount: Uint256::zero(),
+                interest_index: Decimal256::one(),
+                global_interest_index: Decimal256::one(),
+            },
+        },
+    )
+}
+
 pub fn query_borrower_infos<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     start_after: Option<HumanAddr>,
@@ -400,7 +632,7 @@ pub fn query_borrower_infos<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(&deps, start_after, limit)?;
+    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(deps, start_after, limit)?;
     Ok(BorrowerInfosResponse { borrower_infos })
 }
 
@@ -432,5 +664,5 @@ fn assert_max_borrow_factor(
         )));
     }
 
-    return Ok(());
+    Ok(())
 }
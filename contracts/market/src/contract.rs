@@ -1,26 +1,52 @@
+use crate::bad_debt::{query_bad_debt_history, write_off_bad_debt};
 use crate::borrow::{
     borrow_stable, claim_rewards, compute_interest, compute_interest_raw, compute_reward,
-    query_borrower_info, query_borrower_infos, repay_stable, repay_stable_from_liquidation,
+    compute_utilization_ema, compute_utilization_ratio, query_borrower_info,
+    query_borrower_info_projected, query_borrower_infos, query_borrower_snapshot, repay_stable,
+    repay_stable_from_liquidation, smoothed_utilization,
 };
 use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
+use crate::flash::{assert_flash_repayment, assert_no_flash_loan, flash_borrow};
+use crate::freeze::{query_accrual_freeze, set_accrual_frozen};
 use crate::migration::{migrate_config, migrate_state};
 use crate::querier::{query_anc_emission_rate, query_borrow_rate, query_target_deposit_rate};
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::rate_history::{query_rate_history, record_rate_snapshot};
+use crate::reserves::{
+    cancel_reserve_announcement, compute_reserve_transfer, query_reserve_announcement,
+};
+use crate::state::{
+    exportable_namespaces, read_config, read_pause_info, read_state, store_config,
+    store_pause_info, store_state, Config, State,
+};
+use crate::term_deposit::{
+    deposit_stable_locked, fund_locked_interest, query_locked_liabilities, query_locked_positions,
+    withdraw_locked,
+};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    from_binary, log, to_binary, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Env, Extern,
+    from_binary, log, to_binary, Api, Binary, CanonicalAddr, CosmosMsg, Env, Extern,
     HandleResponse, HandleResult, HumanAddr, InitResponse, InitResult, MigrateResponse,
     MigrateResult, Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 use cw20::{Cw20CoinHuman, Cw20ReceiveMsg, MinterResponse};
 
+use moneymarket::denom_migration::{
+    claim_denom_migration, propose_denom_migration, query_pending_denom_migration,
+};
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{
-    ConfigResponse, Cw20HookMsg, EpochStateResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg,
-    StateResponse,
+    ConfigResponse, Cw20HookMsg, EpochStateResponse, HandleMsg, InitMsg, LockupTerm, MigrateMsg,
+    QueryMsg, StateProjectedResponse, StateResponse,
 };
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::ownership::{claim_ownership, propose_owner, query_pending_owner};
+use moneymarket::pause::PauseInfo;
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::{query_balance, query_supply};
+use moneymarket::raw_state::query_raw_state;
+use moneymarket::rounding::{self, Rounding};
+use moneymarket::sweep::{sweep_msg, SweepAsset};
+use moneymarket::tax::{read_tax_policy, store_tax_policy, TaxPolicy};
 use terraswap::hook::InitHook;
 use terraswap::token::InitMsg as TokenInitMsg;
 
@@ -36,7 +62,7 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         .iter()
         .find(|c| c.denom == msg.stable_denom)
         .map(|c| c.amount)
-        .unwrap_or_else(|| Uint128::zero());
+        .unwrap_or_else(Uint128::zero);
 
     if initial_deposit != Uint128(INITIAL_DEPOSIT_AMOUNT) {
         return Err(StdError::generic_err(format!(
@@ -57,8 +83,15 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             distribution_model: CanonicalAddr::default(),
             collector_contract: CanonicalAddr::default(),
             distributor_contract: CanonicalAddr::default(),
+            guardian: None,
             stable_denom: msg.stable_denom.clone(),
             max_borrow_factor: msg.max_borrow_factor,
+            flash_fee: Decimal256::zero(),
+            reserve_announcement_threshold: None,
+            reserve_announcement_delay: 0,
+            utilization_half_life: 0,
+            lockup_terms: vec![],
+            early_withdrawal_penalty: Decimal256::zero(),
         },
     )?;
 
@@ -74,6 +107,10 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             anc_emission_rate: msg.anc_emission_rate,
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )?;
 
@@ -129,19 +166,64 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             collector_contract,
             distributor_contract,
         ),
+        HandleMsg::ProposeOwner {
+            new_owner,
+            expires_in,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_owner(deps, env, &config.owner_addr, new_owner, expires_in)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env),
+        HandleMsg::ProposeDenomMigration {
+            new_denom,
+            conversion_contract,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_denom_migration(
+                deps,
+                env,
+                &config.owner_addr,
+                new_denom,
+                conversion_contract,
+            )
+        }
+        HandleMsg::ExecuteDenomMigration {} => execute_denom_migration(deps, env),
         HandleMsg::UpdateConfig {
-            owner_addr,
             interest_model,
             distribution_model,
             max_borrow_factor,
+            guardian,
+            flash_fee,
+            reserve_announcement_threshold,
+            reserve_announcement_delay,
+            utilization_half_life,
+            lockup_terms,
+            early_withdrawal_penalty,
         } => update_config(
             deps,
             env,
-            owner_addr,
             interest_model,
             distribution_model,
             max_borrow_factor,
+            guardian,
+            flash_fee,
+            reserve_announcement_threshold,
+            reserve_announcement_delay,
+            utilization_half_life,
+            lockup_terms,
+            early_withdrawal_penalty,
         ),
+        HandleMsg::SetPause {
+            deposits,
+            borrows,
+            liquidations,
+            withdrawals,
+        } => set_pause(deps, env, deposits, borrows, liquidations, withdrawals),
+        HandleMsg::UpdateTaxPolicy { tax_policy } => update_tax_policy(deps, env, tax_policy),
+        HandleMsg::CancelReserveAnnouncement {} => cancel_reserve_announcement(deps, env),
+        HandleMsg::SweepUnrelatedFunds { asset, recipient } => {
+            sweep_unrelated_funds(deps, env, asset, recipient)
+        }
         HandleMsg::ExecuteEpochOperations {
             deposit_rate,
             target_deposit_rate,
@@ -155,16 +237,32 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             threshold_deposit_rate,
             distributed_interest,
         ),
-        HandleMsg::DepositStable {} => deposit_stable(deps, env),
+        HandleMsg::FundLockedInterest {} => fund_locked_interest(deps, env),
+        HandleMsg::DepositStable { min_mint_amount } => {
+            deposit_stable(deps, env, None, min_mint_amount)
+        }
+        HandleMsg::DepositStableFor {
+            recipient,
+            min_mint_amount,
+        } => deposit_stable(deps, env, Some(recipient), min_mint_amount),
+        HandleMsg::DepositStableLocked { duration } => deposit_stable_locked(deps, env, duration),
+        HandleMsg::WithdrawLocked { position_id } => withdraw_locked(deps, env, position_id),
         HandleMsg::BorrowStable { borrow_amount, to } => {
             borrow_stable(deps, env, borrow_amount, to)
         }
-        HandleMsg::RepayStable {} => repay_stable(deps, env),
+        HandleMsg::RepayStable {} => repay_stable(deps, env, None),
+        HandleMsg::RepayStableFor { borrower } => repay_stable(deps, env, Some(borrower)),
         HandleMsg::RepayStableFromLiquidation {
             borrower,
             prev_balance,
         } => repay_stable_from_liquidation(deps, env, borrower, prev_balance),
+        HandleMsg::SetAccrualFrozen { borrower, frozen } => {
+            set_accrual_frozen(deps, env, borrower, frozen)
+        }
+        HandleMsg::WriteOffBadDebt { borrower } => write_off_bad_debt(deps, env, borrower),
         HandleMsg::ClaimRewards { to } => claim_rewards(deps, env, to),
+        HandleMsg::FlashBorrow { amount, msg } => flash_borrow(deps, env, amount, msg),
+        HandleMsg::AssertFlashRepayment {} => assert_flash_repayment(deps, env),
     }
 }
 
@@ -176,14 +274,20 @@ pub fn receive_cw20<S: Storage, A: Api, Q: Querier>(
     let contract_addr = env.message.sender.clone();
     if let Some(msg) = cw20_msg.msg {
         match from_binary(&msg)? {
-            Cw20HookMsg::RedeemStable {} => {
+            Cw20HookMsg::RedeemStable { min_redeem_amount } => {
                 // only asset contract can execute this message
                 let config: Config = read_config(&deps.storage)?;
                 if deps.api.canonical_address(&contract_addr)? != config.aterra_contract {
                     return Err(StdError::unauthorized());
                 }
 
-                redeem_stable(deps, env, cw20_msg.sender, cw20_msg.amount)
+                redeem_stable(
+                    deps,
+                    env,
+                    cw20_msg.sender,
+                    cw20_msg.amount,
+                    min_redeem_amount,
+                )
             }
         }
     } else {
@@ -240,13 +344,20 @@ pub fn register_contracts<S: Storage, A: Api, Q: Querier>(
     Ok(HandleResponse::default())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner_addr: Option<HumanAddr>,
     interest_model: Option<HumanAddr>,
     distribution_model: Option<HumanAddr>,
     max_borrow_factor: Option<Decimal256>,
+    guardian: Option<HumanAddr>,
+    flash_fee: Option<Decimal256>,
+    reserve_announcement_threshold: Option<Uint256>,
+    reserve_announcement_delay: Option<u64>,
+    utilization_half_life: Option<u64>,
+    lockup_terms: Option<Vec<LockupTerm>>,
+    early_withdrawal_penalty: Option<Decimal256>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
 
@@ -255,13 +366,9 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
-    }
-
     if interest_model.is_some() {
         let mut state: State = read_state(&deps.storage)?;
-        compute_interest(&deps, &config, &mut state, env.block.height, None)?;
+        compute_interest(deps, &config, &mut state, env.block.height, None)?;
         store_state(&mut deps.storage, &state)?;
 
         if let Some(interest_model) = interest_model {
@@ -277,6 +384,65 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.max_borrow_factor = max_borrow_factor;
     }
 
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
+    if let Some(flash_fee) = flash_fee {
+        if flash_fee > Decimal256::one() {
+            return Err(StdError::generic_err("Flash fee cannot exceed one"));
+        }
+
+        config.flash_fee = flash_fee;
+    }
+
+    if let Some(reserve_announcement_threshold) = reserve_announcement_threshold {
+        config.reserve_announcement_threshold = Some(reserve_announcement_threshold);
+    }
+
+    if let Some(reserve_announcement_delay) = reserve_announcement_delay {
+        config.reserve_announcement_delay = reserve_announcement_delay;
+    }
+
+    if let Some(utilization_half_life) = utilization_half_life {
+        // Start the average from the spot utilization
+        // rather than from whatever it held when disabled
+        if config.utilization_half_life == 0 && utilization_half_life != 0 {
+            let mut state: State = read_state(&deps.storage)?;
+            compute_interest(deps, &config, &mut state, env.block.height, None)?;
+
+            let balance: Uint256 = query_balance(
+                deps,
+                &deps.api.human_address(&config.contract_addr)?,
+                config.stable_denom.to_string(),
+            )?;
+            state.utilization_ema = compute_utilization_ratio(&state, balance);
+            store_state(&mut deps.storage, &state)?;
+        }
+
+        config.utilization_half_life = utilization_half_life;
+    }
+
+    if let Some(lockup_terms) = lockup_terms {
+        if lockup_terms.iter().any(|t| t.duration == 0) {
+            return Err(StdError::generic_err(
+                "Lockup duration must be greater than 0",
+            ));
+        }
+
+        config.lockup_terms = lockup_terms;
+    }
+
+    if let Some(early_withdrawal_penalty) = early_withdrawal_penalty {
+        if early_withdrawal_penalty > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Early withdrawal penalty cannot exceed one",
+            ));
+        }
+
+        config.early_withdrawal_penalty = early_withdrawal_penalty;
+    }
+
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse {
         messages: vec![],
@@ -285,6 +451,103 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+pub fn accept_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    config.owner_addr = claim_ownership(deps, &env)?;
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_ownership"),
+            log("owner", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn execute_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    // the conversion tax lowers the balance, and so
+    // the aterra exchange rate, like any other loss
+    let (migration, messages, conversion_tax) =
+        claim_denom_migration(deps, &env, &config.owner_addr, &config.stable_denom)?;
+
+    let old_denom = std::mem::replace(&mut config.stable_denom, migration.new_denom);
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "execute_denom_migration"),
+            log("old_denom", old_denom),
+            log("new_denom", config.stable_denom),
+            log("conversion_tax", conversion_tax),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits: Option<bool>,
+    borrows: Option<bool>,
+    liquidations: Option<bool>,
+    withdrawals: Option<bool>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only pause
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner_addr;
+    if !is_owner && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(&deps.storage)?;
+    pause_info.update(deposits, borrows, liquidations, withdrawals, is_owner)?;
+    store_pause_info(&mut deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposits", pause_info.deposits),
+            log("borrows", pause_info.borrows),
+            log("liquidations", pause_info.liquidations),
+            log("withdrawals", pause_info.withdrawals),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: owner
+pub fn update_tax_policy<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    tax_policy: TaxPolicy,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    store_tax_policy(&mut deps.storage, &tax_policy)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "update_tax_policy")],
+        data: None,
+    })
+}
+
 pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -298,22 +561,27 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
+    assert_no_flash_loan(&deps.storage)?;
+
     let mut state: State = read_state(&deps.storage)?;
 
     // Compute interest and reward before updating anc_emission_rate
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
     let balance: Uint256 = query_balance(
-        &deps,
+        deps,
         &deps.api.human_address(&config.contract_addr)?,
         config.stable_denom.to_string(),
     )? - distributed_interest;
 
+    compute_utilization_ema(&config, &mut state, env.block.height, balance);
+
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        &deps,
+        deps,
         &deps.api.human_address(&config.interest_model)?,
         balance,
         state.total_liabilities,
         state.total_reserves,
+        smoothed_utilization(&config, &state),
     )?;
 
     compute_interest_raw(
@@ -323,50 +591,61 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
         aterra_supply,
         borrow_rate_res.rate,
         target_deposit_rate,
-    );
+    )?;
 
     // recompute prev_exchange_rate with distributed_interest
     state.prev_exchange_rate =
-        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
+        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest)?;
 
     compute_reward(&mut state, env.block.height);
 
+    record_rate_snapshot(
+        &mut deps.storage,
+        &state,
+        env.block.height,
+        balance,
+        borrow_rate_res.rate,
+        deposit_rate,
+    )?;
+
     // Compute total_reserves to fund collector contract
     // Update total_reserves and send it to collector contract
-    // only when there is enough balance
-    let total_reserves = state.total_reserves * Uint256::one();
-    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() && balance > total_reserves {
+    // only when there is enough balance; large transfers
+    // wait for their announcement delay
+    let total_reserves = if balance > rounding::to_uint(state.total_reserves, Rounding::Down) {
+        compute_reserve_transfer(&mut deps.storage, &config, &state, env.block.height)?
+    } else {
+        Uint256::zero()
+    };
+    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() {
         state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
 
-        vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: deps.api.human_address(&config.collector_contract)?,
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: config.stable_denom,
-                    amount: total_reserves.into(),
-                },
-            )?],
-        })]
+        vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            deps.api.human_address(&config.collector_contract)?,
+            config.stable_denom,
+            total_reserves,
+        )?]
     } else {
         vec![]
     };
 
     // Query updated anc_emission_rate
     state.anc_emission_rate = query_anc_emission_rate(
-        &deps,
+        deps,
         &deps.api.human_address(&config.distribution_model)?,
         deposit_rate,
         target_deposit_rate,
         threshold_deposit_rate,
         state.anc_emission_rate,
+        env.block.height,
     )?
     .emission_rate;
 
     store_state(&mut deps.storage, &state)?;
 
-    return Ok(HandleResponse {
+    Ok(HandleResponse {
         messages,
         log: vec![
             log("action", "execute_epoch_operations"),
@@ -374,7 +653,43 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
             log("anc_emission_rate", state.anc_emission_rate),
         ],
         data: None,
-    });
+    })
+}
+
+pub fn sweep_unrelated_funds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: SweepAsset,
+    recipient: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    // the stable balance backs deposits, loans and reserves
+    let is_tracked = match &asset {
+        SweepAsset::Native { denom } => *denom == config.stable_denom,
+        SweepAsset::Token { .. } => false,
+    };
+    if is_tracked {
+        return Err(StdError::generic_err(format!(
+            "Cannot sweep tracked funds: {}",
+            asset
+        )));
+    }
+
+    let (amount, msg) = sweep_msg(deps, &env, &asset, recipient.clone())?;
+    Ok(HandleResponse {
+        messages: vec![msg],
+        log: vec![
+            log("action", "sweep_unrelated_funds"),
+            log("asset", asset),
+            log("amount", amount),
+            log("recipient", recipient),
+        ],
+        data: None,
+    })
 }
 
 pub fn query<S: Storage, A: Api, Q: Querier>(
@@ -383,6 +698,8 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::PendingDenomMigration {} => to_binary(&query_pending_denom_migration(deps)?),
         QueryMsg::State { block_height } => to_binary(&query_state(deps, block_height)?),
         QueryMsg::EpochState {
             block_height,
@@ -396,9 +713,51 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             borrower,
             block_height,
         } => to_binary(&query_borrower_info(deps, borrower, block_height)?),
+        QueryMsg::BorrowerSnapshot {
+            borrower,
+            block_height,
+        } => to_binary(&query_borrower_snapshot(deps, borrower, block_height)?),
         QueryMsg::BorrowerInfos { start_after, limit } => {
             to_binary(&query_borrower_infos(deps, start_after, limit)?)
         }
+        QueryMsg::StateProjected { block_height } => {
+            to_binary(&query_state_projected(deps, block_height)?)
+        }
+        QueryMsg::BorrowerInfoProjected {
+            borrower,
+            block_height,
+        } => to_binary(&query_borrower_info_projected(
+            deps,
+            borrower,
+            block_height,
+        )?),
+        QueryMsg::PauseInfo {} => to_binary(&read_pause_info(&deps.storage)?),
+        QueryMsg::TaxPolicy {} => to_binary(&read_tax_policy(&deps.storage)?),
+        QueryMsg::ReserveAnnouncement {} => to_binary(&query_reserve_announcement(deps)?),
+        QueryMsg::AccrualFreeze { borrower } => to_binary(&query_accrual_freeze(deps, borrower)?),
+        QueryMsg::BadDebtHistory { start_after, limit } => {
+            to_binary(&query_bad_debt_history(deps, start_after, limit)?)
+        }
+        QueryMsg::RateHistory { start_after, limit } => {
+            to_binary(&query_rate_history(&deps.storage, start_after, limit)?)
+        }
+        QueryMsg::LockedPositions {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_locked_positions(deps, owner, start_after, limit)?),
+        QueryMsg::LockedLiabilities {} => to_binary(&query_locked_liabilities(deps)?),
+        QueryMsg::RawState {
+            namespace,
+            start_after,
+            limit,
+        } => to_binary(&query_raw_state(
+            &deps.storage,
+            &exportable_namespaces(),
+            namespace,
+            start_after,
+            limit,
+        )?),
     }
 }
 
@@ -416,6 +775,16 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
         distributor_contract: deps.api.human_address(&config.distributor_contract)?,
         stable_denom: config.stable_denom,
         max_borrow_factor: config.max_borrow_factor,
+        guardian: match config.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?),
+            None => None,
+        },
+        flash_fee: config.flash_fee,
+        reserve_announcement_threshold: config.reserve_announcement_threshold,
+        reserve_announcement_delay: config.reserve_announcement_delay,
+        utilization_half_life: config.utilization_half_life,
+        lockup_terms: config.lockup_terms,
+        early_withdrawal_penalty: config.early_withdrawal_penalty,
     })
 }
 
@@ -441,7 +810,7 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
         let config: Config = read_config(&deps.storage)?;
 
         // Compute interest rate with given block height
-        compute_interest(&deps, &config, &mut state, block_height, None)?;
+        compute_interest(deps, &config, &mut state, block_height, None)?;
 
         // Compute reward rate with given block height
         compute_reward(&mut state, block_height);
@@ -457,6 +826,39 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
         anc_emission_rate: state.anc_emission_rate,
         prev_aterra_supply: state.prev_aterra_supply,
         prev_exchange_rate: state.prev_exchange_rate,
+        utilization_ema: state.utilization_ema,
+    })
+}
+
+pub fn query_state_projected<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<StateProjectedResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let mut state: State = read_state(&deps.storage)?;
+    if block_height < state.last_interest_updated {
+        return Err(StdError::generic_err(
+            "block_height must bigger than last_interest_updated",
+        ));
+    }
+
+    compute_interest(deps, &config, &mut state, block_height, None)?;
+    compute_reward(&mut state, block_height);
+
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom,
+    )?;
+
+    Ok(StateProjectedResponse {
+        block_height,
+        total_liabilities: state.total_liabilities,
+        total_reserves: state.total_reserves,
+        global_interest_index: state.global_interest_index,
+        global_reward_index: state.global_reward_index,
+        exchange_rate: compute_exchange_rate_raw(&state, aterra_supply, balance)?,
     })
 }
 
@@ -469,9 +871,9 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
     let mut state: State = read_state(&deps.storage)?;
 
     let distributed_interest = distributed_interest.unwrap_or(Uint256::zero());
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
     let balance = query_balance(
-        &deps,
+        deps,
         &deps.api.human_address(&config.contract_addr)?,
         config.stable_denom.to_string(),
     )? - distributed_interest;
@@ -483,16 +885,19 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
             ));
         }
 
+        compute_utilization_ema(&config, &mut state, block_height, balance);
+
         let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-            &deps,
+            deps,
             &deps.api.human_address(&config.interest_model)?,
             balance,
             state.total_liabilities,
             state.total_reserves,
+            smoothed_utilization(&config, &state),
         )?;
 
         let target_deposit_rate: Decimal256 =
-            query_target_deposit_rate(&deps, &deps.api.human_address(&config.overseer_contract)?)?;
+            query_target_deposit_rate(deps, &deps.api.human_address(&config.overseer_contract)?)?;
 
         // Compute interest rate to return latest epoch state
         compute_interest_raw(
@@ -502,18 +907,19 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
             aterra_supply,
             borrow_rate_res.rate,
             target_deposit_rate,
-        );
+        )?;
 
         // compute_interest_raw store current exchange rate
         // as prev_exchange_rate, so just return prev_exchange_rate
-        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest)
+        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest)?
     } else {
-        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest)
+        compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest)?
     };
 
     Ok(EpochStateResponse {
         exchange_rate,
         aterra_supply,
+        unfunded_locked_interest: state.total_locked_interest - state.locked_interest_funded,
     })
 }
 
@@ -530,8 +936,8 @@ pub fn migrate<S: Storage, A: Api, Q: Querier>(
     )?;
 
     let config: Config = read_config(&deps.storage)?;
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(&deps, &env.contract.address, config.stable_denom)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom)?;
 
     // migrate state to use new State
     migrate_state(&mut deps.storage, aterra_supply, balance)?;
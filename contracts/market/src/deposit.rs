@@ -1,19 +1,32 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
-    HumanAddr, Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
+    log, to_binary, Api, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    Querier, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 
 use crate::borrow::{compute_interest, compute_reward};
-use crate::state::{read_config, read_state, store_state, Config, State};
+use crate::flash::assert_no_flash_loan;
+use crate::state::{read_config, read_pause_info, read_state, store_state, Config, State};
 
 use cw20::Cw20HandleMsg;
+use moneymarket::payout::native_payout_msg;
 use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::rounding::{self, Rounding};
 
+/// Deposit the sent stable asset, minting aterra to `recipient`
+/// or to the sender when not given
 pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    recipient: Option<HumanAddr>,
+    min_mint_amount: Option<Uint256>,
 ) -> HandleResult {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(StdError::generic_err("Deposits are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
     let config: Config = read_config(&deps.storage)?;
 
     // Check base denom deposit
@@ -36,7 +49,7 @@ pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
     // Update interest related state
     let mut state: State = read_state(&deps.storage)?;
     compute_interest(
-        &deps,
+        deps,
         &config,
         &mut state,
         env.block.height,
@@ -46,25 +59,40 @@ pub fn deposit_stable<S: Storage, A: Api, Q: Querier>(
 
     // Load anchor token exchange rate with updated state
     let exchange_rate = compute_exchange_rate(deps, &config, &state, Some(deposit_amount))?;
-    let mint_amount = deposit_amount / exchange_rate;
+    let mint_amount = rounding::div(deposit_amount, exchange_rate, Rounding::Down);
+    assert_min_output("Mint", mint_amount, min_mint_amount)?;
 
-    state.prev_aterra_supply = state.prev_aterra_supply + mint_amount;
+    state.prev_aterra_supply += mint_amount;
     store_state(&mut deps.storage, &state)?;
+
+    let depositor = env.message.sender;
+    let mint_recipient = recipient.clone().unwrap_or_else(|| depositor.clone());
+    let logs = match recipient {
+        Some(recipient) => vec![
+            log("action", "deposit_stable_for"),
+            log("depositor", depositor),
+            log("recipient", recipient),
+            log("mint_amount", mint_amount),
+            log("deposit_amount", deposit_amount),
+        ],
+        None => vec![
+            log("action", "deposit_stable"),
+            log("depositor", depositor),
+            log("mint_amount", mint_amount),
+            log("deposit_amount", deposit_amount),
+        ],
+    };
+
     Ok(HandleResponse {
         messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.human_address(&config.aterra_contract)?,
             send: vec![],
             msg: to_binary(&Cw20HandleMsg::Mint {
-                recipient: env.message.sender.clone(),
+                recipient: mint_recipient,
                 amount: mint_amount.into(),
             })?,
         })],
-        log: vec![
-            log("action", "deposit_stable"),
-            log("depositor", env.message.sender),
-            log("mint_amount", mint_amount),
-            log("deposit_amount", deposit_amount),
-        ],
+        log: logs,
         data: None,
     })
 }
@@ -74,27 +102,45 @@ pub fn redeem_stable<S: Storage, A: Api, Q: Querier>(
     env: Env,
     sender: HumanAddr,
     burn_amount: Uint128,
+    min_redeem_amount: Option<Uint256>,
 ) -> HandleResult {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(StdError::generic_err("Withdrawals are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
     let config: Config = read_config(&deps.storage)?;
 
     // Update interest related state
     let mut state: State = read_state(&deps.storage)?;
-    compute_interest(&deps, &config, &mut state, env.block.height, None)?;
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
     compute_reward(&mut state, env.block.height);
 
     // Load anchor token exchange rate with updated state
     let exchange_rate = compute_exchange_rate(deps, &config, &state, None)?;
-    let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
+    let redeem_amount = rounding::mul(Uint256::from(burn_amount), exchange_rate, Rounding::Down);
 
-    let current_balance = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    let current_balance =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
 
     // Assert redeem amount
     assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
 
+    // the redeemer receives the amount after tax
+    let redeem_coin = deduct_tax(
+        deps,
+        Coin {
+            denom: config.stable_denom.clone(),
+            amount: redeem_amount.into(),
+        },
+    )?;
+    assert_min_output(
+        "Redeem",
+        Uint256::from(redeem_coin.amount),
+        min_redeem_amount,
+    )?;
+
     state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
     store_state(&mut deps.storage, &state)?;
     Ok(HandleResponse {
@@ -106,17 +152,13 @@ pub fn redeem_stable<S: Storage, A: Api, Q: Querier>(
                     amount: burn_amount,
                 })?,
             }),
-            CosmosMsg::Bank(BankMsg::Send {
-                from_address: env.contract.address,
-                to_address: sender,
-                amount: vec![deduct_tax(
-                    &deps,
-                    Coin {
-                        denom: config.stable_denom,
-                        amount: redeem_amount.into(),
-                    },
-                )?],
-            }),
+            native_payout_msg(
+                deps,
+                env.contract.address,
+                sender,
+                config.stable_denom,
+                redeem_amount,
+            )?,
         ],
         log: vec![
             log("action", "redeem_stable"),
@@ -127,7 +169,7 @@ pub fn redeem_stable<S: Storage, A: Api, Q: Querier>(
     })
 }
 
-fn assert_redeem_amount(
+pub(crate) fn assert_redeem_amount(
     config: &Config,
     state: &State,
     current_balance: Uint256,
@@ -142,7 +184,25 @@ fn assert_redeem_amount(
         )));
     }
 
-    return Ok(());
+    Ok(())
+}
+
+/// Revert when the output moved below the bound the user quoted
+fn assert_min_output(
+    operation: &str,
+    amount: Uint256,
+    min_amount: Option<Uint256>,
+) -> StdResult<()> {
+    if let Some(min_amount) = min_amount {
+        if amount < min_amount {
+            return Err(StdError::generic_err(format!(
+                "{} amount is less than the minimum; amount: {}, min amount: {}",
+                operation, amount, min_amount
+            )));
+        }
+    }
+
+    Ok(())
 }
 
 pub(crate) fn compute_exchange_rate<S: Storage, A: Api, Q: Querier>(
@@ -151,27 +211,39 @@ pub(crate) fn compute_exchange_rate<S: Storage, A: Api, Q: Querier>(
     state: &State,
     deposit_amount: Option<Uint256>,
 ) -> StdResult<Decimal256> {
-    let aterra_supply = query_supply(&deps, &deps.api.human_address(&config.aterra_contract)?)?;
+    let aterra_supply = query_supply(deps, &deps.api.human_address(&config.aterra_contract)?)?;
     let balance = query_balance(
-        &deps,
+        deps,
         &deps.api.human_address(&config.contract_addr)?,
         config.stable_denom.to_string(),
     )? - deposit_amount.unwrap_or_else(Uint256::zero);
 
-    Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
+    compute_exchange_rate_raw(state, aterra_supply, balance)
 }
 
 pub fn compute_exchange_rate_raw(
     state: &State,
     aterra_supply: Uint256,
     contract_balance: Uint256,
-) -> Decimal256 {
+) -> StdResult<Decimal256> {
     if aterra_supply.is_zero() {
-        return Decimal256::one();
+        return Ok(Decimal256::one());
+    }
+
+    // the locked principal and the interest funded for the
+    // locked positions are owed to their owners, not to aterra
+    let locked_balance =
+        Decimal256::from_uint256(state.total_locked_principal + state.locked_interest_funded);
+
+    let total_assets = Decimal256::from_uint256(contract_balance) + state.total_liabilities;
+    if total_assets < state.total_reserves + locked_balance {
+        return Err(StdError::generic_err(
+            "Reserves and locked balance exceed the market assets",
+        ));
     }
 
     // (aterra / stable_denom)
-    // exchange_rate = (balance + total_liabilities - total_reserves) / aterra_supply
-    (Decimal256::from_uint256(contract_balance) + state.total_liabilities - state.total_reserves)
-        / Decimal256::from_uint256(aterra_supply)
+    // exchange_rate = (balance + total_liabilities - total_reserves - locked_balance) / aterra_supply
+    Ok((total_assets - state.total_reserves - locked_balance)
+        / Decimal256::from_uint256(aterra_supply))
 }
@@ -0,0 +1,139 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HandleResult, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::borrow::compute_interest;
+use crate::state::{
+    read_config, read_flash_loan, read_pause_info, read_state, remove_flash_loan, store_flash_loan,
+    store_state, Config, FlashLoan, State,
+};
+
+use moneymarket::market::HandleMsg;
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::query_balance;
+use moneymarket::rounding::{self, Rounding};
+
+/// The contract balance is lower than usual during a flash loan,
+/// so balance dependent operations must wait for the repayment
+pub fn assert_no_flash_loan<S: Storage>(storage: &S) -> StdResult<()> {
+    if read_flash_loan(storage)?.is_some() {
+        return Err(StdError::generic_err("Flash loan in progress"));
+    }
+
+    Ok(())
+}
+
+pub fn flash_borrow<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint256,
+    msg: Binary,
+) -> HandleResult {
+    if read_pause_info(&deps.storage)?.borrows {
+        return Err(StdError::generic_err("Borrows are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err(
+            "Flash borrow amount must be greater than 0",
+        ));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let prev_balance = query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
+    if amount > prev_balance {
+        return Err(StdError::generic_err(format!(
+            "Not enough stable available; available: {}",
+            prev_balance
+        )));
+    }
+
+    // Accrue interest with the full balance, so the lowered
+    // balance is never used in the utilization
+    let mut state: State = read_state(&deps.storage)?;
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
+    store_state(&mut deps.storage, &state)?;
+
+    let fee = rounding::mul(amount, config.flash_fee, Rounding::Up);
+    store_flash_loan(
+        &mut deps.storage,
+        &FlashLoan {
+            borrower: deps.api.canonical_address(&env.message.sender)?,
+            amount,
+            fee,
+            prev_balance,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![
+            native_payout_msg(
+                deps,
+                env.contract.address.clone(),
+                env.message.sender.clone(),
+                config.stable_denom,
+                amount,
+            )?,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.message.sender.clone(),
+                send: vec![],
+                msg,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address,
+                send: vec![],
+                msg: to_binary(&HandleMsg::AssertFlashRepayment {})?,
+            }),
+        ],
+        log: vec![
+            log("action", "flash_borrow"),
+            log("borrower", env.message.sender),
+            log("amount", amount),
+            log("fee", fee),
+        ],
+        data: None,
+    })
+}
+
+/// The fee stays in the contract balance, so it
+/// is distributed to the depositors
+pub fn assert_flash_repayment<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    if env.message.sender != env.contract.address {
+        return Err(StdError::unauthorized());
+    }
+
+    let flash_loan: FlashLoan = match read_flash_loan(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(StdError::generic_err("No flash loan in progress")),
+    };
+
+    let config: Config = read_config(&deps.storage)?;
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom)?;
+    let required_balance = flash_loan.prev_balance + flash_loan.fee;
+    if balance < required_balance {
+        return Err(StdError::generic_err(format!(
+            "Flash loan not repaid; required balance: {}",
+            required_balance
+        )));
+    }
+
+    remove_flash_loan(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "flash_repay"),
+            log("borrower", deps.api.human_address(&flash_loan.borrower)?),
+            log("amount", flash_loan.amount),
+            log("fee", flash_loan.fee),
+        ],
+        data: None,
+    })
+}
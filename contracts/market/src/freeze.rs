@@ -0,0 +1,108 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::borrow::{
+    apply_borrower_interest, compute_borrower_interest, compute_borrower_reward, compute_interest,
+    compute_reward,
+};
+use crate::state::{
+    read_accrual_freeze, read_borrower_info, read_config, read_frozen_windows, read_state,
+    remove_accrual_freeze, store_accrual_freeze, store_borrower_checkpoint, store_borrower_info,
+    store_frozen_windows, store_state, AccrualFreeze, BorrowerCheckpoint, BorrowerInfo, Config,
+    State,
+};
+
+use moneymarket::market::{AccrualFreezeResponse, FrozenWindow};
+
+/// The overseer freezes the accrual of the loans backed by a halted
+/// collateral; the interest accrued before the freeze is kept, and
+/// the accrual resumes from the global index when it is unfrozen
+pub fn set_accrual_frozen<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    frozen: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut state: State = read_state(&deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(&deps.storage, &borrower_raw);
+
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
+    match (read_accrual_freeze(&deps.storage, &borrower_raw)?, frozen) {
+        (None, true) => {
+            compute_borrower_interest(&state, &mut liability);
+            store_accrual_freeze(
+                &mut deps.storage,
+                &borrower_raw,
+                &AccrualFreeze {
+                    frozen_at: env.block.height,
+                    forgiven_interest: Decimal256::zero(),
+                },
+            )?;
+        }
+        (Some(mut freeze), false) => {
+            apply_borrower_interest(&mut state, &mut liability, Some(&mut freeze));
+            remove_accrual_freeze(&mut deps.storage, &borrower_raw);
+
+            let mut windows = read_frozen_windows(&deps.storage, &borrower_raw)?;
+            windows.push(FrozenWindow {
+                start: freeze.frozen_at,
+                end: env.block.height,
+                forgiven_interest: freeze.forgiven_interest,
+            });
+            store_frozen_windows(&mut deps.storage, &borrower_raw, &windows)?;
+        }
+        (Some(_), true) => return Err(StdError::generic_err("Interest accrual is already frozen")),
+        (None, false) => return Err(StdError::generic_err("Interest accrual is not frozen")),
+    }
+
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    store_state(&mut deps.storage, &state)?;
+    store_borrower_info(&mut deps.storage, &borrower_raw, &liability)?;
+    store_borrower_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &BorrowerCheckpoint {
+            loan_amount: liability.loan_amount,
+            interest_index: liability.interest_index,
+            global_interest_index: state.global_interest_index,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_accrual_frozen"),
+            log("borrower", borrower),
+            log("frozen", frozen),
+            log("loan_amount", liability.loan_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_accrual_freeze<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<AccrualFreezeResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let freeze: Option<AccrualFreeze> = read_accrual_freeze(&deps.storage, &borrower_raw)?;
+
+    Ok(AccrualFreezeResponse {
+        borrower,
+        frozen_at: freeze.as_ref().map(|v| v.frozen_at),
+        forgiven_interest: freeze.map_or(Decimal256::zero(), |v| v.forgiven_interest),
+        windows: read_frozen_windows(&deps.storage, &borrower_raw)?,
+    })
+}
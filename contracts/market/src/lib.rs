@@ -1,8 +1,14 @@
+pub mod bad_debt;
 pub mod borrow;
 pub mod contract;
 pub mod deposit;
+pub mod flash;
+pub mod freeze;
 pub mod querier;
+pub mod rate_history;
+pub mod reserves;
 pub mod state;
+pub mod term_deposit;
 
 mod migration;
 
@@ -74,6 +74,13 @@ pub fn migrate_config<S: Storage>(
             stable_denom: legacy_config.stable_denom,
             max_borrow_factor: legacy_config.max_borrow_factor,
             collector_contract,
+            guardian: None,
+            flash_fee: Decimal256::zero(),
+            reserve_announcement_threshold: None,
+            reserve_announcement_delay: 0,
+            utilization_half_life: 0,
+            lockup_terms: vec![],
+            early_withdrawal_penalty: Decimal256::zero(),
         },
     )
 }
@@ -99,6 +106,10 @@ pub fn migrate_state<S: Storage>(
             anc_emission_rate: legacy_state.anc_emission_rate,
             prev_aterra_supply: aterra_supply,
             prev_exchange_rate: exchange_rate,
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
 }
@@ -5,7 +5,9 @@ use cosmwasm_std::{
 
 use moneymarket::distribution_model::{AncEmissionRateResponse, QueryMsg as DistributionQueryMsg};
 use moneymarket::interest_model::{BorrowRateResponse, QueryMsg as InterestQueryMsg};
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse, QueryMsg as OverseerQueryMsg};
+use moneymarket::overseer::{
+    BorrowLimitResponse, CollateralsResponse, ConfigResponse, QueryMsg as OverseerQueryMsg,
+};
 
 pub fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
@@ -13,6 +15,7 @@ pub fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
     market_balance: Uint256,
     total_liabilities: Decimal256,
     total_reserves: Decimal256,
+    smoothed_utilization: Option<Decimal256>,
 ) -> StdResult<BorrowRateResponse> {
     let borrow_rate: BorrowRateResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -21,6 +24,7 @@ pub fn query_borrow_rate<S: Storage, A: Api, Q: Querier>(
                 market_balance,
                 total_liabilities,
                 total_reserves,
+                smoothed_utilization,
             })?,
         }))?;
 
@@ -52,6 +56,7 @@ pub fn query_anc_emission_rate<S: Storage, A: Api, Q: Querier>(
     target_deposit_rate: Decimal256,
     threshold_deposit_rate: Decimal256,
     current_emission_rate: Decimal256,
+    block_height: u64,
 ) -> StdResult<AncEmissionRateResponse> {
     let anc_emission_rate: AncEmissionRateResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -61,6 +66,7 @@ pub fn query_anc_emission_rate<S: Storage, A: Api, Q: Querier>(
                 target_deposit_rate,
                 threshold_deposit_rate,
                 current_emission_rate,
+                block_height: Some(block_height),
             })?,
         }))?;
 
@@ -79,3 +85,32 @@ pub fn query_target_deposit_rate<S: Storage, A: Api, Q: Querier>(
 
     Ok(overseer_config.target_deposit_rate)
 }
+
+pub fn query_debt_ceiling<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    overseer_contract: &HumanAddr,
+) -> StdResult<Option<Uint256>> {
+    let overseer_config: ConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(overseer_contract),
+            msg: to_binary(&OverseerQueryMsg::Config {})?,
+        }))?;
+
+    Ok(overseer_config.debt_ceiling)
+}
+
+pub fn query_collaterals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    overseer_addr: &HumanAddr,
+    borrower: &HumanAddr,
+) -> StdResult<CollateralsResponse> {
+    let collaterals: CollateralsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(overseer_addr),
+            msg: to_binary(&OverseerQueryMsg::Collaterals {
+                borrower: HumanAddr::from(borrower),
+            })?,
+        }))?;
+
+    Ok(collaterals)
+}
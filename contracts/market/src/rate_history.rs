@@ -0,0 +1,51 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::borrow::compute_utilization_ratio;
+use crate::state::{read_rate_snapshots, store_rate_snapshot, RateSnapshot, State};
+
+use moneymarket::market::{RateHistoryResponse, RateSnapshotResponse};
+
+/// Record the rates of the epoch so indexers can read
+/// the APY history without replaying every block
+pub fn record_rate_snapshot<S: Storage>(
+    storage: &mut S,
+    state: &State,
+    block_height: u64,
+    balance: Uint256,
+    borrow_rate: Decimal256,
+    deposit_rate: Decimal256,
+) -> StdResult<u64> {
+    let utilization_ratio = compute_utilization_ratio(state, balance);
+
+    store_rate_snapshot(
+        storage,
+        &RateSnapshot {
+            block_height,
+            borrow_rate,
+            deposit_rate,
+            utilization_ratio,
+            exchange_rate: state.prev_exchange_rate,
+        },
+    )
+}
+
+pub fn query_rate_history<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RateHistoryResponse> {
+    let snapshots = read_rate_snapshots(storage, start_after, limit)?
+        .into_iter()
+        .map(|(idx, snapshot)| RateSnapshotResponse {
+            idx,
+            block_height: snapshot.block_height,
+            borrow_rate: snapshot.borrow_rate,
+            deposit_rate: snapshot.deposit_rate,
+            utilization_ratio: snapshot.utilization_ratio,
+            exchange_rate: snapshot.exchange_rate,
+        })
+        .collect();
+
+    Ok(RateHistoryResponse { snapshots })
+}
@@ -0,0 +1,98 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, Querier, StdError, StdResult, Storage,
+};
+
+use crate::state::{
+    read_config, read_reserve_announcement, remove_reserve_announcement,
+    store_reserve_announcement, Config, ReserveAnnouncement, State,
+};
+
+use moneymarket::market::ReserveAnnouncementResponse;
+use moneymarket::rounding::{self, Rounding};
+
+/// Reserve amount to send to the collector at this epoch. Transfers
+/// above the threshold are announced first and sent at the first
+/// epoch after the delay; reserves accrued in between wait for the
+/// next transfer.
+pub fn compute_reserve_transfer<S: Storage>(
+    storage: &mut S,
+    config: &Config,
+    state: &State,
+    block_height: u64,
+) -> StdResult<Uint256> {
+    let total_reserves = rounding::to_uint(state.total_reserves, Rounding::Down);
+    if let Some(announcement) = read_reserve_announcement(storage)? {
+        if block_height < announcement.executable_at {
+            return Ok(Uint256::zero());
+        }
+
+        remove_reserve_announcement(storage);
+        return Ok(if announcement.amount < total_reserves {
+            announcement.amount
+        } else {
+            total_reserves
+        });
+    }
+
+    match config.reserve_announcement_threshold {
+        Some(threshold) if total_reserves > threshold => {
+            store_reserve_announcement(
+                storage,
+                &ReserveAnnouncement {
+                    amount: total_reserves,
+                    announced_at: block_height,
+                    executable_at: block_height + config.reserve_announcement_delay,
+                },
+            )?;
+
+            Ok(Uint256::zero())
+        }
+        _ => Ok(total_reserves),
+    }
+}
+
+/// The announced reserves stay in the market; they are announced
+/// again at the next epoch unless the config changes
+pub fn cancel_reserve_announcement<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if sender_raw != config.owner_addr && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let announcement: ReserveAnnouncement = match read_reserve_announcement(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(StdError::generic_err("No reserve announcement")),
+    };
+
+    remove_reserve_announcement(&mut deps.storage);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "cancel_reserve_announcement"),
+            log("amount", announcement.amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_reserve_announcement<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ReserveAnnouncementResponse> {
+    let announcement: ReserveAnnouncement = match read_reserve_announcement(&deps.storage)? {
+        Some(v) => v,
+        None => return Err(StdError::generic_err("No reserve announcement")),
+    };
+
+    Ok(ReserveAnnouncementResponse {
+        amount: announcement.amount,
+        announced_at: announcement.announced_at,
+        executable_at: announcement.executable_at,
+    })
+}
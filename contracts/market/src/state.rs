@@ -3,14 +3,51 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{Api, CanonicalAddr, Extern, HumanAddr, Order, Querier, StdResult, Storage};
-use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::market::{BorrowerInfoResponse, FrozenWindow, LockupTerm};
+use moneymarket::pause::PauseInfo;
+use moneymarket::raw_state::Namespace;
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const KEY_FLASH_LOAN: &[u8] = b"flash_loan";
+const KEY_RESERVE_ANNOUNCEMENT: &[u8] = b"reserve_announcement";
+const KEY_BAD_DEBT_RECORD_IDX: &[u8] = b"bad_debt_record_idx";
+const KEY_RATE_SNAPSHOT_IDX: &[u8] = b"rate_snapshot_idx";
+const KEY_LOCKED_POSITION_IDX: &[u8] = b"locked_position_idx";
 
 const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_LIABILITY_CHECKPOINT: &[u8] = b"liability_checkpoint";
+const PREFIX_ACCRUAL_FREEZE: &[u8] = b"accrual_freeze";
+const PREFIX_FROZEN_WINDOWS: &[u8] = b"frozen_windows";
+const PREFIX_BAD_DEBT_RECORD: &[u8] = b"bad_debt_record";
+const PREFIX_RATE_SNAPSHOT: &[u8] = b"rate_snapshot";
+const PREFIX_LOCKED_POSITION: &[u8] = b"locked_position";
+
+/// Number of epochs kept in the rate history
+pub const RATE_HISTORY_LENGTH: u64 = 100;
+
+/// Protocol state which can be exported with the RawState query
+pub fn exportable_namespaces() -> Vec<Namespace> {
+    vec![
+        Namespace::Singleton(KEY_CONFIG),
+        Namespace::Singleton(KEY_STATE),
+        Namespace::Singleton(KEY_PAUSE_INFO),
+        Namespace::Singleton(KEY_RESERVE_ANNOUNCEMENT),
+        Namespace::Singleton(KEY_BAD_DEBT_RECORD_IDX),
+        Namespace::Singleton(KEY_RATE_SNAPSHOT_IDX),
+        Namespace::Singleton(KEY_LOCKED_POSITION_IDX),
+        Namespace::Bucket(PREFIX_LIABILITY),
+        Namespace::Bucket(PREFIX_LIABILITY_CHECKPOINT),
+        Namespace::Bucket(PREFIX_ACCRUAL_FREEZE),
+        Namespace::Bucket(PREFIX_FROZEN_WINDOWS),
+        Namespace::Bucket(PREFIX_BAD_DEBT_RECORD),
+        Namespace::Bucket(PREFIX_RATE_SNAPSHOT),
+        Namespace::Bucket(PREFIX_LOCKED_POSITION),
+    ]
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -24,6 +61,16 @@ pub struct Config {
     pub distributor_contract: CanonicalAddr,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub guardian: Option<CanonicalAddr>,
+    pub flash_fee: Decimal256,
+    pub reserve_announcement_threshold: Option<Uint256>,
+    pub reserve_announcement_delay: u64,
+    #[serde(default)]
+    pub utilization_half_life: u64,
+    #[serde(default)]
+    pub lockup_terms: Vec<LockupTerm>,
+    #[serde(default = "Decimal256::zero")]
+    pub early_withdrawal_penalty: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -37,6 +84,66 @@ pub struct State {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    /// Moving average of the utilization ratio, kept
+    /// at accrual time while smoothing is enabled
+    #[serde(default = "Decimal256::zero")]
+    pub utilization_ema: Decimal256,
+    /// Principal of the locked positions, which is
+    /// kept out of the aterra exchange rate
+    #[serde(default = "Uint256::zero")]
+    pub total_locked_principal: Uint256,
+    /// Interest owed to the locked positions at maturity
+    #[serde(default = "Uint256::zero")]
+    pub total_locked_interest: Uint256,
+    /// Part of the locked interest received from the interest buffer
+    #[serde(default = "Uint256::zero")]
+    pub locked_interest_funded: Uint256,
+}
+
+/// Flash loan which is not repaid yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    pub borrower: CanonicalAddr,
+    pub amount: Uint256,
+    pub fee: Uint256,
+    /// Contract balance before the loan was sent
+    pub prev_balance: Uint256,
+}
+
+/// Reserve transfer waiting for its announcement delay to pass
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveAnnouncement {
+    pub amount: Uint256,
+    pub announced_at: u64,
+    pub executable_at: u64,
+}
+
+/// Loan written off as bad debt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtRecord {
+    pub borrower: CanonicalAddr,
+    pub amount: Uint256,
+    pub covered_by_reserves: Uint256,
+    pub block_height: u64,
+}
+
+/// Market rates recorded at an epoch operation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSnapshot {
+    pub block_height: u64,
+    pub borrow_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub utilization_ratio: Decimal256,
+    pub exchange_rate: Decimal256,
+}
+
+/// Fixed-rate deposit locked until its maturity
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedPosition {
+    pub principal: Uint256,
+    pub interest: Uint256,
+    pub start_time: u64,
+    pub maturity: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -47,6 +154,21 @@ pub struct BorrowerInfo {
     pub pending_rewards: Decimal256,
 }
 
+/// Ongoing interest accrual freeze of a borrower
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccrualFreeze {
+    pub frozen_at: u64,
+    pub forgiven_interest: Decimal256,
+}
+
+/// Borrower liability kept per height for historical reporting
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerCheckpoint {
+    pub loan_amount: Uint256,
+    pub interest_index: Decimal256,
+    pub global_interest_index: Decimal256,
+}
+
 pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
     Singleton::new(storage, KEY_CONFIG).save(data)
 }
@@ -63,6 +185,45 @@ pub fn read_state<S: Storage>(storage: &S) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn store_pause_info<S: Storage>(storage: &mut S, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info<S: Storage>(storage: &S) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_flash_loan<S: Storage>(storage: &mut S, data: &FlashLoan) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN).save(data)
+}
+
+pub fn read_flash_loan<S: Storage>(storage: &S) -> StdResult<Option<FlashLoan>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_LOAN).may_load()
+}
+
+pub fn remove_flash_loan<S: Storage>(storage: &mut S) {
+    Singleton::<S, FlashLoan>::new(storage, KEY_FLASH_LOAN).remove()
+}
+
+pub fn store_reserve_announcement<S: Storage>(
+    storage: &mut S,
+    data: &ReserveAnnouncement,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_RESERVE_ANNOUNCEMENT).save(data)
+}
+
+pub fn read_reserve_announcement<S: Storage>(
+    storage: &S,
+) -> StdResult<Option<ReserveAnnouncement>> {
+    ReadonlySingleton::new(storage, KEY_RESERVE_ANNOUNCEMENT).may_load()
+}
+
+pub fn remove_reserve_announcement<S: Storage>(storage: &mut S) {
+    Singleton::<S, ReserveAnnouncement>::new(storage, KEY_RESERVE_ANNOUNCEMENT).remove()
+}
+
 pub fn store_borrower_info<S: Storage>(
     storage: &mut S,
     borrower: &CanonicalAddr,
@@ -83,9 +244,225 @@ pub fn read_borrower_info<S: Storage>(storage: &S, borrower: &CanonicalAddr) ->
     }
 }
 
+pub fn store_borrower_checkpoint<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+    checkpoint: &BorrowerCheckpoint,
+) -> StdResult<()> {
+    let mut checkpoint_bucket: Bucket<S, BorrowerCheckpoint> =
+        Bucket::multilevel(&[PREFIX_LIABILITY_CHECKPOINT, borrower.as_slice()], storage);
+    checkpoint_bucket.save(&block_height.to_be_bytes(), checkpoint)
+}
+
+/// Latest borrower checkpoint at or before the given height
+pub fn read_borrower_checkpoint<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+) -> StdResult<Option<(u64, BorrowerCheckpoint)>> {
+    let checkpoint_bucket: ReadonlyBucket<S, BorrowerCheckpoint> =
+        ReadonlyBucket::multilevel(&[PREFIX_LIABILITY_CHECKPOINT, borrower.as_slice()], storage);
+
+    // heights are stored big endian, so the first element
+    // in descending order below the end bound is the nearest one
+    let end = (block_height + 1).to_be_bytes();
+    let nearest = checkpoint_bucket
+        .range(None, Some(&end), Order::Descending)
+        .next();
+    match nearest {
+        Some(elem) => {
+            let (k, v) = elem?;
+            let mut height_bytes = [0u8; 8];
+            height_bytes.copy_from_slice(&k);
+            Ok(Some((u64::from_be_bytes(height_bytes), v)))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn store_accrual_freeze<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    freeze: &AccrualFreeze,
+) -> StdResult<()> {
+    bucket(PREFIX_ACCRUAL_FREEZE, storage).save(borrower.as_slice(), freeze)
+}
+
+pub fn read_accrual_freeze<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<AccrualFreeze>> {
+    bucket_read(PREFIX_ACCRUAL_FREEZE, storage).may_load(borrower.as_slice())
+}
+
+pub fn remove_accrual_freeze<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    Bucket::<S, AccrualFreeze>::new(PREFIX_ACCRUAL_FREEZE, storage).remove(borrower.as_slice())
+}
+
+pub fn store_frozen_windows<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    windows: &[FrozenWindow],
+) -> StdResult<()> {
+    bucket(PREFIX_FROZEN_WINDOWS, storage).save(borrower.as_slice(), &windows.to_vec())
+}
+
+pub fn read_frozen_windows<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Vec<FrozenWindow>> {
+    Ok(bucket_read(PREFIX_FROZEN_WINDOWS, storage)
+        .may_load(borrower.as_slice())?
+        .unwrap_or_default())
+}
+
+/// Store the record under the next index and return the index
+pub fn store_bad_debt_record<S: Storage>(
+    storage: &mut S,
+    record: &BadDebtRecord,
+) -> StdResult<u64> {
+    let idx: u64 = ReadonlySingleton::new(storage, KEY_BAD_DEBT_RECORD_IDX)
+        .may_load()?
+        .unwrap_or(0u64)
+        + 1;
+    Singleton::new(storage, KEY_BAD_DEBT_RECORD_IDX).save(&idx)?;
+    bucket(PREFIX_BAD_DEBT_RECORD, storage).save(&idx.to_be_bytes(), record)?;
+
+    Ok(idx)
+}
+
+/// Store the snapshot under the next index and drop the one
+/// which fell out of the ring buffer
+pub fn store_rate_snapshot<S: Storage>(storage: &mut S, snapshot: &RateSnapshot) -> StdResult<u64> {
+    let idx: u64 = ReadonlySingleton::new(storage, KEY_RATE_SNAPSHOT_IDX)
+        .may_load()?
+        .unwrap_or(0u64)
+        + 1;
+    Singleton::new(storage, KEY_RATE_SNAPSHOT_IDX).save(&idx)?;
+
+    let mut snapshot_bucket: Bucket<S, RateSnapshot> = bucket(PREFIX_RATE_SNAPSHOT, storage);
+    snapshot_bucket.save(&idx.to_be_bytes(), snapshot)?;
+    if idx > RATE_HISTORY_LENGTH {
+        snapshot_bucket.remove(&(idx - RATE_HISTORY_LENGTH).to_be_bytes());
+    }
+
+    Ok(idx)
+}
+
+/// Store the position of the owner under the next index
+/// and return the index
+pub fn store_new_locked_position<S: Storage>(
+    storage: &mut S,
+    owner: &CanonicalAddr,
+    position: &LockedPosition,
+) -> StdResult<u64> {
+    let idx: u64 = ReadonlySingleton::new(storage, KEY_LOCKED_POSITION_IDX)
+        .may_load()?
+        .unwrap_or(0u64)
+        + 1;
+    Singleton::new(storage, KEY_LOCKED_POSITION_IDX).save(&idx)?;
+
+    let mut position_bucket: Bucket<S, LockedPosition> =
+        Bucket::multilevel(&[PREFIX_LOCKED_POSITION, owner.as_slice()], storage);
+    position_bucket.save(&idx.to_be_bytes(), position)?;
+
+    Ok(idx)
+}
+
+pub fn read_locked_position<S: Storage>(
+    storage: &S,
+    owner: &CanonicalAddr,
+    position_id: u64,
+) -> StdResult<Option<LockedPosition>> {
+    let position_bucket: ReadonlyBucket<S, LockedPosition> =
+        ReadonlyBucket::multilevel(&[PREFIX_LOCKED_POSITION, owner.as_slice()], storage);
+    position_bucket.may_load(&position_id.to_be_bytes())
+}
+
+pub fn remove_locked_position<S: Storage>(
+    storage: &mut S,
+    owner: &CanonicalAddr,
+    position_id: u64,
+) {
+    let mut position_bucket: Bucket<S, LockedPosition> =
+        Bucket::multilevel(&[PREFIX_LOCKED_POSITION, owner.as_slice()], storage);
+    position_bucket.remove(&position_id.to_be_bytes())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
+pub fn read_bad_debt_records<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, BadDebtRecord)>> {
+    let record_bucket: ReadonlyBucket<S, BadDebtRecord> =
+        bucket_read(PREFIX_BAD_DEBT_RECORD, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    record_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut idx_bytes = [0u8; 8];
+            idx_bytes.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(idx_bytes), v))
+        })
+        .collect()
+}
+
+pub fn read_rate_snapshots<S: Storage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, RateSnapshot)>> {
+    let snapshot_bucket: ReadonlyBucket<S, RateSnapshot> =
+        bucket_read(PREFIX_RATE_SNAPSHOT, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    snapshot_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut idx_bytes = [0u8; 8];
+            idx_bytes.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(idx_bytes), v))
+        })
+        .collect()
+}
+
+pub fn read_locked_positions<S: Storage>(
+    storage: &S,
+    owner: &CanonicalAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, LockedPosition)>> {
+    let position_bucket: ReadonlyBucket<S, LockedPosition> =
+        ReadonlyBucket::multilevel(&[PREFIX_LOCKED_POSITION, owner.as_slice()], storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    position_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut idx_bytes = [0u8; 8];
+            idx_bytes.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(idx_bytes), v))
+        })
+        .collect()
+}
+
 pub fn read_borrower_infos<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     start_after: Option<CanonicalAddr>,
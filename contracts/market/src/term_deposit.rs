@@ -0,0 +1,266 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::deposit::assert_redeem_amount;
+use crate::flash::assert_no_flash_loan;
+use crate::state::{
+    read_config, read_locked_position, read_locked_positions, read_pause_info, read_state,
+    remove_locked_position, store_new_locked_position, store_state, Config, LockedPosition, State,
+};
+
+use moneymarket::market::{
+    LockedLiabilitiesResponse, LockedPositionResponse, LockedPositionsResponse,
+};
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::query_balance;
+use moneymarket::rounding::{self, Rounding};
+
+const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+/// Lock the sent stable asset at the fixed rate of the lockup term.
+/// The principal stays lendable but is kept out of the aterra
+/// exchange rate; the interest is funded by the interest buffer
+pub fn deposit_stable_locked<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    duration: u64,
+) -> HandleResult {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(StdError::generic_err("Deposits are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
+    let config: Config = read_config(&deps.storage)?;
+    let rate = match config.lockup_terms.iter().find(|t| t.duration == duration) {
+        Some(term) => term.rate,
+        None => {
+            return Err(StdError::generic_err(format!(
+                "Unsupported lockup duration: {}",
+                duration
+            )))
+        }
+    };
+
+    let deposit_amount = sent_stable_amount(&env, &config);
+    if deposit_amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Deposit amount must be greater than 0 {}",
+            config.stable_denom,
+        )));
+    }
+
+    // Update interest related state before the deposit
+    // is moved out of the exchange rate
+    let mut state: State = read_state(&deps.storage)?;
+    compute_interest(
+        deps,
+        &config,
+        &mut state,
+        env.block.height,
+        Some(deposit_amount),
+    )?;
+    compute_reward(&mut state, env.block.height);
+
+    // interest = principal * rate * duration / year
+    let interest = rounding::mul(
+        deposit_amount,
+        rate * Decimal256::from_ratio(duration, SECONDS_PER_YEAR),
+        Rounding::Down,
+    );
+
+    state.total_locked_principal += deposit_amount;
+    state.total_locked_interest += interest;
+    store_state(&mut deps.storage, &state)?;
+
+    let position = LockedPosition {
+        principal: deposit_amount,
+        interest,
+        start_time: env.block.time,
+        maturity: env.block.time + duration,
+    };
+    let position_id = store_new_locked_position(
+        &mut deps.storage,
+        &deps.api.canonical_address(&env.message.sender)?,
+        &position,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deposit_stable_locked"),
+            log("depositor", env.message.sender),
+            log("position_id", position_id),
+            log("deposit_amount", deposit_amount),
+            log("interest", interest),
+            log("maturity", position.maturity),
+        ],
+        data: None,
+    })
+}
+
+/// Withdraw a locked position with its interest at maturity;
+/// before it the interest is forfeited and the penalty is charged,
+/// both going to the aterra holders
+pub fn withdraw_locked<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    position_id: u64,
+) -> HandleResult {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(StdError::generic_err("Withdrawals are paused"));
+    }
+
+    assert_no_flash_loan(&deps.storage)?;
+
+    let config: Config = read_config(&deps.storage)?;
+    let owner_raw = deps.api.canonical_address(&env.message.sender)?;
+    let position: LockedPosition =
+        match read_locked_position(&deps.storage, &owner_raw, position_id)? {
+            Some(position) => position,
+            None => return Err(StdError::generic_err("Locked position not found")),
+        };
+
+    // Update interest related state
+    let mut state: State = read_state(&deps.storage)?;
+    compute_interest(deps, &config, &mut state, env.block.height, None)?;
+    compute_reward(&mut state, env.block.height);
+
+    let (withdraw_amount, penalty) = if env.block.time >= position.maturity {
+        if state.locked_interest_funded < position.interest {
+            return Err(StdError::generic_err(
+                "Interest of the locked position is not funded yet",
+            ));
+        }
+
+        state.locked_interest_funded = state.locked_interest_funded - position.interest;
+        (position.principal + position.interest, Uint256::zero())
+    } else {
+        let penalty = rounding::mul(
+            position.principal,
+            config.early_withdrawal_penalty,
+            Rounding::Up,
+        );
+        (position.principal - penalty, penalty)
+    };
+
+    state.total_locked_principal = state.total_locked_principal - position.principal;
+    state.total_locked_interest = state.total_locked_interest - position.interest;
+
+    // interest already funded for a forfeited position
+    // is left to the aterra holders
+    state.locked_interest_funded =
+        std::cmp::min(state.locked_interest_funded, state.total_locked_interest);
+
+    let current_balance =
+        query_balance(deps, &env.contract.address, config.stable_denom.to_string())?;
+    assert_redeem_amount(&config, &state, current_balance, withdraw_amount)?;
+
+    remove_locked_position(&mut deps.storage, &owner_raw, position_id);
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            env.message.sender.clone(),
+            config.stable_denom,
+            withdraw_amount,
+        )?],
+        log: vec![
+            log("action", "withdraw_locked"),
+            log("owner", env.message.sender),
+            log("position_id", position_id),
+            log("withdraw_amount", withdraw_amount),
+            log("penalty", penalty),
+        ],
+        data: None,
+    })
+}
+
+/// Executor: overseer
+pub fn fund_locked_interest<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if config.overseer_contract != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+
+    let amount = sent_stable_amount(&env, &config);
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Funding amount must be greater than 0 {}",
+            config.stable_denom,
+        )));
+    }
+
+    let mut state: State = read_state(&deps.storage)?;
+    compute_interest(deps, &config, &mut state, env.block.height, Some(amount))?;
+    compute_reward(&mut state, env.block.height);
+
+    // the amount exceeding the unfunded interest is left to the aterra holders
+    let funded_amount = std::cmp::min(
+        amount,
+        state.total_locked_interest - state.locked_interest_funded,
+    );
+    state.locked_interest_funded += funded_amount;
+    store_state(&mut deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "fund_locked_interest"),
+            log("funded_amount", funded_amount),
+        ],
+        data: None,
+    })
+}
+
+fn sent_stable_amount(env: &Env, config: &Config) -> Uint256 {
+    env.message
+        .sent_funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero)
+}
+
+pub fn query_locked_positions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: HumanAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LockedPositionsResponse> {
+    let owner_raw = deps.api.canonical_address(&owner)?;
+    let positions = read_locked_positions(&deps.storage, &owner_raw, start_after, limit)?
+        .into_iter()
+        .map(|(position_id, position)| LockedPositionResponse {
+            position_id,
+            principal: position.principal,
+            interest: position.interest,
+            start_time: position.start_time,
+            maturity: position.maturity,
+        })
+        .collect();
+
+    Ok(LockedPositionsResponse { owner, positions })
+}
+
+pub fn query_locked_liabilities<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<LockedLiabilitiesResponse> {
+    let state: State = read_state(&deps.storage)?;
+
+    Ok(LockedLiabilitiesResponse {
+        total_principal: state.total_locked_principal,
+        total_interest: state.total_locked_interest,
+        funded_interest: state.locked_interest_funded,
+        unfunded_interest: state.total_locked_interest - state.locked_interest_funded,
+    })
+}
@@ -1,9 +1,9 @@
-use crate::borrow::{compute_borrower_interest, compute_interest};
+use crate::borrow::{compute_borrower_interest, compute_interest, compute_utilization_ema};
 use crate::state::{store_state, BorrowerInfo, Config, State};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
-use cosmwasm_std::{Api, Coin, HumanAddr, Uint128};
+use cosmwasm_std::{Api, CanonicalAddr, Coin, HumanAddr, Uint128};
 
 #[test]
 fn proper_compute_borrower_interest() {
@@ -18,6 +18,10 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::zero(),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
     };
     let mut liability1 = BorrowerInfo {
         interest_index: Decimal256::one(),
@@ -44,6 +48,10 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::zero(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::zero(),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
     };
     let mut liability3 = BorrowerInfo {
         interest_index: Decimal256::from_uint256(4u128),
@@ -113,6 +121,13 @@ fn proper_compute_interest() {
             .unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        guardian: None,
+        flash_fee: Decimal256::zero(),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: 0,
+        utilization_half_life: 0,
+        lockup_terms: vec![],
+        early_withdrawal_penalty: Decimal256::zero(),
     };
 
     deps.querier
@@ -128,6 +143,10 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::zero(),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -153,6 +172,10 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 
@@ -178,6 +201,10 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_ratio(19995, 10000),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 
@@ -192,6 +219,10 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::from(2000000u128),
         prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::zero(),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -220,6 +251,69 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_uint256(2u64),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 }
+
+#[test]
+fn proper_compute_utilization_ema() {
+    let mut config = Config {
+        contract_addr: CanonicalAddr::default(),
+        owner_addr: CanonicalAddr::default(),
+        aterra_contract: CanonicalAddr::default(),
+        interest_model: CanonicalAddr::default(),
+        distribution_model: CanonicalAddr::default(),
+        distributor_contract: CanonicalAddr::default(),
+        collector_contract: CanonicalAddr::default(),
+        overseer_contract: CanonicalAddr::default(),
+        stable_denom: "uusd".to_string(),
+        max_borrow_factor: Decimal256::one(),
+        guardian: None,
+        flash_fee: Decimal256::zero(),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: 0,
+        utilization_half_life: 0,
+        lockup_terms: vec![],
+        early_withdrawal_penalty: Decimal256::zero(),
+    };
+
+    let mut state = State {
+        total_liabilities: Decimal256::from_uint256(500000u128),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: 100,
+        last_reward_updated: 100,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::zero(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::percent(10),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
+    };
+
+    // smoothing disabled
+    compute_utilization_ema(&config, &mut state, 110, Uint256::from(500000u128));
+    assert_eq!(state.utilization_ema, Decimal256::percent(10));
+
+    // utilization_ratio = 0.5
+    // one half life; 0.1 * 0.5 + 0.5 * 0.5
+    config.utilization_half_life = 10;
+    compute_utilization_ema(&config, &mut state, 110, Uint256::from(500000u128));
+    assert_eq!(state.utilization_ema, Decimal256::percent(30));
+
+    // half of a half life; 0.3 * 0.75 + 0.5 * 0.25
+    state.last_interest_updated = 110;
+    compute_utilization_ema(&config, &mut state, 115, Uint256::from(500000u128));
+    assert_eq!(state.utilization_ema, Decimal256::percent(35));
+
+    // the previous average is dropped after long enough
+    state.last_interest_updated = 115;
+    compute_utilization_ema(&config, &mut state, 10000, Uint256::from(500000u128));
+    assert_eq!(state.utilization_ema, Decimal256::percent(50));
+}
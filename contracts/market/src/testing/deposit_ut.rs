@@ -51,6 +51,13 @@ fn proper_compute_exchange_rate() {
             .unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        guardian: None,
+        flash_fee: Decimal256::zero(),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: 0,
+        utilization_half_life: 0,
+        lockup_terms: vec![],
+        early_withdrawal_penalty: Decimal256::zero(),
     };
     deps.querier.with_token_balances(&[(
         &HumanAddr::from("AT-uusd"),
@@ -70,6 +77,10 @@ fn proper_compute_exchange_rate() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        utilization_ema: Decimal256::zero(),
+        total_locked_principal: Uint256::zero(),
+        total_locked_interest: Uint256::zero(),
+        locked_interest_funded: Uint256::zero(),
     };
     let mock_deposit_amount = Some(Uint256::from(1000000u128));
 
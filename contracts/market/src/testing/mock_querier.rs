@@ -13,7 +13,8 @@ use std::collections::HashMap;
 use cw20::TokenInfoResponse;
 use moneymarket::distribution_model::AncEmissionRateResponse;
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
+use moneymarket::overseer::{BorrowLimitResponse, CollateralsResponse, ConfigResponse};
+use moneymarket::tokens::TokensHuman;
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -36,9 +37,12 @@ pub enum QueryMsg {
         target_deposit_rate: Decimal256,
         threshold_deposit_rate: Decimal256,
         current_emission_rate: Decimal256,
+        block_height: Option<u64>,
     },
     /// Query overseer config to get target deposit rate
     Config {},
+    /// Query collaterals of a borrower to overseer contract
+    Collaterals { borrower: HumanAddr },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -67,6 +71,8 @@ pub struct WasmMockQuerier {
     tax_querier: TaxQuerier,
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
+    debt_ceiling: Option<Uint256>,
+    collaterals: HashMap<HumanAddr, TokensHuman>,
     canonical_length: usize,
 }
 
@@ -216,12 +222,12 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
-                match from_binary(&msg).unwrap() {
+                match from_binary(msg).unwrap() {
                     QueryMsg::BorrowRate {
                         market_balance: _,
                         total_liabilities: _,
                         total_reserves: _,
-                    } => match self.borrow_rate_querier.borrower_rate.get(&contract_addr) {
+                    } => match self.borrow_rate_querier.borrower_rate.get(contract_addr) {
                         Some(v) => Ok(to_binary(&BorrowRateResponse { rate: *v })),
                         None => Err(SystemError::InvalidRequest {
                             error: "No borrow rate exists".to_string(),
@@ -246,6 +252,7 @@ impl WasmMockQuerier {
                         target_deposit_rate: _,
                         threshold_deposit_rate: _,
                         current_emission_rate: _,
+                        block_height: _,
                     } => Ok(to_binary(&AncEmissionRateResponse {
                         emission_rate: Decimal256::from_uint256(5u64),
                     })),
@@ -262,6 +269,21 @@ impl WasmMockQuerier {
                         stable_denom: "uusd".to_string(),
                         epoch_period: 100u64,
                         price_timeframe: 100u64,
+                        guardian: None,
+                        hysteresis_band: Decimal256::zero(),
+                        hysteresis_epochs: 1u64,
+                        retry_base_delay: 60u64,
+                        retry_max_delay: 3600u64,
+                        priority_tolerance: Decimal256::percent(1),
+                        debt_ceiling: self.debt_ceiling,
+                        liquidation_release_ratio: Decimal256::one(),
+                        liquidation_cooldown: 0u64,
+                        emergency_liquidation_ratio: Decimal256::one(),
+                        liquidation_authorization_period: 10u64,
+                    })),
+                    QueryMsg::Collaterals { borrower } => Ok(to_binary(&CollateralsResponse {
+                        collaterals: self.collaterals.get(&borrower).cloned().unwrap_or_default(),
+                        borrower,
                     })),
                 }
             }
@@ -289,7 +311,7 @@ impl WasmMockQuerier {
                             name: "mAPPL".to_string(),
                             symbol: "mAPPL".to_string(),
                             decimals: 6,
-                            total_supply: total_supply,
+                            total_supply,
                         })
                         .unwrap(),
                     ))
@@ -337,6 +359,8 @@ impl WasmMockQuerier {
             tax_querier: TaxQuerier::default(),
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
+            debt_ceiling: None,
+            collaterals: HashMap::new(),
             canonical_length,
         }
     }
@@ -367,4 +391,15 @@ impl WasmMockQuerier {
     pub fn with_borrow_limit(&mut self, borrow_limit: &[(&HumanAddr, &Uint256)]) {
         self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
     }
+
+    pub fn with_debt_ceiling(&mut self, debt_ceiling: Option<Uint256>) {
+        self.debt_ceiling = debt_ceiling;
+    }
+
+    pub fn with_collaterals(&mut self, collaterals: &[(&HumanAddr, &TokensHuman)]) {
+        self.collaterals = collaterals
+            .iter()
+            .map(|(borrower, tokens)| ((*borrower).clone(), (*tokens).clone()))
+            .collect();
+    }
 }
@@ -1,19 +1,32 @@
 use crate::contract::{handle, init, query, INITIAL_DEPOSIT_AMOUNT};
-use crate::state::{read_borrower_infos, read_state, store_state, State};
+use crate::state::{
+    read_borrower_infos, read_config, read_state, store_borrower_info, store_state, BorrowerInfo,
+    Config, State,
+};
 use crate::testing::mock_querier::mock_dependencies;
 
 use anchor_token::distributor::HandleMsg as FaucetHandleMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, log, to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128,
-    WasmMsg,
+    from_binary, from_slice, log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal,
+    HumanAddr, StdError, Uint128, WasmMsg,
 };
 use cw20::{Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::denom_migration::{
+    ConversionHandleMsg, PendingDenomMigrationResponse, DENOM_MIGRATION_TIMELOCK_PERIOD,
+};
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, HandleMsg, InitMsg, QueryMsg, StateResponse,
+    AccrualFreezeResponse, BadDebtHistoryResponse, BadDebtRecordResponse,
+    BorrowerInfoProjectedResponse, BorrowerInfoResponse, ConfigResponse, Cw20HookMsg,
+    EpochStateResponse, FrozenWindow, HandleMsg, InitMsg, LockedLiabilitiesResponse,
+    LockedPositionResponse, LockedPositionsResponse, LockupTerm, QueryMsg, RateHistoryResponse,
+    RateSnapshotResponse, ReserveAnnouncementResponse, StateProjectedResponse, StateResponse,
 };
+use moneymarket::pause::PauseInfo;
 use moneymarket::querier::deduct_tax;
+use moneymarket::raw_state::RawStateResponse;
+use moneymarket::sweep::SweepAsset;
 use std::str::FromStr;
 use terraswap::hook::InitHook;
 use terraswap::token::InitMsg as TokenInitMsg;
@@ -180,18 +193,20 @@ fn update_config() {
     let env = mock_env("addr0000", &[]);
     let _res = handle(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // transfer ownership
     let env = mock_env("owner", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner_addr: Some(HumanAddr("owner1".to_string())),
-        interest_model: None,
-        distribution_model: None,
-        max_borrow_factor: None,
+    let msg = HandleMsg::ProposeOwner {
+        new_owner: HumanAddr("owner1".to_string()),
+        expires_in: 100u64,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
+    let env = mock_env("owner1", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(0, res.messages.len());
+
     // it worked, let's query the state
     let res = query(&deps, QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&res).unwrap();
@@ -200,10 +215,16 @@ fn update_config() {
     // update left items
     let env = mock_env("owner1", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: Some(HumanAddr::from("interest2")),
         distribution_model: Some(HumanAddr::from("distribution2")),
         max_borrow_factor: Some(Decimal256::percent(100)),
+        guardian: Some(HumanAddr::from("guardian")),
+        flash_fee: Some(Decimal256::permille(1)),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: None,
+        utilization_half_life: None,
+        lockup_terms: None,
+        early_withdrawal_penalty: None,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
@@ -219,14 +240,22 @@ fn update_config() {
         config_res.distribution_model
     );
     assert_eq!(Decimal256::percent(100), config_res.max_borrow_factor);
+    assert_eq!(Some(HumanAddr::from("guardian")), config_res.guardian);
+    assert_eq!(Decimal256::permille(1), config_res.flash_fee);
 
     // Unauthorized err
     let env = mock_env("owner", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: None,
         distribution_model: None,
         max_borrow_factor: None,
+        guardian: None,
+        flash_fee: None,
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: None,
+        utilization_half_life: None,
+        lockup_terms: None,
+        early_withdrawal_penalty: None,
     };
 
     let res = handle(&mut deps, env, msg);
@@ -237,7 +266,7 @@ fn update_config() {
 }
 
 #[test]
-fn deposit_stable_huge_amount() {
+fn set_pause() {
     let mut deps = mock_dependencies(
         20,
         &[Coin {
@@ -261,140 +290,351 @@ fn deposit_stable_huge_amount() {
             amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
+    let _res = init(&mut deps, env, msg).unwrap();
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = init(&mut deps, env.clone(), msg).unwrap();
-    // Register anchor token contract
-    let msg = HandleMsg::RegisterATerra {};
-    let env = mock_env("AT-uusd", &[]);
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        guardian: Some(HumanAddr::from("guardian")),
+        flash_fee: Some(Decimal256::permille(1)),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: None,
+        utilization_half_life: None,
+        lockup_terms: None,
+        early_withdrawal_penalty: None,
+    };
+    let env = mock_env("owner", &[]);
     let _res = handle(&mut deps, env, msg).unwrap();
 
-    // Register overseer contract
-    let msg = HandleMsg::RegisterContracts {
-        overseer_contract: HumanAddr::from("overseer"),
-        interest_model: HumanAddr::from("interest"),
-        distribution_model: HumanAddr::from("distribution"),
-        collector_contract: HumanAddr::from("collector"),
-        distributor_contract: HumanAddr::from("distributor"),
+    // random address cannot pause
+    let msg = HandleMsg::SetPause {
+        deposits: Some(true),
+        borrows: Some(true),
+        liquidations: None,
+        withdrawals: None,
     };
     let env = mock_env("addr0000", &[]);
-    let _res = handle(&mut deps, env, msg).unwrap();
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
 
-    // Must deposit stable_denom
-    let msg = HandleMsg::DepositStable {};
-    let env = mock_env(
-        "addr0000",
-        &[Coin {
-            denom: "ukrw".to_string(),
-            amount: Uint128::from(123u128),
-        }],
+    let env = mock_env("guardian", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_pause"),
+            log("deposits", true),
+            log("borrows", true),
+            log("liquidations", false),
+            log("withdrawals", false),
+        ]
     );
 
-    let res = handle(&mut deps, env, msg.clone());
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+    let res = query(&deps, QueryMsg::PauseInfo {}).unwrap();
+    let pause_info: PauseInfo = from_binary(&res).unwrap();
+    assert_eq!(
+        pause_info,
+        PauseInfo {
+            deposits: true,
+            borrows: true,
+            liquidations: false,
+            withdrawals: false,
         }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    );
 
     let env = mock_env(
         "addr0000",
         &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(55_555_555_000_000u128),
+            amount: Uint128::from(1000000u128),
         }],
     );
+    match handle(
+        &mut deps,
+        env,
+        HandleMsg::DepositStable {
+            min_mint_amount: None,
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Deposits are paused"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    deps.querier
-        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Borrows are paused"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // guardian cannot unpause
+    let msg = HandleMsg::SetPause {
+        deposits: Some(false),
+        borrows: None,
+        liquidations: None,
+        withdrawals: None,
+    };
+    let env = mock_env("guardian", &[]);
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let env = mock_env("owner", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let res = query(&deps, QueryMsg::PauseInfo {}).unwrap();
+    let pause_info: PauseInfo = from_binary(&res).unwrap();
+    assert!(!pause_info.deposits);
+    assert!(pause_info.borrows);
+}
+
+#[test]
+fn sweep_unrelated_funds() {
+    let mut deps = mock_dependencies(
+        20,
+        &[
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(5000u128),
+            },
+        ],
+    );
     deps.querier.with_token_balances(&[(
-        &HumanAddr::from("AT-uusd"),
+        &HumanAddr::from("token0000"),
         &[(
             &HumanAddr::from(MOCK_CONTRACT_ADDR),
-            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            &Uint128::from(100u128),
         )],
     )]);
-    deps.querier.update_balance(
-        HumanAddr::from(MOCK_CONTRACT_ADDR),
-        vec![Coin {
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 55_555_555_000_000u128),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
+    let _res = init(&mut deps, env, msg).unwrap();
 
-    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    let msg = HandleMsg::SweepUnrelatedFunds {
+        asset: SweepAsset::Native {
+            denom: "ukrw".to_string(),
+        },
+        recipient: HumanAddr::from("addr0000"),
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // deposits cannot be swept
+    let env = mock_env("owner", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::SweepUnrelatedFunds {
+            asset: SweepAsset::Native {
+                denom: "uusd".to_string(),
+            },
+            recipient: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot sweep tracked funds: uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
     assert_eq!(
-        res.log,
-        vec![
-            log("action", "deposit_stable"),
-            log("depositor", "addr0000"),
-            log("mint_amount", "55555555000000"),
-            log("deposit_amount", "55555555000000"),
-        ]
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(5000u128),
+            }],
+        })]
     );
 
+    let msg = HandleMsg::SweepUnrelatedFunds {
+        asset: SweepAsset::Token {
+            contract_addr: HumanAddr::from("token0000"),
+        },
+        recipient: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, env, msg).unwrap();
     assert_eq!(
         res.messages,
         vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: HumanAddr::from("AT-uusd"),
+            contract_addr: HumanAddr::from("token0000"),
             send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Mint {
+            msg: to_binary(&Cw20HandleMsg::Transfer {
                 recipient: HumanAddr::from("addr0000"),
-                amount: Uint128::from(55_555_555_000_000u128),
+                amount: Uint128::from(100u128),
             })
             .unwrap(),
         })]
     );
+}
 
-    deps.querier.update_balance(
-        HumanAddr::from(MOCK_CONTRACT_ADDR),
-        vec![Coin {
+#[test]
+fn denom_migration() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(111_111_110_000_000u128),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
 
-    deps.querier.with_token_balances(&[(
-        &HumanAddr::from("AT-uusd"),
-        &[(
-            &HumanAddr::from(MOCK_CONTRACT_ADDR),
-            &Uint128::from(55_555_555_000_000u128),
-        )],
-    )]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
 
-    let res = handle(&mut deps, env, msg).unwrap();
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::ProposeDenomMigration {
+        new_denom: "uusd2".to_string(),
+        conversion_contract: HumanAddr::from("converter"),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let mut env = mock_env("owner", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res = query(&deps, QueryMsg::PendingDenomMigration {}).unwrap();
+    let migration_res: PendingDenomMigrationResponse = from_binary(&res).unwrap();
     assert_eq!(
-        res.log,
-        vec![
-            log("action", "deposit_stable"),
-            log("depositor", "addr0000"),
-            log("mint_amount", "55555555000000"),
-            log("deposit_amount", "55555555000000"),
-        ]
+        migration_res,
+        PendingDenomMigrationResponse {
+            new_denom: "uusd2".to_string(),
+            conversion_contract: HumanAddr::from("converter"),
+            effective_at: env.block.time + DENOM_MIGRATION_TIMELOCK_PERIOD,
+        }
+    );
+
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteDenomMigration {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "Denom migration is not effective until {}",
+                env.block.time + DENOM_MIGRATION_TIMELOCK_PERIOD
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the conversion is taxed out of the converted balance
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
+    let send_coin = deduct_tax(
+        &deps,
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        },
+    )
+    .unwrap();
+    let conversion_tax = Uint256::from(INITIAL_DEPOSIT_AMOUNT) - Uint256::from(send_coin.amount);
+    assert!(!conversion_tax.is_zero());
 
+    env.block.time += DENOM_MIGRATION_TIMELOCK_PERIOD;
+    let res = handle(&mut deps, env, HandleMsg::ExecuteDenomMigration {}).unwrap();
     assert_eq!(
         res.messages,
         vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: HumanAddr::from("AT-uusd"),
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Mint {
-                recipient: HumanAddr::from("addr0000"),
-                amount: Uint128::from(55_555_555_000_000u128),
+            contract_addr: HumanAddr::from("converter"),
+            send: vec![send_coin],
+            msg: to_binary(&ConversionHandleMsg::Convert {
+                target_denom: "uusd2".to_string(),
             })
             .unwrap(),
         })]
     );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "execute_denom_migration"),
+            log("old_denom", "uusd"),
+            log("new_denom", "uusd2"),
+            log("conversion_tax", conversion_tax),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.stable_denom, "uusd2".to_string());
+
+    let res = query(&deps, QueryMsg::PendingDenomMigration {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No pending denom migration"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
 
 #[test]
-fn deposit_stable() {
+fn deposit_stable_huge_amount() {
     let mut deps = mock_dependencies(
         20,
         &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
 
@@ -433,7 +673,9 @@ fn deposit_stable() {
     let _res = handle(&mut deps, env, msg).unwrap();
 
     // Must deposit stable_denom
-    let msg = HandleMsg::DepositStable {};
+    let msg = HandleMsg::DepositStable {
+        min_mint_amount: None,
+    };
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -450,7 +692,161 @@ fn deposit_stable() {
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    // base denom, zero deposit
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(55_555_555_000_000u128),
+        }],
+    );
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 55_555_555_000_000u128),
+        }],
+    );
+
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit_stable"),
+            log("depositor", "addr0000"),
+            log("mint_amount", "55555555000000"),
+            log("deposit_amount", "55555555000000"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(55_555_555_000_000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(111_111_110_000_000u128),
+        }],
+    );
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(55_555_555_000_000u128),
+        )],
+    )]);
+
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit_stable"),
+            log("depositor", "addr0000"),
+            log("mint_amount", "55555555000000"),
+            log("deposit_amount", "55555555000000"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(55_555_555_000_000u128),
+            })
+            .unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn deposit_stable() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Must deposit stable_denom
+    let msg = HandleMsg::DepositStable {
+        min_mint_amount: None,
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::from(123u128),
+        }],
+    );
+
+    let res = handle(&mut deps, env, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Deposit amount must be greater than 0 uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // base denom, zero deposit
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -492,6 +888,17 @@ fn deposit_stable() {
         }],
     );
 
+    let bounded_msg = HandleMsg::DepositStable {
+        min_mint_amount: Some(Uint256::from(1000001u64)),
+    };
+    match handle(&mut deps, env.clone(), bounded_msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Mint amount is less than the minimum; amount: 1000000, min amount: 1000001"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     // 1- As the last place to modify the state is compute_interest, a check on the state ensures the invocation of compute_interest.
     // However, because passed_blocks = 0, interest factor & interest accrued are also 0, and thus the values do not change
@@ -509,6 +916,10 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(1000000u64),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 
@@ -548,11 +959,15 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
 
-    let res = handle(&mut deps, env, msg.clone()).unwrap();
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.log,
         vec![
@@ -576,6 +991,56 @@ fn deposit_stable() {
         })]
     );
 
+    // deposit on behalf of another address at the same exchange rate
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(50000u128),
+            total_reserves: Decimal256::from_uint256(550000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let for_msg = HandleMsg::DepositStableFor {
+        recipient: HumanAddr::from("addr0001"),
+        min_mint_amount: None,
+    };
+    let res = handle(&mut deps, env.clone(), for_msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit_stable_for"),
+            log("depositor", "addr0000"),
+            log("recipient", "addr0001"),
+            log("mint_amount", "2000000"),
+            log("deposit_amount", "1000000"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("AT-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128::from(2000000u128),
+            })
+            .unwrap(),
+        })]
+    );
+
     // Case: compute_interest & compute_reward with block increment
     let mut env = mock_env(
         "addr0000",
@@ -597,6 +1062,10 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
@@ -624,6 +1093,10 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
             prev_exchange_rate: Decimal256::from_ratio(55u64, 100u64),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 }
@@ -674,7 +1147,9 @@ fn redeem_stable() {
     let _res = handle(&mut deps, env, msg).unwrap();
 
     // Deposit 1000000
-    let msg = HandleMsg::DepositStable {};
+    let msg = HandleMsg::DepositStable {
+        min_mint_amount: None,
+    };
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -714,7 +1189,12 @@ fn redeem_stable() {
     let msg = HandleMsg::Receive(Cw20ReceiveMsg {
         sender: HumanAddr::from("addr0000"),
         amount: Uint128::from(1000000u128),
-        msg: Some(to_binary(&Cw20HookMsg::RedeemStable {}).unwrap()),
+        msg: Some(
+            to_binary(&Cw20HookMsg::RedeemStable {
+                min_redeem_amount: None,
+            })
+            .unwrap(),
+        ),
     });
     let env = mock_env("addr0000", &[]);
     let res = handle(&mut deps, env, msg.clone());
@@ -724,6 +1204,24 @@ fn redeem_stable() {
     }
 
     let env = mock_env("AT-uusd", &[]);
+    let bounded_msg = HandleMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(
+            to_binary(&Cw20HookMsg::RedeemStable {
+                min_redeem_amount: Some(Uint256::from(1000001u64)),
+            })
+            .unwrap(),
+        ),
+    });
+    match handle(&mut deps, env.clone(), bounded_msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Redeem amount is less than the minimum; amount: 1000000, min amount: 1000001"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
@@ -764,6 +1262,10 @@ fn redeem_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
@@ -885,6 +1387,10 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
@@ -944,6 +1450,10 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 
@@ -969,6 +1479,10 @@ fn borrow_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         }
     );
 
@@ -1050,10 +1564,26 @@ fn borrow_stable() {
         ),
         _ => panic!("DO NOT ENTER HERE"),
     }
+
+    // Cannot push the total liabilities over the debt ceiling
+    deps.querier
+        .with_debt_ceiling(Some(Uint256::from(2500000u64)));
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(1u64),
+        to: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Borrow amount too high; Total liabilities become greater than debt ceiling: 2500000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
 
 #[test]
-fn assert_max_borrow_factor() {
+fn accrual_freeze() {
     let mut deps = mock_dependencies(
         20,
         &[Coin {
@@ -1061,19 +1591,14 @@ fn assert_max_borrow_factor() {
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InitMsg {
         owner_addr: HumanAddr::from("owner"),
         stable_denom: "uusd".to_string(),
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::percent(1),
+        max_borrow_factor: Decimal256::one(),
     };
-
     let env = mock_env(
         "addr0000",
         &[Coin {
@@ -1081,15 +1606,14 @@ fn assert_max_borrow_factor() {
             amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
+    let _res = init(&mut deps, env, msg).unwrap();
+    let _res = handle(
+        &mut deps,
+        mock_env("AT-uusd", &[]),
+        HandleMsg::RegisterATerra {},
+    )
+    .unwrap();
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = init(&mut deps, env.clone(), msg).unwrap();
-    // Register anchor token contract
-    let msg = HandleMsg::RegisterATerra {};
-    let env = mock_env("AT-uusd", &[]);
-    let _res = handle(&mut deps, env, msg).unwrap();
-
-    // Register overseer contract
     let msg = HandleMsg::RegisterContracts {
         overseer_contract: HumanAddr::from("overseer"),
         interest_model: HumanAddr::from("interest"),
@@ -1097,18 +1621,16 @@ fn assert_max_borrow_factor() {
         collector_contract: HumanAddr::from("collector"),
         distributor_contract: HumanAddr::from("distributor"),
     };
-    let env = mock_env("addr0000", &[]);
+    let mut env = mock_env("addr0000", &[]);
     let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
     deps.querier
         .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
 
     store_state(
         &mut deps.storage,
         &State {
-            total_liabilities: Decimal256::zero(),
+            total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height,
@@ -1117,12 +1639,189 @@ fn assert_max_borrow_factor() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
-
-    let msg = HandleMsg::BorrowStable {
-        borrow_amount: Uint256::from(10000u64),
+    store_borrower_info(
+        &mut deps.storage,
+        &deps
+            .api
+            .canonical_address(&HumanAddr::from("addr0000"))
+            .unwrap(),
+        &BorrowerInfo {
+            interest_index: Decimal256::one(),
+            reward_index: Decimal256::zero(),
+            loan_amount: Uint256::from(1000000u64),
+            pending_rewards: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::SetAccrualFrozen {
+        borrower: HumanAddr::from("addr0000"),
+        frozen: true,
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut overseer_env = mock_env("overseer", &[]);
+    let _res = handle(&mut deps, overseer_env.clone(), msg).unwrap();
+
+    // interest_factor = 1% * 100blocks = 1, but the loan stays
+    env.block.height += 100;
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerInfo {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: Some(env.block.height),
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(1000000u64));
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(1u64),
+        to: None,
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Interest accrual is frozen for the borrower")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the skipped interest leaves the total liabilities
+    overseer_env.block.height += 100;
+    let msg = HandleMsg::SetAccrualFrozen {
+        borrower: HumanAddr::from("addr0000"),
+        frozen: false,
+    };
+    let _res = handle(&mut deps, overseer_env.clone(), msg).unwrap();
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1000000u64)
+    );
+    assert_eq!(state.global_interest_index, Decimal256::from_uint256(2u64));
+
+    let res = query(
+        &deps,
+        QueryMsg::AccrualFreeze {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let freeze_res: AccrualFreezeResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        freeze_res,
+        AccrualFreezeResponse {
+            borrower: HumanAddr::from("addr0000"),
+            frozen_at: None,
+            forgiven_interest: Decimal256::zero(),
+            windows: vec![FrozenWindow {
+                start: overseer_env.block.height - 100,
+                end: overseer_env.block.height,
+                forgiven_interest: Decimal256::from_uint256(1000000u64),
+            }],
+        }
+    );
+
+    // accrual resumes from the unfreeze
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerInfo {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: Some(env.block.height + 100),
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(2000000u64));
+}
+
+#[test]
+fn assert_max_borrow_factor() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::percent(1),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(10000u64),
         to: None,
     };
 
@@ -1224,6 +1923,10 @@ fn repay_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
@@ -1288,7 +1991,7 @@ fn repay_stable() {
     //Loan amount and Total liability have decreased according to the repayment
     let res_loan = read_borrower_infos(&deps, None, None)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::from(400000u128));
@@ -1314,7 +2017,7 @@ fn repay_stable() {
     //Loan amount and Total liability have decreased according to the repayment
     let res_loan = read_borrower_infos(&deps, None, None)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::zero());
@@ -1338,21 +2041,70 @@ fn repay_stable() {
             .unwrap()]
         })]
     );
+
+    // repay on behalf of the borrower; the excess goes back to the payer
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+    };
+    env.message.sent_funds = vec![];
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RepayStableFor {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let mut env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(150000u128),
+        }],
+    );
+    env.block.height += 100;
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "repay_stable_for"),
+            log("payer", "addr0001"),
+            log("borrower", "addr0000"),
+            log("repay_amount", "100000"),
+        ]
+    );
+
+    let res_loan = read_borrower_infos(&deps, None, None)
+        .unwrap()
+        .first()
+        .unwrap()
+        .loan_amount;
+    assert_eq!(res_loan, Uint256::zero());
+
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0001"),
+            amount: vec![deduct_tax(
+                &deps,
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(50000u128),
+                }
+            )
+            .unwrap()]
+        })]
+    );
 }
 
 #[test]
-fn repay_stable_from_liquidation() {
+fn projected_interest() {
     let mut deps = mock_dependencies(
         20,
         &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InitMsg {
         owner_addr: HumanAddr::from("owner"),
@@ -1369,15 +2121,11 @@ fn repay_stable_from_liquidation() {
             amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
+    let _res = init(&mut deps, env, msg).unwrap();
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = init(&mut deps, env.clone(), msg).unwrap();
-    // Register anchor token contract
     let msg = HandleMsg::RegisterATerra {};
-    let env = mock_env("AT-uusd", &[]);
-    let _res = handle(&mut deps, env, msg).unwrap();
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
 
-    // Register overseer contract
     let msg = HandleMsg::RegisterContracts {
         overseer_contract: HumanAddr::from("overseer"),
         interest_model: HumanAddr::from("interest"),
@@ -1405,122 +2153,72 @@ fn repay_stable_from_liquidation() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
 
+    // global interest index becomes 2 at the borrow
+    env.block.height += 100;
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
     };
-
-    env.block.height += 100;
     let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
-    // update balance to make repay
-    deps.querier.update_balance(
-        HumanAddr::from(MOCK_CONTRACT_ADDR),
-        vec![
-            Coin {
-                denom: "ukrw".to_string(),
-                amount: Uint128(100000u128),
-            },
-            Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
-            },
-        ],
+    let res = query(
+        &deps,
+        QueryMsg::StateProjected {
+            block_height: env.block.height - 1,
+        },
     );
-
-    let msg = HandleMsg::RepayStableFromLiquidation {
-        borrower: HumanAddr::from("addr0000"),
-        prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
-    };
-
-    let res = handle(&mut deps, env.clone(), msg.clone());
-    match res {
-        Err(StdError::Unauthorized { .. }) => {}
-        _ => panic!("DO NOT ENTER HERE"),
-    }
-
-    let height = env.block.height;
-    let mut env = mock_env("overseer", &[]);
-    env.block.height = height;
-
-    let res = handle(&mut deps, env.clone(), msg.clone());
     match res {
         Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Repay amount must be greater than 0 uusd")
+            assert_eq!(msg, "block_height must bigger than last_interest_updated")
         }
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    // update balance to make repay
-    deps.querier.update_balance(
-        HumanAddr::from(MOCK_CONTRACT_ADDR),
-        vec![Coin {
+    // another 100 blocks at 1% doubles the index again
+    let res = query(
+        &deps,
+        QueryMsg::StateProjected {
+            block_height: env.block.height + 100,
+        },
+    )
+    .unwrap();
+    let state_res: StateProjectedResponse = from_binary(&res).unwrap();
+    assert_eq!(state_res.global_interest_index, Decimal256::percent(400));
+
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerInfoProjected {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height + 100,
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerInfoProjectedResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_res.block_height, env.block.height + 100);
+    assert_eq!(borrower_res.loan_amount, Uint256::from(1000000u64));
+    assert_eq!(borrower_res.accrued_interest, Uint256::from(500000u64));
+}
+
+#[test]
+fn repay_stable_from_liquidation() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
-    );
-
-    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
-    assert_eq!(
-        res.log,
-        vec![
-            log("action", "repay_stable"),
-            log("borrower", "addr0000"),
-            log("repay_amount", "100000"),
-        ]
-    );
-
-    deps.querier.update_balance(
-        HumanAddr::from(MOCK_CONTRACT_ADDR),
-        vec![Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128(INITIAL_DEPOSIT_AMOUNT + 500000u128),
-        }],
-    );
-
-    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
-    assert_eq!(
-        res.log,
-        vec![
-            log("action", "repay_stable"),
-            log("borrower", "addr0000"),
-            log("repay_amount", "400000"),
-        ]
-    );
-
-    assert_eq!(
-        res.messages,
-        vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
-            to_address: HumanAddr::from("addr0000"),
-            amount: vec![deduct_tax(
-                &deps,
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
-        })]
-    );
-}
-
-#[test]
-fn claim_rewards() {
-    let mut deps = mock_dependencies(
-        20,
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
 
     let msg = InitMsg {
@@ -1541,7 +2239,6 @@ fn claim_rewards() {
 
     // we can just call .unwrap() to assert this was a success
     let _res = init(&mut deps, env.clone(), msg).unwrap();
-
     // Register anchor token contract
     let msg = HandleMsg::RegisterATerra {};
     let env = mock_env("AT-uusd", &[]);
@@ -1575,67 +2272,116 @@ fn claim_rewards() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
 
-    // zero loan claim, will return empty messages
-    let msg = HandleMsg::ClaimRewards { to: None };
-    let res = handle(&mut deps, env.clone(), msg).unwrap();
-    assert_eq!(res.messages.len(), 0);
-
     let msg = HandleMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
     };
+
+    env.block.height += 100;
     let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
-    // zero block passed
-    let msg = HandleMsg::ClaimRewards {
-        to: Some(HumanAddr::from("addr0001")),
+    // update balance to make repay
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128(100000u128),
+            },
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+            },
+        ],
+    );
+
+    let msg = HandleMsg::RepayStableFromLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+        prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
     };
-    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
-    assert_eq!(res.messages.len(), 0);
 
-    // 100 blocks passed
-    env.block.height += 100;
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let height = env.block.height;
+    let mut env = mock_env("overseer", &[]);
+    env.block.height = height;
+
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Repay amount must be greater than 0 uusd")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // update balance to make repay
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+
     let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
-        res.messages,
-        vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: HumanAddr::from("distributor"),
-            send: vec![],
-            msg: to_binary(&FaucetHandleMsg::Spend {
-                recipient: HumanAddr::from("addr0001"),
-                amount: Uint128(33u128),
-            })
-            .unwrap(),
-        })]
+        res.log,
+        vec![
+            log("action", "repay_stable"),
+            log("borrower", "addr0000"),
+            log("repay_amount", "100000"),
+        ]
     );
 
-    let res: BorrowerInfoResponse = from_binary(
-        &query(
-            &deps,
-            QueryMsg::BorrowerInfo {
-                borrower: HumanAddr::from("addr0000"),
-                block_height: None,
-            },
-        )
-        .unwrap(),
-    )
-    .unwrap();
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+        }],
+    );
+
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
-        res.pending_rewards,
-        Decimal256::from_str("0.333333333333").unwrap()
+        res.log,
+        vec![
+            log("action", "repay_stable"),
+            log("borrower", "addr0000"),
+            log("repay_amount", "400000"),
+        ]
     );
+
     assert_eq!(
-        res.reward_index,
-        Decimal256::from_str("0.000066666666666666").unwrap()
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![deduct_tax(
+                &deps,
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }
+            )
+            .unwrap()]
+        })]
     );
 }
 
 #[test]
-fn execute_epoch_operations() {
+fn flash_borrow() {
     let mut deps = mock_dependencies(
         20,
         &[Coin {
@@ -1643,10 +2389,6 @@ fn execute_epoch_operations() {
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InitMsg {
         owner_addr: HumanAddr::from("owner"),
@@ -1663,16 +2405,11 @@ fn execute_epoch_operations() {
             amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
+    let _res = init(&mut deps, env, msg).unwrap();
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = init(&mut deps, env.clone(), msg).unwrap();
-
-    // Register anchor token contract
     let msg = HandleMsg::RegisterATerra {};
-    let env = mock_env("AT-uusd", &[]);
-    let _res = handle(&mut deps, env, msg).unwrap();
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
 
-    // Register overseer contract
     let msg = HandleMsg::RegisterContracts {
         overseer_contract: HumanAddr::from("overseer"),
         interest_model: HumanAddr::from("interest"),
@@ -1680,93 +2417,321 @@ fn execute_epoch_operations() {
         collector_contract: HumanAddr::from("collector"),
         distributor_contract: HumanAddr::from("distributor"),
     };
-    let mut env = mock_env("addr0000", &[]);
-    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
 
     deps.querier
         .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
-
-    store_state(
-        &mut deps.storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
-    )
-    .unwrap();
-
-    env.block.height += 100;
 
-    // reserve == 3000
-    let msg = HandleMsg::ExecuteEpochOperations {
-        deposit_rate: Decimal256::one(),
-        target_deposit_rate: Decimal256::one(),
-        threshold_deposit_rate: Decimal256::one(),
-        distributed_interest: Uint256::zero(),
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        guardian: None,
+        flash_fee: Some(Decimal256::permille(1)),
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: None,
+        utilization_half_life: None,
+        lockup_terms: None,
+        early_withdrawal_penalty: None,
     };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
 
-    // only overseer can execute this
-    let res = handle(&mut deps, env.clone(), msg.clone());
-    match res {
-        Err(StdError::Unauthorized { .. }) => {}
+    let msg = HandleMsg::FlashBorrow {
+        amount: Uint256::from(1000001u64),
+        msg: Binary::from(b"callback".to_vec()),
+    };
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Not enough stable available; available: 1000000")
+        }
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    env.message.sender = HumanAddr::from("overseer");
-    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    let msg = HandleMsg::FlashBorrow {
+        amount: Uint256::from(500000u64),
+        msg: Binary::from(b"callback".to_vec()),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address,
-            to_address: HumanAddr::from("collector"),
-            amount: vec![Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(2970u128), // 1% tax
-            }],
-        })]
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("addr0000"),
+                send: vec![],
+                msg: Binary::from(b"callback".to_vec()),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&HandleMsg::AssertFlashRepayment {}).unwrap(),
+            }),
+        ]
     );
 
-    let state = read_state(&deps.storage).unwrap();
-    assert_eq!(
-        state,
-        State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            anc_emission_rate: Decimal256::from_uint256(5u64),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
+    // balance based operations wait for the repayment
+    let env = mock_env(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100u128),
+        }],
     );
+    match handle(
+        &mut deps,
+        env,
+        HandleMsg::DepositStable {
+            min_mint_amount: None,
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Flash loan in progress"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the callback cannot repay a loan with the flash borrowed funds
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500000u128),
+        }],
+    );
+    for msg in [
+        HandleMsg::RepayStable {},
+        HandleMsg::RepayStableFor {
+            borrower: HumanAddr::from("addr0001"),
+        },
+        HandleMsg::BorrowStable {
+            borrow_amount: Uint256::from(100u64),
+            to: None,
+        },
+    ] {
+        match handle(&mut deps, env.clone(), msg) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Flash loan in progress"),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    let msg = HandleMsg::RepayStableFromLiquidation {
+        borrower: HumanAddr::from("addr0001"),
+        prev_balance: Uint256::from(500000u64),
+    };
+    match handle(&mut deps, mock_env("overseer", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Flash loan in progress"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::AssertFlashRepayment {};
+    match handle(&mut deps, mock_env("addr0000", &[]), msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // only the principal came back
+    match handle(&mut deps, mock_env(MOCK_CONTRACT_ADDR, &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Flash loan not repaid; required balance: 1000500")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    // When there is not enough balance to cover reserve
-    // no message will be sent and reserve will be left as same
     deps.querier.update_balance(
         HumanAddr::from(MOCK_CONTRACT_ADDR),
         vec![Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(2999u128),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500u128),
         }],
     );
+    let res = handle(&mut deps, mock_env(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "flash_repay"),
+            log("borrower", "addr0000"),
+            log("amount", 500000),
+            log("fee", 500),
+        ]
+    );
+}
+
+#[test]
+fn raw_state() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::RawState {
+            namespace: "config".to_string(),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let raw_res: RawStateResponse = from_binary(&res).unwrap();
+    assert_eq!(raw_res.entries.len(), 1);
+    let config: Config = from_slice(raw_res.entries[0].value.as_slice()).unwrap();
+    assert_eq!(config, read_config(&deps.storage).unwrap());
+
+    let borrower_info = BorrowerInfo {
+        interest_index: Decimal256::one(),
+        reward_index: Decimal256::zero(),
+        loan_amount: Uint256::from(100u64),
+        pending_rewards: Decimal256::zero(),
+    };
+    let borrower0 = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
+    let borrower1 = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0001"))
+        .unwrap();
+    store_borrower_info(&mut deps.storage, &borrower0, &borrower_info).unwrap();
+    store_borrower_info(&mut deps.storage, &borrower1, &borrower_info).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::RawState {
+            namespace: "liability".to_string(),
+            start_after: None,
+            limit: Some(1u32),
+        },
+    )
+    .unwrap();
+    let first_page: RawStateResponse = from_binary(&res).unwrap();
+    assert_eq!(first_page.entries.len(), 1);
+    let info: BorrowerInfo = from_slice(first_page.entries[0].value.as_slice()).unwrap();
+    assert_eq!(info, borrower_info);
+
+    let res = query(
+        &deps,
+        QueryMsg::RawState {
+            namespace: "liability".to_string(),
+            start_after: Some(first_page.entries[0].key.clone()),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let second_page: RawStateResponse = from_binary(&res).unwrap();
+    assert_eq!(second_page.entries.len(), 1);
+
+    let mut keys = vec![
+        first_page.entries[0].key.clone(),
+        second_page.entries[0].key.clone(),
+    ];
+    keys.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    let mut borrowers = vec![
+        Binary::from(borrower0.as_slice()),
+        Binary::from(borrower1.as_slice()),
+    ];
+    borrowers.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    assert_eq!(keys, borrowers);
+
+    let res = query(
+        &deps,
+        QueryMsg::RawState {
+            namespace: "flash_loan".to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Namespace is not exportable: flash_loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn claim_rewards() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
 
-    let mut env = mock_env("overseer", &[]);
     store_state(
         &mut deps.storage,
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
+            total_reserves: Decimal256::zero(),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height,
             global_interest_index: Decimal256::one(),
@@ -1774,127 +2739,1047 @@ fn execute_epoch_operations() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
         },
     )
     .unwrap();
 
-    env.block.height += 100;
+    // zero loan claim, will return empty messages
+    let msg = HandleMsg::ClaimRewards { to: None };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
 
-    // reserve == 3000
-    let msg = HandleMsg::ExecuteEpochOperations {
-        deposit_rate: Decimal256::one(),
-        target_deposit_rate: Decimal256::one(),
-        threshold_deposit_rate: Decimal256::one(),
-        distributed_interest: Uint256::zero(),
+    let msg = HandleMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
     };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
-    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    // zero block passed
+    let msg = HandleMsg::ClaimRewards {
+        to: Some(HumanAddr::from("addr0001")),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(res.messages.len(), 0);
 
-    let state = read_state(&deps.storage).unwrap();
+    // 100 blocks passed
+    env.block.height += 100;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
-        state,
-        State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            anc_emission_rate: Decimal256::from_uint256(5u64),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("distributor"),
+            send: vec![],
+            msg: to_binary(&FaucetHandleMsg::Spend {
+                recipient: HumanAddr::from("addr0001"),
+                amount: Uint128(33u128),
+            })
+            .unwrap(),
+        })]
     );
-}
-
-// #[test]
-// fn borrow_repay_execute_operations() {
-//     let mut deps = mock_dependencies(
-//         20,
-//         &[Coin {
-//             denom: "uusd".to_string(),
-//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-//         }],
-//     );
 
-//     let msg = InitMsg {
-//         owner_addr: HumanAddr::from("owner"),
-//         stable_denom: "uusd".to_string(),
-//         aterra_code_id: 123u64,
-//         anc_emission_rate: Decimal256::one(),
-//         max_borrow_factor: Decimal256::one(),
-//     };
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pending_rewards,
+        Decimal256::from_str("0.333333333333").unwrap()
+    );
+    assert_eq!(
+        res.reward_index,
+        Decimal256::from_str("0.000066666666666666").unwrap()
+    );
+}
 
-//     let env = mock_env(
-//         "addr0000",
+#[test]
+fn execute_epoch_operations() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // Register anchor token contract
+    let msg = HandleMsg::RegisterATerra {};
+    let env = mock_env("AT-uusd", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    // Register overseer contract
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let mut env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&HumanAddr::from("addr0000"), &Uint256::from(1000000u64))]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    env.block.height += 100;
+
+    // reserve == 3000
+    let msg = HandleMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    // only overseer can execute this
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.message.sender = HumanAddr::from("overseer");
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: HumanAddr::from("collector"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(2970u128), // 1% tax
+            }],
+        })]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state,
+        State {
+            total_liabilities: Decimal256::from_uint256(2000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::from_uint256(2u64),
+            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
+            anc_emission_rate: Decimal256::from_uint256(5u64),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        }
+    );
+
+    // When there is not enough balance to cover reserve
+    // no message will be sent and reserve will be left as same
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2999u128),
+        }],
+    );
+
+    let mut env = mock_env("overseer", &[]);
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    env.block.height += 100;
+
+    // reserve == 3000
+    let msg = HandleMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state,
+        State {
+            total_liabilities: Decimal256::from_uint256(2000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::from_uint256(2u64),
+            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
+            anc_emission_rate: Decimal256::from_uint256(5u64),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        }
+    );
+
+    // both epochs are recorded, before the reserve transfer
+    let res = query(
+        &deps,
+        QueryMsg::RateHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: RateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.snapshots,
+        vec![
+            RateSnapshotResponse {
+                idx: 1,
+                block_height: env.block.height,
+                borrow_rate: Decimal256::percent(1),
+                deposit_rate: Decimal256::one(),
+                utilization_ratio: Decimal256::from_uint256(2000000u128)
+                    / Decimal256::from_uint256(2997000u128),
+                exchange_rate: Decimal256::one(),
+            },
+            RateSnapshotResponse {
+                idx: 2,
+                block_height: env.block.height,
+                borrow_rate: Decimal256::percent(1),
+                deposit_rate: Decimal256::one(),
+                utilization_ratio: Decimal256::from_uint256(2000000u128)
+                    / Decimal256::from_uint256(1999999u128),
+                exchange_rate: Decimal256::one(),
+            },
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::RateHistory {
+            start_after: Some(1u64),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: RateHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.snapshots.len(), 1);
+    assert_eq!(history.snapshots[0].idx, 2);
+}
+
+#[test]
+fn reserve_announcement() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::RegisterATerra {};
+    let _res = handle(&mut deps, mock_env("AT-uusd", &[]), msg).unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+
+    let msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        guardian: Some(HumanAddr::from("guardian")),
+        flash_fee: None,
+        reserve_announcement_threshold: Some(Uint256::from(2000u64)),
+        reserve_announcement_delay: Some(10u64),
+        utilization_half_life: None,
+        lockup_terms: None,
+        early_withdrawal_penalty: None,
+    };
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+    let mut env = mock_env("overseer", &[]);
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+    env.block.height += 100;
+
+    // reserves above the threshold are announced instead of sent
+    let msg = HandleMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        read_state(&deps.storage).unwrap().total_reserves,
+        Decimal256::from_uint256(3000u128)
+    );
+
+    let res = query(&deps, QueryMsg::ReserveAnnouncement {}).unwrap();
+    let announcement_res: ReserveAnnouncementResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        announcement_res,
+        ReserveAnnouncementResponse {
+            amount: Uint256::from(3000u64),
+            announced_at: env.block.height,
+            executable_at: env.block.height + 10,
+        }
+    );
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0000", &[]),
+        HandleMsg::CancelReserveAnnouncement {},
+    );
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = handle(
+        &mut deps,
+        mock_env("guardian", &[]),
+        HandleMsg::CancelReserveAnnouncement {},
+    )
+    .unwrap();
+    match query(&deps, QueryMsg::ReserveAnnouncement {}) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No reserve announcement"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // announced again, then sent once the delay has passed
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    env.block.height += 9;
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    env.block.height += 1;
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address,
+            to_address: HumanAddr::from("collector"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(2970u128), // 1% tax
+            }],
+        })]
+    );
+    assert!(query(&deps, QueryMsg::ReserveAnnouncement {}).is_err());
+}
+
+// #[test]
+// fn borrow_repay_execute_operations() {
+//     let mut deps = mock_dependencies(
+//         20,
+//         &[Coin {
+//             denom: "uusd".to_string(),
+//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+//         }],
+//     );
+
+//     let msg = InitMsg {
+//         owner_addr: HumanAddr::from("owner"),
+//         stable_denom: "uusd".to_string(),
+//         aterra_code_id: 123u64,
+//         anc_emission_rate: Decimal256::one(),
+//         max_borrow_factor: Decimal256::one(),
+//     };
+
+//     let env = mock_env(
+//         "addr0000",
 //         &[Coin {
 //             denom: "uusd".to_string(),
 //             amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
 //         }],
 //     );
 
-//     // we can just call .unwrap() to assert this was a success
-//     let _res = init(&mut deps, env.clone(), msg).unwrap();
-//     // Register anchor token contract
-//     let msg = HandleMsg::RegisterATerra {};
-//     let env = mock_env("AT-uusd", &[]);
-//     let _res = handle(&mut deps, env, msg).unwrap();
+//     // we can just call .unwrap() to assert this was a success
+//     let _res = init(&mut deps, env.clone(), msg).unwrap();
+//     // Register anchor token contract
+//     let msg = HandleMsg::RegisterATerra {};
+//     let env = mock_env("AT-uusd", &[]);
+//     let _res = handle(&mut deps, env, msg).unwrap();
+
+//     // Register overseer contract
+//     let msg = HandleMsg::RegisterContracts {
+//         overseer_contract: HumanAddr::from("overseer"),
+//         interest_model: HumanAddr::from("interest"),
+//         distribution_model: HumanAddr::from("distribution"),
+//         collector_contract: HumanAddr::from("collector"),
+//         distributor_contract: HumanAddr::from("distributor"),
+//     };
+//     let env = mock_env("addr0000", &[]);
+//     let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+//     deps.querier
+//         .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
+//     deps.querier.with_token_balances(&[(
+//         &HumanAddr::from("AT-uusd"),
+//         &[(
+//             &HumanAddr::from(MOCK_CONTRACT_ADDR),
+//             &Uint128::from(1000000u128),
+//         )],
+//     )]);
+//     deps.querier.update_balance(
+//         HumanAddr::from(MOCK_CONTRACT_ADDR),
+//         vec![Coin {
+//             denom: "uusd".to_string(),
+//             amount: Uint128::from(373025692u128),
+//         }],
+//     );
+
+//     let mut env = mock_env("overseer", &[]);
+
+//     store_state(
+//         &mut deps.storage,
+//         &State {
+//             total_liabilities: Decimal256::from_str("8.198749212085782102").unwrap(),
+//             total_reserves: Decimal256::from_str("372025697.802295205294219818").unwrap(),
+//             last_interest_updated: env.block.height,
+//             last_reward_updated: env.block.height,
+//             global_interest_index: Decimal256::from_str("1.000005078160215988").unwrap(),
+//             global_reward_index: Decimal256::from_str("119531.277425251814227128").unwrap(),
+//             anc_emission_rate: Decimal256::from_str("980001.99").unwrap(),
+//             prev_aterra_supply: Uint256::from(1000000001000000u128),
+//             prev_exchange_rate: Decimal256::from_str("1.0000022").unwrap(),
+//         },
+//     )
+//     .unwrap();
+
+//     env.block.height += 100;
+
+//     let msg = HandleMsg::ExecuteEpochOperations {
+//         deposit_rate: Decimal256::one(),
+//         target_deposit_rate: Decimal256::from_str("0.000000040762727704").unwrap(),
+//         threshold_deposit_rate: Decimal256::from_str("0.000000030572045778").unwrap(),
+//     };
+
+//     // only overseer can execute this
+//     let _ = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+// }
+
+#[test]
+fn write_off_bad_debt() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+    let _res = handle(
+        &mut deps,
+        mock_env("AT-uusd", &[]),
+        HandleMsg::RegisterATerra {},
+    )
+    .unwrap();
+
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(2000000u128),
+        )],
+    )]);
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1500000u128),
+            total_reserves: Decimal256::from_uint256(200000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(2000000u64),
+            prev_exchange_rate: Decimal256::from_str("1.15").unwrap(),
+            utilization_ema: Decimal256::zero(),
+            total_locked_principal: Uint256::zero(),
+            total_locked_interest: Uint256::zero(),
+            locked_interest_funded: Uint256::zero(),
+        },
+    )
+    .unwrap();
+    store_borrower_info(
+        &mut deps.storage,
+        &deps
+            .api
+            .canonical_address(&HumanAddr::from("addr0001"))
+            .unwrap(),
+        &BorrowerInfo {
+            interest_index: Decimal256::one(),
+            reward_index: Decimal256::zero(),
+            loan_amount: Uint256::from(1000000u64),
+            pending_rewards: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = HandleMsg::WriteOffBadDebt {
+        borrower: HumanAddr::from("addr0001"),
+    };
+    for sender in ["addr0000", "owner"].iter() {
+        match handle(&mut deps, mock_env(*sender, &[]), msg.clone()) {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // the loan is not bad while collaterals are left
+    deps.querier.with_collaterals(&[(
+        &HumanAddr::from("addr0001"),
+        &vec![(HumanAddr::from("bluna"), Uint256::from(100u64))],
+    )]);
+    match handle(&mut deps, mock_env("overseer", &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Borrower still has collaterals to liquidate")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_collaterals(&[]);
+
+    // the reserves cover 200,000 and depositors the remaining 800,000
+    // exchange_rate = (1,000,000 + 500,000 - 0) / 2,000,000
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "write_off_bad_debt"),
+            log("record_idx", 1),
+            log("borrower", "addr0001"),
+            log("amount", 1000000),
+            log("covered_by_reserves", 200000),
+            log("exchange_rate", "0.75"),
+        ]
+    );
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(500000u64));
+    assert_eq!(state.total_reserves, Decimal256::zero());
+
+    // nothing left to write off
+    let res = handle(&mut deps, mock_env("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "write_off_bad_debt"),
+            log("borrower", "addr0001"),
+            log("amount", 0),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::BadDebtHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: BadDebtHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.records,
+        vec![BadDebtRecordResponse {
+            idx: 1,
+            borrower: HumanAddr::from("addr0001"),
+            amount: Uint256::from(1000000u64),
+            covered_by_reserves: Uint256::from(200000u64),
+            block_height: env.block.height,
+        }]
+    );
+}
 
-//     // Register overseer contract
-//     let msg = HandleMsg::RegisterContracts {
-//         overseer_contract: HumanAddr::from("overseer"),
-//         interest_model: HumanAddr::from("interest"),
-//         distribution_model: HumanAddr::from("distribution"),
-//         collector_contract: HumanAddr::from("collector"),
-//         distributor_contract: HumanAddr::from("distributor"),
-//     };
-//     let env = mock_env("addr0000", &[]);
-//     let _res = handle(&mut deps, env.clone(), msg).unwrap();
+#[test]
+fn locked_deposits() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
 
-//     deps.querier
-//         .with_borrow_rate(&[(&HumanAddr::from("interest"), &Decimal256::percent(1))]);
-//     deps.querier.with_token_balances(&[(
-//         &HumanAddr::from("AT-uusd"),
-//         &[(
-//             &HumanAddr::from(MOCK_CONTRACT_ADDR),
-//             &Uint128::from(1000000u128),
-//         )],
-//     )]);
-//     deps.querier.update_balance(
-//         HumanAddr::from(MOCK_CONTRACT_ADDR),
-//         vec![Coin {
-//             denom: "uusd".to_string(),
-//             amount: Uint128::from(373025692u128),
-//         }],
-//     );
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    let env = mock_env(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = init(&mut deps, env, msg).unwrap();
+    let _res = handle(
+        &mut deps,
+        mock_env("AT-uusd", &[]),
+        HandleMsg::RegisterATerra {},
+    )
+    .unwrap();
 
-//     let mut env = mock_env("overseer", &[]);
+    let msg = HandleMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
 
-//     store_state(
-//         &mut deps.storage,
-//         &State {
-//             total_liabilities: Decimal256::from_str("8.198749212085782102").unwrap(),
-//             total_reserves: Decimal256::from_str("372025697.802295205294219818").unwrap(),
-//             last_interest_updated: env.block.height,
-//             last_reward_updated: env.block.height,
-//             global_interest_index: Decimal256::from_str("1.000005078160215988").unwrap(),
-//             global_reward_index: Decimal256::from_str("119531.277425251814227128").unwrap(),
-//             anc_emission_rate: Decimal256::from_str("980001.99").unwrap(),
-//             prev_aterra_supply: Uint256::from(1000000001000000u128),
-//             prev_exchange_rate: Decimal256::from_str("1.0000022").unwrap(),
-//         },
-//     )
-//     .unwrap();
+    deps.querier.with_token_balances(&[(
+        &HumanAddr::from("AT-uusd"),
+        &[(
+            &HumanAddr::from(MOCK_CONTRACT_ADDR),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
 
-//     env.block.height += 100;
+    // half a year lockup at 10% a year
+    let duration = 15_768_000u64;
+    let mut msg = HandleMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        guardian: None,
+        flash_fee: None,
+        reserve_announcement_threshold: None,
+        reserve_announcement_delay: None,
+        utilization_half_life: None,
+        lockup_terms: Some(vec![LockupTerm {
+            duration,
+            rate: Decimal256::percent(10),
+        }]),
+        early_withdrawal_penalty: Some(Decimal256::percent(101)),
+    };
+    match handle(&mut deps, mock_env("owner", &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Early withdrawal penalty cannot exceed one")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-//     let msg = HandleMsg::ExecuteEpochOperations {
-//         deposit_rate: Decimal256::one(),
-//         target_deposit_rate: Decimal256::from_str("0.000000040762727704").unwrap(),
-//         threshold_deposit_rate: Decimal256::from_str("0.000000030572045778").unwrap(),
-//     };
+    if let HandleMsg::UpdateConfig {
+        ref mut early_withdrawal_penalty,
+        ..
+    } = msg
+    {
+        *early_withdrawal_penalty = Some(Decimal256::percent(2));
+    }
+    let _res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
 
-//     // only overseer can execute this
-//     let _ = handle(&mut deps, env.clone(), msg.clone()).unwrap();
-// }
+    let deposit_env = |sender: &str, amount: u128| {
+        mock_env(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        )
+    };
+
+    match handle(
+        &mut deps,
+        deposit_env("addr0000", 1000000u128),
+        HandleMsg::DepositStableLocked { duration: 86400 },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Unsupported lockup duration: 86400")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // interest = 1,000,000 * 10% * 0.5 year
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+        }],
+    );
+    let env = deposit_env("addr0000", 1000000u128);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::DepositStableLocked { duration },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deposit_stable_locked"),
+            log("depositor", "addr0000"),
+            log("position_id", 1),
+            log("deposit_amount", 1000000),
+            log("interest", 50000),
+            log("maturity", env.block.time + duration),
+        ]
+    );
+
+    // the locked principal does not move the aterra exchange rate
+    let epoch_state_msg = QueryMsg::EpochState {
+        block_height: None,
+        distributed_interest: None,
+    };
+    let res = query(&deps, epoch_state_msg.clone()).unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        epoch_state,
+        EpochStateResponse {
+            exchange_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            unfunded_locked_interest: Uint256::from(50000u64),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::LockedPositions {
+            owner: HumanAddr::from("addr0000"),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let positions: LockedPositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        positions,
+        LockedPositionsResponse {
+            owner: HumanAddr::from("addr0000"),
+            positions: vec![LockedPositionResponse {
+                position_id: 1,
+                principal: Uint256::from(1000000u64),
+                interest: Uint256::from(50000u64),
+                start_time: env.block.time,
+                maturity: env.block.time + duration,
+            }],
+        }
+    );
+
+    // the interest must be funded before the position can be withdrawn
+    let mut maturity_env = mock_env("addr0000", &[]);
+    maturity_env.block.time += duration;
+    let withdraw_msg = HandleMsg::WithdrawLocked { position_id: 1 };
+    match handle(&mut deps, maturity_env.clone(), withdraw_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Interest of the locked position is not funded yet")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match handle(
+        &mut deps,
+        deposit_env("addr0000", 50000u128),
+        HandleMsg::FundLockedInterest {},
+    ) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1050000u128),
+        }],
+    );
+    let res = handle(
+        &mut deps,
+        deposit_env("overseer", 50000u128),
+        HandleMsg::FundLockedInterest {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "fund_locked_interest"),
+            log("funded_amount", 50000),
+        ]
+    );
+    let res = query(&deps, epoch_state_msg.clone()).unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(epoch_state.exchange_rate, Decimal256::one());
+
+    // an early withdrawal forfeits the interest and pays the 2% penalty,
+    // which is left to the aterra holders
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1550000u128),
+        }],
+    );
+    let _res = handle(
+        &mut deps,
+        deposit_env("addr0001", 500000u128),
+        HandleMsg::DepositStableLocked { duration },
+    )
+    .unwrap();
+
+    match handle(&mut deps, mock_env("addr0001", &[]), withdraw_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Locked position not found"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        mock_env("addr0001", &[]),
+        HandleMsg::WithdrawLocked { position_id: 2 },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0001"),
+            amount: vec![deduct_tax(
+                &deps,
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(490000u128),
+                }
+            )
+            .unwrap()],
+        })]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "withdraw_locked"),
+            log("owner", "addr0001"),
+            log("position_id", 2),
+            log("withdraw_amount", 490000),
+            log("penalty", 10000),
+        ]
+    );
+
+    // exchange_rate = (2,060,000 - 1,000,000 - 50,000) / 1,000,000
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1060000u128),
+        }],
+    );
+    let res = query(&deps, epoch_state_msg.clone()).unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        epoch_state.exchange_rate,
+        Decimal256::from_str("1.01").unwrap()
+    );
+
+    // the locked balance exceeding the market funds is reported,
+    // not underflowed
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    match query(&deps, epoch_state_msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Reserves and locked balance exceed the market assets")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match handle(
+        &mut deps,
+        deposit_env("addr0002", 1000000u128),
+        HandleMsg::DepositStable {
+            min_mint_amount: None,
+        },
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Reserves and locked balance exceed the market assets")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        HumanAddr::from(MOCK_CONTRACT_ADDR),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1060000u128),
+        }],
+    );
+
+    // at maturity the principal is paid with the interest
+    let res = handle(&mut deps, maturity_env, withdraw_msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "withdraw_locked"),
+            log("owner", "addr0000"),
+            log("position_id", 1),
+            log("withdraw_amount", 1050000),
+            log("penalty", 0),
+        ]
+    );
+
+    let res = query(&deps, QueryMsg::LockedLiabilities {}).unwrap();
+    let liabilities: LockedLiabilitiesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liabilities,
+        LockedLiabilitiesResponse {
+            total_principal: Uint256::zero(),
+            total_interest: Uint256::zero(),
+            funded_interest: Uint256::zero(),
+            unfunded_interest: Uint256::zero(),
+        }
+    );
+}
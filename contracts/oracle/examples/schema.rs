@@ -4,7 +4,9 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::oracle::{
-    ConfigResponse, HandleMsg, InitMsg, PriceResponse, PricesResponse, QueryMsg,
+    ConfigResponse, ExternalPriceQueryMsg, ExternalPriceResponse, FeederResponse, FeedersResponse,
+    HandleMsg, InitMsg, MigrateMsg, PendingPriceResponse, PriceLimitsResponse, PriceResponse,
+    PriceSourceResponse, PricesResponse, QueryMsg,
 };
 
 fn main() {
@@ -16,7 +18,15 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(FeederResponse), &out_dir);
+    export_schema(&schema_for!(FeedersResponse), &out_dir);
     export_schema(&schema_for!(PriceResponse), &out_dir);
     export_schema(&schema_for!(PricesResponse), &out_dir);
+    export_schema(&schema_for!(PriceSourceResponse), &out_dir);
+    export_schema(&schema_for!(PriceLimitsResponse), &out_dir);
+    export_schema(&schema_for!(PendingPriceResponse), &out_dir);
+    export_schema(&schema_for!(ExternalPriceQueryMsg), &out_dir);
+    export_schema(&schema_for!(ExternalPriceResponse), &out_dir);
 }
@@ -1,18 +1,28 @@
 use crate::state::{
-    read_config, read_feeder, read_price, read_prices, store_config, store_feeder, store_price,
-    Config, PriceInfo,
+    push_price_observation, read_config, read_feeder_price, read_feeders, read_legacy_config,
+    read_legacy_feeders, read_pending_price, read_price, read_price_limits,
+    read_price_observations, read_price_source, read_prices, remove_feeder_price,
+    remove_legacy_feeder, remove_pending_price, remove_price_limits, shift_price_observations,
+    store_config, store_feeder_price, store_feeders, store_pending_price, store_price,
+    store_price_limits, store_price_source, Config, PendingPrice, PriceInfo, PriceLimits,
+    PriceSourceInfo,
 };
 
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{
-    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
-    InitResponse, MigrateResponse, MigrateResult, Querier, StdError, StdResult, Storage,
+    log, to_binary, Api, Binary, CanonicalAddr, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, InitResponse, MigrateResponse, MigrateResult, Querier, QueryRequest, StdError,
+    StdResult, Storage, WasmQuery,
 };
 
+use moneymarket::halt_recovery::{read_halt_recovery, record_halt_recovery};
 use moneymarket::oracle::{
-    ConfigResponse, FeederResponse, HandleMsg, InitMsg, MigrateMsg, PriceResponse, PricesResponse,
-    PricesResponseElem, QueryMsg,
+    ConfigResponse, ExternalPriceQueryMsg, ExternalPriceResponse, FeederResponse, FeedersResponse,
+    HandleMsg, InitMsg, MigrateMsg, PendingPriceResponse, PriceLimitsResponse, PriceMode,
+    PriceResponse, PriceSource, PriceSourceResponse, PricesResponse, PricesResponseElem, QueryMsg,
+    ORACLE_SCHEMA_VERSION,
 };
+use moneymarket::ownership::{claim_ownership, propose_owner, query_pending_owner};
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
@@ -24,6 +34,7 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         &Config {
             owner: deps.api.canonical_address(&msg.owner)?,
             base_asset: msg.base_asset,
+            price_timeframe: msg.price_timeframe,
         },
     )?;
 
@@ -36,30 +47,198 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMsg,
 ) -> HandleResult {
     match msg {
-        HandleMsg::UpdateConfig { owner } => update_config(deps, env, owner),
+        HandleMsg::ProposeOwner {
+            new_owner,
+            expires_in,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_owner(deps, env, &config.owner, new_owner, expires_in)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env),
+        HandleMsg::UpdateConfig { price_timeframe } => update_config(deps, env, price_timeframe),
         HandleMsg::RegisterFeeder { asset, feeder } => register_feeder(deps, env, asset, feeder),
+        HandleMsg::RegisterFeeders { asset, feeders } => {
+            register_feeders(deps, env, asset, feeders)
+        }
+        HandleMsg::RemoveFeeder { asset, feeder } => remove_feeder(deps, env, asset, feeder),
         HandleMsg::FeedPrice { prices } => feed_prices(deps, env, prices),
+        HandleMsg::RecoverFromHalt { halted_at } => recover_from_halt(deps, env, halted_at),
+        HandleMsg::SetPriceSource { asset, source } => set_price_source(deps, env, asset, source),
+        HandleMsg::SetPriceLimits {
+            asset,
+            min_update_interval,
+            max_deviation,
+            confirmation_delay,
+        } => set_price_limits(
+            deps,
+            env,
+            asset,
+            min_update_interval,
+            max_deviation,
+            confirmation_delay,
+        ),
     }
 }
 
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner: Option<HumanAddr>,
+    price_timeframe: Option<u64>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
     if deps.api.canonical_address(&env.message.sender)? != config.owner {
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.canonical_address(&owner)?;
+    if let Some(price_timeframe) = price_timeframe {
+        config.price_timeframe = price_timeframe;
     }
 
     store_config(&mut deps.storage, &config)?;
     Ok(HandleResponse::default())
 }
 
+pub fn recover_from_halt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    halted_at: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let duration = record_halt_recovery(&mut deps.storage, halted_at, env.block.time)?;
+    shift_price_observations(&mut deps.storage, duration)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "recover_from_halt"),
+            log("halted_at", halted_at),
+            log("duration", duration),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_price_source<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: String,
+    source: PriceSource,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let (source_name, source_info) = match source {
+        PriceSource::Feeder => ("feeder", PriceSourceInfo::Feeder),
+        PriceSource::External { contract, id } => (
+            "external",
+            PriceSourceInfo::External {
+                contract: deps.api.canonical_address(&contract)?,
+                id,
+            },
+        ),
+        PriceSource::Both {
+            contract,
+            id,
+            max_divergence,
+        } => (
+            "both",
+            PriceSourceInfo::Both {
+                contract: deps.api.canonical_address(&contract)?,
+                id,
+                max_divergence,
+            },
+        ),
+    };
+
+    store_price_source(&mut deps.storage, &asset, &source_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_price_source"),
+            log("asset", asset),
+            log("source", source_name),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_price_limits<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: String,
+    min_update_interval: u64,
+    max_deviation: Option<Decimal256>,
+    confirmation_delay: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if max_deviation == Some(Decimal256::zero()) {
+        return Err(StdError::generic_err(
+            "Max deviation must be bigger than zero",
+        ));
+    }
+
+    if min_update_interval == 0 && max_deviation.is_none() && confirmation_delay == 0 {
+        remove_price_limits(&mut deps.storage, &asset);
+    } else {
+        store_price_limits(
+            &mut deps.storage,
+            &asset,
+            &PriceLimits {
+                min_update_interval,
+                max_deviation,
+                confirmation_delay,
+            },
+        )?;
+    }
+
+    // a held back price is re-evaluated under the new limits
+    remove_pending_price(&mut deps.storage, &asset);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_price_limits"),
+            log("asset", asset),
+            log("min_update_interval", min_update_interval),
+            log(
+                "max_deviation",
+                max_deviation.map_or("none".to_string(), |v| v.to_string()),
+            ),
+            log("confirmation_delay", confirmation_delay),
+        ],
+        data: None,
+    })
+}
+
+pub fn accept_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    config.owner = claim_ownership(deps, &env)?;
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_ownership"),
+            log("owner", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
 pub fn register_feeder<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -71,13 +250,16 @@ pub fn register_feeder<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    store_feeder(
-        &mut deps.storage,
-        &asset,
-        &deps.api.canonical_address(&feeder)?,
-    )?;
+    let feeder_raw = deps.api.canonical_address(&feeder)?;
+    if let Ok(prev_feeders) = read_feeders(&deps.storage, &asset) {
+        for prev_feeder in prev_feeders.iter() {
+            remove_feeder_price(&mut deps.storage, &asset, prev_feeder);
+        }
+    }
 
-    return Ok(HandleResponse {
+    store_feeders(&mut deps.storage, &asset, &[feeder_raw])?;
+
+    Ok(HandleResponse {
         messages: vec![],
         log: vec![
             log("action", "register_feeder"),
@@ -85,7 +267,77 @@ pub fn register_feeder<S: Storage, A: Api, Q: Querier>(
             log("feeder", feeder),
         ],
         data: None,
-    });
+    })
+}
+
+pub fn register_feeders<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: String,
+    feeders: Vec<HumanAddr>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut cur_feeders = read_feeders(&deps.storage, &asset).unwrap_or_default();
+    for feeder in feeders.iter() {
+        let feeder_raw = deps.api.canonical_address(feeder)?;
+        if cur_feeders.contains(&feeder_raw) {
+            return Err(StdError::generic_err(format!(
+                "Feeder is already registered: {}",
+                feeder
+            )));
+        }
+
+        cur_feeders.push(feeder_raw);
+    }
+
+    store_feeders(&mut deps.storage, &asset, &cur_feeders)?;
+
+    let feeder_logs: Vec<String> = feeders.iter().map(|f| f.to_string()).collect();
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_feeders"),
+            log("asset", asset),
+            log("feeders", feeder_logs.join(",")),
+        ],
+        data: None,
+    })
+}
+
+pub fn remove_feeder<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: String,
+    feeder: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let feeder_raw = deps.api.canonical_address(&feeder)?;
+    let mut feeders = read_feeders(&deps.storage, &asset)?;
+    if !feeders.contains(&feeder_raw) {
+        return Err(StdError::generic_err("Feeder is not registered"));
+    }
+
+    feeders.retain(|f| *f != feeder_raw);
+    store_feeders(&mut deps.storage, &asset, &feeders)?;
+    remove_feeder_price(&mut deps.storage, &asset, &feeder_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "remove_feeder"),
+            log("asset", asset),
+            log("feeder", feeder),
+        ],
+        data: None,
+    })
 }
 
 pub fn feed_prices<S: Storage, A: Api, Q: Querier>(
@@ -93,6 +345,7 @@ pub fn feed_prices<S: Storage, A: Api, Q: Querier>(
     env: Env,
     prices: Vec<(String, Decimal256)>,
 ) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
     let mut logs = vec![log("action", "feed_prices")];
     let sender_raw = deps.api.canonical_address(&env.message.sender)?;
     for price in prices {
@@ -100,22 +353,49 @@ pub fn feed_prices<S: Storage, A: Api, Q: Querier>(
         let price: Decimal256 = price.1;
 
         // Check feeder permission
-        let feeder = read_feeder(&deps.storage, &asset)?;
-        if feeder != sender_raw {
+        let feeders = read_feeders(&deps.storage, &asset)?;
+        if !feeders.contains(&sender_raw) {
             return Err(StdError::unauthorized());
         }
 
         logs.push(log("asset", asset.to_string()));
         logs.push(log("price", price));
 
-        store_price(
+        store_feeder_price(
             &mut deps.storage,
             &asset,
+            &sender_raw,
             &PriceInfo {
                 last_updated_time: env.block.time,
                 price,
             },
         )?;
+
+        // The asset price is the median of the non-stale feeder submissions
+        let mut submissions: Vec<PriceInfo> = vec![];
+        for feeder in feeders.iter() {
+            if let Some(submission) = read_feeder_price(&deps.storage, &asset, feeder)? {
+                submissions.push(submission);
+            }
+        }
+
+        let valid_time = env.block.time.saturating_sub(config.price_timeframe);
+        let price = compute_median(&submissions, valid_time).unwrap_or(price);
+
+        // the submission is kept, but the asset price stays unchanged
+        if let Some(held_back) =
+            check_price_limits(&mut deps.storage, &env, &asset, &sender_raw, price)?
+        {
+            logs.push(log("held_back", held_back));
+            continue;
+        }
+
+        let price_info = PriceInfo {
+            last_updated_time: env.block.time,
+            price,
+        };
+        store_price(&mut deps.storage, &asset, &price_info)?;
+        push_price_observation(&mut deps.storage, &asset, &price_info)?;
     }
 
     Ok(HandleResponse {
@@ -125,17 +405,95 @@ pub fn feed_prices<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Reason the limits of the asset hold back the aggregated price, if
+/// they do. A deviating price is kept pending until it is confirmed
+fn check_price_limits<S: Storage>(
+    storage: &mut S,
+    env: &Env,
+    asset: &str,
+    feeder: &CanonicalAddr,
+    price: Decimal256,
+) -> StdResult<Option<&'static str>> {
+    let limits = match read_price_limits(storage, asset)? {
+        Some(limits) => limits,
+        None => return Ok(None),
+    };
+
+    // the first price has nothing to be compared with
+    let prev_price = match read_price(storage, asset) {
+        Ok(prev_price) => prev_price,
+        Err(_) => return Ok(None),
+    };
+
+    if env.block.time < prev_price.last_updated_time + limits.min_update_interval {
+        return Ok(Some("rate_limited"));
+    }
+
+    let max_deviation = match limits.max_deviation {
+        Some(max_deviation) => max_deviation,
+        None => return Ok(None),
+    };
+
+    if !deviates(prev_price.price, price, max_deviation) {
+        remove_pending_price(storage, asset);
+        return Ok(None);
+    }
+
+    if let Some(pending_price) = read_pending_price(storage, asset)? {
+        if !deviates(pending_price.price, price, max_deviation) {
+            if pending_price.feeder != *feeder
+                || env.block.time >= pending_price.proposed_at + limits.confirmation_delay
+            {
+                remove_pending_price(storage, asset);
+                return Ok(None);
+            }
+
+            return Ok(Some("pending"));
+        }
+    }
+
+    store_pending_price(
+        storage,
+        asset,
+        &PendingPrice {
+            price,
+            feeder: feeder.clone(),
+            proposed_at: env.block.time,
+        },
+    )?;
+
+    Ok(Some("pending"))
+}
+
+fn deviates(reference: Decimal256, price: Decimal256, max_deviation: Decimal256) -> bool {
+    let deviation = if price > reference {
+        price - reference
+    } else {
+        reference - price
+    };
+
+    deviation > reference * max_deviation
+}
+
 pub fn query<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
         QueryMsg::Feeder { asset } => to_binary(&query_feeder(deps, asset)?),
-        QueryMsg::Price { base, quote } => to_binary(&query_price(deps, base, quote)?),
+        QueryMsg::Feeders { asset } => to_binary(&query_feeders(deps, asset)?),
+        QueryMsg::Price { base, quote, mode } => {
+            to_binary(&query_price(deps, base, quote, mode.unwrap_or_default())?)
+        }
         QueryMsg::Prices { start_after, limit } => {
             to_binary(&query_prices(deps, start_after, limit)?)
         }
+        QueryMsg::HaltRecovery {} => to_binary(&read_halt_recovery(&deps.storage)?),
+        QueryMsg::PriceSource { asset } => to_binary(&query_price_source(deps, asset)?),
+        QueryMsg::PriceLimits { asset } => to_binary(&query_price_limits(deps, asset)?),
+        QueryMsg::PendingPrice { asset } => to_binary(&query_pending_price(deps, asset)?),
     }
 }
 
@@ -144,8 +502,10 @@ fn query_config<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<ConfigResponse> {
     let state = read_config(&deps.storage)?;
     let resp = ConfigResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
         owner: deps.api.human_address(&state.owner)?,
         base_asset: state.base_asset,
+        price_timeframe: state.price_timeframe,
     };
 
     Ok(resp)
@@ -155,10 +515,37 @@ fn query_feeder<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     asset: String,
 ) -> StdResult<FeederResponse> {
-    let feeder = read_feeder(&deps.storage, &asset)?;
+    let feeders = read_feeders(&deps.storage, &asset)?;
+    let feeder = match feeders.first() {
+        Some(v) => deps.api.human_address(v)?,
+        None => {
+            return Err(StdError::generic_err(
+                "No feeder data for the specified asset exist",
+            ))
+        }
+    };
+
     let resp = FeederResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
+        asset,
+        feeder,
+    };
+
+    Ok(resp)
+}
+
+fn query_feeders<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: String,
+) -> StdResult<FeedersResponse> {
+    let feeders = read_feeders(&deps.storage, &asset)?;
+    let resp = FeedersResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
         asset,
-        feeder: deps.api.human_address(&feeder)?,
+        feeders: feeders
+            .iter()
+            .map(|feeder| deps.api.human_address(feeder))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
     };
 
     Ok(resp)
@@ -168,6 +555,7 @@ fn query_price<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     base: String,
     quote: String,
+    mode: PriceMode,
 ) -> StdResult<PriceResponse> {
     let config: Config = read_config(&deps.storage)?;
     let quote_price = if config.base_asset == quote {
@@ -176,7 +564,7 @@ fn query_price<S: Storage, A: Api, Q: Querier>(
             last_updated_time: 9999999999,
         }
     } else {
-        read_price(&deps.storage, &quote)?
+        read_asset_price(deps, &quote, &mode)?
     };
 
     let base_price = if config.base_asset == base {
@@ -185,97 +573,425 @@ fn query_price<S: Storage, A: Api, Q: Querier>(
             last_updated_time: 9999999999,
         }
     } else {
-        read_price(&deps.storage, &base)?
+        read_asset_price(deps, &base, &mode)?
     };
 
     Ok(PriceResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
         rate: base_price.price / quote_price.price,
         last_updated_base: base_price.last_updated_time,
         last_updated_quote: quote_price.last_updated_time,
     })
 }
 
-fn query_prices<S: Storage, A: Api, Q: Querier>(
+fn read_asset_price<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
-    start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<PricesResponse> {
-    let prices: Vec<PricesResponseElem> = read_prices(&deps.storage, start_after, limit)?;
-    Ok(PricesResponse { prices })
+    asset: &str,
+    mode: &PriceMode,
+) -> StdResult<PriceInfo> {
+    match mode {
+        PriceMode::Spot => read_spot_price(deps, asset),
+        _ => read_price_by_mode(&deps.storage, asset, mode),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{from_binary, StdError};
-    use std::str::FromStr;
+/// Spot price from the configured source of the asset, falling
+/// back to the fed price when the external feed cannot be used
+fn read_spot_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: &str,
+) -> StdResult<PriceInfo> {
+    let (contract, id, max_divergence) = match read_price_source(&deps.storage, asset)? {
+        PriceSourceInfo::Feeder => return read_price(&deps.storage, asset),
+        PriceSourceInfo::External { contract, id } => (contract, id, None),
+        PriceSourceInfo::Both {
+            contract,
+            id,
+            max_divergence,
+        } => (contract, id, Some(max_divergence)),
+    };
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies(20, &[]);
+    let config: Config = read_config(&deps.storage)?;
+    let fed_price = read_price(&deps.storage, asset).ok();
+    let external_price = query_external_price(deps, &deps.api.human_address(&contract)?, id).ok();
 
-        let msg = InitMsg {
-            owner: HumanAddr("owner0000".to_string()),
-            base_asset: "base0000".to_string(),
-        };
+    match (external_price, fed_price) {
+        (Some(external_price), Some(fed_price)) => {
+            // the external feed is stale when the feeders kept
+            // updating the price long after it
+            if external_price.last_updated_time + config.price_timeframe
+                < fed_price.last_updated_time
+            {
+                return Ok(fed_price);
+            }
 
-        let env = mock_env("addr0000", &[]);
+            if let Some(max_divergence) = max_divergence {
+                let divergence = if external_price.price > fed_price.price {
+                    external_price.price - fed_price.price
+                } else {
+                    fed_price.price - external_price.price
+                };
 
-        // we can just call .unwrap() to assert this was a success
-        let res = init(&mut deps, env, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+                if divergence > fed_price.price * max_divergence {
+                    return Err(StdError::generic_err(format!(
+                        "Price sources diverge for asset: {}",
+                        asset
+                    )));
+                }
+            }
 
-        // it worked, let's query the state
-        let value = query_config(&deps).unwrap();
-        assert_eq!("owner0000", value.owner.as_str());
-        assert_eq!("base0000", &value.base_asset.to_string());
+            Ok(external_price)
+        }
+        // the divergence cannot be checked without a fed price
+        (Some(external_price), None) if max_divergence.is_none() => Ok(external_price),
+        (None, Some(fed_price)) => Ok(fed_price),
+        _ => Err(StdError::generic_err(
+            "No price data for the specified asset exist",
+        )),
     }
+}
 
-    #[test]
-    fn update_config() {
-        let mut deps = mock_dependencies(20, &[]);
+fn query_external_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    contract: &HumanAddr,
+    id: String,
+) -> StdResult<PriceInfo> {
+    let res: ExternalPriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(contract),
+        msg: to_binary(&ExternalPriceQueryMsg::Price { id })?,
+    }))?;
 
-        let msg = InitMsg {
-            owner: HumanAddr("owner0000".to_string()),
-            base_asset: "base0000".to_string(),
-        };
+    Ok(PriceInfo {
+        price: res.price,
+        last_updated_time: res.last_updated_time,
+    })
+}
 
-        let env = mock_env("addr0000", &[]);
-        let _res = init(&mut deps, env, msg).unwrap();
+/// Aggregated modes are evaluated over the window ending at the last
+/// price update, since the current block time is not available in queries
+fn read_price_by_mode<S: Storage>(
+    storage: &S,
+    asset: &str,
+    mode: &PriceMode,
+) -> StdResult<PriceInfo> {
+    let spot_price: PriceInfo = read_price(storage, asset)?;
+    let window = match mode {
+        PriceMode::Spot => return Ok(spot_price),
+        PriceMode::Twap { window } => *window,
+        PriceMode::Median { window } => *window,
+    };
 
-        // update owner
-        let env = mock_env("owner0000", &[]);
-        let msg = HandleMsg::UpdateConfig {
-            owner: Some(HumanAddr("owner0001".to_string())),
-        };
+    let window_end = spot_price.last_updated_time;
+    let window_start = window_end.saturating_sub(window);
+    let observations: Vec<PriceInfo> = read_price_observations(storage, asset)?;
+    let price = match mode {
+        PriceMode::Twap { .. } => compute_twap(&observations, window_start, window_end),
+        _ => compute_median(&observations, window_start),
+    };
 
-        let res = handle(&mut deps, env, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    // fallback to the spot price when there is no observation to aggregate
+    Ok(PriceInfo {
+        price: price.unwrap_or(spot_price.price),
+        last_updated_time: window_end,
+    })
+}
 
-        // it worked, let's query the state
-        let value = query_config(&deps).unwrap();
-        assert_eq!("owner0001", value.owner.as_str());
-        assert_eq!("base0000", &value.base_asset.to_string());
+/// Each observation is weighted by the time it stayed the latest price
+/// within [window_start, window_end]
+fn compute_twap(
+    observations: &[PriceInfo],
+    window_start: u64,
+    window_end: u64,
+) -> Option<Decimal256> {
+    let mut weighted_sum = Decimal256::zero();
+    let mut total_duration: u64 = 0;
+    for (i, observation) in observations.iter().enumerate() {
+        let start = std::cmp::max(observation.last_updated_time, window_start);
+        let end = observations
+            .get(i + 1)
+            .map(|next| next.last_updated_time)
+            .unwrap_or(window_end);
+        if end <= start {
+            continue;
+        }
 
-        // Unauthorized err
-        let env = mock_env("owner0000", &[]);
-        let msg = HandleMsg::UpdateConfig { owner: None };
+        weighted_sum += observation.price * Decimal256::from_uint256(end - start);
+        total_duration += end - start;
+    }
 
-        let res = handle(&mut deps, env, msg);
-        match res {
-            Err(StdError::Unauthorized { .. }) => {}
-            _ => panic!("Must return unauthorized error"),
-        }
+    if total_duration == 0 {
+        return None;
     }
 
-    #[test]
-    fn register_feeder() {
-        let mut deps = mock_dependencies(20, &[]);
+    Some(weighted_sum / Decimal256::from_uint256(total_duration))
+}
 
-        let msg = InitMsg {
+fn compute_median(observations: &[PriceInfo], window_start: u64) -> Option<Decimal256> {
+    let mut prices: Vec<Decimal256> = observations
+        .iter()
+        .filter(|observation| observation.last_updated_time >= window_start)
+        .map(|observation| observation.price)
+        .collect();
+    if prices.is_empty() {
+        return None;
+    }
+
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len().is_multiple_of(2) {
+        Some((prices[mid - 1] + prices[mid]) / Decimal256::from_uint256(2u64))
+    } else {
+        Some(prices[mid])
+    }
+}
+
+fn query_price_source<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: String,
+) -> StdResult<PriceSourceResponse> {
+    let source = match read_price_source(&deps.storage, &asset)? {
+        PriceSourceInfo::Feeder => PriceSource::Feeder,
+        PriceSourceInfo::External { contract, id } => PriceSource::External {
+            contract: deps.api.human_address(&contract)?,
+            id,
+        },
+        PriceSourceInfo::Both {
+            contract,
+            id,
+            max_divergence,
+        } => PriceSource::Both {
+            contract: deps.api.human_address(&contract)?,
+            id,
+            max_divergence,
+        },
+    };
+
+    Ok(PriceSourceResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
+        asset,
+        source,
+    })
+}
+
+fn query_price_limits<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: String,
+) -> StdResult<PriceLimitsResponse> {
+    let limits = read_price_limits(&deps.storage, &asset)?.unwrap_or(PriceLimits {
+        min_update_interval: 0,
+        max_deviation: None,
+        confirmation_delay: 0,
+    });
+
+    Ok(PriceLimitsResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
+        asset,
+        min_update_interval: limits.min_update_interval,
+        max_deviation: limits.max_deviation,
+        confirmation_delay: limits.confirmation_delay,
+    })
+}
+
+fn query_pending_price<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    asset: String,
+) -> StdResult<Option<PendingPriceResponse>> {
+    match read_pending_price(&deps.storage, &asset)? {
+        Some(pending_price) => Ok(Some(PendingPriceResponse {
+            schema_version: ORACLE_SCHEMA_VERSION,
+            asset,
+            price: pending_price.price,
+            feeder: deps.api.human_address(&pending_price.feeder)?,
+            proposed_at: pending_price.proposed_at,
+        })),
+        None => Ok(None),
+    }
+}
+
+fn query_prices<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PricesResponse> {
+    let prices: Vec<PricesResponseElem> = read_prices(&deps.storage, start_after, limit)?;
+    Ok(PricesResponse {
+        schema_version: ORACLE_SCHEMA_VERSION,
+        prices,
+    })
+}
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: MigrateMsg,
+) -> MigrateResult {
+    let legacy_config = read_legacy_config(&deps.storage)?;
+    store_config(
+        &mut deps.storage,
+        &Config {
+            owner: legacy_config.owner,
+            base_asset: legacy_config.base_asset,
+            price_timeframe: msg.price_timeframe,
+        },
+    )?;
+
+    // Move the single feeder of each asset into its feeder set
+    for (asset, feeder) in read_legacy_feeders(&deps.storage)? {
+        store_feeders(&mut deps.storage, &asset, &[feeder])?;
+        remove_legacy_feeder(&mut deps.storage, &asset);
+    }
+
+    Ok(MigrateResponse::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{from_binary, from_slice, Empty, QuerierResult, StdError};
+    use moneymarket::ownership::PendingOwnerResponse;
+    use std::str::FromStr;
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+
+        // we can just call .unwrap() to assert this was a success
+        let res = init(&mut deps, env, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!("owner0000", value.owner.as_str());
+        assert_eq!("base0000", &value.base_asset.to_string());
+        assert_eq!(60u64, value.price_timeframe);
+    }
+
+    #[test]
+    fn update_config() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            price_timeframe: Some(120u64),
+        };
+
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let value = query_config(&deps).unwrap();
+        assert_eq!("owner0000", value.owner.as_str());
+        assert_eq!("base0000", &value.base_asset.to_string());
+        assert_eq!(120u64, value.price_timeframe);
+
+        // Unauthorized err
+        let env = mock_env("addr0000", &[]);
+        let msg = HandleMsg::UpdateConfig {
+            price_timeframe: None,
+        };
+
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::ProposeOwner {
+            new_owner: HumanAddr("owner0001".to_string()),
+            expires_in: 100u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        match handle(&mut deps, env, msg.clone()) {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let env = mock_env("owner0000", &[]);
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let res: PendingOwnerResponse =
+            from_binary(&query(&deps, QueryMsg::PendingOwner {}).unwrap()).unwrap();
+        assert_eq!(
+            res,
+            PendingOwnerResponse {
+                pending_owner: HumanAddr("owner0001".to_string()),
+                expires_at: env.block.time + 100u64,
+            }
+        );
+
+        // only the proposed owner can accept
+        let env = mock_env("addr0000", &[]);
+        match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // proposal expired
+        let mut env = mock_env("owner0001", &[]);
+        env.block.time += 101u64;
+        match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Ownership transfer proposal has expired")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let env = mock_env("owner0001", &[]);
+        handle(&mut deps, env.clone(), HandleMsg::AcceptOwnership {}).unwrap();
+
+        let value = query_config(&deps).unwrap();
+        assert_eq!("owner0001", value.owner.as_str());
+
+        // proposal is consumed
+        match handle(&mut deps, env, HandleMsg::AcceptOwnership {}) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "No pending ownership transfer")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    #[test]
+    fn register_feeder() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
             owner: HumanAddr("owner0000".to_string()),
             base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -308,6 +1024,7 @@ mod tests {
         assert_eq!(
             feeder_res,
             FeederResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
                 asset: "mAAPL".to_string(),
                 feeder: HumanAddr::from("feeder0000"),
             }
@@ -321,6 +1038,7 @@ mod tests {
         let msg = InitMsg {
             owner: HumanAddr("owner0000".to_string()),
             base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
         };
 
         let env = mock_env("addr0000", &[]);
@@ -350,22 +1068,34 @@ mod tests {
             ],
         };
         let _res = handle(&mut deps, env.clone(), msg).unwrap();
-        let value: PriceResponse =
-            query_price(&deps, "mAAPL".to_string(), "base0000".to_string()).unwrap();
+        let value: PriceResponse = query_price(
+            &deps,
+            "mAAPL".to_string(),
+            "base0000".to_string(),
+            PriceMode::Spot,
+        )
+        .unwrap();
         assert_eq!(
             value,
             PriceResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
                 rate: Decimal256::from_str("1.2").unwrap(),
                 last_updated_base: env.block.time,
                 last_updated_quote: 9999999999,
             }
         );
 
-        let value: PriceResponse =
-            query_price(&deps, "mGOGL".to_string(), "mAAPL".to_string()).unwrap();
+        let value: PriceResponse = query_price(
+            &deps,
+            "mGOGL".to_string(),
+            "mAAPL".to_string(),
+            PriceMode::Spot,
+        )
+        .unwrap();
         assert_eq!(
             value,
             PriceResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
                 rate: Decimal256::from_str("1.833333333333333333").unwrap(),
                 last_updated_base: env.block.time,
                 last_updated_quote: env.block.time,
@@ -376,6 +1106,7 @@ mod tests {
         assert_eq!(
             value,
             PricesResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
                 prices: vec![
                     PricesResponseElem {
                         asset: "mAAPL".to_string(),
@@ -403,12 +1134,548 @@ mod tests {
             _ => panic!("Must return unauthorized error"),
         }
     }
-}
 
-pub fn migrate<S: Storage, A: Api, Q: Querier>(
-    _deps: &mut Extern<S, A, Q>,
-    _env: Env,
-    _msg: MigrateMsg,
-) -> MigrateResult {
-    Ok(MigrateResponse::default())
+    fn feed(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        feeder: &str,
+        time: u64,
+        price: &str,
+    ) -> Decimal256 {
+        let mut env = mock_env(feeder, &[]);
+        env.block.time = time;
+        let msg = HandleMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::from_str(price).unwrap())],
+        };
+        handle(deps, env, msg).unwrap();
+        read_price(&deps.storage, "mAAPL").unwrap().price
+    }
+
+    #[test]
+    fn multi_feeder_price() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::RegisterFeeders {
+            asset: "mAAPL".to_string(),
+            feeders: vec![
+                HumanAddr::from("feeder0000"),
+                HumanAddr::from("feeder0001"),
+                HumanAddr::from("feeder0002"),
+            ],
+        };
+        let env = mock_env("owner0000", &[]);
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+        let msg = HandleMsg::RegisterFeeders {
+            asset: "mAAPL".to_string(),
+            feeders: vec![HumanAddr::from("feeder0000")],
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Feeder is already registered: feeder0000")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1000u64, "1.0"),
+            Decimal256::from_str("1.0").unwrap()
+        );
+
+        // (1.0 + 1.2) / 2
+        assert_eq!(
+            feed(&mut deps, "feeder0001", 1000u64, "1.2"),
+            Decimal256::from_str("1.1").unwrap()
+        );
+
+        // outlier does not move the median
+        assert_eq!(
+            feed(&mut deps, "feeder0002", 1010u64, "5.0"),
+            Decimal256::from_str("1.2").unwrap()
+        );
+
+        // feeder0000 and feeder0001 submissions become stale
+        assert_eq!(
+            feed(&mut deps, "feeder0002", 1100u64, "2.0"),
+            Decimal256::from_str("2.0").unwrap()
+        );
+
+        let env = mock_env("owner0000", &[]);
+        let msg = HandleMsg::RemoveFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: HumanAddr::from("feeder0002"),
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let feeders_res: FeedersResponse = from_binary(
+            &query(
+                &deps,
+                QueryMsg::Feeders {
+                    asset: "mAAPL".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            feeders_res,
+            FeedersResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
+                asset: "mAAPL".to_string(),
+                feeders: vec![HumanAddr::from("feeder0000"), HumanAddr::from("feeder0001"),],
+            }
+        );
+
+        let env = mock_env("feeder0002", &[]);
+        let msg = HandleMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::from_str("2.0").unwrap())],
+        };
+        let res = handle(&mut deps, env, msg);
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // removing every feeder leaves no feeder to query
+        for feeder in ["feeder0000", "feeder0001"] {
+            let msg = HandleMsg::RemoveFeeder {
+                asset: "mAAPL".to_string(),
+                feeder: HumanAddr::from(feeder),
+            };
+            let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+        }
+
+        let res = query(
+            &deps,
+            QueryMsg::Feeder {
+                asset: "mAAPL".to_string(),
+            },
+        );
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "No feeder data for the specified asset exist")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        // an empty feeder list left in storage is not indexed either
+        cosmwasm_storage::Bucket::<_, Vec<CanonicalAddr>>::new(b"feeders", &mut deps.storage)
+            .save(b"mAAPL", &vec![])
+            .unwrap();
+        let res = query(
+            &deps,
+            QueryMsg::Feeder {
+                asset: "mAAPL".to_string(),
+            },
+        );
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "No feeder data for the specified asset exist")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    #[test]
+    fn price_limits() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::RegisterFeeders {
+            asset: "mAAPL".to_string(),
+            feeders: vec![HumanAddr::from("feeder0000"), HumanAddr::from("feeder0001")],
+        };
+        let env = mock_env("owner0000", &[]);
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::SetPriceLimits {
+            asset: "mAAPL".to_string(),
+            min_update_interval: 10u64,
+            max_deviation: Some(Decimal256::percent(10)),
+            confirmation_delay: 100u64,
+        };
+        let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+        match res {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let res = handle(
+            &mut deps,
+            mock_env("owner0000", &[]),
+            HandleMsg::SetPriceLimits {
+                asset: "mAAPL".to_string(),
+                min_update_interval: 10u64,
+                max_deviation: Some(Decimal256::zero()),
+                confirmation_delay: 100u64,
+            },
+        );
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Max deviation must be bigger than zero")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+
+        let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+        let limits_res: PriceLimitsResponse = from_binary(
+            &query(
+                &deps,
+                QueryMsg::PriceLimits {
+                    asset: "mAAPL".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            limits_res,
+            PriceLimitsResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
+                asset: "mAAPL".to_string(),
+                min_update_interval: 10u64,
+                max_deviation: Some(Decimal256::percent(10)),
+                confirmation_delay: 100u64,
+            }
+        );
+
+        // the first price is not limited
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1000u64, "2.0"),
+            Decimal256::from_str("2.0").unwrap()
+        );
+
+        // too soon after the last update
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1005u64, "2.1"),
+            Decimal256::from_str("2.0").unwrap()
+        );
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1010u64, "2.1"),
+            Decimal256::from_str("2.1").unwrap()
+        );
+
+        // a deviating price waits for another feeder
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1020u64, "4.0"),
+            Decimal256::from_str("2.1").unwrap()
+        );
+        let pending_res: Option<PendingPriceResponse> = from_binary(
+            &query(
+                &deps,
+                QueryMsg::PendingPrice {
+                    asset: "mAAPL".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            pending_res,
+            Some(PendingPriceResponse {
+                schema_version: ORACLE_SCHEMA_VERSION,
+                asset: "mAAPL".to_string(),
+                price: Decimal256::from_str("4.0").unwrap(),
+                feeder: HumanAddr::from("feeder0000"),
+                proposed_at: 1020u64,
+            })
+        );
+
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1030u64, "4.0"),
+            Decimal256::from_str("2.1").unwrap()
+        );
+        assert_eq!(
+            feed(&mut deps, "feeder0001", 1040u64, "4.0"),
+            Decimal256::from_str("4.0").unwrap()
+        );
+        let pending_res: Option<PendingPriceResponse> = from_binary(
+            &query(
+                &deps,
+                QueryMsg::PendingPrice {
+                    asset: "mAAPL".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(pending_res, None);
+
+        // or for the confirmation delay, once the other submission is stale
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1200u64, "8.0"),
+            Decimal256::from_str("4.0").unwrap()
+        );
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1250u64, "8.0"),
+            Decimal256::from_str("4.0").unwrap()
+        );
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1300u64, "8.0"),
+            Decimal256::from_str("8.0").unwrap()
+        );
+
+        // removing the limits
+        let msg = HandleMsg::SetPriceLimits {
+            asset: "mAAPL".to_string(),
+            min_update_interval: 0u64,
+            max_deviation: None,
+            confirmation_delay: 0u64,
+        };
+        let _res = handle(&mut deps, mock_env("owner0000", &[]), msg).unwrap();
+        assert_eq!(read_price_limits(&deps.storage, "mAAPL").unwrap(), None);
+        assert_eq!(
+            feed(&mut deps, "feeder0000", 1301u64, "16.0"),
+            Decimal256::from_str("16.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn aggregated_price() {
+        let mut deps = mock_dependencies(20, &[]);
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::RegisterFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: HumanAddr::from("feeder0000"),
+        };
+        let env = mock_env("owner0000", &[]);
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let mut env = mock_env("feeder0000", &[]);
+        for (time, price) in [(1000u64, "1.0"), (1060u64, "1.2"), (1090u64, "5.0")].iter() {
+            env.block.time = *time;
+            let msg = HandleMsg::FeedPrice {
+                prices: vec![("mAAPL".to_string(), Decimal256::from_str(price).unwrap())],
+            };
+            let _res = handle(&mut deps, env.clone(), msg).unwrap();
+        }
+
+        let query_mode = |mode: PriceMode| -> Decimal256 {
+            let res = query(
+                &deps,
+                QueryMsg::Price {
+                    base: "mAAPL".to_string(),
+                    quote: "base0000".to_string(),
+                    mode: Some(mode),
+                },
+            )
+            .unwrap();
+            let value: PriceResponse = from_binary(&res).unwrap();
+            assert_eq!(value.last_updated_base, 1090u64);
+            value.rate
+        };
+
+        assert_eq!(
+            query_mode(PriceMode::Spot),
+            Decimal256::from_str("5.0").unwrap()
+        );
+
+        // (1.0 * 60 + 1.2 * 30) / 90
+        assert_eq!(
+            query_mode(PriceMode::Twap { window: 100 }),
+            Decimal256::from_str("1.066666666666666666").unwrap()
+        );
+
+        // only 1.2 was in effect during the last 20 seconds
+        assert_eq!(
+            query_mode(PriceMode::Twap { window: 20 }),
+            Decimal256::from_str("1.2").unwrap()
+        );
+
+        assert_eq!(
+            query_mode(PriceMode::Median { window: 100 }),
+            Decimal256::from_str("1.2").unwrap()
+        );
+
+        // (1.2 + 5.0) / 2
+        assert_eq!(
+            query_mode(PriceMode::Median { window: 40 }),
+            Decimal256::from_str("3.1").unwrap()
+        );
+    }
+
+    /// Serves the price of "AAPL" from the "adapter0000" external feed
+    struct ExternalFeedQuerier {
+        price: Option<ExternalPriceResponse>,
+    }
+
+    impl Querier for ExternalFeedQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                    if contract_addr == HumanAddr::from("adapter0000") =>
+                {
+                    let ExternalPriceQueryMsg::Price { id } = from_binary(&msg).unwrap();
+                    match &self.price {
+                        Some(price) if id == "AAPL" => Ok(to_binary(price)),
+                        _ => Ok(Err(StdError::generic_err("Feed is unavailable"))),
+                    }
+                }
+                _ => panic!("DO NOT ENTER HERE"),
+            }
+        }
+    }
+
+    #[test]
+    fn external_price_source() {
+        let mut deps = Extern {
+            storage: MockStorage::default(),
+            api: MockApi::new(20),
+            querier: ExternalFeedQuerier { price: None },
+        };
+
+        let msg = InitMsg {
+            owner: HumanAddr("owner0000".to_string()),
+            base_asset: "base0000".to_string(),
+            price_timeframe: 60u64,
+        };
+
+        let env = mock_env("addr0000", &[]);
+        let _res = init(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::RegisterFeeder {
+            asset: "mAAPL".to_string(),
+            feeder: HumanAddr::from("feeder0000"),
+        };
+        let env = mock_env("owner0000", &[]);
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let mut env = mock_env("feeder0000", &[]);
+        env.block.time = 1000u64;
+        let msg = HandleMsg::FeedPrice {
+            prices: vec![("mAAPL".to_string(), Decimal256::from_str("1.0").unwrap())],
+        };
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        let msg = HandleMsg::SetPriceSource {
+            asset: "mAAPL".to_string(),
+            source: PriceSource::External {
+                contract: HumanAddr::from("adapter0000"),
+                id: "AAPL".to_string(),
+            },
+        };
+        let env = mock_env("addr0000", &[]);
+        match handle(&mut deps, env, msg.clone()) {
+            Err(StdError::Unauthorized { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let env = mock_env("owner0000", &[]);
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(
+            res.log,
+            vec![
+                log("action", "set_price_source"),
+                log("asset", "mAAPL"),
+                log("source", "external"),
+            ]
+        );
+
+        let res: PriceSourceResponse = from_binary(
+            &query(
+                &deps,
+                QueryMsg::PriceSource {
+                    asset: "mAAPL".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.source,
+            PriceSource::External {
+                contract: HumanAddr::from("adapter0000"),
+                id: "AAPL".to_string(),
+            }
+        );
+
+        let query_spot = |deps: &Extern<MockStorage, MockApi, ExternalFeedQuerier>| {
+            query(
+                deps,
+                QueryMsg::Price {
+                    base: "mAAPL".to_string(),
+                    quote: "base0000".to_string(),
+                    mode: None,
+                },
+            )
+            .and_then(|res| from_binary::<PriceResponse>(&res))
+        };
+
+        deps.querier.price = Some(ExternalPriceResponse {
+            price: Decimal256::from_str("1.05").unwrap(),
+            last_updated_time: 1000u64,
+        });
+        let res = query_spot(&deps).unwrap();
+        assert_eq!(res.rate, Decimal256::from_str("1.05").unwrap());
+
+        // the fed price is used when the feed fails
+        deps.querier.price = None;
+        let res = query_spot(&deps).unwrap();
+        assert_eq!(res.rate, Decimal256::from_str("1.0").unwrap());
+
+        // or when the feeders updated long after the feed
+        deps.querier.price = Some(ExternalPriceResponse {
+            price: Decimal256::from_str("1.05").unwrap(),
+            last_updated_time: 900u64,
+        });
+        let res = query_spot(&deps).unwrap();
+        assert_eq!(res.rate, Decimal256::from_str("1.0").unwrap());
+        assert_eq!(res.last_updated_base, 1000u64);
+
+        let msg = HandleMsg::SetPriceSource {
+            asset: "mAAPL".to_string(),
+            source: PriceSource::Both {
+                contract: HumanAddr::from("adapter0000"),
+                id: "AAPL".to_string(),
+                max_divergence: Decimal256::percent(10),
+            },
+        };
+        let env = mock_env("owner0000", &[]);
+        let _res = handle(&mut deps, env, msg).unwrap();
+
+        deps.querier.price = Some(ExternalPriceResponse {
+            price: Decimal256::from_str("1.05").unwrap(),
+            last_updated_time: 1000u64,
+        });
+        let res = query_spot(&deps).unwrap();
+        assert_eq!(res.rate, Decimal256::from_str("1.05").unwrap());
+
+        deps.querier.price = Some(ExternalPriceResponse {
+            price: Decimal256::from_str("1.2").unwrap(),
+            last_updated_time: 1000u64,
+        });
+        match query_spot(&deps) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Price sources diverge for asset: mAAPL")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
 }
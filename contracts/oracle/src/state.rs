@@ -9,6 +9,15 @@ use moneymarket::oracle::PricesResponseElem;
 
 static PREFIX_PRICE: &[u8] = b"price";
 static PREFIX_FEEDER: &[u8] = b"feeder";
+static PREFIX_FEEDERS: &[u8] = b"feeders";
+static PREFIX_FEEDER_PRICE: &[u8] = b"feeder_price";
+static PREFIX_PRICE_HISTORY: &[u8] = b"price_history";
+static PREFIX_PRICE_SOURCE: &[u8] = b"price_source";
+static PREFIX_PRICE_LIMITS: &[u8] = b"price_limits";
+static PREFIX_PENDING_PRICE: &[u8] = b"pending_price";
+
+/// Number of observations kept per asset for the aggregated price modes
+pub const MAX_PRICE_OBSERVATIONS: usize = 50;
 
 static KEY_CONFIG: &[u8] = b"config";
 
@@ -16,6 +25,17 @@ static KEY_CONFIG: &[u8] = b"config";
 pub struct Config {
     pub owner: CanonicalAddr,
     pub base_asset: String,
+    pub price_timeframe: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: CanonicalAddr,
+    pub base_asset: String,
+}
+
+pub fn read_legacy_config<S: Storage>(storage: &S) -> StdResult<LegacyConfig> {
+    singleton_read(storage, KEY_CONFIG).load()
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, config: &Config) -> StdResult<()> {
@@ -34,7 +54,7 @@ pub struct PriceInfo {
 
 pub fn store_price<S: Storage>(storage: &mut S, asset: &str, price: &PriceInfo) -> StdResult<()> {
     let mut price_bucket: Bucket<S, PriceInfo> = Bucket::new(PREFIX_PRICE, storage);
-    price_bucket.save(asset.as_bytes(), &price)
+    price_bucket.save(asset.as_bytes(), price)
 }
 
 pub fn read_price<S: Storage>(storage: &S, asset: &str) -> StdResult<PriceInfo> {
@@ -48,6 +68,147 @@ pub fn read_price<S: Storage>(storage: &S, asset: &str) -> StdResult<PriceInfo>
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum PriceSourceInfo {
+    Feeder,
+    External {
+        contract: CanonicalAddr,
+        id: String,
+    },
+    Both {
+        contract: CanonicalAddr,
+        id: String,
+        max_divergence: Decimal256,
+    },
+}
+
+pub fn store_price_source<S: Storage>(
+    storage: &mut S,
+    asset: &str,
+    source: &PriceSourceInfo,
+) -> StdResult<()> {
+    let mut source_bucket: Bucket<S, PriceSourceInfo> = Bucket::new(PREFIX_PRICE_SOURCE, storage);
+    match source {
+        PriceSourceInfo::Feeder => {
+            source_bucket.remove(asset.as_bytes());
+            Ok(())
+        }
+        _ => source_bucket.save(asset.as_bytes(), source),
+    }
+}
+
+pub fn read_price_source<S: Storage>(storage: &S, asset: &str) -> StdResult<PriceSourceInfo> {
+    let source_bucket: ReadonlyBucket<S, PriceSourceInfo> =
+        ReadonlyBucket::new(PREFIX_PRICE_SOURCE, storage);
+    Ok(source_bucket
+        .may_load(asset.as_bytes())?
+        .unwrap_or(PriceSourceInfo::Feeder))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceLimits {
+    pub min_update_interval: u64,
+    pub max_deviation: Option<Decimal256>,
+    pub confirmation_delay: u64,
+}
+
+pub fn store_price_limits<S: Storage>(
+    storage: &mut S,
+    asset: &str,
+    limits: &PriceLimits,
+) -> StdResult<()> {
+    let mut limits_bucket: Bucket<S, PriceLimits> = Bucket::new(PREFIX_PRICE_LIMITS, storage);
+    limits_bucket.save(asset.as_bytes(), limits)
+}
+
+pub fn read_price_limits<S: Storage>(storage: &S, asset: &str) -> StdResult<Option<PriceLimits>> {
+    let limits_bucket: ReadonlyBucket<S, PriceLimits> =
+        ReadonlyBucket::new(PREFIX_PRICE_LIMITS, storage);
+    limits_bucket.may_load(asset.as_bytes())
+}
+
+pub fn remove_price_limits<S: Storage>(storage: &mut S, asset: &str) {
+    let mut limits_bucket: Bucket<S, PriceLimits> = Bucket::new(PREFIX_PRICE_LIMITS, storage);
+    limits_bucket.remove(asset.as_bytes());
+}
+
+/// Price update held back for deviating too far from the current price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPrice {
+    pub price: Decimal256,
+    pub feeder: CanonicalAddr,
+    pub proposed_at: u64,
+}
+
+pub fn store_pending_price<S: Storage>(
+    storage: &mut S,
+    asset: &str,
+    pending_price: &PendingPrice,
+) -> StdResult<()> {
+    let mut pending_bucket: Bucket<S, PendingPrice> = Bucket::new(PREFIX_PENDING_PRICE, storage);
+    pending_bucket.save(asset.as_bytes(), pending_price)
+}
+
+pub fn read_pending_price<S: Storage>(storage: &S, asset: &str) -> StdResult<Option<PendingPrice>> {
+    let pending_bucket: ReadonlyBucket<S, PendingPrice> =
+        ReadonlyBucket::new(PREFIX_PENDING_PRICE, storage);
+    pending_bucket.may_load(asset.as_bytes())
+}
+
+pub fn remove_pending_price<S: Storage>(storage: &mut S, asset: &str) {
+    let mut pending_bucket: Bucket<S, PendingPrice> = Bucket::new(PREFIX_PENDING_PRICE, storage);
+    pending_bucket.remove(asset.as_bytes());
+}
+
+/// Ring buffer of the most recent price observations, oldest first
+pub fn push_price_observation<S: Storage>(
+    storage: &mut S,
+    asset: &str,
+    price: &PriceInfo,
+) -> StdResult<()> {
+    let mut history_bucket: Bucket<S, Vec<PriceInfo>> = Bucket::new(PREFIX_PRICE_HISTORY, storage);
+    let mut observations = history_bucket
+        .may_load(asset.as_bytes())?
+        .unwrap_or_default();
+
+    observations.push(price.clone());
+    if observations.len() > MAX_PRICE_OBSERVATIONS {
+        observations.remove(0);
+    }
+
+    history_bucket.save(asset.as_bytes(), &observations)
+}
+
+pub fn read_price_observations<S: Storage>(storage: &S, asset: &str) -> StdResult<Vec<PriceInfo>> {
+    let history_bucket: ReadonlyBucket<S, Vec<PriceInfo>> =
+        ReadonlyBucket::new(PREFIX_PRICE_HISTORY, storage);
+    Ok(history_bucket
+        .may_load(asset.as_bytes())?
+        .unwrap_or_default())
+}
+
+/// Move every observation forward by the duration of a chain halt
+pub fn shift_price_observations<S: Storage>(storage: &mut S, duration: u64) -> StdResult<()> {
+    let histories: Vec<(Vec<u8>, Vec<PriceInfo>)> = {
+        let history_bucket: ReadonlyBucket<S, Vec<PriceInfo>> =
+            ReadonlyBucket::new(PREFIX_PRICE_HISTORY, storage);
+        history_bucket
+            .range(None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Vec<u8>, Vec<PriceInfo>)>>>()?
+    };
+
+    let mut history_bucket: Bucket<S, Vec<PriceInfo>> = Bucket::new(PREFIX_PRICE_HISTORY, storage);
+    for (k, mut observations) in histories {
+        for observation in observations.iter_mut() {
+            observation.last_updated_time += duration;
+        }
+
+        history_bucket.save(&k, &observations)?;
+    }
+
+    Ok(())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -77,18 +238,42 @@ pub fn read_prices<S: Storage>(
         .collect()
 }
 
-pub fn store_feeder<S: Storage>(
+/// Single feeder per asset, only kept to migrate into the feeder sets
+pub fn read_legacy_feeders<S: Storage>(storage: &S) -> StdResult<Vec<(String, CanonicalAddr)>> {
+    let feeder_bucket: ReadonlyBucket<S, CanonicalAddr> =
+        ReadonlyBucket::new(PREFIX_FEEDER, storage);
+    feeder_bucket
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((std::str::from_utf8(&k).unwrap().to_string(), v))
+        })
+        .collect()
+}
+
+pub fn remove_legacy_feeder<S: Storage>(storage: &mut S, asset: &str) {
+    let mut feeder_bucket: Bucket<S, CanonicalAddr> = Bucket::new(PREFIX_FEEDER, storage);
+    feeder_bucket.remove(asset.as_bytes());
+}
+
+pub fn store_feeders<S: Storage>(
     storage: &mut S,
     asset: &str,
-    feeder: &CanonicalAddr,
+    feeders: &[CanonicalAddr],
 ) -> StdResult<()> {
-    let mut price_bucket: Bucket<S, CanonicalAddr> = Bucket::new(PREFIX_FEEDER, storage);
-    price_bucket.save(asset.as_bytes(), &feeder)
+    let mut feeders_bucket: Bucket<S, Vec<CanonicalAddr>> = Bucket::new(PREFIX_FEEDERS, storage);
+    if feeders.is_empty() {
+        feeders_bucket.remove(asset.as_bytes());
+        Ok(())
+    } else {
+        feeders_bucket.save(asset.as_bytes(), &feeders.to_vec())
+    }
 }
 
-pub fn read_feeder<S: Storage>(storage: &S, asset: &str) -> StdResult<CanonicalAddr> {
-    let price_bucket: ReadonlyBucket<S, CanonicalAddr> = ReadonlyBucket::new(PREFIX_FEEDER, storage);
-    let res = price_bucket.load(asset.as_bytes());
+pub fn read_feeders<S: Storage>(storage: &S, asset: &str) -> StdResult<Vec<CanonicalAddr>> {
+    let feeders_bucket: ReadonlyBucket<S, Vec<CanonicalAddr>> =
+        ReadonlyBucket::new(PREFIX_FEEDERS, storage);
+    let res = feeders_bucket.load(asset.as_bytes());
     match res {
         Ok(data) => Ok(data),
         Err(_err) => Err(StdError::generic_err(
@@ -97,6 +282,33 @@ pub fn read_feeder<S: Storage>(storage: &S, asset: &str) -> StdResult<CanonicalA
     }
 }
 
+pub fn store_feeder_price<S: Storage>(
+    storage: &mut S,
+    asset: &str,
+    feeder: &CanonicalAddr,
+    price: &PriceInfo,
+) -> StdResult<()> {
+    let mut feeder_price_bucket: Bucket<S, PriceInfo> =
+        Bucket::multilevel(&[PREFIX_FEEDER_PRICE, asset.as_bytes()], storage);
+    feeder_price_bucket.save(feeder.as_slice(), price)
+}
+
+pub fn remove_feeder_price<S: Storage>(storage: &mut S, asset: &str, feeder: &CanonicalAddr) {
+    let mut feeder_price_bucket: Bucket<S, PriceInfo> =
+        Bucket::multilevel(&[PREFIX_FEEDER_PRICE, asset.as_bytes()], storage);
+    feeder_price_bucket.remove(feeder.as_slice());
+}
+
+pub fn read_feeder_price<S: Storage>(
+    storage: &S,
+    asset: &str,
+    feeder: &CanonicalAddr,
+) -> StdResult<Option<PriceInfo>> {
+    let feeder_price_bucket: ReadonlyBucket<S, PriceInfo> =
+        ReadonlyBucket::multilevel(&[PREFIX_FEEDER_PRICE, asset.as_bytes()], storage);
+    feeder_price_bucket.may_load(feeder.as_slice())
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<String>) -> Option<Vec<u8>> {
     start_after.map(|idx| {
@@ -0,0 +1,117 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage, WasmMsg,
+};
+
+use crate::collateral::compute_borrow_limit;
+use crate::querier::query_accrual_freeze;
+use crate::state::{
+    read_accrual_halt, read_collaterals, read_config, read_whitelist_elem, remove_accrual_halt,
+    store_accrual_halt, Config,
+};
+
+use moneymarket::market::{AccrualFreezeResponse, HandleMsg as MarketHandleMsg};
+use moneymarket::overseer::AccrualHaltResponse;
+use moneymarket::tokens::Tokens;
+
+pub fn set_accrual_halt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collateral_token: HumanAddr,
+    halted: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only halt
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner_addr;
+    if !is_owner && (!halted || Some(sender_raw) != config.guardian) {
+        return Err(StdError::unauthorized());
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    if !halted {
+        remove_accrual_halt(&mut deps.storage, &collateral_token_raw);
+    } else if read_accrual_halt(&deps.storage, &collateral_token_raw)?.is_none() {
+        store_accrual_halt(&mut deps.storage, &collateral_token_raw, env.block.height)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_accrual_halt"),
+            log("collateral_token", collateral_token),
+            log("halted", halted),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_accrual_freeze<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let collaterals: Tokens =
+        read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+
+    // the feed of a halted collateral can be stale,
+    // so the last known prices are used
+    let mut borrow_limit = Uint256::zero();
+    let mut halted_borrow_limit = Uint256::zero();
+    for collateral in collaterals {
+        let is_halted = read_accrual_halt(&deps.storage, &collateral.0)?.is_some();
+        let (collateral_borrow_limit, _) = compute_borrow_limit(deps, &vec![collateral], None)?;
+
+        borrow_limit += collateral_borrow_limit;
+        if is_halted {
+            halted_borrow_limit += collateral_borrow_limit;
+        }
+    }
+
+    let frozen =
+        !halted_borrow_limit.is_zero() && halted_borrow_limit + halted_borrow_limit > borrow_limit;
+    let freeze_res: AccrualFreezeResponse = query_accrual_freeze(deps, &market, &borrower)?;
+    if freeze_res.frozen_at.is_some() == frozen {
+        return Err(StdError::generic_err(
+            "Accrual freeze is already up to date",
+        ));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::SetAccrualFrozen {
+                borrower: borrower.clone(),
+                frozen,
+            })?,
+        })],
+        log: vec![
+            log("action", "update_accrual_freeze"),
+            log("borrower", borrower),
+            log("frozen", frozen),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_accrual_halt<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<AccrualHaltResponse> {
+    let halted_at = read_accrual_halt(
+        &deps.storage,
+        &deps.api.canonical_address(&collateral_token)?,
+    )?;
+
+    Ok(AccrualHaltResponse {
+        collateral_token,
+        halted_at,
+    })
+}
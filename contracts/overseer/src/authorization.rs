@@ -0,0 +1,159 @@
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::collateral::{
+    assert_liquidation_cooldown, compute_liquidation_amount_with_limit, compute_liquidation_limit,
+    compute_release_limit, execute_liquidation,
+};
+use crate::executor::assert_liquidation_executor;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collaterals, read_config, read_liquidation_authorization, read_pause_info,
+    remove_liquidation_authorization, store_liquidation_authorization, Config,
+    LiquidationAuthorization,
+};
+
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::LiquidationAuthorizationResponse;
+use moneymarket::tokens::{Tokens, TokensToHuman};
+
+pub fn authorize_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(StdError::generic_err("Liquidations are paused"));
+    }
+    assert_liquidation_executor(deps, &env.message.sender)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let (borrow_limit, liquidation_limit, collateral_prices) =
+        compute_liquidation_limit(deps, &cur_collaterals, Some(env.block.time))?;
+    let (collaterals, loan_amount) = compute_liquidation_amount_with_limit(
+        deps,
+        env.block.height,
+        &borrower,
+        &cur_collaterals,
+        borrow_limit,
+        liquidation_limit,
+        collateral_prices,
+    )?;
+    assert_liquidation_cooldown(
+        &deps.storage,
+        &borrower_raw,
+        env.block.time,
+        loan_amount,
+        liquidation_limit,
+    )?;
+
+    let config: Config = read_config(&deps.storage)?;
+    let expires_at = env.block.height + config.liquidation_authorization_period;
+    store_liquidation_authorization(
+        &mut deps.storage,
+        &borrower_raw,
+        &LiquidationAuthorization {
+            collaterals,
+            loan_amount,
+            expires_at,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "authorize_liquidation"),
+            log("borrower", borrower),
+            log("loan_amount", loan_amount),
+            log("expires_at", expires_at),
+        ],
+        data: None,
+    })
+}
+
+/// Execute a stored authorization. The authorized amounts are capped
+/// by the current collaterals, and the execution is rejected when the
+/// loan decreased since the authorization was made or the position
+/// got back under its liquidation limit.
+pub fn execute_authorized_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(StdError::generic_err("Liquidations are paused"));
+    }
+    assert_liquidation_executor(deps, &env.message.sender)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let authorization: LiquidationAuthorization =
+        read_liquidation_authorization(&deps.storage, &borrower_raw)?;
+    if env.block.height > authorization.expires_at {
+        return Err(StdError::generic_err(
+            "Liquidation authorization has expired",
+        ));
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrower_info: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+    if borrower_info.loan_amount < authorization.loan_amount {
+        return Err(StdError::generic_err(
+            "Loan has changed since the liquidation was authorized",
+        ));
+    }
+
+    // the borrower may have been liquidated since the authorization
+    let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let (_, liquidation_limit, _) =
+        compute_liquidation_limit(deps, &cur_collaterals, Some(env.block.time))?;
+    if compute_release_limit(deps, &borrower_raw, liquidation_limit)? >= borrower_info.loan_amount {
+        return Err(StdError::generic_err(
+            "Cannot liquidate safely collateralized loan",
+        ));
+    }
+    assert_liquidation_cooldown(
+        &deps.storage,
+        &borrower_raw,
+        env.block.time,
+        borrower_info.loan_amount,
+        liquidation_limit,
+    )?;
+
+    let liquidation_amount: Tokens = authorization
+        .collaterals
+        .into_iter()
+        .map(|(token, amount)| {
+            let available = cur_collaterals
+                .iter()
+                .find(|c| c.0 == token)
+                .map(|c| c.1)
+                .unwrap_or_else(Uint256::zero);
+            (token, std::cmp::min(amount, available))
+        })
+        .filter(|c| !c.1.is_zero())
+        .collect();
+
+    remove_liquidation_authorization(&mut deps.storage, &borrower_raw);
+    execute_liquidation(deps, env, borrower, liquidation_amount)
+}
+
+pub fn query_liquidation_authorization<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<LiquidationAuthorizationResponse> {
+    let authorization: LiquidationAuthorization =
+        read_liquidation_authorization(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    Ok(LiquidationAuthorizationResponse {
+        borrower,
+        collaterals: authorization.collaterals.to_human(deps)?,
+        loan_amount: authorization.loan_amount,
+        expires_at: authorization.expires_at,
+    })
+}
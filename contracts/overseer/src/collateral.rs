@@ -1,21 +1,40 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, CosmosMsg, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
-    StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
 };
 
-use crate::querier::{query_borrower_info, query_liquidation_amount};
+use crate::executor::assert_liquidation_executor;
+use crate::querier::{
+    query_borrower_info, query_custody_borrower, query_liquidation_amount,
+    query_simulate_liquidation,
+};
+use crate::retry::defer_liquidation;
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    decrease_total_collaterals, increase_total_collaterals, read_all_collaterals,
+    read_borrowers_collaterals, read_collateral_priority, read_collaterals,
+    read_collaterals_checkpoint, read_config, read_epoch_checkpoint, read_last_liquidated,
+    read_liquidation_trigger, read_pause_info, read_total_collateral, read_whitelist_elem,
+    remove_failed_liquidation, remove_liquidation_trigger, store_collateral_priority,
+    store_collaterals, store_collaterals_checkpoint, store_last_liquidated,
+    store_liquidation_trigger, Config, WhitelistElem,
 };
 
 use moneymarket::custody::HandleMsg as CustodyHandleMsg;
-use moneymarket::liquidation::LiquidationAmountResponse;
+use moneymarket::custody_multi::custody_msg;
+use moneymarket::decimals::normalize_amount;
+use moneymarket::liquidation::{HandleMsg as LiquidationHandleMsg, LiquidationAmountResponse};
 use moneymarket::market::{BorrowerInfoResponse, HandleMsg as MarketHandleMsg};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
+use moneymarket::overseer::{
+    AccountCollateral, AccountSummaryResponse, AllCollateralsResponse, BorrowCapUsageResponse,
+    BorrowLimitContributionResponse, BorrowLimitResponse, BorrowerHealthResponse, CollateralHealth,
+    CollateralPriorityResponse, CollateralsResponse, EffectiveLtvResponse, IsolationModeResponse,
+    LiquidatablePosition, LiquidatablePositionsResponse, LiquidationTriggerResponse,
+    PositionSnapshotResponse, ProfitableLiquidation, ProfitableLiquidationsResponse,
+};
 use moneymarket::querier::{query_balance, query_price, TimeConstraints};
+use moneymarket::rounding::{self, Rounding};
 use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
@@ -23,31 +42,70 @@ pub fn lock_collateral<S: Storage, A: Api, Q: Querier>(
     env: Env,
     collaterals_human: TokensHuman,
 ) -> HandleResult {
+    if read_pause_info(&deps.storage)?.deposits {
+        return Err(StdError::generic_err("Deposits are paused"));
+    }
+
     let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
     let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
 
-    let collaterals: Tokens = collaterals_human.to_raw(&deps)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
 
     cur_collaterals.add(collaterals.clone());
+    if cur_collaterals.len() > 1 && isolated_collateral(&deps.storage, &cur_collaterals)?.is_some()
+    {
+        return Err(StdError::generic_err(
+            "Isolated collateral cannot be mixed with other collaterals",
+        ));
+    }
+
+    assert_borrow_caps(deps, &collaterals, env.block.time)?;
+
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    store_collaterals_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &cur_collaterals,
+    )?;
+
+    increase_total_collaterals(&mut deps.storage, &collaterals)?;
+
+    // a failed price query must not block adding collaterals,
+    // the trigger is then kept until the next update
+    if read_liquidation_trigger(&deps.storage, &borrower_raw)?.is_some() {
+        let config: Config = read_config(&deps.storage)?;
+        let market = deps.api.human_address(&config.market_contract)?;
+        if let Ok((borrow_limit, _)) =
+            compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time))
+        {
+            let borrow_amount_res: BorrowerInfoResponse =
+                query_borrower_info(deps, &market, &env.message.sender, env.block.height)?;
+            let release_limit = compute_release_limit(deps, &borrower_raw, borrow_limit)?;
+            if borrow_amount_res.loan_amount <= release_limit {
+                remove_liquidation_trigger(&mut deps.storage, &borrower_raw);
+            }
+        }
+    }
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals {
         let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
-            send: vec![],
-            msg: to_binary(&CustodyHandleMsg::LockCollateral {
+        messages.push(custody_msg(
+            deps.api.human_address(&whitelist_elem.custody_contract)?,
+            deps.api.human_address(&collateral.0)?,
+            whitelist_elem.multi_custody,
+            CustodyHandleMsg::LockCollateral {
                 borrower: env.message.sender.clone(),
                 amount: collateral.1,
-            })?,
-        }));
+            },
+        )?);
     }
 
     // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
-        .map(|c| format!("{}{}", c.1, c.0.to_string()))
+        .map(|c| format!("{}{}", c.1, c.0))
         .collect();
 
     Ok(HandleResponse {
@@ -66,13 +124,17 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
     env: Env,
     collaterals_human: TokensHuman,
 ) -> HandleResult {
+    if read_pause_info(&deps.storage)?.withdrawals {
+        return Err(StdError::generic_err("Withdrawals are paused"));
+    }
+
     let config: Config = read_config(&deps.storage)?;
     let market = deps.api.human_address(&config.market_contract)?;
 
     let borrower = env.message.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
-    let collaterals: Tokens = collaterals_human.to_raw(&deps)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps)?;
 
     // Underflow check is done in sub_collateral
     if cur_collaterals.sub(collaterals.clone()).is_err() {
@@ -83,6 +145,7 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
 
     // Compute borrow limit with collaterals except unlock target collaterals
     let (borrow_limit, _) = compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time))?;
+    let borrow_limit = compute_release_limit(deps, &borrower_raw, borrow_limit)?;
     let borrow_amount_res: BorrowerInfoResponse =
         query_borrower_info(deps, &market, &borrower, env.block.height)?;
     if borrow_limit < borrow_amount_res.loan_amount {
@@ -92,25 +155,36 @@ pub fn unlock_collateral<S: Storage, A: Api, Q: Querier>(
         )));
     }
 
+    // the loan is back below the release limit
+    remove_liquidation_trigger(&mut deps.storage, &borrower_raw);
+
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    store_collaterals_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &cur_collaterals,
+    )?;
+    decrease_total_collaterals(&mut deps.storage, &collaterals)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals.clone() {
         let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
-            send: vec![],
-            msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
+        messages.push(custody_msg(
+            deps.api.human_address(&whitelist_elem.custody_contract)?,
+            deps.api.human_address(&collateral.0)?,
+            whitelist_elem.multi_custody,
+            CustodyHandleMsg::UnlockCollateral {
                 borrower: borrower.clone(),
                 amount: collateral.1,
-            })?,
-        }));
+            },
+        )?);
     }
 
     // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
-        .map(|c| format!("{}{}", c.1, c.0.to_string()))
+        .map(|c| format!("{}{}", c.1, c.0))
         .collect();
 
     Ok(HandleResponse {
@@ -129,81 +203,344 @@ pub fn liquidate_collateral<S: Storage, A: Api, Q: Querier>(
     env: Env,
     borrower: HumanAddr,
 ) -> HandleResult {
-    let config: Config = read_config(&deps.storage)?;
-    let market = deps.api.human_address(&config.market_contract)?;
+    if read_pause_info(&deps.storage)?.liquidations {
+        return Err(StdError::generic_err("Liquidations are paused"));
+    }
+    assert_liquidation_executor(deps, &env.message.sender)?;
 
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+
+    // Price failures are transient, so the liquidation is
+    // recorded for a retry instead of being aborted
+    let (borrow_limit, liquidation_limit, collateral_prices) =
+        match compute_liquidation_limit(deps, &cur_collaterals, Some(env.block.time)) {
+            Ok(res) => res,
+            Err(err) => return defer_liquidation(deps, env, borrower, err),
+        };
+
+    let (liquidation_amount, borrow_amount) = compute_liquidation_amount_with_limit(
+        deps,
+        env.block.height,
+        &borrower,
+        &cur_collaterals,
+        borrow_limit,
+        liquidation_limit,
+        collateral_prices,
+    )?;
+
+    assert_liquidation_cooldown(
+        &deps.storage,
+        &borrower_raw,
+        env.block.time,
+        borrow_amount,
+        liquidation_limit,
+    )?;
+
+    remove_failed_liquidation(&mut deps.storage, &borrower_raw);
+    execute_liquidation(deps, env, borrower, liquidation_amount)
+}
+
+/// A partially liquidated loan is left alone for the cooldown,
+/// unless it got past the emergency ratio in the meantime.
+/// Every liquidation entry point goes through this check
+pub(crate) fn assert_liquidation_cooldown<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    block_time: u64,
+    borrow_amount: Uint256,
+    liquidation_limit: Uint256,
+) -> StdResult<()> {
+    let config: Config = read_config(storage)?;
+    if let Some(last_liquidated) = read_last_liquidated(storage, borrower)? {
+        let cooldown_end = last_liquidated + config.liquidation_cooldown;
+        if block_time < cooldown_end
+            && borrow_amount <= liquidation_limit * config.emergency_liquidation_ratio
+        {
+            return Err(StdError::generic_err(format!(
+                "Borrower is in liquidation cooldown until: {}",
+                cooldown_end
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Same as compute_liquidation_amount, with the borrow and
+/// liquidation limits and collateral prices already computed
+pub(crate) fn compute_liquidation_amount_with_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+    borrower: &HumanAddr,
+    cur_collaterals: &Tokens,
+    borrow_limit: Uint256,
+    liquidation_limit: Uint256,
+    collateral_prices: Vec<Decimal256>,
+) -> StdResult<(Tokens, Uint256)> {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
 
-    // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, collateral_prices) =
-        compute_borrow_limit(deps, &cur_collaterals, Some(env.block.time))?;
     let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+        query_borrower_info(deps, &market, borrower, block_height)?;
     let borrow_amount = borrow_amount_res.loan_amount;
 
-    // borrow limit is equal or bigger than loan amount
-    // cannot liquidation collaterals
-    if borrow_limit >= borrow_amount {
+    // a loan in liquidation is checked against the release limit,
+    // and the liquidated amount brings it back below that limit
+    let borrower_raw = deps.api.canonical_address(borrower)?;
+    let borrow_limit = compute_release_limit(deps, &borrower_raw, borrow_limit)?;
+    let liquidation_limit = compute_release_limit(deps, &borrower_raw, liquidation_limit)?;
+
+    // liquidation limit is equal or bigger than loan amount
+    // cannot liquidation collaterals; the liquidated amount
+    // still brings the loan back under the borrow limit
+    if liquidation_limit >= borrow_amount {
         return Err(StdError::generic_err(
             "Cannot liquidate safely collateralized loan",
         ));
     }
 
     let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
-        &deps,
+        deps,
         &deps.api.human_address(&config.liquidation_contract)?,
         borrow_amount,
         borrow_limit,
-        &cur_collaterals.to_human(&deps)?,
-        collateral_prices,
+        &cur_collaterals.to_human(deps)?,
+        collateral_prices.clone(),
     )?;
 
-    let liquidation_amount = liquidation_amount_res.collaterals.to_raw(&deps)?;
+    let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps)?;
+
+    // the isolated collateral backs the whole loan, so it goes first
+    let priority = match isolated_collateral(&deps.storage, cur_collaterals)? {
+        Some(collateral_token) => vec![collateral_token],
+        None => read_collateral_priority(&deps.storage, &borrower_raw),
+    };
+    let liquidation_amount = apply_collateral_priority(
+        &priority,
+        cur_collaterals,
+        &collateral_prices,
+        liquidation_amount,
+        config.priority_tolerance,
+    );
+
+    Ok((liquidation_amount, borrow_amount))
+}
+
+/// Take the liquidated value from the borrower's preferred collaterals
+/// first; the pro-rata amount is kept when the preferred order recovers
+/// less value than the priority tolerance allows
+fn apply_collateral_priority(
+    priority: &[CanonicalAddr],
+    cur_collaterals: &Tokens,
+    collateral_prices: &[Decimal256],
+    liquidation_amount: Tokens,
+    priority_tolerance: Decimal256,
+) -> Tokens {
+    if priority.is_empty() {
+        return liquidation_amount;
+    }
+
+    let target_value = liquidation_amount
+        .iter()
+        .fold(Uint256::zero(), |total, collateral| {
+            let price = cur_collaterals
+                .iter()
+                .position(|c| c.0 == collateral.0)
+                .map(|i| collateral_prices[i])
+                .unwrap_or_else(Decimal256::zero);
+            total + collateral.1 * price
+        });
+
+    // preferred collaterals first, then the others in storage order
+    let mut order: Vec<usize> = priority
+        .iter()
+        .filter_map(|token| cur_collaterals.iter().position(|c| c.0 == *token))
+        .collect();
+    let rest: Vec<usize> = (0..cur_collaterals.len())
+        .filter(|i| !order.contains(i))
+        .collect();
+    order.extend(rest);
+
+    let mut remaining = target_value;
+    let mut prioritized: Tokens = vec![];
+    for i in order {
+        let (collateral_token, amount) = &cur_collaterals[i];
+        let price = collateral_prices[i];
+        if remaining.is_zero() || price == Decimal256::zero() {
+            continue;
+        }
+
+        let amount = if *amount * price > remaining {
+            rounding::div(remaining, price, Rounding::Down)
+        } else {
+            *amount
+        };
+        if amount.is_zero() {
+            continue;
+        }
+
+        remaining = remaining - amount * price;
+        prioritized.push((collateral_token.clone(), amount));
+    }
+
+    let recovered_value = target_value - remaining;
+    if recovered_value < target_value * (Decimal256::one() - priority_tolerance) {
+        return liquidation_amount;
+    }
+
+    prioritized
+}
+
+/// Liquidate the given collaterals and repay the loan with the proceeds
+pub(crate) fn execute_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    liquidation_amount: Tokens,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
 
     // Store left collaterals
     cur_collaterals.sub(liquidation_amount.clone())?;
     store_collaterals(&mut deps.storage, &borrower_raw, &cur_collaterals)?;
+    store_collaterals_checkpoint(
+        &mut deps.storage,
+        &borrower_raw,
+        env.block.height,
+        &cur_collaterals,
+    )?;
+    decrease_total_collaterals(&mut deps.storage, &liquidation_amount)?;
+    if !cur_collaterals.is_empty() {
+        store_last_liquidated(&mut deps.storage, &borrower_raw, env.block.time)?;
+    }
+    if read_liquidation_trigger(&deps.storage, &borrower_raw)?.is_none() {
+        store_liquidation_trigger(&mut deps.storage, &borrower_raw, env.block.height)?;
+    }
 
     let market_contract = deps.api.human_address(&config.market_contract)?;
-    let prev_balance: Uint256 = query_balance(&deps, &market_contract, config.stable_denom)?;
+    let prev_balance: Uint256 = query_balance(deps, &market_contract, config.stable_denom)?;
 
     let liquidation_messages: Vec<CosmosMsg> = liquidation_amount
         .iter()
         .map(|collateral| {
             let whitelist_elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
 
-            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
-                send: vec![],
-                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+            custody_msg(
+                deps.api.human_address(&whitelist_elem.custody_contract)?,
+                deps.api.human_address(&collateral.0)?,
+                whitelist_elem.multi_custody,
+                CustodyHandleMsg::LiquidateCollateral {
                     liquidator: env.message.sender.clone(),
                     borrower: borrower.clone(),
                     amount: collateral.1,
-                })?,
-            }))
+                },
+            )
         })
         .filter(|msg| msg.is_ok())
         .collect::<StdResult<Vec<CosmosMsg>>>()?;
 
-    Ok(HandleResponse {
-        messages: vec![
-            liquidation_messages,
-            vec![CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: market_contract,
+    // Custodies stage the collaterals, which are then sold together
+    // so the market receives a single aggregated repayment
+    let execute_bids_message = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.liquidation_contract)?,
+        send: vec![],
+        msg: to_binary(&LiquidationHandleMsg::ExecuteBids {
+            liquidator: env.message.sender.clone(),
+            collaterals: liquidation_amount
+                .iter()
+                .map(|c| Ok((deps.api.human_address(&c.0)?, c.1)))
+                .collect::<StdResult<Vec<(HumanAddr, Uint256)>>>()?,
+            fee_address: None,
+            repay_address: Some(market_contract.clone()),
+            liquidator_fee_address: Some(env.message.sender.clone()),
+            borrower: Some(borrower.clone()),
+        })?,
+    });
+
+    let mut messages: Vec<CosmosMsg> = [
+        liquidation_messages,
+        vec![
+            execute_bids_message,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: market_contract.clone(),
                 send: vec![],
                 msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
-                    borrower,
+                    borrower: borrower.clone(),
                     prev_balance,
                 })?,
-            })],
-        ]
-        .concat(),
+            }),
+        ],
+    ]
+    .concat();
+
+    // nothing is left to cover the loan the proceeds did not repay
+    if cur_collaterals.is_empty() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market_contract,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::WriteOffBadDebt { borrower })?,
+        }));
+    }
+
+    Ok(HandleResponse {
+        messages,
         log: vec![],
         data: None,
     })
 }
 
+pub fn set_collateral_priority<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    collaterals: Vec<HumanAddr>,
+) -> HandleResult {
+    let mut priority: Vec<CanonicalAddr> = vec![];
+    for collateral_token in collaterals.iter() {
+        let collateral_token_raw = deps.api.canonical_address(collateral_token)?;
+        if priority.contains(&collateral_token_raw) {
+            return Err(StdError::generic_err(format!(
+                "Duplicate collateral in priority: {}",
+                collateral_token
+            )));
+        }
+
+        // only whitelisted collaterals can be prioritized
+        read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+        priority.push(collateral_token_raw);
+    }
+
+    let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
+    store_collateral_priority(&mut deps.storage, &borrower_raw, &priority)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_collateral_priority"),
+            log("borrower", env.message.sender),
+            log("collaterals", collaterals.len()),
+        ],
+        data: None,
+    })
+}
+
+pub fn query_collateral_priority<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<CollateralPriorityResponse> {
+    let priority = read_collateral_priority(&deps.storage, &deps.api.canonical_address(&borrower)?);
+
+    Ok(CollateralPriorityResponse {
+        borrower,
+        collaterals: priority
+            .iter()
+            .map(|c| deps.api.human_address(c))
+            .collect::<StdResult<Vec<HumanAddr>>>()?,
+    })
+}
+
 pub fn query_collaterals<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     borrower: HumanAddr,
@@ -220,6 +557,41 @@ pub fn query_collaterals<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Collaterals and deposit exchange rate at the nearest
+/// checkpoints at or before the given height
+pub fn query_position_snapshot<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<PositionSnapshotResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let (collaterals_checkpoint_height, collaterals) =
+        match read_collaterals_checkpoint(&deps.storage, &borrower_raw, block_height)? {
+            Some((height, collaterals)) => (Some(height), collaterals),
+            None => (None, vec![]),
+        };
+
+    let (epoch_checkpoint_height, epoch_state) =
+        match read_epoch_checkpoint(&deps.storage, block_height)? {
+            Some(v) => v,
+            None => {
+                return Err(StdError::generic_err(
+                    "No checkpoint exists at the given height",
+                ))
+            }
+        };
+
+    Ok(PositionSnapshotResponse {
+        borrower,
+        block_height,
+        collaterals: collaterals.to_human(deps)?,
+        collaterals_checkpoint_height,
+        exchange_rate: epoch_state.prev_exchange_rate,
+        deposit_rate: epoch_state.deposit_rate,
+        epoch_checkpoint_height,
+    })
+}
+
 pub fn query_all_collaterals<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     start_after: Option<HumanAddr>,
@@ -231,46 +603,422 @@ pub fn query_all_collaterals<S: Storage, A: Api, Q: Querier>(
         None
     };
 
-    let all_collaterals: Vec<CollateralsResponse> =
-        read_all_collaterals(&deps, start_after, limit)?;
+    let all_collaterals: Vec<CollateralsResponse> = read_all_collaterals(deps, start_after, limit)?;
 
     Ok(AllCollateralsResponse { all_collaterals })
 }
 
+pub fn query_liquidatable_positions<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+    block_height: u64,
+) -> StdResult<LiquidatablePositionsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let borrowers = read_borrowers_collaterals(&deps.storage, start_after, limit)?;
+    let mut positions: Vec<LiquidatablePosition> = vec![];
+    for (borrower_raw, collaterals) in borrowers.iter() {
+        let borrower = deps.api.human_address(borrower_raw)?;
+        let borrow_amount =
+            query_borrower_info(deps, &market, &borrower, block_height)?.loan_amount;
+
+        // same check as the liquidation itself
+        let (borrow_limit, liquidation_limit, _) =
+            compute_liquidation_limit(deps, collaterals, None)?;
+        let liquidation_limit = compute_release_limit(deps, borrower_raw, liquidation_limit)?;
+        if liquidation_limit >= borrow_amount {
+            continue;
+        }
+
+        positions.push(LiquidatablePosition {
+            borrower,
+            borrow_amount,
+            borrow_limit: compute_release_limit(deps, borrower_raw, borrow_limit)?,
+            shortfall: borrow_amount - liquidation_limit,
+        });
+    }
+
+    Ok(LiquidatablePositionsResponse {
+        positions,
+        last_borrower: match borrowers.last() {
+            Some((borrower_raw, _)) => Some(deps.api.human_address(borrower_raw)?),
+            None => None,
+        },
+    })
+}
+
+/// Liquidatable positions among the next `limit` borrowers which
+/// `liquidator` is estimated to earn at least `min_profit` on when
+/// filling them with its own bids, most profitable first
+pub fn query_profitable_liquidations<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    liquidator: HumanAddr,
+    min_profit: Option<Uint256>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+    block_height: u64,
+) -> StdResult<ProfitableLiquidationsResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+    let min_profit = min_profit.unwrap_or_else(Uint256::zero);
+
+    let positions = query_liquidatable_positions(deps, start_after, limit, block_height)?;
+    let mut liquidations: Vec<ProfitableLiquidation> = vec![];
+    for position in positions.positions {
+        let borrower_raw = deps.api.canonical_address(&position.borrower)?;
+        let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+        let (borrow_limit, liquidation_limit, collateral_prices) =
+            compute_liquidation_limit(deps, &cur_collaterals, None)?;
+        let (liquidation_amount, _) = compute_liquidation_amount_with_limit(
+            deps,
+            block_height,
+            &position.borrower,
+            &cur_collaterals,
+            borrow_limit,
+            liquidation_limit,
+            collateral_prices,
+        )?;
+
+        let estimated_profit = match estimate_liquidation_profit(
+            deps,
+            &liquidation_contract,
+            &liquidator,
+            &liquidation_amount,
+        )? {
+            Some(estimated_profit) => estimated_profit,
+            None => continue,
+        };
+        if estimated_profit < min_profit {
+            continue;
+        }
+
+        liquidations.push(ProfitableLiquidation {
+            borrower: position.borrower,
+            shortfall: position.shortfall,
+            collaterals: liquidation_amount.to_human(deps)?,
+            estimated_profit,
+        });
+    }
+
+    liquidations.sort_by_key(|l| std::cmp::Reverse(l.estimated_profit));
+    Ok(ProfitableLiquidationsResponse {
+        liquidations,
+        last_borrower: positions.last_borrower,
+    })
+}
+
+/// The liquidator pays for the collaterals with its own bids, so it
+/// earns the bid premium on top of the liquidator fee. `None` when
+/// its bids cannot fill the liquidation
+#[allow(clippy::ptr_arg)]
+fn estimate_liquidation_profit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    liquidation_contract: &HumanAddr,
+    liquidator: &HumanAddr,
+    liquidation_amount: &Tokens,
+) -> StdResult<Option<Uint256>> {
+    let mut estimated_profit = Uint256::zero();
+    for collateral in liquidation_amount.iter() {
+        let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        let simulation = match query_simulate_liquidation(
+            deps,
+            liquidation_contract,
+            &deps.api.human_address(&collateral.0)?,
+            collateral.1,
+            liquidator,
+        ) {
+            Ok(simulation) => simulation,
+            Err(_) => return Ok(None),
+        };
+
+        // the liquidator fee is reported net of tax
+        let collateral_value =
+            normalize_amount(collateral.1, elem.decimals, Rounding::Down) * simulation.price;
+        if collateral_value > simulation.required_stable {
+            estimated_profit += collateral_value - simulation.required_stable;
+        }
+        estimated_profit += simulation.liquidator_fee;
+    }
+
+    Ok(Some(estimated_profit))
+}
+
 #[allow(clippy::ptr_arg)]
 pub(crate) fn compute_borrow_limit<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     collaterals: &Tokens,
     block_time: Option<u64>,
 ) -> StdResult<(Uint256, Vec<Decimal256>)> {
-    let config: Config = read_config(&deps.storage)?;
-    let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+    let position_limits = compute_position_limits(deps, collaterals, block_time)?;
 
     let mut borrow_limit: Uint256 = Uint256::zero();
     let mut collateral_prices: Vec<Decimal256> = vec![];
+    for position_limit in position_limits {
+        borrow_limit += position_limit.borrow_limit;
+        collateral_prices.push(position_limit.price);
+    }
+
+    // returns borrow_limit with collaterals value in stable denom
+    Ok((borrow_limit, collateral_prices))
+}
+
+/// Borrow limit along with the liquidation limit, above which the
+/// loan is liquidated, and the collateral prices
+#[allow(clippy::ptr_arg)]
+pub(crate) fn compute_liquidation_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<(Uint256, Uint256, Vec<Decimal256>)> {
+    let position_limits = compute_position_limits(deps, collaterals, block_time)?;
+
+    let mut borrow_limit: Uint256 = Uint256::zero();
+    let mut liquidation_limit: Uint256 = Uint256::zero();
+    let mut collateral_prices: Vec<Decimal256> = vec![];
+    for position_limit in position_limits {
+        borrow_limit += position_limit.borrow_limit;
+        liquidation_limit += position_limit.liquidation_limit;
+        collateral_prices.push(position_limit.price);
+    }
+
+    Ok((borrow_limit, liquidation_limit, collateral_prices))
+}
+
+/// Value and limits backed by a single collateral of a position
+struct PositionLimit {
+    price: Decimal256,
+    collateral_value: Uint256,
+    borrow_limit: Uint256,
+    liquidation_limit: Uint256,
+}
+
+#[allow(clippy::ptr_arg)]
+fn compute_position_limits<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<Vec<PositionLimit>> {
+    let config: Config = read_config(&deps.storage)?;
+    let isolated = isolated_collateral(&deps.storage, collaterals)?;
+
+    let mut position_limits: Vec<PositionLimit> = vec![];
     for collateral in collaterals.iter() {
-        let collateral_token = collateral.0.clone();
-        let collateral_amount = collateral.1;
-
-        let price: PriceResponse = query_price(
-            &deps,
-            &oracle_contract,
-            (deps.api.human_address(&collateral_token)?).to_string(),
-            config.stable_denom.to_string(),
-            block_time.map(|block_time| TimeConstraints {
-                block_time,
-                valid_timeframe: config.price_timeframe,
-            }),
-        )?;
+        let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        let (collateral_value, price) =
+            compute_collateral_value(deps, &config, collateral, elem.decimals, block_time)?;
+        let collateral_borrow_limit = tiered_borrow_limit(&elem, collateral_value);
+
+        // positions in isolation mode are only backed by the isolated
+        // collateral, e.g. when it was locked before being isolated
+        let (borrow_limit, liquidation_limit) = if isolated
+            .as_ref()
+            .is_some_and(|token| *token != collateral.0)
+        {
+            (Uint256::zero(), Uint256::zero())
+        } else {
+            // a single position cannot be backed beyond the token cap
+            let borrow_limit = match elem.borrow_cap {
+                Some(borrow_cap) if collateral_borrow_limit > borrow_cap => borrow_cap,
+                _ => collateral_borrow_limit,
+            };
+
+            // the liquidation buffer comes on top of the borrow limit
+            let liquidation_buffer = elem
+                .liquidation_ltv
+                .map_or(Uint256::zero(), |liquidation_ltv| {
+                    collateral_value * (liquidation_ltv - elem.max_ltv)
+                });
+            (borrow_limit, borrow_limit + liquidation_buffer)
+        };
+
+        position_limits.push(PositionLimit {
+            price,
+            collateral_value,
+            borrow_limit,
+            liquidation_limit,
+        });
+    }
+
+    Ok(position_limits)
+}
 
+/// Isolated collateral among the given ones, if any
+#[allow(clippy::ptr_arg)]
+fn isolated_collateral<S: Storage>(
+    storage: &S,
+    collaterals: &Tokens,
+) -> StdResult<Option<CanonicalAddr>> {
+    for collateral in collaterals.iter() {
+        if read_whitelist_elem(storage, &collateral.0)?.isolated {
+            return Ok(Some(collateral.0.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn query_isolation_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<IsolationModeResponse> {
+    let collaterals: Tokens =
+        read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let isolated_collateral = match isolated_collateral(&deps.storage, &collaterals)? {
+        Some(v) => Some(deps.api.human_address(&v)?),
+        None => None,
+    };
+
+    Ok(IsolationModeResponse {
+        borrower,
+        isolated_collateral,
+    })
+}
+
+/// Borrow limit a triggered loan must get under to leave liquidation
+pub(crate) fn compute_release_limit<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: &CanonicalAddr,
+    borrow_limit: Uint256,
+) -> StdResult<Uint256> {
+    if read_liquidation_trigger(&deps.storage, borrower)?.is_none() {
+        return Ok(borrow_limit);
+    }
+
+    let config: Config = read_config(&deps.storage)?;
+    Ok(rounding::div(
+        borrow_limit,
+        config.liquidation_release_ratio,
+        Rounding::Down,
+    ))
+}
+
+pub fn query_liquidation_trigger<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<LiquidationTriggerResponse> {
+    let triggered_at =
+        read_liquidation_trigger(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    Ok(LiquidationTriggerResponse {
+        borrower,
+        triggered_at,
+    })
+}
+
+/// Stable denom value of the given collateral amount, along with the price
+fn compute_collateral_value<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    config: &Config,
+    collateral: &(CanonicalAddr, Uint256),
+    decimals: u8,
+    block_time: Option<u64>,
+) -> StdResult<(Uint256, Decimal256)> {
+    let price: PriceResponse = query_price(
+        deps,
+        &deps.api.human_address(&config.oracle_contract)?,
+        (deps.api.human_address(&collateral.0)?).to_string(),
+        config.stable_denom.to_string(),
+        block_time.map(|block_time| TimeConstraints {
+            block_time,
+            valid_timeframe: config.price_timeframe,
+        }),
+    )?;
+
+    // prices are quoted per 6-decimal unit
+    let amount = normalize_amount(collateral.1, decimals, Rounding::Down);
+    Ok((amount * price.rate, price.rate))
+}
+
+/// Borrow limit of a single position, without the cap. Each part of
+/// the value is backed at the LTV of the tier it falls in, so a larger
+/// position never has a lower borrow limit
+fn tiered_borrow_limit(elem: &WhitelistElem, collateral_value: Uint256) -> Uint256 {
+    let mut borrow_limit = Uint256::zero();
+    let mut tier_start = Uint256::zero();
+    let mut max_ltv = elem.max_ltv;
+    for tier in elem.ltv_tiers.iter() {
+        if collateral_value <= tier.notional_threshold {
+            break;
+        }
+
+        borrow_limit += (tier.notional_threshold - tier_start) * max_ltv;
+        tier_start = tier.notional_threshold;
+        max_ltv = tier.max_ltv;
+    }
+
+    borrow_limit + (collateral_value - tier_start) * max_ltv
+}
+
+/// Locking the given tokens must not push the borrow limit
+/// backed by all the locked tokens above the token cap
+#[allow(clippy::ptr_arg)]
+fn assert_borrow_caps<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collaterals: &Tokens,
+    block_time: u64,
+) -> StdResult<()> {
+    let config: Config = read_config(&deps.storage)?;
+    for collateral in collaterals.iter() {
         let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
-        let collateral_value = collateral_amount * price.rate;
-        borrow_limit += collateral_value * elem.max_ltv;
-        collateral_prices.push(price.rate);
+        let borrow_cap = match elem.borrow_cap {
+            Some(v) => v,
+            None => continue,
+        };
+
+        // the tiers only apply to single positions
+        let total_collateral = read_total_collateral(&deps.storage, &collateral.0) + collateral.1;
+        let (collateral_value, _) = compute_collateral_value(
+            deps,
+            &config,
+            &(collateral.0.clone(), total_collateral),
+            elem.decimals,
+            Some(block_time),
+        )?;
+        let borrow_limit = collateral_value * elem.max_ltv;
+        if borrow_limit > borrow_cap {
+            return Err(StdError::generic_err(format!(
+                "Borrow cap exceeded; collateral: {}, borrow cap: {}",
+                deps.api.human_address(&collateral.0)?,
+                borrow_cap
+            )));
+        }
     }
 
-    // returns borrow_limit with collaterals value in stable denom
-    Ok((borrow_limit, collateral_prices))
+    Ok(())
+}
+
+pub fn query_borrow_cap_usage<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+) -> StdResult<BorrowCapUsageResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    let total_collateral = read_total_collateral(&deps.storage, &collateral_token_raw);
+    let (collateral_value, _) = compute_collateral_value(
+        deps,
+        &config,
+        &(collateral_token_raw, total_collateral),
+        elem.decimals,
+        None,
+    )?;
+    let borrow_limit = collateral_value * elem.max_ltv;
+
+    Ok(BorrowCapUsageResponse {
+        collateral_token,
+        borrow_cap: elem.borrow_cap,
+        total_collateral,
+        borrow_limit,
+    })
 }
 
 pub fn query_borrow_limit<S: Storage, A: Api, Q: Querier>(
@@ -288,3 +1036,182 @@ pub fn query_borrow_limit<S: Storage, A: Api, Q: Querier>(
         borrow_limit,
     })
 }
+
+pub fn query_borrow_limit_contribution<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    collateral_token: HumanAddr,
+    amount: Uint256,
+    block_time: Option<u64>,
+) -> StdResult<BorrowLimitContributionResponse> {
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    // Compute borrow limit as if only the given amount was locked
+    let (borrow_limit, collateral_prices) =
+        compute_borrow_limit(deps, &vec![(collateral_token_raw, amount)], block_time)?;
+
+    Ok(BorrowLimitContributionResponse {
+        collateral_token,
+        amount,
+        price: collateral_prices[0],
+        max_ltv: elem.max_ltv,
+        borrow_limit,
+    })
+}
+
+pub fn query_effective_ltv<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    collateral_token: HumanAddr,
+    block_time: Option<u64>,
+) -> StdResult<EffectiveLtvResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral_token_raw)?;
+
+    let amount = read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?)
+        .iter()
+        .find(|c| c.0 == collateral_token_raw)
+        .map(|c| c.1)
+        .unwrap_or_else(Uint256::zero);
+    let (collateral_value, _) = compute_collateral_value(
+        deps,
+        &config,
+        &(collateral_token_raw, amount),
+        elem.decimals,
+        block_time,
+    )?;
+
+    // an empty position would be backed at the first tier
+    let borrow_limit = tiered_borrow_limit(&elem, collateral_value);
+    let effective_ltv = if collateral_value.is_zero() {
+        elem.max_ltv
+    } else {
+        Decimal256::from_uint256(borrow_limit) / Decimal256::from_uint256(collateral_value)
+    };
+
+    Ok(EffectiveLtvResponse {
+        borrower,
+        collateral_token,
+        collateral_value,
+        borrow_limit,
+        effective_ltv,
+    })
+}
+
+pub fn query_borrower_health<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<BorrowerHealthResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrow_amount = query_borrower_info(deps, &market, &borrower, block_height)?.loan_amount;
+
+    let collaterals: Tokens =
+        read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let position_limits = compute_position_limits(deps, &collaterals, None)?;
+
+    let mut collateral_value = Uint256::zero();
+    let mut borrow_limit = Uint256::zero();
+    for position_limit in position_limits.iter() {
+        collateral_value += position_limit.collateral_value;
+        borrow_limit += position_limit.borrow_limit;
+    }
+
+    // the price at which a collateral alone brings the borrow limit down
+    // to the loan, scaling its current backing with the price
+    let mut collateral_healths: Vec<CollateralHealth> = vec![];
+    for (collateral, position_limit) in collaterals.iter().zip(position_limits.iter()) {
+        let other_limit = borrow_limit - position_limit.borrow_limit;
+        let liquidation_price = if borrow_amount.is_zero()
+            || position_limit.borrow_limit.is_zero()
+            || other_limit >= borrow_amount
+        {
+            None
+        } else {
+            Some(
+                position_limit.price * Decimal256::from_uint256(borrow_amount - other_limit)
+                    / Decimal256::from_uint256(position_limit.borrow_limit),
+            )
+        };
+
+        collateral_healths.push(CollateralHealth {
+            collateral_token: deps.api.human_address(&collateral.0)?,
+            amount: collateral.1,
+            price: position_limit.price,
+            liquidation_price,
+        });
+    }
+
+    Ok(BorrowerHealthResponse {
+        borrower,
+        borrow_amount,
+        borrow_limit,
+        collateral_value,
+        health_factor: if borrow_amount.is_zero() {
+            None
+        } else {
+            Some(Decimal256::from_uint256(borrow_limit) / Decimal256::from_uint256(borrow_amount))
+        },
+        ltv: if collateral_value.is_zero() {
+            Decimal256::zero()
+        } else {
+            Decimal256::from_uint256(borrow_amount) / Decimal256::from_uint256(collateral_value)
+        },
+        shortfall: if borrow_amount > borrow_limit {
+            borrow_amount - borrow_limit
+        } else {
+            Uint256::zero()
+        },
+        collaterals: collateral_healths,
+    })
+}
+
+pub fn query_account_summary<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+    block_height: u64,
+) -> StdResult<AccountSummaryResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrower_info = query_borrower_info(deps, &market, &borrower, block_height)?;
+
+    let collaterals: Tokens =
+        read_collaterals(&deps.storage, &deps.api.canonical_address(&borrower)?);
+    let position_limits = compute_position_limits(deps, &collaterals, None)?;
+
+    let mut account_collaterals: Vec<AccountCollateral> = vec![];
+    let mut collateral_value = Uint256::zero();
+    let mut borrow_limit = Uint256::zero();
+    for (collateral, position_limit) in collaterals.iter().zip(position_limits.iter()) {
+        let elem: WhitelistElem = read_whitelist_elem(&deps.storage, &collateral.0)?;
+        let collateral_token = deps.api.human_address(&collateral.0)?;
+        let custody_borrower = query_custody_borrower(
+            deps,
+            &deps.api.human_address(&elem.custody_contract)?,
+            &collateral_token,
+            elem.multi_custody,
+            &borrower,
+        )?;
+
+        collateral_value += position_limit.collateral_value;
+        borrow_limit += position_limit.borrow_limit;
+        account_collaterals.push(AccountCollateral {
+            collateral_token,
+            balance: custody_borrower.balance,
+            locked_amount: collateral.1,
+            price: position_limit.price,
+            value: position_limit.collateral_value,
+        });
+    }
+
+    Ok(AccountSummaryResponse {
+        borrower,
+        collaterals: account_collaterals,
+        collateral_value,
+        borrow_amount: borrower_info.loan_amount,
+        borrow_limit,
+        pending_rewards: borrower_info.pending_rewards,
+    })
+}
@@ -1,28 +1,58 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse,
-    HandleResult, HumanAddr, InitResponse, InitResult, MigrateResponse, MigrateResult, Querier,
-    StdError, StdResult, Storage, WasmMsg,
+    log, to_binary, Api, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, InitResponse, InitResult, MigrateResponse, MigrateResult, Querier, StdError,
+    StdResult, Storage, WasmMsg,
 };
 
+use crate::accrual::{query_accrual_halt, set_accrual_halt, update_accrual_freeze};
+use crate::authorization::{
+    authorize_liquidation, execute_authorized_liquidation, query_liquidation_authorization,
+};
 use crate::collateral::{
-    liquidate_collateral, lock_collateral, query_all_collaterals, query_borrow_limit,
-    query_collaterals, unlock_collateral,
+    liquidate_collateral, lock_collateral, query_account_summary, query_all_collaterals,
+    query_borrow_cap_usage, query_borrow_limit, query_borrow_limit_contribution,
+    query_borrower_health, query_collateral_priority, query_collaterals, query_effective_ltv,
+    query_isolation_mode, query_liquidatable_positions, query_liquidation_trigger,
+    query_position_snapshot, query_profitable_liquidations, set_collateral_priority,
+    unlock_collateral,
+};
+use crate::executor::{
+    deregister_liquidation_executor, query_liquidation_executors, register_liquidation_executor,
+    set_executor_only,
+};
+use crate::notification::{
+    check_health_alerts, deregister_health_alert, query_epoch_operations_progress,
+    query_health_alert, register_health_alert, resume_epoch_operations,
 };
-use crate::querier::query_epoch_state;
+use crate::querier::{query_epoch_state, query_market_state};
+use crate::retry::{query_failed_liquidation, query_failed_liquidations, retry_liquidation};
 use crate::state::{
-    read_config, read_epoch_state, read_whitelist, read_whitelist_elem, store_config,
-    store_epoch_state, store_whitelist_elem, Config, EpochState, WhitelistElem,
+    read_config, read_epoch_state, read_pause_info, read_rate_smoothing, read_whitelist,
+    read_whitelist_elem, rebuild_total_collaterals, shift_failed_liquidations, store_config,
+    store_epoch_checkpoint, store_epoch_state, store_pause_info, store_rate_smoothing,
+    store_whitelist_elem, Config, EpochOperationsCursor, EpochState, RateSmoothing, WhitelistElem,
 };
 
 use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use moneymarket::custody_multi::custody_msg;
+use moneymarket::decimals::{DEFAULT_DECIMALS, MAX_DECIMALS};
+use moneymarket::denom_migration::{
+    claim_denom_migration, propose_denom_migration, query_pending_denom_migration,
+};
+use moneymarket::halt_recovery::{read_halt_recovery, record_halt_recovery};
 use moneymarket::market::EpochStateResponse;
 use moneymarket::market::HandleMsg as MarketHandleMsg;
 use moneymarket::overseer::{
-    ConfigResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg, WhitelistResponse,
+    ConfigResponse, DebtCeilingUsageResponse, HandleMsg, InitMsg, LtvTier, MigrateMsg, QueryMsg,
+    RateSmoothingResponse, SimulateEpochOperationsResponse, WhitelistResponse,
     WhitelistResponseElem,
 };
+use moneymarket::ownership::{claim_ownership, propose_owner, query_pending_owner};
+use moneymarket::pause::PauseInfo;
+use moneymarket::payout::native_payout_msg;
 use moneymarket::querier::{deduct_tax, query_balance};
+use moneymarket::sweep::{sweep_msg, SweepAsset};
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
@@ -44,19 +74,30 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
             buffer_distribution_factor: msg.buffer_distribution_factor,
             anc_purchase_factor: msg.anc_purchase_factor,
             price_timeframe: msg.price_timeframe,
+            guardian: None,
+            hysteresis_band: Decimal256::zero(),
+            hysteresis_epochs: 1u64,
+            retry_base_delay: 60u64,
+            retry_max_delay: 3600u64,
+            priority_tolerance: Decimal256::percent(1),
+            debt_ceiling: None,
+            liquidation_release_ratio: Decimal256::one(),
+            liquidation_cooldown: 0u64,
+            emergency_liquidation_ratio: Decimal256::one(),
+            liquidation_authorization_period: 10u64,
+            contract_addr: deps.api.canonical_address(&env.contract.address)?,
         },
     )?;
 
-    store_epoch_state(
-        &mut deps.storage,
-        &EpochState {
-            deposit_rate: Decimal256::zero(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_interest_buffer: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-            last_executed_height: env.block.height,
-        },
-    )?;
+    let epoch_state = EpochState {
+        deposit_rate: Decimal256::zero(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_interest_buffer: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        last_executed_height: env.block.height,
+    };
+    store_epoch_state(&mut deps.storage, &epoch_state)?;
+    store_epoch_checkpoint(&mut deps.storage, env.block.height, &epoch_state)?;
 
     Ok(InitResponse::default())
 }
@@ -67,8 +108,29 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMsg,
 ) -> HandleResult {
     match msg {
+        HandleMsg::ProposeOwner {
+            new_owner,
+            expires_in,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_owner(deps, env, &config.owner_addr, new_owner, expires_in)
+        }
+        HandleMsg::AcceptOwnership {} => accept_ownership(deps, env),
+        HandleMsg::ProposeDenomMigration {
+            new_denom,
+            conversion_contract,
+        } => {
+            let config: Config = read_config(&deps.storage)?;
+            propose_denom_migration(
+                deps,
+                env,
+                &config.owner_addr,
+                new_denom,
+                conversion_contract,
+            )
+        }
+        HandleMsg::ExecuteDenomMigration {} => execute_denom_migration(deps, env),
         HandleMsg::UpdateConfig {
-            owner_addr,
             oracle_contract,
             liquidation_contract,
             threshold_deposit_rate,
@@ -77,10 +139,21 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             anc_purchase_factor,
             epoch_period,
             price_timeframe,
+            guardian,
+            hysteresis_band,
+            hysteresis_epochs,
+            retry_base_delay,
+            retry_max_delay,
+            priority_tolerance,
+            debt_ceiling,
+            clear_debt_ceiling,
+            liquidation_release_ratio,
+            liquidation_cooldown,
+            emergency_liquidation_ratio,
+            liquidation_authorization_period,
         } => update_config(
             deps,
             env,
-            owner_addr,
             oracle_contract,
             liquidation_contract,
             threshold_deposit_rate,
@@ -89,7 +162,40 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             anc_purchase_factor,
             epoch_period,
             price_timeframe,
+            guardian,
+            hysteresis_band,
+            hysteresis_epochs,
+            retry_base_delay,
+            retry_max_delay,
+            priority_tolerance,
+            debt_ceiling,
+            clear_debt_ceiling,
+            liquidation_release_ratio,
+            liquidation_cooldown,
+            emergency_liquidation_ratio,
+            liquidation_authorization_period,
         ),
+        HandleMsg::SetAccrualHalt {
+            collateral_token,
+            halted,
+        } => set_accrual_halt(deps, env, collateral_token, halted),
+        HandleMsg::AddLiquidationExecutor { executor } => {
+            register_liquidation_executor(deps, env, executor)
+        }
+        HandleMsg::RemoveLiquidationExecutor { executor } => {
+            deregister_liquidation_executor(deps, env, executor)
+        }
+        HandleMsg::SetExecutorOnly { executor_only } => set_executor_only(deps, env, executor_only),
+        HandleMsg::SetPause {
+            deposits,
+            borrows,
+            liquidations,
+            withdrawals,
+        } => set_pause(deps, env, deposits, borrows, liquidations, withdrawals),
+        HandleMsg::RecoverFromHalt { halted_at } => recover_from_halt(deps, env, halted_at),
+        HandleMsg::SweepUnrelatedFunds { asset, recipient } => {
+            sweep_unrelated_funds(deps, env, asset, recipient)
+        }
         HandleMsg::Whitelist {
             name,
             symbol,
@@ -109,15 +215,47 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             collateral_token,
             custody_contract,
             max_ltv,
-        } => update_whitelist(deps, env, collateral_token, custody_contract, max_ltv),
+            liquidation_ltv,
+            borrow_cap,
+            clear_borrow_cap,
+            isolated,
+            ltv_tiers,
+            multi_custody,
+            decimals,
+        } => update_whitelist(
+            deps,
+            env,
+            collateral_token,
+            custody_contract,
+            max_ltv,
+            liquidation_ltv,
+            borrow_cap,
+            clear_borrow_cap,
+            isolated,
+            ltv_tiers,
+            multi_custody,
+            decimals,
+        ),
         HandleMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env),
+        HandleMsg::ResumeEpochOperations {} => resume_epoch_operations(deps, env),
         HandleMsg::UpdateEpochState {
             interest_buffer,
             distributed_interest,
         } => update_epoch_state(deps, env, interest_buffer, distributed_interest),
         HandleMsg::LockCollateral { collaterals } => lock_collateral(deps, env, collaterals),
         HandleMsg::UnlockCollateral { collaterals } => unlock_collateral(deps, env, collaterals),
+        HandleMsg::RegisterHealthAlert { threshold } => register_health_alert(deps, env, threshold),
+        HandleMsg::DeregisterHealthAlert {} => deregister_health_alert(deps, env),
+        HandleMsg::SetCollateralPriority { collaterals } => {
+            set_collateral_priority(deps, env, collaterals)
+        }
         HandleMsg::LiquidateCollateral { borrower } => liquidate_collateral(deps, env, borrower),
+        HandleMsg::AuthorizeLiquidation { borrower } => authorize_liquidation(deps, env, borrower),
+        HandleMsg::ExecuteAuthorizedLiquidation { borrower } => {
+            execute_authorized_liquidation(deps, env, borrower)
+        }
+        HandleMsg::RetryLiquidation { borrower } => retry_liquidation(deps, env, borrower),
+        HandleMsg::UpdateAccrualFreeze { borrower } => update_accrual_freeze(deps, borrower),
     }
 }
 
@@ -125,7 +263,6 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
 pub fn update_config<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    owner_addr: Option<HumanAddr>,
     oracle_contract: Option<HumanAddr>,
     liquidation_contract: Option<HumanAddr>,
     threshold_deposit_rate: Option<Decimal256>,
@@ -134,6 +271,18 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     anc_purchase_factor: Option<Decimal256>,
     epoch_period: Option<u64>,
     price_timeframe: Option<u64>,
+    guardian: Option<HumanAddr>,
+    hysteresis_band: Option<Decimal256>,
+    hysteresis_epochs: Option<u64>,
+    retry_base_delay: Option<u64>,
+    retry_max_delay: Option<u64>,
+    priority_tolerance: Option<Decimal256>,
+    debt_ceiling: Option<Uint256>,
+    clear_debt_ceiling: Option<bool>,
+    liquidation_release_ratio: Option<Decimal256>,
+    liquidation_cooldown: Option<u64>,
+    emergency_liquidation_ratio: Option<Decimal256>,
+    liquidation_authorization_period: Option<u64>,
 ) -> HandleResult {
     let mut config: Config = read_config(&deps.storage)?;
 
@@ -141,10 +290,6 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         return Err(StdError::unauthorized());
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.canonical_address(&owner_addr)?;
-    }
-
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
     }
@@ -177,6 +322,90 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
         config.price_timeframe = price_timeframe;
     }
 
+    if let Some(guardian) = guardian {
+        config.guardian = Some(deps.api.canonical_address(&guardian)?);
+    }
+
+    if let Some(hysteresis_band) = hysteresis_band {
+        config.hysteresis_band = hysteresis_band;
+    }
+
+    if let Some(hysteresis_epochs) = hysteresis_epochs {
+        if hysteresis_epochs == 0 {
+            return Err(StdError::generic_err(
+                "Hysteresis epochs must be bigger than zero",
+            ));
+        }
+
+        config.hysteresis_epochs = hysteresis_epochs;
+    }
+
+    if let Some(retry_base_delay) = retry_base_delay {
+        config.retry_base_delay = retry_base_delay;
+    }
+
+    if let Some(retry_max_delay) = retry_max_delay {
+        config.retry_max_delay = retry_max_delay;
+    }
+
+    if config.retry_base_delay == 0 || config.retry_base_delay > config.retry_max_delay {
+        return Err(StdError::generic_err(
+            "Retry base delay must be bigger than zero and not exceed the max delay",
+        ));
+    }
+
+    if let Some(priority_tolerance) = priority_tolerance {
+        if priority_tolerance > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Priority tolerance cannot exceed one",
+            ));
+        }
+
+        config.priority_tolerance = priority_tolerance;
+    }
+
+    if clear_debt_ceiling.unwrap_or(false) {
+        config.debt_ceiling = None;
+    }
+
+    if let Some(debt_ceiling) = debt_ceiling {
+        config.debt_ceiling = Some(debt_ceiling);
+    }
+
+    if let Some(liquidation_release_ratio) = liquidation_release_ratio {
+        if liquidation_release_ratio < Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Liquidation release ratio cannot be lower than one",
+            ));
+        }
+
+        config.liquidation_release_ratio = liquidation_release_ratio;
+    }
+
+    if let Some(liquidation_cooldown) = liquidation_cooldown {
+        config.liquidation_cooldown = liquidation_cooldown;
+    }
+
+    if let Some(emergency_liquidation_ratio) = emergency_liquidation_ratio {
+        if emergency_liquidation_ratio < Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Emergency liquidation ratio cannot be lower than one",
+            ));
+        }
+
+        config.emergency_liquidation_ratio = emergency_liquidation_ratio;
+    }
+
+    if let Some(liquidation_authorization_period) = liquidation_authorization_period {
+        if liquidation_authorization_period == 0 {
+            return Err(StdError::generic_err(
+                "Liquidation authorization period must be bigger than zero",
+            ));
+        }
+
+        config.liquidation_authorization_period = liquidation_authorization_period;
+    }
+
     store_config(&mut deps.storage, &config)?;
 
     Ok(HandleResponse {
@@ -186,6 +415,109 @@ pub fn update_config<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+pub fn accept_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    config.owner_addr = claim_ownership(deps, &env)?;
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "accept_ownership"),
+            log("owner", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn execute_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    // the conversion tax is paid out of the interest buffer
+    let (migration, messages, conversion_tax) =
+        claim_denom_migration(deps, &env, &config.owner_addr, &config.stable_denom)?;
+
+    let old_denom = std::mem::replace(&mut config.stable_denom, migration.new_denom);
+    store_config(&mut deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![
+            log("action", "execute_denom_migration"),
+            log("old_denom", old_denom),
+            log("new_denom", config.stable_denom),
+            log("conversion_tax", conversion_tax),
+        ],
+        data: None,
+    })
+}
+
+pub fn set_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    deposits: Option<bool>,
+    borrows: Option<bool>,
+    liquidations: Option<bool>,
+    withdrawals: Option<bool>,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+
+    // permission check; guardian can only pause
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    let is_owner = sender_raw == config.owner_addr;
+    if !is_owner && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(&deps.storage)?;
+    pause_info.update(deposits, borrows, liquidations, withdrawals, is_owner)?;
+    store_pause_info(&mut deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pause"),
+            log("deposits", pause_info.deposits),
+            log("borrows", pause_info.borrows),
+            log("liquidations", pause_info.liquidations),
+            log("withdrawals", pause_info.withdrawals),
+        ],
+        data: None,
+    })
+}
+
+/// Epochs are scheduled by height, which does not advance during
+/// a halt, so only the time-based retries are shifted
+pub fn recover_from_halt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    halted_at: u64,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+    if sender_raw != config.owner_addr && Some(sender_raw) != config.guardian {
+        return Err(StdError::unauthorized());
+    }
+
+    let duration = record_halt_recovery(&mut deps.storage, halted_at, env.block.time)?;
+    shift_failed_liquidations(&mut deps.storage, duration)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "recover_from_halt"),
+            log("halted_at", halted_at),
+            log("duration", duration),
+        ],
+        data: None,
+    })
+}
+
 pub fn register_whitelist<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -215,6 +547,12 @@ pub fn register_whitelist<S: Storage, A: Api, Q: Querier>(
             symbol: symbol.to_string(),
             custody_contract: deps.api.canonical_address(&custody_contract)?,
             max_ltv,
+            liquidation_ltv: None,
+            borrow_cap: None,
+            isolated: false,
+            ltv_tiers: vec![],
+            multi_custody: false,
+            decimals: DEFAULT_DECIMALS,
         },
     )?;
 
@@ -232,12 +570,20 @@ pub fn register_whitelist<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     collateral_token: HumanAddr,
     custody_contract: Option<HumanAddr>,
     max_ltv: Option<Decimal256>,
+    liquidation_ltv: Option<Decimal256>,
+    borrow_cap: Option<Uint256>,
+    clear_borrow_cap: Option<bool>,
+    isolated: Option<bool>,
+    ltv_tiers: Option<Vec<LtvTier>>,
+    multi_custody: Option<bool>,
+    decimals: Option<u8>,
 ) -> HandleResult {
     let config: Config = read_config(&deps.storage)?;
     if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
@@ -256,6 +602,69 @@ pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
         whitelist_elem.max_ltv = max_ltv;
     }
 
+    if let Some(liquidation_ltv) = liquidation_ltv {
+        whitelist_elem.liquidation_ltv = Some(liquidation_ltv);
+    }
+
+    // the buffer must stay above max_ltv, also when only max_ltv changed
+    if let Some(liquidation_ltv) = whitelist_elem.liquidation_ltv {
+        if liquidation_ltv <= whitelist_elem.max_ltv || liquidation_ltv > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Liquidation LTV must be bigger than max LTV and cannot exceed one",
+            ));
+        }
+    }
+
+    if clear_borrow_cap.unwrap_or(false) {
+        whitelist_elem.borrow_cap = None;
+    }
+
+    if let Some(borrow_cap) = borrow_cap {
+        whitelist_elem.borrow_cap = Some(borrow_cap);
+    }
+
+    if let Some(isolated) = isolated {
+        whitelist_elem.isolated = isolated;
+    }
+
+    if let Some(multi_custody) = multi_custody {
+        whitelist_elem.multi_custody = multi_custody;
+    }
+
+    if let Some(decimals) = decimals {
+        if decimals > MAX_DECIMALS {
+            return Err(StdError::generic_err(format!(
+                "Collateral decimals cannot exceed {}",
+                MAX_DECIMALS
+            )));
+        }
+
+        whitelist_elem.decimals = decimals;
+    }
+
+    // isolated positions are limited to the token cap
+    if whitelist_elem.isolated && whitelist_elem.borrow_cap.is_none() {
+        return Err(StdError::generic_err(
+            "Isolated collateral requires a borrow cap",
+        ));
+    }
+
+    if let Some(ltv_tiers) = ltv_tiers {
+        whitelist_elem.ltv_tiers = ltv_tiers;
+    }
+
+    // each tier covers larger positions with a lower LTV
+    let mut prev_tier = (Uint256::zero(), whitelist_elem.max_ltv);
+    for tier in whitelist_elem.ltv_tiers.iter() {
+        if tier.notional_threshold <= prev_tier.0 || tier.max_ltv > prev_tier.1 {
+            return Err(StdError::generic_err(
+                "LTV tiers must have increasing thresholds and non-increasing LTVs",
+            ));
+        }
+
+        prev_tier = (tier.notional_threshold, tier.max_ltv);
+    }
+
     store_whitelist_elem(&mut deps.storage, &collateral_token_raw, &whitelist_elem)?;
 
     Ok(HandleResponse {
@@ -268,6 +677,21 @@ pub fn update_whitelist<S: Storage, A: Api, Q: Querier>(
                 deps.api.human_address(&whitelist_elem.custody_contract)?,
             ),
             log("LTV", whitelist_elem.max_ltv),
+            log(
+                "liquidation_LTV",
+                whitelist_elem
+                    .liquidation_ltv
+                    .unwrap_or(whitelist_elem.max_ltv),
+            ),
+            log(
+                "borrow_cap",
+                whitelist_elem
+                    .borrow_cap
+                    .map_or("none".to_string(), |v| v.to_string()),
+            ),
+            log("isolated", whitelist_elem.isolated),
+            log("multi_custody", whitelist_elem.multi_custody),
+            log("decimals", whitelist_elem.decimals),
         ],
         data: None,
     })
@@ -277,9 +701,66 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
 ) -> HandleResult {
+    let epoch_operations = compute_epoch_operations(deps, &env.contract.address, env.block.height)?;
+    store_rate_smoothing(&mut deps.storage, &epoch_operations.rate_smoothing)?;
+
+    // Notify borrowers whose health factor crossed their alert threshold.
+    // Only the first batch is checked here, the rest is left to
+    // ResumeEpochOperations; an unfinished batch of the previous
+    // epoch is dropped so the epoch operations never wedge
+    let mut cursor = EpochOperationsCursor {
+        epoch_height: env.block.height,
+        ..EpochOperationsCursor::default()
+    };
+    let health_alert_logs = check_health_alerts(deps, &env, &mut cursor)?;
+
+    Ok(HandleResponse {
+        messages: epoch_operations.messages,
+        log: [
+            vec![
+                log("action", "epoch_operations"),
+                log("deposit_rate", epoch_operations.deposit_rate),
+                log("exchange_rate", epoch_operations.epoch_state.exchange_rate),
+                log("aterra_supply", epoch_operations.epoch_state.aterra_supply),
+                log(
+                    "distributed_interest",
+                    epoch_operations.distributed_interest,
+                ),
+                log("anc_purchase_amount", epoch_operations.anc_purchase_amount),
+                log(
+                    "locked_interest_funding",
+                    epoch_operations.locked_interest_funding,
+                ),
+            ],
+            health_alert_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+/// Outcome of the epoch operations at a block height
+struct EpochOperations {
+    deposit_rate: Decimal256,
+    epoch_state: EpochStateResponse,
+    rate_smoothing: RateSmoothing,
+    anc_purchase_amount: Uint256,
+    locked_interest_funding: Uint256,
+    distributed_interest: Uint256,
+    interest_buffer: Uint256,
+    messages: Vec<CosmosMsg>,
+}
+
+/// Compute the epoch operations without storing anything, so the
+/// handler and the SimulateEpochOperations query share the logic
+fn compute_epoch_operations<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    contract_addr: &HumanAddr,
+    block_height: u64,
+) -> StdResult<EpochOperations> {
     let config: Config = read_config(&deps.storage)?;
     let state: EpochState = read_epoch_state(&deps.storage)?;
-    if env.block.height < state.last_executed_height + config.epoch_period {
+    if block_height < state.last_executed_height + config.epoch_period {
         return Err(StdError::generic_err(format!(
             "An epoch has not passed yet; last executed height: {}",
             state.last_executed_height
@@ -287,12 +768,12 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
     }
 
     // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - state.last_executed_height);
+    let blocks = Uint256::from(block_height - state.last_executed_height);
 
     // Compute next epoch state
     let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
     let epoch_state: EpochStateResponse =
-        query_epoch_state(&deps, &market_contract, env.block.height, None)?;
+        query_epoch_state(deps, &market_contract, block_height, None)?;
 
     // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
     // deposit_rate = (effective_deposit_rate - 1) / blocks
@@ -300,37 +781,71 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
     let deposit_rate =
         (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
 
+    // Flip the subsidy switch only when the deposit rate stayed beyond
+    // the hysteresis band for hysteresis_epochs consecutive epochs
+    let mut rate_smoothing: RateSmoothing = read_rate_smoothing(&deps.storage)?;
+    let target_subsidy_active =
+        if deposit_rate + config.hysteresis_band < config.threshold_deposit_rate {
+            Some(true)
+        } else if deposit_rate > config.threshold_deposit_rate + config.hysteresis_band {
+            Some(false)
+        } else {
+            None
+        };
+
+    match target_subsidy_active {
+        Some(active) if active != rate_smoothing.subsidy_active => {
+            rate_smoothing.deviation_epochs += 1;
+            if rate_smoothing.deviation_epochs >= config.hysteresis_epochs {
+                rate_smoothing.subsidy_active = active;
+                rate_smoothing.deviation_epochs = 0;
+            }
+        }
+        _ => rate_smoothing.deviation_epochs = 0,
+    }
+
     let mut messages: Vec<CosmosMsg> = vec![];
-    let mut interest_buffer = query_balance(
-        &deps,
-        &env.contract.address,
-        config.stable_denom.to_string(),
-    )?;
+    let mut interest_buffer = query_balance(deps, contract_addr, config.stable_denom.to_string())?;
 
     // Send accrued_buffer * config.anc_purchase_factor amount stable token to collector
     let accrued_buffer = interest_buffer - state.prev_interest_buffer;
     let anc_purchase_amount = accrued_buffer * config.anc_purchase_factor;
     if !anc_purchase_amount.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: env.contract.address.clone(),
-            to_address: deps.api.human_address(&config.collector_contract)?,
-            amount: vec![deduct_tax(
-                &deps,
+        messages.push(native_payout_msg(
+            deps,
+            contract_addr.clone(),
+            deps.api.human_address(&config.collector_contract)?,
+            config.stable_denom.to_string(),
+            anc_purchase_amount,
+        )?);
+    }
+
+    // Deduct anc_purchase_amount from the interest_buffer
+    interest_buffer = interest_buffer - anc_purchase_amount;
+
+    // Fund the interest owed to the locked positions of the market
+    // before any subsidy, as the market has already promised it
+    let locked_interest_funding =
+        std::cmp::min(epoch_state.unfunded_locked_interest, interest_buffer);
+    if !locked_interest_funding.is_zero() {
+        interest_buffer = interest_buffer - locked_interest_funding;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market_contract.clone(),
+            send: vec![deduct_tax(
+                deps,
                 Coin {
                     denom: config.stable_denom.to_string(),
-                    amount: anc_purchase_amount.into(),
+                    amount: locked_interest_funding.into(),
                 },
             )?],
+            msg: to_binary(&MarketHandleMsg::FundLockedInterest {})?,
         }));
     }
 
-    // Deduct anc_purchase_amount from the interest_buffer
-    interest_buffer = interest_buffer - anc_purchase_amount;
-
     // Distribute Interest Buffer to depositor
-    // Only executed when deposit rate < threshold_deposit_rate
+    // Only executed when the subsidy is active and deposit rate < threshold_deposit_rate
     let mut distributed_interest: Uint256 = Uint256::zero();
-    if deposit_rate < config.threshold_deposit_rate {
+    if rate_smoothing.subsidy_active && deposit_rate < config.threshold_deposit_rate {
         // missing_deposit_rate(_per_block)
         let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
         let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
@@ -345,10 +860,19 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
         interest_buffer = interest_buffer - distributed_interest;
 
         if !distributed_interest.is_zero() {
-            // deduct tax
+            // Send some portion of interest buffer to Market contract
+            messages.push(native_payout_msg(
+                deps,
+                contract_addr.clone(),
+                market_contract,
+                config.stable_denom.to_string(),
+                distributed_interest,
+            )?);
+
+            // the market receives the amount after tax
             distributed_interest = Uint256::from(
                 deduct_tax(
-                    &deps,
+                    deps,
                     Coin {
                         denom: config.stable_denom.to_string(),
                         amount: distributed_interest.into(),
@@ -356,32 +880,23 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
                 )?
                 .amount,
             );
-
-            // Send some portion of interest buffer to Market contract
-            messages.push(CosmosMsg::Bank(BankMsg::Send {
-                from_address: env.contract.address.clone(),
-                to_address: market_contract,
-                amount: vec![Coin {
-                    denom: config.stable_denom,
-                    amount: distributed_interest.into(),
-                }],
-            }));
         }
     }
 
     // Execute DistributeRewards
-    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(&deps, None, None)?;
+    let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, None, None)?;
     for elem in whitelist.iter() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: elem.custody_contract.clone(),
-            send: vec![],
-            msg: to_binary(&CustodyHandleMsg::DistributeRewards {})?,
-        }));
+        messages.push(custody_msg(
+            elem.custody_contract.clone(),
+            elem.collateral_token.clone(),
+            elem.multi_custody,
+            CustodyHandleMsg::DistributeRewards {},
+        )?);
     }
 
     // Execute store epoch state operation
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
+        contract_addr: contract_addr.clone(),
         send: vec![],
         msg: to_binary(&HandleMsg::UpdateEpochState {
             interest_buffer,
@@ -389,17 +904,15 @@ pub fn execute_epoch_operations<S: Storage, A: Api, Q: Querier>(
         })?,
     }));
 
-    Ok(HandleResponse {
+    Ok(EpochOperations {
+        deposit_rate,
+        epoch_state,
+        rate_smoothing,
+        anc_purchase_amount,
+        locked_interest_funding,
+        distributed_interest,
+        interest_buffer,
         messages,
-        log: vec![
-            log("action", "epoch_operations"),
-            log("deposit_rate", deposit_rate),
-            log("exchange_rate", epoch_state.exchange_rate),
-            log("aterra_supply", epoch_state.aterra_supply),
-            log("distributed_interest", distributed_interest),
-            log("anc_purchase_amount", anc_purchase_amount),
-        ],
-        data: None,
     })
 }
 
@@ -423,7 +936,7 @@ pub fn update_epoch_state<S: Storage, A: Api, Q: Querier>(
     // Compute next epoch state
     let market_contract: HumanAddr = deps.api.human_address(&config.market_contract)?;
     let market_epoch_state: EpochStateResponse = query_epoch_state(
-        &deps,
+        deps,
         &market_contract,
         env.block.height,
         Some(distributed_interest),
@@ -437,16 +950,15 @@ pub fn update_epoch_state<S: Storage, A: Api, Q: Querier>(
         (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
 
     // store updated epoch state
-    store_epoch_state(
-        &mut deps.storage,
-        &EpochState {
-            last_executed_height: env.block.height,
-            prev_aterra_supply: market_epoch_state.aterra_supply,
-            prev_exchange_rate: market_epoch_state.exchange_rate,
-            prev_interest_buffer: interest_buffer,
-            deposit_rate,
-        },
-    )?;
+    let epoch_state = EpochState {
+        last_executed_height: env.block.height,
+        prev_aterra_supply: market_epoch_state.aterra_supply,
+        prev_exchange_rate: market_epoch_state.exchange_rate,
+        prev_interest_buffer: interest_buffer,
+        deposit_rate,
+    };
+    store_epoch_state(&mut deps.storage, &epoch_state)?;
+    store_epoch_checkpoint(&mut deps.storage, env.block.height, &epoch_state)?;
 
     Ok(HandleResponse {
         messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
@@ -470,13 +982,56 @@ pub fn update_epoch_state<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+pub fn sweep_unrelated_funds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    asset: SweepAsset,
+    recipient: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    // the stable balance is the interest buffer
+    let is_tracked = match &asset {
+        SweepAsset::Native { denom } => *denom == config.stable_denom,
+        SweepAsset::Token { .. } => false,
+    };
+    if is_tracked {
+        return Err(StdError::generic_err(format!(
+            "Cannot sweep tracked funds: {}",
+            asset
+        )));
+    }
+
+    let (amount, msg) = sweep_msg(deps, &env, &asset, recipient.clone())?;
+    Ok(HandleResponse {
+        messages: vec![msg],
+        log: vec![
+            log("action", "sweep_unrelated_funds"),
+            log("asset", asset),
+            log("amount", amount),
+            log("recipient", recipient),
+        ],
+        data: None,
+    })
+}
+
 pub fn query<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::PendingDenomMigration {} => to_binary(&query_pending_denom_migration(deps)?),
         QueryMsg::EpochState {} => to_binary(&query_state(deps)?),
+        QueryMsg::RateSmoothing {} => to_binary(&query_rate_smoothing(deps)?),
+        QueryMsg::SimulateEpochOperations { block_height } => {
+            to_binary(&query_simulate_epoch_operations(deps, block_height)?)
+        }
+        QueryMsg::EpochOperationsProgress {} => to_binary(&query_epoch_operations_progress(deps)?),
         QueryMsg::Whitelist {
             collateral_token,
             start_after,
@@ -488,13 +1043,98 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
             limit,
         )?),
         QueryMsg::Collaterals { borrower } => to_binary(&query_collaterals(deps, borrower)?),
+        QueryMsg::PositionSnapshot {
+            borrower,
+            block_height,
+        } => to_binary(&query_position_snapshot(deps, borrower, block_height)?),
         QueryMsg::AllCollaterals { start_after, limit } => {
             to_binary(&query_all_collaterals(deps, start_after, limit)?)
         }
+        QueryMsg::LiquidatablePositions {
+            start_after,
+            limit,
+            block_height,
+        } => to_binary(&query_liquidatable_positions(
+            deps,
+            start_after,
+            limit,
+            block_height,
+        )?),
+        QueryMsg::ProfitableLiquidations {
+            liquidator,
+            min_profit,
+            start_after,
+            limit,
+            block_height,
+        } => to_binary(&query_profitable_liquidations(
+            deps,
+            liquidator,
+            min_profit,
+            start_after,
+            limit,
+            block_height,
+        )?),
         QueryMsg::BorrowLimit {
             borrower,
             block_time,
         } => to_binary(&query_borrow_limit(deps, borrower, block_time)?),
+        QueryMsg::BorrowLimitContribution {
+            collateral_token,
+            amount,
+            block_time,
+        } => to_binary(&query_borrow_limit_contribution(
+            deps,
+            collateral_token,
+            amount,
+            block_time,
+        )?),
+        QueryMsg::EffectiveLtv {
+            borrower,
+            collateral_token,
+            block_time,
+        } => to_binary(&query_effective_ltv(
+            deps,
+            borrower,
+            collateral_token,
+            block_time,
+        )?),
+        QueryMsg::HealthAlert { borrower } => to_binary(&query_health_alert(deps, borrower)?),
+        QueryMsg::BorrowerHealth {
+            borrower,
+            block_height,
+        } => to_binary(&query_borrower_health(deps, borrower, block_height)?),
+        QueryMsg::AccountSummary {
+            borrower,
+            block_height,
+        } => to_binary(&query_account_summary(deps, borrower, block_height)?),
+        QueryMsg::BorrowCapUsage { collateral_token } => {
+            to_binary(&query_borrow_cap_usage(deps, collateral_token)?)
+        }
+        QueryMsg::DebtCeilingUsage {} => to_binary(&query_debt_ceiling_usage(deps)?),
+        QueryMsg::IsolationMode { borrower } => to_binary(&query_isolation_mode(deps, borrower)?),
+        QueryMsg::CollateralPriority { borrower } => {
+            to_binary(&query_collateral_priority(deps, borrower)?)
+        }
+        QueryMsg::LiquidationAuthorization { borrower } => {
+            to_binary(&query_liquidation_authorization(deps, borrower)?)
+        }
+        QueryMsg::FailedLiquidation { borrower } => {
+            to_binary(&query_failed_liquidation(deps, borrower)?)
+        }
+        QueryMsg::FailedLiquidations { start_after, limit } => {
+            to_binary(&query_failed_liquidations(deps, start_after, limit)?)
+        }
+        QueryMsg::LiquidationExecutors { start_after, limit } => {
+            to_binary(&query_liquidation_executors(deps, start_after, limit)?)
+        }
+        QueryMsg::PauseInfo {} => to_binary(&read_pause_info(&deps.storage)?),
+        QueryMsg::HaltRecovery {} => to_binary(&read_halt_recovery(&deps.storage)?),
+        QueryMsg::AccrualHalt { collateral_token } => {
+            to_binary(&query_accrual_halt(deps, collateral_token)?)
+        }
+        QueryMsg::LiquidationTrigger { borrower } => {
+            to_binary(&query_liquidation_trigger(deps, borrower)?)
+        }
     }
 }
 
@@ -515,6 +1155,53 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
         buffer_distribution_factor: config.buffer_distribution_factor,
         anc_purchase_factor: config.anc_purchase_factor,
         price_timeframe: config.price_timeframe,
+        guardian: match config.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?),
+            None => None,
+        },
+        hysteresis_band: config.hysteresis_band,
+        hysteresis_epochs: config.hysteresis_epochs,
+        retry_base_delay: config.retry_base_delay,
+        retry_max_delay: config.retry_max_delay,
+        priority_tolerance: config.priority_tolerance,
+        debt_ceiling: config.debt_ceiling,
+        liquidation_release_ratio: config.liquidation_release_ratio,
+        liquidation_cooldown: config.liquidation_cooldown,
+        emergency_liquidation_ratio: config.emergency_liquidation_ratio,
+        liquidation_authorization_period: config.liquidation_authorization_period,
+    })
+}
+
+pub fn query_rate_smoothing<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<RateSmoothingResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let rate_smoothing: RateSmoothing = read_rate_smoothing(&deps.storage)?;
+    Ok(RateSmoothingResponse {
+        subsidy_active: rate_smoothing.subsidy_active,
+        deviation_epochs: rate_smoothing.deviation_epochs,
+        hysteresis_band: config.hysteresis_band,
+        hysteresis_epochs: config.hysteresis_epochs,
+    })
+}
+
+pub fn query_simulate_epoch_operations<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    block_height: u64,
+) -> StdResult<SimulateEpochOperationsResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let contract_addr = deps.api.human_address(&config.contract_addr)?;
+    let epoch_operations = compute_epoch_operations(deps, &contract_addr, block_height)?;
+
+    Ok(SimulateEpochOperationsResponse {
+        deposit_rate: epoch_operations.deposit_rate,
+        target_deposit_rate: config.target_deposit_rate,
+        subsidy_active: epoch_operations.rate_smoothing.subsidy_active,
+        anc_purchase_amount: epoch_operations.anc_purchase_amount,
+        locked_interest_funding: epoch_operations.locked_interest_funding,
+        distributed_interest: epoch_operations.distributed_interest,
+        interest_buffer: epoch_operations.interest_buffer,
+        messages: epoch_operations.messages,
     })
 }
 
@@ -524,6 +1211,18 @@ pub fn query_state<S: Storage, A: Api, Q: Querier>(
     read_epoch_state(&deps.storage)
 }
 
+pub fn query_debt_ceiling_usage<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DebtCeilingUsageResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let market_state = query_market_state(deps, &deps.api.human_address(&config.market_contract)?)?;
+
+    Ok(DebtCeilingUsageResponse {
+        debt_ceiling: config.debt_ceiling,
+        total_liabilities: Uint256::one() * market_state.total_liabilities,
+    })
+}
+
 pub fn query_whitelist<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     collateral_token: Option<HumanAddr>,
@@ -540,7 +1239,15 @@ pub fn query_whitelist<S: Storage, A: Api, Q: Querier>(
                 name: whitelist_elem.name,
                 symbol: whitelist_elem.symbol,
                 max_ltv: whitelist_elem.max_ltv,
+                liquidation_ltv: whitelist_elem
+                    .liquidation_ltv
+                    .unwrap_or(whitelist_elem.max_ltv),
+                ltv_tiers: whitelist_elem.ltv_tiers,
+                borrow_cap: whitelist_elem.borrow_cap,
+                isolated: whitelist_elem.isolated,
                 custody_contract: deps.api.human_address(&whitelist_elem.custody_contract)?,
+                multi_custody: whitelist_elem.multi_custody,
+                decimals: whitelist_elem.decimals,
                 collateral_token,
             }],
         })
@@ -551,14 +1258,14 @@ pub fn query_whitelist<S: Storage, A: Api, Q: Querier>(
             None
         };
 
-        let whitelist: Vec<WhitelistResponseElem> = read_whitelist(&deps, start_after, limit)?;
+        let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, start_after, limit)?;
         Ok(WhitelistResponse { elems: whitelist })
     }
 }
 
 pub fn migrate<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    _env: Env,
+    env: Env,
     msg: MigrateMsg,
 ) -> MigrateResult {
     let config: Config = read_config(&deps.storage)?;
@@ -567,9 +1274,11 @@ pub fn migrate<S: Storage, A: Api, Q: Querier>(
         &Config {
             target_deposit_rate: msg.target_deposit_rate,
             threshold_deposit_rate: msg.threshold_deposit_rate,
+            contract_addr: deps.api.canonical_address(&env.contract.address)?,
             ..config
         },
     )?;
+    rebuild_total_collaterals(&mut deps.storage)?;
 
     Ok(MigrateResponse::default())
 }
@@ -0,0 +1,159 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::state::{
+    has_liquidation_executors, read_config, read_executor_only, read_liquidation_executor,
+    read_liquidation_executors, remove_liquidation_executor, store_executor_only,
+    store_liquidation_executor, Config,
+};
+
+use moneymarket::overseer::{LiquidationExecutorResponse, LiquidationExecutorsResponse};
+
+pub fn register_liquidation_executor<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    executor: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let executor_raw = deps.api.canonical_address(&executor)?;
+    if read_liquidation_executor(&deps.storage, &executor_raw)?.is_some() {
+        return Err(StdError::generic_err(format!(
+            "Liquidation executor is already registered: {}",
+            executor
+        )));
+    }
+
+    store_liquidation_executor(&mut deps.storage, &executor_raw, env.block.height)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "add_liquidation_executor"),
+            log("executor", executor),
+        ],
+        data: None,
+    })
+}
+
+pub fn deregister_liquidation_executor<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    executor: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    let executor_raw = deps.api.canonical_address(&executor)?;
+    if read_liquidation_executor(&deps.storage, &executor_raw)?.is_none() {
+        return Err(StdError::generic_err(format!(
+            "Liquidation executor is not registered: {}",
+            executor
+        )));
+    }
+
+    if read_executor_only(&deps.storage)?
+        && read_liquidation_executors(&deps.storage, None, Some(2))?.len() < 2
+    {
+        return Err(StdError::generic_err(
+            "Cannot remove the last liquidation executor while liquidations are executor only",
+        ));
+    }
+
+    remove_liquidation_executor(&mut deps.storage, &executor_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "remove_liquidation_executor"),
+            log("executor", executor),
+        ],
+        data: None,
+    })
+}
+
+/// Restrict liquidations to the registered executors, or open them
+/// to anyone again. Registering executors alone changes nothing
+pub fn set_executor_only<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    executor_only: bool,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner_addr {
+        return Err(StdError::unauthorized());
+    }
+
+    if executor_only && !has_liquidation_executors(&deps.storage) {
+        return Err(StdError::generic_err(
+            "No liquidation executors are registered",
+        ));
+    }
+
+    store_executor_only(&mut deps.storage, executor_only)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_executor_only"),
+            log("executor_only", executor_only),
+        ],
+        data: None,
+    })
+}
+
+/// Liquidations are open to anyone unless the owner
+/// has restricted them to the registered executors
+pub(crate) fn assert_liquidation_executor<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: &HumanAddr,
+) -> StdResult<()> {
+    if !read_executor_only(&deps.storage)? {
+        return Ok(());
+    }
+
+    let sender_raw = deps.api.canonical_address(sender)?;
+    if read_liquidation_executor(&deps.storage, &sender_raw)?.is_none() {
+        return Err(StdError::generic_err(format!(
+            "Sender is not a registered liquidation executor: {}",
+            sender
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn query_liquidation_executors<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<LiquidationExecutorsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let executors: Vec<LiquidationExecutorResponse> =
+        read_liquidation_executors(&deps.storage, start_after, limit)?
+            .into_iter()
+            .map(|(executor, added_at)| {
+                Ok(LiquidationExecutorResponse {
+                    executor: deps.api.human_address(&executor)?,
+                    added_at,
+                })
+            })
+            .collect::<StdResult<Vec<LiquidationExecutorResponse>>>()?;
+
+    Ok(LiquidationExecutorsResponse {
+        executor_only: read_executor_only(&deps.storage)?,
+        executors,
+    })
+}
@@ -1,6 +1,11 @@
+pub mod accrual;
+pub mod authorization;
 pub mod collateral;
 pub mod contract;
+pub mod executor;
+pub mod notification;
 pub mod querier;
+pub mod retry;
 pub mod state;
 
 #[cfg(test)]
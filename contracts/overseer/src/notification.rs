@@ -0,0 +1,211 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, LogAttribute,
+    Querier, StdError, StdResult, Storage,
+};
+
+use crate::collateral::compute_borrow_limit;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collaterals, read_config, read_epoch_operations_cursor, read_health_alert,
+    read_health_alerts, remove_health_alert, store_epoch_operations_cursor, store_health_alert,
+    Config, EpochOperationsCursor, HealthAlert, EPOCH_OPERATIONS_BATCH_SIZE,
+};
+
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::{EpochOperationsProgressResponse, HealthAlertResponse};
+use moneymarket::tokens::Tokens;
+
+pub fn register_health_alert<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    threshold: Decimal256,
+) -> HandleResult {
+    if threshold == Decimal256::zero() {
+        return Err(StdError::generic_err(
+            "Health alert threshold must be bigger than zero",
+        ));
+    }
+
+    let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
+    store_health_alert(
+        &mut deps.storage,
+        &borrower_raw,
+        &HealthAlert {
+            threshold,
+            triggered: false,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "register_health_alert"),
+            log("borrower", env.message.sender),
+            log("threshold", threshold),
+        ],
+        data: None,
+    })
+}
+
+pub fn deregister_health_alert<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let borrower_raw = deps.api.canonical_address(&env.message.sender)?;
+    read_health_alert(&deps.storage, &borrower_raw)?;
+    remove_health_alert(&mut deps.storage, &borrower_raw);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "deregister_health_alert"),
+            log("borrower", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+/// Compare the health factor of the next batch of registered borrowers
+/// with their thresholds, advancing the cursor, and return the logs for
+/// the alerts which changed side since the last epoch.
+/// A borrower whose health cannot be computed is skipped, so a single
+/// broken position never blocks the epoch operations.
+pub(crate) fn check_health_alerts<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    cursor: &mut EpochOperationsCursor,
+) -> StdResult<Vec<LogAttribute>> {
+    let config: Config = read_config(&deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let health_alerts = read_health_alerts(
+        &deps.storage,
+        cursor.last_borrower.clone(),
+        EPOCH_OPERATIONS_BATCH_SIZE,
+    )?;
+    cursor.completed = health_alerts.len() < EPOCH_OPERATIONS_BATCH_SIZE as usize;
+    cursor.processed_borrowers += health_alerts.len() as u64;
+    if let Some((borrower_raw, _)) = health_alerts.last() {
+        cursor.last_borrower = Some(borrower_raw.clone());
+    }
+    store_epoch_operations_cursor(&mut deps.storage, cursor)?;
+
+    let mut logs: Vec<LogAttribute> = vec![];
+    for (borrower_raw, mut health_alert) in health_alerts {
+        let health_factor = match compute_health_factor(deps, &market, &borrower_raw, env) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        // no loan means nothing to be notified about
+        let is_unhealthy = match health_factor {
+            Some(health_factor) => health_factor < health_alert.threshold,
+            None => false,
+        };
+
+        if is_unhealthy == health_alert.triggered {
+            continue;
+        }
+
+        let borrower = deps.api.human_address(&borrower_raw)?;
+        if is_unhealthy {
+            logs.push(log("health_alert", borrower));
+        } else {
+            logs.push(log("health_recovered", borrower));
+        }
+
+        health_alert.triggered = is_unhealthy;
+        store_health_alert(&mut deps.storage, &borrower_raw, &health_alert)?;
+    }
+
+    Ok(logs)
+}
+
+fn compute_health_factor<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market: &HumanAddr,
+    borrower_raw: &CanonicalAddr,
+    env: &Env,
+) -> StdResult<Option<Decimal256>> {
+    let borrower = deps.api.human_address(borrower_raw)?;
+    let borrower_info: BorrowerInfoResponse =
+        query_borrower_info(deps, market, &borrower, env.block.height)?;
+    if borrower_info.loan_amount.is_zero() {
+        return Ok(None);
+    }
+
+    let collaterals: Tokens = read_collaterals(&deps.storage, borrower_raw);
+    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, Some(env.block.time))?;
+
+    // health_factor = borrow_limit / loan_amount
+    Ok(Some(
+        Decimal256::from_uint256(borrow_limit)
+            / Decimal256::from_uint256(borrower_info.loan_amount),
+    ))
+}
+
+pub fn query_health_alert<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<HealthAlertResponse> {
+    let health_alert: HealthAlert =
+        read_health_alert(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    Ok(HealthAlertResponse {
+        borrower,
+        threshold: health_alert.threshold,
+        triggered: health_alert.triggered,
+    })
+}
+
+/// Continue the per-borrower epoch operations left unfinished by
+/// the last ExecuteEpochOperations. Callable by anyone
+pub fn resume_epoch_operations<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> HandleResult {
+    let mut cursor = match read_epoch_operations_cursor(&deps.storage)? {
+        Some(cursor) if !cursor.completed => cursor,
+        _ => return Err(StdError::generic_err("No epoch operations to resume")),
+    };
+
+    let health_alert_logs = check_health_alerts(deps, &env, &mut cursor)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: [
+            vec![
+                log("action", "resume_epoch_operations"),
+                log("epoch_height", cursor.epoch_height),
+                log("processed_borrowers", cursor.processed_borrowers),
+                log("completed", cursor.completed),
+            ],
+            health_alert_logs,
+        ]
+        .concat(),
+        data: None,
+    })
+}
+
+pub fn query_epoch_operations_progress<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<EpochOperationsProgressResponse> {
+    let cursor = match read_epoch_operations_cursor(&deps.storage)? {
+        Some(cursor) => cursor,
+        None => EpochOperationsCursor {
+            completed: true,
+            ..EpochOperationsCursor::default()
+        },
+    };
+
+    Ok(EpochOperationsProgressResponse {
+        epoch_height: cursor.epoch_height,
+        last_borrower: match cursor.last_borrower {
+            Some(v) => Some(deps.api.human_address(&v)?),
+            None => None,
+        },
+        processed_borrowers: cursor.processed_borrowers,
+        completed: cursor.completed,
+    })
+}
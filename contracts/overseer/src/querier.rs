@@ -3,8 +3,15 @@ use cosmwasm_std::{
     to_binary, Api, Extern, HumanAddr, Querier, QueryRequest, StdResult, Storage, WasmQuery,
 };
 
-use moneymarket::liquidation::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
-use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse, QueryMsg as MarketQueryMsg};
+use moneymarket::custody::{BorrowerResponse, QueryMsg as CustodyQueryMsg};
+use moneymarket::custody_multi::QueryMsg as CustodyMultiQueryMsg;
+use moneymarket::liquidation::{
+    LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg, SimulateLiquidationResponse,
+};
+use moneymarket::market::{
+    AccrualFreezeResponse, BorrowerInfoResponse, EpochStateResponse, QueryMsg as MarketQueryMsg,
+    StateResponse,
+};
 use moneymarket::tokens::TokensHuman;
 
 pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
@@ -18,13 +25,41 @@ pub fn query_epoch_state<S: Storage, A: Api, Q: Querier>(
             contract_addr: HumanAddr::from(market_addr),
             msg: to_binary(&MarketQueryMsg::EpochState {
                 block_height: Some(block_height),
-                distributed_interest: distributed_interest,
+                distributed_interest,
             })?,
         }))?;
 
     Ok(epoch_state)
 }
 
+pub fn query_market_state<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market_addr: &HumanAddr,
+) -> StdResult<StateResponse> {
+    let state: StateResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(market_addr),
+        msg: to_binary(&MarketQueryMsg::State { block_height: None })?,
+    }))?;
+
+    Ok(state)
+}
+
+pub fn query_accrual_freeze<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market_addr: &HumanAddr,
+    borrower: &HumanAddr,
+) -> StdResult<AccrualFreezeResponse> {
+    let freeze: AccrualFreezeResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(market_addr),
+            msg: to_binary(&MarketQueryMsg::AccrualFreeze {
+                borrower: HumanAddr::from(borrower),
+            })?,
+        }))?;
+
+    Ok(freeze)
+}
+
 /// Query borrow amount from the market contract
 pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
@@ -44,6 +79,31 @@ pub fn query_borrower_info<S: Storage, A: Api, Q: Querier>(
     Ok(borrower_amount)
 }
 
+/// Query the collateral balance of the borrower from its custody contract
+pub fn query_custody_borrower<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    custody_addr: &HumanAddr,
+    collateral_token: &HumanAddr,
+    multi_custody: bool,
+    borrower: &HumanAddr,
+) -> StdResult<BorrowerResponse> {
+    let msg = if multi_custody {
+        to_binary(&CustodyMultiQueryMsg::Borrower {
+            collateral_token: HumanAddr::from(collateral_token),
+            address: HumanAddr::from(borrower),
+        })?
+    } else {
+        to_binary(&CustodyQueryMsg::Borrower {
+            address: HumanAddr::from(borrower),
+        })?
+    };
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(custody_addr),
+        msg,
+    }))
+}
+
 #[allow(clippy::ptr_arg)]
 pub fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
@@ -66,3 +126,25 @@ pub fn query_liquidation_amount<S: Storage, A: Api, Q: Querier>(
 
     Ok(liquidation_amount_res)
 }
+
+/// Dry run of selling the collateral to the bid of `bidder`
+pub fn query_simulate_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    liquidation_contract: &HumanAddr,
+    collateral_token: &HumanAddr,
+    collateral_amount: Uint256,
+    bidder: &HumanAddr,
+) -> StdResult<SimulateLiquidationResponse> {
+    let simulation: SimulateLiquidationResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: HumanAddr::from(liquidation_contract),
+            msg: to_binary(&LiquidationQueryMsg::SimulateLiquidation {
+                collateral_token: HumanAddr::from(collateral_token),
+                collateral_amount,
+                bidder: HumanAddr::from(bidder),
+                price_override: None,
+            })?,
+        }))?;
+
+    Ok(simulation)
+}
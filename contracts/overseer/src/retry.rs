@@ -0,0 +1,176 @@
+use cosmwasm_std::{
+    log, Api, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier, StdError, StdResult,
+    Storage,
+};
+
+use crate::collateral::{
+    assert_liquidation_cooldown, compute_liquidation_limit, liquidate_collateral,
+};
+use crate::querier::query_borrower_info;
+use crate::state::{
+    may_read_failed_liquidation, read_collaterals, read_config, read_failed_liquidation,
+    read_failed_liquidations, remove_failed_liquidation, store_failed_liquidation, Config,
+    FailedLiquidation,
+};
+
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::{FailedLiquidationResponse, FailedLiquidationsResponse};
+use moneymarket::tokens::Tokens;
+
+/// Record a liquidation which could not be executed, and schedule
+/// its next retry with an exponential backoff. Failures before the
+/// pending retry is due are rejected, so they cannot push it back
+pub fn defer_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+    err: StdError,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+
+    // a borrower without a loan can never be liquidated,
+    // so there is nothing to retry
+    let market = deps.api.human_address(&config.market_contract)?;
+    let borrower_info: BorrowerInfoResponse =
+        query_borrower_info(deps, &market, &borrower, env.block.height)?;
+    if borrower_info.loan_amount.is_zero() {
+        return Err(err);
+    }
+
+    let attempts = match may_read_failed_liquidation(&deps.storage, &borrower_raw)? {
+        Some(failed_liquidation) if env.block.time < failed_liquidation.next_retry_at => {
+            return Err(StdError::generic_err(format!(
+                "Liquidation cannot be retried until {}",
+                failed_liquidation.next_retry_at
+            )));
+        }
+        Some(failed_liquidation) => failed_liquidation.attempts + 1,
+        None => 1,
+    };
+
+    let delay = std::cmp::min(
+        config
+            .retry_base_delay
+            .saturating_mul(2u64.saturating_pow(attempts - 1)),
+        config.retry_max_delay,
+    );
+
+    let reason = err.to_string();
+    let next_retry_at = env.block.time + delay;
+    store_failed_liquidation(
+        &mut deps.storage,
+        &borrower_raw,
+        &FailedLiquidation {
+            reason: reason.clone(),
+            attempts,
+            next_retry_at,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "defer_liquidation"),
+            log("borrower", borrower),
+            log("reason", reason),
+            log("attempts", attempts),
+            log("next_retry_at", next_retry_at),
+        ],
+        data: None,
+    })
+}
+
+/// Retries are open to anyone, unless the owner restricted
+/// liquidations to the registered executors
+pub fn retry_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    borrower: HumanAddr,
+) -> HandleResult {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let failed_liquidation: FailedLiquidation =
+        read_failed_liquidation(&deps.storage, &borrower_raw)?;
+    if env.block.time < failed_liquidation.next_retry_at {
+        return Err(StdError::generic_err(format!(
+            "Liquidation cannot be retried until {}",
+            failed_liquidation.next_retry_at
+        )));
+    }
+
+    // Drop the retry when the loan became safe in the meantime
+    let cur_collaterals: Tokens = read_collaterals(&deps.storage, &borrower_raw);
+    if let Ok((_, liquidation_limit, _)) =
+        compute_liquidation_limit(deps, &cur_collaterals, Some(env.block.time))
+    {
+        let config: Config = read_config(&deps.storage)?;
+        let market = deps.api.human_address(&config.market_contract)?;
+        let borrower_info: BorrowerInfoResponse =
+            query_borrower_info(deps, &market, &borrower, env.block.height)?;
+        if liquidation_limit >= borrower_info.loan_amount {
+            remove_failed_liquidation(&mut deps.storage, &borrower_raw);
+            return Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "cancel_liquidation_retry"),
+                    log("borrower", borrower),
+                ],
+                data: None,
+            });
+        }
+
+        assert_liquidation_cooldown(
+            &deps.storage,
+            &borrower_raw,
+            env.block.time,
+            borrower_info.loan_amount,
+            liquidation_limit,
+        )?;
+    }
+
+    liquidate_collateral(deps, env, borrower)
+}
+
+pub fn query_failed_liquidation<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    borrower: HumanAddr,
+) -> StdResult<FailedLiquidationResponse> {
+    let failed_liquidation: FailedLiquidation =
+        read_failed_liquidation(&deps.storage, &deps.api.canonical_address(&borrower)?)?;
+
+    Ok(FailedLiquidationResponse {
+        borrower,
+        reason: failed_liquidation.reason,
+        attempts: failed_liquidation.attempts,
+        next_retry_at: failed_liquidation.next_retry_at,
+    })
+}
+
+pub fn query_failed_liquidations<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<FailedLiquidationsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let failed_liquidations: Vec<FailedLiquidationResponse> =
+        read_failed_liquidations(&deps.storage, start_after, limit)?
+            .into_iter()
+            .map(|(borrower, failed_liquidation)| {
+                Ok(FailedLiquidationResponse {
+                    borrower: deps.api.human_address(&borrower)?,
+                    reason: failed_liquidation.reason,
+                    attempts: failed_liquidation.attempts,
+                    next_retry_at: failed_liquidation.next_retry_at,
+                })
+            })
+            .collect::<StdResult<Vec<FailedLiquidationResponse>>>()?;
+
+    Ok(FailedLiquidationsResponse {
+        failed_liquidations,
+    })
+}
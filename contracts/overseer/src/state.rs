@@ -7,14 +7,34 @@ use cosmwasm_std::{
 };
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
-use moneymarket::tokens::Tokens;
+use moneymarket::decimals::default_decimals;
+use moneymarket::overseer::{CollateralsResponse, LtvTier, WhitelistResponseElem};
+use moneymarket::pause::PauseInfo;
+use moneymarket::tokens::{Tokens, TokensMath};
 
 const KEY_CONFIG: &[u8] = b"config";
 const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const KEY_RATE_SMOOTHING: &[u8] = b"rate_smoothing";
+const KEY_EPOCH_OPERATIONS_CURSOR: &[u8] = b"epoch_operations_cursor";
+const KEY_EXECUTOR_ONLY: &[u8] = b"executor_only";
+
+/// Number of borrowers processed by a single epoch operations call
+pub const EPOCH_OPERATIONS_BATCH_SIZE: u32 = 30;
 
 const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
+const PREFIX_HEALTH_ALERT: &[u8] = b"health_alert";
+const PREFIX_LIQUIDATION_AUTHORIZATION: &[u8] = b"liquidation_authorization";
+const PREFIX_COLLATERALS_CHECKPOINT: &[u8] = b"collaterals_checkpoint";
+const PREFIX_EPOCH_CHECKPOINT: &[u8] = b"epoch_checkpoint";
+const PREFIX_FAILED_LIQUIDATION: &[u8] = b"failed_liquidation";
+const PREFIX_COLLATERAL_PRIORITY: &[u8] = b"collateral_priority";
+const PREFIX_TOTAL_COLLATERAL: &[u8] = b"total_collateral";
+const PREFIX_ACCRUAL_HALT: &[u8] = b"accrual_halt";
+const PREFIX_LIQUIDATION_TRIGGER: &[u8] = b"liquidation_trigger";
+const PREFIX_LAST_LIQUIDATED: &[u8] = b"last_liquidated";
+const PREFIX_LIQUIDATION_EXECUTOR: &[u8] = b"liquidation_executor";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -30,6 +50,26 @@ pub struct Config {
     pub buffer_distribution_factor: Decimal256,
     pub anc_purchase_factor: Decimal256,
     pub price_timeframe: u64,
+    pub guardian: Option<CanonicalAddr>,
+    pub hysteresis_band: Decimal256,
+    pub hysteresis_epochs: u64,
+    pub retry_base_delay: u64,
+    pub retry_max_delay: u64,
+    pub priority_tolerance: Decimal256,
+    pub debt_ceiling: Option<Uint256>,
+    pub liquidation_release_ratio: Decimal256,
+    pub liquidation_cooldown: u64,
+    pub emergency_liquidation_ratio: Decimal256,
+    #[serde(default = "default_liquidation_authorization_period")]
+    pub liquidation_authorization_period: u64,
+    /// Address of the overseer itself, which queries cannot read
+    /// from the environment; set at init and on migration
+    #[serde(default)]
+    pub contract_addr: CanonicalAddr,
+}
+
+fn default_liquidation_authorization_period() -> u64 {
+    10u64
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -41,12 +81,69 @@ pub struct EpochState {
     pub last_executed_height: u64,
 }
 
+/// Deposit rate subsidy switch, which only flips after the
+/// deposit rate stayed beyond the hysteresis band long enough
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct RateSmoothing {
+    pub subsidy_active: bool,
+    pub deviation_epochs: u64,
+}
+
+/// Progress of the per-borrower part of the epoch operations, which
+/// runs in batches so it never exceeds the block gas limit
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct EpochOperationsCursor {
+    pub epoch_height: u64,
+    pub last_borrower: Option<CanonicalAddr>,
+    pub processed_borrowers: u64,
+    pub completed: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct WhitelistElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    /// Liquidation LTV above `max_ltv`; loans between the two
+    /// cannot borrow more but are not liquidated yet
+    #[serde(default)]
+    pub liquidation_ltv: Option<Decimal256>,
     pub custody_contract: CanonicalAddr,
+    pub borrow_cap: Option<Uint256>,
+    /// An isolated collateral is the only one of its positions
+    #[serde(default)]
+    pub isolated: bool,
+    #[serde(default)]
+    pub ltv_tiers: Vec<LtvTier>,
+    /// The custody contract holds several collaterals and
+    /// takes custody messages wrapped with the token
+    #[serde(default)]
+    pub multi_custody: bool,
+    /// Decimals of the collateral token; prices are
+    /// quoted per 6-decimal unit
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HealthAlert {
+    pub threshold: Decimal256,
+    pub triggered: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationAuthorization {
+    pub collaterals: Tokens,
+    pub loan_amount: Uint256,
+    pub expires_at: u64,
+}
+
+/// Liquidation deferred by a failed price query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedLiquidation {
+    pub reason: String,
+    pub attempts: u32,
+    pub next_retry_at: u64,
 }
 
 pub fn store_config<S: Storage>(storage: &mut S, data: &Config) -> StdResult<()> {
@@ -65,13 +162,46 @@ pub fn read_epoch_state<S: Storage>(storage: &S) -> StdResult<EpochState> {
     ReadonlySingleton::new(storage, KEY_EPOCH_STATE).load()
 }
 
+pub fn store_rate_smoothing<S: Storage>(storage: &mut S, data: &RateSmoothing) -> StdResult<()> {
+    Singleton::new(storage, KEY_RATE_SMOOTHING).save(data)
+}
+
+pub fn read_rate_smoothing<S: Storage>(storage: &S) -> StdResult<RateSmoothing> {
+    Ok(ReadonlySingleton::new(storage, KEY_RATE_SMOOTHING)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_epoch_operations_cursor<S: Storage>(
+    storage: &mut S,
+    data: &EpochOperationsCursor,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_EPOCH_OPERATIONS_CURSOR).save(data)
+}
+
+pub fn read_epoch_operations_cursor<S: Storage>(
+    storage: &S,
+) -> StdResult<Option<EpochOperationsCursor>> {
+    ReadonlySingleton::new(storage, KEY_EPOCH_OPERATIONS_CURSOR).may_load()
+}
+
+pub fn store_pause_info<S: Storage>(storage: &mut S, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info<S: Storage>(storage: &S) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_whitelist_elem<S: Storage>(
     storage: &mut S,
     collateral_token: &CanonicalAddr,
     whitelist_elem: &WhitelistElem,
 ) -> StdResult<()> {
     let mut whitelist_bucket: Bucket<S, WhitelistElem> = Bucket::new(PREFIX_WHITELIST, storage);
-    whitelist_bucket.save(collateral_token.as_slice(), &whitelist_elem)?;
+    whitelist_bucket.save(collateral_token.as_slice(), whitelist_elem)?;
 
     Ok(())
 }
@@ -82,7 +212,7 @@ pub fn read_whitelist_elem<S: Storage>(
 ) -> StdResult<WhitelistElem> {
     let whitelist_bucket: ReadonlyBucket<S, WhitelistElem> =
         ReadonlyBucket::new(PREFIX_WHITELIST, storage);
-    match whitelist_bucket.load(&collateral_token.as_slice()) {
+    match whitelist_bucket.load(collateral_token.as_slice()) {
         Ok(v) => Ok(v),
         _ => Err(StdError::generic_err(
             "Token is not registered as collateral",
@@ -113,7 +243,13 @@ pub fn read_whitelist<S: Storage, A: Api, Q: Querier>(
                 symbol: v.symbol,
                 collateral_token,
                 custody_contract,
+                multi_custody: v.multi_custody,
+                decimals: v.decimals,
                 max_ltv: v.max_ltv,
+                liquidation_ltv: v.liquidation_ltv.unwrap_or(v.max_ltv),
+                ltv_tiers: v.ltv_tiers,
+                borrow_cap: v.borrow_cap,
+                isolated: v.isolated,
             })
         })
         .collect()
@@ -126,10 +262,10 @@ pub fn store_collaterals<S: Storage>(
     collaterals: &Tokens,
 ) -> StdResult<()> {
     let mut collaterals_bucket: Bucket<S, Tokens> = Bucket::new(PREFIX_COLLATERALS, storage);
-    if collaterals.len() == 0 {
-        collaterals_bucket.remove(&borrower.as_slice());
+    if collaterals.is_empty() {
+        collaterals_bucket.remove(borrower.as_slice());
     } else {
-        collaterals_bucket.save(&borrower.as_slice(), &collaterals)?;
+        collaterals_bucket.save(borrower.as_slice(), collaterals)?;
     }
 
     Ok(())
@@ -138,10 +274,323 @@ pub fn store_collaterals<S: Storage>(
 pub fn read_collaterals<S: Storage>(storage: &S, borrower: &CanonicalAddr) -> Tokens {
     let collaterals_bucket: ReadonlyBucket<S, Tokens> =
         ReadonlyBucket::new(PREFIX_COLLATERALS, storage);
-    match collaterals_bucket.load(&borrower.as_slice()) {
+    collaterals_bucket
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}
+
+/// Keep the collaterals of the borrower at the given height,
+/// so positions can be reported at past heights
+#[allow(clippy::ptr_arg)]
+pub fn store_collaterals_checkpoint<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+    collaterals: &Tokens,
+) -> StdResult<()> {
+    let mut checkpoint_bucket: Bucket<S, Tokens> = Bucket::multilevel(
+        &[PREFIX_COLLATERALS_CHECKPOINT, borrower.as_slice()],
+        storage,
+    );
+    checkpoint_bucket.save(&block_height.to_be_bytes(), collaterals)
+}
+
+/// Latest collaterals checkpoint at or before the given height
+pub fn read_collaterals_checkpoint<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+) -> StdResult<Option<(u64, Tokens)>> {
+    let checkpoint_bucket: ReadonlyBucket<S, Tokens> = ReadonlyBucket::multilevel(
+        &[PREFIX_COLLATERALS_CHECKPOINT, borrower.as_slice()],
+        storage,
+    );
+    read_checkpoint(&checkpoint_bucket, block_height)
+}
+
+pub fn store_epoch_checkpoint<S: Storage>(
+    storage: &mut S,
+    block_height: u64,
+    epoch_state: &EpochState,
+) -> StdResult<()> {
+    let mut checkpoint_bucket: Bucket<S, EpochState> =
+        Bucket::new(PREFIX_EPOCH_CHECKPOINT, storage);
+    checkpoint_bucket.save(&block_height.to_be_bytes(), epoch_state)
+}
+
+/// Latest epoch state checkpoint at or before the given height
+pub fn read_epoch_checkpoint<S: Storage>(
+    storage: &S,
+    block_height: u64,
+) -> StdResult<Option<(u64, EpochState)>> {
+    let checkpoint_bucket: ReadonlyBucket<S, EpochState> =
+        ReadonlyBucket::new(PREFIX_EPOCH_CHECKPOINT, storage);
+    read_checkpoint(&checkpoint_bucket, block_height)
+}
+
+fn read_checkpoint<S: Storage, T: serde::de::DeserializeOwned + serde::Serialize>(
+    checkpoint_bucket: &ReadonlyBucket<S, T>,
+    block_height: u64,
+) -> StdResult<Option<(u64, T)>> {
+    // heights are stored big endian, so the first element
+    // in descending order below the end bound is the nearest one
+    let end = (block_height + 1).to_be_bytes();
+    match checkpoint_bucket
+        .range(None, Some(&end), Order::Descending)
+        .next()
+    {
+        Some(elem) => {
+            let (k, v) = elem?;
+            let mut height_bytes = [0u8; 8];
+            height_bytes.copy_from_slice(&k);
+            Ok(Some((u64::from_be_bytes(height_bytes), v)))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn store_health_alert<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    health_alert: &HealthAlert,
+) -> StdResult<()> {
+    let mut health_alert_bucket: Bucket<S, HealthAlert> = Bucket::new(PREFIX_HEALTH_ALERT, storage);
+    health_alert_bucket.save(borrower.as_slice(), health_alert)
+}
+
+pub fn remove_health_alert<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut health_alert_bucket: Bucket<S, HealthAlert> = Bucket::new(PREFIX_HEALTH_ALERT, storage);
+    health_alert_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_health_alert<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<HealthAlert> {
+    let health_alert_bucket: ReadonlyBucket<S, HealthAlert> =
+        ReadonlyBucket::new(PREFIX_HEALTH_ALERT, storage);
+    match health_alert_bucket.load(borrower.as_slice()) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("No health alert registered")),
+    }
+}
+
+pub fn read_health_alerts<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: u32,
+) -> StdResult<Vec<(CanonicalAddr, HealthAlert)>> {
+    let health_alert_bucket: ReadonlyBucket<S, HealthAlert> =
+        ReadonlyBucket::new(PREFIX_HEALTH_ALERT, storage);
+
+    let start = calc_range_start(start_after);
+    health_alert_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit as usize)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_collateral_priority<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    collaterals: &[CanonicalAddr],
+) -> StdResult<()> {
+    let mut priority_bucket: Bucket<S, Vec<CanonicalAddr>> =
+        Bucket::new(PREFIX_COLLATERAL_PRIORITY, storage);
+    if collaterals.is_empty() {
+        priority_bucket.remove(borrower.as_slice());
+        Ok(())
+    } else {
+        priority_bucket.save(borrower.as_slice(), &collaterals.to_vec())
+    }
+}
+
+pub fn read_collateral_priority<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> Vec<CanonicalAddr> {
+    let priority_bucket: ReadonlyBucket<S, Vec<CanonicalAddr>> =
+        ReadonlyBucket::new(PREFIX_COLLATERAL_PRIORITY, storage);
+    priority_bucket
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn store_total_collateral<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    amount: &Uint256,
+) -> StdResult<()> {
+    let mut total_bucket: Bucket<S, Uint256> = Bucket::new(PREFIX_TOTAL_COLLATERAL, storage);
+    total_bucket.save(collateral_token.as_slice(), amount)
+}
+
+pub fn read_total_collateral<S: Storage>(storage: &S, collateral_token: &CanonicalAddr) -> Uint256 {
+    let total_bucket: ReadonlyBucket<S, Uint256> =
+        ReadonlyBucket::new(PREFIX_TOTAL_COLLATERAL, storage);
+    match total_bucket.load(collateral_token.as_slice()) {
         Ok(v) => v,
-        _ => vec![],
+        _ => Uint256::zero(),
+    }
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn increase_total_collaterals<S: Storage>(storage: &mut S, tokens: &Tokens) -> StdResult<()> {
+    for (collateral_token, amount) in tokens.iter() {
+        let total = read_total_collateral(storage, collateral_token) + *amount;
+        store_total_collateral(storage, collateral_token, &total)?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn decrease_total_collaterals<S: Storage>(storage: &mut S, tokens: &Tokens) -> StdResult<()> {
+    for (collateral_token, amount) in tokens.iter() {
+        let total = read_total_collateral(storage, collateral_token);
+        let total = if total > *amount {
+            total - *amount
+        } else {
+            Uint256::zero()
+        };
+        store_total_collateral(storage, collateral_token, &total)?;
+    }
+
+    Ok(())
+}
+
+/// Recompute the locked totals from every borrower position,
+/// for the deployments which did not track them yet
+pub fn rebuild_total_collaterals<S: Storage>(storage: &mut S) -> StdResult<()> {
+    let mut totals: Tokens = vec![];
+    {
+        let collaterals_bucket: ReadonlyBucket<S, Tokens> =
+            ReadonlyBucket::new(PREFIX_COLLATERALS, storage);
+        for elem in collaterals_bucket.range(None, None, Order::Ascending) {
+            let (_, collaterals) = elem?;
+            totals.add(collaterals);
+        }
+    }
+
+    for (collateral_token, amount) in totals.iter() {
+        store_total_collateral(storage, collateral_token, amount)?;
     }
+
+    Ok(())
+}
+
+pub fn store_accrual_halt<S: Storage>(
+    storage: &mut S,
+    collateral_token: &CanonicalAddr,
+    halted_at: u64,
+) -> StdResult<()> {
+    let mut halt_bucket: Bucket<S, u64> = Bucket::new(PREFIX_ACCRUAL_HALT, storage);
+    halt_bucket.save(collateral_token.as_slice(), &halted_at)
+}
+
+pub fn remove_accrual_halt<S: Storage>(storage: &mut S, collateral_token: &CanonicalAddr) {
+    let mut halt_bucket: Bucket<S, u64> = Bucket::new(PREFIX_ACCRUAL_HALT, storage);
+    halt_bucket.remove(collateral_token.as_slice());
+}
+
+/// Height the collateral was halted at, if it is halted
+pub fn read_accrual_halt<S: Storage>(
+    storage: &S,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<Option<u64>> {
+    let halt_bucket: ReadonlyBucket<S, u64> = ReadonlyBucket::new(PREFIX_ACCRUAL_HALT, storage);
+    halt_bucket.may_load(collateral_token.as_slice())
+}
+
+pub fn store_liquidation_trigger<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    triggered_at: u64,
+) -> StdResult<()> {
+    let mut trigger_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LIQUIDATION_TRIGGER, storage);
+    trigger_bucket.save(borrower.as_slice(), &triggered_at)
+}
+
+pub fn remove_liquidation_trigger<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut trigger_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LIQUIDATION_TRIGGER, storage);
+    trigger_bucket.remove(borrower.as_slice());
+}
+
+/// Height the borrower entered liquidation at, if not released yet
+pub fn read_liquidation_trigger<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<u64>> {
+    let trigger_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_TRIGGER, storage);
+    trigger_bucket.may_load(borrower.as_slice())
+}
+
+pub fn store_last_liquidated<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    liquidated_at: u64,
+) -> StdResult<()> {
+    let mut liquidated_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LAST_LIQUIDATED, storage);
+    liquidated_bucket.save(borrower.as_slice(), &liquidated_at)
+}
+
+/// Block time of the last partial liquidation of the borrower
+pub fn read_last_liquidated<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<u64>> {
+    let liquidated_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LAST_LIQUIDATED, storage);
+    liquidated_bucket.may_load(borrower.as_slice())
+}
+
+pub fn store_liquidation_executor<S: Storage>(
+    storage: &mut S,
+    executor: &CanonicalAddr,
+    added_at: u64,
+) -> StdResult<()> {
+    let mut executor_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LIQUIDATION_EXECUTOR, storage);
+    executor_bucket.save(executor.as_slice(), &added_at)
+}
+
+pub fn remove_liquidation_executor<S: Storage>(storage: &mut S, executor: &CanonicalAddr) {
+    let mut executor_bucket: Bucket<S, u64> = Bucket::new(PREFIX_LIQUIDATION_EXECUTOR, storage);
+    executor_bucket.remove(executor.as_slice());
+}
+
+/// Height the executor was registered at, if it is registered
+pub fn read_liquidation_executor<S: Storage>(
+    storage: &S,
+    executor: &CanonicalAddr,
+) -> StdResult<Option<u64>> {
+    let executor_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_EXECUTOR, storage);
+    executor_bucket.may_load(executor.as_slice())
+}
+
+pub fn store_executor_only<S: Storage>(storage: &mut S, executor_only: bool) -> StdResult<()> {
+    Singleton::new(storage, KEY_EXECUTOR_ONLY).save(&executor_only)
+}
+
+pub fn read_executor_only<S: Storage>(storage: &S) -> StdResult<bool> {
+    Ok(ReadonlySingleton::new(storage, KEY_EXECUTOR_ONLY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn has_liquidation_executors<S: Storage>(storage: &S) -> bool {
+    let executor_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_EXECUTOR, storage);
+    let has_executors = executor_bucket
+        .range(None, None, Order::Ascending)
+        .next()
+        .is_some();
+    has_executors
 }
 
 // settings for pagination
@@ -177,6 +626,27 @@ pub fn read_all_collaterals<S: Storage, A: Api, Q: Querier>(
         .collect()
 }
 
+pub fn read_borrowers_collaterals<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Tokens)>> {
+    let collaterals_bucket: ReadonlyBucket<S, Tokens> =
+        ReadonlyBucket::new(PREFIX_COLLATERALS, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    collaterals_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
@@ -185,3 +655,130 @@ fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
         v
     })
 }
+
+pub fn store_liquidation_authorization<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    authorization: &LiquidationAuthorization,
+) -> StdResult<()> {
+    let mut authorization_bucket: Bucket<S, LiquidationAuthorization> =
+        Bucket::new(PREFIX_LIQUIDATION_AUTHORIZATION, storage);
+    authorization_bucket.save(borrower.as_slice(), authorization)
+}
+
+pub fn remove_liquidation_authorization<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut authorization_bucket: Bucket<S, LiquidationAuthorization> =
+        Bucket::new(PREFIX_LIQUIDATION_AUTHORIZATION, storage);
+    authorization_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_liquidation_authorization<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<LiquidationAuthorization> {
+    let authorization_bucket: ReadonlyBucket<S, LiquidationAuthorization> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_AUTHORIZATION, storage);
+    match authorization_bucket.load(borrower.as_slice()) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("No liquidation authorization exists")),
+    }
+}
+
+pub fn store_failed_liquidation<S: Storage>(
+    storage: &mut S,
+    borrower: &CanonicalAddr,
+    failed_liquidation: &FailedLiquidation,
+) -> StdResult<()> {
+    let mut failed_liquidation_bucket: Bucket<S, FailedLiquidation> =
+        Bucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+    failed_liquidation_bucket.save(borrower.as_slice(), failed_liquidation)
+}
+
+pub fn remove_failed_liquidation<S: Storage>(storage: &mut S, borrower: &CanonicalAddr) {
+    let mut failed_liquidation_bucket: Bucket<S, FailedLiquidation> =
+        Bucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+    failed_liquidation_bucket.remove(borrower.as_slice());
+}
+
+pub fn read_failed_liquidation<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<FailedLiquidation> {
+    let failed_liquidation_bucket: ReadonlyBucket<S, FailedLiquidation> =
+        ReadonlyBucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+    match failed_liquidation_bucket.load(borrower.as_slice()) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("No failed liquidation exists")),
+    }
+}
+
+pub fn may_read_failed_liquidation<S: Storage>(
+    storage: &S,
+    borrower: &CanonicalAddr,
+) -> StdResult<Option<FailedLiquidation>> {
+    let failed_liquidation_bucket: ReadonlyBucket<S, FailedLiquidation> =
+        ReadonlyBucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+    failed_liquidation_bucket.may_load(borrower.as_slice())
+}
+
+/// Defer every scheduled retry by the duration of a chain halt
+pub fn shift_failed_liquidations<S: Storage>(storage: &mut S, duration: u64) -> StdResult<()> {
+    let failed_liquidations: Vec<(Vec<u8>, FailedLiquidation)> = {
+        let failed_liquidation_bucket: ReadonlyBucket<S, FailedLiquidation> =
+            ReadonlyBucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+        failed_liquidation_bucket
+            .range(None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Vec<u8>, FailedLiquidation)>>>()?
+    };
+
+    let mut failed_liquidation_bucket: Bucket<S, FailedLiquidation> =
+        Bucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+    for (k, mut failed_liquidation) in failed_liquidations {
+        failed_liquidation.next_retry_at += duration;
+        failed_liquidation_bucket.save(&k, &failed_liquidation)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_failed_liquidations<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, FailedLiquidation)>> {
+    let failed_liquidation_bucket: ReadonlyBucket<S, FailedLiquidation> =
+        ReadonlyBucket::new(PREFIX_FAILED_LIQUIDATION, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    failed_liquidation_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn read_liquidation_executors<S: Storage>(
+    storage: &S,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, u64)>> {
+    let executor_bucket: ReadonlyBucket<S, u64> =
+        ReadonlyBucket::new(PREFIX_LIQUIDATION_EXECUTOR, storage);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    executor_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
@@ -89,9 +89,10 @@ fn proper_compute_borrow_limit() {
     collaterals.push(token2);
 
     let res = compute_borrow_limit(&deps, &collaterals, None).unwrap();
-    let mut vec: Vec<Decimal256> = vec![];
-    vec.push(Decimal256::from_uint256(1000u128));
-    vec.push(Decimal256::from_uint256(2000u128));
+    let vec: Vec<Decimal256> = vec![
+        Decimal256::from_uint256(1000u128),
+        Decimal256::from_uint256(2000u128),
+    ];
 
     let res2 = (Uint256::from(1800000u128), vec);
     assert_eq!(res, res2);
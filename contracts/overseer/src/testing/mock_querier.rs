@@ -1,3 +1,6 @@
+// the mocks are fed with nested tuples of the queried values
+#![allow(clippy::type_complexity)]
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -9,9 +12,12 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use moneymarket::liquidation::LiquidationAmountResponse;
+use moneymarket::custody::BorrowerResponse;
+use moneymarket::liquidation::{
+    LiquidationAmountResponse, SimulateLiquidationResponse, LIQUIDATION_SCHEMA_VERSION,
+};
 use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse};
-use moneymarket::oracle::PriceResponse;
+use moneymarket::oracle::{PriceResponse, ORACLE_SCHEMA_VERSION};
 use moneymarket::tokens::TokensHuman;
 
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
@@ -38,6 +44,15 @@ pub enum QueryMsg {
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
     },
+    /// Query collateral balance to custody contract
+    Borrower { address: HumanAddr },
+    /// Query bid simulation to liquidation contract
+    SimulateLiquidation {
+        collateral_token: HumanAddr,
+        collateral_amount: Uint256,
+        bidder: HumanAddr,
+        price_override: Option<Decimal256>,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -66,6 +81,8 @@ pub struct WasmMockQuerier {
     oracle_price_querier: OraclePriceQuerier,
     loan_amount_querier: LoanAmountQuerier,
     liquidation_percent_querier: LiquidationPercentQuerier,
+    custody_balance_querier: CustodyBalanceQuerier,
+    bid_querier: BidQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -205,6 +222,42 @@ pub(crate) fn liquidation_percent_to_map(
     liquidation_percent_map
 }
 
+#[derive(Clone, Default)]
+pub struct CustodyBalanceQuerier {
+    // (custody contract, borrower) to balance
+    balance: HashMap<(HumanAddr, HumanAddr), Uint256>,
+}
+
+impl CustodyBalanceQuerier {
+    pub fn new(balance: &[(&(HumanAddr, HumanAddr), &Uint256)]) -> Self {
+        let mut balance_map: HashMap<(HumanAddr, HumanAddr), Uint256> = HashMap::new();
+        for (custody_borrower, balance) in balance.iter() {
+            balance_map.insert((*custody_borrower).clone(), **balance);
+        }
+
+        CustodyBalanceQuerier {
+            balance: balance_map,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BidQuerier {
+    // (bidder, collateral token) to (bid amount, premium rate)
+    bids: HashMap<(HumanAddr, HumanAddr), (Uint256, Decimal256)>,
+}
+
+impl BidQuerier {
+    pub fn new(bids: &[(&(HumanAddr, HumanAddr), &(Uint256, Decimal256))]) -> Self {
+        let mut bid_map: HashMap<(HumanAddr, HumanAddr), (Uint256, Decimal256)> = HashMap::new();
+        for (bidder_collateral, bid) in bids.iter() {
+            bid_map.insert((*bidder_collateral).clone(), **bid);
+        }
+
+        BidQuerier { bids: bid_map }
+    }
+}
+
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
         match &request {
@@ -234,14 +287,15 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
-                match from_binary(&msg).unwrap() {
+                match from_binary(msg).unwrap() {
                     QueryMsg::EpochState {
                         block_height: _,
                         distributed_interest: _,
-                    } => match self.epoch_state_querier.epoch_state.get(&contract_addr) {
+                    } => match self.epoch_state_querier.epoch_state.get(contract_addr) {
                         Some(v) => Ok(to_binary(&EpochStateResponse {
                             aterra_supply: v.0,
                             exchange_rate: v.1,
+                            unfunded_locked_interest: Uint256::zero(),
                         })),
                         None => Err(SystemError::InvalidRequest {
                             error: "No epoch state exists".to_string(),
@@ -267,6 +321,7 @@ impl WasmMockQuerier {
                     QueryMsg::Price { base, quote } => {
                         match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                             Some(v) => Ok(to_binary(&PriceResponse {
+                                schema_version: ORACLE_SCHEMA_VERSION,
                                 rate: v.0,
                                 last_updated_base: v.1,
                                 last_updated_quote: v.2,
@@ -286,11 +341,12 @@ impl WasmMockQuerier {
                         match self
                             .liquidation_percent_querier
                             .liquidation_percent
-                            .get(&contract_addr)
+                            .get(contract_addr)
                         {
                             Some(v) => {
                                 if borrow_amount > borrow_limit {
                                     Ok(to_binary(&LiquidationAmountResponse {
+                                        schema_version: LIQUIDATION_SCHEMA_VERSION,
                                         collaterals: collaterals
                                             .iter()
                                             .map(|x| (x.0.clone(), x.1 * *v))
@@ -299,6 +355,7 @@ impl WasmMockQuerier {
                                     }))
                                 } else {
                                     Ok(to_binary(&LiquidationAmountResponse {
+                                        schema_version: LIQUIDATION_SCHEMA_VERSION,
                                         collaterals: vec![],
                                     }))
                                 }
@@ -309,6 +366,76 @@ impl WasmMockQuerier {
                             }),
                         }
                     }
+                    QueryMsg::Borrower { address } => {
+                        let balance = self
+                            .custody_balance_querier
+                            .balance
+                            .get(&(contract_addr.clone(), address.clone()))
+                            .copied()
+                            .unwrap_or_default();
+                        Ok(to_binary(&BorrowerResponse {
+                            borrower: address,
+                            balance,
+                            spendable: balance,
+                        }))
+                    }
+                    QueryMsg::SimulateLiquidation {
+                        collateral_token,
+                        collateral_amount,
+                        bidder,
+                        price_override: _,
+                    } => {
+                        let bid = match self
+                            .bid_querier
+                            .bids
+                            .get(&(bidder, collateral_token.clone()))
+                        {
+                            Some(v) => v,
+                            None => {
+                                return Err(SystemError::InvalidRequest {
+                                    error: "No bids with the specified information exist"
+                                        .to_string(),
+                                    request: msg.as_slice().into(),
+                                })
+                            }
+                        };
+                        let price = match self
+                            .oracle_price_querier
+                            .oracle_price
+                            .get(&(collateral_token.to_string(), "uusd".to_string()))
+                        {
+                            Some(v) => v.0,
+                            None => {
+                                return Err(SystemError::InvalidRequest {
+                                    error: "No oracle price exists".to_string(),
+                                    request: msg.as_slice().into(),
+                                })
+                            }
+                        };
+
+                        // 1% liquidator fee, no bid fee and no tax
+                        let required_stable =
+                            collateral_amount * price * (Decimal256::one() - bid.1);
+                        if required_stable > bid.0 {
+                            return Err(SystemError::InvalidRequest {
+                                error: "Insufficient bid balance".to_string(),
+                                request: msg.as_slice().into(),
+                            });
+                        }
+                        let liquidator_fee = required_stable * Decimal256::percent(1);
+                        Ok(to_binary(&SimulateLiquidationResponse {
+                            schema_version: LIQUIDATION_SCHEMA_VERSION,
+                            collateral_token,
+                            collateral_amount,
+                            price,
+                            premium_rate: bid.1,
+                            required_stable,
+                            repay_amount: required_stable - liquidator_fee,
+                            bid_fee: Uint256::zero(),
+                            liquidator_fee,
+                            remaining_bid_amount: bid.0 - required_stable,
+                        }))
+                    }
                 }
             }
             _ => self.base.handle_query(request),
@@ -325,6 +452,8 @@ impl WasmMockQuerier {
             oracle_price_querier: OraclePriceQuerier::default(),
             loan_amount_querier: LoanAmountQuerier::default(),
             liquidation_percent_querier: LiquidationPercentQuerier::default(),
+            custody_balance_querier: CustodyBalanceQuerier::default(),
+            bid_querier: BidQuerier::default(),
         }
     }
 
@@ -351,4 +480,12 @@ impl WasmMockQuerier {
     pub fn with_liquidation_percent(&mut self, liquidation_percent: &[(&HumanAddr, &Decimal256)]) {
         self.liquidation_percent_querier = LiquidationPercentQuerier::new(liquidation_percent);
     }
+
+    pub fn with_custody_balance(&mut self, balance: &[(&(HumanAddr, HumanAddr), &Uint256)]) {
+        self.custody_balance_querier = CustodyBalanceQuerier::new(balance);
+    }
+
+    pub fn with_bids(&mut self, bids: &[(&(HumanAddr, HumanAddr), &(Uint256, Decimal256))]) {
+        self.bid_querier = BidQuerier::new(bids);
+    }
 }
@@ -11,10 +11,19 @@ use cosmwasm_std::{
 };
 
 use moneymarket::custody::HandleMsg as CustodyHandleMsg;
+use moneymarket::custody_multi::HandleMsg as MultiCustodyHandleMsg;
+use moneymarket::liquidation::HandleMsg as LiquidationHandleMsg;
 use moneymarket::market::HandleMsg as MarketHandleMsg;
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, HandleMsg,
-    InitMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    AccountCollateral, AccountSummaryResponse, AllCollateralsResponse, BorrowCapUsageResponse,
+    BorrowLimitContributionResponse, BorrowLimitResponse, BorrowerHealthResponse, CollateralHealth,
+    CollateralPriorityResponse, CollateralsResponse, ConfigResponse, EffectiveLtvResponse,
+    EpochOperationsProgressResponse, FailedLiquidationResponse, FailedLiquidationsResponse,
+    HandleMsg, HealthAlertResponse, InitMsg, IsolationModeResponse, LiquidatablePosition,
+    LiquidatablePositionsResponse, LiquidationAuthorizationResponse, LiquidationExecutorResponse,
+    LiquidationExecutorsResponse, LiquidationTriggerResponse, LtvTier, PositionSnapshotResponse,
+    ProfitableLiquidation, ProfitableLiquidationsResponse, QueryMsg, RateSmoothingResponse,
+    SimulateEpochOperationsResponse, WhitelistResponse, WhitelistResponseElem,
 };
 use moneymarket::querier::deduct_tax;
 
@@ -61,6 +70,17 @@ fn proper_initialization() {
             buffer_distribution_factor: Decimal256::percent(20),
             anc_purchase_factor: Decimal256::percent(20),
             price_timeframe: 60u64,
+            guardian: None,
+            hysteresis_band: Decimal256::zero(),
+            hysteresis_epochs: 1u64,
+            retry_base_delay: 60u64,
+            retry_max_delay: 3600u64,
+            priority_tolerance: Decimal256::percent(1),
+            debt_ceiling: None,
+            liquidation_release_ratio: Decimal256::one(),
+            liquidation_cooldown: 0u64,
+            emergency_liquidation_ratio: Decimal256::one(),
+            liquidation_authorization_period: 10u64,
         }
     );
 
@@ -101,23 +121,20 @@ fn update_config() {
     // we can just call .unwrap() to assert this was a success
     let _res = init(&mut deps, env, msg).unwrap();
 
-    // update owner
+    // transfer ownership
     let env = mock_env("owner", &[]);
-    let msg = HandleMsg::UpdateConfig {
-        owner_addr: Some(HumanAddr("owner1".to_string())),
-        oracle_contract: None,
-        liquidation_contract: None,
-        threshold_deposit_rate: None,
-        target_deposit_rate: None,
-        buffer_distribution_factor: None,
-        anc_purchase_factor: None,
-        epoch_period: None,
-        price_timeframe: None,
+    let msg = HandleMsg::ProposeOwner {
+        new_owner: HumanAddr("owner1".to_string()),
+        expires_in: 100u64,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
+    let env = mock_env("owner1", &[]);
+    let res = handle(&mut deps, env, HandleMsg::AcceptOwnership {}).unwrap();
+    assert_eq!(0, res.messages.len());
+
     // it worked, let's query the state
     let res = query(&deps, QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&res).unwrap();
@@ -126,7 +143,6 @@ fn update_config() {
     // update left items
     let env = mock_env("owner1", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: Some(HumanAddr("oracle1".to_string())),
         liquidation_contract: Some(HumanAddr("liquidation1".to_string())),
         threshold_deposit_rate: Some(Decimal256::permille(1)),
@@ -135,6 +151,18 @@ fn update_config() {
         anc_purchase_factor: Some(Decimal256::percent(10)),
         epoch_period: Some(100000u64),
         price_timeframe: Some(120u64),
+        guardian: Some(HumanAddr::from("guardian")),
+        hysteresis_band: Some(Decimal256::from_ratio(1u64, 1000000u64)),
+        hysteresis_epochs: Some(3u64),
+        retry_base_delay: Some(30u64),
+        retry_max_delay: Some(600u64),
+        priority_tolerance: Some(Decimal256::percent(5)),
+        debt_ceiling: Some(Uint256::from(1000000000u64)),
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
     };
 
     let res = handle(&mut deps, env, msg).unwrap();
@@ -158,11 +186,50 @@ fn update_config() {
     assert_eq!(Decimal256::percent(10), config_res.anc_purchase_factor);
     assert_eq!(100000u64, config_res.epoch_period);
     assert_eq!(120u64, config_res.price_timeframe);
+    assert_eq!(Some(HumanAddr::from("guardian")), config_res.guardian);
+    assert_eq!(
+        Decimal256::from_ratio(1u64, 1000000u64),
+        config_res.hysteresis_band
+    );
+    assert_eq!(3u64, config_res.hysteresis_epochs);
+    assert_eq!(30u64, config_res.retry_base_delay);
+    assert_eq!(600u64, config_res.retry_max_delay);
+    assert_eq!(Decimal256::percent(5), config_res.priority_tolerance);
+    assert_eq!(Some(Uint256::from(1000000000u64)), config_res.debt_ceiling);
+
+    // the debt ceiling can be removed again
+    let env = mock_env("owner1", &[]);
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: None,
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: Some(true),
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let res = query(&deps, QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(None, config_res.debt_ceiling);
 
     // Unauthorized err
     let env = mock_env("owner", &[]);
     let msg = HandleMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: None,
         liquidation_contract: None,
         threshold_deposit_rate: None,
@@ -171,6 +238,18 @@ fn update_config() {
         anc_purchase_factor: None,
         epoch_period: None,
         price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: None,
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
     };
 
     let res = handle(&mut deps, env, msg);
@@ -250,7 +329,13 @@ fn whitelist() {
                 symbol: "bluna".to_string(),
                 collateral_token: HumanAddr::from("bluna"),
                 custody_contract: HumanAddr::from("custody"),
+                multi_custody: false,
+                decimals: 6,
                 max_ltv: Decimal256::percent(60),
+                liquidation_ltv: Decimal256::percent(60),
+                borrow_cap: None,
+                isolated: false,
+                ltv_tiers: vec![],
             }]
         }
     );
@@ -277,6 +362,13 @@ fn whitelist() {
         collateral_token: HumanAddr::from("bluna"),
         custody_contract: Some(HumanAddr::from("custody2")),
         max_ltv: Some(Decimal256::percent(30)),
+        liquidation_ltv: Some(Decimal256::percent(40)),
+        borrow_cap: Some(Uint256::from(1000000u64)),
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
     };
 
     let env = mock_env("addr0000", &[]);
@@ -295,6 +387,11 @@ fn whitelist() {
             log("collateral_token", "bluna"),
             log("custody_contract", "custody2"),
             log("LTV", "0.3"),
+            log("liquidation_LTV", "0.4"),
+            log("borrow_cap", "1000000"),
+            log("isolated", "false"),
+            log("multi_custody", "false"),
+            log("decimals", "6"),
         ]
     );
 
@@ -316,10 +413,72 @@ fn whitelist() {
                 symbol: "bluna".to_string(),
                 collateral_token: HumanAddr::from("bluna"),
                 custody_contract: HumanAddr::from("custody2"),
+                multi_custody: false,
+                decimals: 6,
                 max_ltv: Decimal256::percent(30),
+                liquidation_ltv: Decimal256::percent(40),
+                borrow_cap: Some(Uint256::from(1000000u64)),
+                isolated: false,
+                ltv_tiers: vec![],
             }]
         }
     );
+
+    // the liquidation LTV must stay above max_ltv
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(40)),
+        liquidation_ltv: None,
+        borrow_cap: None,
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
+    };
+    match handle(&mut deps, mock_env("owner", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Liquidation LTV must be bigger than max LTV and cannot exceed one"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: None,
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: Some(19),
+    };
+    match handle(&mut deps, mock_env("owner", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Collateral decimals cannot exceed 18")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the borrow cap can be removed again
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: None,
+        clear_borrow_cap: Some(true),
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
+    };
+    let res = handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+    assert!(res.log.contains(&log("borrow_cap", "none")));
 }
 
 #[test]
@@ -441,6 +600,7 @@ fn execute_epoch_operations() {
             log("aterra_supply", "1000000"),
             log("distributed_interest", "0"),
             log("anc_purchase_amount", "2000000000"),
+            log("locked_interest_funding", "0"),
         ]
     );
 
@@ -474,6 +634,16 @@ fn execute_epoch_operations() {
     // interest_buffer = 9,999,000,000
     // (125 / 120 - 1) / 86400
     // deposit rate = 0.000000482253086419
+    let simulation: SimulateEpochOperationsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::SimulateEpochOperations {
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
     let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
@@ -532,9 +702,188 @@ fn execute_epoch_operations() {
             log("exchange_rate", "1.25"),
             log("aterra_supply", "1000000"),
             log("distributed_interest", "53148"),
-            log("anc_purchase_amount", "200000")
+            log("anc_purchase_amount", "200000"),
+            log("locked_interest_funding", "0"),
         ]
     );
+
+    // the simulation previews the executed epoch
+    assert_eq!(
+        simulation,
+        SimulateEpochOperationsResponse {
+            deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
+            target_deposit_rate: Decimal256::permille(5),
+            subsidy_active: true,
+            anc_purchase_amount: Uint256::from(200_000u128),
+            locked_interest_funding: Uint256::zero(),
+            distributed_interest: Uint256::from(53148u128),
+            interest_buffer: Uint256::from(9999746320u128),
+            messages: res.messages,
+        }
+    );
+
+    // nothing can be simulated before the epoch passes
+    let res = query(
+        &deps,
+        QueryMsg::SimulateEpochOperations {
+            block_height: env.block.height - 1,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "An epoch has not passed yet; last executed height: {}",
+                env.block.height - 86400
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn deposit_rate_hysteresis() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000000u128),
+        }],
+    );
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: Some(0u64),
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Hysteresis epochs must be bigger than zero")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: Some(2u64),
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    store_epoch_state(
+        &mut deps.storage,
+        &EpochState {
+            last_executed_height: env.block.height,
+            prev_exchange_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_interest_buffer: Uint256::from(10000000000u64),
+            deposit_rate: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    // deposit rate = 0, below the threshold
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+    env.block.height += 86400u64;
+
+    let msg = HandleMsg::ExecuteEpochOperations {};
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert!(res.log.contains(&log("distributed_interest", "0")));
+
+    let res = query(&deps, QueryMsg::RateSmoothing {}).unwrap();
+    let rate_smoothing_res: RateSmoothingResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        rate_smoothing_res,
+        RateSmoothingResponse {
+            subsidy_active: false,
+            deviation_epochs: 1u64,
+            hysteresis_band: Decimal256::zero(),
+            hysteresis_epochs: 2u64,
+        }
+    );
+
+    // second consecutive epoch below the threshold switches the subsidy on
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert!(res.messages.iter().any(|msg| match msg {
+        CosmosMsg::Bank(BankMsg::Send { to_address, .. }) => {
+            *to_address == HumanAddr::from("market")
+        }
+        _ => false,
+    }));
+
+    let res = query(&deps, QueryMsg::RateSmoothing {}).unwrap();
+    let rate_smoothing_res: RateSmoothingResponse = from_binary(&res).unwrap();
+    assert!(rate_smoothing_res.subsidy_active);
+    assert_eq!(rate_smoothing_res.deviation_epochs, 0u64);
+
+    // a single epoch above the threshold does not switch it off
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert!(res.log.contains(&log("distributed_interest", "0")));
+
+    let res = query(&deps, QueryMsg::RateSmoothing {}).unwrap();
+    let rate_smoothing_res: RateSmoothingResponse = from_binary(&res).unwrap();
+    assert!(rate_smoothing_res.subsidy_active);
+    assert_eq!(rate_smoothing_res.deviation_epochs, 1u64);
 }
 
 #[test]
@@ -811,7 +1160,7 @@ fn lock_collateral() {
 }
 
 #[test]
-fn unlock_collateral() {
+fn lock_collateral_multi_custody() {
     let mut deps = mock_dependencies(20, &[]);
 
     let env = mock_env("owner", &[]);
@@ -833,7 +1182,92 @@ fn unlock_collateral() {
     // we can just call .unwrap() to assert this was a success
     let _res = init(&mut deps, env.clone(), msg).unwrap();
 
-    // store whitelist elems
+    // both collaterals are held by one custody
+    for token in ["bluna", "batom"].iter() {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from("custody_multi"),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+        let msg = HandleMsg::UpdateWhitelist {
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: None,
+            max_ltv: None,
+            liquidation_ltv: None,
+            borrow_cap: None,
+            clear_borrow_cap: None,
+            isolated: None,
+            ltv_tiers: None,
+            multi_custody: Some(true),
+            decimals: None,
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_multi"),
+                send: vec![],
+                msg: to_binary(&MultiCustodyHandleMsg::Collateral {
+                    collateral_token: HumanAddr::from("bluna"),
+                    msg: CustodyHandleMsg::LockCollateral {
+                        borrower: HumanAddr::from("addr0000"),
+                        amount: Uint256::from(1000000u64),
+                    },
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_multi"),
+                send: vec![],
+                msg: to_binary(&MultiCustodyHandleMsg::Collateral {
+                    collateral_token: HumanAddr::from("batom"),
+                    msg: CustodyHandleMsg::LockCollateral {
+                        borrower: HumanAddr::from("addr0000"),
+                        amount: Uint256::from(10000000u64),
+                    },
+                })
+                .unwrap(),
+            })
+        ]
+    );
+}
+
+#[test]
+fn borrow_cap() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
     let msg = HandleMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
@@ -841,101 +1275,75 @@ fn unlock_collateral() {
         custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
     };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
-    let _res = handle(&mut deps, env.clone(), msg);
-
-    let msg = HandleMsg::Whitelist {
-        name: "batom".to_string(),
-        symbol: "batom".to_string(),
-        collateral_token: HumanAddr::from("batom"),
-        custody_contract: HumanAddr::from("custody_batom"),
-        max_ltv: Decimal256::percent(60),
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: Some(Uint256::from(600000000u64)),
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
     };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
-    let _res = handle(&mut deps, env.clone(), msg);
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
 
+    // 1000000 * 1000 * 0.6 reaches the cap exactly
     let msg = HandleMsg::LockCollateral {
-        collaterals: vec![
-            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
-            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
-        ],
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
     };
-    let env = mock_env("addr0000", &[]);
-    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
 
-    // Failed to unlock more than locked amount
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![
-            (HumanAddr::from("bluna"), Uint256::from(1000001u64)),
-            (HumanAddr::from("batom"), Uint256::from(10000001u64)),
-        ],
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::one())],
     };
-    let res = handle(&mut deps, env.clone(), msg);
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Unlock amount cannot exceed locked amount")
-        }
+    match handle(&mut deps, mock_env("addr0001", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Borrow cap exceeded; collateral: bluna, borrow cap: 600000000"
+        ),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    deps.querier.with_oracle_price(&[
-        (
-            &("bluna".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(1000u64, 1u64),
-                env.block.time,
-                env.block.time,
-            ),
-        ),
-        (
-            &("batom".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(2000u64, 1u64),
-                env.block.time,
-                env.block.time,
-            ),
-        ),
-    ]);
-
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
-    deps.querier
-        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000000u64))]);
-
-    // cannot unlock any tokens
-    // Failed to unlock more than locked amount
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![(HumanAddr::from("bluna"), Uint256::one())],
-    };
-    let res = handle(&mut deps, env.clone(), msg);
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(
-                msg,
-                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599999400"
-            )
-        }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
-
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![(HumanAddr::from("batom"), Uint256::one())],
-    };
-    let res = handle(&mut deps, env.clone(), msg);
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(
-                msg,
-                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800"
-            )
+    let res = query(
+        &deps,
+        QueryMsg::BorrowCapUsage {
+            collateral_token: HumanAddr::from("bluna"),
+        },
+    )
+    .unwrap();
+    let usage_res: BorrowCapUsageResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        usage_res,
+        BorrowCapUsageResponse {
+            collateral_token: HumanAddr::from("bluna"),
+            borrow_cap: Some(Uint256::from(600000000u64)),
+            total_collateral: Uint256::from(1000000u64),
+            borrow_limit: Uint256::from(600000000u64),
         }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    );
 
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
-    deps.querier
-        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12599999400u64))]);
+    // a single position is capped once the price moves up
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(2000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
     let res = query(
         &deps,
         QueryMsg::BorrowLimit {
@@ -945,96 +1353,12 @@ fn unlock_collateral() {
     )
     .unwrap();
     let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
-    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(12600000000u64),);
-
-    // Cannot unlock 2bluna
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(2u64))],
-    };
-    let res = handle(&mut deps, env.clone(), msg);
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800")
-        }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
-
-    // Can unlock 1bluna
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![(HumanAddr::from("bluna"), Uint256::one())],
-    };
-    let res = handle(&mut deps, env.clone(), msg).unwrap();
-    assert_eq!(
-        res.messages,
-        vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: HumanAddr::from("custody_bluna"),
-            send: vec![],
-            msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
-                borrower: HumanAddr::from("addr0000"),
-                amount: Uint256::one(),
-            })
-            .unwrap(),
-        }),]
-    );
-
-    assert_eq!(
-        res.log,
-        vec![
-            log("action", "unlock_collateral"),
-            log("borrower", "addr0000"),
-            log("collaterals", "1bluna"),
-        ]
-    );
-
-    //testing for unlocking more collaterals
-    deps.querier
-        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(125999900u128))]);
-
-    let msg = HandleMsg::UnlockCollateral {
-        collaterals: vec![
-            (HumanAddr::from("bluna"), Uint256::from(1u128)),
-            (HumanAddr::from("batom"), Uint256::from(1u128)),
-        ],
-    };
-    let res = handle(&mut deps, env.clone(), msg).unwrap();
-    assert_eq!(
-        res.messages,
-        vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: HumanAddr::from("custody_bluna"),
-                send: vec![],
-                msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
-                    borrower: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(1u128),
-                })
-                .unwrap(),
-            }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: HumanAddr::from("custody_batom"),
-                send: vec![],
-                msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
-                    borrower: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(1u128),
-                })
-                .unwrap(),
-            })
-        ]
-    );
-    assert_eq!(
-        res.log,
-        vec![
-            log("action", "unlock_collateral"),
-            log("borrower", "addr0000"),
-            log("collaterals", "1bluna,1batom"),
-        ]
-    );
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000000u64));
 }
 
 #[test]
-fn liquidate_collateral() {
+fn isolation_mode() {
     let mut deps = mock_dependencies(20, &[]);
-    deps.querier
-        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
 
     let env = mock_env("owner", &[]);
     let msg = InitMsg {
@@ -1051,38 +1375,50 @@ fn liquidate_collateral() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
     };
-
-    // we can just call .unwrap() to assert this was a success
     let _res = init(&mut deps, env.clone(), msg).unwrap();
 
-    // store whitelist elems
-    let msg = HandleMsg::Whitelist {
-        name: "bluna".to_string(),
-        symbol: "bluna".to_string(),
-        collateral_token: HumanAddr::from("bluna"),
-        custody_contract: HumanAddr::from("custody_bluna"),
-        max_ltv: Decimal256::percent(60),
-    };
-
-    let _res = handle(&mut deps, env.clone(), msg);
+    for token in ["bluna", "batom"].iter() {
+        let msg = HandleMsg::Whitelist {
+            name: token.to_string(),
+            symbol: token.to_string(),
+            collateral_token: HumanAddr::from(*token),
+            custody_contract: HumanAddr::from(format!("custody_{}", token)),
+            max_ltv: Decimal256::percent(60),
+        };
+        let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    }
 
-    let msg = HandleMsg::Whitelist {
-        name: "batom".to_string(),
-        symbol: "batom".to_string(),
-        collateral_token: HumanAddr::from("batom"),
-        custody_contract: HumanAddr::from("custody_batom"),
-        max_ltv: Decimal256::percent(60),
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: None,
+        clear_borrow_cap: None,
+        isolated: Some(true),
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
     };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Isolated collateral requires a borrow cap")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    let _res = handle(&mut deps, env.clone(), msg);
-
-    let msg = HandleMsg::LockCollateral {
-        collaterals: vec![
-            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
-            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
-        ],
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: Some(Uint256::from(1000000000u64)),
+        clear_borrow_cap: None,
+        isolated: Some(true),
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
     };
-    let env = mock_env("addr0000", &[]);
     let _res = handle(&mut deps, env.clone(), msg).unwrap();
 
     deps.querier.with_oracle_price(&[
@@ -1097,84 +1433,2661 @@ fn liquidate_collateral() {
         (
             &("batom".to_string(), "uusd".to_string()),
             &(
-                Decimal256::from_ratio(2000u64, 1u64),
+                Decimal256::from_ratio(10u64, 1u64),
                 env.block.time,
                 env.block.time,
             ),
         ),
     ]);
 
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
-    deps.querier
-        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000000u64))]);
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
 
-    let msg = HandleMsg::LiquidateCollateral {
-        borrower: HumanAddr::from("addr0000"),
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("batom"), Uint256::from(1000u64))],
     };
-    let env = mock_env("addr0001", &[]);
-    let res = handle(&mut deps, env.clone(), msg.clone());
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
-        }
+    match handle(&mut deps, mock_env("addr0000", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Isolated collateral cannot be mixed with other collaterals"
+        ),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    deps.querier
-        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
-    let res = handle(&mut deps, env, msg).unwrap();
-    assert_eq!(
-        res.messages,
-        vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: HumanAddr::from("custody_batom"),
-                send: vec![],
-                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
-                    liquidator: HumanAddr::from("addr0001"),
-                    borrower: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(100000u64),
-                })
-                .unwrap(),
-            }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: HumanAddr::from("custody_bluna"),
-                send: vec![],
-                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
-                    liquidator: HumanAddr::from("addr0001"),
-                    borrower: HumanAddr::from("addr0000"),
-                    amount: Uint256::from(10000u64),
-                })
-                .unwrap(),
-            }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: HumanAddr::from("market"),
-                send: vec![],
-                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
-                    borrower: HumanAddr::from("addr0000"),
-                    prev_balance: Uint256::zero(),
-                })
-                .unwrap(),
-            })
-        ]
-    );
-
     let res = query(
         &deps,
-        QueryMsg::Collaterals {
+        QueryMsg::IsolationMode {
             borrower: HumanAddr::from("addr0000"),
         },
     )
     .unwrap();
-    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    let isolation_res: IsolationModeResponse = from_binary(&res).unwrap();
     assert_eq!(
-        collaterals_res,
-        CollateralsResponse {
+        isolation_res,
+        IsolationModeResponse {
             borrower: HumanAddr::from("addr0000"),
-            collaterals: vec![
-                (HumanAddr::from("batom"), Uint256::from(9900000u64)),
-                (HumanAddr::from("bluna"), Uint256::from(990000u64)),
-            ]
+            isolated_collateral: Some(HumanAddr::from("bluna")),
         }
     );
+
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000u64));
+}
+
+#[test]
+fn borrow_limit_contribution() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimitContribution {
+            collateral_token: HumanAddr::from("bluna"),
+            amount: Uint256::from(1000000u64),
+            block_time: Some(env.block.time),
+        },
+    )
+    .unwrap();
+    let contribution_res: BorrowLimitContributionResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        contribution_res,
+        BorrowLimitContributionResponse {
+            collateral_token: HumanAddr::from("bluna"),
+            amount: Uint256::from(1000000u64),
+            price: Decimal256::from_ratio(1000u64, 1u64),
+            max_ltv: Decimal256::percent(60),
+            borrow_limit: Uint256::from(600000000u64),
+        }
+    );
+
+    // nothing is locked by the preview
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("owner"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(collaterals_res.collaterals, vec![]);
+
+    // not whitelisted collateral
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimitContribution {
+            collateral_token: HumanAddr::from("batom"),
+            amount: Uint256::from(1000000u64),
+            block_time: None,
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn position_snapshot() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    // no checkpoint before init
+    let res = query(
+        &deps,
+        QueryMsg::PositionSnapshot {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height - 1,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No checkpoint exists at the given height")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let init_height = env.block.height;
+    let mut env = mock_env("addr0000", &[]);
+    env.block.height = init_height + 10;
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::zero())]);
+
+    env.block.height = init_height + 20;
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(400000u64))],
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::PositionSnapshot {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: init_height + 5,
+        },
+    )
+    .unwrap();
+    let snapshot_res: PositionSnapshotResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        snapshot_res,
+        PositionSnapshotResponse {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: init_height + 5,
+            collaterals: vec![],
+            collaterals_checkpoint_height: None,
+            exchange_rate: Decimal256::one(),
+            deposit_rate: Decimal256::zero(),
+            epoch_checkpoint_height: init_height,
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::PositionSnapshot {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: init_height + 15,
+        },
+    )
+    .unwrap();
+    let snapshot_res: PositionSnapshotResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        snapshot_res.collaterals,
+        vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))]
+    );
+    assert_eq!(
+        snapshot_res.collaterals_checkpoint_height,
+        Some(init_height + 10)
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::PositionSnapshot {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: init_height + 100,
+        },
+    )
+    .unwrap();
+    let snapshot_res: PositionSnapshotResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        snapshot_res.collaterals,
+        vec![(HumanAddr::from("bluna"), Uint256::from(600000u64))]
+    );
+    assert_eq!(
+        snapshot_res.collaterals_checkpoint_height,
+        Some(init_height + 20)
+    );
+}
+
+#[test]
+fn unlock_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // Failed to unlock more than locked amount
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000001u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000001u64)),
+        ],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Unlock amount cannot exceed locked amount")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
+    // = 12,600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000000u64))]);
+
+    // cannot unlock any tokens
+    // Failed to unlock more than locked amount
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::one())],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599999400"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("batom"), Uint256::one())],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(
+                msg,
+                "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800"
+            )
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
+    // = 12,600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12599999400u64))]);
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(12600000000u64),);
+
+    // Cannot unlock 2bluna
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(2u64))],
+    };
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Unlock amount too high; Loan liability becomes greater than borrow limit: 12599998800")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // Can unlock 1bluna
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::one())],
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::one(),
+            })
+            .unwrap(),
+        }),]
+    );
+
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "unlock_collateral"),
+            log("borrower", "addr0000"),
+            log("collaterals", "1bluna"),
+        ]
+    );
+
+    //testing for unlocking more collaterals
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(125999900u128))]);
+
+    let msg = HandleMsg::UnlockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1u128)),
+            (HumanAddr::from("batom"), Uint256::from(1u128)),
+        ],
+    };
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1u128),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::UnlockCollateral {
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(1u128),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "unlock_collateral"),
+            log("borrower", "addr0000"),
+            log("collaterals", "1bluna,1batom"),
+        ]
+    );
+}
+
+#[test]
+fn liquidate_collateral() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
+    // = 12,600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000000u64))]);
+
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let env = mock_env("addr0001", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(100000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(10000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation"),
+                send: vec![],
+                msg: to_binary(&LiquidationHandleMsg::ExecuteBids {
+                    liquidator: HumanAddr::from("addr0001"),
+                    collaterals: vec![
+                        (HumanAddr::from("batom"), Uint256::from(100000u64)),
+                        (HumanAddr::from("bluna"), Uint256::from(10000u64)),
+                    ],
+                    fee_address: None,
+                    repay_address: Some(HumanAddr::from("market")),
+                    liquidator_fee_address: Some(HumanAddr::from("addr0001")),
+                    borrower: Some(HumanAddr::from("addr0000")),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res,
+        CollateralsResponse {
+            borrower: HumanAddr::from("addr0000"),
+            collaterals: vec![
+                (HumanAddr::from("batom"), Uint256::from(9900000u64)),
+                (HumanAddr::from("bluna"), Uint256::from(990000u64)),
+            ]
+        }
+    );
+
+    // the market writes off what is left of the loan
+    // once a liquidation takes the last collaterals
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::one())]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(res.messages.len(), 5);
+    assert_eq!(
+        res.messages[4],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::WriteOffBadDebt {
+                borrower: HumanAddr::from("addr0000"),
+            })
+            .unwrap(),
+        })
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::Collaterals {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(collaterals_res.collaterals, vec![]);
+}
+
+#[test]
+fn liquidation_release() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let mut update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: None,
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: Some(Decimal256::percent(90)),
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: None,
+    };
+    match handle(&mut deps, env.clone(), update_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidation release ratio cannot be lower than one")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateConfig {
+        liquidation_release_ratio,
+        ..
+    } = &mut update_msg
+    {
+        *liquidation_release_ratio = Some(Decimal256::percent(125));
+    }
+    let _res = handle(&mut deps, env.clone(), update_msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidationTrigger {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let trigger_res: LiquidationTriggerResponse = from_binary(&res).unwrap();
+    assert_eq!(trigger_res.triggered_at, Some(env.block.height));
+
+    // below the borrow limit of 594,000,000 but above
+    // the release limit of 594,000,000 / 1.25 = 475,200,000
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(500000000u64))]);
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+
+    // release limit = 980100 * 1000 * 0.6 / 1.25 = 470,448,000
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(470448000u64))]);
+    match handle(&mut deps, mock_env("addr0001", &[]), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // locking collaterals releases the loan once it is safe
+    let lock_msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000u64))],
+    };
+    let _res = handle(&mut deps, env, lock_msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidationTrigger {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let trigger_res: LiquidationTriggerResponse = from_binary(&res).unwrap();
+    assert_eq!(trigger_res.triggered_at, None);
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(500000000u64))]);
+    match handle(&mut deps, mock_env("addr0001", &[]), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn collateral_priority() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let env = mock_env("addr0000", &[]);
+    let msg = HandleMsg::SetCollateralPriority {
+        collaterals: vec![HumanAddr::from("bluna"), HumanAddr::from("bluna")],
+    };
+    match handle(&mut deps, env.clone(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Duplicate collateral in priority: bluna")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::SetCollateralPriority {
+        collaterals: vec![HumanAddr::from("bluna")],
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let res = query(
+        &deps,
+        QueryMsg::CollateralPriority {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let priority_res: CollateralPriorityResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        priority_res,
+        CollateralPriorityResponse {
+            borrower: HumanAddr::from("addr0000"),
+            collaterals: vec![HumanAddr::from("bluna")],
+        }
+    );
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+
+    // pro-rata liquidation is 100000 batom + 10000 bluna = 210,000,000 uusd,
+    // which is fully taken from bluna instead
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(210000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation"),
+                send: vec![],
+                msg: to_binary(&LiquidationHandleMsg::ExecuteBids {
+                    liquidator: HumanAddr::from("addr0001"),
+                    collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(210000u64))],
+                    fee_address: None,
+                    repay_address: Some(HumanAddr::from("market")),
+                    liquidator_fee_address: Some(HumanAddr::from("addr0001")),
+                    borrower: Some(HumanAddr::from("addr0000")),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+}
+
+#[test]
+fn liquidation_retry() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    // price is older than the price timeframe
+    let stale_time = env.block.time - 100u64;
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            stale_time,
+            stale_time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let mut env = mock_env("addr0001", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert!(res.log.contains(&log("action", "defer_liquidation")));
+
+    let res = query(
+        &deps,
+        QueryMsg::FailedLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let failed_liquidation_res: FailedLiquidationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        failed_liquidation_res,
+        FailedLiquidationResponse {
+            borrower: HumanAddr::from("addr0000"),
+            reason: StdError::generic_err("Price is too old").to_string(),
+            attempts: 1u32,
+            next_retry_at: env.block.time + 60u64,
+        }
+    );
+
+    // cannot retry before the backoff period passes, and failing
+    // again in the meantime does not push the retry back
+    let liquidate_msg = msg;
+    let msg = HandleMsg::RetryLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    for pending_msg in [msg.clone(), liquidate_msg] {
+        let res = handle(&mut deps, env.clone(), pending_msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                format!(
+                    "Liquidation cannot be retried until {}",
+                    env.block.time + 60
+                )
+            ),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    let res = query(
+        &deps,
+        QueryMsg::FailedLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let failed_liquidation_res: FailedLiquidationResponse = from_binary(&res).unwrap();
+    assert_eq!(failed_liquidation_res.attempts, 1u32);
+
+    // a borrower without a loan is not recorded
+    let _res = handle(
+        &mut deps,
+        mock_env("addr0002", &[]),
+        HandleMsg::LockCollateral {
+            collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+        },
+    )
+    .unwrap();
+    deps.querier.with_loan_amount(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(600000001u64)),
+        (&HumanAddr::from("addr0002"), &Uint256::zero()),
+    ]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::LiquidateCollateral {
+            borrower: HumanAddr::from("addr0002"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Price is too old"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // failing again doubles the backoff
+    env.block.time += 60u64;
+    let _res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    let res = query(
+        &deps,
+        QueryMsg::FailedLiquidations {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let failed_liquidations_res: FailedLiquidationsResponse = from_binary(&res).unwrap();
+    assert_eq!(failed_liquidations_res.failed_liquidations.len(), 1);
+    assert_eq!(
+        failed_liquidations_res.failed_liquidations[0].attempts,
+        2u32
+    );
+    assert_eq!(
+        failed_liquidations_res.failed_liquidations[0].next_retry_at,
+        env.block.time + 120u64
+    );
+
+    // the retry is deferred by a 500 second chain halt
+    let mut recover_env = mock_env("owner", &[]);
+    recover_env.block.time = env.block.time + 500u64;
+    let msg_recover = HandleMsg::RecoverFromHalt {
+        halted_at: env.block.time,
+    };
+    let _res = handle(&mut deps, recover_env, msg_recover).unwrap();
+
+    env.block.time += 500u64;
+    let res = query(
+        &deps,
+        QueryMsg::FailedLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let failed_liquidation_res: FailedLiquidationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        failed_liquidation_res.next_retry_at,
+        env.block.time + 120u64
+    );
+
+    // price is updated; the retry liquidates the loan
+    env.block.time += 120u64;
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    let res = handle(&mut deps, env, msg).unwrap();
+    // custody liquidation, execute bids and repay
+    assert_eq!(res.messages.len(), 3);
+
+    let res = query(
+        &deps,
+        QueryMsg::FailedLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No failed liquidation exists"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn liquidation_authorization() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let mut update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: None,
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: None,
+        emergency_liquidation_ratio: None,
+        liquidation_authorization_period: Some(0u64),
+    };
+    match handle(&mut deps, env.clone(), update_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Liquidation authorization period must be bigger than zero"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateConfig {
+        liquidation_authorization_period,
+        ..
+    } = &mut update_msg
+    {
+        *liquidation_authorization_period = Some(20u64);
+    }
+    let _res = handle(&mut deps, env.clone(), update_msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg);
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(10000000u64)),
+        ],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+
+    let msg = HandleMsg::AuthorizeLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let env = mock_env("keeper0000", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "authorize_liquidation"),
+            log("borrower", "addr0000"),
+            log("loan_amount", "12600000001"),
+            log("expires_at", env.block.height + 20u64),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidationAuthorization {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let authorization_res: LiquidationAuthorizationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        authorization_res,
+        LiquidationAuthorizationResponse {
+            borrower: HumanAddr::from("addr0000"),
+            collaterals: vec![
+                (HumanAddr::from("batom"), Uint256::from(100000u64)),
+                (HumanAddr::from("bluna"), Uint256::from(10000u64)),
+            ],
+            loan_amount: Uint256::from(12600000001u64),
+            expires_at: env.block.height + 20u64,
+        }
+    );
+
+    // expired authorization cannot be executed
+    let exec_msg = HandleMsg::ExecuteAuthorizedLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let mut expired_env = mock_env("addr0001", &[]);
+    expired_env.block.height += 21u64;
+    let res = handle(&mut deps, expired_env, exec_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidation authorization has expired")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // any keeper can execute within the window
+    let env = mock_env("addr0001", &[]);
+    let res = handle(&mut deps, env.clone(), exec_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(100000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
+                msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(10000u64),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("liquidation"),
+                send: vec![],
+                msg: to_binary(&LiquidationHandleMsg::ExecuteBids {
+                    liquidator: HumanAddr::from("addr0001"),
+                    collaterals: vec![
+                        (HumanAddr::from("batom"), Uint256::from(100000u64)),
+                        (HumanAddr::from("bluna"), Uint256::from(10000u64)),
+                    ],
+                    fee_address: None,
+                    repay_address: Some(HumanAddr::from("market")),
+                    liquidator_fee_address: Some(HumanAddr::from("addr0001")),
+                    borrower: Some(HumanAddr::from("addr0000")),
+                })
+                .unwrap(),
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::RepayStableFromLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+
+    // authorization is consumed
+    let res = handle(&mut deps, env.clone(), exec_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No liquidation authorization exists")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a repaid loan invalidates the authorization
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12000000000u64))]);
+    let res = handle(&mut deps, env.clone(), exec_msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Loan has changed since the liquidation was authorized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a price recovery within the window invalidates the authorization
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(12600000001u64))]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+    let res = handle(&mut deps, env, exec_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn health_alert() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let user_env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, user_env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::RegisterHealthAlert {
+        threshold: Decimal256::zero(),
+    };
+    let res = handle(&mut deps, user_env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Health alert threshold must be bigger than zero")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = HandleMsg::RegisterHealthAlert {
+        threshold: Decimal256::percent(150),
+    };
+    let res = handle(&mut deps, user_env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "register_health_alert"),
+            log("borrower", "addr0000"),
+            log("threshold", "1.5"),
+        ]
+    );
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000
+    // health_factor = 600,000,000 / 500,000,000 = 1.2 < 1.5
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(500000000u64))]);
+
+    env.block.height += 86400u64;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(
+        res.log.last().unwrap().clone(),
+        log("health_alert", "addr0000")
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::HealthAlert {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let health_alert_res: HealthAlertResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        health_alert_res,
+        HealthAlertResponse {
+            borrower: HumanAddr::from("addr0000"),
+            threshold: Decimal256::percent(150),
+            triggered: true,
+        }
+    );
+
+    // alert is not emitted again while the position stays unhealthy
+    env.block.height += 86400u64;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(
+        res.log.last().unwrap().clone(),
+        log("locked_interest_funding", "0")
+    );
+
+    // health_factor = 600,000,000 / 300,000,000 = 2 >= 1.5
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(300000000u64))]);
+
+    env.block.height += 86400u64;
+    let res = handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+    assert_eq!(
+        res.log.last().unwrap().clone(),
+        log("health_recovered", "addr0000")
+    );
+
+    let res = handle(&mut deps, user_env, HandleMsg::DeregisterHealthAlert {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "deregister_health_alert"),
+            log("borrower", "addr0000"),
+        ]
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::HealthAlert {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No health alert registered"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn liquidatable_positions() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone()).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier.with_loan_amount(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(600000100u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(600000000u64)),
+    ]);
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            limit: None,
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidatablePositionsResponse {
+            positions: vec![LiquidatablePosition {
+                borrower: HumanAddr::from("addr0000"),
+                borrow_amount: Uint256::from(600000100u64),
+                borrow_limit: Uint256::from(600000000u64),
+                shortfall: Uint256::from(100u64),
+            }],
+            last_borrower: Some(HumanAddr::from("addr0001")),
+        }
+    );
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidatablePositions {
+            start_after: Some(HumanAddr::from("addr0001")),
+            limit: None,
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidatablePositionsResponse {
+            positions: vec![],
+            last_borrower: None,
+        }
+    );
+
+    // liquidation_limit = 1000 * 1000000 * 0.65 = 650,000,000 uusd
+    let msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: Some(Decimal256::percent(65)),
+        borrow_cap: None,
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: None,
+        multi_custody: None,
+        decimals: None,
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_loan_amount(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(650000000u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(650000100u64)),
+    ]);
+
+    let res = query(
+        &deps,
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            limit: None,
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidatablePositionsResponse {
+            positions: vec![LiquidatablePosition {
+                borrower: HumanAddr::from("addr0001"),
+                borrow_amount: Uint256::from(650000100u64),
+                borrow_limit: Uint256::from(600000000u64),
+                shortfall: Uint256::from(100u64),
+            }],
+            last_borrower: Some(HumanAddr::from("addr0001")),
+        }
+    );
+}
+
+#[test]
+fn profitable_liquidations() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(2000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier.with_loan_amount(&[
+        (&HumanAddr::from("addr0000"), &Uint256::from(600000100u64)),
+        (&HumanAddr::from("addr0001"), &Uint256::from(1200000100u64)),
+    ]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(10))]);
+    deps.querier.with_bids(&[(
+        &(HumanAddr::from("keeper"), HumanAddr::from("bluna")),
+        &(Uint256::from(1000000000u64), Decimal256::percent(5)),
+    )]);
+
+    // addr0000: 100000 bluna worth 100,000,000 uusd bought for 95,000,000
+    // uusd, plus the 1% liquidator fee = 5,950,000 uusd
+    let res: ProfitableLiquidationsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ProfitableLiquidations {
+                liquidator: HumanAddr::from("keeper"),
+                min_profit: None,
+                start_after: None,
+                limit: None,
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ProfitableLiquidationsResponse {
+            liquidations: vec![
+                ProfitableLiquidation {
+                    borrower: HumanAddr::from("addr0001"),
+                    shortfall: Uint256::from(100u64),
+                    collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(200000u64))],
+                    estimated_profit: Uint256::from(11900000u64),
+                },
+                ProfitableLiquidation {
+                    borrower: HumanAddr::from("addr0000"),
+                    shortfall: Uint256::from(100u64),
+                    collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(100000u64))],
+                    estimated_profit: Uint256::from(5950000u64),
+                },
+            ],
+            last_borrower: Some(HumanAddr::from("addr0001")),
+        }
+    );
+
+    let res: ProfitableLiquidationsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ProfitableLiquidations {
+                liquidator: HumanAddr::from("keeper"),
+                min_profit: Some(Uint256::from(10000000u64)),
+                start_after: None,
+                limit: None,
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.liquidations.len(), 1);
+    assert_eq!(res.liquidations[0].borrower, HumanAddr::from("addr0001"));
+
+    // no bids to fill the liquidations with
+    let res: ProfitableLiquidationsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::ProfitableLiquidations {
+                liquidator: HumanAddr::from("addr0002"),
+                min_profit: None,
+                start_after: None,
+                limit: None,
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.liquidations, vec![]);
+}
+
+#[test]
+fn account_summary() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(100000000u64))]);
+
+    // part of the deposited collateral is not locked
+    deps.querier.with_custody_balance(&[(
+        &(
+            HumanAddr::from("custody_bluna"),
+            HumanAddr::from("addr0000"),
+        ),
+        &Uint256::from(1500000u64),
+    )]);
+
+    let res: AccountSummaryResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::AccountSummary {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: env.block.height,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        AccountSummaryResponse {
+            borrower: HumanAddr::from("addr0000"),
+            collaterals: vec![AccountCollateral {
+                collateral_token: HumanAddr::from("bluna"),
+                balance: Uint256::from(1500000u64),
+                locked_amount: Uint256::from(1000000u64),
+                price: Decimal256::from_ratio(1000u64, 1u64),
+                value: Uint256::from(1000000000u64),
+            }],
+            collateral_value: Uint256::from(1000000000u64),
+            borrow_amount: Uint256::from(100000000u64),
+            borrow_limit: Uint256::from(600000000u64),
+            pending_rewards: Decimal256::zero(),
+        }
+    );
+}
+
+#[test]
+fn borrower_health() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(50),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![
+            (HumanAddr::from("bluna"), Uint256::from(1000000u64)),
+            (HumanAddr::from("batom"), Uint256::from(2000000u64)),
+        ],
+    };
+    let _res = handle(&mut deps, mock_env("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(100u64, 1u64),
+                env.block.time,
+                env.block.time,
+            ),
+        ),
+    ]);
+
+    // no loan, no health factor
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::zero())]);
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerHealth {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(res.health_factor, None);
+    assert_eq!(res.ltv, Decimal256::zero());
+
+    // borrow_limit = 1000 * 1000000 * 0.6 + 100 * 2000000 * 0.5 = 700,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(560000000u64))]);
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerHealth {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        BorrowerHealthResponse {
+            borrower: HumanAddr::from("addr0000"),
+            borrow_amount: Uint256::from(560000000u64),
+            borrow_limit: Uint256::from(700000000u64),
+            collateral_value: Uint256::from(1200000000u64),
+            health_factor: Some(Decimal256::from_str("1.25").unwrap()),
+            ltv: Decimal256::from_uint256(560000000u64) / Decimal256::from_uint256(1200000000u64),
+            shortfall: Uint256::zero(),
+            collaterals: vec![
+                // bluna alone keeps the borrow limit above the loan
+                CollateralHealth {
+                    collateral_token: HumanAddr::from("batom"),
+                    amount: Uint256::from(2000000u64),
+                    price: Decimal256::from_ratio(100u64, 1u64),
+                    liquidation_price: None,
+                },
+                // 460,000,000 of the loan must be backed by bluna
+                CollateralHealth {
+                    collateral_token: HumanAddr::from("bluna"),
+                    amount: Uint256::from(1000000u64),
+                    price: Decimal256::from_ratio(1000u64, 1u64),
+                    liquidation_price: Some(
+                        Decimal256::from_ratio(1000u64, 1u64)
+                            * Decimal256::from_uint256(460000000u64)
+                            / Decimal256::from_uint256(600000000u64)
+                    ),
+                },
+            ],
+        }
+    );
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(700000100u64))]);
+    let res = query(
+        &deps,
+        QueryMsg::BorrowerHealth {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: env.block.height,
+        },
+    )
+    .unwrap();
+    let res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(res.shortfall, Uint256::from(100u64));
+}
+
+#[test]
+fn resume_epoch_operations() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let mut env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_epoch_state(&[(
+        &HumanAddr::from("market"),
+        &(Uint256::from(1000000u64), Decimal256::one()),
+    )]);
+
+    // one more borrower than a single batch
+    for i in 0..31 {
+        let msg = HandleMsg::RegisterHealthAlert {
+            threshold: Decimal256::percent(150),
+        };
+        handle(&mut deps, mock_env(format!("addr{:04}", i), &[]), msg).unwrap();
+    }
+
+    match handle(&mut deps, env.clone(), HandleMsg::ResumeEpochOperations {}) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No epoch operations to resume"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 86400u64;
+    handle(&mut deps, env.clone(), HandleMsg::ExecuteEpochOperations {}).unwrap();
+
+    let res: EpochOperationsProgressResponse =
+        from_binary(&query(&deps, QueryMsg::EpochOperationsProgress {}).unwrap()).unwrap();
+    assert_eq!(res.epoch_height, env.block.height);
+    assert_eq!(res.processed_borrowers, 30);
+    assert!(!res.completed);
+
+    let res = handle(&mut deps, env.clone(), HandleMsg::ResumeEpochOperations {}).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "resume_epoch_operations"),
+            log("epoch_height", env.block.height),
+            log("processed_borrowers", 31),
+            log("completed", true),
+        ]
+    );
+
+    let res: EpochOperationsProgressResponse =
+        from_binary(&query(&deps, QueryMsg::EpochOperationsProgress {}).unwrap()).unwrap();
+    assert!(res.last_borrower.is_some());
+    assert!(res.completed);
+
+    match handle(&mut deps, env, HandleMsg::ResumeEpochOperations {}) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No epoch operations to resume"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn liquidation_cooldown() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let mut update_msg = HandleMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        guardian: None,
+        hysteresis_band: None,
+        hysteresis_epochs: None,
+        retry_base_delay: None,
+        retry_max_delay: None,
+        priority_tolerance: None,
+        debt_ceiling: None,
+        clear_debt_ceiling: None,
+        liquidation_release_ratio: None,
+        liquidation_cooldown: Some(100u64),
+        emergency_liquidation_ratio: Some(Decimal256::percent(90)),
+        liquidation_authorization_period: None,
+    };
+    match handle(&mut deps, env.clone(), update_msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Emergency liquidation ratio cannot be lower than one")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateConfig {
+        emergency_liquidation_ratio,
+        ..
+    } = &mut update_msg
+    {
+        *emergency_liquidation_ratio = Some(Decimal256::percent(110));
+    }
+    let _res = handle(&mut deps, env.clone(), update_msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let authorize_msg = HandleMsg::AuthorizeLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let execute_authorized_msg = HandleMsg::ExecuteAuthorizedLiquidation {
+        borrower: HumanAddr::from("addr0000"),
+    };
+
+    // authorized before the liquidation below
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), authorize_msg.clone()).unwrap();
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+
+    // below the emergency limit of 990000 * 1000 * 0.6 * 1.1 = 653,400,000,
+    // neither the direct nor the authorized path can liquidate again
+    for cooldown_msg in &[msg.clone(), authorize_msg, execute_authorized_msg] {
+        match handle(&mut deps, mock_env("addr0001", &[]), cooldown_msg.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                format!(
+                    "Borrower is in liquidation cooldown until: {}",
+                    env.block.time + 100
+                )
+            ),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(653400001u64))]);
+    let _res = handle(&mut deps, mock_env("addr0001", &[]), msg.clone()).unwrap();
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+    let mut liquidator_env = mock_env("addr0001", &[]);
+    liquidator_env.block.time += 100;
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            liquidator_env.block.time,
+            liquidator_env.block.time,
+        ),
+    )]);
+    let res = handle(&mut deps, liquidator_env, msg).unwrap();
+    assert_eq!(res.messages.len(), 3);
+}
+
+#[test]
+fn ltv_tiers() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    let mut msg = HandleMsg::UpdateWhitelist {
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        borrow_cap: None,
+        clear_borrow_cap: None,
+        isolated: None,
+        ltv_tiers: Some(vec![
+            LtvTier {
+                notional_threshold: Uint256::from(100000000u64),
+                max_ltv: Decimal256::percent(50),
+            },
+            LtvTier {
+                notional_threshold: Uint256::from(500000000u64),
+                max_ltv: Decimal256::percent(55),
+            },
+        ]),
+        multi_custody: None,
+        decimals: None,
+    };
+    match handle(&mut deps, env.clone(), msg.clone()) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "LTV tiers must have increasing thresholds and non-increasing LTVs"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    if let HandleMsg::UpdateWhitelist { ltv_tiers, .. } = &mut msg {
+        *ltv_tiers = Some(vec![
+            LtvTier {
+                notional_threshold: Uint256::from(100000000u64),
+                max_ltv: Decimal256::percent(50),
+            },
+            LtvTier {
+                notional_threshold: Uint256::from(500000000u64),
+                max_ltv: Decimal256::percent(40),
+            },
+        ]);
+    }
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // 100,000,000 * 0.6 + 400,000,000 * 0.5 + 500,000,000 * 0.4
+    let res = query(
+        &deps,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(460000000u64));
+
+    let res = query(
+        &deps,
+        QueryMsg::EffectiveLtv {
+            borrower: HumanAddr::from("addr0000"),
+            collateral_token: HumanAddr::from("bluna"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let ltv_res: EffectiveLtvResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        ltv_res,
+        EffectiveLtvResponse {
+            borrower: HumanAddr::from("addr0000"),
+            collateral_token: HumanAddr::from("bluna"),
+            collateral_value: Uint256::from(1000000000u64),
+            borrow_limit: Uint256::from(460000000u64),
+            effective_ltv: Decimal256::percent(46),
+        }
+    );
+}
+
+#[test]
+fn liquidation_executors() {
+    let mut deps = mock_dependencies(20, &[]);
+    deps.querier
+        .with_liquidation_percent(&[(&HumanAddr::from("liquidation"), &Decimal256::percent(1))]);
+
+    let env = mock_env("owner", &[]);
+    let msg = InitMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+    };
+
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let msg = HandleMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let msg = HandleMsg::LockCollateral {
+        collaterals: vec![(HumanAddr::from("bluna"), Uint256::from(1000000u64))],
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = handle(&mut deps, env.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time,
+            env.block.time,
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+
+    let msg = HandleMsg::AddLiquidationExecutor {
+        executor: HumanAddr::from("keeper0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let env = mock_env("owner", &[]);
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "add_liquidation_executor"),
+            log("executor", "keeper0000"),
+        ]
+    );
+
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Liquidation executor is already registered: keeper0000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // registering an executor alone keeps liquidations open;
+    // addr0001 gets past the executor check to the loan check
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000000u64))]);
+    let liquidate_msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let res = handle(&mut deps, mock_env("addr0001", &[]), liquidate_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+
+    let msg = HandleMsg::SetExecutorOnly {
+        executor_only: true,
+    };
+    let res = handle(&mut deps, mock_env("addr0000", &[]), msg.clone());
+    match res {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env.clone(), msg).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_executor_only"),
+            log("executor_only", true),
+        ]
+    );
+
+    let res: LiquidationExecutorsResponse = from_binary(
+        &query(
+            &deps,
+            QueryMsg::LiquidationExecutors {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LiquidationExecutorsResponse {
+            executor_only: true,
+            executors: vec![LiquidationExecutorResponse {
+                executor: HumanAddr::from("keeper0000"),
+                added_at: env.block.height,
+            }],
+        }
+    );
+
+    // unregistered senders cannot liquidate anymore,
+    // nor authorize a liquidation for later
+    let msg = HandleMsg::LiquidateCollateral {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    for unregistered_msg in [
+        msg.clone(),
+        HandleMsg::AuthorizeLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+        HandleMsg::ExecuteAuthorizedLiquidation {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    ] {
+        let res = handle(&mut deps, mock_env("addr0001", &[]), unregistered_msg);
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+                msg,
+                "Sender is not a registered liquidation executor: addr0001"
+            ),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // registered executors still need an unsafe loan
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000000u64))]);
+    let res = handle(&mut deps, mock_env("keeper0000", &[]), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&HumanAddr::from("addr0000"), &Uint256::from(600000001u64))]);
+    let res = handle(&mut deps, mock_env("keeper0000", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
+            msg: to_binary(&CustodyHandleMsg::LiquidateCollateral {
+                liquidator: HumanAddr::from("keeper0000"),
+                borrower: HumanAddr::from("addr0000"),
+                amount: Uint256::from(10000u64),
+            })
+            .unwrap(),
+        })
+    );
+
+    // the last executor cannot be removed while liquidations are
+    // executor only, as nobody could liquidate anymore
+    let msg = HandleMsg::RemoveLiquidationExecutor {
+        executor: HumanAddr::from("keeper0000"),
+    };
+    let res = handle(&mut deps, env.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Cannot remove the last liquidation executor while liquidations are executor only"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::SetExecutorOnly {
+            executor_only: false,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "set_executor_only"),
+            log("executor_only", false),
+        ]
+    );
+
+    let res = handle(&mut deps, env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.log,
+        vec![
+            log("action", "remove_liquidation_executor"),
+            log("executor", "keeper0000"),
+        ]
+    );
+
+    let res = handle(&mut deps, env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Liquidation executor is not registered: keeper0000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // without executors the restriction cannot be turned on
+    let res = handle(
+        &mut deps,
+        env,
+        HandleMsg::SetExecutorOnly {
+            executor_only: true,
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No liquidation executors are registered")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
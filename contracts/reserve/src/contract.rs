@@ -0,0 +1,143 @@
+use crate::state::{read_config, store_config, Config};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HandleResult, HumanAddr,
+    InitResponse, MigrateResponse, MigrateResult, Querier, StdError, StdResult, Storage,
+};
+use moneymarket::payout::native_payout_msg;
+use moneymarket::querier::query_balance;
+use moneymarket::reserve::{
+    BalanceResponse, ConfigResponse, HandleMsg, InitMsg, MigrateMsg, QueryMsg,
+};
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        &mut deps.storage,
+        &Config {
+            contract_addr: deps.api.canonical_address(&env.contract.address)?,
+            owner: deps.api.canonical_address(&msg.owner)?,
+            stable_denom: msg.stable_denom,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> HandleResult {
+    match msg {
+        HandleMsg::UpdateConfig { owner } => update_config(deps, env, owner),
+        HandleMsg::Withdraw { amount, recipient } => withdraw(deps, env, amount, recipient),
+    }
+}
+
+pub fn update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: Option<HumanAddr>,
+) -> HandleResult {
+    let mut config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+
+    store_config(&mut deps.storage, &config)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Option<Uint256>,
+    recipient: HumanAddr,
+) -> HandleResult {
+    let config: Config = read_config(&deps.storage)?;
+    if deps.api.canonical_address(&env.message.sender)? != config.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let balance = query_balance(deps, &env.contract.address, config.stable_denom.clone())?;
+    let amount = amount.unwrap_or(balance);
+    if amount > balance {
+        return Err(StdError::generic_err(format!(
+            "Withdraw amount cannot exceed the reserve balance: {}",
+            balance
+        )));
+    }
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Nothing to withdraw"));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![native_payout_msg(
+            deps,
+            env.contract.address,
+            recipient.clone(),
+            config.stable_denom,
+            amount,
+        )?],
+        log: vec![
+            log("action", "withdraw"),
+            log("recipient", recipient),
+            log("amount", amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Balance {} => to_binary(&query_reserve_balance(deps)?),
+    }
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?,
+        stable_denom: config.stable_denom,
+    })
+}
+
+fn query_reserve_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<BalanceResponse> {
+    let config: Config = read_config(&deps.storage)?;
+    let balance = query_balance(
+        deps,
+        &deps.api.human_address(&config.contract_addr)?,
+        config.stable_denom.clone(),
+    )?;
+
+    Ok(BalanceResponse {
+        denom: config.stable_denom,
+        balance,
+    })
+}
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    _deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> MigrateResult {
+    Ok(MigrateResponse::default())
+}
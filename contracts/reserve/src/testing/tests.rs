@@ -0,0 +1,114 @@
+use crate::contract::{handle, init, query};
+use crate::testing::mock_querier::mock_dependencies;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{from_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128};
+use moneymarket::reserve::{BalanceResponse, ConfigResponse, HandleMsg, InitMsg, QueryMsg};
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        stable_denom: "uusd".to_string(),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env.clone(), msg).unwrap();
+
+    let config: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: HumanAddr::from("owner0000"),
+            stable_denom: "uusd".to_string(),
+        }
+    );
+
+    let msg = HandleMsg::UpdateConfig {
+        owner: Some(HumanAddr::from("owner0001")),
+    };
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    let _res = handle(&mut deps, env, msg).unwrap();
+
+    let config: ConfigResponse = from_binary(&query(&deps, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, HumanAddr::from("owner0001"));
+}
+
+#[test]
+fn withdraw() {
+    let mut deps = mock_dependencies(
+        20,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InitMsg {
+        owner: HumanAddr::from("owner0000"),
+        stable_denom: "uusd".to_string(),
+    };
+    let env = mock_env("addr0000", &[]);
+    let _res = init(&mut deps, env, msg).unwrap();
+
+    let balance: BalanceResponse =
+        from_binary(&query(&deps, QueryMsg::Balance {}).unwrap()).unwrap();
+    assert_eq!(
+        balance,
+        BalanceResponse {
+            denom: "uusd".to_string(),
+            balance: Uint256::from(1000000u64),
+        }
+    );
+
+    let msg = HandleMsg::Withdraw {
+        amount: None,
+        recipient: HumanAddr::from("addr0000"),
+    };
+    let env = mock_env("addr0000", &[]);
+    match handle(&mut deps, env, msg.clone()) {
+        Err(StdError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let env = mock_env("owner0000", &[]);
+    let res = handle(
+        &mut deps,
+        env.clone(),
+        HandleMsg::Withdraw {
+            amount: Some(Uint256::from(1000001u64)),
+            recipient: HumanAddr::from("addr0000"),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Withdraw amount cannot exceed the reserve balance: 1000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(&mut deps, env, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(990099u128),
+            }],
+        })]
+    );
+}
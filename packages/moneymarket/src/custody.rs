@@ -1,10 +1,14 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_bignumber::Uint256;
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::HumanAddr;
 use cw20::Cw20ReceiveMsg;
 
+use crate::swap::SwapRoute;
+use crate::sweep::SweepAsset;
+use crate::tax::TaxPolicy;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InitMsg {
@@ -40,7 +44,38 @@ pub enum HandleMsg {
     UpdateConfig {
         owner: Option<HumanAddr>,
         liquidation_contract: Option<HumanAddr>,
+        guardian: Option<HumanAddr>,
+        /// Track rebasing collateral as shares; can only
+        /// be set while the custody has no borrowers
+        exchange_rate_contract: Option<HumanAddr>,
+        /// Swap route which buys the collateral with the compounded
+        /// share of the rewards; cannot be used with rebasing collateral
+        compound_contract: Option<HumanAddr>,
+        /// Share of the rewards compounded into the collateral
+        compound_ratio: Option<Decimal256>,
+        /// Router which executes the configured reward swap routes
+        swap_router: Option<HumanAddr>,
+    },
+    /// Set the route used to swap the reward `denom` into the
+    /// `stable_denom`; `None` falls back to the market swap
+    UpdateSwapRoute {
+        denom: String,
+        route: Option<SwapRoute>,
+    },
+    /// Transfer out funds which are not part of the tracked state
+    SweepUnrelatedFunds {
+        asset: SweepAsset,
+        recipient: HumanAddr,
+    },
+    /// Halt or resume flows; the guardian can only pause
+    SetPause {
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
     },
+    /// Set how the tax on stable transfers is computed
+    UpdateTaxPolicy { tax_policy: TaxPolicy },
     /// Make specified amount of tokens unspendable
     LockCollateral {
         borrower: HumanAddr,
@@ -83,6 +118,9 @@ pub enum HandleMsg {
 pub enum Cw20HookMsg {
     /// Deposit collateral token
     DepositCollateral {},
+    /// (internal) Collateral bought with the compounded rewards;
+    /// only accepted from the compound contract
+    CompoundCollateral {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -96,6 +134,15 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    PauseInfo {},
+    TaxPolicy {},
+    /// Collateral credited to the borrower by reward compounding
+    CompoundedCollateral {
+        borrower: HumanAddr,
+    },
+    SwapRoute {
+        denom: String,
+    },
 }
 
 // We define a custom struct for each query response
@@ -109,6 +156,11 @@ pub struct ConfigResponse {
     pub liquidation_contract: HumanAddr,
     pub stable_denom: String,
     pub basset_info: BAssetInfo,
+    pub guardian: Option<HumanAddr>,
+    pub exchange_rate_contract: Option<HumanAddr>,
+    pub compound_contract: Option<HumanAddr>,
+    pub compound_ratio: Decimal256,
+    pub swap_router: Option<HumanAddr>,
 }
 
 // We define a custom struct for each query response
@@ -125,6 +177,45 @@ pub struct BorrowersResponse {
     pub borrowers: Vec<BorrowerResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CompoundedCollateralResponse {
+    pub borrower: HumanAddr,
+    pub compounded: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRouteResponse {
+    pub denom: String,
+    pub route: Option<SwapRoute>,
+}
+
+/// Interface of the swap route used to compound rewards; the bought
+/// collateral is sent back with the CompoundCollateral hook
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CompoundContractHandleMsg {
+    SwapToCollateral {
+        collateral_token: HumanAddr,
+        recipient: HumanAddr,
+    },
+}
+
+/// Interface of the exchange rate contract of a rebasing collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeRateQueryMsg {
+    ExchangeRate {},
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateResponse {
+    /// Collateral token amount per share
+    pub rate: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BAssetInfo {
     pub name: String,
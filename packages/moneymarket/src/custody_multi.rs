@@ -0,0 +1,154 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, CosmosMsg, HumanAddr, StdResult, WasmMsg};
+use cw20::Cw20ReceiveMsg;
+
+use crate::custody::{BAssetInfo, HandleMsg as CustodyHandleMsg};
+use crate::tax::TaxPolicy;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct InitMsg {
+    /// owner address
+    pub owner: HumanAddr,
+    /// overseer contract address
+    pub overseer_contract: HumanAddr,
+    /// market contract address
+    pub market_contract: HumanAddr,
+    /// liquidation contract address
+    pub liquidation_contract: HumanAddr,
+    /// Expected reward denom. Rewards of every collateral
+    /// are converted to the `stable_denom`.
+    pub stable_denom: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    /// CW20 token receiver; accepts the custody DepositCollateral
+    /// hook from any registered collateral token
+    Receive(Cw20ReceiveMsg),
+
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+
+    /// Update config
+    UpdateConfig {
+        owner: Option<HumanAddr>,
+        liquidation_contract: Option<HumanAddr>,
+        guardian: Option<HumanAddr>,
+    },
+    /// Register a new collateral token with its bAsset reward contract
+    RegisterCollateral {
+        collateral_token: HumanAddr,
+        reward_contract: HumanAddr,
+        basset_info: BAssetInfo,
+    },
+    /// Halt or resume flows; the guardian can only pause
+    SetPause {
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// Set how the tax on stable transfers is computed
+    UpdateTaxPolicy { tax_policy: TaxPolicy },
+
+    ////////////////////
+    /// Collateral operations
+    ////////////////////
+
+    /// Execute a single-asset custody message against
+    /// the balances of `collateral_token`
+    Collateral {
+        collateral_token: HumanAddr,
+        msg: CustodyHandleMsg,
+    },
+
+    /// (internal) Send withdrawn rewards to overseer
+    DistributeHook {},
+
+    /// (internal) Swap all coins to stable_denom
+    SwapToStableDenom {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Collateral {
+        collateral_token: HumanAddr,
+    },
+    Collaterals {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    Borrower {
+        collateral_token: HumanAddr,
+        address: HumanAddr,
+    },
+    Borrowers {
+        collateral_token: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    PauseInfo {},
+    TaxPolicy {},
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub market_contract: HumanAddr,
+    pub liquidation_contract: HumanAddr,
+    pub stable_denom: String,
+    pub guardian: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralResponse {
+    pub collateral_token: HumanAddr,
+    pub reward_contract: HumanAddr,
+    pub basset_info: BAssetInfo,
+    /// Block height of the last reward distribution
+    pub last_distributed: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralsResponse {
+    pub collaterals: Vec<CollateralResponse>,
+}
+
+/// We currently take no arguments for migrations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+/// Message executing a single-asset custody message on the custody of
+/// `collateral_token`; multi-asset custodies get it wrapped for dispatch
+pub fn custody_msg(
+    custody_contract: HumanAddr,
+    collateral_token: HumanAddr,
+    multi_custody: bool,
+    msg: CustodyHandleMsg,
+) -> StdResult<CosmosMsg> {
+    let msg = if multi_custody {
+        to_binary(&HandleMsg::Collateral {
+            collateral_token,
+            msg,
+        })?
+    } else {
+        to_binary(&msg)?
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: custody_contract,
+        send: vec![],
+        msg,
+    }))
+}
@@ -0,0 +1,27 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+
+use crate::rounding::{self, Rounding};
+
+/// Oracle prices and the stable denom are both 6-decimal based
+pub const DEFAULT_DECIMALS: u8 = 6;
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Serde default for the decimals of collaterals
+/// registered before decimals were stored
+pub fn default_decimals() -> u8 {
+    DEFAULT_DECIMALS
+}
+
+/// Convert a raw collateral amount into the 6-decimal basis
+/// which oracle prices are quoted in
+pub fn normalize_amount(amount: Uint256, decimals: u8, rounding: Rounding) -> Uint256 {
+    if decimals == DEFAULT_DECIMALS {
+        return amount;
+    }
+
+    let scale = Decimal256::from_ratio(
+        10u64.pow(DEFAULT_DECIMALS as u32),
+        10u64.pow(decimals as u32),
+    );
+    rounding::mul(amount, scale, rounding)
+}
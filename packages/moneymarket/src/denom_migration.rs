@@ -0,0 +1,158 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    log, to_binary, Api, CanonicalAddr, Coin, CosmosMsg, Env, Extern, HandleResponse, HandleResult,
+    HumanAddr, Querier, StdError, StdResult, Storage, WasmMsg,
+};
+use cosmwasm_storage::{singleton, singleton_read};
+
+use crate::querier::{deduct_tax, query_balance};
+
+static KEY_PENDING_DENOM_MIGRATION: &[u8] = b"pending_denom_migration";
+
+/// Delay in seconds before a proposed denom migration can be executed
+pub const DENOM_MIGRATION_TIMELOCK_PERIOD: u64 = 86400;
+
+/// Stable denom change waiting for its timelock to pass
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDenomMigration {
+    pub new_denom: String,
+    pub conversion_contract: CanonicalAddr,
+    pub effective_at: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDenomMigrationResponse {
+    pub new_denom: String,
+    pub conversion_contract: HumanAddr,
+    pub effective_at: u64,
+}
+
+/// Interface of the conversion contract, which must send
+/// the same amount of `target_denom` back to the sender
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionHandleMsg {
+    Convert { target_denom: String },
+}
+
+/// Propose a new stable denom, executable by the owner once the
+/// timelock has passed. A new proposal replaces the previous one.
+pub fn propose_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: &CanonicalAddr,
+    new_denom: String,
+    conversion_contract: HumanAddr,
+) -> HandleResult {
+    if deps.api.canonical_address(&env.message.sender)? != *owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let effective_at = env.block.time + DENOM_MIGRATION_TIMELOCK_PERIOD;
+    singleton(&mut deps.storage, KEY_PENDING_DENOM_MIGRATION).save(&PendingDenomMigration {
+        new_denom: new_denom.clone(),
+        conversion_contract: deps.api.canonical_address(&conversion_contract)?,
+        effective_at,
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "propose_denom_migration"),
+            log("new_denom", new_denom),
+            log("conversion_contract", conversion_contract),
+            log("effective_at", effective_at),
+        ],
+        data: None,
+    })
+}
+
+/// Consume the pending migration, which the caller must apply to
+/// its config together with the returned conversion messages.
+///
+/// Sending the balance to the conversion contract is taxed like any
+/// other transfer, and the tax is paid out of the balance itself, so
+/// `conversion_tax` less comes back in the new denom. The caller must
+/// account for this loss; it is returned as the last element.
+pub fn claim_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &CanonicalAddr,
+    old_denom: &str,
+) -> StdResult<(PendingDenomMigration, Vec<CosmosMsg>, Uint256)> {
+    if deps.api.canonical_address(&env.message.sender)? != *owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let migration: PendingDenomMigration =
+        match singleton_read(&deps.storage, KEY_PENDING_DENOM_MIGRATION).may_load()? {
+            Some(v) => v,
+            None => return Err(StdError::generic_err("No pending denom migration")),
+        };
+
+    if env.block.time < migration.effective_at {
+        return Err(StdError::generic_err(format!(
+            "Denom migration is not effective until {}",
+            migration.effective_at
+        )));
+    }
+
+    singleton::<S, PendingDenomMigration>(&mut deps.storage, KEY_PENDING_DENOM_MIGRATION).remove();
+
+    // Convert the whole balance of the old denom
+    let balance = query_balance(deps, &env.contract.address, old_denom.to_string())?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut conversion_tax = Uint256::zero();
+    if !balance.is_zero() {
+        let send_coin = deduct_tax(
+            deps,
+            Coin {
+                denom: old_denom.to_string(),
+                amount: balance.into(),
+            },
+        )?;
+        conversion_tax = balance - Uint256::from(send_coin.amount);
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&migration.conversion_contract)?,
+            send: vec![send_coin],
+            msg: to_binary(&ConversionHandleMsg::Convert {
+                target_denom: migration.new_denom.clone(),
+            })?,
+        }));
+    }
+
+    Ok((migration, messages, conversion_tax))
+}
+
+pub fn query_pending_denom_migration<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<PendingDenomMigrationResponse> {
+    let migration: PendingDenomMigration =
+        match singleton_read(&deps.storage, KEY_PENDING_DENOM_MIGRATION).may_load()? {
+            Some(v) => v,
+            None => return Err(StdError::generic_err("No pending denom migration")),
+        };
+
+    Ok(PendingDenomMigrationResponse {
+        new_denom: migration.new_denom,
+        conversion_contract: deps.api.human_address(&migration.conversion_contract)?,
+        effective_at: migration.effective_at,
+    })
+}
+
+/// Defer the pending migration by the duration of a chain halt
+pub fn shift_pending_denom_migration<S: Storage>(storage: &mut S, duration: u64) -> StdResult<()> {
+    let pending_migration: Option<PendingDenomMigration> =
+        singleton_read(storage, KEY_PENDING_DENOM_MIGRATION).may_load()?;
+    if let Some(mut pending_migration) = pending_migration {
+        pending_migration.effective_at += duration;
+        singleton(storage, KEY_PENDING_DENOM_MIGRATION).save(&pending_migration)?;
+    }
+
+    Ok(())
+}
@@ -23,6 +23,21 @@ pub enum HandleMsg {
         increment_multiplier: Option<Decimal256>,
         decrement_multiplier: Option<Decimal256>,
     },
+    /// Replace the emission schedule; an empty schedule
+    /// leaves only the config cap and floor in effect
+    UpdateEmissionSchedule { schedule: Vec<EmissionSegment> },
+}
+
+/// Cap and floor in effect over [start_height, end_height). Both
+/// shrink linearly over the segment, ending `decay` lower than
+/// they started; a zero decay keeps them flat
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionSegment {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub emission_cap: Decimal256,
+    pub emission_floor: Decimal256,
+    pub decay: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -34,6 +49,12 @@ pub enum QueryMsg {
         target_deposit_rate: Decimal256,
         threshold_deposit_rate: Decimal256,
         current_emission_rate: Decimal256,
+        /// Selects the segment of the emission schedule; without it
+        /// the config cap and floor are used
+        block_height: Option<u64>,
+    },
+    ActiveEmissionSegment {
+        block_height: u64,
     },
 }
 
@@ -53,6 +74,15 @@ pub struct AncEmissionRateResponse {
     pub emission_rate: Decimal256,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActiveEmissionSegmentResponse {
+    pub segment: Option<EmissionSegment>,
+    /// Cap and floor in effect at the height
+    pub emission_cap: Decimal256,
+    pub emission_floor: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {}
@@ -0,0 +1,62 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+
+use crate::denom_migration::shift_pending_denom_migration;
+use crate::ownership::shift_pending_owner;
+
+static KEY_HALT_RECOVERY: &[u8] = b"halt_recovery";
+
+/// Last chain halt the stored deadlines were shifted for
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HaltRecovery {
+    pub halted_at: u64,
+    pub recovered_at: u64,
+}
+
+impl HaltRecovery {
+    pub fn duration(&self) -> u64 {
+        self.recovered_at - self.halted_at
+    }
+}
+
+/// Record the halt which lasted from `halted_at` until the current block
+/// and shift the pending owner and denom migration deadlines by its
+/// duration. Each halt can only be recovered once; the caller shifts
+/// its own deadlines by the returned duration
+pub fn record_halt_recovery<S: Storage>(
+    storage: &mut S,
+    halted_at: u64,
+    block_time: u64,
+) -> StdResult<u64> {
+    if halted_at >= block_time {
+        return Err(StdError::generic_err("Halt must have ended before now"));
+    }
+
+    if let Some(last_recovery) = read_halt_recovery(storage)? {
+        if halted_at < last_recovery.recovered_at {
+            return Err(StdError::generic_err(format!(
+                "Halt has already been recovered: {}",
+                last_recovery.recovered_at
+            )));
+        }
+    }
+
+    let halt_recovery = HaltRecovery {
+        halted_at,
+        recovered_at: block_time,
+    };
+    singleton(storage, KEY_HALT_RECOVERY).save(&halt_recovery)?;
+
+    let duration = halt_recovery.duration();
+    shift_pending_owner(storage, duration)?;
+    shift_pending_denom_migration(storage, duration)?;
+
+    Ok(duration)
+}
+
+pub fn read_halt_recovery<S: Storage>(storage: &S) -> StdResult<Option<HaltRecovery>> {
+    singleton_read(storage, KEY_HALT_RECOVERY).may_load()
+}
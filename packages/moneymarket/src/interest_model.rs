@@ -9,6 +9,35 @@ pub struct InitMsg {
     pub owner: HumanAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    /// Defaults to the linear model
+    pub rate_model: Option<RateModel>,
+    /// Bounds applied to the borrow rate of the model
+    pub min_borrow_rate: Option<Decimal256>,
+    pub max_borrow_rate: Option<Decimal256>,
+    /// Drive the borrow rate with the smoothed utilization
+    /// reported by the market; defaults to the spot utilization
+    pub use_smoothed_utilization: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateModel {
+    /// base_rate + utilization * interest_multiplier
+    Linear {},
+    /// base + utilization * slope_1 up to the optimal utilization,
+    /// plus (utilization - optimal_utilization) * slope_2 past it
+    KinkedRate {
+        base: Decimal256,
+        slope_1: Decimal256,
+        slope_2: Decimal256,
+        optimal_utilization: Decimal256,
+    },
+}
+
+impl Default for RateModel {
+    fn default() -> Self {
+        RateModel::Linear {}
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -18,6 +47,13 @@ pub enum HandleMsg {
         owner: Option<HumanAddr>,
         base_rate: Option<Decimal256>,
         interest_multiplier: Option<Decimal256>,
+        rate_model: Option<RateModel>,
+        min_borrow_rate: Option<Decimal256>,
+        max_borrow_rate: Option<Decimal256>,
+        /// Remove both borrow rate bounds; bounds given
+        /// in the same message are applied after it
+        clear_borrow_rate_bounds: Option<bool>,
+        use_smoothed_utilization: Option<bool>,
     },
 }
 
@@ -29,6 +65,14 @@ pub enum QueryMsg {
         market_balance: Uint256,
         total_liabilities: Decimal256,
         total_reserves: Decimal256,
+        /// Moving average of the utilization kept by the market,
+        /// used instead of the spot utilization when enabled
+        smoothed_utilization: Option<Decimal256>,
+    },
+    /// Borrow rates the current parameters produce
+    /// for the given historical utilization ratios
+    BacktestRates {
+        utilizations: Vec<Decimal256>,
     },
 }
 
@@ -38,6 +82,10 @@ pub struct ConfigResponse {
     pub owner: HumanAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub rate_model: RateModel,
+    pub min_borrow_rate: Option<Decimal256>,
+    pub max_borrow_rate: Option<Decimal256>,
+    pub use_smoothed_utilization: bool,
 }
 
 // We define a custom struct for each query response
@@ -45,3 +93,9 @@ pub struct ConfigResponse {
 pub struct BorrowRateResponse {
     pub rate: Decimal256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BacktestRatesResponse {
+    pub rates: Vec<Decimal256>,
+}
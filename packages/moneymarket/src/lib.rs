@@ -1,11 +1,25 @@
 pub mod custody;
+pub mod custody_multi;
+pub mod decimals;
+pub mod denom_migration;
 pub mod distribution_model;
+pub mod halt_recovery;
 pub mod interest_model;
 pub mod liquidation;
+pub mod liquidation_vault;
 pub mod market;
 pub mod oracle;
 pub mod overseer;
+pub mod ownership;
+pub mod pause;
+pub mod payout;
 pub mod querier;
+pub mod raw_state;
+pub mod reserve;
+pub mod rounding;
+pub mod swap;
+pub mod sweep;
+pub mod tax;
 pub mod tokens;
 
 #[cfg(test)]
@@ -1,10 +1,15 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use std::fmt;
+
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::HumanAddr;
-use cw20::Cw20ReceiveMsg;
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, HumanAddr, StdResult, WasmMsg};
+use cw20::{Cw20HandleMsg, Cw20ReceiveMsg};
 
+use crate::oracle::PriceMode;
+use crate::sweep::SweepAsset;
+use crate::tax::TaxPolicy;
 use crate::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -18,6 +23,9 @@ pub struct InitMsg {
     /// Fee applied to executed bids
     /// Sent to Overseer interest buffer
     pub bid_fee: Decimal256,
+    /// Fee applied to executed bids
+    /// Sent to the liquidation executor as incentive
+    pub liquidator_fee: Decimal256,
     /// Maximum fee applied to liquidated collaterals
     /// Sent to liquidator as incentive
     pub max_premium_rate: Decimal256,
@@ -25,54 +33,367 @@ pub struct InitMsg {
     /// When the current collaterals value is smaller than
     /// the threshold, all collaterals will be liquidated
     pub liquidation_threshold: Uint256,
+    /// Maximum portion of the collaterals a single
+    /// liquidation can seize, in (0, 1]
+    pub max_close_factor: Decimal256,
     /// Valid oracle price timeframe
     pub price_timeframe: u64,
+    /// Oracle price aggregation used to value liquidated collaterals
+    pub price_mode: PriceMode,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum HandleMsg {
     Receive(Cw20ReceiveMsg),
+    /// Propose a new owner, who must accept within `expires_in` seconds
+    ProposeOwner {
+        new_owner: HumanAddr,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership transfer
+    AcceptOwnership {},
+    /// Propose a new stable denom, executable after the timelock
+    ProposeDenomMigration {
+        new_denom: String,
+        conversion_contract: HumanAddr,
+    },
+    /// Convert the old denom balance and switch to the new denom
+    ExecuteDenomMigration {},
     UpdateConfig {
-        owner: Option<HumanAddr>,
         oracle_contract: Option<HumanAddr>,
-        stable_denom: Option<String>,
         safe_ratio: Option<Decimal256>,
         bid_fee: Option<Decimal256>,
+        liquidator_fee: Option<Decimal256>,
         max_premium_rate: Option<Decimal256>,
         liquidation_threshold: Option<Uint256>,
+        max_close_factor: Option<Decimal256>,
         price_timeframe: Option<u64>,
+        price_mode: Option<PriceMode>,
+        guardian: Option<HumanAddr>,
+        /// Collaterals worth less than this in stable denom are left
+        /// out of the LiquidationAmount response; zero disables it
+        min_liquidation: Option<Uint256>,
+        /// Contract that receives `reserve_fee_share` of every bid fee
+        reserve_contract: Option<HumanAddr>,
+        reserve_fee_share: Option<Decimal256>,
+        referral_fee_share: Option<Decimal256>,
+        /// Bids below this amount can be swept into the reserve
+        /// contract by the owner; zero disables sweeping
+        dust_threshold: Option<Uint256>,
+        delegated_bids_enabled: Option<bool>,
+        /// Cw20 token paid out as bid incentives
+        reward_token: Option<HumanAddr>,
+        /// Stable value of the bids that can be filled per collateral
+        /// within a fill window; zero disables the limit
+        max_fill_per_window: Option<Uint256>,
+        /// Length of the fill window in seconds; zero limits
+        /// the fills of a single block
+        fill_window: Option<u64>,
+        /// Once set, the bid fees owed to the fee address accrue in
+        /// the contract until this address withdraws them
+        fee_admin: Option<HumanAddr>,
+        /// Overseer allowed to stage the collaterals its
+        /// custodies send for a single ExecuteBids
+        overseer_contract: Option<HumanAddr>,
     },
+    /// Activate the pending risk parameter change once its
+    /// timelock has passed. Callable by anyone
+    ApplyConfig {},
     SubmitBid {
         collateral_token: HumanAddr,
         premium_rate: Decimal256,
+        /// Block time from which the bid can no longer be filled
+        expires_at: Option<u64>,
+        /// Frontend credited with `referral_fee_share` of the bid fees
+        referrer: Option<HumanAddr>,
+        /// Address recorded as the bidder instead of the sender. It
+        /// alone can retract the bid and receives the collateral
+        bid_for: Option<HumanAddr>,
+        /// Address receiving the collateral bought by the bid;
+        /// defaults to the bidder
+        unlock_receiver: Option<HumanAddr>,
     },
     RetractBid {
         collateral_token: HumanAddr,
         amount: Option<Uint256>,
     },
+    /// Bid that can be filled by any of the collaterals; a bid on
+    /// the specific collateral is filled first
+    SubmitCrossBid {
+        collateral_tokens: Vec<HumanAddr>,
+        premium_rate: Decimal256,
+    },
+    RetractCrossBid {
+        amount: Option<Uint256>,
+    },
+    /// Sell the collaterals staged by the sender to the bids of the
+    /// `liquidator` at once; the payouts are aggregated per recipient.
+    /// The fee and repay addresses default to the sender and the
+    /// liquidator fee address to the `liquidator` when not given
+    ExecuteBids {
+        liquidator: HumanAddr,
+        collaterals: Vec<(HumanAddr, Uint256)>,
+        fee_address: Option<HumanAddr>,
+        repay_address: Option<HumanAddr>,
+        liquidator_fee_address: Option<HumanAddr>,
+        borrower: Option<HumanAddr>,
+    },
+    /// Move the sender's bid on the collateral to another address
+    TransferBid {
+        collateral_token: HumanAddr,
+        new_bidder: HumanAddr,
+    },
+    /// Change the premium rate of the sender's bid on the collateral
+    /// without retracting and submitting it again
+    MoveBid {
+        collateral_token: HumanAddr,
+        new_premium_rate: Decimal256,
+    },
+    /// Route the collateral bought by the sender's bid on the
+    /// collateral to another address, or back to the sender when
+    /// not given
+    UpdateBidReceiver {
+        collateral_token: HumanAddr,
+        unlock_receiver: Option<HumanAddr>,
+    },
+    /// Blacklisted addresses cannot submit bids or receive
+    /// liquidated collaterals, but can still retract their bids
+    UpdateBlacklist {
+        add: Vec<HumanAddr>,
+        remove: Vec<HumanAddr>,
+    },
+    /// When enabled, only allowlisted addresses can submit bids
+    /// or have their bids filled, including overseer liquidations
+    UpdateAllowlist {
+        enabled: Option<bool>,
+        add: Vec<HumanAddr>,
+        remove: Vec<HumanAddr>,
+    },
+    /// Stop executing bids for the collateral
+    PauseCollateral {
+        collateral_token: HumanAddr,
+    },
+    ResumeCollateral {
+        collateral_token: HumanAddr,
+    },
+    /// Retire the collateral: new bids are rejected and the
+    /// outstanding bids can still be executed or retracted
+    DeprecateCollateral {
+        collateral_token: HumanAddr,
+    },
+    /// Register the decimals of a collateral token;
+    /// unregistered collaterals are treated as 6-decimal tokens
+    RegisterCollateral {
+        collateral_token: HumanAddr,
+        decimals: u8,
+    },
+    /// Reject oracle prices of the collateral outside the bounds
+    /// when executing bids; None removes a bound. Owner only
+    SetPriceBounds {
+        collateral_token: HumanAddr,
+        min_price: Option<Decimal256>,
+        max_price: Option<Decimal256>,
+    },
+    /// Price the collateral with another oracle than the configured
+    /// one, e.g. a feed specific to the asset; None removes the
+    /// override. Owner only
+    SetPriceSource {
+        collateral_token: HumanAddr,
+        oracle_contract: Option<HumanAddr>,
+    },
+    /// Register the custody contract allowed to stage the collateral
+    /// for ExecuteBids; None removes it. Owner only
+    SetCollateralCustody {
+        collateral_token: HumanAddr,
+        custody_contract: Option<HumanAddr>,
+    },
+    /// Transfer out funds which are not part of the tracked state
+    SweepUnrelatedFunds {
+        asset: SweepAsset,
+        recipient: HumanAddr,
+    },
+    /// Halt or resume flows; the guardian can only pause
+    SetPause {
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// Set how the tax on stable transfers is computed
+    UpdateTaxPolicy {
+        tax_policy: TaxPolicy,
+    },
+    /// Shift the stored deadlines by the duration of the chain halt
+    /// which started at `halted_at`; each halt is recovered once
+    RecoverFromHalt {
+        halted_at: u64,
+    },
+    /// Forward up to `limit` outstanding bids into the liquidation
+    /// queue; new bids are rejected once the migration has started
+    /// and cross bids are refunded to their bidders
+    MigrateBids {
+        new_liquidation_queue: HumanAddr,
+        limit: Option<u32>,
+    },
+    /// Refund up to `limit` expired bids on the collateral to
+    /// their bidders. Callable by anyone
+    PruneExpiredBids {
+        collateral_token: HumanAddr,
+        limit: Option<u32>,
+    },
+    /// Send the referral fees accrued by the sender
+    ClaimReferralFees {},
+    /// Send `amount` of the accrued bid fees, or all of them,
+    /// to the recipient. Fee admin only
+    WithdrawFees {
+        recipient: HumanAddr,
+        amount: Option<Uint256>,
+    },
+    /// Move up to `limit` bids on the collateral that are below the
+    /// dust threshold into the reserve contract. Owner only
+    SweepDust {
+        collateral_token: HumanAddr,
+        limit: Option<u32>,
+    },
+    /// Register the contract converting the collateral into
+    /// `claim_as`; None removes it. Owner only
+    RegisterConverter {
+        collateral_token: HumanAddr,
+        claim_as: ClaimAsset,
+        converter: Option<HumanAddr>,
+    },
+    /// Receive the collateral bought by the sender's bid in the
+    /// given form; None sends the collateral as is
+    SetClaimAs {
+        collateral_token: HumanAddr,
+        claim_as: Option<ClaimAsset>,
+    },
+    /// Reward tokens emitted per second to the bids on the
+    /// collateral, shared pro-rata to their active amounts
+    SetIncentiveRate {
+        collateral_token: HumanAddr,
+        emission_rate: Decimal256,
+    },
+    ClaimRewards {
+        collateral_token: HumanAddr,
+    },
 }
 
+/// Form in which a bidder receives the collateral bought by its bid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimAsset {
+    /// Swapped to the stable denom
+    Swap,
+    /// Unbonded to the underlying asset
+    Unbond,
+}
+
+impl ClaimAsset {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ClaimAsset::Swap => "swap",
+            ClaimAsset::Unbond => "unbond",
+        }
+    }
+}
+
+impl fmt::Display for ClaimAsset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Hook sent along with the collateral to a registered converter,
+/// which delivers the converted asset to `recipient`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConverterHookMsg {
+    Convert {
+        claim_as: ClaimAsset,
+        recipient: HumanAddr,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
+
+/// Version of the caller-facing liquidation interface, which is
+/// the ExecuteBid hook together with its repay and fee semantics.
+/// Bumped on any breaking change so external markets can check it
+pub const LIQUIDATION_INTERFACE_VERSION: u32 = 1;
+
+/// Version of the query response layout, carried by every response
+/// as `schema_version` so SDK generators can detect breaking changes
+pub const LIQUIDATION_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
+    /// Sell the sent collaterals to the bids of the `liquidator`.
+    /// The fee and repay addresses default to the cw20 sender and the
+    /// liquidator fee address to the `liquidator` when not given
     ExecuteBid {
         liquidator: HumanAddr,
+        /// Receives the bid fee in stable denom
         fee_address: Option<HumanAddr>,
+        /// Receives the repay amount in stable denom
         repay_address: Option<HumanAddr>,
+        /// Receives the liquidator fee in stable denom
+        liquidator_fee_address: Option<HumanAddr>,
+        /// Owner of the liquidated position, recorded in the history
+        borrower: Option<HumanAddr>,
     },
+    /// Hold the sent collaterals for an ExecuteBids call of the
+    /// `operator`, which defaults to the cw20 sender. Only the custody
+    /// registered for the collateral can stage, for itself or the overseer
+    StageCollateral { operator: Option<HumanAddr> },
+}
+
+/// Send `amount` of the collateral token to the liquidation contract
+/// with the given hook; this is all a lending market needs to plug
+/// its collateral into the liquidation contract
+pub fn execute_bid_msg<T: Clone + fmt::Debug + PartialEq + JsonSchema>(
+    collateral_token: HumanAddr,
+    liquidation_contract: HumanAddr,
+    amount: Uint256,
+    hook: Cw20HookMsg,
+) -> StdResult<CosmosMsg<T>> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: collateral_token,
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Send {
+            contract: liquidation_contract,
+            amount: amount.into(),
+            msg: Some(to_binary(&hook)?),
+        })?,
+    }))
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingConfig {},
+    PendingOwner {},
+    PendingDenomMigration {},
     LiquidationAmount {
         borrow_amount: Uint256,
         borrow_limit: Uint256,
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
     },
+    /// LiquidationAmount with the collateral prices read from the
+    /// configured oracle; fails on a price older than price_timeframe
+    LiquidationAmountAtOraclePrices {
+        borrow_amount: Uint256,
+        borrow_limit: Uint256,
+        collaterals: TokensHuman,
+        block_time: u64,
+    },
     Bid {
         collateral_token: HumanAddr,
         bidder: HumanAddr,
@@ -82,43 +403,432 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    CrossBid {
+        bidder: HumanAddr,
+    },
+    /// Dry run of ExecuteBid against the bid of `bidder`,
+    /// optionally at a hypothetical price
+    SimulateLiquidation {
+        collateral_token: HumanAddr,
+        collateral_amount: Uint256,
+        bidder: HumanAddr,
+        price_override: Option<Decimal256>,
+    },
+    /// Running bid and fill totals of the bidder per collateral
+    BidderStats {
+        bidder: HumanAddr,
+    },
     BidsByCollateral {
         collateral_token: HumanAddr,
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    Blacklist {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    Allowlist {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    DeprecatedCollaterals {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    /// Executed bids of the collateral, optionally filtered by borrower
+    LiquidationHistory {
+        collateral_token: HumanAddr,
+        borrower: Option<HumanAddr>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    CollateralStatus {
+        collateral_token: HumanAddr,
+        block_time: u64,
+    },
+    CollateralInfo {
+        collateral_token: HumanAddr,
+    },
+    PauseInfo {},
+    TaxPolicy {},
+    /// Last recovered chain halt, if any
+    HaltRecovery {},
+    /// Liquidation queue the bids are migrated into, if any
+    BidMigration {},
+    ReferralFees {
+        referrer: HumanAddr,
+    },
+    /// Bid fees accrued for the fee admin
+    AccruedFees {},
+    SweptDust {
+        collateral_token: HumanAddr,
+    },
+    Converter {
+        collateral_token: HumanAddr,
+        claim_as: ClaimAsset,
+    },
+    ClaimAs {
+        collateral_token: HumanAddr,
+        bidder: HumanAddr,
+    },
+    /// Compare a page of bids on the collateral with the
+    /// bidder stats kept alongside them
+    Invariants {
+        collateral_token: HumanAddr,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    /// Rewards of the bidder, accrued up to `block_time` if given
+    PendingRewards {
+        bidder: HumanAddr,
+        collateral_token: HumanAddr,
+        block_time: Option<u64>,
+    },
+    InterfaceVersion {},
+    /// Raw key/values of an exportable storage namespace
+    RawState {
+        namespace: String,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// Every stored bid in storage order; `start_after`
+    /// is the (bidder, collateral_token) of the last bid
+    ExportBids {
+        start_after: Option<(HumanAddr, HumanAddr)>,
+        limit: Option<u32>,
+    },
+    /// Every stored cross bid in storage order
+    ExportCrossBids {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub owner: HumanAddr,
     pub oracle_contract: HumanAddr,
     pub stable_denom: String,
     pub safe_ratio: Decimal256,
     pub bid_fee: Decimal256,
+    pub liquidator_fee: Decimal256,
     pub max_premium_rate: Decimal256,
     pub liquidation_threshold: Uint256,
+    pub max_close_factor: Decimal256,
     pub price_timeframe: u64,
+    pub price_mode: PriceMode,
+    pub guardian: Option<HumanAddr>,
+    pub min_liquidation: Uint256,
+    pub reserve_contract: Option<HumanAddr>,
+    pub reserve_fee_share: Decimal256,
+    pub referral_fee_share: Decimal256,
+    pub dust_threshold: Uint256,
+    pub delegated_bids_enabled: bool,
+    pub reward_token: Option<HumanAddr>,
+    pub max_fill_per_window: Uint256,
+    pub fill_window: u64,
+    pub fee_admin: Option<HumanAddr>,
+    pub overseer_contract: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InterfaceVersionResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub version: u32,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingConfigResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub safe_ratio: Option<Decimal256>,
+    pub bid_fee: Option<Decimal256>,
+    pub liquidator_fee: Option<Decimal256>,
+    pub max_premium_rate: Option<Decimal256>,
+    pub liquidation_threshold: Option<Uint256>,
+    pub max_close_factor: Option<Decimal256>,
+    pub effective_at: u64,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct LiquidationAmountResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub collaterals: TokensHuman,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub collateral_token: HumanAddr,
     pub bidder: HumanAddr,
     pub amount: Uint256,
     pub premium_rate: Decimal256,
+    pub expires_at: Option<u64>,
+    pub unlock_receiver: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CrossBidResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub bidder: HumanAddr,
+    pub collateral_tokens: Vec<HumanAddr>,
+    pub amount: Uint256,
+    pub premium_rate: Decimal256,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub bids: Vec<BidResponse>,
+    /// Total bids of the bidder; only set by `BidsByUser`
+    pub total_count: Option<u64>,
+    /// `start_after` of the next page, set while the page is
+    /// full. The next page may still turn out empty
+    pub next_start_after: Option<HumanAddr>,
+}
+
+/// Stored bid with every field, including the ones
+/// the regular bid queries leave out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportedBid {
+    pub bidder: HumanAddr,
+    pub collateral_token: HumanAddr,
+    pub amount: Uint256,
+    pub premium_rate: Decimal256,
+    pub expires_at: Option<u64>,
+    pub referrer: Option<HumanAddr>,
+    pub unlock_receiver: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportBidsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub bids: Vec<ExportedBid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExportCrossBidsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub cross_bids: Vec<CrossBidResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateLiquidationResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub collateral_amount: Uint256,
+    pub price: Decimal256,
+    pub premium_rate: Decimal256,
+    pub required_stable: Uint256,
+    pub repay_amount: Uint256,
+    pub bid_fee: Uint256,
+    pub liquidator_fee: Uint256,
+    pub remaining_bid_amount: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidderCollateralStats {
+    pub collateral_token: HumanAddr,
+    pub active_bid_amount: Uint256,
+    pub filled_amount: Uint256,
+    pub collateral_received: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvariantsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub sampled_bids: u32,
+    /// Sum of the sampled bid amounts
+    pub total_bid_amount: Uint256,
+    /// Sum of the active bid amounts in the stats of the sampled bidders
+    pub total_active_bid_amount: Uint256,
+    /// Absolute difference between the two totals
+    pub drift: Uint256,
+    /// Sampled bidders whose bid and stats disagree
+    pub mismatched_bidders: Vec<HumanAddr>,
+    /// Last sampled bidder, to continue from
+    pub last_bidder: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SweptDustResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub total_swept: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralFeesResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub referrer: HumanAddr,
+    pub pending_fees: Uint256,
+    pub claimed_fees: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccruedFeesResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub fee_admin: Option<HumanAddr>,
+    pub denom: String,
+    pub pending_fees: Uint256,
+    pub withdrawn_fees: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidderStatsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub bidder: HumanAddr,
+    pub total_active_bid_amount: Uint256,
+    pub total_filled_amount: Uint256,
+    pub collaterals: Vec<BidderCollateralStats>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlacklistResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub addresses: Vec<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub enabled: bool,
+    pub addresses: Vec<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DeprecatedCollateralsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collaterals: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationRecordResponse {
+    pub idx: u64,
+    pub collateral_token: HumanAddr,
+    pub borrower: Option<HumanAddr>,
+    pub repay_amount: Uint256,
+    pub collateral_amount: Uint256,
+    pub avg_premium: Decimal256,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationHistoryResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub records: Vec<LiquidationRecordResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceStatus {
+    Fresh,
+    Stale,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralStatusResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub paused: bool,
+    pub price_status: PriceStatus,
+    pub last_updated_base: u64,
+    pub last_updated_quote: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralInfoResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub decimals: u8,
+    pub min_price: Option<Decimal256>,
+    pub max_price: Option<Decimal256>,
+    /// Oracle overriding the configured one for this collateral
+    pub price_source: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConverterResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub claim_as: ClaimAsset,
+    pub converter: Option<HumanAddr>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimAsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub collateral_token: HumanAddr,
+    pub bidder: HumanAddr,
+    pub claim_as: Option<ClaimAsset>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub bidder: HumanAddr,
+    pub collateral_token: HumanAddr,
+    pub emission_rate: Decimal256,
+    pub pending_rewards: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidMigrationResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub new_liquidation_queue: Option<HumanAddr>,
+}
+
+/// Interface of the liquidation queue the bids are migrated into
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationQueueHandleMsg {
+    /// `bidder` is only honored from the legacy liquidation
+    /// contract, which submits the bids on behalf of their owners
+    SubmitBid {
+        collateral_token: HumanAddr,
+        premium_slot: u8,
+        bidder: Option<HumanAddr>,
+    },
 }
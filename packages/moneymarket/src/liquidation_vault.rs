@@ -0,0 +1,93 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub owner: HumanAddr,
+    pub stable_denom: String,
+    pub liquidation_contract: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub oracle_contract: HumanAddr,
+}
+
+/// Portion of the vault funds bid on a collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyAllocation {
+    pub collateral_token: HumanAddr,
+    pub premium_rate: Decimal256,
+    pub weight: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    UpdateConfig {
+        owner: Option<HumanAddr>,
+    },
+    /// Replace the bidding strategy; the sum of the
+    /// weights cannot exceed one, the rest is kept idle
+    UpdateStrategy {
+        allocations: Vec<StrategyAllocation>,
+    },
+    /// Deposit stable denom in exchange for vault shares
+    DepositStable {},
+    /// Burn vault shares for a pro-rata part of the stable
+    /// balance, the active bids and the received collaterals
+    Withdraw {
+        shares: Uint256,
+    },
+    /// Retract every bid of the vault and bid the whole
+    /// stable balance again following the strategy
+    Rebalance {},
+    /// Liquidate the borrower against the vault bids, so the
+    /// vault receives the collaterals and the liquidator fee
+    Liquidate {
+        borrower: HumanAddr,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Strategy {},
+    State {},
+    Shares { address: HumanAddr },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: HumanAddr,
+    pub stable_denom: String,
+    pub liquidation_contract: HumanAddr,
+    pub overseer_contract: HumanAddr,
+    pub oracle_contract: HumanAddr,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyResponse {
+    pub allocations: Vec<StrategyAllocation>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateResponse {
+    pub total_shares: Uint256,
+    pub stable_balance: Uint256,
+    pub bid_amount: Uint256,
+    /// Held collaterals with their value in stable denom
+    pub collaterals: Vec<(HumanAddr, Uint256, Uint256)>,
+    pub total_value: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SharesResponse {
+    pub address: HumanAddr,
+    pub shares: Uint256,
+}
@@ -2,9 +2,12 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{Binary, HumanAddr};
 use cw20::Cw20ReceiveMsg;
 
+use crate::sweep::SweepAsset;
+use crate::tax::TaxPolicy;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InitMsg {
@@ -47,14 +50,61 @@ pub enum HandleMsg {
     /// A-Token will invoke this after init
     RegisterATerra {},
 
+    /// Propose a new owner, who must accept within `expires_in` seconds
+    ProposeOwner {
+        new_owner: HumanAddr,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership transfer
+    AcceptOwnership {},
+    /// Propose a new stable denom, executable after the timelock
+    ProposeDenomMigration {
+        new_denom: String,
+        conversion_contract: HumanAddr,
+    },
+    /// Convert the old denom balance and switch to the new denom
+    ExecuteDenomMigration {},
+
     /// Update config values
     UpdateConfig {
-        owner_addr: Option<HumanAddr>,
         max_borrow_factor: Option<Decimal256>,
         interest_model: Option<HumanAddr>,
         distribution_model: Option<HumanAddr>,
+        guardian: Option<HumanAddr>,
+        flash_fee: Option<Decimal256>,
+        /// Reserve transfers above this amount must be announced
+        reserve_announcement_threshold: Option<Uint256>,
+        /// Blocks between the announcement and the transfer
+        reserve_announcement_delay: Option<u64>,
+        /// Blocks after which the previous utilization average
+        /// weighs half; zero disables the smoothing
+        utilization_half_life: Option<u64>,
+        /// Fixed-term deposits offered; empty disables them
+        lockup_terms: Option<Vec<LockupTerm>>,
+        /// Ratio of the principal charged when a locked
+        /// position is withdrawn before its maturity
+        early_withdrawal_penalty: Option<Decimal256>,
     },
 
+    /// Transfer out funds which are not part of the tracked state
+    SweepUnrelatedFunds {
+        asset: SweepAsset,
+        recipient: HumanAddr,
+    },
+    /// Halt or resume flows; the guardian can only pause
+    SetPause {
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// Set how the tax on stable transfers is computed
+    UpdateTaxPolicy {
+        tax_policy: TaxPolicy,
+    },
+    /// Drop the announced reserve transfer; owner or guardian
+    CancelReserveAnnouncement {},
+
     ////////////////////
     /// Overseer operations
     ////////////////////
@@ -64,6 +114,20 @@ pub enum HandleMsg {
         prev_balance: Uint256,
     },
 
+    /// Stop or restart the interest accrual of a borrower
+    /// backed by a halted collateral
+    SetAccrualFrozen {
+        borrower: HumanAddr,
+        frozen: bool,
+    },
+
+    /// Write off the loan left after a liquidation could not repay it;
+    /// the reserves absorb the loss first and depositors the rest.
+    /// Sent by the overseer once the borrower has no collaterals left
+    WriteOffBadDebt {
+        borrower: HumanAddr,
+    },
+
     /// Execute epoch operations
     /// 1. send reserve to collector contract
     /// 2. update anc_emission_rate state
@@ -74,11 +138,33 @@ pub enum HandleMsg {
         distributed_interest: Uint256,
     },
 
+    /// Fund the interest owed to the locked positions
+    /// with the stable coins sent from the interest buffer
+    FundLockedInterest {},
+
     ////////////////////
     /// User operations
     ////////////////////
-    /// Deposit stable asset to get interest
-    DepositStable {},
+    /// Deposit stable asset to get interest;
+    /// reverts when less than `min_mint_amount` aterra is minted
+    DepositStable {
+        min_mint_amount: Option<Uint256>,
+    },
+    /// Deposit stable asset, minting the aterra to `recipient`
+    DepositStableFor {
+        recipient: HumanAddr,
+        min_mint_amount: Option<Uint256>,
+    },
+    /// Lock the sent stable asset for `duration` seconds at the
+    /// fixed rate of the matching lockup term; no aterra is minted
+    DepositStableLocked {
+        duration: u64,
+    },
+    /// Withdraw a locked position; before its maturity the
+    /// interest is forfeited and the penalty is charged
+    WithdrawLocked {
+        position_id: u64,
+    },
 
     /// Borrow stable asset with collaterals in overseer contract
     BorrowStable {
@@ -88,19 +174,45 @@ pub enum HandleMsg {
 
     /// Repay stable asset to decrease liability
     RepayStable {},
+    /// Repay stable asset to decrease the liability of `borrower`;
+    /// the amount exceeding the loan is returned to the sender
+    RepayStableFor {
+        borrower: HumanAddr,
+    },
 
     /// Claim distributed ANC rewards
     ClaimRewards {
         to: Option<HumanAddr>,
     },
+
+    /// Lend stable asset to the sender and execute `msg` on it;
+    /// `amount` plus the flash fee must be back before the end
+    /// of the transaction or the whole transaction reverts
+    FlashBorrow {
+        amount: Uint256,
+        msg: Binary,
+    },
+
+    /// (internal) Check the flash loan was repaid with the fee
+    AssertFlashRepayment {},
+}
+
+/// Fixed-term deposit offered by the market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockupTerm {
+    /// Lockup duration in seconds
+    pub duration: u64,
+    /// Fixed yearly rate paid at maturity
+    pub rate: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
-    /// according to exchange rate
-    RedeemStable {},
+    /// according to exchange rate; reverts when
+    /// less than `min_redeem_amount` would be sent
+    RedeemStable { min_redeem_amount: Option<Uint256> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -113,6 +225,8 @@ pub struct MigrateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
+    PendingDenomMigration {},
     State {
         block_height: Option<u64>,
     },
@@ -128,6 +242,54 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    /// State and exchange rate projected to `block_height`,
+    /// assuming the current rates hold
+    StateProjected {
+        block_height: u64,
+    },
+    /// Liability projected to `block_height`,
+    /// assuming the current rates hold
+    BorrowerInfoProjected {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
+    /// Liability at the nearest checkpoint at or before `block_height`
+    BorrowerSnapshot {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
+    PauseInfo {},
+    TaxPolicy {},
+    /// Announced reserve transfer to the collector contract
+    ReserveAnnouncement {},
+    /// Current and past interest accrual freezes of the borrower
+    AccrualFreeze {
+        borrower: HumanAddr,
+    },
+    /// Written off loans, oldest first
+    BadDebtHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Rates recorded at the last epoch operations, oldest first
+    RateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Locked positions of the owner, oldest first
+    LockedPositions {
+        owner: HumanAddr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Principal and interest owed to all locked positions
+    LockedLiabilities {},
+    /// Raw key/values of an exportable storage namespace
+    RawState {
+        namespace: String,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -142,6 +304,21 @@ pub struct ConfigResponse {
     pub distributor_contract: HumanAddr,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub guardian: Option<HumanAddr>,
+    pub flash_fee: Decimal256,
+    pub reserve_announcement_threshold: Option<Uint256>,
+    pub reserve_announcement_delay: u64,
+    pub utilization_half_life: u64,
+    pub lockup_terms: Vec<LockupTerm>,
+    pub early_withdrawal_penalty: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveAnnouncementResponse {
+    pub amount: Uint256,
+    pub announced_at: u64,
+    pub executable_at: u64,
 }
 
 // We define a custom struct for each query response
@@ -156,6 +333,18 @@ pub struct StateResponse {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    pub utilization_ema: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateProjectedResponse {
+    pub block_height: u64,
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub global_interest_index: Decimal256,
+    pub global_reward_index: Decimal256,
+    pub exchange_rate: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -163,6 +352,10 @@ pub struct StateResponse {
 pub struct EpochStateResponse {
     pub exchange_rate: Decimal256,
     pub aterra_supply: Uint256,
+    /// Interest owed to the locked positions which
+    /// the interest buffer has not funded yet
+    #[serde(default = "Uint256::zero")]
+    pub unfunded_locked_interest: Uint256,
 }
 
 // We define a custom struct for each query response
@@ -175,8 +368,104 @@ pub struct BorrowerInfoResponse {
     pub pending_rewards: Decimal256,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerInfoProjectedResponse {
+    pub borrower: HumanAddr,
+    pub block_height: u64,
+    pub loan_amount: Uint256,
+    /// Interest accrued since the last borrower update
+    pub accrued_interest: Uint256,
+    pub pending_rewards: Decimal256,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerSnapshotResponse {
+    pub borrower: HumanAddr,
+    pub block_height: u64,
+    pub checkpoint_height: Option<u64>,
+    pub loan_amount: Uint256,
+    pub interest_index: Decimal256,
+    pub global_interest_index: Decimal256,
+}
+
+/// Period in which the interest accrual of a borrower was frozen
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrozenWindow {
+    pub start: u64,
+    pub end: u64,
+    /// Interest the loan would have accrued in the window
+    pub forgiven_interest: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccrualFreezeResponse {
+    pub borrower: HumanAddr,
+    pub frozen_at: Option<u64>,
+    pub forgiven_interest: Decimal256,
+    pub windows: Vec<FrozenWindow>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtRecordResponse {
+    pub idx: u64,
+    pub borrower: HumanAddr,
+    pub amount: Uint256,
+    /// Part of the amount covered by the reserves
+    pub covered_by_reserves: Uint256,
+    pub block_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtHistoryResponse {
+    pub records: Vec<BadDebtRecordResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSnapshotResponse {
+    pub idx: u64,
+    pub block_height: u64,
+    /// Borrow rate per block
+    pub borrow_rate: Decimal256,
+    /// Deposit rate per block reported by the overseer
+    pub deposit_rate: Decimal256,
+    pub utilization_ratio: Decimal256,
+    pub exchange_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateHistoryResponse {
+    pub snapshots: Vec<RateSnapshotResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedPositionResponse {
+    pub position_id: u64,
+    pub principal: Uint256,
+    /// Interest paid with the principal at maturity
+    pub interest: Uint256,
+    pub start_time: u64,
+    pub maturity: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedPositionsResponse {
+    pub owner: HumanAddr,
+    pub positions: Vec<LockedPositionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedLiabilitiesResponse {
+    pub total_principal: Uint256,
+    pub total_interest: Uint256,
+    /// Part of the interest already sent by the interest buffer
+    pub funded_interest: Uint256,
+    pub unfunded_interest: Uint256,
+}
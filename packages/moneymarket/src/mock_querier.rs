@@ -1,3 +1,6 @@
+// the mocks are fed with nested tuples of the queried values
+#![allow(clippy::type_complexity)]
+
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
@@ -6,7 +9,7 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg, ORACLE_SCHEMA_VERSION};
 
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
@@ -131,10 +134,11 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
-            }) => match from_binary(&msg).unwrap() {
-                OracleQueryMsg::Price { base, quote } => {
+            }) => match from_binary(msg).unwrap() {
+                OracleQueryMsg::Price { base, quote, .. } => {
                     match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                         Some(v) => Ok(to_binary(&PriceResponse {
+                            schema_version: ORACLE_SCHEMA_VERSION,
                             rate: v.0,
                             last_updated_base: v.1,
                             last_updated_quote: v.2,
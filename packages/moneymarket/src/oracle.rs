@@ -8,57 +8,184 @@ use cosmwasm_std::HumanAddr;
 pub struct InitMsg {
     pub owner: HumanAddr,
     pub base_asset: String,
+    /// Feeder submissions older than this are ignored
+    /// when aggregating the price
+    pub price_timeframe: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
+    /// Propose a new owner, who must accept within `expires_in` seconds
+    ProposeOwner {
+        new_owner: HumanAddr,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership transfer
+    AcceptOwnership {},
     UpdateConfig {
-        owner: Option<HumanAddr>,
+        price_timeframe: Option<u64>,
     },
+    /// Replace the feeder set of the asset with the given feeder
     RegisterFeeder {
         asset: String,
         feeder: HumanAddr,
     },
+    /// Add feeders to the feeder set of the asset
+    RegisterFeeders {
+        asset: String,
+        feeders: Vec<HumanAddr>,
+    },
+    RemoveFeeder {
+        asset: String,
+        feeder: HumanAddr,
+    },
     FeedPrice {
         prices: Vec<(String, Decimal256)>, // (asset, price)
     },
+    /// Shift the price history by the duration of the chain halt which
+    /// started at `halted_at`, so aggregated prices skip the halt.
+    /// The fed prices stay stale until new prices are fed
+    RecoverFromHalt {
+        halted_at: u64,
+    },
+    /// Change where the spot price of the asset is read from
+    SetPriceSource {
+        asset: String,
+        source: PriceSource,
+    },
+    /// Limit how often and how far the fed price of the asset can move.
+    /// A price deviating more than `max_deviation` from the current one
+    /// is held pending until another feeder confirms it, or the same
+    /// feeder does after `confirmation_delay` seconds. Setting every
+    /// value to zero or `None` removes the limits
+    SetPriceLimits {
+        asset: String,
+        min_update_interval: u64,
+        max_deviation: Option<Decimal256>,
+        confirmation_delay: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
     Feeder {
         asset: String,
     },
+    Feeders {
+        asset: String,
+    },
     Price {
         base: String,
         quote: String,
+        /// Aggregation applied to the fed prices, defaults to spot
+        mode: Option<PriceMode>,
     },
     Prices {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Last recovered chain halt, if any
+    HaltRecovery {},
+    PriceSource {
+        asset: String,
+    },
+    PriceLimits {
+        asset: String,
+    },
+    /// Deviating price waiting for a confirmation, if any
+    PendingPrice {
+        asset: String,
+    },
+}
+
+/// Source of the spot price of an asset; the aggregated
+/// price modes always use the fed price observations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    /// Prices fed by the registered feeders
+    Feeder,
+    /// Price adapter contract answering ExternalPriceQueryMsg; the fed
+    /// price is used when the read fails or the fed price is newer by
+    /// more than the price timeframe
+    External { contract: HumanAddr, id: String },
+    /// Same as External, but the external price is rejected when it
+    /// diverges from the fed price by more than `max_divergence`
+    Both {
+        contract: HumanAddr,
+        id: String,
+        max_divergence: Decimal256,
+    },
+}
+
+/// Query interface of the external price adapters,
+/// which wrap feeds such as Pyth or Band
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalPriceQueryMsg {
+    Price { id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalPriceResponse {
+    pub price: Decimal256,
+    pub last_updated_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum PriceMode {
+    /// Last fed price
+    #[default]
+    Spot,
+    /// Time weighted average price over the last `window` seconds
+    Twap { window: u64 },
+    /// Median of the prices fed within the last `window` seconds
+    Median { window: u64 },
 }
 
+/// Version of the query response layout, carried by every response
+/// as `schema_version` so SDK generators can detect breaking changes
+pub const ORACLE_SCHEMA_VERSION: u32 = 1;
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub owner: HumanAddr,
     pub base_asset: String,
+    pub price_timeframe: u64,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FeederResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub asset: String,
     pub feeder: HumanAddr,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedersResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub asset: String,
+    pub feeders: Vec<HumanAddr>,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub rate: Decimal256,
     pub last_updated_base: u64,
     pub last_updated_quote: u64,
@@ -75,9 +202,40 @@ pub struct PricesResponseElem {
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PricesResponse {
+    #[serde(default)]
+    pub schema_version: u32,
     pub prices: Vec<PricesResponseElem>,
 }
 
-/// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct PriceSourceResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub asset: String,
+    pub source: PriceSource,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceLimitsResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub asset: String,
+    pub min_update_interval: u64,
+    pub max_deviation: Option<Decimal256>,
+    pub confirmation_delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPriceResponse {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub asset: String,
+    pub price: Decimal256,
+    pub feeder: HumanAddr,
+    pub proposed_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub price_timeframe: u64,
+}
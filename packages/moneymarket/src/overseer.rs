@@ -1,9 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::sweep::SweepAsset;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{CosmosMsg, HumanAddr};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -40,14 +41,29 @@ pub struct InitMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum HandleMsg {
     ////////////////////
     /// Owner operations
     ////////////////////
 
+    /// Propose a new owner, who must accept within `expires_in` seconds
+    ProposeOwner {
+        new_owner: HumanAddr,
+        expires_in: u64,
+    },
+    /// Accept the pending ownership transfer
+    AcceptOwnership {},
+    /// Propose a new stable denom, executable after the timelock
+    ProposeDenomMigration {
+        new_denom: String,
+        conversion_contract: HumanAddr,
+    },
+    /// Convert the old denom balance and switch to the new denom
+    ExecuteDenomMigration {},
+
     /// Update Configs
     UpdateConfig {
-        owner_addr: Option<HumanAddr>,
         oracle_contract: Option<HumanAddr>,
         liquidation_contract: Option<HumanAddr>,
         threshold_deposit_rate: Option<Decimal256>,
@@ -56,6 +72,66 @@ pub enum HandleMsg {
         anc_purchase_factor: Option<Decimal256>,
         epoch_period: Option<u64>,
         price_timeframe: Option<u64>,
+        guardian: Option<HumanAddr>,
+        hysteresis_band: Option<Decimal256>,
+        hysteresis_epochs: Option<u64>,
+        retry_base_delay: Option<u64>,
+        retry_max_delay: Option<u64>,
+        priority_tolerance: Option<Decimal256>,
+        debt_ceiling: Option<Uint256>,
+        /// Remove the debt ceiling; a ceiling given
+        /// in the same message is applied after it
+        clear_debt_ceiling: Option<bool>,
+        /// A liquidated loan stays liquidatable until it is back
+        /// below the borrow limit divided by this ratio
+        liquidation_release_ratio: Option<Decimal256>,
+        /// Seconds after a partial liquidation during which the
+        /// borrower can only be liquidated past the emergency ratio
+        liquidation_cooldown: Option<u64>,
+        /// Loan amount over borrow limit ratio that overrides
+        /// the liquidation cooldown
+        emergency_liquidation_ratio: Option<Decimal256>,
+        /// Blocks an authorized liquidation stays executable
+        liquidation_authorization_period: Option<u64>,
+    },
+
+    /// Transfer out funds which are not part of the tracked state
+    SweepUnrelatedFunds {
+        asset: SweepAsset,
+        recipient: HumanAddr,
+    },
+    /// Halt or resume flows; the guardian can only pause
+    SetPause {
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// Shift the liquidation retry schedule by the duration of the chain
+    /// halt which started at `halted_at`; each halt is recovered once
+    RecoverFromHalt {
+        halted_at: u64,
+    },
+
+    /// Halt or resume the interest accrual of the loans mostly
+    /// backed by the collateral; the guardian can only halt
+    SetAccrualHalt {
+        collateral_token: HumanAddr,
+        halted: bool,
+    },
+
+    /// Register a contract allowed to liquidate while
+    /// liquidations are restricted to the executors
+    AddLiquidationExecutor {
+        executor: HumanAddr,
+    },
+    RemoveLiquidationExecutor {
+        executor: HumanAddr,
+    },
+    /// Restrict liquidations to the registered executors,
+    /// or open them to anyone again
+    SetExecutorOnly {
+        executor_only: bool,
     },
 
     /// Create new custody contract for the given collateral token
@@ -71,6 +147,13 @@ pub enum HandleMsg {
         collateral_token: HumanAddr,         // bAsset token contract
         custody_contract: Option<HumanAddr>, // bAsset custody contract
         max_ltv: Option<Decimal256>,         // Loan To Value ratio
+        liquidation_ltv: Option<Decimal256>, // LTV above which loans are liquidated
+        borrow_cap: Option<Uint256>,         // Max borrow limit backed by the token
+        clear_borrow_cap: Option<bool>,      // Remove the cap before borrow_cap applies
+        isolated: Option<bool>,              // Cannot be mixed with other collaterals
+        ltv_tiers: Option<Vec<LtvTier>>,     // Lower LTVs for larger positions
+        multi_custody: Option<bool>,         // Custody holds several collaterals
+        decimals: Option<u8>,                // Collateral token decimals
     },
 
     /// Claims all staking rewards from the bAsset contracts
@@ -79,6 +162,9 @@ pub enum HandleMsg {
     /// 2. Invoke [Custody] DistributeRewards
     /// 3. Update epoch state
     ExecuteEpochOperations {},
+    /// Continue checking the borrowers left unchecked by
+    /// ExecuteEpochOperations, one batch per call
+    ResumeEpochOperations {},
     UpdateEpochState {
         interest_buffer: Uint256,
         distributed_interest: Uint256,
@@ -93,18 +179,62 @@ pub enum HandleMsg {
     UnlockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
     },
+    /// Register a health factor threshold; an event is emitted
+    /// during epoch operations whenever the borrower's health
+    /// factor (borrow_limit / loan_amount) crosses it
+    RegisterHealthAlert {
+        threshold: Decimal256,
+    },
+    DeregisterHealthAlert {},
+    /// Register the order in which collaterals are liquidated;
+    /// an empty list restores the pro-rata liquidation
+    SetCollateralPriority {
+        collaterals: Vec<HumanAddr>,
+    },
 
     /////////////////////////////
     /// Permissionless operations
     /////////////////////////////
-    LiquidateCollateral { borrower: HumanAddr },
+    LiquidateCollateral {
+        borrower: HumanAddr,
+    },
+    /// Compute the liquidation amount of an unsafe loan and store it,
+    /// so any keeper can execute it within the validity window
+    AuthorizeLiquidation {
+        borrower: HumanAddr,
+    },
+    /// Execute a previously authorized liquidation
+    ExecuteAuthorizedLiquidation {
+        borrower: HumanAddr,
+    },
+    /// Freeze the market interest accrual of the borrower when more
+    /// than half of its borrow limit is backed by halted collaterals,
+    /// and unfreeze it once that is no longer the case
+    UpdateAccrualFreeze {
+        borrower: HumanAddr,
+    },
+    /// Retry a liquidation deferred by a failed price query,
+    /// once its backoff period has passed. Anyone can retry,
+    /// unless liquidations are executor only
+    RetryLiquidation {
+        borrower: HumanAddr,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
+    PendingDenomMigration {},
     EpochState {},
+    EpochOperationsProgress {},
+    /// Effects the epoch operations would have at `block_height`,
+    /// computed without changing any state
+    SimulateEpochOperations {
+        block_height: u64,
+    },
+    RateSmoothing {},
     Whitelist {
         collateral_token: Option<HumanAddr>,
         start_after: Option<HumanAddr>,
@@ -113,14 +243,103 @@ pub enum QueryMsg {
     Collaterals {
         borrower: HumanAddr,
     },
+    /// Position at the nearest checkpoints at or before `block_height`
+    PositionSnapshot {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
     AllCollaterals {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    /// Underwater borrowers among the next `limit` borrowers, with
+    /// loans accrued to `block_height`. Continue from `last_borrower`
+    /// of the response to scan all the borrowers
+    LiquidatablePositions {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+        block_height: u64,
+    },
+    /// LiquidatablePositions the liquidator is estimated to earn at
+    /// least `min_profit` on with its own bids, after fees and tax.
+    /// Ranked by the estimated profit within the scanned page
+    ProfitableLiquidations {
+        liquidator: HumanAddr,
+        min_profit: Option<Uint256>,
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+        block_height: u64,
+    },
     BorrowLimit {
         borrower: HumanAddr,
         block_time: Option<u64>,
     },
+    /// Borrow limit the given collateral amount would add
+    /// at the current price and max_ltv
+    BorrowLimitContribution {
+        collateral_token: HumanAddr,
+        amount: Uint256,
+        block_time: Option<u64>,
+    },
+    /// LTV in effect for the borrower's position in the
+    /// collateral, after applying the LTV tiers
+    EffectiveLtv {
+        borrower: HumanAddr,
+        collateral_token: HumanAddr,
+        block_time: Option<u64>,
+    },
+    HealthAlert {
+        borrower: HumanAddr,
+    },
+    /// Health factor, LTV, shortfall and per-collateral liquidation
+    /// prices at the current prices, with the loan accrued to `block_height`
+    BorrowerHealth {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
+    /// Locked collaterals with their custody balances, the loan accrued
+    /// to `block_height`, the borrow limit and the pending rewards
+    AccountSummary {
+        borrower: HumanAddr,
+        block_height: u64,
+    },
+    /// Borrow limit currently backed by the collateral against its cap
+    BorrowCapUsage {
+        collateral_token: HumanAddr,
+    },
+    /// Market liabilities against the protocol-wide debt ceiling
+    DebtCeilingUsage {},
+    CollateralPriority {
+        borrower: HumanAddr,
+    },
+    /// Isolated collateral backing the borrower position, if any
+    IsolationMode {
+        borrower: HumanAddr,
+    },
+    LiquidationAuthorization {
+        borrower: HumanAddr,
+    },
+    FailedLiquidation {
+        borrower: HumanAddr,
+    },
+    FailedLiquidations {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    LiquidationExecutors {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    PauseInfo {},
+    /// Last recovered chain halt, if any
+    HaltRecovery {},
+    AccrualHalt {
+        collateral_token: HumanAddr,
+    },
+    /// Height the borrower entered liquidation at, until released
+    LiquidationTrigger {
+        borrower: HumanAddr,
+    },
 }
 
 // We define a custom struct for each query response
@@ -138,6 +357,25 @@ pub struct ConfigResponse {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub price_timeframe: u64,
+    pub guardian: Option<HumanAddr>,
+    pub hysteresis_band: Decimal256,
+    pub hysteresis_epochs: u64,
+    pub retry_base_delay: u64,
+    pub retry_max_delay: u64,
+    pub priority_tolerance: Decimal256,
+    pub debt_ceiling: Option<Uint256>,
+    pub liquidation_release_ratio: Decimal256,
+    pub liquidation_cooldown: u64,
+    pub emergency_liquidation_ratio: Decimal256,
+    pub liquidation_authorization_period: u64,
+}
+
+/// LTV applied to the part of a position's collateral value
+/// above `notional_threshold`, up to the next tier threshold
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LtvTier {
+    pub notional_threshold: Uint256,
+    pub max_ltv: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -146,7 +384,15 @@ pub struct WhitelistResponseElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    /// Loans are liquidated above this LTV; equal to
+    /// `max_ltv` unless a buffer was configured
+    pub liquidation_ltv: Decimal256,
+    pub ltv_tiers: Vec<LtvTier>,
+    pub borrow_cap: Option<Uint256>,
+    pub isolated: bool,
     pub custody_contract: HumanAddr,
+    pub multi_custody: bool,
+    pub decimals: u8,
     pub collateral_token: HumanAddr,
 }
 
@@ -169,12 +415,231 @@ pub struct AllCollateralsResponse {
     pub all_collaterals: Vec<CollateralsResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatablePosition {
+    pub borrower: HumanAddr,
+    pub borrow_amount: Uint256,
+    pub borrow_limit: Uint256,
+    /// Loan amount above the liquidation limit
+    pub shortfall: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatablePositionsResponse {
+    pub positions: Vec<LiquidatablePosition>,
+    /// Last scanned borrower; `None` when none was left to scan
+    pub last_borrower: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProfitableLiquidation {
+    pub borrower: HumanAddr,
+    pub shortfall: Uint256,
+    /// Collaterals the liquidation would sell
+    pub collaterals: TokensHuman,
+    /// Bid premium plus liquidator fee, net of tax
+    pub estimated_profit: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProfitableLiquidationsResponse {
+    pub liquidations: Vec<ProfitableLiquidation>,
+    /// Last scanned borrower; `None` when none was left to scan
+    pub last_borrower: Option<HumanAddr>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowLimitResponse {
     pub borrower: HumanAddr,
     pub borrow_limit: Uint256,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowLimitContributionResponse {
+    pub collateral_token: HumanAddr,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    pub max_ltv: Decimal256,
+    pub borrow_limit: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EffectiveLtvResponse {
+    pub borrower: HumanAddr,
+    pub collateral_token: HumanAddr,
+    pub collateral_value: Uint256,
+    pub borrow_limit: Uint256,
+    pub effective_ltv: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowCapUsageResponse {
+    pub collateral_token: HumanAddr,
+    pub borrow_cap: Option<Uint256>,
+    pub total_collateral: Uint256,
+    pub borrow_limit: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtCeilingUsageResponse {
+    pub debt_ceiling: Option<Uint256>,
+    pub total_liabilities: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsolationModeResponse {
+    pub borrower: HumanAddr,
+    pub isolated_collateral: Option<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccrualHaltResponse {
+    pub collateral_token: HumanAddr,
+    pub halted_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationTriggerResponse {
+    pub borrower: HumanAddr,
+    pub triggered_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralPriorityResponse {
+    pub borrower: HumanAddr,
+    pub collaterals: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionSnapshotResponse {
+    pub borrower: HumanAddr,
+    pub block_height: u64,
+    pub collaterals: TokensHuman,
+    pub collaterals_checkpoint_height: Option<u64>,
+    pub exchange_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub epoch_checkpoint_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochOperationsProgressResponse {
+    pub epoch_height: u64,
+    pub last_borrower: Option<HumanAddr>,
+    pub processed_borrowers: u64,
+    pub completed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateSmoothingResponse {
+    pub subsidy_active: bool,
+    pub deviation_epochs: u64,
+    pub hysteresis_band: Decimal256,
+    pub hysteresis_epochs: u64,
+}
+
+/// The epoch operations only pay out the interest buffer and
+/// distribute the custody rewards; liquidations are never part
+/// of them, see LiquidatablePositions for those
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateEpochOperationsResponse {
+    pub deposit_rate: Decimal256,
+    pub target_deposit_rate: Decimal256,
+    pub subsidy_active: bool,
+    pub anc_purchase_amount: Uint256,
+    /// Interest buffer sent to fund the locked positions of the market
+    pub locked_interest_funding: Uint256,
+    pub distributed_interest: Uint256,
+    pub interest_buffer: Uint256,
+    pub messages: Vec<CosmosMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralHealth {
+    pub collateral_token: HumanAddr,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    /// Price at which the position becomes liquidatable when only this
+    /// collateral moves; `None` when it cannot make the position liquidatable
+    pub liquidation_price: Option<Decimal256>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerHealthResponse {
+    pub borrower: HumanAddr,
+    pub borrow_amount: Uint256,
+    pub borrow_limit: Uint256,
+    pub collateral_value: Uint256,
+    /// borrow_limit / borrow_amount; `None` without a loan
+    pub health_factor: Option<Decimal256>,
+    /// borrow_amount / collateral_value
+    pub ltv: Decimal256,
+    pub shortfall: Uint256,
+    pub collaterals: Vec<CollateralHealth>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccountCollateral {
+    pub collateral_token: HumanAddr,
+    /// Deposited in the custody contract, locked or not
+    pub balance: Uint256,
+    pub locked_amount: Uint256,
+    pub price: Decimal256,
+    /// Stable denom value of the locked amount
+    pub value: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AccountSummaryResponse {
+    pub borrower: HumanAddr,
+    pub collaterals: Vec<AccountCollateral>,
+    pub collateral_value: Uint256,
+    pub borrow_amount: Uint256,
+    pub borrow_limit: Uint256,
+    pub pending_rewards: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HealthAlertResponse {
+    pub borrower: HumanAddr,
+    pub threshold: Decimal256,
+    pub triggered: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationAuthorizationResponse {
+    pub borrower: HumanAddr,
+    pub collaterals: TokensHuman,
+    pub loan_amount: Uint256,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedLiquidationResponse {
+    pub borrower: HumanAddr,
+    pub reason: String,
+    pub attempts: u32,
+    pub next_retry_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedLiquidationsResponse {
+    pub failed_liquidations: Vec<FailedLiquidationResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationExecutorResponse {
+    pub executor: HumanAddr,
+    pub added_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationExecutorsResponse {
+    pub executor_only: bool,
+    pub executors: Vec<LiquidationExecutorResponse>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {
@@ -0,0 +1,107 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    log, Api, CanonicalAddr, Env, Extern, HandleResponse, HandleResult, HumanAddr, Querier,
+    StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{singleton, singleton_read};
+
+static KEY_PENDING_OWNER: &[u8] = b"pending_owner";
+
+/// Ownership transfer waiting to be accepted by the new owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: CanonicalAddr,
+    pub expires_at: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: HumanAddr,
+    pub expires_at: u64,
+}
+
+/// Propose a new owner, who has `expires_in` seconds to accept.
+/// A new proposal replaces the previous one.
+pub fn propose_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    owner: &CanonicalAddr,
+    new_owner: HumanAddr,
+    expires_in: u64,
+) -> HandleResult {
+    if deps.api.canonical_address(&env.message.sender)? != *owner {
+        return Err(StdError::unauthorized());
+    }
+
+    let expires_at = env.block.time + expires_in;
+    singleton(&mut deps.storage, KEY_PENDING_OWNER).save(&PendingOwner {
+        owner: deps.api.canonical_address(&new_owner)?,
+        expires_at,
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "propose_owner"),
+            log("new_owner", new_owner),
+            log("expires_at", expires_at),
+        ],
+        data: None,
+    })
+}
+
+/// Consume the pending proposal on behalf of the sender and
+/// return the new owner, which the caller must store in its config
+pub fn claim_ownership<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<CanonicalAddr> {
+    let pending_owner: PendingOwner =
+        match singleton_read(&deps.storage, KEY_PENDING_OWNER).may_load()? {
+            Some(v) => v,
+            None => return Err(StdError::generic_err("No pending ownership transfer")),
+        };
+
+    if deps.api.canonical_address(&env.message.sender)? != pending_owner.owner {
+        return Err(StdError::unauthorized());
+    }
+
+    if env.block.time > pending_owner.expires_at {
+        return Err(StdError::generic_err(
+            "Ownership transfer proposal has expired",
+        ));
+    }
+
+    singleton::<S, PendingOwner>(&mut deps.storage, KEY_PENDING_OWNER).remove();
+    Ok(pending_owner.owner)
+}
+
+pub fn query_pending_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<PendingOwnerResponse> {
+    let pending_owner: PendingOwner =
+        match singleton_read(&deps.storage, KEY_PENDING_OWNER).may_load()? {
+            Some(v) => v,
+            None => return Err(StdError::generic_err("No pending ownership transfer")),
+        };
+
+    Ok(PendingOwnerResponse {
+        pending_owner: deps.api.human_address(&pending_owner.owner)?,
+        expires_at: pending_owner.expires_at,
+    })
+}
+
+/// Extend the pending proposal by the duration of a chain halt
+pub fn shift_pending_owner<S: Storage>(storage: &mut S, duration: u64) -> StdResult<()> {
+    let pending_owner: Option<PendingOwner> =
+        singleton_read(storage, KEY_PENDING_OWNER).may_load()?;
+    if let Some(mut pending_owner) = pending_owner {
+        pending_owner.expires_at += duration;
+        singleton(storage, KEY_PENDING_OWNER).save(&pending_owner)?;
+    }
+
+    Ok(())
+}
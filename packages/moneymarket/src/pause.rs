@@ -0,0 +1,45 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// Flows which can be halted by the owner or the guardian
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfo {
+    pub deposits: bool,
+    pub borrows: bool,
+    pub liquidations: bool,
+    pub withdrawals: bool,
+}
+
+impl PauseInfo {
+    /// Apply the given switches. The guardian can only pause flows,
+    /// lifting a pause is restricted to the owner.
+    pub fn update(
+        &mut self,
+        deposits: Option<bool>,
+        borrows: Option<bool>,
+        liquidations: Option<bool>,
+        withdrawals: Option<bool>,
+        is_owner: bool,
+    ) -> StdResult<()> {
+        let switches = vec![
+            (&mut self.deposits, deposits),
+            (&mut self.borrows, borrows),
+            (&mut self.liquidations, liquidations),
+            (&mut self.withdrawals, withdrawals),
+        ];
+
+        for (paused, value) in switches {
+            if let Some(value) = value {
+                if *paused && !value && !is_owner {
+                    return Err(StdError::unauthorized());
+                }
+
+                *paused = value;
+            }
+        }
+
+        Ok(())
+    }
+}
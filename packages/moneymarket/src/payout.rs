@@ -0,0 +1,54 @@
+use schemars::JsonSchema;
+use std::fmt;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    to_binary, Api, BankMsg, Coin, CosmosMsg, Extern, HumanAddr, Querier, StdResult, Storage,
+    WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+
+use crate::querier::deduct_tax;
+
+/// Send `amount` of a native denom, net of the tax computed
+/// with the tax policy of the sending contract
+pub fn native_payout_msg<
+    S: Storage,
+    A: Api,
+    Q: Querier,
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+>(
+    deps: &Extern<S, A, Q>,
+    from_address: HumanAddr,
+    to_address: HumanAddr,
+    denom: String,
+    amount: Uint256,
+) -> StdResult<CosmosMsg<T>> {
+    Ok(CosmosMsg::Bank(BankMsg::Send {
+        from_address,
+        to_address,
+        amount: vec![deduct_tax(
+            deps,
+            Coin {
+                denom,
+                amount: amount.into(),
+            },
+        )?],
+    }))
+}
+
+/// Transfer `amount` of a cw20 token; token transfers are never taxed
+pub fn token_payout_msg<T: Clone + fmt::Debug + PartialEq + JsonSchema>(
+    contract_addr: HumanAddr,
+    recipient: HumanAddr,
+    amount: Uint256,
+) -> StdResult<CosmosMsg<T>> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr,
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Transfer {
+            recipient,
+            amount: amount.into(),
+        })?,
+    }))
+}
@@ -10,7 +10,9 @@ use cosmwasm_storage::to_length_prefixed;
 use cw20::TokenInfoResponse;
 use terra_cosmwasm::TerraQuerier;
 
-use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::custody::{ExchangeRateQueryMsg, ExchangeRateResponse};
+use crate::oracle::{PriceMode, PriceResponse, QueryMsg as OracleQueryMsg};
+use crate::tax::{read_tax_policy, TaxPolicy};
 
 pub fn query_all_balances<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
@@ -50,7 +52,7 @@ pub fn query_token_balance<S: Storage, A: Api, Q: Querier>(
             contract_addr: HumanAddr::from(contract_addr),
             key: Binary::from(concat(
                 &to_length_prefixed(b"balance").to_vec(),
-                (deps.api.canonical_address(&account_addr)?).as_slice(),
+                (deps.api.canonical_address(account_addr)?).as_slice(),
             )),
         }))
         .unwrap_or_else(|_| to_binary(&Uint128::zero()).unwrap());
@@ -76,17 +78,31 @@ pub fn query_supply<S: Storage, A: Api, Q: Querier>(
 pub fn query_tax_rate<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
 ) -> StdResult<Decimal256> {
-    let terra_querier = TerraQuerier::new(&deps.querier);
-    Ok(terra_querier.query_tax_rate()?.rate.into())
+    match read_tax_policy(&deps.storage)? {
+        TaxPolicy::Treasury => {
+            let terra_querier = TerraQuerier::new(&deps.querier);
+            Ok(terra_querier.query_tax_rate()?.rate.into())
+        }
+        TaxPolicy::Fixed { rate, .. } => Ok(rate),
+        TaxPolicy::None => Ok(Decimal256::zero()),
+    }
 }
 
 pub fn compute_tax<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     coin: &Coin,
 ) -> StdResult<Uint256> {
-    let terra_querier = TerraQuerier::new(&deps.querier);
-    let tax_rate = Decimal256::from((terra_querier.query_tax_rate()?).rate);
-    let tax_cap = Uint256::from((terra_querier.query_tax_cap(coin.denom.to_string())?).cap);
+    let (tax_rate, tax_cap) = match read_tax_policy(&deps.storage)? {
+        TaxPolicy::Treasury => {
+            let terra_querier = TerraQuerier::new(&deps.querier);
+            (
+                Decimal256::from((terra_querier.query_tax_rate()?).rate),
+                Uint256::from((terra_querier.query_tax_cap(coin.denom.to_string())?).cap),
+            )
+        }
+        TaxPolicy::Fixed { rate, cap } => (rate, cap),
+        TaxPolicy::None => return Ok(Uint256::zero()),
+    };
     let amount = Uint256::from(coin.amount);
     Ok(std::cmp::min(
         amount * Decimal256::one() - amount / (Decimal256::one() + tax_rate),
@@ -117,11 +133,22 @@ pub fn query_price<S: Storage, A: Api, Q: Querier>(
     base: String,
     quote: String,
     time_contraints: Option<TimeConstraints>,
+) -> StdResult<PriceResponse> {
+    query_price_with_mode(deps, oracle_addr, base, quote, None, time_contraints)
+}
+
+pub fn query_price_with_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    oracle_addr: &HumanAddr,
+    base: String,
+    quote: String,
+    mode: Option<PriceMode>,
+    time_contraints: Option<TimeConstraints>,
 ) -> StdResult<PriceResponse> {
     let oracle_price: PriceResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: HumanAddr::from(oracle_addr),
-            msg: to_binary(&OracleQueryMsg::Price { base, quote })?,
+            msg: to_binary(&OracleQueryMsg::Price { base, quote, mode })?,
         }))?;
 
     if let Some(time_contraints) = time_contraints {
@@ -136,6 +163,23 @@ pub fn query_price<S: Storage, A: Api, Q: Querier>(
     Ok(oracle_price)
 }
 
+/// Collateral token amount per share of a rebasing collateral
+pub fn query_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    exchange_rate_contract: &HumanAddr,
+) -> StdResult<Decimal256> {
+    let res: ExchangeRateResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: HumanAddr::from(exchange_rate_contract),
+        msg: to_binary(&ExchangeRateQueryMsg::ExchangeRate {})?,
+    }))?;
+
+    if res.rate == Decimal256::zero() {
+        return Err(StdError::generic_err("Exchange rate cannot be zero"));
+    }
+
+    Ok(res.rate)
+}
+
 #[inline]
 fn concat(namespace: &[u8], key: &[u8]) -> Vec<u8> {
     let mut k = namespace.to_vec();
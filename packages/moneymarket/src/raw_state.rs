@@ -0,0 +1,93 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Binary, Order, ReadonlyStorage, StdError, StdResult};
+use cosmwasm_storage::{to_length_prefixed, ReadonlyPrefixedStorage};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Storage namespace which can be exported
+pub enum Namespace {
+    /// Single value stored under the key
+    Singleton(&'static [u8]),
+    /// Values stored under keys prefixed with the namespace
+    Bucket(&'static [u8]),
+}
+
+impl Namespace {
+    fn name(&self) -> &'static [u8] {
+        match self {
+            Namespace::Singleton(key) => key,
+            Namespace::Bucket(prefix) => prefix,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RawStateEntry {
+    /// Key within the namespace
+    pub key: Binary,
+    /// Stored value, as serialized by the contract
+    pub value: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RawStateResponse {
+    pub namespace: String,
+    pub entries: Vec<RawStateEntry>,
+}
+
+/// Export the raw key/values of one of the `exportable` namespaces,
+/// so the state can be recovered without node storage access
+pub fn query_raw_state<S: ReadonlyStorage>(
+    storage: &S,
+    exportable: &[Namespace],
+    namespace: String,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<RawStateResponse> {
+    let target = match exportable
+        .iter()
+        .find(|elem| elem.name() == namespace.as_bytes())
+    {
+        Some(v) => v,
+        None => {
+            return Err(StdError::generic_err(format!(
+                "Namespace is not exportable: {}",
+                namespace
+            )))
+        }
+    };
+
+    let entries: Vec<RawStateEntry> = match target {
+        Namespace::Singleton(key) => storage
+            .get(&to_length_prefixed(key))
+            .map(|value| RawStateEntry {
+                key: Binary::from(key.to_vec()),
+                value: Binary::from(value),
+            })
+            .into_iter()
+            .collect(),
+        Namespace::Bucket(prefix) => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|key| {
+                let mut v = key.as_slice().to_vec();
+                v.push(0);
+                v
+            });
+
+            ReadonlyPrefixedStorage::new(prefix, storage)
+                .range(start.as_deref(), None, Order::Ascending)
+                .take(limit)
+                .map(|(key, value)| RawStateEntry {
+                    key: Binary::from(key),
+                    value: Binary::from(value),
+                })
+                .collect()
+        }
+    };
+
+    Ok(RawStateResponse { namespace, entries })
+}
@@ -0,0 +1,50 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+
+/// Every conversion from a decimal value into an integer token amount
+/// goes through this module, so the direction of each truncation is an
+/// explicit decision rather than a side effect of integer division.
+///
+/// The policy is to favor the protocol:
+/// - amounts paid to the protocol (bid consumption, protocol fees) round up
+/// - amounts paid out by the protocol (aterra mint, redeem, rewards,
+///   incentives, liquidated collaterals) round down
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// amount * rate
+pub fn mul(amount: Uint256, rate: Decimal256, rounding: Rounding) -> Uint256 {
+    let floor = amount * rate;
+    match rounding {
+        Rounding::Down => floor,
+        Rounding::Up => {
+            if Decimal256::from_uint256(floor) < Decimal256::from_uint256(amount) * rate {
+                floor + Uint256::one()
+            } else {
+                floor
+            }
+        }
+    }
+}
+
+/// amount / rate
+pub fn div(amount: Uint256, rate: Decimal256, rounding: Rounding) -> Uint256 {
+    let floor = amount / rate;
+    match rounding {
+        Rounding::Down => floor,
+        Rounding::Up => {
+            if floor * rate < amount {
+                floor + Uint256::one()
+            } else {
+                floor
+            }
+        }
+    }
+}
+
+/// Integer part of a decimal amount
+pub fn to_uint(value: Decimal256, rounding: Rounding) -> Uint256 {
+    mul(Uint256::one(), value, rounding)
+}
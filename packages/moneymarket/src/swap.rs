@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Uint128};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: HumanAddr },
+    NativeToken { denom: String },
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetInfo::Token { contract_addr } => write!(f, "{}", contract_addr),
+            AssetInfo::NativeToken { denom } => write!(f, "{}", denom),
+        }
+    }
+}
+
+/// Single hop of a swap route, executed by the swap router
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapOperation {
+    /// Terra market swap between native denoms
+    NativeSwap {
+        offer_denom: String,
+        ask_denom: String,
+    },
+    /// Swap through the pair of the given assets on the router's DEX
+    PairSwap {
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+    },
+}
+
+impl SwapOperation {
+    pub fn offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { offer_denom, .. } => AssetInfo::NativeToken {
+                denom: offer_denom.clone(),
+            },
+            SwapOperation::PairSwap {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
+    pub fn ask_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { ask_denom, .. } => AssetInfo::NativeToken {
+                denom: ask_denom.clone(),
+            },
+            SwapOperation::PairSwap { ask_asset_info, .. } => ask_asset_info.clone(),
+        }
+    }
+}
+
+/// Multi-hop swap of one asset into another
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub operations: Vec<SwapOperation>,
+}
+
+impl SwapRoute {
+    /// The route must start with `offer` and every hop must
+    /// continue from the previous one until `ask`
+    pub fn assert_valid(&self, offer: &AssetInfo, ask: &AssetInfo) -> StdResult<()> {
+        let mut current = offer.clone();
+        for operation in self.operations.iter() {
+            if operation.offer_asset_info() != current {
+                return Err(StdError::generic_err(format!(
+                    "Swap route is broken at: {}",
+                    current
+                )));
+            }
+
+            current = operation.ask_asset_info();
+        }
+
+        if self.operations.is_empty() || current != *ask {
+            return Err(StdError::generic_err(format!(
+                "Swap route must end with: {}",
+                ask
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Interface of the swap router
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouterHandleMsg {
+    ExecuteSwapOperations {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Option<Uint128>,
+        to: Option<HumanAddr>,
+    },
+}
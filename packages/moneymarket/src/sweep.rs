@@ -0,0 +1,66 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{Api, CosmosMsg, Env, Extern, HumanAddr, Querier, StdError, StdResult, Storage};
+
+use crate::payout::{native_payout_msg, token_payout_msg};
+use crate::querier::{query_balance, query_token_balance};
+
+/// Asset held by a contract outside of its tracked state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepAsset {
+    Native { denom: String },
+    Token { contract_addr: HumanAddr },
+}
+
+impl fmt::Display for SweepAsset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SweepAsset::Native { denom } => write!(f, "{}", denom),
+            SweepAsset::Token { contract_addr } => write!(f, "{}", contract_addr),
+        }
+    }
+}
+
+/// Transfer the whole contract balance of `asset` to `recipient`.
+/// Callers must reject the assets their state accounts for.
+pub fn sweep_msg<S: Storage, A: Api, Q: Querier, T: Clone + fmt::Debug + PartialEq + JsonSchema>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    asset: &SweepAsset,
+    recipient: HumanAddr,
+) -> StdResult<(Uint256, CosmosMsg<T>)> {
+    let amount = match asset {
+        SweepAsset::Native { denom } => {
+            query_balance(deps, &env.contract.address, denom.to_string())?
+        }
+        SweepAsset::Token { contract_addr } => {
+            query_token_balance(deps, contract_addr, &env.contract.address)?
+        }
+    };
+
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No funds to sweep: {}",
+            asset
+        )));
+    }
+
+    let msg = match asset {
+        SweepAsset::Native { denom } => native_payout_msg(
+            deps,
+            env.contract.address.clone(),
+            recipient,
+            denom.to_string(),
+            amount,
+        )?,
+        SweepAsset::Token { contract_addr } => {
+            token_payout_msg(contract_addr.clone(), recipient, amount)?
+        }
+    };
+
+    Ok((amount, msg))
+}
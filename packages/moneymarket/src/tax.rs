@@ -0,0 +1,41 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+
+static KEY_TAX_POLICY: &[u8] = b"tax_policy";
+
+/// How the tax on native stable transfers is computed; chains
+/// without the Terra treasury module use a fixed or no tax
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum TaxPolicy {
+    /// Tax rate and cap of the Terra treasury module
+    #[default]
+    Treasury,
+    Fixed {
+        rate: Decimal256,
+        cap: Uint256,
+    },
+    None,
+}
+
+pub fn store_tax_policy<S: Storage>(storage: &mut S, tax_policy: &TaxPolicy) -> StdResult<()> {
+    if let TaxPolicy::Fixed { rate, .. } = tax_policy {
+        if *rate >= Decimal256::one() {
+            return Err(StdError::generic_err("Tax rate must be smaller than one"));
+        }
+    }
+
+    singleton(storage, KEY_TAX_POLICY).save(tax_policy)
+}
+
+/// Contracts which never set a policy keep the treasury tax
+pub fn read_tax_policy<S: Storage>(storage: &S) -> StdResult<TaxPolicy> {
+    Ok(singleton_read(storage, KEY_TAX_POLICY)
+        .may_load()?
+        .unwrap_or_default())
+}
@@ -1,10 +1,18 @@
+use crate::decimals::normalize_amount;
 use crate::mock_querier::mock_dependencies;
-use crate::oracle::PriceResponse;
+use crate::oracle::{PriceResponse, ORACLE_SCHEMA_VERSION};
+use crate::payout::{native_payout_msg, token_payout_msg};
 use crate::querier::{compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints};
+use crate::rounding::{self, Rounding};
+use crate::tax::{store_tax_policy, TaxPolicy};
 use crate::tokens::{Tokens, TokensHuman, TokensMath, TokensToRaw};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Coin, Decimal, HumanAddr, StdError, Uint128};
+use cosmwasm_std::{
+    to_binary, BankMsg, Coin, CosmosMsg, Decimal, HumanAddr, StdError, Uint128, WasmMsg,
+};
+use cw20::Cw20HandleMsg;
+use std::str::FromStr;
 
 #[test]
 fn tax_rate_querier() {
@@ -64,6 +72,119 @@ fn test_deduct_tax() {
     );
 }
 
+#[test]
+fn tax_policy() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let res = store_tax_policy(
+        &mut deps.storage,
+        &TaxPolicy::Fixed {
+            rate: Decimal256::one(),
+            cap: Uint256::zero(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Tax rate must be smaller than one")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    store_tax_policy(
+        &mut deps.storage,
+        &TaxPolicy::Fixed {
+            rate: Decimal256::percent(2),
+            cap: Uint256::from(1000u64),
+        },
+    )
+    .unwrap();
+    assert_eq!(query_tax_rate(&deps).unwrap(), Decimal256::percent(2));
+    assert_eq!(
+        compute_tax(&deps, &Coin::new(5100u128, "uusd")).unwrap(),
+        Uint256::from(100u64)
+    );
+    assert_eq!(
+        compute_tax(&deps, &Coin::new(102000u128, "uusd")).unwrap(),
+        Uint256::from(1000u64)
+    );
+
+    store_tax_policy(&mut deps.storage, &TaxPolicy::None).unwrap();
+    assert_eq!(query_tax_rate(&deps).unwrap(), Decimal256::zero());
+    assert_eq!(
+        deduct_tax(&deps, Coin::new(50000000u128, "uusd")).unwrap(),
+        Coin::new(50000000u128, "uusd")
+    );
+}
+
+#[test]
+fn payout_msgs() {
+    let mut deps = mock_dependencies(20, &[]);
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg: CosmosMsg = native_payout_msg(
+        &deps,
+        HumanAddr::from("contract0000"),
+        HumanAddr::from("addr0000"),
+        "uusd".to_string(),
+        Uint256::from(50000000u64),
+    )
+    .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from("contract0000"),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin::new(49504950u128, "uusd")],
+        })
+    );
+
+    store_tax_policy(&mut deps.storage, &TaxPolicy::None).unwrap();
+    let msg: CosmosMsg = native_payout_msg(
+        &deps,
+        HumanAddr::from("contract0000"),
+        HumanAddr::from("addr0000"),
+        "uusd".to_string(),
+        Uint256::from(50000000u64),
+    )
+    .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from("contract0000"),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin::new(50000000u128, "uusd")],
+        })
+    );
+
+    let msg: CosmosMsg = token_payout_msg(
+        HumanAddr::from("token0000"),
+        HumanAddr::from("addr0000"),
+        Uint256::from(100u64),
+    )
+    .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("token0000"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0000"),
+                amount: Uint128::from(100u128),
+            })
+            .unwrap(),
+        })
+    );
+}
+
 #[test]
 fn oracle_price_querier() {
     let mut deps = mock_dependencies(20, &[]);
@@ -85,6 +206,7 @@ fn oracle_price_querier() {
     assert_eq!(
         oracle_price,
         PriceResponse {
+            schema_version: ORACLE_SCHEMA_VERSION,
             rate: Decimal256::from_ratio(131, 2),
             last_updated_base: 123,
             last_updated_quote: 321,
@@ -151,9 +273,9 @@ fn tokens_math() {
     let tokens_3_raw: Tokens = tokens_3.to_raw(&deps).unwrap();
     let tokens_4_raw: Tokens = tokens_4.to_raw(&deps).unwrap();
 
-    assert_eq!(tokens_1_raw.clone().sub(tokens_2_raw).is_err(), true);
-    assert_eq!(tokens_1_raw.clone().sub(tokens_3_raw).is_err(), true);
-    assert_eq!(tokens_1_raw.clone().sub(tokens_4_raw).is_err(), true);
+    assert!(tokens_1_raw.clone().sub(tokens_2_raw).is_err());
+    assert!(tokens_1_raw.clone().sub(tokens_3_raw).is_err());
+    assert!(tokens_1_raw.clone().sub(tokens_4_raw).is_err());
 }
 
 #[test]
@@ -251,3 +373,74 @@ fn token_math_invalid_token_2() {
 
     let _ = tokens_1_raw.clone().sub(tokens_2_raw);
 }
+
+#[test]
+fn rounding_policy() {
+    let rate = Decimal256::from_str("0.3").unwrap();
+
+    // 10 * 0.3 = 3
+    assert_eq!(
+        rounding::mul(Uint256::from(10u64), rate, Rounding::Down),
+        Uint256::from(3u64)
+    );
+    assert_eq!(
+        rounding::mul(Uint256::from(10u64), rate, Rounding::Up),
+        Uint256::from(3u64)
+    );
+
+    // 11 * 0.3 = 3.3
+    assert_eq!(
+        rounding::mul(Uint256::from(11u64), rate, Rounding::Down),
+        Uint256::from(3u64)
+    );
+    assert_eq!(
+        rounding::mul(Uint256::from(11u64), rate, Rounding::Up),
+        Uint256::from(4u64)
+    );
+
+    // 3 / 0.3 = 10
+    assert_eq!(
+        rounding::div(Uint256::from(3u64), rate, Rounding::Down),
+        Uint256::from(10u64)
+    );
+    assert_eq!(
+        rounding::div(Uint256::from(3u64), rate, Rounding::Up),
+        Uint256::from(10u64)
+    );
+
+    // 1 / 0.3 = 3.33..
+    assert_eq!(
+        rounding::div(Uint256::one(), rate, Rounding::Down),
+        Uint256::from(3u64)
+    );
+    assert_eq!(
+        rounding::div(Uint256::one(), rate, Rounding::Up),
+        Uint256::from(4u64)
+    );
+
+    assert_eq!(
+        rounding::to_uint(Decimal256::from_str("2.5").unwrap(), Rounding::Down),
+        Uint256::from(2u64)
+    );
+    assert_eq!(
+        rounding::to_uint(Decimal256::from_str("2.5").unwrap(), Rounding::Up),
+        Uint256::from(3u64)
+    );
+}
+
+#[test]
+fn normalize_decimals() {
+    let amount = Uint256::from(1_500_000u64);
+    assert_eq!(normalize_amount(amount, 6, Rounding::Down), amount);
+
+    // 18 decimals: 1.5 tokens plus one wei of dust
+    let amount = Uint256::from(1_500_000_000_000_000_001u128);
+    assert_eq!(
+        normalize_amount(amount, 18, Rounding::Down),
+        Uint256::from(1_500_000u64)
+    );
+    assert_eq!(
+        normalize_amount(amount, 18, Rounding::Up),
+        Uint256::from(1_500_001u64)
+    );
+}
@@ -26,11 +26,11 @@ pub trait TokensToRaw {
 
 impl TokensMath for Tokens {
     fn sub(&mut self, tokens: Tokens) -> StdResult<()> {
-        self.sort_by(|a, b| a.0.as_slice().cmp(&b.0.as_slice()));
+        self.sort_by(|a, b| a.0.as_slice().cmp(b.0.as_slice()));
         self.assert_duplicate_token();
 
         let mut tokens = tokens;
-        tokens.sort_by(|a, b| a.0.as_slice().cmp(&b.0.as_slice()));
+        tokens.sort_by(|a, b| a.0.as_slice().cmp(b.0.as_slice()));
         tokens.assert_duplicate_token();
 
         let mut i = 0;
@@ -45,8 +45,7 @@ impl TokensMath for Tokens {
 
                 i += 1;
                 j += 1;
-            } else if self[i].0.as_slice().cmp(&tokens[j].0.as_slice()) == std::cmp::Ordering::Less
-            {
+            } else if self[i].0.as_slice().cmp(tokens[j].0.as_slice()) == std::cmp::Ordering::Less {
                 i += 1;
             } else {
                 return Err(StdError::generic_err("Subtraction underflow"));
@@ -64,11 +63,11 @@ impl TokensMath for Tokens {
     }
 
     fn add(&mut self, tokens: Tokens) {
-        self.sort_by(|a, b| a.0.as_slice().cmp(&b.0.as_slice()));
+        self.sort_by(|a, b| a.0.as_slice().cmp(b.0.as_slice()));
         self.assert_duplicate_token();
 
         let mut tokens = tokens;
-        tokens.sort_by(|a, b| a.0.as_slice().cmp(&b.0.as_slice()));
+        tokens.sort_by(|a, b| a.0.as_slice().cmp(b.0.as_slice()));
         tokens.assert_duplicate_token();
 
         let mut tmp_tokens: Tokens = vec![];
@@ -81,7 +80,7 @@ impl TokensMath for Tokens {
 
                 i += 1;
                 j += 1;
-            } else if self[i].0.as_slice().cmp(&tokens[j].0.as_slice())
+            } else if self[i].0.as_slice().cmp(tokens[j].0.as_slice())
                 == std::cmp::Ordering::Greater
             {
                 tmp_tokens.push((tokens[j].0.clone(), tokens[j].1));
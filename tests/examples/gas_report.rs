@@ -0,0 +1,31 @@
+//! Print the storage gas report of the liquidation hot paths:
+//!
+//!     cargo run -p moneymarket-tests --example gas_report > gas_report.md
+//!
+//! The report is headed by the given revision, or the git HEAD by default,
+//! so the reports of two commits can be diffed.
+
+use std::env;
+use std::process::Command;
+
+use moneymarket_tests::gas::{measure, render_report, report_cases};
+
+fn main() {
+    let revision = env::args().nth(1).unwrap_or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|revision| revision.trim().to_string())
+            .filter(|revision| !revision.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    });
+
+    let measurements = report_cases()
+        .iter()
+        .map(|case| measure(case).unwrap())
+        .collect::<Vec<_>>();
+
+    print!("{}", render_report(&revision, &measurements));
+}
@@ -0,0 +1,683 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, AllBalanceResponse, BalanceResponse, BankMsg, BankQuery,
+    Binary, Coin, CosmosMsg, Decimal, Env, Extern, HandleResponse, HumanAddr, InitResponse,
+    LogAttribute, Order, Querier, QuerierResult, QueryRequest, ReadonlyStorage, StdError,
+    StdResult, Storage, SystemError, Uint128, WasmMsg, WasmQuery, KV,
+};
+use terra_cosmwasm::{
+    TaxCapResponse, TaxRateResponse, TerraMsgWrapper, TerraQuery, TerraQueryWrapper, TerraRoute,
+};
+
+/// Every contract is given the same api, so the canonical
+/// addresses agree across the contracts
+const CANONICAL_LENGTH: usize = 20;
+
+/// Seconds between two blocks
+pub const BLOCK_TIME: u64 = 6;
+
+// Storage gas of the default KV store config of the cosmos sdk
+const READ_COST_FLAT: u64 = 1000;
+const READ_COST_PER_BYTE: u64 = 3;
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const DELETE_COST: u64 = 1000;
+const ITER_NEXT_COST_FLAT: u64 = 30;
+
+pub type AppDeps = Extern<MeteredStorage, MockApi, AppQuerier>;
+
+/// Storage gas consumed by the contracts of the app
+#[derive(Default)]
+struct GasMeter {
+    used: Cell<u64>,
+}
+
+impl GasMeter {
+    fn consume(&self, amount: u64) {
+        self.used.set(self.used.get() + amount);
+    }
+}
+
+/// Contract storage charging its reads and writes to the gas meter
+/// of the app; the wasm execution itself is not metered
+pub struct MeteredStorage {
+    storage: MockStorage,
+    meter: Rc<GasMeter>,
+}
+
+impl MeteredStorage {
+    fn new(meter: Rc<GasMeter>, data: &[(Vec<u8>, Vec<u8>)]) -> Self {
+        let mut storage = MockStorage::new();
+        for (key, value) in data.iter() {
+            storage.set(key, value);
+        }
+
+        MeteredStorage { storage, meter }
+    }
+
+    /// Key value pairs of the storage, read without gas
+    fn data(&self) -> StorageData {
+        self.storage.range(None, None, Order::Ascending).collect()
+    }
+}
+
+impl ReadonlyStorage for MeteredStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.storage.get(key);
+        self.meter.consume(
+            READ_COST_FLAT + READ_COST_PER_BYTE * value.as_ref().map_or(0, |v| v.len()) as u64,
+        );
+        value
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = KV> + 'a> {
+        let meter = self.meter.clone();
+        Box::new(
+            self.storage
+                .range(start, end, order)
+                .inspect(move |(key, value)| {
+                    meter.consume(
+                        ITER_NEXT_COST_FLAT + READ_COST_PER_BYTE * (key.len() + value.len()) as u64,
+                    )
+                }),
+        )
+    }
+}
+
+impl Storage for MeteredStorage {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.meter
+            .consume(WRITE_COST_FLAT + WRITE_COST_PER_BYTE * (key.len() + value.len()) as u64);
+        self.storage.set(key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.meter.consume(DELETE_COST);
+        self.storage.remove(key);
+    }
+}
+
+/// Contract code run by the app; messages are passed
+/// serialized, as the chain would
+pub trait Contract {
+    fn init(
+        &self,
+        deps: &mut AppDeps,
+        env: Env,
+        msg: Binary,
+    ) -> StdResult<InitResponse<TerraMsgWrapper>>;
+
+    fn handle(
+        &self,
+        deps: &mut AppDeps,
+        env: Env,
+        msg: Binary,
+    ) -> StdResult<HandleResponse<TerraMsgWrapper>>;
+
+    fn query(&self, deps: &AppDeps, msg: Binary) -> StdResult<Binary>;
+}
+
+type InitFn<T, C, E> = fn(&mut AppDeps, Env, T) -> Result<InitResponse<C>, E>;
+type HandleFn<T, C, E> = fn(&mut AppDeps, Env, T) -> Result<HandleResponse<C>, E>;
+type QueryFn<T> = fn(&AppDeps, T) -> StdResult<Binary>;
+
+/// Wraps the init, handle and query entry points of a contract crate
+pub struct ContractWrapper<T1, T2, T3, C1, C2, E1, E2>
+where
+    C1: Clone + fmt::Debug + PartialEq + JsonSchema,
+    C2: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    init_fn: InitFn<T1, C1, E1>,
+    handle_fn: HandleFn<T2, C2, E2>,
+    query_fn: QueryFn<T3>,
+}
+
+impl<T1, T2, T3, C1, C2, E1, E2> ContractWrapper<T1, T2, T3, C1, C2, E1, E2>
+where
+    C1: Clone + fmt::Debug + PartialEq + JsonSchema,
+    C2: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    pub fn new(
+        init_fn: InitFn<T1, C1, E1>,
+        handle_fn: HandleFn<T2, C2, E2>,
+        query_fn: QueryFn<T3>,
+    ) -> Self {
+        ContractWrapper {
+            init_fn,
+            handle_fn,
+            query_fn,
+        }
+    }
+}
+
+impl<T1, T2, T3, C1, C2, E1, E2> Contract for ContractWrapper<T1, T2, T3, C1, C2, E1, E2>
+where
+    T1: DeserializeOwned,
+    T2: DeserializeOwned,
+    T3: DeserializeOwned,
+    C1: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize,
+    C2: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize,
+    E1: Into<StdError>,
+    E2: Into<StdError>,
+{
+    fn init(
+        &self,
+        deps: &mut AppDeps,
+        env: Env,
+        msg: Binary,
+    ) -> StdResult<InitResponse<TerraMsgWrapper>> {
+        let res = (self.init_fn)(deps, env, from_binary(&msg)?).map_err(Into::<StdError>::into)?;
+        Ok(InitResponse {
+            messages: into_app_msgs(res.messages)?,
+            log: res.log,
+        })
+    }
+
+    fn handle(
+        &self,
+        deps: &mut AppDeps,
+        env: Env,
+        msg: Binary,
+    ) -> StdResult<HandleResponse<TerraMsgWrapper>> {
+        let res =
+            (self.handle_fn)(deps, env, from_binary(&msg)?).map_err(Into::<StdError>::into)?;
+        Ok(HandleResponse {
+            messages: into_app_msgs(res.messages)?,
+            log: res.log,
+            data: res.data,
+        })
+    }
+
+    fn query(&self, deps: &AppDeps, msg: Binary) -> StdResult<Binary> {
+        (self.query_fn)(deps, from_binary(&msg)?)
+    }
+}
+
+/// Contracts emit messages of their own custom type;
+/// the app only routes the terra ones
+fn into_app_msgs<C>(messages: Vec<CosmosMsg<C>>) -> StdResult<Vec<CosmosMsg<TerraMsgWrapper>>>
+where
+    C: Clone + fmt::Debug + PartialEq + JsonSchema + Serialize,
+{
+    messages
+        .iter()
+        .map(|msg| from_binary(&to_binary(msg)?))
+        .collect()
+}
+
+struct Instance {
+    contract: Rc<dyn Contract>,
+    deps: RefCell<AppDeps>,
+}
+
+impl Instance {
+    /// Run the action on a copy of the contract storage, committed when
+    /// it succeeds; the contract can be queried in the meantime, as on
+    /// the chain, and the queries see the state before the execution
+    fn execute<F, R>(&self, action: F) -> StdResult<R>
+    where
+        F: FnOnce(&mut AppDeps) -> StdResult<R>,
+    {
+        let mut deps = {
+            let committed = self.deps.borrow();
+            Extern {
+                storage: MeteredStorage::new(
+                    committed.storage.meter.clone(),
+                    &committed.storage.data(),
+                ),
+                api: committed.api,
+                querier: committed.querier.clone(),
+            }
+        };
+
+        let res = action(&mut deps)?;
+        self.deps.borrow_mut().storage = deps.storage;
+        Ok(res)
+    }
+}
+
+/// State shared between the app and the queriers of its contracts
+#[derive(Default)]
+struct Router {
+    contracts: RefCell<HashMap<String, Rc<Instance>>>,
+    balances: RefCell<HashMap<String, HashMap<String, u128>>>,
+    gas_meter: Rc<GasMeter>,
+}
+
+/// Key value pairs of a contract storage
+type StorageData = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// State of the router before a transaction, restored when it fails
+struct Snapshot {
+    contracts: HashMap<String, Rc<Instance>>,
+    storages: Vec<(Rc<Instance>, StorageData)>,
+    balances: HashMap<String, HashMap<String, u128>>,
+}
+
+impl Router {
+    fn instance(&self, addr: &HumanAddr) -> StdResult<Rc<Instance>> {
+        self.contracts
+            .borrow()
+            .get(addr.as_str())
+            .cloned()
+            .ok_or_else(|| StdError::generic_err(format!("No such contract: {}", addr)))
+    }
+
+    fn balance(&self, addr: &HumanAddr, denom: &str) -> u128 {
+        self.balances
+            .borrow()
+            .get(addr.as_str())
+            .and_then(|balances| balances.get(denom))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    fn all_balances(&self, addr: &HumanAddr) -> Vec<Coin> {
+        let mut coins: Vec<Coin> = self
+            .balances
+            .borrow()
+            .get(addr.as_str())
+            .map(|balances| {
+                balances
+                    .iter()
+                    .filter(|(_, amount)| **amount > 0)
+                    .map(|(denom, amount)| Coin {
+                        denom: denom.to_string(),
+                        amount: Uint128(*amount),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        coins
+    }
+
+    fn set_balance(&self, addr: &HumanAddr, denom: &str, amount: u128) {
+        self.balances
+            .borrow_mut()
+            .entry(addr.to_string())
+            .or_default()
+            .insert(denom.to_string(), amount);
+    }
+
+    fn transfer(&self, from: &HumanAddr, to: &HumanAddr, coins: &[Coin]) -> StdResult<()> {
+        for coin in coins.iter() {
+            let from_balance = self.balance(from, &coin.denom);
+            if from_balance < coin.amount.u128() {
+                return Err(StdError::generic_err(format!(
+                    "Insufficient funds: {} has {}{}, needs {}{}",
+                    from, from_balance, coin.denom, coin.amount, coin.denom
+                )));
+            }
+
+            self.set_balance(from, &coin.denom, from_balance - coin.amount.u128());
+            let to_balance = self.balance(to, &coin.denom);
+            self.set_balance(to, &coin.denom, to_balance + coin.amount.u128());
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let contracts = self.contracts.borrow().clone();
+        let storages = contracts
+            .values()
+            .map(|instance| {
+                let data = instance.deps.borrow().storage.data();
+                (instance.clone(), data)
+            })
+            .collect();
+
+        Snapshot {
+            contracts,
+            storages,
+            balances: self.balances.borrow().clone(),
+        }
+    }
+
+    fn restore(&self, snapshot: Snapshot) {
+        for (instance, data) in snapshot.storages.into_iter() {
+            instance.deps.borrow_mut().storage = MeteredStorage::new(self.gas_meter.clone(), &data);
+        }
+
+        *self.contracts.borrow_mut() = snapshot.contracts;
+        *self.balances.borrow_mut() = snapshot.balances;
+    }
+
+    fn query_contract<F>(&self, contract_addr: HumanAddr, action: F) -> QuerierResult
+    where
+        F: FnOnce(&Instance, &AppDeps) -> StdResult<Binary>,
+    {
+        let instance = match self.instance(&contract_addr) {
+            Ok(instance) => instance,
+            Err(_) => {
+                return Err(SystemError::NoSuchContract {
+                    addr: contract_addr,
+                })
+            }
+        };
+
+        let deps = instance.deps.borrow();
+        Ok(action(&instance, &deps))
+    }
+
+    fn query(&self, request: QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => self
+                .query_contract(contract_addr, |instance, deps| {
+                    instance.contract.query(deps, msg)
+                }),
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                // the chain returns the raw value json encoded
+                self.query_contract(contract_addr, |_, deps| {
+                    let value = deps.storage.get(key.as_slice()).unwrap_or_default();
+                    to_binary(&Binary::from(value))
+                })
+            }
+            QueryRequest::Bank(BankQuery::Balance { address, denom }) => {
+                let amount = Uint128(self.balance(&address, &denom));
+                Ok(to_binary(&BalanceResponse {
+                    amount: Coin { denom, amount },
+                }))
+            }
+            QueryRequest::Bank(BankQuery::AllBalances { address }) => {
+                Ok(to_binary(&AllBalanceResponse {
+                    amount: self.all_balances(&address),
+                }))
+            }
+            // transfers are not taxed in the app
+            QueryRequest::Custom(TerraQueryWrapper {
+                route: TerraRoute::Treasury,
+                query_data,
+            }) => match query_data {
+                TerraQuery::TaxRate {} => Ok(to_binary(&TaxRateResponse {
+                    rate: Decimal::zero(),
+                })),
+                TerraQuery::TaxCap { .. } => Ok(to_binary(&TaxCapResponse {
+                    cap: Uint128::zero(),
+                })),
+                _ => Err(SystemError::InvalidRequest {
+                    error: "Unsupported treasury query".to_string(),
+                    request: Binary::default(),
+                }),
+            },
+            _ => Err(SystemError::InvalidRequest {
+                error: "Unsupported query".to_string(),
+                request: Binary::default(),
+            }),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AppQuerier {
+    router: Weak<Router>,
+}
+
+impl Querier for AppQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+
+        self.router
+            .upgrade()
+            .expect("contracts are not queried after the app is dropped")
+            .query(request)
+    }
+}
+
+/// Runs several contracts together on a simulated chain.
+///
+/// Messages are executed depth first, in the order they are returned,
+/// and a transaction is reverted as a whole when any of them fails.
+/// Bank balances are tracked; transfers are not taxed and the terra
+/// custom messages (market swaps) are not supported.
+///
+/// The storage access of the contracts is metered with the gas costs
+/// of the chain, which is what storage layouts are compared with.
+pub struct App {
+    router: Rc<Router>,
+    codes: Vec<Rc<dyn Contract>>,
+    block_height: u64,
+    block_time: u64,
+    instance_count: u64,
+    gas_used: u64,
+}
+
+impl App {
+    pub fn new(block_height: u64, block_time: u64) -> Self {
+        App {
+            router: Rc::new(Router::default()),
+            codes: vec![],
+            block_height,
+            block_time,
+            instance_count: 0,
+            gas_used: 0,
+        }
+    }
+
+    /// Storage gas used by the last transaction, failed or not
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.block_height
+    }
+
+    pub fn block_time(&self) -> u64 {
+        self.block_time
+    }
+
+    /// Advance the chain by `blocks` blocks of BLOCK_TIME seconds
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.block_height += blocks;
+        self.block_time += blocks * BLOCK_TIME;
+    }
+
+    /// Store the contract code, returning its code id
+    pub fn store_code(&mut self, contract: Box<dyn Contract>) -> u64 {
+        self.codes.push(Rc::from(contract));
+        self.codes.len() as u64
+    }
+
+    pub fn set_balance(&mut self, addr: &HumanAddr, coins: &[Coin]) {
+        for coin in coins.iter() {
+            self.router
+                .set_balance(addr, &coin.denom, coin.amount.u128());
+        }
+    }
+
+    pub fn balance(&self, addr: &HumanAddr, denom: &str) -> Uint128 {
+        Uint128(self.router.balance(addr, denom))
+    }
+
+    /// Instantiate the code at the given address
+    pub fn instantiate<T: Serialize>(
+        &mut self,
+        code_id: u64,
+        sender: &HumanAddr,
+        addr: &HumanAddr,
+        msg: &T,
+        send: &[Coin],
+    ) -> StdResult<Vec<LogAttribute>> {
+        let msg = to_binary(msg)?;
+        self.transact(|app| app.init_contract(code_id, sender, addr.clone(), msg, send.to_vec()))
+    }
+
+    pub fn execute<T: Serialize>(
+        &mut self,
+        sender: &HumanAddr,
+        contract_addr: &HumanAddr,
+        msg: &T,
+        send: &[Coin],
+    ) -> StdResult<Vec<LogAttribute>> {
+        let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_binary(msg)?,
+            send: send.to_vec(),
+        });
+        self.transact(|app| app.dispatch(sender, msg))
+    }
+
+    pub fn query<T: Serialize, R: DeserializeOwned>(
+        &self,
+        contract_addr: &HumanAddr,
+        msg: &T,
+    ) -> StdResult<R> {
+        let instance = self.router.instance(contract_addr)?;
+        let res = instance
+            .contract
+            .query(&instance.deps.borrow(), to_binary(msg)?)?;
+        from_binary(&res)
+    }
+
+    fn transact<F>(&mut self, action: F) -> StdResult<Vec<LogAttribute>>
+    where
+        F: FnOnce(&mut App) -> StdResult<Vec<LogAttribute>>,
+    {
+        let snapshot = self.router.snapshot();
+        let instance_count = self.instance_count;
+
+        self.router.gas_meter.used.set(0);
+        let res = action(self);
+        self.gas_used = self.router.gas_meter.used.get();
+        if res.is_err() {
+            self.router.restore(snapshot);
+            self.instance_count = instance_count;
+        }
+
+        res
+    }
+
+    fn env(&self, sender: &HumanAddr, contract_addr: &HumanAddr, send: &[Coin]) -> Env {
+        let mut env = mock_env(sender.clone(), send);
+        env.block.height = self.block_height;
+        env.block.time = self.block_time;
+        env.contract.address = contract_addr.clone();
+        env
+    }
+
+    fn init_contract(
+        &mut self,
+        code_id: u64,
+        sender: &HumanAddr,
+        addr: HumanAddr,
+        msg: Binary,
+        send: Vec<Coin>,
+    ) -> StdResult<Vec<LogAttribute>> {
+        let contract = code_id
+            .checked_sub(1)
+            .and_then(|index| self.codes.get(index as usize))
+            .cloned()
+            .ok_or_else(|| StdError::generic_err(format!("No such code: {}", code_id)))?;
+        if self.router.instance(&addr).is_ok() {
+            return Err(StdError::generic_err(format!(
+                "Contract already exists: {}",
+                addr
+            )));
+        }
+
+        let instance = Rc::new(Instance {
+            contract,
+            deps: RefCell::new(Extern {
+                storage: MeteredStorage::new(self.router.gas_meter.clone(), &[]),
+                api: MockApi::new(CANONICAL_LENGTH),
+                querier: AppQuerier {
+                    router: Rc::downgrade(&self.router),
+                },
+            }),
+        });
+        self.router
+            .contracts
+            .borrow_mut()
+            .insert(addr.to_string(), instance.clone());
+        self.instance_count += 1;
+
+        self.router.transfer(sender, &addr, &send)?;
+        let env = self.env(sender, &addr, &send);
+        let res = instance.execute(|deps| instance.contract.init(deps, env, msg))?;
+
+        self.dispatch_all(&addr, res.log, res.messages)
+    }
+
+    fn dispatch(
+        &mut self,
+        sender: &HumanAddr,
+        msg: CosmosMsg<TerraMsgWrapper>,
+    ) -> StdResult<Vec<LogAttribute>> {
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address,
+                to_address,
+                amount,
+            }) => {
+                if from_address != *sender {
+                    return Err(StdError::unauthorized());
+                }
+
+                self.router.transfer(&from_address, &to_address, &amount)?;
+                Ok(vec![])
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                send,
+            }) => {
+                let instance = self.router.instance(&contract_addr)?;
+                self.router.transfer(sender, &contract_addr, &send)?;
+
+                let env = self.env(sender, &contract_addr, &send);
+                let res = instance.execute(|deps| instance.contract.handle(deps, env, msg))?;
+
+                self.dispatch_all(&contract_addr, res.log, res.messages)
+            }
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id, msg, send, ..
+            }) => {
+                let addr = HumanAddr::from(format!("contract{}", self.instance_count));
+                self.init_contract(code_id, sender, addr, msg, send)
+            }
+            _ => Err(StdError::generic_err(format!(
+                "Unsupported message: {:?}",
+                msg
+            ))),
+        }
+    }
+
+    /// Execute the messages returned by a contract,
+    /// returning the logs of the whole message tree
+    fn dispatch_all(
+        &mut self,
+        contract_addr: &HumanAddr,
+        log: Vec<LogAttribute>,
+        messages: Vec<CosmosMsg<TerraMsgWrapper>>,
+    ) -> StdResult<Vec<LogAttribute>> {
+        let mut logs = log;
+        for msg in messages.into_iter() {
+            logs.extend(self.dispatch(contract_addr, msg)?);
+        }
+
+        Ok(logs)
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt::Write;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{to_binary, Coin, HumanAddr, StdResult, Uint128};
+use cw20::Cw20HandleMsg;
+
+use moneymarket::liquidation::{Cw20HookMsg as LiquidationCw20HookMsg, HandleMsg};
+
+use crate::suite::{Suite, STABLE_DENOM};
+
+/// Bids resting on the collateral, the whole percent premium slots
+/// they are spread over and the order of magnitude of the amounts
+#[derive(Clone, Debug, PartialEq)]
+pub struct GasCase {
+    pub bids: usize,
+    pub premium_slots: usize,
+    pub scale: u128,
+}
+
+/// Storage gas of each liquidation hot path in a case
+#[derive(Clone, Debug, PartialEq)]
+pub struct GasMeasurement {
+    pub case: GasCase,
+    pub submit: u64,
+    pub retract: u64,
+    pub execute: u64,
+    pub claim: u64,
+}
+
+/// Cases of the gas report
+pub fn report_cases() -> Vec<GasCase> {
+    let mut cases = vec![];
+    for bids in &[0usize, 10, 100] {
+        for premium_slots in &[1usize, 10, 30] {
+            for scale in &[1_000_000u128, 1_000_000_000, 1_000_000_000_000] {
+                cases.push(GasCase {
+                    bids: *bids,
+                    premium_slots: *premium_slots,
+                    scale: *scale,
+                });
+            }
+        }
+    }
+
+    cases
+}
+
+/// Submit a referred bid next to the resting ones, sell collateral to it,
+/// claim the referral fee and retract the rest, metering each transaction
+pub fn measure(case: &GasCase) -> StdResult<GasMeasurement> {
+    let mut suite = Suite::init()?;
+    suite.feed_price(Decimal256::one())?;
+
+    let liquidation = suite.liquidation.clone();
+    let bluna = suite.bluna.clone();
+    let owner = suite.owner.clone();
+    suite.app.execute(
+        &owner,
+        &liquidation,
+        &HandleMsg::UpdateConfig {
+            oracle_contract: None,
+            safe_ratio: None,
+            bid_fee: None,
+            liquidator_fee: None,
+            max_premium_rate: None,
+            liquidation_threshold: None,
+            max_close_factor: None,
+            price_timeframe: None,
+            price_mode: None,
+            guardian: None,
+            min_liquidation: None,
+            reserve_contract: None,
+            reserve_fee_share: None,
+            referral_fee_share: Some(Decimal256::percent(10)),
+            dust_threshold: None,
+            delegated_bids_enabled: None,
+            reward_token: None,
+            max_fill_per_window: None,
+            fill_window: None,
+            fee_admin: None,
+            overseer_contract: None,
+        },
+        &[],
+    )?;
+
+    for i in 0..case.bids {
+        let bidder = HumanAddr::from(format!("bidder{:04}", i));
+        let premium_rate = Decimal256::percent((i % case.premium_slots.max(1)) as u64 + 1);
+        suite.fund(&bidder, case.scale);
+        submit_bid(&mut suite, &bidder, premium_rate, case.scale, None)?;
+    }
+
+    let bidder = HumanAddr::from("bidder");
+    let referrer = HumanAddr::from("referrer");
+    suite.fund(&bidder, case.scale);
+    submit_bid(
+        &mut suite,
+        &bidder,
+        Decimal256::percent(1),
+        case.scale,
+        Some(referrer.clone()),
+    )?;
+    let submit = suite.app.gas_used();
+
+    let seller = HumanAddr::from("seller");
+    let collateral_amount = case.scale / 4;
+    suite.mint_bluna(&seller, collateral_amount)?;
+    suite.app.execute(
+        &seller,
+        &bluna,
+        &Cw20HandleMsg::Send {
+            contract: liquidation.clone(),
+            amount: Uint128(collateral_amount),
+            msg: Some(to_binary(&LiquidationCw20HookMsg::ExecuteBid {
+                liquidator: bidder.clone(),
+                repay_address: None,
+                fee_address: None,
+                liquidator_fee_address: None,
+                borrower: None,
+            })?),
+        },
+        &[],
+    )?;
+    let execute = suite.app.gas_used();
+
+    suite.app.execute(
+        &referrer,
+        &liquidation,
+        &HandleMsg::ClaimReferralFees {},
+        &[],
+    )?;
+    let claim = suite.app.gas_used();
+
+    suite.app.execute(
+        &bidder,
+        &liquidation,
+        &HandleMsg::RetractBid {
+            collateral_token: bluna,
+            amount: None,
+        },
+        &[],
+    )?;
+    let retract = suite.app.gas_used();
+
+    Ok(GasMeasurement {
+        case: case.clone(),
+        submit,
+        retract,
+        execute,
+        claim,
+    })
+}
+
+fn submit_bid(
+    suite: &mut Suite,
+    bidder: &HumanAddr,
+    premium_rate: Decimal256,
+    amount: u128,
+    referrer: Option<HumanAddr>,
+) -> StdResult<()> {
+    let liquidation = suite.liquidation.clone();
+    let collateral_token = suite.bluna.clone();
+    suite.app.execute(
+        bidder,
+        &liquidation,
+        &HandleMsg::SubmitBid {
+            collateral_token,
+            premium_rate,
+            expires_at: None,
+            referrer,
+            bid_for: None,
+            unlock_receiver: None,
+        },
+        &[Coin {
+            denom: STABLE_DENOM.to_string(),
+            amount: Uint128(amount),
+        }],
+    )?;
+
+    Ok(())
+}
+
+/// Markdown table of the measurements, headed by the measured revision
+pub fn render_report(revision: &str, measurements: &[GasMeasurement]) -> String {
+    let mut report = String::new();
+    writeln!(report, "# Liquidation storage gas at {}", revision).unwrap();
+    writeln!(report).unwrap();
+    writeln!(
+        report,
+        "Storage gas of each transaction with the default KV store costs \
+         of the cosmos sdk; the wasm execution gas is not included."
+    )
+    .unwrap();
+    writeln!(report).unwrap();
+    writeln!(
+        report,
+        "| bids | premium slots | scale | submit | retract | execute | claim |"
+    )
+    .unwrap();
+    writeln!(report, "|---:|---:|---:|---:|---:|---:|---:|").unwrap();
+    for m in measurements.iter() {
+        writeln!(
+            report,
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            m.case.bids,
+            m.case.premium_slots,
+            m.case.scale,
+            m.submit,
+            m.retract,
+            m.execute,
+            m.claim
+        )
+        .unwrap();
+    }
+
+    report
+}
@@ -0,0 +1,8 @@
+pub mod app;
+pub mod gas;
+pub mod mock;
+pub mod suite;
+pub mod token;
+
+#[cfg(test)]
+mod testing;
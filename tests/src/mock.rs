@@ -0,0 +1,93 @@
+use cosmwasm_std::{
+    BankMsg, Binary, Coin, CosmosMsg, Env, HandleResponse, InitResponse, StdError, StdResult,
+};
+use moneymarket::querier::query_balance;
+use terra_cosmwasm::TerraMsgWrapper;
+
+use crate::app::{AppDeps, Contract};
+
+/// Accepts every message and keeps the funds sent to it;
+/// stands in for the collector and the distributor
+pub struct Sink;
+
+impl Contract for Sink {
+    fn init(
+        &self,
+        _deps: &mut AppDeps,
+        _env: Env,
+        _msg: Binary,
+    ) -> StdResult<InitResponse<TerraMsgWrapper>> {
+        Ok(InitResponse {
+            messages: vec![],
+            log: vec![],
+        })
+    }
+
+    fn handle(
+        &self,
+        _deps: &mut AppDeps,
+        _env: Env,
+        _msg: Binary,
+    ) -> StdResult<HandleResponse<TerraMsgWrapper>> {
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![],
+            data: None,
+        })
+    }
+
+    fn query(&self, _deps: &AppDeps, _msg: Binary) -> StdResult<Binary> {
+        Err(StdError::generic_err("Sink contract cannot be queried"))
+    }
+}
+
+/// bAsset reward contract paying out its whole balance
+/// of `denom` to whoever claims the rewards
+pub struct Reward {
+    pub denom: String,
+}
+
+impl Contract for Reward {
+    fn init(
+        &self,
+        _deps: &mut AppDeps,
+        _env: Env,
+        _msg: Binary,
+    ) -> StdResult<InitResponse<TerraMsgWrapper>> {
+        Ok(InitResponse {
+            messages: vec![],
+            log: vec![],
+        })
+    }
+
+    fn handle(
+        &self,
+        deps: &mut AppDeps,
+        env: Env,
+        _msg: Binary,
+    ) -> StdResult<HandleResponse<TerraMsgWrapper>> {
+        let reward_amount = query_balance(deps, &env.contract.address, self.denom.to_string())?;
+
+        let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+        if !reward_amount.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address,
+                to_address: env.message.sender,
+                amount: vec![Coin {
+                    denom: self.denom.to_string(),
+                    amount: reward_amount.into(),
+                }],
+            }));
+        }
+
+        Ok(HandleResponse {
+            messages,
+            log: vec![],
+            data: None,
+        })
+    }
+
+    fn query(&self, _deps: &AppDeps, _msg: Binary) -> StdResult<Binary> {
+        Err(StdError::generic_err("Reward contract cannot be queried"))
+    }
+}
@@ -0,0 +1,590 @@
+use std::str::FromStr;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{to_binary, Coin, HumanAddr, LogAttribute, StdResult, Uint128};
+use cw20::{BalanceResponse, Cw20HandleMsg, Cw20QueryMsg, MinterResponse};
+use terraswap::token::InitMsg as TokenInitMsg;
+
+use moneymarket::custody::{
+    BAssetInfo, Cw20HookMsg as CustodyCw20HookMsg, InitMsg as CustodyInitMsg,
+};
+use moneymarket::distribution_model::InitMsg as DistributionModelInitMsg;
+use moneymarket::interest_model::InitMsg as InterestModelInitMsg;
+use moneymarket::liquidation::{
+    BidResponse, HandleMsg as LiquidationHandleMsg, InitMsg as LiquidationInitMsg,
+    QueryMsg as LiquidationQueryMsg,
+};
+use moneymarket::market::{
+    BorrowerInfoResponse, ConfigResponse as MarketConfigResponse, HandleMsg as MarketHandleMsg,
+    InitMsg as MarketInitMsg, QueryMsg as MarketQueryMsg,
+};
+use moneymarket::oracle::{HandleMsg as OracleHandleMsg, InitMsg as OracleInitMsg, PriceMode};
+use moneymarket::overseer::{
+    CollateralsResponse, HandleMsg as OverseerHandleMsg, InitMsg as OverseerInitMsg,
+    QueryMsg as OverseerQueryMsg,
+};
+use moneymarket_market::contract::INITIAL_DEPOSIT_AMOUNT;
+use moneymarket_overseer::state::EpochState;
+
+use crate::app::{App, ContractWrapper};
+use crate::mock::{Reward, Sink};
+use crate::token;
+
+pub const STABLE_DENOM: &str = "uusd";
+pub const GENESIS_HEIGHT: u64 = 100;
+pub const GENESIS_TIME: u64 = 1_600_000_000;
+pub const EPOCH_PERIOD: u64 = 100;
+pub const PRICE_TIMEFRAME: u64 = 60;
+
+/// The money market deployed on an app: the market with its aterra token,
+/// the overseer, a bLuna custody, the oracle, the liquidation contract and
+/// the models. The bLuna reward contract pays out whatever it holds, and
+/// the collector and distributor only keep the funds sent to them.
+pub struct Suite {
+    pub app: App,
+    pub owner: HumanAddr,
+    pub feeder: HumanAddr,
+    pub market: HumanAddr,
+    pub aterra: HumanAddr,
+    pub overseer: HumanAddr,
+    pub custody: HumanAddr,
+    pub bluna: HumanAddr,
+    pub oracle: HumanAddr,
+    pub liquidation: HumanAddr,
+    pub interest_model: HumanAddr,
+    pub distribution_model: HumanAddr,
+    pub reward: HumanAddr,
+    pub collector: HumanAddr,
+    pub distributor: HumanAddr,
+}
+
+impl Suite {
+    pub fn init() -> StdResult<Self> {
+        let mut app = App::new(GENESIS_HEIGHT, GENESIS_TIME);
+
+        let token_code = app.store_code(Box::new(ContractWrapper::new(
+            token::init,
+            token::handle,
+            token::query,
+        )));
+        let market_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_market::contract::init,
+            moneymarket_market::contract::handle,
+            moneymarket_market::contract::query,
+        )));
+        let overseer_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_overseer::contract::init,
+            moneymarket_overseer::contract::handle,
+            moneymarket_overseer::contract::query,
+        )));
+        let custody_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_custody_bluna::contract::init,
+            moneymarket_custody_bluna::contract::handle,
+            moneymarket_custody_bluna::contract::query,
+        )));
+        let oracle_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_oracle::contract::init,
+            moneymarket_oracle::contract::handle,
+            moneymarket_oracle::contract::query,
+        )));
+        let liquidation_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_liquidation::contract::init,
+            moneymarket_liquidation::contract::handle,
+            moneymarket_liquidation::contract::query,
+        )));
+        let interest_model_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_interest_model::contract::init,
+            moneymarket_interest_model::contract::handle,
+            moneymarket_interest_model::contract::query,
+        )));
+        let distribution_model_code = app.store_code(Box::new(ContractWrapper::new(
+            moneymarket_distribution_model::contract::init,
+            moneymarket_distribution_model::contract::handle,
+            moneymarket_distribution_model::contract::query,
+        )));
+        let reward_code = app.store_code(Box::new(Reward {
+            denom: STABLE_DENOM.to_string(),
+        }));
+        let sink_code = app.store_code(Box::new(Sink));
+
+        let owner = HumanAddr::from("owner");
+        let feeder = HumanAddr::from("feeder");
+        let market = HumanAddr::from("market");
+        let overseer = HumanAddr::from("overseer");
+        let custody = HumanAddr::from("custody_bluna");
+        let bluna = HumanAddr::from("bluna");
+        let oracle = HumanAddr::from("oracle");
+        let liquidation = HumanAddr::from("liquidation");
+        let interest_model = HumanAddr::from("interest_model");
+        let distribution_model = HumanAddr::from("distribution_model");
+        let reward = HumanAddr::from("bluna_reward");
+        let collector = HumanAddr::from("collector");
+        let distributor = HumanAddr::from("distributor");
+
+        for sink in [&collector, &distributor].iter() {
+            app.instantiate(sink_code, &owner, sink, &(), &[])?;
+        }
+        app.instantiate(reward_code, &owner, &reward, &(), &[])?;
+
+        app.instantiate(
+            token_code,
+            &owner,
+            &bluna,
+            &TokenInitMsg {
+                name: "bonded luna".to_string(),
+                symbol: "BLUNA".to_string(),
+                decimals: 6u8,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: owner.clone(),
+                    cap: None,
+                }),
+                init_hook: None,
+            },
+            &[],
+        )?;
+
+        app.instantiate(
+            oracle_code,
+            &owner,
+            &oracle,
+            &OracleInitMsg {
+                owner: owner.clone(),
+                base_asset: STABLE_DENOM.to_string(),
+                price_timeframe: PRICE_TIMEFRAME,
+            },
+            &[],
+        )?;
+        app.execute(
+            &owner,
+            &oracle,
+            &OracleHandleMsg::RegisterFeeder {
+                asset: bluna.to_string(),
+                feeder: feeder.clone(),
+            },
+            &[],
+        )?;
+
+        app.instantiate(
+            interest_model_code,
+            &owner,
+            &interest_model,
+            &InterestModelInitMsg {
+                owner: owner.clone(),
+                base_rate: Decimal256::from_str("0.000000002")?,
+                interest_multiplier: Decimal256::from_str("0.00000002")?,
+                rate_model: None,
+                min_borrow_rate: None,
+                max_borrow_rate: None,
+                use_smoothed_utilization: None,
+            },
+            &[],
+        )?;
+
+        app.instantiate(
+            distribution_model_code,
+            &owner,
+            &distribution_model,
+            &DistributionModelInitMsg {
+                owner: owner.clone(),
+                emission_cap: Decimal256::from_uint256(100u64),
+                emission_floor: Decimal256::from_uint256(10u64),
+                increment_multiplier: Decimal256::percent(110),
+                decrement_multiplier: Decimal256::percent(90),
+            },
+            &[],
+        )?;
+
+        // the market instantiates its aterra token,
+        // which registers itself through the init hook
+        let initial_deposit = Coin {
+            denom: STABLE_DENOM.to_string(),
+            amount: Uint128(INITIAL_DEPOSIT_AMOUNT),
+        };
+        app.set_balance(&owner, std::slice::from_ref(&initial_deposit));
+        app.instantiate(
+            market_code,
+            &owner,
+            &market,
+            &MarketInitMsg {
+                owner_addr: owner.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                aterra_code_id: token_code,
+                anc_emission_rate: Decimal256::one(),
+                max_borrow_factor: Decimal256::one(),
+            },
+            &[initial_deposit],
+        )?;
+
+        app.instantiate(
+            overseer_code,
+            &owner,
+            &overseer,
+            &OverseerInitMsg {
+                owner_addr: owner.clone(),
+                oracle_contract: oracle.clone(),
+                market_contract: market.clone(),
+                liquidation_contract: liquidation.clone(),
+                collector_contract: collector.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                epoch_period: EPOCH_PERIOD,
+                threshold_deposit_rate: Decimal256::from_str("0.000000030572")?,
+                target_deposit_rate: Decimal256::from_str("0.000000040762")?,
+                buffer_distribution_factor: Decimal256::percent(20),
+                anc_purchase_factor: Decimal256::percent(10),
+                price_timeframe: PRICE_TIMEFRAME,
+            },
+            &[],
+        )?;
+
+        app.instantiate(
+            liquidation_code,
+            &owner,
+            &liquidation,
+            &LiquidationInitMsg {
+                owner: owner.clone(),
+                oracle_contract: oracle.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                safe_ratio: Decimal256::percent(80),
+                bid_fee: Decimal256::percent(1),
+                liquidator_fee: Decimal256::percent(1),
+                max_premium_rate: Decimal256::percent(30),
+                liquidation_threshold: Uint256::from(500u64),
+                max_close_factor: Decimal256::one(),
+                price_timeframe: PRICE_TIMEFRAME,
+                price_mode: PriceMode::Spot,
+            },
+            &[],
+        )?;
+
+        app.instantiate(
+            custody_code,
+            &owner,
+            &custody,
+            &CustodyInitMsg {
+                owner: owner.clone(),
+                collateral_token: bluna.clone(),
+                overseer_contract: overseer.clone(),
+                market_contract: market.clone(),
+                reward_contract: reward.clone(),
+                liquidation_contract: liquidation.clone(),
+                stable_denom: STABLE_DENOM.to_string(),
+                basset_info: BAssetInfo {
+                    name: "bonded luna".to_string(),
+                    symbol: "BLUNA".to_string(),
+                    decimals: 6u8,
+                },
+            },
+            &[],
+        )?;
+
+        app.execute(
+            &owner,
+            &market,
+            &MarketHandleMsg::RegisterContracts {
+                overseer_contract: overseer.clone(),
+                interest_model: interest_model.clone(),
+                distribution_model: distribution_model.clone(),
+                collector_contract: collector.clone(),
+                distributor_contract: distributor.clone(),
+            },
+            &[],
+        )?;
+        let market_config: MarketConfigResponse = app.query(&market, &MarketQueryMsg::Config {})?;
+
+        app.execute(
+            &owner,
+            &overseer,
+            &OverseerHandleMsg::Whitelist {
+                name: "bonded luna".to_string(),
+                symbol: "BLUNA".to_string(),
+                collateral_token: bluna.clone(),
+                custody_contract: custody.clone(),
+                max_ltv: Decimal256::percent(50),
+            },
+            &[],
+        )?;
+
+        // the custody stages the liquidated collateral for the overseer
+        app.execute(
+            &owner,
+            &liquidation,
+            &LiquidationHandleMsg::SetCollateralCustody {
+                collateral_token: bluna.clone(),
+                custody_contract: Some(custody.clone()),
+            },
+            &[],
+        )?;
+        app.execute(
+            &owner,
+            &liquidation,
+            &LiquidationHandleMsg::UpdateConfig {
+                oracle_contract: None,
+                safe_ratio: None,
+                bid_fee: None,
+                liquidator_fee: None,
+                max_premium_rate: None,
+                liquidation_threshold: None,
+                max_close_factor: None,
+                price_timeframe: None,
+                price_mode: None,
+                guardian: None,
+                min_liquidation: None,
+                reserve_contract: None,
+                reserve_fee_share: None,
+                referral_fee_share: None,
+                dust_threshold: None,
+                delegated_bids_enabled: None,
+                reward_token: None,
+                max_fill_per_window: None,
+                fill_window: None,
+                fee_admin: None,
+                overseer_contract: Some(overseer.clone()),
+            },
+            &[],
+        )?;
+
+        Ok(Suite {
+            app,
+            owner,
+            feeder,
+            market,
+            aterra: market_config.aterra_contract,
+            overseer,
+            custody,
+            bluna,
+            oracle,
+            liquidation,
+            interest_model,
+            distribution_model,
+            reward,
+            collector,
+            distributor,
+        })
+    }
+
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.app.advance_blocks(blocks);
+    }
+
+    /// Give the address `amount` of the stable denom
+    pub fn fund(&mut self, addr: &HumanAddr, amount: u128) {
+        let balance = self.stable_balance(addr);
+        self.app.set_balance(
+            addr,
+            &[Coin {
+                denom: STABLE_DENOM.to_string(),
+                amount: Uint128(balance.u128() + amount),
+            }],
+        );
+    }
+
+    pub fn feed_price(&mut self, price: Decimal256) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            &self.feeder,
+            &self.oracle,
+            &OracleHandleMsg::FeedPrice {
+                prices: vec![(self.bluna.to_string(), price)],
+            },
+            &[],
+        )
+    }
+
+    pub fn mint_bluna(
+        &mut self,
+        recipient: &HumanAddr,
+        amount: u128,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            &self.owner,
+            &self.bluna,
+            &Cw20HandleMsg::Mint {
+                recipient: recipient.clone(),
+                amount: Uint128(amount),
+            },
+            &[],
+        )
+    }
+
+    pub fn deposit_stable(
+        &mut self,
+        depositor: &HumanAddr,
+        amount: u128,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            depositor,
+            &self.market,
+            &MarketHandleMsg::DepositStable {
+                min_mint_amount: None,
+            },
+            &[Coin {
+                denom: STABLE_DENOM.to_string(),
+                amount: Uint128(amount),
+            }],
+        )
+    }
+
+    /// Deposit bLuna to the custody and lock it in the overseer
+    pub fn provide_collateral(
+        &mut self,
+        borrower: &HumanAddr,
+        amount: u128,
+    ) -> StdResult<Vec<LogAttribute>> {
+        let mut logs = self.app.execute(
+            borrower,
+            &self.bluna,
+            &Cw20HandleMsg::Send {
+                contract: self.custody.clone(),
+                amount: Uint128(amount),
+                msg: Some(to_binary(&CustodyCw20HookMsg::DepositCollateral {})?),
+            },
+            &[],
+        )?;
+
+        logs.extend(self.app.execute(
+            borrower,
+            &self.overseer,
+            &OverseerHandleMsg::LockCollateral {
+                collaterals: vec![(self.bluna.clone(), Uint256::from(amount))],
+            },
+            &[],
+        )?);
+
+        Ok(logs)
+    }
+
+    pub fn borrow_stable(
+        &mut self,
+        borrower: &HumanAddr,
+        amount: u128,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            borrower,
+            &self.market,
+            &MarketHandleMsg::BorrowStable {
+                borrow_amount: Uint256::from(amount),
+                to: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn submit_bid(
+        &mut self,
+        bidder: &HumanAddr,
+        premium_rate: Decimal256,
+        amount: u128,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            bidder,
+            &self.liquidation,
+            &LiquidationHandleMsg::SubmitBid {
+                collateral_token: self.bluna.clone(),
+                premium_rate,
+                expires_at: None,
+                referrer: None,
+                bid_for: None,
+                unlock_receiver: None,
+            },
+            &[Coin {
+                denom: STABLE_DENOM.to_string(),
+                amount: Uint128(amount),
+            }],
+        )
+    }
+
+    pub fn liquidate(
+        &mut self,
+        liquidator: &HumanAddr,
+        borrower: &HumanAddr,
+    ) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            liquidator,
+            &self.overseer,
+            &OverseerHandleMsg::LiquidateCollateral {
+                borrower: borrower.clone(),
+            },
+            &[],
+        )
+    }
+
+    pub fn execute_epoch_operations(&mut self, sender: &HumanAddr) -> StdResult<Vec<LogAttribute>> {
+        self.app.execute(
+            sender,
+            &self.overseer,
+            &OverseerHandleMsg::ExecuteEpochOperations {},
+            &[],
+        )
+    }
+
+    pub fn stable_balance(&self, addr: &HumanAddr) -> Uint128 {
+        self.app.balance(addr, STABLE_DENOM)
+    }
+
+    pub fn bluna_balance(&self, addr: &HumanAddr) -> StdResult<Uint128> {
+        token_balance(&self.app, &self.bluna, addr)
+    }
+
+    pub fn aterra_balance(&self, addr: &HumanAddr) -> StdResult<Uint128> {
+        token_balance(&self.app, &self.aterra, addr)
+    }
+
+    pub fn loan_amount(&self, borrower: &HumanAddr) -> StdResult<Uint256> {
+        let borrower_info: BorrowerInfoResponse = self.app.query(
+            &self.market,
+            &MarketQueryMsg::BorrowerInfo {
+                borrower: borrower.clone(),
+                block_height: Some(self.app.block_height()),
+            },
+        )?;
+        Ok(borrower_info.loan_amount)
+    }
+
+    /// Locked amount of bLuna
+    pub fn collateral_amount(&self, borrower: &HumanAddr) -> StdResult<Uint256> {
+        let res: CollateralsResponse = self.app.query(
+            &self.overseer,
+            &OverseerQueryMsg::Collaterals {
+                borrower: borrower.clone(),
+            },
+        )?;
+        Ok(res
+            .collaterals
+            .iter()
+            .find(|c| c.0 == self.bluna)
+            .map(|c| c.1)
+            .unwrap_or_else(Uint256::zero))
+    }
+
+    pub fn bid(&self, bidder: &HumanAddr) -> StdResult<BidResponse> {
+        self.app.query(
+            &self.liquidation,
+            &LiquidationQueryMsg::Bid {
+                collateral_token: self.bluna.clone(),
+                bidder: bidder.clone(),
+            },
+        )
+    }
+
+    pub fn epoch_state(&self) -> StdResult<EpochState> {
+        self.app
+            .query(&self.overseer, &OverseerQueryMsg::EpochState {})
+    }
+}
+
+fn token_balance(app: &App, token: &HumanAddr, addr: &HumanAddr) -> StdResult<Uint128> {
+    let res: BalanceResponse = app.query(
+        token,
+        &Cw20QueryMsg::Balance {
+            address: addr.clone(),
+        },
+    )?;
+    Ok(res.balance)
+}
+
+/// Value of the first log attribute with the key
+pub fn log_value(logs: &[LogAttribute], key: &str) -> Option<String> {
+    logs.iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
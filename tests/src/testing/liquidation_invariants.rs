@@ -0,0 +1,392 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{to_binary, HumanAddr, Uint128};
+use cw20::Cw20HandleMsg;
+use proptest::prelude::*;
+
+use moneymarket::liquidation::{
+    Cw20HookMsg as LiquidationCw20HookMsg, HandleMsg, InvariantsResponse,
+    LiquidationAmountResponse, QueryMsg, SimulateLiquidationResponse,
+};
+
+use moneymarket_liquidation::state::CONFIG_TIMELOCK_PERIOD;
+
+use crate::app::BLOCK_TIME;
+use crate::suite::Suite;
+
+const BIDDERS: usize = 3;
+const POSITION_COLLATERAL: u128 = 100_000_000;
+
+/// Liquidation config a sequence of operations runs against
+#[derive(Clone, Debug)]
+struct Params {
+    max_close_factor: Decimal256,
+    bid_fee: Decimal256,
+    liquidator_fee: Decimal256,
+    price: Decimal256,
+    liquidation_threshold: Uint256,
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Submit {
+        bidder: usize,
+        premium_rate: Decimal256,
+        amount: u128,
+    },
+    Retract {
+        bidder: usize,
+        amount: Option<u128>,
+    },
+    /// Sell the LiquidationAmount of a loan at `loan_percent`
+    /// of the position's borrow limit to the bidder
+    Execute {
+        bidder: usize,
+        loan_percent: u64,
+    },
+}
+
+fn params() -> impl Strategy<Value = Params> {
+    (
+        1u64..=100,
+        0u64..=20,
+        0u64..=20,
+        100u64..=5000,
+        0u64..=1_000_000_000,
+    )
+        .prop_map(
+            |(max_close_factor, bid_fee, liquidator_fee, price, liquidation_threshold)| Params {
+                max_close_factor: Decimal256::percent(max_close_factor),
+                bid_fee: Decimal256::percent(bid_fee),
+                liquidator_fee: Decimal256::percent(liquidator_fee),
+                price: Decimal256::permille(price),
+                liquidation_threshold: Uint256::from(liquidation_threshold),
+            },
+        )
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..BIDDERS, 0u64..=5, 1_000u128..=1_000_000_000).prop_map(
+            |(bidder, premium_rate, amount)| Op::Submit {
+                bidder,
+                premium_rate: Decimal256::percent(premium_rate),
+                amount,
+            }
+        ),
+        (0..BIDDERS, proptest::option::of(1u128..=1_000_000_000))
+            .prop_map(|(bidder, amount)| Op::Retract { bidder, amount }),
+        (0..BIDDERS, 101u64..=300).prop_map(|(bidder, loan_percent)| Op::Execute {
+            bidder,
+            loan_percent
+        }),
+    ]
+}
+
+fn bidder(i: usize) -> HumanAddr {
+    HumanAddr::from(format!("bidder{:04}", i))
+}
+
+fn setup(params: &Params) -> Suite {
+    let mut suite = Suite::init().unwrap();
+
+    let liquidation = suite.liquidation.clone();
+    let owner = suite.owner.clone();
+    suite
+        .app
+        .execute(
+            &owner,
+            &liquidation,
+            &HandleMsg::UpdateConfig {
+                oracle_contract: None,
+                safe_ratio: None,
+                bid_fee: Some(params.bid_fee),
+                liquidator_fee: Some(params.liquidator_fee),
+                max_premium_rate: None,
+                liquidation_threshold: Some(params.liquidation_threshold),
+                max_close_factor: Some(params.max_close_factor),
+                price_timeframe: None,
+                price_mode: None,
+                guardian: None,
+                min_liquidation: None,
+                reserve_contract: None,
+                reserve_fee_share: None,
+                referral_fee_share: None,
+                dust_threshold: None,
+                delegated_bids_enabled: None,
+                reward_token: None,
+                max_fill_per_window: None,
+                fill_window: None,
+                fee_admin: None,
+                overseer_contract: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    // risk parameters only apply after the timelock
+    suite.advance_blocks(CONFIG_TIMELOCK_PERIOD / BLOCK_TIME + 1);
+    suite
+        .app
+        .execute(&owner, &liquidation, &HandleMsg::ApplyConfig {}, &[])
+        .unwrap();
+    suite.feed_price(params.price).unwrap();
+
+    let seller = HumanAddr::from("seller");
+    suite.mint_bluna(&seller, POSITION_COLLATERAL).unwrap();
+
+    suite
+}
+
+fn bid_amount(suite: &Suite, bidder: &HumanAddr) -> Uint256 {
+    suite
+        .bid(bidder)
+        .map(|bid| bid.amount)
+        .unwrap_or_else(|_| Uint256::zero())
+}
+
+fn liquidation_amount(
+    suite: &Suite,
+    params: &Params,
+    collateral: Uint256,
+    loan_percent: u64,
+) -> Uint256 {
+    let borrow_limit = collateral * params.price * Decimal256::percent(60);
+    let res: LiquidationAmountResponse = suite
+        .app
+        .query(
+            &suite.liquidation,
+            &QueryMsg::LiquidationAmount {
+                borrow_amount: borrow_limit * Decimal256::percent(loan_percent),
+                borrow_limit,
+                collaterals: vec![(suite.bluna.clone(), collateral)],
+                collateral_prices: vec![params.price],
+            },
+        )
+        .unwrap();
+
+    // only the given collateral is liquidated, at most once
+    assert!(res.collaterals.len() <= 1);
+    res.collaterals
+        .iter()
+        .find(|c| c.0 == suite.bluna)
+        .map(|c| c.1)
+        .unwrap_or_else(Uint256::zero)
+}
+
+/// Apply the operation, checking it against the expected bids
+fn apply(
+    suite: &mut Suite,
+    params: &Params,
+    bids: &mut [Uint256; BIDDERS],
+    op: &Op,
+) -> Result<(), TestCaseError> {
+    let liquidation = suite.liquidation.clone();
+    let bluna = suite.bluna.clone();
+    match op.clone() {
+        Op::Submit {
+            bidder: i,
+            premium_rate,
+            amount,
+        } => {
+            suite.fund(&bidder(i), amount);
+            let res = suite.submit_bid(&bidder(i), premium_rate, amount);
+            if bids[i].is_zero() {
+                prop_assert!(res.is_ok());
+                bids[i] = Uint256::from(amount);
+            } else {
+                prop_assert!(res.is_err());
+            }
+        }
+        Op::Retract { bidder: i, amount } => {
+            let res = suite.app.execute(
+                &bidder(i),
+                &liquidation,
+                &HandleMsg::RetractBid {
+                    collateral_token: bluna,
+                    amount: amount.map(Uint256::from),
+                },
+                &[],
+            );
+            let amount = amount.map(Uint256::from).unwrap_or(bids[i]);
+            if bids[i].is_zero() || amount > bids[i] {
+                prop_assert!(res.is_err());
+            } else {
+                prop_assert!(res.is_ok());
+                bids[i] = bids[i] - amount;
+            }
+        }
+        Op::Execute {
+            bidder: i,
+            loan_percent,
+        } => {
+            let seller = HumanAddr::from("seller");
+            let collateral = Uint256::from(suite.bluna_balance(&seller).unwrap());
+            let amount = liquidation_amount(suite, params, collateral, loan_percent);
+
+            // collateral is conserved: never more than the position
+            // holds, and the close factor caps the positions over the
+            // liquidation threshold
+            prop_assert!(amount <= collateral);
+            if collateral * params.price >= params.liquidation_threshold {
+                prop_assert!(amount <= collateral * params.max_close_factor);
+            }
+
+            // a bigger loan never liquidates less
+            let bigger = liquidation_amount(suite, params, collateral, loan_percent + 50);
+            prop_assert!(bigger >= amount);
+
+            if amount.is_zero() {
+                return Ok(());
+            }
+
+            let simulated: Result<SimulateLiquidationResponse, _> = suite.app.query(
+                &liquidation,
+                &QueryMsg::SimulateLiquidation {
+                    collateral_token: bluna.clone(),
+                    collateral_amount: amount,
+                    bidder: bidder(i),
+                    price_override: None,
+                },
+            );
+
+            let bidder_collateral = Uint256::from(suite.bluna_balance(&bidder(i)).unwrap());
+            let res = suite.app.execute(
+                &seller,
+                &bluna,
+                &Cw20HandleMsg::Send {
+                    contract: liquidation.clone(),
+                    amount: amount.into(),
+                    msg: Some(
+                        to_binary(&LiquidationCw20HookMsg::ExecuteBid {
+                            liquidator: bidder(i),
+                            repay_address: None,
+                            fee_address: None,
+                            liquidator_fee_address: None,
+                            borrower: None,
+                        })
+                        .unwrap(),
+                    ),
+                },
+                &[],
+            );
+
+            let simulated = match simulated {
+                Ok(simulated) => simulated,
+                Err(_) => {
+                    // a bid that cannot pay for the collateral is left alone
+                    prop_assert!(res.is_err());
+                    return Ok(());
+                }
+            };
+            prop_assert!(res.is_ok());
+
+            // the fees never exceed the stable the bid pays
+            prop_assert!(simulated.bid_fee + simulated.liquidator_fee <= simulated.required_stable);
+            prop_assert!(
+                simulated.repay_amount + simulated.bid_fee + simulated.liquidator_fee
+                    <= simulated.required_stable
+            );
+            prop_assert_eq!(
+                simulated.remaining_bid_amount,
+                bids[i] - simulated.required_stable
+            );
+            bids[i] = simulated.remaining_bid_amount;
+
+            // the sold collateral goes to the bidder as a whole
+            prop_assert_eq!(
+                Uint256::from(suite.bluna_balance(&seller).unwrap()),
+                collateral - amount
+            );
+            prop_assert_eq!(
+                Uint256::from(suite.bluna_balance(&bidder(i)).unwrap()),
+                bidder_collateral + amount
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Invariants holding after every operation
+fn check(suite: &Suite, bids: &[Uint256; BIDDERS]) -> Result<(), TestCaseError> {
+    for (i, amount) in bids.iter().enumerate() {
+        prop_assert_eq!(bid_amount(suite, &bidder(i)), *amount);
+    }
+
+    let res: InvariantsResponse = suite
+        .app
+        .query(
+            &suite.liquidation,
+            &QueryMsg::Invariants {
+                collateral_token: suite.bluna.clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    prop_assert_eq!(res.drift, Uint256::zero());
+    prop_assert!(res.mismatched_bidders.is_empty());
+
+    // the contract holds the stable of every bid,
+    // and none of the collateral it sold
+    let total_bid_amount = bids.iter().fold(Uint256::zero(), |acc, v| acc + *v);
+    prop_assert_eq!(res.total_bid_amount, total_bid_amount);
+    prop_assert!(Uint256::from(suite.stable_balance(&suite.liquidation)) >= total_bid_amount);
+    prop_assert_eq!(
+        suite.bluna_balance(&suite.liquidation).unwrap(),
+        Uint128::zero()
+    );
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn liquidation_amount_invariants(
+        params in params(),
+        ops in prop::collection::vec(op(), 1..24),
+    ) {
+        let mut suite = setup(&params);
+        let mut bids = [Uint256::zero(); BIDDERS];
+        for op in ops.iter() {
+            apply(&mut suite, &params, &mut bids, op)?;
+            check(&suite, &bids)?;
+        }
+    }
+}
+
+#[test]
+fn liquidation_amount_invariants_with_funded_bids() {
+    // bids big enough for any liquidation amount, so the
+    // fills are covered whatever the generated sequences hit
+    let params = Params {
+        max_close_factor: Decimal256::percent(50),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(1),
+        price: Decimal256::one(),
+        liquidation_threshold: Uint256::from(500u64),
+    };
+    let mut suite = setup(&params);
+    let mut bids = [Uint256::zero(); BIDDERS];
+    let amount = POSITION_COLLATERAL * 2;
+    for i in 0..BIDDERS {
+        let op = Op::Submit {
+            bidder: i,
+            premium_rate: Decimal256::percent(i as u64),
+            amount,
+        };
+        apply(&mut suite, &params, &mut bids, &op).unwrap();
+    }
+
+    for loan_percent in [101u64, 150, 300].iter() {
+        let op = Op::Execute {
+            bidder: 1,
+            loan_percent: *loan_percent,
+        };
+        apply(&mut suite, &params, &mut bids, &op).unwrap();
+        check(&suite, &bids).unwrap();
+    }
+    assert!(bids[1] < Uint256::from(amount));
+}
@@ -0,0 +1,2 @@
+mod liquidation_invariants;
+mod tests;
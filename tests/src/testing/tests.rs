@@ -0,0 +1,200 @@
+use crate::gas::{measure, render_report, GasCase};
+use crate::suite::{log_value, Suite, EPOCH_PERIOD};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{HumanAddr, StdError, Uint128};
+
+const DEPOSIT_AMOUNT: u128 = 1_000_000_000;
+const COLLATERAL_AMOUNT: u128 = 1_000_000_000;
+const BORROW_AMOUNT: u128 = 400_000_000;
+const BID_AMOUNT: u128 = 1_000_000_000;
+
+/// Market with a deposit, a bLuna backed loan at
+/// 80% of its borrow limit and a bid on bLuna
+fn setup_loan() -> Suite {
+    let mut suite = Suite::init().unwrap();
+    let depositor = HumanAddr::from("depositor");
+    let borrower = HumanAddr::from("borrower");
+
+    suite.feed_price(Decimal256::one()).unwrap();
+
+    suite.fund(&depositor, DEPOSIT_AMOUNT);
+    suite.deposit_stable(&depositor, DEPOSIT_AMOUNT).unwrap();
+    assert_eq!(
+        suite.aterra_balance(&depositor).unwrap(),
+        Uint128(DEPOSIT_AMOUNT)
+    );
+
+    suite.mint_bluna(&borrower, COLLATERAL_AMOUNT).unwrap();
+    suite
+        .provide_collateral(&borrower, COLLATERAL_AMOUNT)
+        .unwrap();
+    assert_eq!(
+        suite.collateral_amount(&borrower).unwrap(),
+        Uint256::from(COLLATERAL_AMOUNT)
+    );
+
+    suite.borrow_stable(&borrower, BORROW_AMOUNT).unwrap();
+    assert_eq!(suite.stable_balance(&borrower), Uint128(BORROW_AMOUNT));
+
+    let keeper = HumanAddr::from("keeper");
+    suite.fund(&keeper, BID_AMOUNT);
+    suite
+        .submit_bid(&keeper, Decimal256::percent(5), BID_AMOUNT)
+        .unwrap();
+
+    suite
+}
+
+#[test]
+fn price_drop_liquidation_and_epoch_operations() {
+    let mut suite = setup_loan();
+    let borrower = HumanAddr::from("borrower");
+    let keeper = HumanAddr::from("keeper");
+    let market = suite.market.clone();
+    let overseer = suite.overseer.clone();
+    let custody = suite.custody.clone();
+    let collector = suite.collector.clone();
+    let reward = suite.reward.clone();
+
+    // the loan is safe at the current price
+    suite.advance_blocks(1);
+    suite.feed_price(Decimal256::one()).unwrap();
+    match suite.liquidate(&keeper, &borrower) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot liquidate safely collateralized loan")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // bLuna drops 40%, which puts the loan above the liquidation limit
+    suite.advance_blocks(1);
+    suite.feed_price(Decimal256::percent(60)).unwrap();
+    let loan_before = suite.loan_amount(&borrower).unwrap();
+    let market_balance_before = suite.stable_balance(&market);
+    let keeper_balance_before = suite.stable_balance(&keeper);
+
+    suite.liquidate(&keeper, &borrower).unwrap();
+
+    // the custody handed the liquidated bLuna over to the bidder
+    let remaining_collateral = suite.collateral_amount(&borrower).unwrap();
+    let liquidated = Uint256::from(COLLATERAL_AMOUNT) - remaining_collateral;
+    assert!(!liquidated.is_zero());
+    assert_eq!(
+        Uint256::from(suite.bluna_balance(&keeper).unwrap()),
+        liquidated
+    );
+    assert_eq!(
+        Uint256::from(suite.bluna_balance(&custody).unwrap()),
+        remaining_collateral
+    );
+    assert!(suite.bid(&keeper).unwrap().amount < Uint256::from(BID_AMOUNT));
+
+    // the bid repaid the loan, and paid the bid fee to
+    // the interest buffer and the liquidator fee to the keeper
+    assert!(suite.loan_amount(&borrower).unwrap() < loan_before);
+    assert!(suite.stable_balance(&market) > market_balance_before);
+    assert!(suite.stable_balance(&keeper) > keeper_balance_before);
+    let interest_buffer = suite.stable_balance(&overseer);
+    assert!(!interest_buffer.is_zero());
+
+    // an epoch has not passed yet
+    match suite.execute_epoch_operations(&keeper) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "An epoch has not passed yet; last executed height: {}",
+                suite.epoch_state().unwrap().last_executed_height
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the custody claims the bLuna rewards during the epoch operations
+    // and forwards them to the interest buffer, after the buffer
+    // to be kept was recorded; the first epoch has no previous
+    // deposits to subsidize
+    suite.advance_blocks(EPOCH_PERIOD);
+    suite.fund(&reward, 10_000_000);
+    let logs = suite.execute_epoch_operations(&keeper).unwrap();
+
+    let anc_purchase_amount = Uint256::from(interest_buffer) * Decimal256::percent(10);
+    assert_eq!(
+        log_value(&logs, "anc_purchase_amount"),
+        Some(anc_purchase_amount.to_string())
+    );
+    assert!(Uint256::from(suite.stable_balance(&collector)) >= anc_purchase_amount);
+    assert_eq!(suite.stable_balance(&reward), Uint128::zero());
+    assert_eq!(
+        Uint256::from(suite.stable_balance(&overseer)),
+        Uint256::from(interest_buffer) - anc_purchase_amount + Uint256::from(10_000_000u64)
+    );
+
+    let epoch_state = suite.epoch_state().unwrap();
+    assert_eq!(epoch_state.last_executed_height, suite.app.block_height());
+    assert_eq!(
+        epoch_state.prev_interest_buffer,
+        Uint256::from(interest_buffer) - anc_purchase_amount
+    );
+}
+
+#[test]
+fn failed_liquidation_is_reverted() {
+    let mut suite = setup_loan();
+    let borrower = HumanAddr::from("borrower");
+    let liquidation = suite.liquidation.clone();
+    let custody = suite.custody.clone();
+
+    suite.advance_blocks(1);
+    suite.feed_price(Decimal256::percent(60)).unwrap();
+    let loan_before = suite.loan_amount(&borrower).unwrap();
+
+    // the overseer and the custody release the collateral before the
+    // liquidation contract finds no bid of the liquidator to fill
+    match suite.liquidate(&HumanAddr::from("liquidator"), &borrower) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "No bids with the specified information exist")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // and all of it is reverted
+    assert_eq!(
+        suite.collateral_amount(&borrower).unwrap(),
+        Uint256::from(COLLATERAL_AMOUNT)
+    );
+    assert_eq!(
+        suite.bluna_balance(&custody).unwrap(),
+        Uint128(COLLATERAL_AMOUNT)
+    );
+    assert_eq!(suite.bluna_balance(&liquidation).unwrap(), Uint128::zero());
+    assert_eq!(suite.loan_amount(&borrower).unwrap(), loan_before);
+}
+
+#[test]
+fn liquidation_gas_does_not_grow_with_resting_bids() {
+    let case = |bids| GasCase {
+        bids,
+        premium_slots: 10,
+        scale: 1_000_000_000,
+    };
+    let empty = measure(&case(0)).unwrap();
+    let crowded = measure(&case(100)).unwrap();
+
+    // the bids are keyed by bidder, so none of the
+    // hot paths scans the resting bids
+    for (gas, crowded_gas) in &[
+        (empty.submit, crowded.submit),
+        (empty.retract, crowded.retract),
+        (empty.execute, crowded.execute),
+        (empty.claim, crowded.claim),
+    ] {
+        assert!(*gas > 0);
+        assert!(*crowded_gas < gas + 1000);
+    }
+
+    let report = render_report("HEAD", &[empty, crowded]);
+    assert!(report.starts_with("# Liquidation storage gas at HEAD\n"));
+    // the header and a row per measurement
+    assert_eq!(report.lines().filter(|l| l.starts_with("| ")).count(), 3);
+}
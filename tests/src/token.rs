@@ -0,0 +1,225 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse,
+    HandleResult, HumanAddr, InitResponse, InitResult, Querier, StdError, StdResult, Storage,
+    Uint128, WasmMsg,
+};
+use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cw20::{BalanceResponse, Cw20HandleMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
+use terraswap::token::InitMsg;
+
+// The keys match cw20-base, so the raw
+// queries of the contracts can be answered
+const KEY_TOKEN_INFO: &[u8] = b"token_info";
+const KEY_MINTER: &[u8] = b"minter";
+const PREFIX_BALANCE: &[u8] = b"balance";
+
+/// Message sent to the contract receiving a cw20 Send
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverHandleMsg {
+    Receive(Cw20ReceiveMsg),
+}
+
+/// Minimal cw20 token standing in for aterra and the bAsset tokens;
+/// only transfers, sends, mints and burns are supported
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: InitMsg,
+) -> InitResult {
+    let mut total_supply: u128 = 0;
+    for coin in msg.initial_balances.iter() {
+        let address_raw = deps.api.canonical_address(&coin.address)?;
+        store_balance(&mut deps.storage, &address_raw, coin.amount.u128())?;
+        total_supply += coin.amount.u128();
+    }
+
+    Singleton::new(&mut deps.storage, KEY_TOKEN_INFO).save(&TokenInfoResponse {
+        name: msg.name,
+        symbol: msg.symbol,
+        decimals: msg.decimals,
+        total_supply: Uint128(total_supply),
+    })?;
+
+    let minter: Option<CanonicalAddr> = match msg.mint {
+        Some(mint) => Some(deps.api.canonical_address(&mint.minter)?),
+        None => None,
+    };
+    Singleton::new(&mut deps.storage, KEY_MINTER).save(&minter)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(init_hook) = msg.init_hook {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: init_hook.contract_addr,
+            msg: init_hook.msg,
+            send: vec![],
+        }));
+    }
+
+    Ok(InitResponse {
+        messages,
+        log: vec![],
+    })
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: Cw20HandleMsg,
+) -> HandleResult {
+    match msg {
+        Cw20HandleMsg::Transfer { recipient, amount } => {
+            transfer(deps, &env.message.sender, &recipient, amount)?;
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "transfer"),
+                    log("from", env.message.sender),
+                    log("to", recipient),
+                    log("amount", amount),
+                ],
+                data: None,
+            })
+        }
+        Cw20HandleMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => {
+            transfer(deps, &env.message.sender, &contract, amount)?;
+            Ok(HandleResponse {
+                messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract.clone(),
+                    msg: to_binary(&ReceiverHandleMsg::Receive(Cw20ReceiveMsg {
+                        sender: env.message.sender.clone(),
+                        amount,
+                        msg,
+                    }))?,
+                    send: vec![],
+                })],
+                log: vec![
+                    log("action", "send"),
+                    log("from", env.message.sender),
+                    log("to", contract),
+                    log("amount", amount),
+                ],
+                data: None,
+            })
+        }
+        Cw20HandleMsg::Mint { recipient, amount } => {
+            let minter: Option<CanonicalAddr> =
+                ReadonlySingleton::new(&deps.storage, KEY_MINTER).load()?;
+            if minter != Some(deps.api.canonical_address(&env.message.sender)?) {
+                return Err(StdError::unauthorized());
+            }
+
+            let recipient_raw = deps.api.canonical_address(&recipient)?;
+            let balance = read_balance(&deps.storage, &recipient_raw);
+            store_balance(&mut deps.storage, &recipient_raw, balance + amount.u128())?;
+            update_supply(&mut deps.storage, |supply| Ok(supply + amount.u128()))?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "mint"),
+                    log("to", recipient),
+                    log("amount", amount),
+                ],
+                data: None,
+            })
+        }
+        Cw20HandleMsg::Burn { amount } => {
+            let sender_raw = deps.api.canonical_address(&env.message.sender)?;
+            let balance = read_balance(&deps.storage, &sender_raw);
+            store_balance(&mut deps.storage, &sender_raw, sub_amount(balance, amount)?)?;
+            update_supply(&mut deps.storage, |supply| sub_amount(supply, amount))?;
+
+            Ok(HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "burn"),
+                    log("from", env.message.sender),
+                    log("amount", amount),
+                ],
+                data: None,
+            })
+        }
+        _ => Err(StdError::generic_err("Unsupported token message")),
+    }
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: Cw20QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        Cw20QueryMsg::Balance { address } => {
+            let address_raw = deps.api.canonical_address(&address)?;
+            to_binary(&BalanceResponse {
+                balance: Uint128(read_balance(&deps.storage, &address_raw)),
+            })
+        }
+        Cw20QueryMsg::TokenInfo {} => {
+            let token_info: TokenInfoResponse =
+                ReadonlySingleton::new(&deps.storage, KEY_TOKEN_INFO).load()?;
+            to_binary(&token_info)
+        }
+        _ => Err(StdError::generic_err("Unsupported token query")),
+    }
+}
+
+fn transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    from: &HumanAddr,
+    to: &HumanAddr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let from_raw = deps.api.canonical_address(from)?;
+    let to_raw = deps.api.canonical_address(to)?;
+
+    let from_balance = read_balance(&deps.storage, &from_raw);
+    store_balance(
+        &mut deps.storage,
+        &from_raw,
+        sub_amount(from_balance, amount)?,
+    )?;
+    let to_balance = read_balance(&deps.storage, &to_raw);
+    store_balance(&mut deps.storage, &to_raw, to_balance + amount.u128())
+}
+
+fn sub_amount(balance: u128, amount: Uint128) -> StdResult<u128> {
+    balance
+        .checked_sub(amount.u128())
+        .ok_or_else(|| StdError::generic_err(format!("Insufficient token balance: {}", balance)))
+}
+
+fn update_supply<S: Storage, F>(storage: &mut S, action: F) -> StdResult<()>
+where
+    F: FnOnce(u128) -> StdResult<u128>,
+{
+    let mut token_info: TokenInfoResponse =
+        ReadonlySingleton::new(storage, KEY_TOKEN_INFO).load()?;
+    token_info.total_supply = Uint128(action(token_info.total_supply.u128())?);
+    Singleton::new(storage, KEY_TOKEN_INFO).save(&token_info)
+}
+
+// Balances are stored as Uint128, as cw20-base does
+fn store_balance<S: Storage>(
+    storage: &mut S,
+    address_raw: &CanonicalAddr,
+    balance: u128,
+) -> StdResult<()> {
+    let mut balance_bucket: Bucket<S, Uint128> = Bucket::new(PREFIX_BALANCE, storage);
+    balance_bucket.save(address_raw.as_slice(), &Uint128(balance))
+}
+
+fn read_balance<S: Storage>(storage: &S, address_raw: &CanonicalAddr) -> u128 {
+    let balance_bucket: ReadonlyBucket<S, Uint128> = ReadonlyBucket::new(PREFIX_BALANCE, storage);
+    balance_bucket
+        .load(address_raw.as_slice())
+        .map(|balance| balance.u128())
+        .unwrap_or(0)
+}